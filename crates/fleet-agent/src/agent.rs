@@ -408,6 +408,21 @@ async fn handle_deploy_kdl(
                 .await?;
                 return Ok(());
             }
+            fleetflow_core::Backend::Kubernetes | fleetflow_core::Backend::Nomad => {
+                send_command_result(
+                    channel,
+                    request_id,
+                    json!({
+                        "status": "failed",
+                        "error": format!(
+                            "backend \"{}\" は agent では扱えません（クラスタへは `fleet up` で適用）",
+                            stage.backend.as_str()
+                        ),
+                    }),
+                )
+                .await?;
+                return Ok(());
+            }
             // Docker は以下の従来 bollard 経路へ
             fleetflow_core::Backend::Docker => {}
         }
@@ -5,6 +5,7 @@
 use crate::error::{BuildError, BuildResult};
 use base64::Engine;
 use bollard::auth::DockerCredentials;
+use fleetflow_core::RegistryCredentials;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::io::Write;
@@ -42,6 +43,8 @@ struct CredentialResponse {
 #[derive(Debug)]
 pub struct RegistryAuth {
     config_path: PathBuf,
+    /// ステージの `registry` ブロックで宣言した認証情報（レジストリ -> 認証情報）
+    overrides: HashMap<String, DockerCredentials>,
 }
 
 impl Default for RegistryAuth {
@@ -64,12 +67,29 @@ impl RegistryAuth {
             })
             .join("config.json");
 
-        Self { config_path }
+        Self::with_config_path(config_path)
     }
 
     /// 指定したパスの config.json を使用
     pub fn with_config_path(config_path: PathBuf) -> Self {
-        Self { config_path }
+        Self {
+            config_path,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// ステージの `registry` ブロックの認証情報を config.json より優先して使う
+    pub fn with_credentials(mut self, credentials: RegistryCredentials) -> Self {
+        self.overrides.insert(
+            credentials.registry.clone(),
+            DockerCredentials {
+                username: Some(credentials.username),
+                password: Some(credentials.password),
+                serveraddress: Some(credentials.registry),
+                ..Default::default()
+            },
+        );
+        self
     }
 
     /// イメージ名からレジストリの認証情報を取得
@@ -84,6 +104,12 @@ impl RegistryAuth {
     pub fn get_credentials(&self, image: &str) -> BuildResult<Option<DockerCredentials>> {
         let registry = self.extract_registry(image);
 
+        // 0. ステージで宣言した認証情報
+        if let Some(creds) = self.overrides.get(&registry) {
+            tracing::debug!("Using stage registry credentials for {}", registry);
+            return Ok(Some(creds.clone()));
+        }
+
         // config.json が存在する場合は auths / credsStore を試行
         if self.config_path.exists() {
             let config = self.load_docker_config()?;
@@ -387,6 +413,29 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_stage_credentials_take_precedence() {
+        let auth = RegistryAuth::with_config_path(PathBuf::from("/nonexistent/config.json"))
+            .with_credentials(RegistryCredentials {
+                registry: "registry.example.com".to_string(),
+                username: "deploy".to_string(),
+                password: "token".to_string(),
+            });
+        let creds = auth
+            .get_credentials("registry.example.com/team/api:v1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(creds.username.as_deref(), Some("deploy"));
+        assert_eq!(creds.password.as_deref(), Some("token"));
+
+        // 別のレジストリには使わない
+        assert!(
+            auth.get_credentials("quay.io/team/api:v1")
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_extract_registry_gcr() {
         let auth = RegistryAuth::new();
@@ -1,24 +1,72 @@
 use crate::error::{BuildError, BuildResult};
+use crate::events::{BuildEvent, BuildkitLogParser};
+use crate::progress::{self, ProgressReporter, TaskProgress};
 use bollard::Docker;
 use colored::Colorize;
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::Arc;
 
 /// ImageBuilder - docker buildxを使用してBuildKitでイメージをビルド
 pub struct ImageBuilder {
     // Docker接続（後方互換性のため保持、実際はCLI経由でビルド）
     #[allow(dead_code)]
     docker: Docker,
+    progress: Arc<dyn ProgressReporter>,
+    /// ビルドに使う Docker デーモン（例: ssh://root@host）。None ならローカル
+    docker_host: Option<String>,
+    /// 共有インラインキャッシュのイメージ参照。None ならキャッシュ指定なし
+    inline_cache: Option<String>,
 }
 
 impl ImageBuilder {
     pub fn new(docker: Docker) -> Self {
-        Self { docker }
+        Self {
+            docker,
+            progress: progress::reporter(),
+            docker_host: None,
+            inline_cache: None,
+        }
+    }
+
+    /// リモートの Docker デーモンでビルドする（`DOCKER_HOST` として渡す）
+    ///
+    /// `ssh://user@host` を指定すると、ビルドコンテキストは SSH 経由で転送され、
+    /// イメージはリモート側にロードされる。
+    pub fn with_docker_host(mut self, docker_host: impl Into<String>) -> Self {
+        self.docker_host = Some(docker_host.into());
+        self
+    }
+
+    /// 共有インラインキャッシュを使う（`--cache-from` / `--cache-to type=inline`）
+    ///
+    /// ビルドしたイメージに `cache_ref` のタグも付けるため、プロジェクト内の
+    /// 複数サービスでベースレイヤーのキャッシュを共有できる。
+    pub fn with_inline_cache(mut self, cache_ref: impl Into<String>) -> Self {
+        self.inline_cache = Some(cache_ref.into());
+        self
+    }
+
+    fn docker_command(&self) -> Command {
+        let mut cmd = Command::new("docker");
+        if let Some(host) = &self.docker_host {
+            cmd.env("DOCKER_HOST", host);
+        }
+        cmd
+    }
+
+    /// 進捗表示の出力先を指定（複数ビルドで MultiProgress を共有する場合など）
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
     }
 
     /// イメージをビルド（docker buildx使用でBuildKit有効）
+    ///
+    /// 進捗は [`ProgressReporter`] で表示する。独自に表示する場合は
+    /// [`ImageBuilder::build_image_from_path_with_events`] を使う。
     #[allow(clippy::too_many_arguments)]
     pub async fn build_image_from_path(
         &self,
@@ -30,11 +78,55 @@ impl ImageBuilder {
         no_cache: bool,
         platform: Option<&str>,
     ) -> BuildResult<()> {
-        tracing::info!("Building image: {}", tag);
+        let task = self.progress.step_task("build", tag);
+        task.set_message("ビルド中...");
 
-        let mut cmd = Command::new("docker");
+        let result = self
+            .build_image_from_path_with_events(
+                context_path,
+                dockerfile_path,
+                tag,
+                build_args,
+                target,
+                no_cache,
+                platform,
+                |event| render_event(task.as_ref(), &event),
+            )
+            .await;
+
+        match &result {
+            Ok(()) => task.finish("ビルド完了"),
+            Err(_) => task.fail("ビルド失敗"),
+        }
+        result
+    }
+
+    /// イメージをビルドし、進捗を [`BuildEvent`] として通知する
+    ///
+    /// `--progress=plain` の出力を解析するため、ステップ開始・完了、
+    /// キャッシュ再利用、ダイジェスト等を表示側で扱える。内部では何も出力しない。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build_image_from_path_with_events(
+        &self,
+        context_path: &Path,
+        dockerfile_path: &Path,
+        tag: &str,
+        build_args: HashMap<String, String>,
+        target: Option<&str>,
+        no_cache: bool,
+        platform: Option<&str>,
+        mut on_event: impl FnMut(BuildEvent),
+    ) -> BuildResult<()> {
+        tracing::info!(
+            "Building image: {} (docker host: {:?})",
+            tag,
+            self.docker_host
+        );
+
+        let mut cmd = self.docker_command();
         cmd.arg("buildx")
             .arg("build")
+            .arg("--progress=plain")
             .arg("-t")
             .arg(tag)
             .arg("-f")
@@ -53,6 +145,13 @@ impl ImageBuilder {
         // キャッシュ無効化
         if no_cache {
             cmd.arg("--no-cache");
+        } else if let Some(cache_ref) = &self.inline_cache {
+            cmd.arg("-t")
+                .arg(cache_ref)
+                .arg("--cache-from")
+                .arg(cache_ref)
+                .arg("--cache-to")
+                .arg("type=inline");
         }
 
         // プラットフォーム指定
@@ -66,7 +165,7 @@ impl ImageBuilder {
         // コンテキストパス
         cmd.arg(context_path);
 
-        // 出力をリアルタイムで表示
+        // 出力をリアルタイムで解析
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -83,19 +182,21 @@ impl ImageBuilder {
             BuildError::BuildFailed(format!("Failed to spawn docker buildx: {}", e))
         })?;
 
+        let mut parser = BuildkitLogParser::new();
+
         // stdoutを読み取り
         if let Some(stdout) = child.stdout.take() {
             let reader = BufReader::new(stdout);
             for line in reader.lines().map_while(Result::ok) {
-                println!("{}", line);
+                on_event(parser.parse_line(&line));
             }
         }
 
-        // stderrを読み取り
+        // stderrを読み取り（BuildKit の進捗はこちらに出力される）
         if let Some(stderr) = child.stderr.take() {
             let reader = BufReader::new(stderr);
             for line in reader.lines().map_while(Result::ok) {
-                eprintln!("{}", line);
+                on_event(parser.parse_line(&line));
             }
         }
 
@@ -123,18 +224,7 @@ impl ImageBuilder {
         target: Option<&str>,
         no_cache: bool,
     ) -> BuildResult<()> {
-        // tarファイルを一時ディレクトリに展開してビルド
-        let temp_dir = tempfile::tempdir()
-            .map_err(|e| BuildError::BuildFailed(format!("Failed to create temp dir: {}", e)))?;
-
-        // tarを展開
-        use std::io::Cursor;
-        let cursor = Cursor::new(context_data);
-        let mut archive = tar::Archive::new(cursor);
-        archive
-            .unpack(temp_dir.path())
-            .map_err(|e| BuildError::BuildFailed(format!("Failed to unpack context: {}", e)))?;
-
+        let temp_dir = unpack_context(context_data)?;
         let dockerfile_path = temp_dir.path().join("Dockerfile");
 
         self.build_image_from_path(
@@ -149,9 +239,36 @@ impl ImageBuilder {
         .await
     }
 
+    /// イメージをビルドし、進捗を [`BuildEvent`] として通知する（tarコンテキスト用）
+    pub async fn build_image_with_events(
+        &self,
+        context_data: Vec<u8>,
+        tag: &str,
+        build_args: HashMap<String, String>,
+        target: Option<&str>,
+        no_cache: bool,
+        on_event: impl FnMut(BuildEvent),
+    ) -> BuildResult<()> {
+        let temp_dir = unpack_context(context_data)?;
+        let dockerfile_path = temp_dir.path().join("Dockerfile");
+
+        self.build_image_from_path_with_events(
+            temp_dir.path(),
+            &dockerfile_path,
+            tag,
+            build_args,
+            target,
+            no_cache,
+            None,
+            on_event,
+        )
+        .await
+    }
+
     /// イメージの存在確認
     pub async fn image_exists(&self, image_tag: &str) -> BuildResult<bool> {
-        let output = Command::new("docker")
+        let output = self
+            .docker_command()
             .args(["image", "inspect", image_tag])
             .stdout(Stdio::null())
             .stderr(Stdio::null())
@@ -162,6 +279,33 @@ impl ImageBuilder {
     }
 }
 
+/// tarコンテキストを一時ディレクトリに展開
+fn unpack_context(context_data: Vec<u8>) -> BuildResult<tempfile::TempDir> {
+    let temp_dir = tempfile::tempdir()
+        .map_err(|e| BuildError::BuildFailed(format!("Failed to create temp dir: {}", e)))?;
+
+    let cursor = std::io::Cursor::new(context_data);
+    let mut archive = tar::Archive::new(cursor);
+    archive
+        .unpack(temp_dir.path())
+        .map_err(|e| BuildError::BuildFailed(format!("Failed to unpack context: {}", e)))?;
+
+    Ok(temp_dir)
+}
+
+/// ビルドイベントを進捗表示に反映
+fn render_event(task: &dyn TaskProgress, event: &BuildEvent) {
+    match event {
+        BuildEvent::StepStarted { id, name } => task.log_line(&format!("#{} {}", id, name)),
+        BuildEvent::LayerCached { id } => task.log_line(&format!("#{} CACHED", id)),
+        BuildEvent::StepFailed { id, message } => {
+            task.log_line(&format!("#{} ERROR: {}", id, message))
+        }
+        BuildEvent::Log { line, .. } => task.log_line(line),
+        BuildEvent::StepFinished { .. } | BuildEvent::Digest { .. } => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,6 +38,23 @@ pub enum BuildError {
 }
 
 impl BuildError {
+    /// 診断コード（`fleet::build::*`）
+    pub fn code(&self) -> &'static str {
+        match self {
+            BuildError::DockerfileNotFound(_) => "fleet::build::dockerfile_not_found",
+            BuildError::ContextNotFound(_) => "fleet::build::context_not_found",
+            BuildError::DockerConnection(_) => "fleet::build::docker_connection",
+            BuildError::BuildFailed(_) => "fleet::build::failed",
+            BuildError::InvalidConfig(_) => "fleet::build::invalid_config",
+            BuildError::VariableNotFound(_) => "fleet::build::variable_not_found",
+            BuildError::Io(_) => "fleet::build::io",
+            BuildError::AuthFailed { .. } => "fleet::build::auth_failed",
+            BuildError::NoRegistry { .. } => "fleet::build::no_registry",
+            BuildError::PushFailed { .. } => "fleet::build::push_failed",
+            BuildError::InvalidTag { .. } => "fleet::build::invalid_tag",
+        }
+    }
+
     /// ユーザー向けの分かりやすいエラーメッセージ
     pub fn user_message(&self) -> String {
         match self {
@@ -0,0 +1,176 @@
+//! ビルド進捗イベント
+//!
+//! `docker buildx build --progress=plain` の出力を型付きイベントに変換する。
+//! 表示方法は呼び出し側（CLI、MCP サーバー等）が決める。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// ビルド進捗イベント
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BuildEvent {
+    /// ビルドステップ開始（例: `[2/4] RUN apt-get update`）
+    StepStarted { id: u32, name: String },
+    /// ビルドステップ完了
+    StepFinished { id: u32, duration_secs: Option<f64> },
+    /// キャッシュ済みレイヤーを再利用
+    LayerCached { id: u32 },
+    /// ビルドステップ失敗
+    StepFailed { id: u32, message: String },
+    /// ビルドされたイメージのダイジェスト
+    Digest { digest: String },
+    /// その他の出力行（RUN の標準出力など）
+    Log { id: Option<u32>, line: String },
+}
+
+/// BuildKit の plain 進捗出力を 1 行ずつ解析する
+///
+/// ```text
+/// #5 [2/3] RUN echo hello
+/// #5 0.231 hello
+/// #5 DONE 0.3s
+/// #6 [3/3] COPY . .
+/// #6 CACHED
+/// #8 writing image sha256:4d1f... done
+/// ```
+#[derive(Debug, Default)]
+pub struct BuildkitLogParser {
+    started: HashSet<u32>,
+}
+
+impl BuildkitLogParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1 行を解析してイベントに変換
+    pub fn parse_line(&mut self, line: &str) -> BuildEvent {
+        let Some((id, rest)) = split_step_id(line) else {
+            return BuildEvent::Log {
+                id: None,
+                line: line.to_string(),
+            };
+        };
+
+        if self.started.insert(id) {
+            return BuildEvent::StepStarted {
+                id,
+                name: rest.to_string(),
+            };
+        }
+
+        if rest == "CACHED" {
+            return BuildEvent::LayerCached { id };
+        }
+        if let Some(duration) = rest.strip_prefix("DONE") {
+            return BuildEvent::StepFinished {
+                id,
+                duration_secs: duration.trim().trim_end_matches('s').parse().ok(),
+            };
+        }
+        if let Some(message) = rest.strip_prefix("ERROR") {
+            return BuildEvent::StepFailed {
+                id,
+                message: message.trim_start_matches(':').trim().to_string(),
+            };
+        }
+        if let Some(digest) = rest
+            .strip_prefix("writing image ")
+            .and_then(|r| r.split_whitespace().next())
+            .filter(|d| d.starts_with("sha256:"))
+        {
+            return BuildEvent::Digest {
+                digest: digest.to_string(),
+            };
+        }
+
+        BuildEvent::Log {
+            id: Some(id),
+            line: rest.to_string(),
+        }
+    }
+}
+
+/// `#12 rest` 形式の行からステップ ID と残りを取り出す
+fn split_step_id(line: &str) -> Option<(u32, &str)> {
+    let rest = line.strip_prefix('#')?;
+    let (id, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((id.parse().ok()?, rest.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_buildkit_plain_output() {
+        let mut parser = BuildkitLogParser::new();
+        let events: Vec<BuildEvent> = [
+            "#0 building with \"default\" instance using docker driver",
+            "#5 [2/3] RUN echo hello",
+            "#5 0.231 hello",
+            "#5 DONE 0.3s",
+            "#6 [3/3] COPY . .",
+            "#6 CACHED",
+            "#8 exporting to image",
+            "#8 writing image sha256:4d1f2a done",
+            "#9 [4/4] RUN false",
+            "#9 ERROR: process \"/bin/sh -c false\" did not complete successfully",
+            "plain text",
+        ]
+        .iter()
+        .map(|line| parser.parse_line(line))
+        .collect();
+
+        assert_eq!(
+            events[1],
+            BuildEvent::StepStarted {
+                id: 5,
+                name: "[2/3] RUN echo hello".to_string()
+            }
+        );
+        assert_eq!(
+            events[2],
+            BuildEvent::Log {
+                id: Some(5),
+                line: "0.231 hello".to_string()
+            }
+        );
+        assert_eq!(
+            events[3],
+            BuildEvent::StepFinished {
+                id: 5,
+                duration_secs: Some(0.3)
+            }
+        );
+        assert_eq!(events[5], BuildEvent::LayerCached { id: 6 });
+        assert_eq!(
+            events[7],
+            BuildEvent::Digest {
+                digest: "sha256:4d1f2a".to_string()
+            }
+        );
+        assert_eq!(
+            events[9],
+            BuildEvent::StepFailed {
+                id: 9,
+                message: "process \"/bin/sh -c false\" did not complete successfully".to_string()
+            }
+        );
+        assert_eq!(
+            events[10],
+            BuildEvent::Log {
+                id: None,
+                line: "plain text".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_build_event_serialization() {
+        let event = BuildEvent::LayerCached { id: 3 };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"type":"LayerCached","id":3}"#);
+    }
+}
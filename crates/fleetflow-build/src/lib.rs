@@ -8,6 +8,7 @@ pub mod auth;
 pub mod builder;
 pub mod context;
 pub mod error;
+pub mod events;
 pub mod progress;
 pub mod pusher;
 pub mod resolver;
@@ -16,6 +17,10 @@ pub use auth::RegistryAuth;
 pub use builder::ImageBuilder;
 pub use context::ContextBuilder;
 pub use error::{BuildError, BuildResult};
-pub use progress::BuildProgress;
-pub use pusher::{ImagePusher, resolve_tag, split_image_tag};
-pub use resolver::BuildResolver;
+pub use events::{BuildEvent, BuildkitLogParser};
+pub use progress::{
+    BuildProgress, JsonProgress, PlainProgress, ProgressEvent, ProgressReporter, ProgressStatus,
+    TaskProgress, TerminalProgress, reporter, set_reporter,
+};
+pub use pusher::{ImagePusher, RetryPolicy, is_transient, resolve_tag, split_image_tag};
+pub use resolver::{BuildResolver, render_image_template};
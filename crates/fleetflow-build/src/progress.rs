@@ -1,5 +1,372 @@
-use indicatif::{ProgressBar, ProgressStyle};
+//! 進捗表示レイヤー
+//!
+//! pull / build / deploy の進捗を [`ProgressReporter`] 経由で報告する。
+//! TTY では indicatif の MultiProgress で複数タスクを同時に描画し、
+//! CI やパイプ出力では `\r` を使わない行単位のプレーンテキストにフォールバックする。
+//! GUI などから CLI を包む場合は [`JsonProgress`] で改行区切りの JSON イベントを出力する。
 
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// 失敗時に表示するため TTY 表示で保持する直近の出力行数
+const RECENT_LINES: usize = 30;
+
+/// 進捗表示の出力先
+pub trait ProgressReporter: Send + Sync {
+    /// 新しいタスク（イメージ pull、ビルド等）の進捗表示を開始
+    fn task(&self, label: &str) -> Box<dyn TaskProgress>;
+
+    /// 処理の段階を指定してタスクを開始（`step` は JSON 出力で使う。表示は [`Self::task`] と同じ）
+    fn step_task(&self, _step: &str, label: &str) -> Box<dyn TaskProgress> {
+        self.task(label)
+    }
+
+    /// 描画中の進捗表示を崩さずに 1 行出力
+    fn println(&self, line: &str);
+
+    /// 構造化された進捗イベント（人向けの表示は呼び出し側が別に出力するため既定では何もしない）
+    fn event(&self, _event: &ProgressEvent) {}
+}
+
+/// 進捗イベントの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressStatus {
+    Started,
+    Running,
+    Log,
+    Done,
+    Skipped,
+    Failed,
+}
+
+/// 機械可読な進捗イベント（`--progress=json` では 1 行 1 イベントで出力する）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressEvent {
+    /// 処理の段階（`pull` / `build` / `push` / `up` / `setup` / `deploy` など）
+    pub step: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// 対象（イメージ名など）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: ProgressStatus,
+    /// 0〜100（分かる場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>,
+    pub message: String,
+}
+
+impl ProgressEvent {
+    pub fn new(step: &str, status: ProgressStatus, message: impl Into<String>) -> Self {
+        Self {
+            step: step.to_string(),
+            service: None,
+            target: None,
+            status,
+            percent: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn service(mut self, service: &str) -> Self {
+        self.service = Some(service.to_string());
+        self
+    }
+
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn percent(mut self, percent: f64) -> Self {
+        self.percent = Some(percent);
+        self
+    }
+}
+
+/// 個々のタスクの進捗
+pub trait TaskProgress: Send {
+    /// 現在の状態メッセージを更新
+    fn set_message(&self, message: &str);
+
+    /// バイト数などの進捗を更新
+    fn set_position(&self, current: u64, total: u64);
+
+    /// タスクの出力 1 行を報告（プレーン表示ではそのまま出力する）
+    fn log_line(&self, line: &str);
+
+    /// 成功として完了
+    fn finish(&self, message: &str);
+
+    /// 失敗として完了
+    fn fail(&self, message: &str);
+}
+
+/// [`set_reporter`] で固定されたレポーター
+static REPORTER: OnceLock<Arc<dyn ProgressReporter>> = OnceLock::new();
+
+/// プロセス全体で使うレポーターを固定する（`--progress` の指定。2 回目以降は無視される）
+pub fn set_reporter(reporter: Arc<dyn ProgressReporter>) {
+    let _ = REPORTER.set(reporter);
+}
+
+/// 実行環境に応じたレポーターを返す
+///
+/// [`set_reporter`] で固定されていればそれを使う。そうでなければ
+/// stderr が TTY で、かつ CI 環境（`CI` 環境変数）でなければ indicatif を使う。
+pub fn reporter() -> Arc<dyn ProgressReporter> {
+    if let Some(reporter) = REPORTER.get() {
+        return reporter.clone();
+    }
+    if is_interactive() {
+        Arc::new(TerminalProgress::new())
+    } else {
+        Arc::new(PlainProgress)
+    }
+}
+
+fn is_interactive() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("CI").is_none()
+}
+
+/// indicatif の MultiProgress による TTY 向け表示
+pub struct TerminalProgress {
+    multi: MultiProgress,
+}
+
+impl TerminalProgress {
+    pub fn new() -> Self {
+        Self {
+            multi: MultiProgress::with_draw_target(ProgressDrawTarget::stderr()),
+        }
+    }
+}
+
+impl Default for TerminalProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter for TerminalProgress {
+    fn task(&self, label: &str) -> Box<dyn TaskProgress> {
+        let bar = self.multi.add(ProgressBar::new_spinner());
+        bar.set_style(spinner_style());
+        bar.set_prefix(label.to_string());
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Box::new(TerminalTask {
+            bar,
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_LINES)),
+        })
+    }
+
+    fn println(&self, line: &str) {
+        let _ = self.multi.println(line);
+    }
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::with_template("  {spinner:.green} {prefix:.cyan} {wide_msg}")
+        .unwrap_or_else(|_| ProgressStyle::default_spinner())
+}
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::with_template(
+        "  {spinner:.green} {prefix:.cyan} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {wide_msg}",
+    )
+    .unwrap_or_else(|_| ProgressStyle::default_bar())
+    .progress_chars("=> ")
+}
+
+struct TerminalTask {
+    bar: ProgressBar,
+    recent: Mutex<VecDeque<String>>,
+}
+
+impl TaskProgress for TerminalTask {
+    fn set_message(&self, message: &str) {
+        self.bar.set_message(message.to_string());
+    }
+
+    fn set_position(&self, current: u64, total: u64) {
+        if self.bar.length() != Some(total) {
+            self.bar.set_style(bar_style());
+            self.bar.set_length(total);
+        }
+        self.bar.set_position(current);
+    }
+
+    fn log_line(&self, line: &str) {
+        // 最新行のみをスピナー横に表示し、失敗時のために直近の行を保持する
+        self.bar.set_message(line.trim().to_string());
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.to_string());
+    }
+
+    fn finish(&self, message: &str) {
+        self.bar.set_style(spinner_style());
+        self.bar.finish_with_message(format!("✓ {}", message));
+    }
+
+    fn fail(&self, message: &str) {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        for line in recent.iter() {
+            self.bar.println(line);
+        }
+        self.bar.set_style(spinner_style());
+        self.bar.abandon_with_message(format!("✗ {}", message));
+    }
+}
+
+/// 非 TTY 向けのプレーンテキスト表示
+///
+/// 同じメッセージの連続出力は間引き、バイト進捗は出力しない。
+pub struct PlainProgress;
+
+impl ProgressReporter for PlainProgress {
+    fn task(&self, label: &str) -> Box<dyn TaskProgress> {
+        Box::new(PlainTask {
+            label: label.to_string(),
+            last_message: Mutex::new(String::new()),
+        })
+    }
+
+    fn println(&self, line: &str) {
+        println!("{}", line);
+    }
+}
+
+struct PlainTask {
+    label: String,
+    last_message: Mutex<String>,
+}
+
+impl TaskProgress for PlainTask {
+    fn set_message(&self, message: &str) {
+        let mut last = self.last_message.lock().unwrap_or_else(|e| e.into_inner());
+        if *last != message {
+            println!("  {}: {}", self.label, message);
+            *last = message.to_string();
+        }
+    }
+
+    fn set_position(&self, _current: u64, _total: u64) {}
+
+    fn log_line(&self, line: &str) {
+        println!("{}", line);
+    }
+
+    fn finish(&self, message: &str) {
+        println!("  ✓ {}: {}", self.label, message);
+    }
+
+    fn fail(&self, message: &str) {
+        eprintln!("  ✗ {}: {}", self.label, message);
+    }
+}
+
+/// 改行区切り JSON（NDJSON）の進捗出力
+///
+/// タスクの進捗は `step`（段階）と `target`（タスクのラベル）を持つイベントとして出力する。
+/// 人向けの行（[`ProgressReporter::println`]）は stderr に出す。
+pub struct JsonProgress {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl JsonProgress {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Arc::new(Mutex::new(out)),
+        }
+    }
+}
+
+fn write_event(out: &Mutex<Box<dyn Write + Send>>, event: &ProgressEvent) {
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
+
+impl ProgressReporter for JsonProgress {
+    fn task(&self, label: &str) -> Box<dyn TaskProgress> {
+        self.step_task(label, label)
+    }
+
+    fn step_task(&self, step: &str, label: &str) -> Box<dyn TaskProgress> {
+        let task = JsonTask {
+            step: step.to_string(),
+            target: label.to_string(),
+            out: self.out.clone(),
+        };
+        task.emit(ProgressStatus::Started, "", None);
+        Box::new(task)
+    }
+
+    fn println(&self, line: &str) {
+        eprintln!("{}", line);
+    }
+
+    fn event(&self, event: &ProgressEvent) {
+        write_event(&self.out, event);
+    }
+}
+
+struct JsonTask {
+    step: String,
+    target: String,
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl JsonTask {
+    fn emit(&self, status: ProgressStatus, message: &str, percent: Option<f64>) {
+        let mut event = ProgressEvent::new(&self.step, status, message);
+        event.target = Some(self.target.clone());
+        event.percent = percent;
+        write_event(&self.out, &event);
+    }
+}
+
+impl TaskProgress for JsonTask {
+    fn set_message(&self, message: &str) {
+        self.emit(ProgressStatus::Running, message, None);
+    }
+
+    fn set_position(&self, current: u64, total: u64) {
+        if total > 0 {
+            let percent = (current as f64 / total as f64 * 100.0).min(100.0);
+            self.emit(
+                ProgressStatus::Running,
+                "",
+                Some((percent * 10.0).round() / 10.0),
+            );
+        }
+    }
+
+    fn log_line(&self, line: &str) {
+        self.emit(ProgressStatus::Log, line, None);
+    }
+
+    fn finish(&self, message: &str) {
+        self.emit(ProgressStatus::Done, message, Some(100.0));
+    }
+
+    fn fail(&self, message: &str) {
+        self.emit(ProgressStatus::Failed, message, None);
+    }
+}
+
+/// 単一ビルドのスピナー表示（後方互換）
 pub struct BuildProgress {
     progress_bar: ProgressBar,
 }
@@ -34,3 +401,71 @@ impl BuildProgress {
             .finish_with_message(format!("Build failed: {}", error));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_disables_interactive_progress() {
+        temp_env::with_var("CI", Some("true"), || {
+            assert!(!is_interactive());
+        });
+    }
+
+    /// テスト用に書き込み内容を共有するバッファ
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_progress_emits_ndjson() {
+        let buf = SharedBuf::default();
+        let progress = JsonProgress::new(Box::new(buf.clone()));
+        let task = progress.step_task("pull", "nginx:1.27");
+        task.set_position(50, 200);
+        task.finish("nginx:1.27");
+        progress.event(&ProgressEvent::new("up", ProgressStatus::Started, "起動中").service("web"));
+
+        let output = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["status"], "started");
+        assert_eq!(lines[0]["step"], "pull");
+        assert_eq!(lines[0]["target"], "nginx:1.27");
+        assert_eq!(lines[1]["percent"], 25.0);
+        assert_eq!(lines[2]["status"], "done");
+        assert_eq!(lines[3]["step"], "up");
+        assert_eq!(lines[3]["service"], "web");
+        assert!(lines[0].get("service").is_none());
+    }
+
+    #[test]
+    fn test_terminal_task_keeps_recent_lines() {
+        let task = TerminalTask {
+            bar: ProgressBar::hidden(),
+            recent: Mutex::new(VecDeque::new()),
+        };
+        for i in 0..RECENT_LINES + 5 {
+            task.log_line(&format!("line {}", i));
+        }
+
+        let recent = task.recent.lock().unwrap();
+        assert_eq!(recent.len(), RECENT_LINES);
+        assert_eq!(recent.front().map(String::as_str), Some("line 5"));
+        assert_eq!(task.bar.message(), format!("line {}", RECENT_LINES + 4));
+    }
+}
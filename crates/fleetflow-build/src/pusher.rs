@@ -1,19 +1,100 @@
 //! イメージプッシュ処理
 //!
 //! ビルドしたイメージをコンテナレジストリにプッシュします。
+//! 一時的なエラー（タイムアウト、接続断、5xx、レート制限）は指数バックオフで再試行する。
+//! 再試行時はレジストリに既にあるレイヤー（`Layer already exists`）は送り直さないため、
+//! 途中まで送れたレイヤーから再開される。
 
 use crate::auth::RegistryAuth;
 use crate::error::{BuildError, BuildResult};
+use crate::progress::{self, ProgressReporter, TaskProgress};
 use bollard::Docker;
 use bollard::models::PushImageInfo;
 use colored::Colorize;
 use futures_util::StreamExt;
-use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// プッシュの再試行ポリシー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最大試行回数（1 なら再試行しない）
+    pub max_attempts: u32,
+    /// 初回の待ち時間（以降は倍々）
+    pub initial_delay: Duration,
+    /// 待ち時間の上限
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 再試行しない
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// `attempt` 回目（1 始まり）の失敗後に待つ時間
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// 再試行で回復が見込めるエラーか（認証・タグ不正などは再試行しない）
+pub fn is_transient(error: &BuildError) -> bool {
+    let message = match error {
+        BuildError::PushFailed { message } => message.to_lowercase(),
+        BuildError::DockerConnection(e) => e.to_string().to_lowercase(),
+        BuildError::Io(_) => return true,
+        _ => return false,
+    };
+    const PERMANENT: [&str; 5] = [
+        "denied",
+        "unauthorized",
+        "forbidden",
+        "not found",
+        "invalid",
+    ];
+    // HTTP ステータスはダイジェスト中の数字と区別するため前に空白を付ける
+    const TRANSIENT: [&str; 14] = [
+        "timeout",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "broken pipe",
+        "eof",
+        "tls handshake",
+        "too many requests",
+        " 429",
+        " 500",
+        " 502",
+        " 503",
+        " 504",
+        "blob upload unknown",
+    ];
+    !PERMANENT.iter().any(|p| message.contains(p)) && TRANSIENT.iter().any(|t| message.contains(t))
+}
 
 /// イメージプッシュを実行するハンドラ
 pub struct ImagePusher {
     docker: Docker,
     auth: RegistryAuth,
+    retry: RetryPolicy,
+    progress: Arc<dyn ProgressReporter>,
 }
 
 impl ImagePusher {
@@ -22,12 +103,31 @@ impl ImagePusher {
         Self {
             docker,
             auth: RegistryAuth::new(),
+            retry: RetryPolicy::default(),
+            progress: progress::reporter(),
         }
     }
 
     /// 認証情報マネージャーを指定して作成
     pub fn with_auth(docker: Docker, auth: RegistryAuth) -> Self {
-        Self { docker, auth }
+        Self {
+            docker,
+            auth,
+            retry: RetryPolicy::default(),
+            progress: progress::reporter(),
+        }
+    }
+
+    /// 再試行ポリシーを指定
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 進捗表示の出力先を指定（複数イメージの並列プッシュで MultiProgress を共有する場合など）
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
     }
 
     /// イメージをレジストリにプッシュ
@@ -39,11 +139,45 @@ impl ImagePusher {
     /// # Returns
     /// プッシュ成功時は完全なイメージ名を返す
     pub async fn push(&self, image: &str, tag: &str) -> BuildResult<String> {
-        let full_image = format!("{}:{}", image, tag);
-
         // タグのバリデーション
         self.validate_tag(tag)?;
 
+        let full_image = format!("{}:{}", image, tag);
+        let task = self.progress.step_task("push", &full_image);
+        let mut attempt = 1;
+        loop {
+            match self.push_once(image, tag, task.as_ref()).await {
+                Ok(()) => {
+                    task.finish("プッシュ完了");
+                    return Ok(full_image);
+                }
+                Err(e) if attempt < self.retry.max_attempts && is_transient(&e) => {
+                    let delay = self.retry.delay(attempt);
+                    self.progress.println(&format!(
+                        "  {} {}: {}（{}/{} 回目、{}秒後に再試行）",
+                        "↻".yellow(),
+                        full_image,
+                        e,
+                        attempt,
+                        self.retry.max_attempts,
+                        delay.as_secs()
+                    ));
+                    task.set_message("再試行待ち...");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    task.fail("プッシュ失敗");
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// 1 回分のプッシュ
+    async fn push_once(&self, image: &str, tag: &str, task: &dyn TaskProgress) -> BuildResult<()> {
+        let full_image = format!("{}:{}", image, tag);
+
         // 認証情報を取得
         let credentials = self.auth.get_credentials(&full_image)?;
 
@@ -52,11 +186,8 @@ impl ImagePusher {
             ..Default::default()
         };
 
-        println!("  → {}", full_image.cyan());
-
         let mut stream = self.docker.push_image(image, Some(options), credentials);
 
-        let mut last_status = String::new();
         let mut error_message: Option<String> = None;
 
         while let Some(result) = stream.next().await {
@@ -71,7 +202,7 @@ impl ImagePusher {
                                 .unwrap_or_else(|| "unknown push error".to_string()),
                         );
                     } else {
-                        self.handle_progress(&info, &mut last_status);
+                        self.handle_progress(&info, task);
                     }
                 }
                 Err(e) => {
@@ -82,15 +213,12 @@ impl ImagePusher {
             }
         }
 
-        // 最終行の改行
-        println!();
-
         // エラーがあった場合
         if let Some(err) = error_message {
             return Err(BuildError::PushFailed { message: err });
         }
 
-        Ok(full_image)
+        Ok(())
     }
 
     /// タグのバリデーション
@@ -130,42 +258,24 @@ impl ImagePusher {
     }
 
     /// プッシュ進捗を表示
-    fn handle_progress(&self, info: &PushImageInfo, last_status: &mut String) {
-        if let Some(status) = &info.status {
-            // bollard 0.21: 整形済み progress 文字列が廃止 → progress_detail(current/total) から再構成
-            let progress = info
-                .progress_detail
-                .as_ref()
-                .and_then(|pd| match (pd.current, pd.total) {
-                    (Some(c), Some(t)) if t > 0 => Some(format!("{c}/{t}")),
-                    _ => None,
-                })
-                .unwrap_or_default();
-
-            // 状態に応じた表示
-            match status.as_str() {
-                "Pushing" => {
-                    // プログレスバー表示
-                    print!("\r  ↑ {} {}     ", status, progress);
-                    std::io::stdout().flush().ok();
-                }
-                "Pushed" => {
-                    println!("\r  {} Pushed                    ", "✓".green());
-                }
-                "Layer already exists" => {
-                    println!("\r  {} Layer already exists      ", "✓".green());
-                }
-                "Preparing" | "Waiting" => {
-                    // 準備中は表示をスキップ（ノイズ軽減）
-                }
-                _ => {
-                    // その他のステータス
-                    if status != last_status {
-                        println!("\r  ℹ {}                    ", status);
-                        *last_status = status.clone();
-                    }
+    fn handle_progress(&self, info: &PushImageInfo, task: &dyn TaskProgress) {
+        let Some(status) = &info.status else {
+            return;
+        };
+        match status.as_str() {
+            "Pushing" => {
+                // bollard 0.21: 整形済み progress 文字列が廃止 → progress_detail(current/total) から再構成
+                task.set_message(status);
+                if let Some(pd) = &info.progress_detail
+                    && let (Some(current), Some(total)) = (pd.current, pd.total)
+                    && total > 0
+                {
+                    task.set_position(current as u64, total as u64);
                 }
             }
+            // 準備中は表示をスキップ（ノイズ軽減）
+            "Preparing" | "Waiting" => {}
+            _ => task.set_message(status),
         }
     }
 }
@@ -216,6 +326,36 @@ pub fn resolve_tag(cli_tag: Option<&str>, kdl_image: &str) -> (String, String) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_retry_delay_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_secs(2));
+        assert_eq!(policy.delay(2), Duration::from_secs(4));
+        assert_eq!(policy.delay(3), Duration::from_secs(8));
+        assert_eq!(policy.delay(10), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let push_error = |message: &str| BuildError::PushFailed {
+            message: message.to_string(),
+        };
+        assert!(is_transient(&push_error("net/http: TLS handshake timeout")));
+        assert!(is_transient(&push_error(
+            "received unexpected HTTP status: 503 Service Unavailable"
+        )));
+        assert!(is_transient(&push_error("write: connection reset by peer")));
+        assert!(!is_transient(&push_error(
+            "denied: permission_denied: write_package"
+        )));
+        assert!(!is_transient(&push_error(
+            "unauthorized: authentication required"
+        )));
+        assert!(!is_transient(&BuildError::InvalidTag {
+            tag: "-bad".to_string()
+        }));
+    }
+
     #[test]
     fn test_split_image_tag_with_tag() {
         let (image, tag) = split_image_tag("ghcr.io/org/app:v1.0");
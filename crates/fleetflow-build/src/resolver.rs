@@ -1,7 +1,7 @@
 use crate::error::{BuildError, BuildResult};
 use fleetflow_core::Service;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct BuildResolver {
     project_root: PathBuf,
@@ -145,6 +145,140 @@ impl BuildResolver {
         format!("{}-{}:{}", project_name, service_name, stage_name)
     }
 
+    /// Dockerfile の `FROM` 行からベースイメージを取得
+    ///
+    /// キャッシュウォーミング（事前 pull）用。`build_args` と Dockerfile 先頭の
+    /// `ARG` 既定値で変数を展開する。
+    pub fn resolve_base_images(
+        &self,
+        dockerfile: &Path,
+        build_args: &HashMap<String, String>,
+    ) -> BuildResult<Vec<String>> {
+        let content = std::fs::read_to_string(dockerfile)?;
+        Ok(parse_base_images(&content, build_args))
+    }
+
+    /// `image_template` 用の git 変数（`git_sha` / `git_short_sha` / `git_branch`）
+    ///
+    /// git リポジトリでない、または git が無い場合は空。ブランチ名の `/` 等は
+    /// タグに使えないため `-` に置き換える。
+    pub fn git_vars(&self) -> HashMap<String, String> {
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(&self.project_root)
+                .args(args)
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let mut vars = HashMap::new();
+        if let Some(sha) = git(&["rev-parse", "HEAD"]) {
+            vars.insert(
+                "git_short_sha".to_string(),
+                sha[..sha.len().min(7)].to_string(),
+            );
+            vars.insert("git_sha".to_string(), sha);
+        }
+        if let Some(branch) = git(&["rev-parse", "--abbrev-ref", "HEAD"]) {
+            let branch: String = branch
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            vars.insert("git_branch".to_string(), branch);
+        }
+        vars
+    }
+
+    /// `since`（例: `origin/main`）以降に変更されたファイル（プロジェクトルートからの相対パス）
+    ///
+    /// `since...HEAD` のコミット済みの差分に加え、未コミットの変更と未追跡ファイルも含める。
+    pub fn changed_files(&self, since: &str) -> BuildResult<Vec<PathBuf>> {
+        let git = |args: &[&str]| -> BuildResult<Vec<PathBuf>> {
+            let output = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&self.project_root)
+                .args(args)
+                .output()?;
+            if !output.status.success() {
+                return Err(BuildError::InvalidConfig(format!(
+                    "git {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect())
+        };
+
+        let range = format!("{since}...HEAD");
+        let mut files = git(&["diff", "--name-only", "--relative", &range])?;
+        files.extend(git(&["diff", "--name-only", "--relative", "HEAD"])?);
+        files.extend(git(&["ls-files", "--others", "--exclude-standard"])?);
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// 変更ファイルがサービスのビルドに影響するか
+    ///
+    /// `watch_paths` があればその glob に一致するか、なければビルドコンテキスト配下か
+    /// で判定する。コンテキストがプロジェクトルートの場合は何か変更があれば対象。
+    /// Dockerfile 自体の変更は常に対象。
+    pub fn is_affected(&self, service: &Service, changed: &[PathBuf]) -> BuildResult<bool> {
+        let Some(build) = &service.build else {
+            return Ok(false);
+        };
+
+        if let Some(dockerfile) = &build.dockerfile
+            && changed
+                .iter()
+                .any(|file| *file == normalize_path(dockerfile))
+        {
+            return Ok(true);
+        }
+
+        if build.watch_paths.is_empty() {
+            let context = build
+                .context
+                .as_deref()
+                .map(normalize_path)
+                .unwrap_or_default();
+            if context.as_os_str().is_empty() {
+                return Ok(!changed.is_empty());
+            }
+            return Ok(changed.iter().any(|file| file.starts_with(&context)));
+        }
+
+        let patterns = build
+            .watch_paths
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|e| {
+                    BuildError::InvalidConfig(format!(
+                        "Invalid watch_paths pattern '{}': {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<BuildResult<Vec<_>>>()?;
+        Ok(changed
+            .iter()
+            .any(|file| patterns.iter().any(|p| p.matches_path(file))))
+    }
+
     /// ビルド引数の検証（機密情報の警告）
     pub fn validate_build_arg(&self, key: &str, _value: &str) {
         let sensitive_patterns = ["password", "token", "secret", "api_key", "private_key"];
@@ -163,6 +297,118 @@ impl BuildResolver {
     }
 }
 
+/// `./services/api` → `services/api`（`.` 成分を除く）
+fn normalize_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// `image_template` を展開する
+///
+/// 変数は `{name}` または `{{name}}`。未定義の変数はエラー。
+/// `registry` が空のときは先頭の `/` を取り除く（`{registry}/{project}` → `project`）。
+pub fn render_image_template(
+    template: &str,
+    vars: &HashMap<String, String>,
+) -> BuildResult<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            BuildError::InvalidConfig(format!("image_template has an unclosed '{{': {}", template))
+        })?;
+        let name = rest[start..start + end].trim_start_matches('{').trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| BuildError::VariableNotFound(name.to_string()))?;
+        out.push_str(value);
+        rest = rest[start + end..].trim_start_matches('}');
+    }
+    out.push_str(rest);
+    Ok(out.trim_start_matches('/').to_string())
+}
+
+/// `$VAR` / `${VAR}` を展開する。未定義の変数があれば None
+fn expand_arg(value: &str, args: &HashMap<String, String>) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let (name, tail) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced.find('}')?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        out.push_str(args.get(name)?);
+        rest = tail;
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Dockerfile の内容からベースイメージを出現順（重複なし）に返す
+///
+/// 同じ Dockerfile 内のステージ参照（`FROM builder`）と `scratch`、
+/// 展開できない変数を含むイメージは対象外。
+pub fn parse_base_images(content: &str, build_args: &HashMap<String, String>) -> Vec<String> {
+    let mut args: HashMap<String, String> = HashMap::new();
+    let mut stages: Vec<String> = Vec::new();
+    let mut images: Vec<String> = Vec::new();
+    let mut seen_from = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        let Some((instruction, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        match instruction.to_ascii_uppercase().as_str() {
+            // 最初の FROM より前の ARG だけが FROM 行で使える
+            "ARG" if !seen_from => {
+                let (name, default) = match rest.trim().split_once('=') {
+                    Some((name, default)) => (name, Some(default.trim_matches('"'))),
+                    None => (rest.trim(), None),
+                };
+                if let Some(value) = build_args.get(name).map(String::as_str).or(default) {
+                    args.insert(name.to_string(), value.to_string());
+                }
+            }
+            "FROM" => {
+                seen_from = true;
+                let mut words = rest.split_whitespace().filter(|w| !w.starts_with("--"));
+                let Some(image) = words.next() else {
+                    continue;
+                };
+                // 自身の別名を登録する前に、前のステージへの参照かを判定する
+                let image = expand_arg(image, &args);
+                let is_stage = image
+                    .as_ref()
+                    .is_some_and(|i| stages.contains(&i.to_ascii_lowercase()));
+                if let (Some(keyword), Some(alias)) = (words.next(), words.next())
+                    && keyword.eq_ignore_ascii_case("as")
+                {
+                    stages.push(alias.to_ascii_lowercase());
+                }
+                let Some(image) = image else {
+                    continue;
+                };
+                if image == "scratch" || is_stage || images.contains(&image) {
+                    continue;
+                }
+                images.push(image);
+            }
+            _ => {}
+        }
+    }
+    images
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,4 +548,138 @@ mod tests {
         let context = resolver.resolve_context(&service).unwrap();
         assert_eq!(context, ctx_dir);
     }
+
+    #[test]
+    fn test_render_image_template() {
+        let vars = HashMap::from([
+            ("registry".to_string(), "ghcr.io/acme".to_string()),
+            ("project".to_string(), "shop".to_string()),
+            ("service".to_string(), "api".to_string()),
+            ("stage".to_string(), "prod".to_string()),
+            ("git_short_sha".to_string(), "1a2b3c4".to_string()),
+        ]);
+        assert_eq!(
+            render_image_template(
+                "{{registry}}/{{project}}/{{service}}:{stage}-{git_short_sha}",
+                &vars
+            )
+            .unwrap(),
+            "ghcr.io/acme/shop/api:prod-1a2b3c4"
+        );
+
+        let mut local = vars.clone();
+        local.insert("registry".to_string(), String::new());
+        assert_eq!(
+            render_image_template("{registry}/{project}-{service}:{stage}", &local).unwrap(),
+            "shop-api:prod"
+        );
+
+        assert!(matches!(
+            render_image_template("{project}:{git_branch}", &vars),
+            Err(BuildError::VariableNotFound(name)) if name == "git_branch"
+        ));
+    }
+
+    #[test]
+    fn test_parse_base_images_multi_stage() {
+        let dockerfile = "\
+ARG RUST_VERSION=1.85
+ARG BASE
+FROM --platform=$BUILDPLATFORM rust:${RUST_VERSION} AS builder
+RUN cargo build --release
+FROM ${BASE}
+FROM builder AS test
+FROM debian:bookworm-slim
+COPY --from=builder /app /app
+FROM scratch
+FROM debian:bookworm-slim
+";
+        let images = parse_base_images(dockerfile, &HashMap::new());
+        assert_eq!(images, vec!["rust:1.85", "debian:bookworm-slim"]);
+
+        let args = HashMap::from([
+            ("RUST_VERSION".to_string(), "1.86".to_string()),
+            ("BASE".to_string(), "alpine:3.20".to_string()),
+        ]);
+        let images = parse_base_images(dockerfile, &args);
+        assert_eq!(
+            images,
+            vec!["rust:1.86", "alpine:3.20", "debian:bookworm-slim"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_base_images() {
+        let temp_dir = tempdir().unwrap();
+        let dockerfile_path = temp_dir.path().join("Dockerfile");
+        fs::write(&dockerfile_path, "from node:20-alpine as deps\nFROM deps\n").unwrap();
+
+        let resolver = BuildResolver::new(temp_dir.path().to_path_buf());
+        let images = resolver
+            .resolve_base_images(&dockerfile_path, &HashMap::new())
+            .unwrap();
+        assert_eq!(images, vec!["node:20-alpine"]);
+    }
+
+    #[test]
+    fn test_is_affected() {
+        let resolver = BuildResolver::new(PathBuf::from("/tmp"));
+        let service = |build: BuildConfig| Service {
+            build: Some(build),
+            ..Default::default()
+        };
+        let changed = |files: &[&str]| files.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        let api = service(BuildConfig {
+            watch_paths: vec!["services/api/**".into(), "libs/shared/**".into()],
+            ..Default::default()
+        });
+        assert!(
+            resolver
+                .is_affected(&api, &changed(&["libs/shared/src/lib.rs"]))
+                .unwrap()
+        );
+        assert!(
+            !resolver
+                .is_affected(&api, &changed(&["services/web/index.ts"]))
+                .unwrap()
+        );
+
+        // watch_paths が無ければコンテキスト配下で判定
+        let web = service(BuildConfig {
+            context: Some(PathBuf::from("./services/web")),
+            dockerfile: Some(PathBuf::from("docker/web.Dockerfile")),
+            ..Default::default()
+        });
+        assert!(
+            resolver
+                .is_affected(&web, &changed(&["services/web/index.ts"]))
+                .unwrap()
+        );
+        assert!(
+            resolver
+                .is_affected(&web, &changed(&["docker/web.Dockerfile"]))
+                .unwrap()
+        );
+        assert!(
+            !resolver
+                .is_affected(&web, &changed(&["services/api/main.rs"]))
+                .unwrap()
+        );
+
+        // コンテキストがプロジェクトルートなら何か変更があれば対象
+        let root = service(BuildConfig::default());
+        assert!(
+            resolver
+                .is_affected(&root, &changed(&["README.md"]))
+                .unwrap()
+        );
+        assert!(!resolver.is_affected(&root, &[]).unwrap());
+
+        let invalid = service(BuildConfig {
+            watch_paths: vec!["services/[api".into()],
+            ..Default::default()
+        });
+        assert!(resolver.is_affected(&invalid, &changed(&["x"])).is_err());
+    }
 }
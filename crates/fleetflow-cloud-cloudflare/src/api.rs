@@ -0,0 +1,430 @@
+//! Cloudflare REST API client for R2 and Workers
+//!
+//! Talks to the Cloudflare API directly with an API token, so R2 buckets and
+//! Worker scripts can be managed in CI without installing or logging in to
+//! wrangler. [`crate::CloudflareProvider`] uses this client when
+//! `CLOUDFLARE_API_TOKEN` and an account ID are available, and falls back to
+//! wrangler otherwise.
+
+use crate::dns::{ApiResponse, CLOUDFLARE_API_BASE};
+use crate::error::{CloudflareError, Result};
+use crate::wrangler::{R2BucketInfo, WorkerConfig, WorkerInfo};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// Compatibility date sent with Worker uploads
+pub(crate) const WORKER_COMPATIBILITY_DATE: &str = "2024-09-23";
+
+/// Cloudflare REST API client (account scoped)
+pub struct CloudflareApi {
+    client: reqwest::Client,
+    api_token: String,
+    account_id: String,
+    /// Zone for Worker routes (`CLOUDFLARE_ZONE_ID`)
+    zone_id: Option<String>,
+}
+
+impl CloudflareApi {
+    pub fn new(api_token: String, account_id: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_token,
+            account_id,
+            zone_id: None,
+        }
+    }
+
+    /// Build from `CLOUDFLARE_API_TOKEN` and the account ID
+    /// (`account_id` or `CLOUDFLARE_ACCOUNT_ID`)
+    pub fn from_env(account_id: Option<String>) -> Result<Self> {
+        let api_token = std::env::var("CLOUDFLARE_API_TOKEN")
+            .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_API_TOKEN".to_string()))?;
+        let account_id = match account_id {
+            Some(id) => id,
+            None => std::env::var("CLOUDFLARE_ACCOUNT_ID")
+                .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_ACCOUNT_ID".to_string()))?,
+        };
+        Ok(Self::new(api_token, account_id).with_zone_id(std::env::var("CLOUDFLARE_ZONE_ID").ok()))
+    }
+
+    /// Zone used when attaching Worker routes
+    pub fn with_zone_id(mut self, zone_id: Option<String>) -> Self {
+        self.zone_id = zone_id;
+        self
+    }
+
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    fn account_url(&self, path: &str) -> String {
+        format!(
+            "{}/accounts/{}/{}",
+            CLOUDFLARE_API_BASE, self.account_id, path
+        )
+    }
+
+    /// Check that the API token is valid and active
+    pub async fn verify_token(&self) -> Result<bool> {
+        let response = self
+            .client
+            .get(format!("{}/user/tokens/verify", CLOUDFLARE_API_BASE))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        let status: ApiTokenStatus = parse(response).await?;
+        Ok(status.status == "active")
+    }
+
+    // ========== R2 Bucket Operations ==========
+
+    /// List all R2 buckets
+    pub async fn list_r2_buckets(&self) -> Result<Vec<R2BucketInfo>> {
+        let response = self
+            .client
+            .get(self.account_url("r2/buckets"))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        let list: ApiBucketList = parse(response).await?;
+        Ok(list.buckets.into_iter().map(Into::into).collect())
+    }
+
+    /// Create an R2 bucket
+    pub async fn create_r2_bucket(&self, name: &str) -> Result<R2BucketInfo> {
+        let response = self
+            .client
+            .post(self.account_url("r2/buckets"))
+            .bearer_auth(&self.api_token)
+            .json(&serde_json::json!({ "name": name }))
+            .send()
+            .await?;
+        let bucket: ApiBucket = parse(response).await?;
+        Ok(bucket.into())
+    }
+
+    /// Delete an R2 bucket (the bucket must be empty)
+    pub async fn delete_r2_bucket(&self, name: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(self.account_url(&format!("r2/buckets/{}", name)))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        parse::<serde_json::Value>(response).await?;
+        Ok(())
+    }
+
+    /// Custom domains attached to an R2 bucket
+    pub async fn list_r2_custom_domains(&self, bucket: &str) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(self.account_url(&format!("r2/buckets/{}/domains/custom", bucket)))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        let list: ApiCustomDomainList = parse(response).await?;
+        Ok(list.domains.into_iter().map(|d| d.domain).collect())
+    }
+
+    /// Serve an R2 bucket on a custom domain
+    ///
+    /// Cloudflare creates the DNS record and certificate for the domain, which
+    /// must be in the zone `zone_id`. Does nothing if already attached.
+    pub async fn ensure_r2_custom_domain(
+        &self,
+        bucket: &str,
+        domain: &str,
+        zone_id: &str,
+    ) -> Result<()> {
+        if self
+            .list_r2_custom_domains(bucket)
+            .await?
+            .iter()
+            .any(|d| d == domain)
+        {
+            return Ok(());
+        }
+
+        tracing::info!(bucket, domain, "Attaching R2 custom domain");
+        let response = self
+            .client
+            .post(self.account_url(&format!("r2/buckets/{}/domains/custom", bucket)))
+            .bearer_auth(&self.api_token)
+            .json(&serde_json::json!({ "domain": domain, "zoneId": zone_id, "enabled": true }))
+            .send()
+            .await?;
+        parse::<serde_json::Value>(response).await?;
+        Ok(())
+    }
+
+    // ========== Worker Operations ==========
+
+    /// List all Worker scripts
+    pub async fn list_workers(&self) -> Result<Vec<WorkerInfo>> {
+        let response = self
+            .client
+            .get(self.account_url("workers/scripts"))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        let scripts: Vec<ApiWorkerScript> = parse(response).await?;
+        Ok(scripts
+            .into_iter()
+            .map(|s| WorkerInfo {
+                name: s.id,
+                created_at: s.created_on,
+                routes: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// Upload a Worker script and attach its routes
+    ///
+    /// ES module scripts (`export default`) are uploaded as modules, others in
+    /// service-worker format. `vars` become plain-text bindings.
+    pub async fn deploy_worker(&self, config: &WorkerConfig) -> Result<WorkerInfo> {
+        let script = tokio::fs::read_to_string(&config.script_path).await?;
+        let file_name = std::path::Path::new(&config.script_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "worker.js".to_string());
+        let metadata = worker_metadata(config, &file_name, is_module(&script));
+        let content_type = if is_module(&script) {
+            "application/javascript+module"
+        } else {
+            "application/javascript"
+        };
+
+        let form = reqwest::multipart::Form::new()
+            .part(
+                "metadata",
+                reqwest::multipart::Part::text(metadata.to_string())
+                    .mime_str("application/json")?,
+            )
+            .part(
+                file_name.clone(),
+                reqwest::multipart::Part::text(script)
+                    .file_name(file_name)
+                    .mime_str(content_type)?,
+            );
+
+        tracing::info!(worker = %config.name, "Uploading Worker script");
+        let response = self
+            .client
+            .put(self.account_url(&format!("workers/scripts/{}", config.name)))
+            .bearer_auth(&self.api_token)
+            .multipart(form)
+            .send()
+            .await?;
+        let uploaded: ApiWorkerScript = parse(response).await?;
+
+        for pattern in &config.routes {
+            self.ensure_worker_route(pattern, &config.name).await?;
+        }
+
+        Ok(WorkerInfo {
+            name: config.name.clone(),
+            created_at: uploaded.created_on,
+            routes: config.routes.clone(),
+        })
+    }
+
+    /// Attach a route pattern (e.g. `api.example.com/*`) to a Worker
+    async fn ensure_worker_route(&self, pattern: &str, script: &str) -> Result<()> {
+        let zone_id = self.zone_id.as_deref().ok_or_else(|| {
+            CloudflareError::InvalidConfig(format!(
+                "CLOUDFLARE_ZONE_ID is required to attach Worker route {}",
+                pattern
+            ))
+        })?;
+        let url = format!("{}/zones/{}/workers/routes", CLOUDFLARE_API_BASE, zone_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        let routes: Vec<ApiWorkerRoute> = parse(response).await?;
+        if routes
+            .iter()
+            .any(|r| r.pattern == pattern && r.script.as_deref() == Some(script))
+        {
+            return Ok(());
+        }
+
+        tracing::info!(pattern, script, "Attaching Worker route");
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.api_token)
+            .json(&serde_json::json!({ "pattern": pattern, "script": script }))
+            .send()
+            .await?;
+        parse::<serde_json::Value>(response).await?;
+        Ok(())
+    }
+
+    /// Delete a Worker script
+    pub async fn delete_worker(&self, name: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(self.account_url(&format!("workers/scripts/{}?force=true", name)))
+            .bearer_auth(&self.api_token)
+            .send()
+            .await?;
+        parse::<serde_json::Value>(response).await?;
+        Ok(())
+    }
+}
+
+/// Whether a Worker script uses ES module syntax
+fn is_module(script: &str) -> bool {
+    script.contains("export default")
+}
+
+/// Upload metadata for a Worker script
+fn worker_metadata(config: &WorkerConfig, file_name: &str, module: bool) -> serde_json::Value {
+    let mut vars: Vec<(&String, &String)> = config.vars.iter().collect();
+    vars.sort();
+    let bindings: Vec<serde_json::Value> = vars
+        .into_iter()
+        .map(|(name, text)| serde_json::json!({ "type": "plain_text", "name": name, "text": text }))
+        .collect();
+
+    let mut metadata = serde_json::json!({
+        "compatibility_date": WORKER_COMPATIBILITY_DATE,
+        "bindings": bindings,
+    });
+    let key = if module { "main_module" } else { "body_part" };
+    metadata[key] = serde_json::json!(file_name);
+    metadata
+}
+
+/// Parse a Cloudflare API envelope, turning `success: false` into an error
+async fn parse<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let api_response: ApiResponse<Option<T>> = response.json().await?;
+
+    if !api_response.success {
+        let error_msg = api_response
+            .errors
+            .first()
+            .map(|e| e.message.clone())
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(CloudflareError::ApiError(error_msg));
+    }
+
+    api_response
+        .result
+        .ok_or_else(|| CloudflareError::ApiError("Empty result".to_string()))
+}
+
+// ============ API Types ============
+
+#[derive(Debug, Deserialize)]
+struct ApiTokenStatus {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiBucketList {
+    #[serde(default)]
+    buckets: Vec<ApiBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiBucket {
+    name: String,
+    creation_date: Option<String>,
+}
+
+impl From<ApiBucket> for R2BucketInfo {
+    fn from(b: ApiBucket) -> Self {
+        R2BucketInfo {
+            name: b.name,
+            created_at: b.creation_date,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiCustomDomainList {
+    #[serde(default)]
+    domains: Vec<ApiCustomDomain>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiCustomDomain {
+    domain: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiWorkerScript {
+    id: String,
+    created_on: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiWorkerRoute {
+    pattern: String,
+    script: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn worker(vars: &[(&str, &str)]) -> WorkerConfig {
+        WorkerConfig {
+            name: "edge".to_string(),
+            script_path: "dist/worker.js".to_string(),
+            routes: vec![],
+            vars: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_worker_metadata_module() {
+        let metadata = worker_metadata(&worker(&[("MODE", "prod")]), "worker.js", true);
+        assert_eq!(metadata["main_module"], "worker.js");
+        assert!(metadata.get("body_part").is_none());
+        assert_eq!(metadata["bindings"][0]["type"], "plain_text");
+        assert_eq!(metadata["bindings"][0]["name"], "MODE");
+        assert_eq!(metadata["bindings"][0]["text"], "prod");
+    }
+
+    #[test]
+    fn test_worker_metadata_service_worker() {
+        let metadata = worker_metadata(&worker(&[]), "worker.js", false);
+        assert_eq!(metadata["body_part"], "worker.js");
+        assert!(is_module("export default { fetch() {} }"));
+        assert!(!is_module("addEventListener('fetch', e => {})"));
+    }
+
+    #[test]
+    fn test_bucket_list_deserialize() {
+        let json = r#"{
+            "success": true,
+            "errors": [],
+            "result": { "buckets": [{ "name": "assets", "creation_date": "2024-01-01T00:00:00Z" }] }
+        }"#;
+        let response: ApiResponse<Option<ApiBucketList>> = serde_json::from_str(json).unwrap();
+        let buckets: Vec<R2BucketInfo> = response
+            .result
+            .unwrap()
+            .buckets
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        assert_eq!(buckets[0].name, "assets");
+        assert_eq!(
+            buckets[0].created_at.as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+    }
+}
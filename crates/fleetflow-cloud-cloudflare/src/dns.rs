@@ -5,9 +5,10 @@
 
 use crate::error::{CloudflareError, Result};
 use crate::wrangler::DnsRecordInfo;
+use fleetflow_cloud::dns_provider::{DnsProvider, DnsRecordRequest, DnsRecordSpec};
 use serde::{Deserialize, Serialize};
 
-const CLOUDFLARE_API_BASE: &str = "https://api.cloudflare.com/client/v4";
+pub(crate) const CLOUDFLARE_API_BASE: &str = "https://api.cloudflare.com/client/v4";
 
 /// Cloudflare DNS manager
 pub struct CloudflareDns {
@@ -15,6 +16,7 @@ pub struct CloudflareDns {
     api_token: String,
     zone_id: String,
     domain: String,
+    subdomain_template: Option<String>,
 }
 
 /// Configuration for DNS manager
@@ -23,24 +25,225 @@ pub struct DnsConfig {
     pub api_token: String,
     pub zone_id: String,
     pub domain: String,
+    /// Subdomain template with `{service}` / `{stage}` (see [`render_subdomain`])
+    pub subdomain_template: Option<String>,
 }
 
 impl DnsConfig {
     /// Create DnsConfig from environment variables
     pub fn from_env() -> Result<Self> {
-        let api_token = std::env::var("CLOUDFLARE_API_TOKEN")
-            .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_API_TOKEN".to_string()))?;
-        let zone_id = std::env::var("CLOUDFLARE_ZONE_ID")
-            .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_ZONE_ID".to_string()))?;
-        let domain = std::env::var("CLOUDFLARE_DOMAIN")
-            .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_DOMAIN".to_string()))?;
+        Self::with_overrides(None, None, None)
+    }
+
+    /// Create DnsConfig from explicit values (e.g. declared in fleet.kdl),
+    /// falling back to the `CLOUDFLARE_*` environment variables for each missing value
+    pub fn with_overrides(
+        api_token: Option<String>,
+        zone_id: Option<String>,
+        domain: Option<String>,
+    ) -> Result<Self> {
+        let api_token = match api_token {
+            Some(token) => token,
+            None => std::env::var("CLOUDFLARE_API_TOKEN")
+                .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_API_TOKEN".to_string()))?,
+        };
+        let zone_id = match zone_id {
+            Some(zone_id) => zone_id,
+            None => std::env::var("CLOUDFLARE_ZONE_ID")
+                .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_ZONE_ID".to_string()))?,
+        };
+        let domain = match domain {
+            Some(domain) => domain,
+            None => domain_from_env()?,
+        };
 
         Ok(Self {
             api_token,
             zone_id,
             domain,
+            subdomain_template: None,
         })
     }
+
+    /// Use a subdomain template instead of the default naming rule
+    pub fn with_subdomain_template(mut self, template: Option<String>) -> Self {
+        self.subdomain_template = template;
+        self
+    }
+}
+
+/// Read the managed domain from `CLOUDFLARE_DOMAIN`
+///
+/// Unlike [`DnsConfig::from_env`], this does not require API credentials,
+/// so it can be used to build service URLs without touching the API.
+pub fn domain_from_env() -> Result<String> {
+    std::env::var("CLOUDFLARE_DOMAIN")
+        .map_err(|_| CloudflareError::MissingEnvVar("CLOUDFLARE_DOMAIN".to_string()))
+}
+
+/// Generate a subdomain name from service and stage
+pub fn service_subdomain(service: &str, stage: &str) -> String {
+    let short_name = service
+        .trim_start_matches("creo-")
+        .trim_end_matches("-server")
+        .trim_end_matches("-viewer");
+    format!("{}-{}", short_name, stage)
+}
+
+/// Generate a subdomain from a template with `{service}` / `{stage}` placeholders
+///
+/// Without a template, falls back to [`service_subdomain`].
+pub fn render_subdomain(template: Option<&str>, service: &str, stage: &str) -> String {
+    match template {
+        Some(template) => template
+            .replace("{service}", service)
+            .replace("{stage}", stage),
+        None => service_subdomain(service, stage),
+    }
+}
+
+/// Public HTTPS URL of a service deployed to a stage under `domain`
+pub fn service_url(domain: &str, service: &str, stage: &str) -> String {
+    format!("https://{}.{}", service_subdomain(service, stage), domain)
+}
+
+/// Look up the zone ID of `domain` (requires the Zone:Read permission)
+pub async fn lookup_zone_id(api_token: &str, domain: &str) -> Result<String> {
+    let url = format!("{}/zones?name={}", CLOUDFLARE_API_BASE, domain);
+    let response = reqwest::Client::new()
+        .get(&url)
+        .bearer_auth(api_token)
+        .send()
+        .await?;
+
+    let api_response: ApiResponse<Vec<ApiZone>> = response.json().await?;
+    if !api_response.success {
+        let error_msg = api_response
+            .errors
+            .first()
+            .map(|e| e.message.clone())
+            .unwrap_or_else(|| "Unknown error".to_string());
+        return Err(CloudflareError::ApiError(error_msg));
+    }
+
+    api_response
+        .result
+        .into_iter()
+        .find(|zone| zone.name == domain)
+        .map(|zone| zone.id)
+        .ok_or_else(|| CloudflareError::ApiError(format!("Zone not found: {}", domain)))
+}
+
+/// TTL value Cloudflare treats as "automatic"
+pub const AUTO_TTL: u32 = 1;
+
+/// DNS record types managed by FleetFlow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Cname,
+    Txt,
+}
+
+impl RecordType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+            RecordType::Cname => "CNAME",
+            RecordType::Txt => "TXT",
+        }
+    }
+}
+
+impl std::fmt::Display for RecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for RecordType {
+    type Err = CloudflareError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "A" => Ok(RecordType::A),
+            "AAAA" => Ok(RecordType::Aaaa),
+            "CNAME" => Ok(RecordType::Cname),
+            "TXT" => Ok(RecordType::Txt),
+            _ => Err(CloudflareError::InvalidConfig(format!(
+                "Unsupported DNS record type: {} (expected A / AAAA / CNAME / TXT)",
+                s
+            ))),
+        }
+    }
+}
+
+/// TTL and proxy settings applied when creating or updating a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordOptions {
+    /// TTL in seconds ([`AUTO_TTL`] = automatic)
+    pub ttl: u32,
+    /// Route traffic through the Cloudflare proxy
+    pub proxied: bool,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            ttl: AUTO_TTL,
+            proxied: false,
+        }
+    }
+}
+
+impl RecordOptions {
+    /// Whether an existing record already has these settings
+    ///
+    /// Proxied records always report an automatic TTL, so TTL is only compared
+    /// for DNS-only records.
+    pub fn matches(&self, record: &DnsRecordInfo) -> bool {
+        record.proxied == self.proxied
+            && (self.proxied || record.ttl.unwrap_or(AUTO_TTL) == self.ttl)
+    }
+}
+
+/// Records per page when listing a whole zone
+const LIST_PAGE_SIZE: u32 = 100;
+
+/// DNS managers for several zones, selected by the name being managed
+#[derive(Default)]
+pub struct DnsZones {
+    zones: Vec<CloudflareDns>,
+}
+
+impl DnsZones {
+    pub fn new(zones: Vec<CloudflareDns>) -> Self {
+        Self { zones }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Managers of all zones
+    pub fn iter(&self) -> impl Iterator<Item = &CloudflareDns> {
+        self.zones.iter()
+    }
+
+    /// Domains of all managed zones
+    pub fn domains(&self) -> impl Iterator<Item = &str> {
+        self.zones.iter().map(|z| z.domain())
+    }
+
+    /// The manager whose zone contains `name` (the longest matching domain wins)
+    pub fn zone_for(&self, name: &str) -> Option<&CloudflareDns> {
+        self.zones
+            .iter()
+            .filter(|z| z.relative_name(name).is_some())
+            .max_by_key(|z| z.domain().len())
+    }
 }
 
 impl CloudflareDns {
@@ -51,6 +254,7 @@ impl CloudflareDns {
             api_token: config.api_token,
             zone_id: config.zone_id,
             domain: config.domain,
+            subdomain_template: config.subdomain_template,
         }
     }
 
@@ -59,13 +263,14 @@ impl CloudflareDns {
         &self.domain
     }
 
+    /// Get the zone ID
+    pub fn zone_id(&self) -> &str {
+        &self.zone_id
+    }
+
     /// Generate a subdomain name from service and stage
     pub fn generate_subdomain(&self, service: &str, stage: &str) -> String {
-        let short_name = service
-            .trim_start_matches("creo-")
-            .trim_end_matches("-server")
-            .trim_end_matches("-viewer");
-        format!("{}-{}", short_name, stage)
+        render_subdomain(self.subdomain_template.as_deref(), service, stage)
     }
 
     /// Get the full domain name for a subdomain
@@ -73,6 +278,18 @@ impl CloudflareDns {
         format!("{}.{}", subdomain, self.domain)
     }
 
+    /// Name relative to this zone (`app.internal.example.com` → `app.internal`),
+    /// or None if `name` is not a subdomain of the zone
+    pub fn relative_name(&self, name: &str) -> Option<String> {
+        if name == self.domain {
+            return Some(String::new());
+        }
+        name.strip_suffix(&self.domain)?
+            .strip_suffix('.')
+            .filter(|relative| !relative.is_empty())
+            .map(str::to_string)
+    }
+
     /// List all DNS A records in the zone
     pub async fn list_records(&self) -> Result<Vec<DnsRecordInfo>> {
         let url = format!(
@@ -98,26 +315,59 @@ impl CloudflareDns {
             return Err(CloudflareError::ApiError(error_msg));
         }
 
-        Ok(api_response
-            .result
-            .into_iter()
-            .map(|r| DnsRecordInfo {
-                id: r.id,
-                name: r.name,
-                record_type: r.r#type,
-                content: r.content,
-                ttl: Some(r.ttl),
-                proxied: r.proxied,
-            })
-            .collect())
+        Ok(api_response.result.into_iter().map(Into::into).collect())
     }
 
-    /// Find a DNS record by subdomain
-    pub async fn find_record(&self, subdomain: &str) -> Result<Option<DnsRecordInfo>> {
+    /// List all DNS records in the zone (every type, following pagination)
+    pub async fn list_all_records(&self) -> Result<Vec<DnsRecordSpec>> {
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}/zones/{}/dns_records?per_page={}&page={}",
+                CLOUDFLARE_API_BASE, self.zone_id, LIST_PAGE_SIZE, page
+            );
+
+            let response = self
+                .client
+                .get(&url)
+                .bearer_auth(&self.api_token)
+                .send()
+                .await?;
+
+            let api_response: ApiResponse<Vec<ApiDnsRecord>> = response.json().await?;
+
+            if !api_response.success {
+                let error_msg = api_response
+                    .errors
+                    .first()
+                    .map(|e| e.message.clone())
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                return Err(CloudflareError::ApiError(error_msg));
+            }
+
+            let total_pages = api_response
+                .result_info
+                .as_ref()
+                .map_or(1, |info| info.total_pages);
+            records.extend(api_response.result.into_iter().map(to_record_spec));
+            if page >= total_pages {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+
+    /// Find DNS records of a type by subdomain
+    pub async fn find_records(
+        &self,
+        record_type: RecordType,
+        subdomain: &str,
+    ) -> Result<Vec<DnsRecordInfo>> {
         let full_name = self.full_domain(subdomain);
         let url = format!(
-            "{}/zones/{}/dns_records?type=A&name={}",
-            CLOUDFLARE_API_BASE, self.zone_id, full_name
+            "{}/zones/{}/dns_records?type={}&name={}",
+            CLOUDFLARE_API_BASE, self.zone_id, record_type, full_name
         );
 
         let response = self
@@ -138,37 +388,45 @@ impl CloudflareDns {
             return Err(CloudflareError::ApiError(error_msg));
         }
 
-        Ok(api_response
-            .result
-            .into_iter()
-            .next()
-            .map(|r| DnsRecordInfo {
-                id: r.id,
-                name: r.name,
-                record_type: r.r#type,
-                content: r.content,
-                ttl: Some(r.ttl),
-                proxied: r.proxied,
-            }))
+        Ok(api_response.result.into_iter().map(Into::into).collect())
     }
 
-    /// Create a new DNS A record
-    pub async fn create_record(&self, subdomain: &str, ip: &str) -> Result<DnsRecordInfo> {
-        let url = format!("{}/zones/{}/dns_records", CLOUDFLARE_API_BASE, self.zone_id);
+    /// Find a DNS record by subdomain
+    pub async fn find_record(&self, subdomain: &str) -> Result<Option<DnsRecordInfo>> {
+        Ok(self
+            .find_records(RecordType::A, subdomain)
+            .await?
+            .into_iter()
+            .next())
+    }
 
-        let request_body = CreateDnsRecordRequest {
-            r#type: "A".to_string(),
+    /// Create a new DNS record of any supported type
+    pub async fn create_typed_record(
+        &self,
+        record_type: RecordType,
+        subdomain: &str,
+        content: &str,
+        options: &RecordOptions,
+    ) -> Result<DnsRecordInfo> {
+        self.post_record(&CreateDnsRecordRequest {
+            r#type: record_type.to_string(),
             name: subdomain.to_string(),
-            content: ip.to_string(),
-            ttl: 1, // Auto
-            proxied: false,
-        };
+            content: content.to_string(),
+            ttl: options.ttl,
+            proxied: options.proxied,
+            comment: None,
+        })
+        .await
+    }
+
+    async fn post_record(&self, request_body: &CreateDnsRecordRequest) -> Result<DnsRecordInfo> {
+        let url = format!("{}/zones/{}/dns_records", CLOUDFLARE_API_BASE, self.zone_id);
 
         let response = self
             .client
             .post(&url)
             .bearer_auth(&self.api_token)
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
@@ -183,33 +441,63 @@ impl CloudflareDns {
             return Err(CloudflareError::ApiError(error_msg));
         }
 
-        let r = api_response.result;
-        Ok(DnsRecordInfo {
-            id: r.id,
-            name: r.name,
-            record_type: r.r#type,
-            content: r.content,
-            ttl: Some(r.ttl),
-            proxied: r.proxied,
-        })
+        Ok(api_response.result.into())
+    }
+
+    /// Create a new DNS A record
+    pub async fn create_record(&self, subdomain: &str, ip: &str) -> Result<DnsRecordInfo> {
+        self.create_typed_record(RecordType::A, subdomain, ip, &RecordOptions::default())
+            .await
+    }
+
+    /// Update the content, TTL and proxy status of an existing DNS record
+    pub async fn update_typed_record(
+        &self,
+        record_id: &str,
+        content: &str,
+        options: &RecordOptions,
+    ) -> Result<DnsRecordInfo> {
+        self.patch_record(
+            record_id,
+            &UpdateDnsRecordRequest {
+                content: content.to_string(),
+                ttl: Some(options.ttl),
+                proxied: Some(options.proxied),
+                comment: None,
+            },
+        )
+        .await
     }
 
     /// Update an existing DNS record
     pub async fn update_record(&self, record_id: &str, ip: &str) -> Result<DnsRecordInfo> {
+        self.patch_record(
+            record_id,
+            &UpdateDnsRecordRequest {
+                content: ip.to_string(),
+                ttl: None,
+                proxied: None,
+                comment: None,
+            },
+        )
+        .await
+    }
+
+    async fn patch_record(
+        &self,
+        record_id: &str,
+        request_body: &UpdateDnsRecordRequest,
+    ) -> Result<DnsRecordInfo> {
         let url = format!(
             "{}/zones/{}/dns_records/{}",
             CLOUDFLARE_API_BASE, self.zone_id, record_id
         );
 
-        let request_body = UpdateDnsRecordRequest {
-            content: ip.to_string(),
-        };
-
         let response = self
             .client
             .patch(&url)
             .bearer_auth(&self.api_token)
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
@@ -224,15 +512,7 @@ impl CloudflareDns {
             return Err(CloudflareError::ApiError(error_msg));
         }
 
-        let r = api_response.result;
-        Ok(DnsRecordInfo {
-            id: r.id,
-            name: r.name,
-            record_type: r.r#type,
-            content: r.content,
-            ttl: Some(r.ttl),
-            proxied: r.proxied,
-        })
+        Ok(api_response.result.into())
     }
 
     /// Delete a DNS record
@@ -263,85 +543,102 @@ impl CloudflareDns {
         Ok(())
     }
 
-    /// Ensure a DNS record exists with the specified IP (create or update)
-    pub async fn ensure_record(&self, subdomain: &str, ip: &str) -> Result<DnsRecordInfo> {
-        if let Some(existing) = self.find_record(subdomain).await? {
-            if existing.content == ip {
+    /// Ensure a DNS record exists with the specified content and options (create or update)
+    ///
+    /// A name can hold several TXT records (e.g. domain verification), so for TXT only
+    /// the record with the same content is updated; other values are left alone.
+    pub async fn ensure_typed_record(
+        &self,
+        record_type: RecordType,
+        subdomain: &str,
+        content: &str,
+        options: &RecordOptions,
+    ) -> Result<DnsRecordInfo> {
+        let existing = self.find_records(record_type, subdomain).await?;
+        let existing = match record_type {
+            RecordType::Txt => existing.into_iter().find(|r| r.content == content),
+            _ => existing.into_iter().next(),
+        };
+
+        if let Some(existing) = existing {
+            if existing.content == content && options.matches(&existing) {
                 tracing::debug!(
-                    "DNS record already exists with correct IP: {}",
-                    existing.name
+                    "{} record already up to date: {} -> {}",
+                    record_type,
+                    existing.name,
+                    content
                 );
                 return Ok(existing);
             }
             tracing::info!(
-                "Updating DNS record {} from {} to {}",
+                "Updating {} record {} from {} to {}",
+                record_type,
                 existing.name,
                 existing.content,
-                ip
+                content
             );
-            return self.update_record(&existing.id, ip).await;
+            return self
+                .update_typed_record(&existing.id, content, options)
+                .await;
         }
 
         tracing::info!(
-            "Creating DNS record: {}.{} -> {}",
+            "Creating {} record: {}.{} -> {}",
+            record_type,
             subdomain,
             self.domain,
-            ip
+            content
         );
-        self.create_record(subdomain, ip).await
+        self.create_typed_record(record_type, subdomain, content, options)
+            .await
     }
 
-    /// Remove a DNS record if it exists
-    pub async fn remove_record(&self, subdomain: &str) -> Result<()> {
-        if let Some(record) = self.find_record(subdomain).await? {
-            tracing::info!("Deleting DNS record: {}", record.name);
+    /// Ensure a DNS A record exists with the specified IP (create or update)
+    pub async fn ensure_record(
+        &self,
+        subdomain: &str,
+        ip: &str,
+        options: &RecordOptions,
+    ) -> Result<DnsRecordInfo> {
+        self.ensure_typed_record(RecordType::A, subdomain, ip, options)
+            .await
+    }
+
+    /// Remove all DNS records of a type for a subdomain
+    pub async fn remove_typed_record(
+        &self,
+        record_type: RecordType,
+        subdomain: &str,
+    ) -> Result<()> {
+        let records = self.find_records(record_type, subdomain).await?;
+        if records.is_empty() {
+            tracing::debug!(
+                "{} record not found, nothing to delete: {}",
+                record_type,
+                subdomain
+            );
+        }
+        for record in records {
+            tracing::info!("Deleting {} record: {}", record_type, record.name);
             self.delete_record(&record.id).await?;
-        } else {
-            tracing::debug!("DNS record not found, nothing to delete: {}", subdomain);
         }
         Ok(())
     }
 
+    /// Remove a DNS record if it exists
+    pub async fn remove_record(&self, subdomain: &str) -> Result<()> {
+        self.remove_typed_record(RecordType::A, subdomain).await
+    }
+
     // ============ CNAME Record Management ============
 
     /// Find a CNAME record by subdomain
     pub async fn find_cname_record(&self, subdomain: &str) -> Result<Option<DnsRecordInfo>> {
-        let full_name = self.full_domain(subdomain);
-        let url = format!(
-            "{}/zones/{}/dns_records?type=CNAME&name={}",
-            CLOUDFLARE_API_BASE, self.zone_id, full_name
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .bearer_auth(&self.api_token)
-            .send()
-            .await?;
-
-        let api_response: ApiResponse<Vec<ApiDnsRecord>> = response.json().await?;
-
-        if !api_response.success {
-            let error_msg = api_response
-                .errors
-                .first()
-                .map(|e| e.message.clone())
-                .unwrap_or_else(|| "Unknown error".to_string());
-            return Err(CloudflareError::ApiError(error_msg));
-        }
-
-        Ok(api_response
-            .result
+        Ok(self
+            .find_records(RecordType::Cname, subdomain)
+            .await?
             .into_iter()
-            .next()
-            .map(|r| DnsRecordInfo {
-                id: r.id,
-                name: r.name,
-                record_type: r.r#type,
-                content: r.content,
-                ttl: Some(r.ttl),
-                proxied: r.proxied,
-            }))
+            .next())
     }
 
     /// Create a new CNAME record
@@ -351,44 +648,13 @@ impl CloudflareDns {
         subdomain: &str,
         target: &str,
     ) -> Result<DnsRecordInfo> {
-        let url = format!("{}/zones/{}/dns_records", CLOUDFLARE_API_BASE, self.zone_id);
-
-        let request_body = CreateDnsRecordRequest {
-            r#type: "CNAME".to_string(),
-            name: subdomain.to_string(),
-            content: target.to_string(),
-            ttl: 1, // Auto
-            proxied: false,
-        };
-
-        let response = self
-            .client
-            .post(&url)
-            .bearer_auth(&self.api_token)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        let api_response: ApiResponse<ApiDnsRecord> = response.json().await?;
-
-        if !api_response.success {
-            let error_msg = api_response
-                .errors
-                .first()
-                .map(|e| e.message.clone())
-                .unwrap_or_else(|| "Unknown error".to_string());
-            return Err(CloudflareError::ApiError(error_msg));
-        }
-
-        let r = api_response.result;
-        Ok(DnsRecordInfo {
-            id: r.id,
-            name: r.name,
-            record_type: r.r#type,
-            content: r.content,
-            ttl: Some(r.ttl),
-            proxied: r.proxied,
-        })
+        self.create_typed_record(
+            RecordType::Cname,
+            subdomain,
+            target,
+            &RecordOptions::default(),
+        )
+        .await
     }
 
     /// Update an existing CNAME record
@@ -397,43 +663,7 @@ impl CloudflareDns {
         record_id: &str,
         target: &str,
     ) -> Result<DnsRecordInfo> {
-        let url = format!(
-            "{}/zones/{}/dns_records/{}",
-            CLOUDFLARE_API_BASE, self.zone_id, record_id
-        );
-
-        let request_body = UpdateDnsRecordRequest {
-            content: target.to_string(),
-        };
-
-        let response = self
-            .client
-            .patch(&url)
-            .bearer_auth(&self.api_token)
-            .json(&request_body)
-            .send()
-            .await?;
-
-        let api_response: ApiResponse<ApiDnsRecord> = response.json().await?;
-
-        if !api_response.success {
-            let error_msg = api_response
-                .errors
-                .first()
-                .map(|e| e.message.clone())
-                .unwrap_or_else(|| "Unknown error".to_string());
-            return Err(CloudflareError::ApiError(error_msg));
-        }
-
-        let r = api_response.result;
-        Ok(DnsRecordInfo {
-            id: r.id,
-            name: r.name,
-            record_type: r.r#type,
-            content: r.content,
-            ttl: Some(r.ttl),
-            proxied: r.proxied,
-        })
+        self.update_record(record_id, target).await
     }
 
     /// Ensure a CNAME record exists with the specified target (create or update)
@@ -442,61 +672,139 @@ impl CloudflareDns {
         &self,
         subdomain: &str,
         target: &str,
+        options: &RecordOptions,
     ) -> Result<DnsRecordInfo> {
-        if let Some(existing) = self.find_cname_record(subdomain).await? {
-            if existing.content == target {
-                tracing::debug!(
-                    "CNAME record already exists with correct target: {} -> {}",
+        self.ensure_typed_record(RecordType::Cname, subdomain, target, options)
+            .await
+    }
+
+    /// Remove a CNAME record if it exists
+    pub async fn remove_cname_record(&self, subdomain: &str) -> Result<()> {
+        self.remove_typed_record(RecordType::Cname, subdomain).await
+    }
+}
+
+fn to_cloud_error(e: CloudflareError) -> fleetflow_cloud::CloudError {
+    fleetflow_cloud::CloudError::ApiError(e.to_string())
+}
+
+impl DnsProvider for CloudflareDns {
+    fn provider_name(&self) -> &str {
+        "cloudflare"
+    }
+
+    fn zone_domain(&self) -> &str {
+        &self.domain
+    }
+
+    async fn list_dns_records(&self) -> fleetflow_cloud::Result<Vec<DnsRecordSpec>> {
+        self.list_all_records().await.map_err(to_cloud_error)
+    }
+
+    async fn upsert_dns_record(
+        &self,
+        request: &DnsRecordRequest,
+    ) -> fleetflow_cloud::Result<DnsRecordSpec> {
+        let record_type: RecordType = request.record_type.parse().map_err(to_cloud_error)?;
+        let options = RecordOptions {
+            ttl: request.ttl.unwrap_or(AUTO_TTL),
+            proxied: request.proxied,
+        };
+        let existing = self
+            .find_records(record_type, &request.name)
+            .await
+            .map_err(to_cloud_error)?;
+        // TXT は同じ名前に複数の値を持てるので、値が同じものだけを更新する
+        let existing = match record_type {
+            RecordType::Txt => existing.into_iter().find(|r| r.content == request.content),
+            _ => existing.into_iter().next(),
+        };
+
+        let result = match existing {
+            Some(existing) => {
+                tracing::info!(
+                    "Updating {} record {} to {}",
+                    record_type,
                     existing.name,
-                    target
+                    request.content
                 );
-                return Ok(existing);
+                self.patch_record(
+                    &existing.id,
+                    &UpdateDnsRecordRequest {
+                        content: request.content.clone(),
+                        ttl: Some(options.ttl),
+                        proxied: Some(options.proxied),
+                        comment: request.comment.clone(),
+                    },
+                )
+                .await
+            }
+            None => {
+                tracing::info!(
+                    "Creating {} record: {}.{} -> {}",
+                    record_type,
+                    request.name,
+                    self.domain,
+                    request.content
+                );
+                self.post_record(&CreateDnsRecordRequest {
+                    r#type: record_type.to_string(),
+                    name: request.name.clone(),
+                    content: request.content.clone(),
+                    ttl: options.ttl,
+                    proxied: options.proxied,
+                    comment: request.comment.clone(),
+                })
+                .await
             }
-            tracing::info!(
-                "Updating CNAME record {} from {} to {}",
-                existing.name,
-                existing.content,
-                target
-            );
-            return self.update_cname_record(&existing.id, target).await;
         }
-
-        tracing::info!(
-            "Creating CNAME record: {}.{} -> {}",
-            subdomain,
-            self.domain,
-            target
-        );
-        self.create_cname_record(subdomain, target).await
+        .map_err(to_cloud_error)?;
+
+        Ok(DnsRecordSpec {
+            id: result.id,
+            name: result.name,
+            record_type: result.record_type,
+            content: result.content,
+            ttl: result.ttl,
+            proxied: result.proxied,
+            comment: request.comment.clone(),
+        })
     }
 
-    /// Remove a CNAME record if it exists
-    pub async fn remove_cname_record(&self, subdomain: &str) -> Result<()> {
-        if let Some(record) = self.find_cname_record(subdomain).await? {
-            tracing::info!("Deleting CNAME record: {}", record.name);
-            self.delete_record(&record.id).await?;
-        } else {
-            tracing::debug!("CNAME record not found, nothing to delete: {}", subdomain);
-        }
-        Ok(())
+    async fn delete_dns_record(&self, record_id: &str) -> fleetflow_cloud::Result<()> {
+        self.delete_record(record_id).await.map_err(to_cloud_error)
     }
 }
 
 // ============ API Types ============
 
 #[derive(Debug, Deserialize)]
-struct ApiResponse<T> {
-    success: bool,
-    result: T,
+pub(crate) struct ApiResponse<T> {
+    pub(crate) success: bool,
+    pub(crate) result: T,
+    #[serde(default)]
+    pub(crate) errors: Vec<ApiError>,
+    #[serde(default)]
+    result_info: Option<ResultInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultInfo {
     #[serde(default)]
-    errors: Vec<ApiError>,
+    total_pages: u32,
 }
 
 #[derive(Debug, Deserialize)]
-struct ApiError {
+struct ApiZone {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApiError {
     #[allow(dead_code)]
     code: i32,
-    message: String,
+    pub(crate) message: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -508,6 +816,8 @@ struct ApiDnsRecord {
     content: String,
     ttl: u32,
     proxied: bool,
+    #[serde(default)]
+    comment: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -518,11 +828,44 @@ struct CreateDnsRecordRequest {
     content: String,
     ttl: u32,
     proxied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+fn to_record_spec(r: ApiDnsRecord) -> DnsRecordSpec {
+    DnsRecordSpec {
+        id: r.id,
+        name: r.name,
+        record_type: r.r#type,
+        content: r.content,
+        ttl: Some(r.ttl),
+        proxied: r.proxied,
+        comment: r.comment,
+    }
+}
+
+impl From<ApiDnsRecord> for DnsRecordInfo {
+    fn from(r: ApiDnsRecord) -> Self {
+        DnsRecordInfo {
+            id: r.id,
+            name: r.name,
+            record_type: r.r#type,
+            content: r.content,
+            ttl: Some(r.ttl),
+            proxied: r.proxied,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 struct UpdateDnsRecordRequest {
     content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxied: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -540,12 +883,93 @@ mod tests {
             api_token: "test-token".to_string(),
             zone_id: "test-zone".to_string(),
             domain: domain.to_string(),
+            subdomain_template: None,
         };
         CloudflareDns::new(config)
     }
 
+    #[test]
+    fn test_record_type_and_options() {
+        assert_eq!("aaaa".parse::<RecordType>().unwrap(), RecordType::Aaaa);
+        assert_eq!(RecordType::Txt.to_string(), "TXT");
+        assert!("MX".parse::<RecordType>().is_err());
+
+        let record = DnsRecordInfo {
+            id: "1".to_string(),
+            name: "app.example.com".to_string(),
+            record_type: "A".to_string(),
+            content: "203.0.113.1".to_string(),
+            ttl: Some(1),
+            proxied: true,
+        };
+        let proxied = RecordOptions {
+            ttl: 300,
+            proxied: true,
+        };
+        assert!(proxied.matches(&record));
+        assert!(!RecordOptions::default().matches(&record));
+        assert!(
+            !RecordOptions {
+                ttl: 300,
+                proxied: false
+            }
+            .matches(&DnsRecordInfo {
+                proxied: false,
+                ..record
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_subdomain_template() {
+        assert_eq!(
+            render_subdomain(Some("{stage}.{service}"), "api", "prod"),
+            "prod.api"
+        );
+        assert_eq!(
+            render_subdomain(None, "creo-api-server", "prod"),
+            "api-prod"
+        );
+    }
+
+    #[test]
+    fn test_dns_zones_select_longest_match() {
+        let zones = DnsZones::new(vec![
+            test_dns("example.com"),
+            test_dns("internal.example.com"),
+        ]);
+        assert_eq!(
+            zones
+                .zone_for("app.internal.example.com")
+                .map(|z| z.domain()),
+            Some("internal.example.com")
+        );
+        assert_eq!(
+            zones.zone_for("web.example.com").map(|z| z.domain()),
+            Some("example.com")
+        );
+        assert!(zones.zone_for("web.example.net").is_none());
+        assert!(zones.zone_for("notexample.com").is_none());
+        assert_eq!(
+            test_dns("example.com").relative_name("a.b.example.com"),
+            Some("a.b".to_string())
+        );
+    }
+
     // ---- generate_subdomain tests ----
 
+    #[test]
+    fn test_service_url() {
+        assert_eq!(
+            service_url("example.com", "creo-api-server", "prod"),
+            "https://api-prod.example.com"
+        );
+        assert_eq!(
+            service_url("example.com", "web", "stg"),
+            "https://web-stg.example.com"
+        );
+    }
+
     #[test]
     fn test_generate_subdomain() {
         let dns = test_dns("example.com");
@@ -625,6 +1049,19 @@ mod tests {
         assert!(err.to_string().contains("CLOUDFLARE_API_TOKEN"));
     }
 
+    #[test]
+    fn test_dns_config_with_overrides() {
+        let config = DnsConfig::with_overrides(
+            Some("token".to_string()),
+            Some("zone".to_string()),
+            Some("example.com".to_string()),
+        )
+        .unwrap();
+        assert_eq!(config.api_token, "token");
+        assert_eq!(config.zone_id, "zone");
+        assert_eq!(config.domain, "example.com");
+    }
+
     // ---- API types serde tests ----
 
     #[test]
@@ -635,6 +1072,7 @@ mod tests {
             content: "203.0.113.1".to_string(),
             ttl: 1,
             proxied: false,
+            comment: None,
         };
 
         let json = serde_json::to_value(&req).unwrap();
@@ -649,9 +1087,15 @@ mod tests {
     fn test_update_dns_record_request_serialize() {
         let req = UpdateDnsRecordRequest {
             content: "10.0.0.1".to_string(),
+            ttl: None,
+            proxied: None,
+            comment: None,
         };
         let json = serde_json::to_value(&req).unwrap();
         assert_eq!(json["content"], "10.0.0.1");
+        // 未指定の ttl / proxied は送らず既存の設定を残す
+        assert!(json.get("ttl").is_none());
+        assert!(json.get("proxied").is_none());
     }
 
     #[test]
@@ -674,6 +1118,22 @@ mod tests {
         assert!(!record.proxied);
     }
 
+    #[test]
+    fn test_api_dns_record_to_spec_keeps_comment() {
+        let json = r#"{
+            "id": "rec-1",
+            "name": "api.example.com",
+            "type": "TXT",
+            "content": "verify=abc",
+            "ttl": 3600,
+            "proxied": false,
+            "comment": "fleetflow:myapp:web-01"
+        }"#;
+        let spec = to_record_spec(serde_json::from_str(json).unwrap());
+        assert_eq!(spec.record_type, "TXT");
+        assert_eq!(spec.managed_by(), Some(("myapp", Some("web-01"))));
+    }
+
     #[test]
     fn test_api_response_success() {
         let json = r#"{
@@ -6,12 +6,15 @@
 //! # Features
 //!
 //! - R2 bucket management (create, delete, list)
-//! - Worker deployment (planned)
+//! - Worker script upload and routes
+//! - R2 object upload/download via the S3-compatible API (`R2_ACCESS_KEY_ID`, `R2_SECRET_ACCESS_KEY`)
 //! - DNS record management via Cloudflare API
 //!
 //! # Requirements
 //!
-//! - `wrangler` CLI must be installed and configured (for R2/Workers)
+//! - For R2/Workers: `CLOUDFLARE_API_TOKEN` and `CLOUDFLARE_ACCOUNT_ID` (or the provider's
+//!   account ID) to call the API directly; `CLOUDFLARE_ZONE_ID` for Worker routes.
+//!   Without a token, falls back to the `wrangler` CLI
 //! - For DNS: `CLOUDFLARE_API_TOKEN`, `CLOUDFLARE_ZONE_ID`, `CLOUDFLARE_DOMAIN` env vars
 //!
 //! # Example
@@ -35,26 +38,38 @@
 //! # DNS Management
 //!
 //! ```ignore
-//! use fleetflow_cloud_cloudflare::dns::{CloudflareDns, DnsConfig};
+//! use fleetflow_cloud_cloudflare::dns::{CloudflareDns, DnsConfig, RecordOptions, RecordType};
 //!
 //! let config = DnsConfig::from_env()?;
 //! let dns = CloudflareDns::new(config);
 //!
 //! // Ensure a DNS record exists
-//! let record = dns.ensure_record("mcp-prod", "203.0.113.1").await?;
+//! let record = dns
+//!     .ensure_record("mcp-prod", "203.0.113.1", &RecordOptions::default())
+//!     .await?;
+//!
+//! // Proxied record with a TXT verification entry
+//! let proxied = RecordOptions { ttl: 1, proxied: true };
+//! dns.ensure_record("www", "203.0.113.1", &proxied).await?;
+//! dns.ensure_typed_record(RecordType::Txt, "_verify", "token=abc", &RecordOptions::default())
+//!     .await?;
 //!
 //! // Remove a DNS record
 //! dns.remove_record("mcp-prod").await?;
 //! ```
 
+pub mod api;
 pub mod dns;
 pub mod error;
 pub mod provider;
+pub mod r2;
 pub mod wrangler;
 
-pub use dns::{CloudflareDns, DnsConfig};
+pub use api::CloudflareApi;
+pub use dns::{CloudflareDns, DnsConfig, DnsZones, RecordOptions, RecordType};
 pub use error::{CloudflareError, Result};
 pub use provider::CloudflareProvider;
+pub use r2::{R2Client, R2Config, R2Object};
 pub use wrangler::{
     DnsRecordInfo, PagesDeployResult, R2BucketInfo, WorkerConfig, WorkerInfo, Wrangler,
 };
@@ -1,20 +1,28 @@
 //! Cloudflare provider implementation
 //!
 //! DNS record management via Cloudflare API.
-//! R2 bucket management via wrangler CLI.
+//! R2 buckets and Workers via Cloudflare API when `CLOUDFLARE_API_TOKEN` is set,
+//! otherwise via wrangler CLI.
 
-use crate::dns::{CloudflareDns, DnsConfig};
+use crate::api::CloudflareApi;
+use crate::dns::{AUTO_TTL, CloudflareDns, DnsConfig, RecordOptions, RecordType};
 use crate::error::CloudflareError;
-use crate::wrangler::Wrangler;
+use crate::wrangler::{R2BucketInfo, WorkerConfig, WorkerInfo, Wrangler};
 use async_trait::async_trait;
 use fleetflow_cloud::{
     Action, ActionType, ApplyResult, AuthStatus, CloudProvider, Plan, ProviderState, ResourceSet,
     ResourceState, ResourceStatus,
 };
+use std::collections::HashMap;
+
+/// dns-record リソースから apply に引き継ぐ設定
+const DNS_RECORD_KEYS: &[&str] = &["ip", "target", "content", "ttl", "proxied"];
 
 /// Cloudflare provider
 pub struct CloudflareProvider {
     wrangler: Wrangler,
+    /// API クライアント（トークンとアカウント ID がなければ wrangler にフォールバック）
+    api: Option<CloudflareApi>,
     #[allow(dead_code)]
     account_id: Option<String>,
 }
@@ -23,10 +31,48 @@ impl CloudflareProvider {
     pub fn new(account_id: Option<String>) -> Self {
         Self {
             wrangler: Wrangler::new(account_id.clone()),
+            api: CloudflareApi::from_env(account_id.clone()).ok(),
             account_id,
         }
     }
 
+    /// API 直接呼び出しを使うか（false なら wrangler）
+    pub fn uses_api(&self) -> bool {
+        self.api.is_some()
+    }
+
+    async fn list_r2_buckets(&self) -> Result<Vec<R2BucketInfo>, CloudflareError> {
+        match &self.api {
+            Some(api) => api.list_r2_buckets().await,
+            None => self.wrangler.list_r2_buckets().await,
+        }
+    }
+
+    async fn create_r2_bucket(&self, name: &str) -> Result<R2BucketInfo, CloudflareError> {
+        match &self.api {
+            Some(api) => api.create_r2_bucket(name).await,
+            None => self.wrangler.create_r2_bucket(name).await,
+        }
+    }
+
+    async fn delete_r2_bucket(&self, name: &str) -> Result<(), CloudflareError> {
+        match &self.api {
+            Some(api) => api.delete_r2_bucket(name).await,
+            None => self.wrangler.delete_r2_bucket(name).await,
+        }
+    }
+
+    /// Worker スクリプトをデプロイする（API 経由、なければ wrangler）
+    pub async fn deploy_worker(
+        &self,
+        config: &WorkerConfig,
+    ) -> Result<WorkerInfo, CloudflareError> {
+        match &self.api {
+            Some(api) => api.deploy_worker(config).await,
+            None => self.wrangler.deploy_worker(config).await,
+        }
+    }
+
     /// DNS クライアントを環境変数から生成（必要時のみ）
     fn create_dns_client() -> Result<CloudflareDns, CloudflareError> {
         let config = DnsConfig::from_env()?;
@@ -34,6 +80,20 @@ impl CloudflareProvider {
     }
 }
 
+/// アクション詳細の ttl / proxied からレコードオプションを作る（未指定は TTL 自動・プロキシなし）
+fn record_options(details: &HashMap<String, serde_json::Value>) -> RecordOptions {
+    RecordOptions {
+        ttl: details
+            .get("ttl")
+            .and_then(|v| v.as_u64())
+            .map_or(AUTO_TTL, |ttl| ttl as u32),
+        proxied: details
+            .get("proxied")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    }
+}
+
 #[async_trait]
 impl CloudProvider for CloudflareProvider {
     fn name(&self) -> &str {
@@ -45,6 +105,16 @@ impl CloudProvider for CloudflareProvider {
     }
 
     async fn check_auth(&self) -> fleetflow_cloud::Result<AuthStatus> {
+        if let Some(api) = &self.api {
+            return match api.verify_token().await {
+                Ok(true) => Ok(AuthStatus::ok(api.account_id().to_string())),
+                Ok(false) => Ok(AuthStatus::failed(
+                    "CLOUDFLARE_API_TOKEN が有効ではありません",
+                )),
+                Err(e) => Ok(AuthStatus::failed(e.to_string())),
+            };
+        }
+
         match self.wrangler.check_auth().await {
             Ok(auth) => {
                 if auth.authenticated {
@@ -64,6 +134,20 @@ impl CloudProvider for CloudflareProvider {
     async fn get_state(&self) -> fleetflow_cloud::Result<ProviderState> {
         let mut state = ProviderState::new();
 
+        // R2 バケット状態を取得（API 利用時）
+        if let Some(api) = &self.api
+            && let Ok(buckets) = api.list_r2_buckets().await
+        {
+            for bucket in buckets {
+                let mut resource = ResourceState::new(&bucket.name, "r2-bucket")
+                    .with_status(ResourceStatus::Running);
+                if let Some(created_at) = &bucket.created_at {
+                    resource = resource.with_attribute("created_at", serde_json::json!(created_at));
+                }
+                state.add(bucket.name, resource);
+            }
+        }
+
         // DNS レコード状態を取得
         if let Ok(dns) = Self::create_dns_client()
             && let Ok(records) = dns.list_records().await
@@ -154,6 +238,9 @@ impl CloudProvider for CloudflareProvider {
                             ("hostname".to_string(), serde_json::json!(hostname)),
                         ]
                         .into_iter()
+                        .chain(DNS_RECORD_KEYS.iter().filter_map(|key| {
+                            Some((key.to_string(), resource.config.get(*key)?.clone()))
+                        }))
                         .collect(),
                     });
                 }
@@ -182,7 +269,7 @@ impl CloudProvider for CloudflareProvider {
                 ActionType::Create => match action.resource_type.as_str() {
                     "r2-bucket" => {
                         tracing::info!("Creating R2 bucket: {}", action.resource_id);
-                        match self.wrangler.create_r2_bucket(&action.resource_id).await {
+                        match self.create_r2_bucket(&action.resource_id).await {
                             Ok(_bucket) => {
                                 result.add_success(
                                     action.id.clone(),
@@ -206,6 +293,8 @@ impl CloudProvider for CloudflareProvider {
                             .and_then(|v| v.as_str())
                             .unwrap_or("A");
 
+                        let options = record_options(&action.details);
+
                         match Self::create_dns_client() {
                             Ok(dns) => {
                                 let dns_result = match record_type {
@@ -216,7 +305,24 @@ impl CloudProvider for CloudflareProvider {
                                             .and_then(|v| v.as_str())
                                             .unwrap_or("");
                                         let target_fqdn = dns.full_domain(target);
-                                        dns.ensure_cname_record(hostname, &target_fqdn).await
+                                        dns.ensure_cname_record(hostname, &target_fqdn, &options)
+                                            .await
+                                    }
+                                    "AAAA" | "TXT" => {
+                                        let content = action
+                                            .details
+                                            .get("content")
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("");
+                                        match record_type.parse::<RecordType>() {
+                                            Ok(cf_type) => {
+                                                dns.ensure_typed_record(
+                                                    cf_type, hostname, content, &options,
+                                                )
+                                                .await
+                                            }
+                                            Err(e) => Err(e),
+                                        }
                                     }
                                     _ => {
                                         // A レコード: IP は details["ip"] から取得
@@ -236,7 +342,7 @@ impl CloudProvider for CloudflareProvider {
                                             );
                                             continue;
                                         }
-                                        dns.ensure_record(hostname, ip).await
+                                        dns.ensure_record(hostname, ip, &options).await
                                     }
                                 };
 
@@ -274,7 +380,7 @@ impl CloudProvider for CloudflareProvider {
                 ActionType::Delete => match action.resource_type.as_str() {
                     "r2-bucket" => {
                         tracing::info!("Deleting R2 bucket: {}", action.resource_id);
-                        match self.wrangler.delete_r2_bucket(&action.resource_id).await {
+                        match self.delete_r2_bucket(&action.resource_id).await {
                             Ok(()) => {
                                 result.add_success(
                                     action.id.clone(),
@@ -300,9 +406,9 @@ impl CloudProvider for CloudflareProvider {
 
                         match Self::create_dns_client() {
                             Ok(dns) => {
-                                let dns_result = match record_type {
-                                    "CNAME" => dns.remove_cname_record(hostname).await,
-                                    _ => dns.remove_record(hostname).await,
+                                let dns_result = match record_type.parse::<RecordType>() {
+                                    Ok(cf_type) => dns.remove_typed_record(cf_type, hostname).await,
+                                    Err(e) => Err(e),
                                 };
 
                                 match dns_result {
@@ -371,8 +477,7 @@ impl CloudProvider for CloudflareProvider {
             }
         } else {
             // R2 バケット削除
-            self.wrangler
-                .delete_r2_bucket(resource_id)
+            self.delete_r2_bucket(resource_id)
                 .await
                 .map_err(|e| fleetflow_cloud::CloudError::ApiError(e.to_string()))?;
         }
@@ -386,13 +491,12 @@ impl CloudProvider for CloudflareProvider {
 
         // Delete all R2 buckets
         let buckets = self
-            .wrangler
             .list_r2_buckets()
             .await
             .map_err(|e| fleetflow_cloud::CloudError::ApiError(e.to_string()))?;
 
         for bucket in buckets {
-            match self.wrangler.delete_r2_bucket(&bucket.name).await {
+            match self.delete_r2_bucket(&bucket.name).await {
                 Ok(()) => {
                     result.add_success(
                         format!("delete-{}", bucket.name),
@@ -424,6 +528,24 @@ mod tests {
         assert_eq!(provider.display_name(), "Cloudflare");
     }
 
+    #[test]
+    fn test_record_options_from_details() {
+        let details: HashMap<String, serde_json::Value> = [
+            ("ttl".to_string(), serde_json::json!(300)),
+            ("proxied".to_string(), serde_json::json!(true)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            record_options(&details),
+            RecordOptions {
+                ttl: 300,
+                proxied: true
+            }
+        );
+        assert_eq!(record_options(&HashMap::new()), RecordOptions::default());
+    }
+
     #[test]
     fn test_provider_new_without_account_id() {
         let provider = CloudflareProvider::new(None);
@@ -0,0 +1,598 @@
+//! R2 object storage client (S3-compatible API)
+//!
+//! Object operations go through R2's S3-compatible endpoint
+//! (`https://<account_id>.r2.cloudflarestorage.com`) with AWS Signature V4,
+//! using an R2 API token's access key pair. Any S3-compatible endpoint can be
+//! used by setting `R2_ENDPOINT`.
+//!
+//! Large objects are uploaded in parallel parts. ETags are compared with
+//! [`local_etag`] to skip unchanged files.
+
+use crate::error::{CloudflareError, Result};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Objects larger than this are uploaded with multipart upload
+pub const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Parts uploaded concurrently per object
+const PART_CONCURRENCY: usize = 4;
+
+const REGION: &str = "auto";
+const SERVICE: &str = "s3";
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// R2 access credentials
+#[derive(Debug, Clone)]
+pub struct R2Config {
+    /// S3-compatible endpoint (e.g., `https://<account_id>.r2.cloudflarestorage.com`)
+    pub endpoint: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl R2Config {
+    /// Load from `R2_ACCESS_KEY_ID`, `R2_SECRET_ACCESS_KEY` and either `R2_ENDPOINT`
+    /// or the account ID (`account_id` or `CLOUDFLARE_ACCOUNT_ID`)
+    pub fn from_env(account_id: Option<String>) -> Result<Self> {
+        let access_key_id = std::env::var("R2_ACCESS_KEY_ID")
+            .map_err(|_| CloudflareError::MissingEnvVar("R2_ACCESS_KEY_ID".to_string()))?;
+        let secret_access_key = std::env::var("R2_SECRET_ACCESS_KEY")
+            .map_err(|_| CloudflareError::MissingEnvVar("R2_SECRET_ACCESS_KEY".to_string()))?;
+
+        let endpoint = match std::env::var("R2_ENDPOINT") {
+            Ok(endpoint) => endpoint,
+            Err(_) => {
+                let account_id = match account_id {
+                    Some(id) => id,
+                    None => std::env::var("CLOUDFLARE_ACCOUNT_ID").map_err(|_| {
+                        CloudflareError::MissingEnvVar("CLOUDFLARE_ACCOUNT_ID".to_string())
+                    })?,
+                };
+                format!("https://{}.r2.cloudflarestorage.com", account_id)
+            }
+        };
+
+        Ok(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            access_key_id,
+            secret_access_key,
+        })
+    }
+}
+
+/// Object in a bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R2Object {
+    pub key: String,
+    pub size: u64,
+    /// ETag without quotes
+    pub etag: String,
+}
+
+/// R2 object storage client
+#[derive(Clone)]
+pub struct R2Client {
+    client: reqwest::Client,
+    config: R2Config,
+}
+
+impl R2Client {
+    pub fn new(config: R2Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    pub fn from_env(account_id: Option<String>) -> Result<Self> {
+        Ok(Self::new(R2Config::from_env(account_id)?))
+    }
+
+    /// List all objects under a prefix
+    pub async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<R2Object>> {
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = self
+                .send(reqwest::Method::GET, bucket, None, &query, Vec::new(), None)
+                .await?;
+            let body = response.text().await?;
+            objects.extend(parse_list_objects(&body));
+
+            token = xml_value(&body, "NextContinuationToken").map(|t| xml_unescape(&t));
+            if token.is_none() {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Upload an object, using multipart upload above [`MULTIPART_PART_SIZE`]
+    ///
+    /// Returns the ETag reported by R2.
+    pub async fn upload_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String> {
+        if data.len() > MULTIPART_PART_SIZE {
+            self.multipart_upload(bucket, key, data, content_type).await
+        } else {
+            self.put_object(bucket, key, data, content_type).await
+        }
+    }
+
+    /// Upload an object in a single request
+    pub async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String> {
+        let response = self
+            .send(
+                reqwest::Method::PUT,
+                bucket,
+                Some(key),
+                &[],
+                data,
+                Some(content_type),
+            )
+            .await?;
+        Ok(etag_header(&response))
+    }
+
+    async fn multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String> {
+        let response = self
+            .send(
+                reqwest::Method::POST,
+                bucket,
+                Some(key),
+                &[("uploads", "")],
+                Vec::new(),
+                Some(content_type),
+            )
+            .await?;
+        let body = response.text().await?;
+        let upload_id = xml_value(&body, "UploadId").ok_or_else(|| {
+            CloudflareError::ApiError(format!("No UploadId for multipart upload of {}", key))
+        })?;
+
+        tracing::debug!(
+            key,
+            parts = data.len().div_ceil(MULTIPART_PART_SIZE),
+            "Multipart upload"
+        );
+        let parts: Result<Vec<(usize, String)>> = stream::iter(
+            data.chunks(MULTIPART_PART_SIZE)
+                .enumerate()
+                .map(|(i, chunk)| (i + 1, chunk.to_vec())),
+        )
+        .map(|(number, chunk)| {
+            let upload_id = upload_id.as_str();
+            async move {
+                let part_number = number.to_string();
+                let response = self
+                    .send(
+                        reqwest::Method::PUT,
+                        bucket,
+                        Some(key),
+                        &[
+                            ("partNumber", part_number.as_str()),
+                            ("uploadId", upload_id),
+                        ],
+                        chunk,
+                        None,
+                    )
+                    .await?;
+                Ok((number, etag_header(&response)))
+            }
+        })
+        .buffer_unordered(PART_CONCURRENCY)
+        .try_collect()
+        .await;
+
+        let mut parts = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                // Abort so the uploaded parts don't linger in the bucket
+                let _ = self
+                    .send(
+                        reqwest::Method::DELETE,
+                        bucket,
+                        Some(key),
+                        &[("uploadId", upload_id.as_str())],
+                        Vec::new(),
+                        None,
+                    )
+                    .await;
+                return Err(e);
+            }
+        };
+        parts.sort_by_key(|(number, _)| *number);
+
+        let response = self
+            .send(
+                reqwest::Method::POST,
+                bucket,
+                Some(key),
+                &[("uploadId", upload_id.as_str())],
+                complete_multipart_body(&parts).into_bytes(),
+                Some("application/xml"),
+            )
+            .await?;
+        let body = response.text().await?;
+        Ok(xml_value(&body, "ETag")
+            .map(|etag| xml_unescape(&etag).trim_matches('"').to_string())
+            .unwrap_or_default())
+    }
+
+    /// Download an object
+    pub async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>> {
+        let response = self
+            .send(
+                reqwest::Method::GET,
+                bucket,
+                Some(key),
+                &[],
+                Vec::new(),
+                None,
+            )
+            .await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Delete an object
+    pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<()> {
+        self.send(
+            reqwest::Method::DELETE,
+            bucket,
+            Some(key),
+            &[],
+            Vec::new(),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Send a signed request, turning non-2xx responses into errors
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        bucket: &str,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let path = match key {
+            Some(key) => format!("/{}/{}", uri_encode(bucket, true), uri_encode(key, false)),
+            None => format!("/{}", uri_encode(bucket, true)),
+        };
+        let query = canonical_query(query);
+        let payload_hash = format!("{:x}", Sha256::digest(&body));
+        let now = Utc::now();
+        let authorization = self.authorization(method.as_str(), &path, &query, &payload_hash, now);
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.config.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.config.endpoint, path, query)
+        };
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date(now))
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("authorization", authorization)
+            .body(body);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+
+        let response = request.send().await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let message = xml_value(&body, "Message")
+            .or_else(|| xml_value(&body, "Code"))
+            .unwrap_or_else(|| status.to_string());
+        Err(CloudflareError::ApiError(format!(
+            "R2 {}{}: {}",
+            bucket,
+            key.map(|k| format!("/{}", k)).unwrap_or_default(),
+            message
+        )))
+    }
+
+    /// `Authorization` header value (AWS Signature V4)
+    fn authorization(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        payload_hash: &str,
+        now: DateTime<Utc>,
+    ) -> String {
+        let host = self
+            .config
+            .endpoint
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let amz_date = amz_date(now);
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/{}/aws4_request", date, REGION, SERVICE);
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, query, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date,
+            scope,
+            Sha256::digest(canonical_request.as_bytes())
+        );
+        let key = signing_key(&self.config.secret_access_key, &date, REGION, SERVICE);
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id, scope, SIGNED_HEADERS, signature
+        )
+    }
+}
+
+/// ETag R2 assigns to this content when uploaded with [`R2Client::upload_object`]
+///
+/// Single-part objects use the MD5 of the content; multipart objects use the MD5
+/// of the concatenated part digests with a `-<parts>` suffix.
+pub fn local_etag(data: &[u8]) -> String {
+    if data.len() <= MULTIPART_PART_SIZE {
+        return format!("{:x}", md5::compute(data));
+    }
+    let mut digests = Vec::new();
+    let mut parts = 0;
+    for chunk in data.chunks(MULTIPART_PART_SIZE) {
+        digests.extend_from_slice(&md5::compute(chunk).0);
+        parts += 1;
+    }
+    format!("{:x}-{}", md5::compute(&digests), parts)
+}
+
+/// Content-Type for a file name, by extension
+pub fn content_type_for(path: &str) -> &'static str {
+    let ext = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "application/javascript",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn amz_date(now: DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let k_region = hmac(&k_date, region.as_bytes());
+    let k_service = hmac(&k_region, service.as_bytes());
+    hmac(&k_service, b"aws4_request")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// URI-encode per SigV4 (`/` is kept in object keys)
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Canonical query string (sorted, URI-encoded)
+fn canonical_query(query: &[(&str, &str)]) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn etag_header(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .trim_matches('"')
+        .to_string()
+}
+
+fn complete_multipart_body(parts: &[(usize, String)]) -> String {
+    let parts: String = parts
+        .iter()
+        .map(|(number, etag)| {
+            format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>\"{}\"</ETag></Part>",
+                number, etag
+            )
+        })
+        .collect();
+    format!(
+        "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+        parts
+    )
+}
+
+/// First `<tag>value</tag>` in an XML document
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    Some(xml[start..end].to_string())
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Objects in a ListObjectsV2 response
+fn parse_list_objects(xml: &str) -> Vec<R2Object> {
+    xml.split("<Contents>")
+        .skip(1)
+        .filter_map(|block| {
+            let block = block.split("</Contents>").next()?;
+            Some(R2Object {
+                key: xml_unescape(&xml_value(block, "Key")?),
+                size: xml_value(block, "Size")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+                etag: xml_value(block, "ETag")
+                    .map(|e| xml_unescape(&e).trim_matches('"').to_string())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key() {
+        // AWS documentation example
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_uri_encode_and_query() {
+        assert_eq!(uri_encode("assets/app v2.js", false), "assets/app%20v2.js");
+        assert_eq!(uri_encode("a/b", true), "a%2Fb");
+        assert_eq!(
+            canonical_query(&[("uploadId", "x/y"), ("partNumber", "2")]),
+            "partNumber=2&uploadId=x%2Fy"
+        );
+        assert_eq!(canonical_query(&[("uploads", "")]), "uploads=");
+    }
+
+    #[test]
+    fn test_parse_list_objects() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult>
+  <Name>assets</Name>
+  <Contents><Key>site/index.html</Key><Size>120</Size><ETag>&quot;abc&quot;</ETag></Contents>
+  <Contents><Key>site/a&amp;b.css</Key><Size>7</Size><ETag>"def-2"</ETag></Contents>
+  <IsTruncated>true</IsTruncated>
+  <NextContinuationToken>tok</NextContinuationToken>
+</ListBucketResult>"#;
+        let objects = parse_list_objects(xml);
+        assert_eq!(
+            objects,
+            vec![
+                R2Object {
+                    key: "site/index.html".to_string(),
+                    size: 120,
+                    etag: "abc".to_string(),
+                },
+                R2Object {
+                    key: "site/a&b.css".to_string(),
+                    size: 7,
+                    etag: "def-2".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            xml_value(xml, "NextContinuationToken").as_deref(),
+            Some("tok")
+        );
+    }
+
+    #[test]
+    fn test_local_etag() {
+        assert_eq!(local_etag(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        let large = vec![0u8; MULTIPART_PART_SIZE + 1];
+        assert!(local_etag(&large).ends_with("-2"));
+        assert_eq!(content_type_for("index.HTML"), "text/html; charset=utf-8");
+        assert_eq!(content_type_for("LICENSE"), "application/octet-stream");
+    }
+}
@@ -3,6 +3,7 @@
 //! Wraps the wrangler CLI commands for Cloudflare operations.
 //! This is a skeleton implementation for future development.
 
+use crate::api::WORKER_COMPATIBILITY_DATE;
 use crate::error::{CloudflareError, Result};
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
@@ -129,11 +130,37 @@ impl Wrangler {
     }
 
     /// Deploy a Worker
-    pub async fn deploy_worker(&self, _config: &WorkerConfig) -> Result<WorkerInfo> {
-        // TODO: Implement when needed
-        Err(CloudflareError::CommandFailed(
-            "Worker deployment not yet implemented".to_string(),
-        ))
+    ///
+    /// `wrangler deploy <script> --name <name> [--route <pattern>] [--var KEY:VALUE]`
+    pub async fn deploy_worker(&self, config: &WorkerConfig) -> Result<WorkerInfo> {
+        let mut vars: Vec<String> = config
+            .vars
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect();
+        vars.sort();
+
+        let mut args = vec![
+            "deploy",
+            config.script_path.as_str(),
+            "--name",
+            config.name.as_str(),
+            "--compatibility-date",
+            WORKER_COMPATIBILITY_DATE,
+        ];
+        for route in &config.routes {
+            args.extend(["--route", route.as_str()]);
+        }
+        for var in &vars {
+            args.extend(["--var", var.as_str()]);
+        }
+        self.run_command(&args).await?;
+
+        Ok(WorkerInfo {
+            name: config.name.clone(),
+            created_at: None,
+            routes: config.routes.clone(),
+        })
     }
 
     /// Delete a Worker
@@ -38,6 +38,6 @@ pub mod startup_scripts;
 pub mod usacloud;
 
 pub use error::{Result, SakuraError};
-pub use provider::{CreateServerOptions, SakuraCloudProvider, SimpleServerInfo};
+pub use provider::{CreateServerOptions, SakuraCloudProvider, SimpleServerInfo, parse_plan};
 pub use startup_scripts::{get_builtin_script, is_builtin_script};
-pub use usacloud::{CreateServerConfig, NoteInfo, ServerInfo, SshKeyInfo, Usacloud};
+pub use usacloud::{CreateServerConfig, DiskInfo, NoteInfo, ServerInfo, SshKeyInfo, Usacloud};
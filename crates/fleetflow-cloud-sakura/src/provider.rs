@@ -13,7 +13,7 @@ use fleetflow_cloud::{
 };
 
 /// Parse plan string like "2core-4gb" to (core, memory_gb)
-fn parse_plan(plan: &Option<String>) -> (i32, i32) {
+pub fn parse_plan(plan: &Option<String>) -> (i32, i32) {
     if let Some(p) = plan {
         // Try to parse "NcoreN-Mgb" format
         let parts: Vec<&str> = p.split('-').collect();
@@ -251,7 +251,7 @@ impl SakuraCloudProvider {
             name: info.name.clone(),
             cpu: info.cpu,
             memory_gb: info.memory_mb.map(|mb| mb / 1024),
-            disk_gb: None, // usacloud server list ではディスク情報が含まれない
+            disk_gb: info.disk_gb(),
             status: if info.is_running() {
                 ServerStatus::Running
             } else {
@@ -798,6 +798,7 @@ mod tests {
                 ip_address: Some("203.0.113.10".to_string()),
             }]),
             tags: vec![],
+            disks: vec![],
         };
 
         let simple: SimpleServerInfo = server_info.into();
@@ -817,6 +818,7 @@ mod tests {
             instance_status: Some("down".to_string()),
             interfaces: None,
             tags: vec![],
+            disks: vec![],
         };
 
         let simple: SimpleServerInfo = server_info.into();
@@ -429,6 +429,9 @@ pub struct ServerInfo {
 
     #[serde(rename = "Tags", default)]
     pub tags: Vec<String>,
+
+    #[serde(rename = "Disks", default)]
+    pub disks: Vec<DiskInfo>,
 }
 
 impl ServerInfo {
@@ -449,6 +452,20 @@ impl ServerInfo {
     pub fn is_running(&self) -> bool {
         self.instance_status.as_deref() == Some("up")
     }
+
+    /// Size of the first (boot) disk in GB
+    pub fn disk_gb(&self) -> Option<i32> {
+        self.disks
+            .first()
+            .and_then(|d| d.size_mb)
+            .map(|mb| (mb / 1024) as i32)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskInfo {
+    #[serde(rename = "SizeMB")]
+    pub size_mb: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -568,6 +585,7 @@ mod tests {
                 ip_address: Some("192.168.1.1".to_string()),
             }]),
             tags: vec!["fleetflow:test:server".to_string()],
+            disks: vec![],
         };
 
         assert_eq!(server.ip_address(), Some("192.168.1.1".to_string()));
@@ -585,6 +603,7 @@ mod tests {
             instance_status: None,
             interfaces: None,
             tags: vec![],
+            disks: vec![],
         };
 
         assert_eq!(server.ip_address(), None);
@@ -601,6 +620,7 @@ mod tests {
             instance_status: Some("up".to_string()),
             interfaces: Some(vec![]),
             tags: vec![],
+            disks: vec![],
         };
 
         assert_eq!(server.ip_address(), None);
@@ -617,6 +637,7 @@ mod tests {
             instance_status: Some("down".to_string()),
             interfaces: Some(vec![InterfaceInfo { ip_address: None }]),
             tags: vec![],
+            disks: vec![],
         };
 
         assert_eq!(server.ip_address(), None);
@@ -641,6 +662,7 @@ mod tests {
                 },
             ]),
             tags: vec![],
+            disks: vec![],
         };
 
         // Should return first IP found
@@ -657,6 +679,7 @@ mod tests {
             instance_status: None,
             interfaces: None,
             tags: vec![],
+            disks: vec![],
         };
 
         assert_eq!(server.id_str(), "123456789012");
@@ -672,6 +695,7 @@ mod tests {
             instance_status: status.map(|s| s.to_string()),
             interfaces: None,
             tags: vec![],
+            disks: vec![],
         };
 
         assert!(make_server(Some("up")).is_running());
@@ -691,7 +715,8 @@ mod tests {
             "MemoryMB": 8192,
             "InstanceStatus": "up",
             "Interfaces": [{"IPAddress": "203.0.113.1"}],
-            "Tags": ["fleetflow:proj:web"]
+            "Tags": ["fleetflow:proj:web"],
+            "Disks": [{"SizeMB": 40960}]
         }"#;
 
         let server: ServerInfo = serde_json::from_str(json).unwrap();
@@ -702,6 +727,7 @@ mod tests {
         assert!(server.is_running());
         assert_eq!(server.ip_address(), Some("203.0.113.1".to_string()));
         assert_eq!(server.tags.len(), 1);
+        assert_eq!(server.disk_gb(), Some(40));
     }
 
     #[test]
@@ -711,6 +737,7 @@ mod tests {
         assert_eq!(server.id, 1);
         assert_eq!(server.name, "min");
         assert!(server.tags.is_empty()); // default
+        assert!(server.disk_gb().is_none());
     }
 
     // ---- SshKeyInfo tests ----
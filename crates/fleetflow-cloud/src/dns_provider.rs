@@ -0,0 +1,147 @@
+//! DNS レコード管理の抽象化
+//!
+//! ServerProvider と同様に、DNS レコードの命令型 CRUD 操作を提供する。
+//! FleetFlow が作成したレコードにはコメントに管理タグ（[`managed_comment`]）を付け、
+//! プロジェクトが管理するレコードを他のレコードと区別する。
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// 管理タグの接頭辞（サーバーの `fleetflow:<project>:<server>` タグと揃える）
+pub const MANAGED_PREFIX: &str = "fleetflow:";
+
+/// FleetFlow が管理するレコードのコメント（`fleetflow:<project>[:<server>]`）
+pub fn managed_comment(project: &str, server: Option<&str>) -> String {
+    match server {
+        Some(server) => format!("{}{}:{}", MANAGED_PREFIX, project, server),
+        None => format!("{}{}", MANAGED_PREFIX, project),
+    }
+}
+
+/// 管理タグからプロジェクト名とサーバー名を取り出す
+pub fn parse_managed_comment(comment: &str) -> Option<(&str, Option<&str>)> {
+    let rest = comment.trim().strip_prefix(MANAGED_PREFIX)?;
+    let (project, server) = match rest.split_once(':') {
+        Some((project, server)) => (project, Some(server)),
+        None => (rest, None),
+    };
+    (!project.is_empty()).then_some((project, server.filter(|s| !s.is_empty())))
+}
+
+/// DNS レコード管理の trait
+///
+/// Note: async fn in trait は object-safe でないため、
+/// 複数プロバイダーを扱う場合は具体型または enum でディスパッチする。
+#[allow(async_fn_in_trait)]
+pub trait DnsProvider: Send + Sync {
+    /// プロバイダー名（e.g., "cloudflare"）
+    fn provider_name(&self) -> &str;
+
+    /// 管理対象のドメイン（ゾーン）
+    fn zone_domain(&self) -> &str;
+
+    /// ゾーンの全レコード取得
+    async fn list_dns_records(&self) -> Result<Vec<DnsRecordSpec>>;
+
+    /// レコードの作成または更新
+    ///
+    /// 同じ名前・タイプのレコードがあれば更新する（TXT は値が同じものだけ）。
+    async fn upsert_dns_record(&self, request: &DnsRecordRequest) -> Result<DnsRecordSpec>;
+
+    /// レコード削除
+    async fn delete_dns_record(&self, record_id: &str) -> Result<()>;
+}
+
+/// DNS レコード情報（プロバイダー非依存）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecordSpec {
+    /// プロバイダー上の ID
+    pub id: String,
+
+    /// FQDN
+    pub name: String,
+
+    /// レコードタイプ（A, AAAA, CNAME, TXT など）
+    pub record_type: String,
+
+    /// レコードの値
+    pub content: String,
+
+    /// TTL（秒）
+    pub ttl: Option<u32>,
+
+    /// プロキシ経由か
+    #[serde(default)]
+    pub proxied: bool,
+
+    /// コメント（管理タグ）
+    pub comment: Option<String>,
+}
+
+impl DnsRecordSpec {
+    /// 管理タグのプロジェクト名とサーバー名（FleetFlow 管理外なら None）
+    pub fn managed_by(&self) -> Option<(&str, Option<&str>)> {
+        parse_managed_comment(self.comment.as_deref()?)
+    }
+}
+
+/// DNS レコード作成リクエスト（プロバイダー非依存）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsRecordRequest {
+    /// ゾーンからの相対名（e.g., "api"）
+    pub name: String,
+
+    /// レコードタイプ（A, AAAA, CNAME, TXT）
+    pub record_type: String,
+
+    /// レコードの値
+    pub content: String,
+
+    /// TTL（秒。None はプロバイダーの自動設定）
+    pub ttl: Option<u32>,
+
+    /// プロキシ経由にするか
+    #[serde(default)]
+    pub proxied: bool,
+
+    /// コメント（管理タグ）
+    pub comment: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_comment_roundtrip() {
+        let comment = managed_comment("myapp", Some("web-01"));
+        assert_eq!(comment, "fleetflow:myapp:web-01");
+        assert_eq!(
+            parse_managed_comment(&comment),
+            Some(("myapp", Some("web-01")))
+        );
+        assert_eq!(
+            parse_managed_comment(&managed_comment("myapp", None)),
+            Some(("myapp", None))
+        );
+        assert_eq!(parse_managed_comment("created by hand"), None);
+        assert_eq!(parse_managed_comment("fleetflow:"), None);
+    }
+
+    #[test]
+    fn test_dns_record_spec_managed_by() {
+        let mut record = DnsRecordSpec {
+            id: "1".into(),
+            name: "api.example.com".into(),
+            record_type: "A".into(),
+            content: "203.0.113.1".into(),
+            ttl: Some(1),
+            proxied: false,
+            comment: None,
+        };
+        assert_eq!(record.managed_by(), None);
+        record.comment = Some("fleetflow:myapp".into());
+        assert_eq!(record.managed_by(), Some(("myapp", None)));
+    }
+}
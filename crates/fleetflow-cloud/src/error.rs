@@ -35,6 +35,9 @@ pub enum CloudError {
     #[error("Timeout: {0}")]
     Timeout(String),
 
+    #[error("SSH error: {0}")]
+    Ssh(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -42,6 +45,43 @@ pub enum CloudError {
     Json(#[from] serde_json::Error),
 }
 
+impl CloudError {
+    /// Diagnostic code (`fleet::cloud::*`, or `fleet::ssh::*` for remote execution)
+    pub fn code(&self) -> &'static str {
+        match self {
+            CloudError::ProviderNotFound(_) => "fleet::cloud::provider_not_found",
+            CloudError::ResourceNotFound(_) => "fleet::cloud::resource_not_found",
+            CloudError::ResourceAlreadyExists(_) => "fleet::cloud::resource_already_exists",
+            CloudError::AuthenticationFailed(_) => "fleet::cloud::authentication_failed",
+            CloudError::ApiError(_) => "fleet::cloud::api_error",
+            CloudError::CommandFailed(_) => "fleet::cloud::command_failed",
+            CloudError::InvalidConfig(_) => "fleet::cloud::invalid_config",
+            CloudError::StateError(_) => "fleet::cloud::state_error",
+            CloudError::LockError(_) => "fleet::cloud::lock_error",
+            CloudError::Timeout(_) => "fleet::cloud::timeout",
+            CloudError::Ssh(_) => "fleet::ssh::failed",
+            CloudError::Io(_) => "fleet::cloud::io",
+            CloudError::Json(_) => "fleet::cloud::json",
+        }
+    }
+
+    /// How to resolve the error, when the message alone does not say
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            CloudError::AuthenticationFailed(_) => Some(
+                "check the provider credentials (e.g. `usacloud config`, CLOUDFLARE_API_TOKEN)",
+            ),
+            CloudError::LockError(_) => {
+                Some("another fleet process may be running; retry once it finishes")
+            }
+            CloudError::Ssh(_) => {
+                Some("check `tailscale status` and that the Tailscale ACL allows SSH to the host")
+            }
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, CloudError>;
 
 #[cfg(test)]
@@ -72,6 +112,18 @@ mod tests {
         assert_eq!(err.to_string(), "Authentication failed: invalid token");
     }
 
+    #[test]
+    fn test_error_code() {
+        assert_eq!(
+            CloudError::ApiError("x".to_string()).code(),
+            "fleet::cloud::api_error"
+        );
+        let err = CloudError::Ssh("timeout".to_string());
+        assert_eq!(err.to_string(), "SSH error: timeout");
+        assert_eq!(err.code(), "fleet::ssh::failed");
+        assert!(err.help().is_some());
+    }
+
     #[test]
     fn test_error_display_api_error() {
         let err = CloudError::ApiError("500 internal".to_string());
@@ -34,6 +34,7 @@
 //! ```
 
 pub mod action;
+pub mod dns_provider;
 pub mod error;
 pub mod provider;
 pub mod server_provider;
@@ -43,6 +44,9 @@ pub mod tailscale;
 
 // Re-exports
 pub use action::{Action, ActionType, ApplyResult, Plan, PlanSummary};
+pub use dns_provider::{
+    DnsProvider, DnsRecordRequest, DnsRecordSpec, managed_comment, parse_managed_comment,
+};
 pub use error::{CloudError, Result};
 pub use provider::{AuthStatus, CloudProvider, ResourceConfig, ResourceSet, RetryConfig};
 pub use server_provider::{CreateServerRequest, NetworkConfig, ServerSpec, ServerStatus};
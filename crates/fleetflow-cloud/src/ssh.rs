@@ -45,12 +45,53 @@ pub async fn exec_with_timeout(
     )
     .await
     .map_err(|_| {
-        CloudError::Timeout(format!(
+        CloudError::Ssh(format!(
             "SSH タイムアウト ({:.0}s): {target}",
             timeout.as_secs_f64()
         ))
     })?
-    .map_err(|e| CloudError::CommandFailed(format!("tailscale ssh 実行失敗: {e}")))?;
+    .map_err(|e| CloudError::Ssh(format!("tailscale ssh 実行失敗: {e}")))?;
+
+    let exit_code = output.status.code().unwrap_or(-1);
+
+    Ok(SshResult {
+        host: host.to_string(),
+        exit_code,
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        success: output.status.success(),
+    })
+}
+
+/// 標準入力にデータを渡してリモートコマンドを実行
+///
+/// トークンなど、コマンドラインに載せたくない値を渡すために使う。
+pub async fn exec_with_input(
+    host: &str,
+    user: &str,
+    command: &str,
+    input: &[u8],
+) -> Result<SshResult, CloudError> {
+    let target = format!("{user}@{host}");
+
+    let mut child = Command::new("tailscale")
+        .args(["ssh", &target, "--", command])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| CloudError::Ssh(format!("tailscale ssh 起動失敗: {e}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        stdin.write_all(input).await.ok();
+        stdin.shutdown().await.ok();
+    }
+
+    let output = tokio::time::timeout(Duration::from_secs(30), child.wait_with_output())
+        .await
+        .map_err(|_| CloudError::Ssh(format!("SSH タイムアウト (30s): {target}")))?
+        .map_err(|e| CloudError::Ssh(format!("tailscale ssh 実行失敗: {e}")))?;
 
     let exit_code = output.status.code().unwrap_or(-1);
 
@@ -88,20 +129,32 @@ fn shell_escape(s: &str) -> String {
 
 /// ファイルをリモートにコピー（tailscale ssh 経由で安全に転送）
 ///
-/// ローカルファイルを base64 エンコードし、リモート側で decode して書き込む。
-/// stdin 経由で base64 データを渡すことで、シェルインジェクションを完全に排除。
+/// ローカルファイルを読み込み、root として [`write_file`] で書き込む。
 pub async fn copy_file(local_path: &str, host: &str, remote_path: &str) -> Result<(), CloudError> {
     let content = tokio::fs::read(local_path)
         .await
         .map_err(|e| CloudError::CommandFailed(format!("ローカルファイル読み込み失敗: {e}")))?;
 
-    let encoded = base64_encode(&content);
+    write_file(host, "root", remote_path, &content).await
+}
+
+/// 内容をリモートのファイルに書き込む（tailscale ssh 経由で安全に転送）
+///
+/// 内容を base64 エンコードし、リモート側で decode して書き込む。
+/// stdin 経由で base64 データを渡すことで、シェルインジェクションを完全に排除。
+pub async fn write_file(
+    host: &str,
+    user: &str,
+    remote_path: &str,
+    content: &[u8],
+) -> Result<(), CloudError> {
+    let encoded = base64_encode(content);
     let escaped_path = shell_escape(remote_path);
 
     // stdin 経由で base64 データを渡し、リモート側で decode → ファイル書き込み
     // encoded は [A-Za-z0-9+/=] のみで構成されるため安全
     // remote_path はシェルエスケープ済み
-    let target = format!("root@{host}");
+    let target = format!("{user}@{host}");
     let decode_cmd = format!("base64 -d > {escaped_path}");
 
     let mut child = tokio::process::Command::new("tailscale")
@@ -110,7 +163,7 @@ pub async fn copy_file(local_path: &str, host: &str, remote_path: &str) -> Resul
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-        .map_err(|e| CloudError::CommandFailed(format!("tailscale ssh 起動失敗: {e}")))?;
+        .map_err(|e| CloudError::Ssh(format!("tailscale ssh 起動失敗: {e}")))?;
 
     // stdin に base64 データを書き込んで閉じる
     if let Some(mut stdin) = child.stdin.take() {
@@ -121,11 +174,11 @@ pub async fn copy_file(local_path: &str, host: &str, remote_path: &str) -> Resul
 
     let output = tokio::time::timeout(Duration::from_secs(60), child.wait_with_output())
         .await
-        .map_err(|_| CloudError::Timeout("ファイル転送タイムアウト (60s)".into()))?
-        .map_err(|e| CloudError::CommandFailed(format!("ファイル転送失敗: {e}")))?;
+        .map_err(|_| CloudError::Ssh("ファイル転送タイムアウト (60s)".into()))?
+        .map_err(|e| CloudError::Ssh(format!("ファイル転送失敗: {e}")))?;
 
     if !output.status.success() {
-        return Err(CloudError::CommandFailed(format!(
+        return Err(CloudError::Ssh(format!(
             "ファイル転送失敗: {}",
             String::from_utf8_lossy(&output.stderr)
         )));
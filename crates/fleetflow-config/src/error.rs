@@ -14,6 +14,9 @@ pub enum ConfigError {
     )]
     FlowFileNotFound,
 
+    #[error("グローバル設定が不正です: {0}")]
+    InvalidGlobalConfig(String),
+
     #[error("IO エラー: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -0,0 +1,537 @@
+//! ユーザー単位のグローバル設定（~/.config/fleetflow/config.kdl）
+//!
+//! プロジェクト設定より優先度の低いデフォルト値を保持する。
+//!
+//! ```kdl
+//! default-stage "dev"
+//! color #false
+//! registry "ghcr.io/acme"
+//! docker-socket "/var/run/docker.sock"
+//! docker-retry {
+//!     attempts 5
+//!     interval 2
+//! }
+//! update {
+//!     check #false
+//!     channel "beta"
+//! }
+//! permissions {
+//!     role "operator"
+//!     protected "prod"
+//!     profile "operator" {
+//!         stages "local" "dev"
+//!     }
+//! }
+//! ```
+
+use crate::{ConfigError, Result};
+use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// グローバル設定ファイル名
+pub const GLOBAL_CONFIG_FILE: &str = "config.kdl";
+
+/// `fleet config set` で設定できるキーと説明
+pub const GLOBAL_CONFIG_KEYS: &[(&str, &str)] = &[
+    ("default-stage", "ステージ省略時に使うステージ名"),
+    ("color", "色付き出力 (true / false)"),
+    ("registry", "プロジェクトで未指定の場合に使うレジストリ"),
+    ("docker-socket", "Docker デーモンのソケットパス"),
+    (
+        "docker-retry.attempts",
+        "Docker への接続を試みる回数（デーモン再起動中の再接続を含む）",
+    ),
+    (
+        "docker-retry.interval",
+        "Docker への再接続の初回待機秒数（以降は倍々に延ばす）",
+    ),
+    ("update.check", "起動時の更新確認 (true / false)"),
+    ("update.channel", "更新チャネル (stable / beta)"),
+];
+
+/// グローバル設定
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalConfig {
+    /// ステージ省略時のデフォルト（プロジェクトに存在する場合のみ使う）
+    pub default_stage: Option<String>,
+    /// 色付き出力の強制（None なら端末・NO_COLOR に従う）
+    pub color: Option<bool>,
+    /// プロジェクトに registry が無い場合のデフォルト
+    pub registry: Option<String>,
+    /// Docker デーモンのソケットパス（DOCKER_HOST 未設定時に使う。未設定なら既知の場所を自動検出）
+    pub docker_socket: Option<String>,
+    pub docker_retry: DockerRetryConfig,
+    pub update: UpdateConfig,
+    pub permissions: PermissionsConfig,
+}
+
+/// `update { ... }` ブロック
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateConfig {
+    /// 起動時の更新確認
+    pub check: bool,
+    /// 更新チャネル（"stable" / "beta"）
+    pub channel: Option<String>,
+}
+
+/// `docker-retry { ... }` ブロック — デーモン再起動中の接続リトライ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerRetryConfig {
+    /// 接続を試みる回数（1 ならリトライしない）
+    pub attempts: u32,
+    /// 初回の待機秒数（以降は倍々に延ばす）
+    pub interval: u64,
+}
+
+/// `permissions { ... }` ブロック — 共有サーバーでのコマンド権限
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionsConfig {
+    /// このマシンでの既定のロール（`--role` で上書き）
+    pub role: Option<String>,
+    /// 変更に `--role admin` と確認が必要なステージ
+    pub protected: Vec<String>,
+    /// ロールごとに変更を許可するステージ（admin は常に全ステージ）
+    pub profiles: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            check: true,
+            channel: None,
+        }
+    }
+}
+
+impl Default for DockerRetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            interval: 1,
+        }
+    }
+}
+
+impl GlobalConfig {
+    /// グローバル設定ファイルのパス
+    pub fn path() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
+            .ok_or(ConfigError::ConfigDirNotFound)?
+            .join("fleetflow")
+            .join(GLOBAL_CONFIG_FILE))
+    }
+
+    /// グローバル設定を読み込む（ファイルが無ければデフォルト、不明なキーはエラー）
+    pub fn load() -> Result<Self> {
+        let (config, unknown) = Self::load_lenient()?;
+        match unknown.first() {
+            Some(key) => Err(unknown_key(key)),
+            None => Ok(config),
+        }
+    }
+
+    /// グローバル設定を読み込み、不明なキーは無視してその一覧を返す
+    pub fn load_lenient() -> Result<(Self, Vec<String>)> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok((Self::default(), Vec::new()));
+        }
+        Self::parse_lenient(&std::fs::read_to_string(path)?)
+    }
+
+    /// KDL 文字列からグローバル設定を読み込む（不明なキーはエラー）
+    pub fn parse(content: &str) -> Result<Self> {
+        let (config, unknown) = Self::parse_lenient(content)?;
+        match unknown.first() {
+            Some(key) => Err(unknown_key(key)),
+            None => Ok(config),
+        }
+    }
+
+    /// KDL 文字列からグローバル設定を読み込み、不明なキーは無視してその一覧を返す
+    ///
+    /// 新しいバージョンで追加されたキーや削除されたキーがあっても、残りの設定を
+    /// 捨てずに使えるようにする。値の誤りはエラーのまま。
+    pub fn parse_lenient(content: &str) -> Result<(Self, Vec<String>)> {
+        let doc = parse_document(content)?;
+        let mut config = Self::default();
+        let mut unknown = Vec::new();
+
+        for node in doc.nodes() {
+            match node.name().value() {
+                block @ ("update" | "docker-retry") => {
+                    for child in node.iter_children() {
+                        let key = format!("{}.{}", block, child.name().value());
+                        if is_known_key(&key) {
+                            config.apply(&key, first_value(child, &key)?)?;
+                        } else {
+                            unknown.push(key);
+                        }
+                    }
+                }
+                "permissions" => config.permissions = parse_permissions(node)?,
+                key if is_known_key(key) => config.apply(key, first_value(node, key)?)?,
+                key => unknown.push(key.to_string()),
+            }
+        }
+
+        Ok((config, unknown))
+    }
+
+    fn apply(&mut self, key: &str, value: &KdlValue) -> Result<()> {
+        match key {
+            "default-stage" => self.default_stage = Some(expect_string(key, value)?),
+            "color" => self.color = Some(expect_bool(key, value)?),
+            "registry" => self.registry = Some(expect_string(key, value)?),
+            "docker-socket" => self.docker_socket = Some(expect_string(key, value)?),
+            "docker-retry.attempts" => {
+                let attempts = expect_integer(key, value)?;
+                if attempts == 0 {
+                    return Err(invalid(format!("{} は 1 以上を指定してください", key)));
+                }
+                self.docker_retry.attempts = attempts;
+            }
+            "docker-retry.interval" => {
+                self.docker_retry.interval = u64::from(expect_integer(key, value)?)
+            }
+            "update.check" => self.update.check = expect_bool(key, value)?,
+            "update.channel" => {
+                let channel = expect_string(key, value)?;
+                if !matches!(channel.as_str(), "stable" | "beta") {
+                    return Err(invalid(format!(
+                        "{} は \"stable\" または \"beta\" を指定してください",
+                        key
+                    )));
+                }
+                self.update.channel = Some(channel);
+            }
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_permissions(node: &KdlNode) -> Result<PermissionsConfig> {
+    let mut permissions = PermissionsConfig::default();
+    for child in node.iter_children() {
+        match child.name().value() {
+            "role" => {
+                permissions.role = Some(expect_string(
+                    "permissions.role",
+                    first_value(child, "permissions.role")?,
+                )?);
+            }
+            "protected" => {
+                permissions
+                    .protected
+                    .extend(string_values(child, "permissions.protected")?);
+            }
+            "profile" => {
+                let name = expect_string(
+                    "permissions.profile",
+                    first_value(child, "permissions.profile")?,
+                )?;
+                let mut stages = Vec::new();
+                for entry in child.iter_children() {
+                    match entry.name().value() {
+                        "stages" => stages.extend(string_values(entry, "profile.stages")?),
+                        other => {
+                            return Err(invalid(format!(
+                                "profile \"{}\" の不明な項目です: {}",
+                                name, other
+                            )));
+                        }
+                    }
+                }
+                permissions.profiles.insert(name, stages);
+            }
+            other => {
+                return Err(invalid(format!(
+                    "permissions の不明な項目です: {}（role / protected / profile）",
+                    other
+                )));
+            }
+        }
+    }
+    Ok(permissions)
+}
+
+fn string_values(node: &KdlNode, key: &str) -> Result<Vec<String>> {
+    node.entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .map(|e| expect_string(key, e.value()))
+        .collect()
+}
+
+/// グローバル設定の 1 項目を書き換えた KDL を返す
+///
+/// 既存の他の項目・コメントは保持する。`value` はキーの型に合わせて
+/// 真偽値（true / false / on / off）または文字列として書き込む。
+pub fn set_global_value(content: &str, key: &str, value: &str) -> Result<String> {
+    let value = typed_value(key, value)?;
+
+    // 値として正しいかを先に検証
+    GlobalConfig::default().apply(key, &value)?;
+
+    let mut doc = parse_document(content)?;
+    let (parent, name) = match key.split_once('.') {
+        Some((parent, name)) => (Some(parent), name),
+        None => (None, key),
+    };
+
+    let mut node = KdlNode::new(name);
+    node.push(KdlEntry::new(value));
+
+    let nodes = match parent {
+        None => &mut doc,
+        Some(parent) => {
+            let idx = match doc.nodes().iter().position(|n| n.name().value() == parent) {
+                Some(idx) => idx,
+                None => {
+                    doc.nodes_mut().push(KdlNode::new(parent));
+                    doc.nodes().len() - 1
+                }
+            };
+            doc.nodes_mut()[idx].ensure_children()
+        }
+    };
+    match nodes.nodes().iter().position(|n| n.name().value() == name) {
+        Some(idx) => nodes.nodes_mut()[idx] = node,
+        None => nodes.nodes_mut().push(node),
+    }
+    if let Some(parent) = parent
+        && let Some(node) = doc
+            .nodes_mut()
+            .iter_mut()
+            .find(|n| n.name().value() == parent)
+    {
+        node.autoformat();
+    }
+
+    Ok(doc.to_string())
+}
+
+/// グローバル設定ファイルの 1 項目を書き換えて保存し、保存先を返す
+pub fn save_global_value(key: &str, value: &str) -> Result<PathBuf> {
+    let path = GlobalConfig::path()?;
+    let content = if path.exists() {
+        std::fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+
+    let updated = set_global_value(&content, key, value)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, updated)?;
+    Ok(path)
+}
+
+fn typed_value(key: &str, value: &str) -> Result<KdlValue> {
+    match key {
+        "color" | "update.check" => match value {
+            "true" | "on" | "yes" | "#true" => Ok(KdlValue::Bool(true)),
+            "false" | "off" | "no" | "#false" => Ok(KdlValue::Bool(false)),
+            _ => Err(invalid(format!(
+                "{} は true または false を指定してください",
+                key
+            ))),
+        },
+        "docker-retry.attempts" | "docker-retry.interval" => value
+            .parse::<i128>()
+            .map(KdlValue::Integer)
+            .map_err(|_| invalid(format!("{} は整数で指定してください", key))),
+        _ if is_known_key(key) => Ok(KdlValue::String(value.to_string())),
+        _ => Err(unknown_key(key)),
+    }
+}
+
+fn parse_document(content: &str) -> Result<KdlDocument> {
+    content
+        .parse()
+        .map_err(|e| invalid(format!("KDL のパースに失敗しました: {}", e)))
+}
+
+fn first_value<'a>(node: &'a KdlNode, key: &str) -> Result<&'a KdlValue> {
+    node.entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .map(|e| e.value())
+        .ok_or_else(|| invalid(format!("{} に値がありません", key)))
+}
+
+fn expect_string(key: &str, value: &KdlValue) -> Result<String> {
+    value
+        .as_string()
+        .map(str::to_string)
+        .ok_or_else(|| invalid(format!("{} は文字列で指定してください", key)))
+}
+
+fn expect_bool(key: &str, value: &KdlValue) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| invalid(format!("{} は #true / #false で指定してください", key)))
+}
+
+fn expect_integer(key: &str, value: &KdlValue) -> Result<u32> {
+    value
+        .as_integer()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| invalid(format!("{} は 0 以上の整数で指定してください", key)))
+}
+
+fn is_known_key(key: &str) -> bool {
+    GLOBAL_CONFIG_KEYS.iter().any(|(k, _)| *k == key)
+}
+
+fn unknown_key(key: &str) -> ConfigError {
+    invalid(format!(
+        "不明な設定キーです: {}\n利用可能なキー: {}",
+        key,
+        GLOBAL_CONFIG_KEYS
+            .iter()
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+fn invalid(message: String) -> ConfigError {
+    ConfigError::InvalidGlobalConfig(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(GlobalConfig::parse("").unwrap(), GlobalConfig::default());
+    }
+
+    #[test]
+    fn test_parse_all_keys() {
+        let config = GlobalConfig::parse(
+            r#"
+            default-stage "dev"
+            color #false
+            registry "ghcr.io/acme"
+            docker-socket "/run/user/1000/docker.sock"
+            docker-retry {
+                attempts 5
+                interval 2
+            }
+            update {
+                check #false
+                channel "beta"
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.default_stage.as_deref(), Some("dev"));
+        assert_eq!(config.color, Some(false));
+        assert_eq!(config.registry.as_deref(), Some("ghcr.io/acme"));
+        assert_eq!(
+            config.docker_socket.as_deref(),
+            Some("/run/user/1000/docker.sock")
+        );
+        assert_eq!(
+            config.docker_retry,
+            DockerRetryConfig {
+                attempts: 5,
+                interval: 2
+            }
+        );
+        assert!(!config.update.check);
+        assert_eq!(config.update.channel.as_deref(), Some("beta"));
+    }
+
+    #[test]
+    fn test_parse_permissions() {
+        let config = GlobalConfig::parse(
+            r#"
+            permissions {
+                role "operator"
+                protected "prod" "stg"
+                profile "operator" {
+                    stages "local" "dev"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let permissions = &config.permissions;
+        assert_eq!(permissions.role.as_deref(), Some("operator"));
+        assert_eq!(permissions.protected, vec!["prod", "stg"]);
+        assert_eq!(
+            permissions.profiles.get("operator"),
+            Some(&vec!["local".to_string(), "dev".to_string()])
+        );
+
+        assert!(GlobalConfig::parse("permissions { roles \"x\" }").is_err());
+        assert!(GlobalConfig::parse("permissions { profile \"x\" { stage \"dev\" } }").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!(GlobalConfig::parse("colour #false").is_err());
+        assert!(GlobalConfig::parse(r#"color "no""#).is_err());
+        assert!(GlobalConfig::parse(r#"update { channel "nightly" }"#).is_err());
+        assert!(GlobalConfig::parse("registry").is_err());
+        assert!(GlobalConfig::parse("docker-retry { attempts 0 }").is_err());
+        assert!(GlobalConfig::parse(r#"docker-retry { interval "2s" }"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_lenient_skips_unknown_keys() {
+        let (config, unknown) = GlobalConfig::parse_lenient(
+            r#"
+            registry "ghcr.io/acme"
+            telemetry #false
+            update {
+                check #false
+                interval 3
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.registry.as_deref(), Some("ghcr.io/acme"));
+        assert!(!config.update.check);
+        assert_eq!(unknown, vec!["telemetry", "update.interval"]);
+        // 値の誤りは無視しない
+        assert!(GlobalConfig::parse_lenient(r#"color "no""#).is_err());
+    }
+
+    #[test]
+    fn test_set_global_value_adds_and_replaces() {
+        let content = "// 自分用の設定\nregistry \"ghcr.io/old\"\n";
+
+        let updated = set_global_value(content, "registry", "ghcr.io/new").unwrap();
+        let updated = set_global_value(&updated, "color", "off").unwrap();
+        let updated = set_global_value(&updated, "update.check", "false").unwrap();
+        let updated = set_global_value(&updated, "update.channel", "beta").unwrap();
+        let updated = set_global_value(&updated, "docker-retry.attempts", "10").unwrap();
+
+        assert!(updated.contains("// 自分用の設定"));
+        let config = GlobalConfig::parse(&updated).unwrap();
+        assert_eq!(config.registry.as_deref(), Some("ghcr.io/new"));
+        assert_eq!(config.color, Some(false));
+        assert!(!config.update.check);
+        assert_eq!(config.update.channel.as_deref(), Some("beta"));
+        assert_eq!(config.docker_retry.attempts, 10);
+    }
+
+    #[test]
+    fn test_set_global_value_validates() {
+        assert!(set_global_value("", "unknown", "x").is_err());
+        assert!(set_global_value("", "color", "maybe").is_err());
+        assert!(set_global_value("", "update.channel", "nightly").is_err());
+        assert!(set_global_value("", "docker-retry.interval", "2s").is_err());
+    }
+}
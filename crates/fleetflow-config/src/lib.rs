@@ -1,6 +1,8 @@
 pub mod error;
+pub mod global;
 
 pub use error::*;
+pub use global::*;
 
 use std::path::PathBuf;
 
@@ -0,0 +1,89 @@
+//! クラスタ型デプロイ先の抽象化 — `backend "kubernetes"` / `backend "nomad"`
+//!
+//! クラスタ型のバックエンドはどれも「ステージ定義を生成 → ファイルに書き出し →
+//! クラスタに投入 → 状態を問い合わせる」という同じ流れを取る。`ClusterTarget` はその
+//! 共通部分で、`fleet up` / `fleet down` / `fleet ps` はクラスタ型のバックエンドを
+//! 問わずこの trait 経由で扱う。
+//!
+//! 対象はクラスタ型だけ。ローカルの Docker エンジン（`engine` / `runtime`）と
+//! Quadlet / Compose はそれぞれ専用の経路のままで、全バックエンドを 1 つの trait で
+//! 駆動する抽象化にはなっていない。
+//!
+//! クラスタとの通信は API クライアント（kube-rs / nomad API）ではなく `kubectl` /
+//! `nomad` CLI に委ねる。利用者が普段使っている context・認証プラグイン
+//! （`KUBECONFIG` / `NOMAD_ADDR` / `NOMAD_TOKEN`）をそのまま使え、クラスタごとの
+//! 認証方式を fleetflow 側で再実装せずに済むため。生成したファイルは
+//! `.fleetflow/` に残るので、同じ内容を CLI で手動適用して確認することもできる。
+
+use std::path::{Path, PathBuf};
+
+use fleetflow_core::{Backend, Flow, Stage};
+
+/// クラスタ型のデプロイ先。
+pub trait ClusterTarget: Send + Sync {
+    /// バックエンド名（`backend "..."` の値）
+    fn name(&self) -> &'static str;
+
+    /// ステージの投入先の表示名（例: `namespace myapp-prod`）
+    fn resource(&self, config: &Flow, stage_name: &str) -> String;
+
+    /// ステージ定義（マニフェスト / job）を生成する（純粋関数、dry-run 表示用）
+    fn render(&self, config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<String>;
+
+    /// ステージ定義を書き出してクラスタに投入し、書き出したファイルを返す
+    fn up(
+        &self,
+        project_root: &Path,
+        config: &Flow,
+        stage_name: &str,
+        stage: &Stage,
+    ) -> anyhow::Result<PathBuf>;
+
+    /// ステージを停止する（`remove` 指定時は定義ごと削除）
+    fn down(
+        &self,
+        config: &Flow,
+        stage_name: &str,
+        stage: &Stage,
+        remove: bool,
+    ) -> anyhow::Result<()>;
+
+    /// ステージの状態を表示する
+    fn status(&self, config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<()>;
+}
+
+/// Kubernetes（`kubectl`）
+pub struct KubernetesTarget;
+
+/// Nomad（`nomad` CLI）
+pub struct NomadTarget;
+
+/// ステージの backend に対応するクラスタ型デプロイ先。
+///
+/// Docker / Quadlet / Compose はクラスタ型ではない（専用経路で扱う）ため `None`。
+pub fn cluster_target(backend: Backend) -> Option<&'static dyn ClusterTarget> {
+    match backend {
+        Backend::Kubernetes => Some(&KubernetesTarget),
+        Backend::Nomad => Some(&NomadTarget),
+        Backend::Docker | Backend::Quadlet | Backend::Compose => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_target_only_for_cluster_backends() {
+        assert_eq!(
+            cluster_target(Backend::Kubernetes).map(|t| t.name()),
+            Some("kubernetes")
+        );
+        assert_eq!(
+            cluster_target(Backend::Nomad).map(|t| t.name()),
+            Some("nomad")
+        );
+        assert!(cluster_target(Backend::Docker).is_none());
+        assert!(cluster_target(Backend::Compose).is_none());
+    }
+}
@@ -12,31 +12,26 @@
 //!
 //! 規約:
 //! - compose プロジェクト名・ネットワーク名 = `{project}-{stage}`
-//! - `container_name` = `{project}-{stage}-{service}`（fleetflow 命名規約）
+//! - `container_name` = 既定 `{project}-{stage}-{service}`（`container_name` / `container_name_template` に従う）
 //! - attribution は `fleetflow.{project,stage,service}` label
 //! - volume の host パスは compose ファイル位置非依存にするため絶対パス化
 
 use std::io;
 use std::path::{Path, PathBuf};
 
-use fleetflow_core::{Flow, Protocol, RestartPolicy, Stage};
+use fleetflow_core::{Flow, NetworkMode, Protocol, RestartPolicy, Stage};
 
 /// Compose プロジェクト名（`{project}-{stage}`）。
 pub fn compose_project_name(project: &str, stage: &str) -> String {
     format!("{project}-{stage}")
 }
 
-/// `{project}-{stage}-{service}` 形式のコンテナ名。
-fn container_name(project: &str, stage: &str, service: &str) -> String {
-    format!("{project}-{stage}-{service}")
-}
-
 /// YAML 二重引用符スカラーにエスケープする（純粋関数）。
 ///
 /// `\` `"` に加えて制御文字（LF/CR/TAB 等）もエスケープする。YAML の
 /// double-quoted スカラー中の生 LF は空白に folde されるため、エスケープ
 /// しないと複数行の値（PEM 証明書・JWT 等）が silent に壊れる。
-fn yaml_quote(s: &str) -> String {
+pub(crate) fn yaml_quote(s: &str) -> String {
     let mut escaped = String::with_capacity(s.len() + 2);
     for c in s.chars() {
         match c {
@@ -103,7 +98,12 @@ pub fn generate_compose_yaml(
         out.push_str(&format!("    image: {}\n", yaml_quote(image)));
         out.push_str(&format!(
             "    container_name: {}\n",
-            yaml_quote(&container_name(project, stage_name, service_name))
+            yaml_quote(&fleetflow_core::container_name(
+                service.container_name.as_deref(),
+                project,
+                stage_name,
+                service_name
+            ))
         ));
         let restart = service.restart.unwrap_or(RestartPolicy::UnlessStopped);
         out.push_str(&format!(
@@ -148,10 +148,31 @@ pub fn generate_compose_yaml(
             }
         }
 
-        // 実行コマンド
+        // 実行コマンド・エントリーポイント・作業ディレクトリ
         if let Some(command) = &service.command {
             out.push_str(&format!("    command: {}\n", yaml_quote(command)));
         }
+        if let Some(entrypoint) = &service.entrypoint {
+            out.push_str("    entrypoint:\n");
+            for arg in entrypoint {
+                out.push_str(&format!("      - {}\n", yaml_quote(arg)));
+            }
+        }
+        if let Some(dir) = &service.working_dir {
+            out.push_str(&format!("    working_dir: {}\n", yaml_quote(dir)));
+        }
+        if let Some(hostname) = &service.hostname {
+            out.push_str(&format!("    hostname: {}\n", yaml_quote(hostname)));
+        }
+
+        // ネットワークモード（container:<svc> は Compose では service:<svc>）
+        if let Some(mode) = &service.network_mode {
+            let value = match mode {
+                NetworkMode::Container(target) => format!("service:{target}"),
+                other => other.to_string(),
+            };
+            out.push_str(&format!("    network_mode: {}\n", yaml_quote(&value)));
+        }
 
         // 依存
         if !service.depends_on.is_empty() {
@@ -195,6 +216,15 @@ pub fn generate_compose_yaml(
             "      fleetflow.service: {}\n",
             yaml_quote(service_name)
         ));
+        let mut labels: Vec<(&String, &String)> = service.labels.iter().collect();
+        labels.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in labels {
+            out.push_str(&format!(
+                "      {}: {}\n",
+                yaml_quote(key),
+                yaml_quote(value)
+            ));
+        }
     }
 
     // ネットワーク — default を {project}-{stage} で命名（サービス名 DNS は
@@ -305,11 +335,7 @@ mod tests {
             name: "myapp".to_string(),
             services: svc_map,
             stages,
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
         (flow, stage)
     }
@@ -375,6 +401,26 @@ mod tests {
         assert!(yaml.contains("    name: \"myapp-live\""));
     }
 
+    #[test]
+    fn generate_compose_yaml_renders_network_mode() {
+        let mut sidecar = container_service();
+        sidecar.network_mode = Some(NetworkMode::Container("db".to_string()));
+        sidecar.depends_on = vec!["db".to_string()];
+        let mut sniffer = container_service();
+        sniffer.network_mode = Some(NetworkMode::Host);
+        let (flow, stage) = flow_with(
+            vec![
+                ("db", container_service()),
+                ("sidecar", sidecar),
+                ("sniffer", sniffer),
+            ],
+            vec!["db", "sidecar", "sniffer"],
+        );
+        let yaml = generate_compose_yaml(Path::new("/proj"), &flow, "live", &stage).unwrap();
+        assert!(yaml.contains("    network_mode: \"service:db\"\n"));
+        assert!(yaml.contains("    network_mode: \"host\"\n"));
+    }
+
     #[test]
     fn generate_compose_yaml_renders_ports_env_volumes() {
         let mut svc = container_service();
@@ -384,12 +430,16 @@ mod tests {
                 container: 80,
                 protocol: Protocol::Tcp,
                 host_ip: None,
+                name: None,
+                app_protocol: None,
             },
             Port {
                 host: 53,
                 container: 53,
                 protocol: Protocol::Udp,
                 host_ip: None,
+                name: None,
+                app_protocol: None,
             },
         ];
         svc.environment.insert("B_KEY".into(), "2".into());
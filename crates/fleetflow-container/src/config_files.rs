@@ -0,0 +1,437 @@
+//! 設定ファイルのレンダリングとマウント — `configs` → `.fleetflow/rendered/`
+//!
+//! トップレベル `configs` で宣言したファイル / インライン内容をテンプレート展開し、
+//! `.fleetflow/rendered/{stage}/{service}/{name}` に書き出して read-only で
+//! bind mount する。
+//!
+//! レンダリング結果のハッシュを `fleetflow.config-hash` ラベルに記録し、
+//! 内容が変わった場合は `fleet up` がコンテナを再作成する。
+//!
+//! リモートステージではサーバー上の `/var/lib/fleetflow/rendered/{project}/{stage}/{service}/`
+//! に配置するファイルを返し、配置は呼び出し側（SSH）が行う。ホストパスを共有しない
+//! Kubernetes / Nomad では `configs` を使うサービスを拒否する。
+
+use std::path::{Path, PathBuf};
+
+use fleetflow_core::{ConfigFile, Flow, ReferenceResolver, Service, TemplateProcessor, Volume};
+
+/// レンダリング結果のハッシュを記録するコンテナラベル。
+pub const CONFIG_HASH_LABEL: &str = "fleetflow.config-hash";
+
+/// レンダリング済み設定ファイルの出力先ディレクトリ。
+pub fn rendered_config_dir(project_root: &Path, stage: &str, service: &str) -> PathBuf {
+    project_root
+        .join(".fleetflow")
+        .join("rendered")
+        .join(stage)
+        .join(service)
+}
+
+/// リモートサーバー上でレンダリング済み設定ファイルを置くディレクトリ。
+pub fn remote_rendered_config_dir(project: &str, stage: &str, service: &str) -> PathBuf {
+    Path::new("/var/lib/fleetflow/rendered")
+        .join(project)
+        .join(stage)
+        .join(service)
+}
+
+/// 設定ファイルをテンプレート展開する。
+///
+/// 変数はプロジェクト共通 `variables` → ステージ `variables` の順に適用する
+/// （ステージ側が優先）。`port()` / `service_host()` などのサービス参照も解決する。
+/// `file` はプロジェクトルートからの相対パス。
+pub fn render_config_file(
+    project_root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+    config: &ConfigFile,
+) -> anyhow::Result<String> {
+    let mut processor = TemplateProcessor::new();
+    processor.defer_references(Some(stage_name));
+    processor.add_env_variables();
+    for (key, value) in &flow.variables {
+        processor.add_variable(key.clone(), serde_json::Value::String(value.clone()));
+    }
+    if let Some(stage) = flow.stages.get(stage_name) {
+        for (key, value) in &stage.variables {
+            processor.add_variable(key.clone(), serde_json::Value::String(value.clone()));
+        }
+    }
+
+    let rendered = match (&config.file, &config.content) {
+        (Some(file), _) => {
+            let path = if file.is_absolute() {
+                file.clone()
+            } else {
+                project_root.join(file)
+            };
+            processor.render_file(&path)?
+        }
+        (None, Some(content)) => processor.render_str(content)?,
+        (None, None) => anyhow::bail!("config には file または content が必要です"),
+    };
+
+    Ok(ReferenceResolver::new(&flow.name, Some(stage_name), &flow.services).resolve(&rendered)?)
+}
+
+/// レンダリング済みの設定ファイル（書き出し先パスと内容）。
+pub type RenderedFile = (PathBuf, String);
+
+/// サービスの `config` マウントを `dir` 配下のファイルとしてレンダリングする（書き出しはしない）。
+fn render_service_configs(
+    project_root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+    dir: &Path,
+) -> anyhow::Result<(Service, Vec<RenderedFile>, u64)> {
+    let mut service = service.clone();
+    let mut files = Vec::new();
+    let mut hash = FNV_OFFSET;
+    for mount in &service.configs {
+        let config = flow.configs.get(&mount.source).ok_or_else(|| {
+            anyhow::anyhow!(
+                "サービス '{}' が未定義の config '{}' を参照しています",
+                service_name,
+                mount.source
+            )
+        })?;
+        let rendered = render_config_file(project_root, flow, stage_name, config)?;
+
+        hash = fnv1a(hash, mount.source.as_bytes());
+        hash = fnv1a(hash, mount.target.to_string_lossy().as_bytes());
+        hash = fnv1a(hash, rendered.as_bytes());
+
+        let path = dir.join(&mount.source);
+        service.volumes.push(Volume {
+            host: path.clone(),
+            container: mount.target.clone(),
+            read_only: true,
+        });
+        files.push((path, rendered));
+    }
+    Ok((service, files, hash))
+}
+
+/// サービスの `config` マウントをレンダリングし、bind mount を追加したサービスを返す。
+///
+/// 戻り値の 2 要素目はレンダリング結果のハッシュ（`config` マウントが無い場合は
+/// `None`）。`CONFIG_HASH_LABEL` ラベルとしてコンテナに付与する。
+pub fn materialize_configs(
+    project_root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+) -> anyhow::Result<(Service, Option<String>)> {
+    if service.configs.is_empty() {
+        return Ok((service.clone(), None));
+    }
+
+    let dir = rendered_config_dir(project_root, stage_name, service_name);
+    let (service, files, hash) =
+        render_service_configs(project_root, flow, stage_name, service_name, service, &dir)?;
+    write_rendered_files(&files)?;
+    Ok((service, Some(format!("{:016x}", hash))))
+}
+
+/// リモートステージ用に `config` マウントをレンダリングする。
+///
+/// 戻り値のサービスはサーバー上の [`remote_rendered_config_dir`] を mount する。
+/// 2 要素目のファイルをデプロイ前に各サーバーへ配置すること。
+pub fn remote_configs(
+    project_root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+) -> anyhow::Result<(Service, Vec<RenderedFile>)> {
+    let dir = remote_rendered_config_dir(&flow.name, stage_name, service_name);
+    let (service, files, _) =
+        render_service_configs(project_root, flow, stage_name, service_name, service, &dir)?;
+    Ok((service, files))
+}
+
+/// ステージの全サービスの `config` マウントをレンダリングする（書き出しはしない）。
+///
+/// Compose / Quadlet のようにステージ単位で定義を生成する経路向け。戻り値は
+/// bind mount を追加した Flow と、[`write_rendered_files`] で書き出すファイル。
+pub fn render_stage_configs(
+    project_root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+) -> anyhow::Result<(Flow, Vec<RenderedFile>)> {
+    let mut rendered = flow.clone();
+    let mut files = Vec::new();
+    let Some(stage) = flow.stages.get(stage_name) else {
+        return Ok((rendered, files));
+    };
+    for service_name in &stage.services {
+        let Some(service) = flow.services.get(service_name) else {
+            continue;
+        };
+        if service.configs.is_empty() {
+            continue;
+        }
+        let dir = rendered_config_dir(project_root, stage_name, service_name);
+        let (service, service_files, _) =
+            render_service_configs(project_root, flow, stage_name, service_name, service, &dir)?;
+        rendered.services.insert(service_name.clone(), service);
+        files.extend(service_files);
+    }
+    Ok((rendered, files))
+}
+
+/// レンダリング済みファイルを書き出す。
+pub fn write_rendered_files(files: &[RenderedFile]) -> anyhow::Result<()> {
+    for (path, content) in files {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// ホストのファイルを mount できない経路（`target`）で `configs` を使うサービスを拒否する。
+///
+/// マウントが欠けたまま起動すると設定の無いコンテナが動いてしまうため、黙って落とさない。
+pub fn reject_configs(flow: &Flow, stage_name: &str, target: &str) -> anyhow::Result<()> {
+    let Some(stage) = flow.stages.get(stage_name) else {
+        return Ok(());
+    };
+    let services: Vec<&str> = stage
+        .services
+        .iter()
+        .filter(|name| {
+            flow.services
+                .get(name.as_str())
+                .is_some_and(|s| !s.configs.is_empty())
+        })
+        .map(String::as_str)
+        .collect();
+    if services.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{} では config マウントを扱えません（対象サービス: {}）",
+        target,
+        services.join(", ")
+    )
+}
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a（ビルド間で安定したハッシュが必要なため std の Hasher は使わない）
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{ConfigMount, Stage};
+    use std::collections::HashMap;
+
+    fn flow_with_config(config: ConfigFile) -> Flow {
+        let mut configs = HashMap::new();
+        configs.insert("app.conf".to_string(), config);
+        let mut variables = HashMap::new();
+        variables.insert("PORT".to_string(), "3000".to_string());
+        let mut stage_variables = HashMap::new();
+        stage_variables.insert("PORT".to_string(), "8080".to_string());
+        let mut stages = HashMap::new();
+        stages.insert(
+            "prod".to_string(),
+            Stage {
+                variables: stage_variables,
+                ..Default::default()
+            },
+        );
+        Flow {
+            name: "myapp".to_string(),
+            stages,
+            variables,
+            configs,
+            ..Default::default()
+        }
+    }
+
+    fn service_with_mount() -> Service {
+        Service {
+            configs: vec![ConfigMount {
+                source: "app.conf".to_string(),
+                target: PathBuf::from("/etc/app.conf"),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_inline_content_with_stage_variables() {
+        let flow = flow_with_config(ConfigFile {
+            content: Some("listen {{ PORT }}".to_string()),
+            ..Default::default()
+        });
+        let rendered = render_config_file(
+            Path::new("/nonexistent"),
+            &flow,
+            "prod",
+            &flow.configs["app.conf"],
+        )
+        .unwrap();
+        assert_eq!(rendered, "listen 8080");
+    }
+
+    #[test]
+    fn renders_service_references() {
+        let mut flow = flow_with_config(ConfigFile {
+            content: Some(
+                "upstream {{ service_host('api') }}:{{ port('api') }}; # {{ project }}-{{ stage }}"
+                    .to_string(),
+            ),
+            ..Default::default()
+        });
+        flow.services.insert(
+            "api".to_string(),
+            Service {
+                ports: vec![fleetflow_core::Port {
+                    host: 18080,
+                    container: 8080,
+                    protocol: Default::default(),
+                    host_ip: None,
+                    name: None,
+                    app_protocol: None,
+                }],
+                ..Default::default()
+            },
+        );
+        let rendered = render_config_file(
+            Path::new("/nonexistent"),
+            &flow,
+            "prod",
+            &flow.configs["app.conf"],
+        )
+        .unwrap();
+        assert_eq!(rendered, "upstream api:8080; # myapp-prod");
+    }
+
+    #[test]
+    fn renders_file_relative_to_project_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("app.conf.tera"), "port={{ PORT }}").unwrap();
+        let flow = flow_with_config(ConfigFile {
+            file: Some(PathBuf::from("app.conf.tera")),
+            ..Default::default()
+        });
+        let rendered =
+            render_config_file(dir.path(), &flow, "local", &flow.configs["app.conf"]).unwrap();
+        assert_eq!(rendered, "port=3000");
+    }
+
+    #[test]
+    fn materialize_writes_file_and_adds_read_only_bind() {
+        let dir = tempfile::tempdir().unwrap();
+        let flow = flow_with_config(ConfigFile {
+            content: Some("listen {{ PORT }}".to_string()),
+            ..Default::default()
+        });
+
+        let (service, hash) =
+            materialize_configs(dir.path(), &flow, "prod", "web", &service_with_mount()).unwrap();
+
+        let expected = dir.path().join(".fleetflow/rendered/prod/web/app.conf");
+        assert_eq!(std::fs::read_to_string(&expected).unwrap(), "listen 8080");
+        assert_eq!(service.volumes.len(), 1);
+        assert_eq!(service.volumes[0].host, expected);
+        assert_eq!(service.volumes[0].container, PathBuf::from("/etc/app.conf"));
+        assert!(service.volumes[0].read_only);
+        assert!(hash.is_some());
+    }
+
+    #[test]
+    fn remote_configs_mount_server_path() {
+        let flow = flow_with_config(ConfigFile {
+            content: Some("listen {{ PORT }}".to_string()),
+            ..Default::default()
+        });
+
+        let (service, files) = remote_configs(
+            Path::new("/nonexistent"),
+            &flow,
+            "prod",
+            "web",
+            &service_with_mount(),
+        )
+        .unwrap();
+
+        let expected = PathBuf::from("/var/lib/fleetflow/rendered/myapp/prod/web/app.conf");
+        assert_eq!(files, vec![(expected.clone(), "listen 8080".to_string())]);
+        assert_eq!(service.volumes[0].host, expected);
+    }
+
+    #[test]
+    fn render_stage_configs_covers_stage_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut flow = flow_with_config(ConfigFile {
+            content: Some("listen {{ PORT }}".to_string()),
+            ..Default::default()
+        });
+        flow.services
+            .insert("web".to_string(), service_with_mount());
+        flow.stages.get_mut("prod").unwrap().services = vec!["web".to_string()];
+
+        let (rendered, files) = render_stage_configs(dir.path(), &flow, "prod").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(rendered.services["web"].volumes[0].host, files[0].0);
+        assert!(!files[0].0.exists(), "書き出しは呼び出し側が行う");
+
+        write_rendered_files(&files).unwrap();
+        assert_eq!(std::fs::read_to_string(&files[0].0).unwrap(), "listen 8080");
+
+        let err = reject_configs(&flow, "prod", "backend \"nomad\"").unwrap_err();
+        assert!(err.to_string().contains("web"));
+    }
+
+    #[test]
+    fn hash_changes_with_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let flow_a = flow_with_config(ConfigFile {
+            content: Some("a".to_string()),
+            ..Default::default()
+        });
+        let flow_b = flow_with_config(ConfigFile {
+            content: Some("b".to_string()),
+            ..Default::default()
+        });
+
+        let (_, hash_a) =
+            materialize_configs(dir.path(), &flow_a, "prod", "web", &service_with_mount()).unwrap();
+        let (_, hash_a2) =
+            materialize_configs(dir.path(), &flow_a, "prod", "web", &service_with_mount()).unwrap();
+        let (_, hash_b) =
+            materialize_configs(dir.path(), &flow_b, "prod", "web", &service_with_mount()).unwrap();
+
+        assert_eq!(hash_a, hash_a2);
+        assert_ne!(hash_a, hash_b);
+    }
+
+    #[test]
+    fn materialize_without_mounts_is_noop() {
+        let dir = tempfile::tempdir().unwrap();
+        let flow = flow_with_config(ConfigFile::default());
+
+        let (service, hash) =
+            materialize_configs(dir.path(), &flow, "prod", "web", &Service::default()).unwrap();
+
+        assert!(service.volumes.is_empty());
+        assert!(hash.is_none());
+        assert!(!dir.path().join(".fleetflow").exists());
+    }
+}
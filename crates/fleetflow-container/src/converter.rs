@@ -1,10 +1,10 @@
 //! FlowConfig から Docker API パラメータへの変換
 
 use bollard::models::{
-    ContainerCreateBody, EndpointSettings, HealthConfig, HostConfig, NetworkingConfig, PortBinding,
-    RestartPolicy, RestartPolicyNameEnum,
+    ContainerCreateBody, EndpointSettings, HealthConfig, HostConfig, HostConfigLogConfig,
+    NetworkingConfig, PortBinding, ResourcesUlimits, RestartPolicy, RestartPolicyNameEnum,
 };
-use bollard::query_parameters::CreateContainerOptions;
+use bollard::query_parameters::{CreateContainerOptions, StopContainerOptions};
 use fleetflow_core::{Flow, Service};
 use std::collections::HashMap;
 
@@ -13,14 +13,36 @@ pub fn get_network_name(project_name: &str, stage_name: &str) -> String {
     format!("{}-{}", project_name, stage_name)
 }
 
+/// サービスが使うイメージ名（タグ付き）を決定
+///
+/// `version` があればタグとして使い、タグ未指定なら `latest` を補う。
+/// image 未指定ならサービス名をイメージ名とする。
+pub fn resolve_image(service_name: &str, service: &Service) -> String {
+    match (&service.image, &service.version) {
+        (Some(img), Some(ver)) => format!("{}:{}", img, ver),
+        (Some(img), None) => {
+            if img.contains(':') {
+                img.clone()
+            } else {
+                format!("{}:latest", img)
+            }
+        }
+        (None, Some(ver)) => format!("{}:{}", service_name, ver),
+        (None, None) => format!("{}:latest", service_name),
+    }
+}
+
 /// FlowConfigのServiceをDockerのコンテナ設定に変換
+///
+/// `flow` はプロジェクト名と、`network_mode "container:<service>"` の参照先の
+/// コンテナ名（`container_name` / `container_name_template`）の解決に使う。
 pub fn service_to_container_config(
     service_name: &str,
     service: &Service,
     stage_name: &str,
-    project_name: &str,
+    flow: &Flow,
 ) -> (ContainerCreateBody, CreateContainerOptions) {
-    service_to_container_config_with_network(service_name, service, stage_name, project_name, true)
+    service_to_container_config_with_network(service_name, service, stage_name, flow, true)
 }
 
 /// FlowConfigのServiceをDockerのコンテナ設定に変換（ネットワーク設定オプション付き）
@@ -28,22 +50,12 @@ pub fn service_to_container_config_with_network(
     service_name: &str,
     service: &Service,
     stage_name: &str,
-    project_name: &str,
+    flow: &Flow,
     use_network: bool,
 ) -> (ContainerCreateBody, CreateContainerOptions) {
+    let project_name = flow.name.as_str();
     // イメージ名の決定
-    let image = match (&service.image, &service.version) {
-        (Some(img), Some(ver)) => format!("{}:{}", img, ver),
-        (Some(img), None) => {
-            if img.contains(':') {
-                img.clone()
-            } else {
-                format!("{}:latest", img)
-            }
-        }
-        (None, Some(ver)) => format!("{}:{}", service_name, ver),
-        (None, None) => format!("{}:latest", service_name),
-    };
+    let image = resolve_image(service_name, service);
 
     // 環境変数の設定
     let env: Vec<String> = service
@@ -53,7 +65,7 @@ pub fn service_to_container_config_with_network(
         .collect();
 
     // ポートバインディングの設定
-    let mut port_bindings = HashMap::new();
+    let mut port_bindings: HashMap<String, Option<Vec<PortBinding>>> = HashMap::new();
     // bollard 0.21: exposed_ports は Vec<String>（旧 HashMap<String, HashMap<(),()>> から変更）
     let mut exposed_ports: Vec<String> = Vec::new();
 
@@ -69,17 +81,20 @@ pub fn service_to_container_config_with_network(
         );
 
         // ポート公開設定
-        exposed_ports.push(container_port.clone());
+        if !exposed_ports.contains(&container_port) {
+            exposed_ports.push(container_port.clone());
+        }
 
-        // ホストポートバインディング
+        // ホストポートバインディング（同じコンテナポートを複数のホストポートへ公開できる）
         let host_ip = port.host_ip.as_deref().unwrap_or("0.0.0.0");
-        port_bindings.insert(
-            container_port,
-            Some(vec![PortBinding {
+        port_bindings
+            .entry(container_port)
+            .or_insert_with(|| Some(Vec::new()))
+            .get_or_insert_with(Vec::new)
+            .push(PortBinding {
                 host_ip: Some(host_ip.to_string()),
                 host_port: Some(port.host.to_string()),
-            }]),
-        );
+            });
     }
 
     // ボリュームバインディング
@@ -116,30 +131,95 @@ pub fn service_to_container_config_with_network(
         }
     });
 
+    // ログドライバ・ローテーション設定
+    let log_config = service.logging.as_ref().map(|logging| {
+        let options = logging.docker_options();
+        HostConfigLogConfig {
+            typ: logging.driver.clone(),
+            config: if options.is_empty() {
+                None
+            } else {
+                Some(options)
+            },
+        }
+    });
+
+    // tmpfs マウント（target → "size=64m,mode=1777"）
+    let tmpfs: HashMap<String, String> = service
+        .tmpfs
+        .iter()
+        .map(|t| (t.target.display().to_string(), t.docker_options()))
+        .collect();
+
+    // /dev/shm サイズ（パース時に検証済み）
+    let shm_size = service
+        .shm_size
+        .as_deref()
+        .and_then(fleetflow_core::parse_byte_size)
+        .map(|bytes| bytes as i64);
+
+    // リソース制限
+    let ulimits: Vec<ResourcesUlimits> = service
+        .ulimits
+        .iter()
+        .map(|u| ResourcesUlimits {
+            name: Some(u.name.clone()),
+            soft: Some(u.soft),
+            hard: Some(u.hard),
+        })
+        .collect();
+
+    // network_mode 指定時はステージのネットワークに参加せず、ポートも公開しない
+    // （Docker は host / container モードでのポート公開を受け付けない）
+    let network_mode = service
+        .network_mode
+        .as_ref()
+        .map(|mode| mode.docker_value(flow, stage_name));
+    let (port_bindings, exposed_ports) = if network_mode.is_some() {
+        (None, None)
+    } else {
+        (Some(port_bindings), Some(exposed_ports))
+    };
+
     // HostConfig設定
     let host_config = Some(HostConfig {
-        port_bindings: Some(port_bindings),
+        port_bindings,
+        network_mode,
         binds: Some(binds),
         restart_policy,
+        log_config,
+        cap_add: non_empty(&service.cap_add),
+        cap_drop: non_empty(&service.cap_drop),
+        readonly_rootfs: service.read_only,
+        security_opt: non_empty(&service.security_opt),
+        privileged: service.privileged,
+        tmpfs: if tmpfs.is_empty() { None } else { Some(tmpfs) },
+        shm_size,
+        ulimits: if ulimits.is_empty() {
+            None
+        } else {
+            Some(ulimits)
+        },
+        sysctls: if service.sysctls.is_empty() {
+            None
+        } else {
+            Some(service.sysctls.clone())
+        },
         ..Default::default()
     });
 
-    // ラベル設定（OrbStackグループ化対応）
-    let mut labels = HashMap::new();
-    labels.insert(
-        "com.docker.compose.project".to_string(),
-        format!("{}-{}", project_name, stage_name),
-    );
-    labels.insert(
-        "com.docker.compose.service".to_string(),
-        service_name.to_string(),
-    );
-    labels.insert("fleetflow.project".to_string(), project_name.to_string());
-    labels.insert("fleetflow.stage".to_string(), stage_name.to_string());
-    labels.insert("fleetflow.service".to_string(), service_name.to_string());
+    // ラベル設定（OrbStackグループ化対応 + ラベルベースの管理）
+    // 任意のラベルは管理用ラベルを上書きしない（予約済みの接頭辞はパース時に拒否済み）
+    let mut labels = service.labels.clone();
+    labels.extend(crate::labels::standard_labels(
+        project_name,
+        stage_name,
+        service_name,
+        service,
+    ));
 
     // ネットワーク設定（サービス名でエイリアス #14）
-    let networking_config = if use_network {
+    let networking_config = if use_network && service.network_mode.is_none() {
         let mut endpoints = HashMap::new();
         endpoints.insert(
             network_name,
@@ -169,26 +249,60 @@ pub fn service_to_container_config_with_network(
     let config = ContainerCreateBody {
         image: Some(image),
         env: Some(env),
-        exposed_ports: Some(exposed_ports),
+        exposed_ports,
         host_config,
         labels: Some(labels),
-        cmd: service
-            .command
-            .as_ref()
-            .map(|c| c.split_whitespace().map(String::from).collect()),
+        cmd: service.command_args(),
+        entrypoint: service.entrypoint.clone(),
+        working_dir: service.working_dir.clone(),
+        hostname: service.hostname.clone(),
         healthcheck,
         networking_config,
+        user: service.user.clone(),
+        stop_signal: service.stop_signal.clone(),
+        tty: service.tty,
+        open_stdin: service.stdin_open,
+        stop_timeout: service.stop_grace_period.map(|secs| secs as i64),
         ..Default::default()
     };
 
     let options = CreateContainerOptions {
-        name: Some(format!("{}-{}-{}", project_name, stage_name, service_name)),
+        name: Some(fleetflow_core::container_name(
+            service.container_name.as_deref(),
+            project_name,
+            stage_name,
+            service_name,
+        )),
         ..Default::default()
     };
 
     (config, options)
 }
 
+/// サービスの stop_signal / stop_grace_period を停止オプションに変換
+///
+/// どちらも未指定なら None（コンテナ作成時の設定・デーモン既定値を使う）。
+pub fn stop_container_options(service: &Service) -> Option<StopContainerOptions> {
+    if service.stop_signal.is_none() && service.stop_grace_period.is_none() {
+        return None;
+    }
+    Some(StopContainerOptions {
+        signal: service.stop_signal.clone(),
+        t: service
+            .stop_grace_period
+            .map(|secs| i32::try_from(secs).unwrap_or(i32::MAX)),
+    })
+}
+
+/// 空のリストは Docker API に渡さない（デーモン既定値を維持する）
+fn non_empty(values: &[String]) -> Option<Vec<String>> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.to_vec())
+    }
+}
+
 /// ステージに含まれるサービスのリストを取得
 pub fn get_stage_services(flow: &Flow, stage_name: &str) -> Result<Vec<String>, String> {
     flow.stages
@@ -204,6 +318,13 @@ mod tests {
     use std::collections::HashMap;
     use std::path::PathBuf;
 
+    fn flow(name: &str) -> Flow {
+        Flow {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_service_to_container_config_basic() {
         let service = Service {
@@ -213,7 +334,7 @@ mod tests {
         };
 
         let (config, options) =
-            service_to_container_config("postgres", &service, "local", "vantage");
+            service_to_container_config("postgres", &service, "local", &flow("vantage"));
 
         assert_eq!(config.image, Some("postgres:16".to_string()));
         assert_eq!(options.name, Some("vantage-local-postgres".to_string()));
@@ -223,7 +344,7 @@ mod tests {
     fn test_service_to_container_config_default_image() {
         let service = Service::default();
 
-        let (config, _) = service_to_container_config("redis", &service, "local", "test");
+        let (config, _) = service_to_container_config("redis", &service, "local", &flow("test"));
 
         assert_eq!(config.image, Some("redis:latest".to_string()));
     }
@@ -242,7 +363,7 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("api", &service, "local", "test");
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
 
         let env = config.env.unwrap();
         assert!(env.contains(&"DATABASE_URL=postgres://localhost".to_string()));
@@ -257,12 +378,16 @@ mod tests {
                 container: 3000,
                 protocol: Protocol::Tcp,
                 host_ip: None,
+                name: None,
+                app_protocol: None,
             },
             Port {
                 host: 5432,
                 container: 5432,
                 protocol: Protocol::Tcp,
                 host_ip: Some("127.0.0.1".to_string()),
+                name: None,
+                app_protocol: None,
             },
         ];
 
@@ -271,7 +396,7 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("web", &service, "local", "test");
+        let (config, _) = service_to_container_config("web", &service, "local", &flow("test"));
 
         let exposed_ports = config.exposed_ports.unwrap();
         assert!(exposed_ports.contains(&"3000/tcp".to_string()));
@@ -295,6 +420,8 @@ mod tests {
             container: 53,
             protocol: Protocol::Udp,
             host_ip: None,
+            name: None,
+            app_protocol: None,
         }];
 
         let service = Service {
@@ -302,12 +429,102 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("dns", &service, "local", "test");
+        let (config, _) = service_to_container_config("dns", &service, "local", &flow("test"));
 
         let exposed_ports = config.exposed_ports.unwrap();
         assert!(exposed_ports.contains(&"53/udp".to_string()));
     }
 
+    #[test]
+    fn test_service_to_container_config_with_network_mode() {
+        let service = Service {
+            network_mode: Some(fleetflow_core::NetworkMode::Container("vpn".to_string())),
+            ports: vec![Port {
+                host: 8080,
+                container: 80,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("app", &service, "local", &flow("myapp"));
+
+        let host_config = config.host_config.unwrap();
+        assert_eq!(
+            host_config.network_mode.as_deref(),
+            Some("container:myapp-local-vpn")
+        );
+        assert!(host_config.port_bindings.is_none());
+        assert!(config.exposed_ports.is_none());
+        assert!(config.networking_config.is_none());
+    }
+
+    #[test]
+    fn test_network_mode_follows_container_name_template() {
+        let mut services = HashMap::new();
+        services.insert("vpn".to_string(), Service::default());
+        services.insert(
+            "db".to_string(),
+            Service {
+                container_name: Some("shared-db".to_string()),
+                ..Default::default()
+            },
+        );
+        let flow = Flow {
+            name: "myapp".to_string(),
+            container_name_template: Some("{service}.{project}.{stage}".to_string()),
+            services,
+            ..Default::default()
+        };
+
+        let network_mode = |target: &str| {
+            let service = Service {
+                network_mode: Some(fleetflow_core::NetworkMode::Container(target.to_string())),
+                ..Default::default()
+            };
+            let (config, _) = service_to_container_config("app", &service, "local", &flow);
+            config.host_config.unwrap().network_mode.unwrap()
+        };
+        assert_eq!(network_mode("vpn"), "container:vpn.myapp.local");
+        assert_eq!(network_mode("db"), "container:shared-db");
+    }
+
+    #[test]
+    fn test_service_to_container_config_binds_same_container_port_twice() {
+        let port = |host: u16, container: u16, protocol: Protocol, host_ip: Option<&str>| Port {
+            host,
+            container,
+            protocol,
+            host_ip: host_ip.map(str::to_string),
+            name: None,
+            app_protocol: None,
+        };
+        let service = Service {
+            ports: vec![
+                port(8080, 80, Protocol::Tcp, None),
+                port(8081, 80, Protocol::Tcp, Some("127.0.0.1")),
+                port(7000, 7000, Protocol::Udp, None),
+                port(7001, 7001, Protocol::Udp, None),
+            ],
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("app", &service, "local", &flow("test"));
+
+        let exposed_ports = config.exposed_ports.unwrap();
+        assert_eq!(exposed_ports, vec!["80/tcp", "7000/udp", "7001/udp"]);
+
+        let port_bindings = config.host_config.unwrap().port_bindings.unwrap();
+        let binding_80 = port_bindings.get("80/tcp").unwrap().as_ref().unwrap();
+        assert_eq!(binding_80.len(), 2);
+        assert_eq!(binding_80[1].host_port, Some("8081".to_string()));
+        assert_eq!(binding_80[1].host_ip, Some("127.0.0.1".to_string()));
+        assert!(port_bindings.contains_key("7001/udp"));
+    }
+
     #[test]
     fn test_service_to_container_config_with_volumes() {
         let volumes = vec![
@@ -328,7 +545,7 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("db", &service, "local", "test");
+        let (config, _) = service_to_container_config("db", &service, "local", &flow("test"));
 
         let host_config = config.host_config.unwrap();
         let binds = host_config.binds.unwrap();
@@ -345,7 +562,7 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("db", &service, "local", "test");
+        let (config, _) = service_to_container_config("db", &service, "local", &flow("test"));
 
         let cmd = config.cmd.unwrap();
         assert_eq!(cmd, vec!["start", "--user", "root", "--pass", "root"]);
@@ -373,11 +590,7 @@ mod tests {
             name: "test".to_string(),
             services,
             stages,
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
 
         let result = get_stage_services(&flow, "local").unwrap();
@@ -390,13 +603,7 @@ mod tests {
     fn test_get_stage_services_not_found() {
         let flow = Flow {
             name: "test".to_string(),
-            services: HashMap::new(),
-            stages: HashMap::new(),
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
 
         let result = get_stage_services(&flow, "prod");
@@ -407,7 +614,8 @@ mod tests {
     #[test]
     fn test_container_name_format() {
         let service = Service::default();
-        let (_, options) = service_to_container_config("my-service", &service, "dev", "myapp");
+        let (_, options) =
+            service_to_container_config("my-service", &service, "dev", &flow("myapp"));
 
         assert_eq!(options.name, Some("myapp-dev-my-service".to_string()));
     }
@@ -415,7 +623,8 @@ mod tests {
     #[test]
     fn test_orbstack_labels_generation() {
         let service = Service::default();
-        let (config, _) = service_to_container_config("postgres", &service, "local", "vantage");
+        let (config, _) =
+            service_to_container_config("postgres", &service, "local", &flow("vantage"));
 
         let labels = config.labels.unwrap();
 
@@ -436,21 +645,29 @@ mod tests {
             labels.get("fleetflow.service"),
             Some(&"postgres".to_string())
         );
-        assert_eq!(labels.len(), 5);
+        assert_eq!(
+            labels.get(crate::VERSION_LABEL),
+            Some(&env!("CARGO_PKG_VERSION").to_string())
+        );
+        assert_eq!(
+            labels.get(crate::CONFIG_HASH_LABEL),
+            Some(&crate::service_hash(&service))
+        );
     }
 
     #[test]
     fn test_orbstack_labels_with_different_stages() {
         let service = Service::default();
 
-        let (config_local, _) = service_to_container_config("api", &service, "local", "myapp");
+        let (config_local, _) =
+            service_to_container_config("api", &service, "local", &flow("myapp"));
         let labels_local = config_local.labels.unwrap();
         assert_eq!(
             labels_local.get("com.docker.compose.project"),
             Some(&"myapp-local".to_string())
         );
 
-        let (config_prod, _) = service_to_container_config("api", &service, "prod", "myapp");
+        let (config_prod, _) = service_to_container_config("api", &service, "prod", &flow("myapp"));
         let labels_prod = config_prod.labels.unwrap();
         assert_eq!(
             labels_prod.get("com.docker.compose.project"),
@@ -466,7 +683,8 @@ mod tests {
     fn test_orbstack_labels_with_multiple_projects() {
         let service = Service::default();
 
-        let (config_a, _) = service_to_container_config("db", &service, "local", "project-a");
+        let (config_a, _) =
+            service_to_container_config("db", &service, "local", &flow("project-a"));
         let labels_a = config_a.labels.unwrap();
         assert_eq!(
             labels_a.get("com.docker.compose.project"),
@@ -477,7 +695,8 @@ mod tests {
             Some(&"project-a".to_string())
         );
 
-        let (config_b, _) = service_to_container_config("db", &service, "local", "project-b");
+        let (config_b, _) =
+            service_to_container_config("db", &service, "local", &flow("project-b"));
         let labels_b = config_b.labels.unwrap();
         assert_eq!(
             labels_b.get("com.docker.compose.project"),
@@ -507,7 +726,7 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("api", &service, "local", "test");
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
 
         let healthcheck = config.healthcheck.unwrap();
         assert_eq!(
@@ -530,8 +749,199 @@ mod tests {
             ..Default::default()
         };
 
-        let (config, _) = service_to_container_config("api", &service, "local", "test");
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
 
         assert!(config.healthcheck.is_none());
     }
+
+    #[test]
+    fn test_service_to_container_config_with_stop_settings() {
+        let service = Service {
+            stop_signal: Some("SIGQUIT".to_string()),
+            stop_grace_period: Some(45),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("db", &service, "local", &flow("test"));
+        assert_eq!(config.stop_signal.as_deref(), Some("SIGQUIT"));
+        assert_eq!(config.stop_timeout, Some(45));
+
+        let options = stop_container_options(&service).unwrap();
+        assert_eq!(options.signal.as_deref(), Some("SIGQUIT"));
+        assert_eq!(options.t, Some(45));
+
+        assert!(stop_container_options(&Service::default()).is_none());
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_logging() {
+        use fleetflow_core::LoggingConfig;
+
+        let service = Service {
+            logging: Some(LoggingConfig {
+                driver: Some("json-file".to_string()),
+                max_size: Some("10m".to_string()),
+                max_file: Some(3),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
+
+        let log_config = config.host_config.unwrap().log_config.unwrap();
+        assert_eq!(log_config.typ, Some("json-file".to_string()));
+        let opts = log_config.config.unwrap();
+        assert_eq!(opts.get("max-size"), Some(&"10m".to_string()));
+        assert_eq!(opts.get("max-file"), Some(&"3".to_string()));
+    }
+
+    #[test]
+    fn test_service_to_container_config_without_logging() {
+        let service = Service::default();
+
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
+
+        assert!(config.host_config.unwrap().log_config.is_none());
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_security_options() {
+        let service = Service {
+            user: Some("1000:1000".to_string()),
+            cap_add: vec!["NET_BIND_SERVICE".to_string()],
+            cap_drop: vec!["ALL".to_string()],
+            read_only: Some(true),
+            security_opt: vec!["no-new-privileges:true".to_string()],
+            privileged: Some(false),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
+
+        assert_eq!(config.user, Some("1000:1000".to_string()));
+        let host_config = config.host_config.unwrap();
+        assert_eq!(
+            host_config.cap_add,
+            Some(vec!["NET_BIND_SERVICE".to_string()])
+        );
+        assert_eq!(host_config.cap_drop, Some(vec!["ALL".to_string()]));
+        assert_eq!(host_config.readonly_rootfs, Some(true));
+        assert_eq!(
+            host_config.security_opt,
+            Some(vec!["no-new-privileges:true".to_string()])
+        );
+        assert_eq!(host_config.privileged, Some(false));
+    }
+
+    #[test]
+    fn test_service_to_container_config_without_security_options() {
+        let service = Service::default();
+
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
+
+        assert!(config.user.is_none());
+        let host_config = config.host_config.unwrap();
+        assert!(host_config.cap_add.is_none());
+        assert!(host_config.cap_drop.is_none());
+        assert!(host_config.readonly_rootfs.is_none());
+        assert!(host_config.privileged.is_none());
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_tty() {
+        let service = Service {
+            tty: Some(true),
+            stdin_open: Some(true),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("repl", &service, "local", &flow("test"));
+
+        assert_eq!(config.tty, Some(true));
+        assert_eq!(config.open_stdin, Some(true));
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_tmpfs_and_shm_size() {
+        use fleetflow_core::TmpfsMount;
+
+        let service = Service {
+            tmpfs: vec![
+                TmpfsMount {
+                    target: PathBuf::from("/tmp"),
+                    size: Some("64m".to_string()),
+                    mode: None,
+                },
+                TmpfsMount {
+                    target: PathBuf::from("/run"),
+                    size: None,
+                    mode: Some("1777".to_string()),
+                },
+            ],
+            shm_size: Some("1g".to_string()),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("chrome", &service, "local", &flow("test"));
+
+        let host_config = config.host_config.unwrap();
+        let tmpfs = host_config.tmpfs.unwrap();
+        assert_eq!(tmpfs.get("/tmp"), Some(&"size=64m".to_string()));
+        assert_eq!(tmpfs.get("/run"), Some(&"mode=1777".to_string()));
+        assert_eq!(host_config.shm_size, Some(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_ulimits_and_sysctls() {
+        use fleetflow_core::Ulimit;
+
+        let mut sysctls = HashMap::new();
+        sysctls.insert("net.core.somaxconn".to_string(), "1024".to_string());
+
+        let service = Service {
+            ulimits: vec![Ulimit {
+                name: "nofile".to_string(),
+                soft: 65536,
+                hard: 65536,
+            }],
+            sysctls,
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("redis", &service, "local", &flow("test"));
+
+        let host_config = config.host_config.unwrap();
+        let ulimits = host_config.ulimits.unwrap();
+        assert_eq!(ulimits.len(), 1);
+        assert_eq!(ulimits[0].name, Some("nofile".to_string()));
+        assert_eq!(ulimits[0].soft, Some(65536));
+        assert_eq!(ulimits[0].hard, Some(65536));
+        assert_eq!(
+            host_config.sysctls.unwrap().get("net.core.somaxconn"),
+            Some(&"1024".to_string())
+        );
+    }
+
+    #[test]
+    fn test_service_to_container_config_with_process_settings() {
+        let service = Service {
+            command: Some("npm run 'dev server'".to_string()),
+            entrypoint: Some(vec!["/usr/bin/tini".to_string(), "--".to_string()]),
+            working_dir: Some("/app".to_string()),
+            hostname: Some("api-host".to_string()),
+            labels: HashMap::from([("com.example.team".to_string(), "api".to_string())]),
+            ..Default::default()
+        };
+
+        let (config, _) = service_to_container_config("api", &service, "local", &flow("test"));
+
+        assert_eq!(config.cmd.unwrap(), vec!["npm", "run", "dev server"]);
+        assert_eq!(config.entrypoint.unwrap(), vec!["/usr/bin/tini", "--"]);
+        assert_eq!(config.working_dir.as_deref(), Some("/app"));
+        assert_eq!(config.hostname.as_deref(), Some("api-host"));
+        let labels = config.labels.unwrap();
+        assert_eq!(labels["com.example.team"], "api");
+        assert_eq!(labels[crate::labels::SERVICE_LABEL], "api");
+    }
 }
@@ -1,18 +1,204 @@
 use crate::error::{ContainerError, Result};
 use bollard::Docker;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-/// Docker接続を初期化
-pub async fn init_docker() -> Result<Docker> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| ContainerError::DockerConnectionFailed(e.to_string()))?;
+/// ソケット接続のタイムアウト（秒）
+const SOCKET_TIMEOUT_SECS: u64 = 120;
 
-    // 接続テスト
-    docker
-        .ping()
-        .await
-        .map_err(|e| ContainerError::DockerConnectionFailed(e.to_string()))?;
+/// bollard の既定の解釈（`DOCKER_HOST`、Windows の名前付きパイプなど）で接続する接続先
+const DOCKER_HOST_SOURCE: &str = "DOCKER_HOST";
+const DEFAULT_SOURCE: &str = "既定";
+
+/// 再接続の待機時間の上限
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 接続リトライの設定
+///
+/// OrbStack / Docker Desktop の再起動中などデーモンが一時的に応答しない間、
+/// 接続を `attempts` 回まで試す。待機時間は `interval` から倍々に延ばす。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectRetry {
+    /// 接続を試みる回数（1 ならリトライしない）
+    pub attempts: u32,
+    /// 初回の待機時間
+    pub interval: Duration,
+}
+
+impl Default for ConnectRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ConnectRetry {
+    /// `attempt` 回目（1 始まり）の失敗後の待機時間
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.interval.saturating_mul(factor).min(MAX_RETRY_INTERVAL)
+    }
+}
+
+/// Docker の接続先
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerEndpoint {
+    /// 接続先の種類（`DOCKER_HOST` / `docker-socket` 設定 / Colima など）
+    pub source: &'static str,
+    /// ソケットパスまたは URL
+    pub address: String,
+}
+
+impl std::fmt::Display for DockerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.source, self.address)
+    }
+}
+
+/// 既知のソケットの場所（優先順）
+///
+/// 標準の `/var/run/docker.sock` が無い環境（Colima / Lima / rootless Docker / Podman など）
+/// でも接続できるよう、各ツールの既定のソケットを順に探す。
+fn known_sockets(home: Option<&Path>, runtime_dir: Option<&Path>) -> Vec<(&'static str, PathBuf)> {
+    let mut sockets = vec![("Docker", PathBuf::from("/var/run/docker.sock"))];
+    if let Some(home) = home {
+        sockets.extend([
+            ("OrbStack", home.join(".orbstack/run/docker.sock")),
+            ("Docker Desktop", home.join(".docker/run/docker.sock")),
+            ("Colima", home.join(".colima/default/docker.sock")),
+            ("Colima", home.join(".colima/docker.sock")),
+            ("Lima", home.join(".lima/docker/sock/docker.sock")),
+            ("Rancher Desktop", home.join(".rd/docker.sock")),
+        ]);
+    }
+    if let Some(runtime_dir) = runtime_dir {
+        sockets.extend([
+            ("rootless Docker", runtime_dir.join("docker.sock")),
+            ("Podman (rootless)", runtime_dir.join("podman/podman.sock")),
+        ]);
+    }
+    sockets.push(("Podman", PathBuf::from("/run/podman/podman.sock")));
+    sockets
+}
+
+/// 接続先の候補（試行順）
+///
+/// `DOCKER_HOST` または `socket_override`（グローバル設定の `docker-socket`）が
+/// あればそれだけを使う。無ければ存在するソケットを既知の場所から探す。
+pub fn docker_endpoint_candidates(socket_override: Option<&str>) -> Vec<DockerEndpoint> {
+    if let Some(host) = std::env::var("DOCKER_HOST").ok().filter(|h| !h.is_empty()) {
+        return vec![DockerEndpoint {
+            source: DOCKER_HOST_SOURCE,
+            address: host,
+        }];
+    }
+    if let Some(socket) = socket_override {
+        return vec![DockerEndpoint {
+            source: "docker-socket 設定",
+            address: socket.to_string(),
+        }];
+    }
 
-    Ok(docker)
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    let endpoints = existing_sockets(known_sockets(home.as_deref(), runtime_dir.as_deref()));
+    if endpoints.is_empty() {
+        // 既知のソケットが無い環境（Windows など）は bollard の既定に任せる
+        return vec![DockerEndpoint {
+            source: DEFAULT_SOURCE,
+            address: "local defaults".to_string(),
+        }];
+    }
+    endpoints
+}
+
+/// 存在するソケットだけを残す（シンボリックリンクで同じ実体を指すものは 1 つにまとめる）
+fn existing_sockets(sockets: Vec<(&'static str, PathBuf)>) -> Vec<DockerEndpoint> {
+    let mut seen = Vec::new();
+    let mut endpoints = Vec::new();
+    for (source, path) in sockets {
+        let Ok(resolved) = path.canonicalize() else {
+            continue;
+        };
+        if seen.contains(&resolved) {
+            continue;
+        }
+        seen.push(resolved);
+        endpoints.push(DockerEndpoint {
+            source,
+            address: path.display().to_string(),
+        });
+    }
+    endpoints
+}
+
+fn connect_endpoint(
+    endpoint: &DockerEndpoint,
+) -> std::result::Result<Docker, bollard::errors::Error> {
+    match endpoint.source {
+        // DOCKER_HOST は tcp:// / ssh:// なども含めて bollard の既定の解釈に任せる
+        DOCKER_HOST_SOURCE | DEFAULT_SOURCE => Docker::connect_with_local_defaults(),
+        _ => Docker::connect_with_socket(
+            &endpoint.address,
+            SOCKET_TIMEOUT_SECS,
+            bollard::API_DEFAULT_VERSION,
+        ),
+    }
+}
+
+/// 候補を順に試して Docker に接続し、使った接続先を返す
+pub async fn connect_docker(socket_override: Option<&str>) -> Result<(Docker, DockerEndpoint)> {
+    let mut failures = Vec::new();
+    for endpoint in docker_endpoint_candidates(socket_override) {
+        let result = match connect_endpoint(&endpoint) {
+            Ok(docker) => docker.ping().await.map(|_| docker),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(docker) => {
+                tracing::debug!(endpoint = %endpoint, "Connected to Docker");
+                return Ok((docker, endpoint));
+            }
+            Err(e) => {
+                tracing::debug!(endpoint = %endpoint, error = %e, "Docker endpoint unavailable");
+                failures.push(format!("{}: {}", endpoint, e));
+            }
+        }
+    }
+    Err(ContainerError::DockerConnectionFailed(
+        failures.join("\n  "),
+    ))
+}
+
+/// 接続に失敗したら `retry` に従って待機しながら再試行する
+///
+/// 再試行の前に `on_retry(失敗した回数, 待機時間)` を呼ぶ。
+pub async fn connect_docker_with_retry(
+    socket_override: Option<&str>,
+    retry: &ConnectRetry,
+    mut on_retry: impl FnMut(u32, Duration),
+) -> Result<(Docker, DockerEndpoint)> {
+    let mut attempt = 1;
+    loop {
+        match connect_docker(socket_override).await {
+            Ok(connected) => return Ok(connected),
+            Err(e) if attempt >= retry.attempts => return Err(e),
+            Err(e) => {
+                let delay = retry.delay(attempt);
+                tracing::debug!(attempt, error = %e, "Retrying Docker connection");
+                on_retry(attempt, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Docker接続を初期化
+pub async fn init_docker() -> Result<Docker> {
+    connect_docker(None).await.map(|(docker, _)| docker)
 }
 
 /// Dockerバージョンを取得
@@ -24,3 +210,47 @@ pub async fn get_docker_version() -> Result<String> {
         .map_err(|e| ContainerError::DockerApiError(e.to_string()))?;
     Ok(version.version.unwrap_or_else(|| "unknown".to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        let retry = ConnectRetry {
+            attempts: 10,
+            interval: Duration::from_secs(2),
+        };
+        assert_eq!(retry.delay(1), Duration::from_secs(2));
+        assert_eq!(retry.delay(2), Duration::from_secs(4));
+        assert_eq!(retry.delay(3), Duration::from_secs(8));
+        assert_eq!(retry.delay(10), MAX_RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn test_existing_sockets_in_priority_order() {
+        let home = tempfile::tempdir().unwrap();
+        let runtime = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join(".colima/default")).unwrap();
+        std::fs::write(home.path().join(".colima/default/docker.sock"), "").unwrap();
+        std::fs::write(runtime.path().join("docker.sock"), "").unwrap();
+        // Colima の旧パスが同じ実体を指していても 1 つにまとめる
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            home.path().join(".colima/default/docker.sock"),
+            home.path().join(".colima/docker.sock"),
+        )
+        .unwrap();
+
+        let endpoints: Vec<DockerEndpoint> =
+            existing_sockets(known_sockets(Some(home.path()), Some(runtime.path())))
+                .into_iter()
+                .filter(|e| {
+                    e.address.starts_with(home.path().to_str().unwrap())
+                        || e.address.starts_with(runtime.path().to_str().unwrap())
+                })
+                .collect();
+        let sources: Vec<&str> = endpoints.iter().map(|e| e.source).collect();
+        assert_eq!(sources, vec!["Colima", "rootless Docker"]);
+    }
+}
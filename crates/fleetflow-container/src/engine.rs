@@ -6,11 +6,13 @@
 use std::collections::HashMap;
 
 use bollard::Docker;
+use bollard::auth::DockerCredentials;
 use futures_util::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 
 use crate::converter;
-use fleetflow_core::Flow;
+use crate::maintenance::{ImageEntry, project_build_repositories, retention_candidates};
+use fleetflow_core::{Flow, PruneSchedule};
 
 /// デプロイリクエスト（JSON シリアライズ可能 → Unison で送受信）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,6 +86,40 @@ pub fn order_by_dependencies(services: &[String], flow: &Flow) -> Vec<String> {
     ordered
 }
 
+/// 停止順にサービスを並べる（依存される側を後にする）
+///
+/// 起動順（depends_on のトポロジカル順）の逆順。依存先を停止する前に、
+/// それに依存するサービスを停止してフラッシュ等を完了させる。
+/// 循環や対象外サービスへの依存は無視し、入力順を可能な限り保つ。
+pub fn shutdown_order(services: &[String], flow: &Flow) -> Vec<String> {
+    fn visit(
+        name: &str,
+        services: &[String],
+        flow: &Flow,
+        visiting: &mut Vec<String>,
+        startup: &mut Vec<String>,
+    ) {
+        if startup.iter().any(|s| s == name) || visiting.iter().any(|s| s == name) {
+            return;
+        }
+        visiting.push(name.to_string());
+        if let Some(svc) = flow.services.get(name) {
+            for dep in svc.depends_on.iter().filter(|d| services.contains(d)) {
+                visit(dep, services, flow, visiting, startup);
+            }
+        }
+        visiting.pop();
+        startup.push(name.to_string());
+    }
+
+    let mut startup: Vec<String> = Vec::new();
+    for name in services {
+        visit(name, services, flow, &mut Vec::new(), &mut startup);
+    }
+    startup.reverse();
+    startup
+}
+
 impl DeployEngine {
     pub fn new(docker: Docker) -> Self {
         Self { docker }
@@ -96,7 +132,7 @@ impl DeployEngine {
     /// 2. イメージの pull
     /// 3. ネットワーク作成
     /// 4. コンテナ作成・起動（依存順）
-    /// 5. 不要イメージ・キャッシュの削除
+    /// 5. ステージから外れたコンテナ・不要イメージ・キャッシュの削除
     pub async fn execute(
         &self,
         request: &DeployRequest,
@@ -133,8 +169,14 @@ impl DeployEngine {
             },
         });
         if !request.no_pull {
-            self.pull_images(flow, &request.target_services, &on_event, &mut log)
-                .await;
+            self.pull_images(
+                flow,
+                stage_name,
+                &request.target_services,
+                &on_event,
+                &mut log,
+            )
+            .await;
         }
         on_event(DeployEvent::StepCompleted { step: 2 });
 
@@ -166,7 +208,8 @@ impl DeployEngine {
         .await?;
         on_event(DeployEvent::StepCompleted { step: 4 });
 
-        // Step 5: 不要イメージ・キャッシュ削除
+        // Step 5: 不要イメージ・キャッシュ削除（maintenance ポリシーに従う）
+        let maintenance = flow.maintenance.clone().unwrap_or_default();
         on_event(DeployEvent::StepStarted {
             step: 5,
             total: 5,
@@ -177,7 +220,16 @@ impl DeployEngine {
             },
         });
         if !request.no_prune {
-            self.prune(&on_event, &mut log).await;
+            self.remove_orphans(flow, stage_name, &on_event, &mut log)
+                .await;
+            if maintenance.prune_schedule != PruneSchedule::Never {
+                self.prune(maintenance.prune_schedule, &on_event, &mut log)
+                    .await;
+            }
+            if let Some(keep) = maintenance.image_retention {
+                self.enforce_retention(flow, stage_name, keep as usize, &on_event, &mut log)
+                    .await;
+            }
         }
         on_event(DeployEvent::StepCompleted { step: 5 });
 
@@ -193,6 +245,24 @@ impl DeployEngine {
         })
     }
 
+    /// 実行環境の準備のみを行う（イメージの pull とネットワーク作成）
+    ///
+    /// 既存コンテナには触れないため何度実行してもよい。deploy の Step 2–3 に相当する。
+    pub async fn prepare(
+        &self,
+        flow: &Flow,
+        stage_name: &str,
+        services: &[String],
+        on_event: impl Fn(DeployEvent),
+    ) -> anyhow::Result<Vec<String>> {
+        let mut log: Vec<String> = Vec::new();
+        self.pull_images(flow, stage_name, services, &on_event, &mut log)
+            .await;
+        let network_name = converter::get_network_name(&flow.name, stage_name);
+        self.ensure_network(&network_name, &mut log).await?;
+        Ok(log)
+    }
+
     /// Step 1: 既存コンテナの停止・削除
     async fn stop_and_remove(
         &self,
@@ -202,66 +272,153 @@ impl DeployEngine {
         on_event: &impl Fn(DeployEvent),
         log: &mut Vec<String>,
     ) {
-        for service_name in services {
-            let container_name = format!("{}-{}-{}", flow.name, stage_name, service_name);
-
-            // 停止
-            match self
-                .docker
-                .stop_container(
-                    &container_name,
-                    None::<bollard::query_parameters::StopContainerOptions>,
-                )
+        for service_name in &shutdown_order(services, flow) {
+            let stop_options = flow
+                .services
+                .get(service_name)
+                .and_then(converter::stop_container_options);
+            for container_name in self
+                .service_containers(flow, stage_name, service_name)
                 .await
             {
-                Ok(_) => {
-                    on_event(DeployEvent::ServiceProgress {
-                        service: service_name.clone(),
-                        action: "stopped".into(),
-                    });
-                    log.push(format!("{}: stopped", service_name));
-                }
-                Err(bollard::errors::Error::DockerResponseServerError {
-                    status_code: 404, ..
-                }) => {
-                    log.push(format!("{}: no container", service_name));
-                }
-                Err(bollard::errors::Error::DockerResponseServerError {
-                    status_code: 304, ..
-                }) => {
-                    log.push(format!("{}: already stopped", service_name));
-                }
-                Err(e) => {
-                    log.push(format!("{}: stop error: {}", service_name, e));
-                }
-            }
-
-            // 削除（強制）
-            match self
-                .docker
-                .remove_container(
+                self.stop_and_remove_container(
+                    service_name,
                     &container_name,
-                    Some(bollard::query_parameters::RemoveContainerOptions {
-                        force: true,
-                        ..Default::default()
-                    }),
+                    stop_options.clone(),
+                    on_event,
+                    log,
                 )
+                .await;
+            }
+        }
+    }
+
+    /// サービスのコンテナ名（ラベルで見つかったもの + 現在の設定で決まる名前）
+    ///
+    /// ラベルの無い古いコンテナが同名で残っていると作成に失敗するため、現在の名前も含める。
+    async fn service_containers(
+        &self,
+        flow: &Flow,
+        stage_name: &str,
+        service_name: &str,
+    ) -> Vec<String> {
+        let mut names: Vec<String> = crate::labels::find_containers(
+            &self.docker,
+            &flow.name,
+            Some(stage_name),
+            Some(service_name),
+        )
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter_map(crate::labels::summary_name)
+        .map(str::to_string)
+        .collect();
+        let current = flow.container_name(stage_name, service_name);
+        if !names.contains(&current) {
+            names.push(current);
+        }
+        names
+    }
+
+    /// コンテナを停止して強制削除する
+    async fn stop_and_remove_container(
+        &self,
+        service_name: &str,
+        container_name: &str,
+        stop_options: Option<bollard::query_parameters::StopContainerOptions>,
+        on_event: &impl Fn(DeployEvent),
+        log: &mut Vec<String>,
+    ) {
+        // 停止
+        match self
+            .docker
+            .stop_container(container_name, stop_options)
+            .await
+        {
+            Ok(_) => {
+                on_event(DeployEvent::ServiceProgress {
+                    service: service_name.to_string(),
+                    action: "stopped".into(),
+                });
+                log.push(format!("{}: stopped", service_name));
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {
+                log.push(format!("{}: no container", service_name));
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 304, ..
+            }) => {
+                log.push(format!("{}: already stopped", service_name));
+            }
+            Err(e) => {
+                log.push(format!("{}: stop error: {}", service_name, e));
+            }
+        }
+
+        // 削除（強制）
+        match self
+            .docker
+            .remove_container(
+                container_name,
+                Some(bollard::query_parameters::RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            Ok(_) => {
+                on_event(DeployEvent::ServiceProgress {
+                    service: service_name.to_string(),
+                    action: "removed".into(),
+                });
+                log.push(format!("{}: removed", service_name));
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => {
+                log.push(format!("{}: remove error: {}", service_name, e));
+            }
+        }
+    }
+
+    /// Step 5: ステージの定義から外れたサービスのコンテナを削除する（ラベルで判定）
+    async fn remove_orphans(
+        &self,
+        flow: &Flow,
+        stage_name: &str,
+        on_event: &impl Fn(DeployEvent),
+        log: &mut Vec<String>,
+    ) {
+        let Some(stage) = flow.stages.get(stage_name) else {
+            return;
+        };
+        let containers =
+            match crate::labels::find_containers(&self.docker, &flow.name, Some(stage_name), None)
                 .await
             {
-                Ok(_) => {
-                    on_event(DeployEvent::ServiceProgress {
-                        service: service_name.clone(),
-                        action: "removed".into(),
-                    });
-                    log.push(format!("{}: removed", service_name));
-                }
-                Err(bollard::errors::Error::DockerResponseServerError {
-                    status_code: 404, ..
-                }) => {}
+                Ok(containers) => containers,
                 Err(e) => {
-                    log.push(format!("{}: remove error: {}", service_name, e));
+                    log.push(format!("orphan lookup error: {}", e));
+                    return;
                 }
+            };
+        for container in &containers {
+            let (Some(service_name), Some(container_name)) = (
+                crate::labels::label(container, crate::labels::SERVICE_LABEL),
+                crate::labels::summary_name(container),
+            ) else {
+                continue;
+            };
+            if stage.services.iter().any(|s| s == service_name) {
+                continue;
             }
+            self.stop_and_remove_container(service_name, container_name, None, on_event, log)
+                .await;
         }
     }
 
@@ -269,6 +426,7 @@ impl DeployEngine {
     async fn pull_images(
         &self,
         flow: &Flow,
+        stage_name: &str,
         services: &[String],
         on_event: &impl Fn(DeployEvent),
         log: &mut Vec<String>,
@@ -277,12 +435,18 @@ impl DeployEngine {
             if let Some(svc) = flow.services.get(service_name)
                 && let Some(image) = &svc.image
             {
+                // サーバー上でビルドしたイメージはレジストリに存在しない
+                if svc.build.as_ref().is_some_and(|b| b.remote) {
+                    log.push(format!("{}: built on server, skip pull", service_name));
+                    continue;
+                }
+
                 on_event(DeployEvent::ServiceProgress {
                     service: service_name.clone(),
                     action: format!("pulling {}", image),
                 });
 
-                match self.pull_image(image).await {
+                match self.pull_image(flow, stage_name, image).await {
                     Ok(_) => {
                         log.push(format!("{}: pulled {}", service_name, image));
                     }
@@ -295,7 +459,21 @@ impl DeployEngine {
     }
 
     /// 単一イメージを pull
-    async fn pull_image(&self, image: &str) -> anyhow::Result<()> {
+    ///
+    /// ステージの `registry` ブロックで認証情報を宣言していれば、そのレジストリのイメージに使う。
+    async fn pull_image(&self, flow: &Flow, stage_name: &str, image: &str) -> anyhow::Result<()> {
+        let credentials = match flow.stages.get(stage_name) {
+            Some(stage) => stage.registry_credentials()?,
+            None => None,
+        }
+        .filter(|creds| fleetflow_core::registry_host(image) == creds.registry)
+        .map(|creds| DockerCredentials {
+            username: Some(creds.username),
+            password: Some(creds.password),
+            serveraddress: Some(creds.registry),
+            ..Default::default()
+        });
+
         let (image_name, tag) = if let Some((name, tag)) = image.split_once(':') {
             (name, tag)
         } else {
@@ -308,7 +486,7 @@ impl DeployEngine {
             ..Default::default()
         };
 
-        let mut stream = self.docker.create_image(Some(options), None, None);
+        let mut stream = self.docker.create_image(Some(options), None, credentials);
 
         while let Some(info) = stream.next().await {
             match info {
@@ -375,12 +553,8 @@ impl DeployEngine {
                 action: "creating".into(),
             });
 
-            let (container_config, create_options) = converter::service_to_container_config(
-                service_name,
-                service_def,
-                stage_name,
-                &flow.name,
-            );
+            let (mut container_config, create_options) =
+                converter::service_to_container_config(service_name, service_def, stage_name, flow);
 
             let image = container_config.image.as_ref().ok_or_else(|| {
                 anyhow::anyhow!("サービス '{}' のイメージ設定が見つかりません", service_name)
@@ -394,13 +568,15 @@ impl DeployEngine {
                         status_code: 404,
                         ..
                     }) => {
-                        self.pull_image(image).await?;
+                        self.pull_image(flow, stage_name, image).await?;
                         log.push(format!("{}: auto-pulled {}", service_name, image));
                     }
                     Err(e) => return Err(e.into()),
                 }
             }
 
+            crate::inject_wait(&self.docker, service_def, flow, &mut container_config).await?;
+
             // コンテナ作成
             self.docker
                 .create_container(Some(create_options), container_config)
@@ -413,7 +589,7 @@ impl DeployEngine {
                 && !service_def.depends_on.is_empty()
             {
                 for dep_service in &service_def.depends_on {
-                    let dep_container = format!("{}-{}-{}", flow.name, stage_name, dep_service);
+                    let dep_container = flow.container_name(stage_name, dep_service);
                     match crate::wait_for_service(&self.docker, &dep_container, wait_config).await {
                         Ok(_) => {
                             log.push(format!(
@@ -432,7 +608,7 @@ impl DeployEngine {
             }
 
             // コンテナ起動
-            let container_name = format!("{}-{}-{}", flow.name, stage_name, service_name);
+            let container_name = flow.container_name(stage_name, service_name);
             self.docker
                 .start_container(
                     &container_name,
@@ -452,10 +628,20 @@ impl DeployEngine {
     }
 
     /// Step 5: 不要イメージ・キャッシュ削除
-    async fn prune(&self, on_event: &impl Fn(DeployEvent), log: &mut Vec<String>) {
-        // 1 週間以上古い未使用イメージを削除
+    ///
+    /// `schedule` の期間（既定は 1 週間）より古い未使用のものだけを削除する。
+    async fn prune(
+        &self,
+        schedule: PruneSchedule,
+        on_event: &impl Fn(DeployEvent),
+        log: &mut Vec<String>,
+    ) {
+        let until = schedule.until_filter();
+
         let mut image_filters = HashMap::new();
-        image_filters.insert("until".to_string(), vec!["168h".to_string()]);
+        if let Some(until) = until {
+            image_filters.insert("until".to_string(), vec![until.to_string()]);
+        }
         image_filters.insert("dangling".to_string(), vec!["true".to_string()]);
 
         let prune_opts = bollard::query_parameters::PruneImagesOptions {
@@ -486,7 +672,9 @@ impl DeployEngine {
 
         // ビルドキャッシュの削除
         let mut build_filters = HashMap::new();
-        build_filters.insert("until".to_string(), vec!["168h".to_string()]);
+        if let Some(until) = until {
+            build_filters.insert("until".to_string(), vec![until.to_string()]);
+        }
 
         let build_prune_opts = bollard::query_parameters::PruneBuildOptions {
             filters: Some(build_filters),
@@ -509,6 +697,83 @@ impl DeployEngine {
             }
         }
     }
+
+    /// Step 5: プロジェクトのビルドイメージを新しい順に `keep` 個だけ残す
+    async fn enforce_retention(
+        &self,
+        flow: &Flow,
+        stage_name: &str,
+        keep: usize,
+        on_event: &impl Fn(DeployEvent),
+        log: &mut Vec<String>,
+    ) {
+        let images = match self
+            .docker
+            .list_images(Some(bollard::query_parameters::ListImagesOptions {
+                all: false,
+                ..Default::default()
+            }))
+            .await
+        {
+            Ok(images) => images,
+            Err(e) => {
+                log.push(format!("image retention error: {}", e));
+                return;
+            }
+        };
+        let containers = match self
+            .docker
+            .list_containers(Some(bollard::query_parameters::ListContainersOptions {
+                all: true,
+                ..Default::default()
+            }))
+            .await
+        {
+            Ok(containers) => containers,
+            Err(e) => {
+                log.push(format!("image retention error: {}", e));
+                return;
+            }
+        };
+
+        let entries: Vec<ImageEntry> = images
+            .iter()
+            .flat_map(|image| {
+                image.repo_tags.iter().map(|repo_tag| ImageEntry {
+                    repo_tag: repo_tag.clone(),
+                    id: image.id.clone(),
+                    created: image.created,
+                })
+            })
+            .collect();
+        let in_use: Vec<String> = containers
+            .into_iter()
+            .flat_map(|c| [c.image, c.image_id])
+            .flatten()
+            .collect();
+        let repositories = project_build_repositories(flow, stage_name);
+
+        for repo_tag in retention_candidates(&entries, &repositories, keep, &in_use) {
+            match self
+                .docker
+                .remove_image(
+                    &repo_tag,
+                    None::<bollard::query_parameters::RemoveImageOptions>,
+                    None,
+                )
+                .await
+            {
+                Ok(_) => {
+                    on_event(DeployEvent::ServiceProgress {
+                        service: "prune".into(),
+                        action: format!("removed old image {}", repo_tag),
+                    });
+                    log.push(format!("removed old image {}", repo_tag));
+                }
+                Err(e) => log.push(format!("image retention error ({}): {}", repo_tag, e)),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -536,11 +801,7 @@ mod tests {
             name: "test-project".to_string(),
             services: svc_map,
             stages,
-            providers: std::collections::HashMap::new(),
-            servers: std::collections::HashMap::new(),
-            registry: None,
-            variables: std::collections::HashMap::new(),
-            tenant: None,
+            ..Default::default()
         }
     }
 
@@ -600,6 +861,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_shutdown_order_stops_dependents_first() {
+        let flow = make_test_flow(
+            vec![
+                ("db", Service::default()),
+                (
+                    "api",
+                    Service {
+                        depends_on: vec!["db".into(), "cache".into()],
+                        ..Default::default()
+                    },
+                ),
+                ("cache", Service::default()),
+                (
+                    "web",
+                    Service {
+                        depends_on: vec!["api".into()],
+                        ..Default::default()
+                    },
+                ),
+            ],
+            vec!["db", "api", "cache", "web"],
+        );
+        let services = vec!["db".into(), "api".into(), "cache".into(), "web".into()];
+        let ordered = shutdown_order(&services, &flow);
+        assert_eq!(ordered, vec!["web", "api", "cache", "db"]);
+    }
+
+    #[test]
+    fn test_shutdown_order_tolerates_cycles() {
+        let flow = make_test_flow(
+            vec![
+                (
+                    "a",
+                    Service {
+                        depends_on: vec!["b".into()],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "b",
+                    Service {
+                        depends_on: vec!["a".into()],
+                        ..Default::default()
+                    },
+                ),
+            ],
+            vec!["a", "b"],
+        );
+        let services = vec!["a".into(), "b".into()];
+        let ordered = shutdown_order(&services, &flow);
+        assert_eq!(ordered.len(), 2);
+    }
+
     #[test]
     fn test_order_by_dependencies_no_deps() {
         let flow = make_test_flow(
@@ -36,6 +36,13 @@ pub enum ContainerError {
         "サービス '{service}' の準備完了を待機中にタイムアウトしました（{max_retries}回リトライ）\n\nヒント:\n  • 依存サービスが正常に起動しているか確認してください\n  • wait_forのmax_retriesを増やしてみてください"
     )]
     ServiceWaitTimeout { service: String, max_retries: u32 },
+
+    #[error("'{target}' が {timeout_secs} 秒以内に {condition} になりませんでした")]
+    WaitTimeout {
+        target: String,
+        condition: String,
+        timeout_secs: u64,
+    },
 }
 
 impl From<bollard::errors::Error> for ContainerError {
@@ -68,4 +75,33 @@ impl From<bollard::errors::Error> for ContainerError {
     }
 }
 
+impl ContainerError {
+    /// 診断コード（`fleet::docker::*`）
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContainerError::DockerConnectionFailed(_) => "fleet::docker::connection_failed",
+            ContainerError::ContainerNotFound { .. } => "fleet::docker::container_not_found",
+            ContainerError::ContainerAlreadyRunning { .. } => "fleet::docker::already_running",
+            ContainerError::ContainerAlreadyStopped { .. } => "fleet::docker::already_stopped",
+            ContainerError::ImageNotFound { .. } => "fleet::docker::image_not_found",
+            ContainerError::PortAlreadyInUse { .. } => "fleet::docker::port_in_use",
+            ContainerError::DockerApiError(_) => "fleet::docker::api_error",
+            ContainerError::ConfigError(_) => "fleet::docker::config",
+            ContainerError::ServiceWaitTimeout { .. } | ContainerError::WaitTimeout { .. } => {
+                "fleet::docker::wait_timeout"
+            }
+        }
+    }
+
+    /// 解決のヒント（メッセージに含まれていないもの）
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            ContainerError::ContainerNotFound { .. } | ContainerError::WaitTimeout { .. } => {
+                Some("fleet ps でコンテナの状態を確認してください")
+            }
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ContainerError>;
@@ -0,0 +1,133 @@
+//! ホストサービスの systemd ユニット生成 — KDL `host_service` → `.service` ファイル
+//!
+//! コンテナではなくホストで直接動かすコンポーネント（cloudflared、node_exporter 等）を
+//! system スコープの systemd ユニットとして記述する。
+//!
+//! 本モジュールは **純粋関数のみ**（SSH 越しの配置や systemctl 実行は CLI 側）。
+//!
+//! 規約:
+//! - ユニット名 `fleetflow-{name}.service`（配布パッケージのユニットと衝突させない）
+//! - 配置先 `/etc/systemd/system/`
+//! - attribution は先頭コメント `# fleetflow.project=...` / `# fleetflow.server=...`
+
+use fleetflow_core::HostService;
+
+/// ホストサービスの配置先ディレクトリ
+pub const HOST_SERVICE_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// ユニット名（`fleetflow-{name}.service`）。
+pub fn host_service_unit_name(name: &str) -> String {
+    format!("fleetflow-{name}.service")
+}
+
+/// ユニットファイルの配置パス。
+pub fn host_service_unit_path(name: &str) -> String {
+    format!("{HOST_SERVICE_UNIT_DIR}/{}", host_service_unit_name(name))
+}
+
+/// `Environment=` の値をクォートする（`"` と `\` をエスケープ）。
+fn systemd_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `HostService` から systemd `.service` ユニットのテキストを生成する（純粋関数）。
+pub fn generate_host_service_unit(
+    project: &str,
+    server: &str,
+    name: &str,
+    host_service: &HostService,
+) -> String {
+    let mut out = String::new();
+    out.push_str("# Generated by fleetflow — DO NOT EDIT\n");
+    out.push_str(&format!("# fleetflow.project={project}\n"));
+    out.push_str(&format!("# fleetflow.server={server}\n"));
+
+    out.push_str("\n[Unit]\n");
+    let description = host_service
+        .description
+        .clone()
+        .unwrap_or_else(|| format!("{name} (fleetflow host service)"));
+    out.push_str(&format!("Description={description}\n"));
+    if !host_service.after.is_empty() {
+        let after = host_service.after.join(" ");
+        out.push_str(&format!("After={after}\n"));
+        out.push_str(&format!("Wants={after}\n"));
+    }
+
+    out.push_str("\n[Service]\n");
+    out.push_str(&format!("ExecStart={}\n", host_service.exec_start));
+    if let Some(user) = &host_service.user {
+        out.push_str(&format!("User={user}\n"));
+    }
+    if let Some(dir) = &host_service.working_directory {
+        out.push_str(&format!("WorkingDirectory={dir}\n"));
+    }
+    // 決定的出力のためキー順ソート
+    let mut env: Vec<(&String, &String)> = host_service.environment.iter().collect();
+    env.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in env {
+        out.push_str(&format!(
+            "Environment={}\n",
+            systemd_quote(&format!("{key}={value}"))
+        ));
+    }
+    out.push_str(&format!(
+        "Restart={}\n",
+        host_service.restart.as_deref().unwrap_or("always")
+    ));
+    out.push_str("RestartSec=5\n");
+
+    out.push_str("\n[Install]\n");
+    out.push_str("WantedBy=multi-user.target\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn cloudflared() -> HostService {
+        HostService {
+            exec_start: "/usr/bin/cloudflared tunnel run".to_string(),
+            after: vec!["network-online.target".to_string()],
+            environment: HashMap::from([
+                ("TUNNEL_TOKEN".to_string(), "a\"b".to_string()),
+                ("NO_AUTOUPDATE".to_string(), "true".to_string()),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unit_name_is_prefixed() {
+        assert_eq!(
+            host_service_unit_name("cloudflared"),
+            "fleetflow-cloudflared.service"
+        );
+        assert_eq!(
+            host_service_unit_path("cloudflared"),
+            "/etc/systemd/system/fleetflow-cloudflared.service"
+        );
+    }
+
+    #[test]
+    fn generate_unit_has_sections_and_attribution() {
+        let unit = generate_host_service_unit("myapp", "edge-1", "cloudflared", &cloudflared());
+        assert!(unit.contains("# fleetflow.project=myapp\n# fleetflow.server=edge-1\n"));
+        assert!(unit.contains("Description=cloudflared (fleetflow host service)\n"));
+        assert!(unit.contains("After=network-online.target\nWants=network-online.target\n"));
+        assert!(unit.contains("ExecStart=/usr/bin/cloudflared tunnel run\n"));
+        assert!(unit.contains("Restart=always\n"));
+        assert!(unit.contains("WantedBy=multi-user.target\n"));
+        assert!(!unit.contains("User="));
+    }
+
+    #[test]
+    fn generate_unit_quotes_sorted_environment() {
+        let unit = generate_host_service_unit("myapp", "edge-1", "cloudflared", &cloudflared());
+        let no_update = unit.find("Environment=\"NO_AUTOUPDATE=true\"").unwrap();
+        let token = unit.find("Environment=\"TUNNEL_TOKEN=a\\\"b\"").unwrap();
+        assert!(no_update < token);
+    }
+}
@@ -0,0 +1,748 @@
+//! Kubernetes マニフェスト生成 — KDL `Service`/`Stage` → Deployment/Service YAML
+//!
+//! stage が `backend "kubernetes"`（別名 `target "kubernetes"`）を宣言しているとき、
+//! 同じ fleet.kdl からクラスタ向けのマニフェストを生成し `kubectl apply` に渡す。
+//! 接続先は kubectl の current-context（`KUBECONFIG`）に従う。クラスタとの通信を
+//! kube-rs ではなく kubectl に委ねる理由は `cluster_target` を参照。
+//!
+//! YAML 生成は `compose.rs` と同じく手書き（全 scalar を二重引用符でエスケープ）。
+//!
+//! 規約:
+//! - namespace = `{project}-{stage}`（ステージごとに分離）
+//! - image は Docker 経路と同じく `resolve_image`（`version` をタグに使う）
+//! - Deployment / Service 名 = サービス名（namespace 内の DNS 名が Docker の
+//!   ネットワークエイリアスと一致する）
+//! - attribution は `fleetflow.{project,stage,service}` label
+//! - ホストパスの volume はクラスタでは意味を持たないためマッピングしない
+//! - `user` / `cap_add` / `cap_drop` / `read_only` / `privileged` /
+//!   `security_opt "no-new-privileges"` は container の `securityContext`、`sysctls` は
+//!   pod の `securityContext`、`network_mode "host"` は `hostNetwork`、`tmpfs` /
+//!   `shm_size` は `emptyDir`（`medium: Memory`）にマッピングする。対応する表現が無い
+//!   設定（`ulimits` / `logging` 等）を持つサービスは黙って落とさずエラーにする
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use fleetflow_core::{Flow, NetworkMode, Protocol, Service, Stage, parse_byte_size};
+
+use crate::cluster_target::{ClusterTarget, KubernetesTarget};
+use crate::compose::yaml_quote;
+use crate::converter::resolve_image;
+
+/// namespace 名（`{project}-{stage}`）。
+pub fn k8s_namespace(project: &str, stage: &str) -> String {
+    format!("{project}-{stage}")
+}
+
+/// `fleetflow.*` label を指定インデントで出力する。
+fn push_labels(out: &mut String, indent: &str, labels: &[(&str, &str)]) {
+    for (key, value) in labels {
+        out.push_str(&format!("{indent}{key}: {}\n", yaml_quote(value)));
+    }
+}
+
+/// YAML のフロー形式シーケンス（`["a", "b"]`）。
+fn yaml_list(items: &[String]) -> String {
+    let quoted: Vec<String> = items.iter().map(|s| yaml_quote(s)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+/// Docker 形式のヘルスチェックを exec probe のコマンドに変換する。
+///
+/// `CMD-SHELL` は `/bin/sh -c`、`CMD` はそのまま、`NONE` は probe なし。
+fn probe_command(test: &[String]) -> Option<Vec<String>> {
+    match test.split_first() {
+        Some((kind, rest)) if kind == "CMD-SHELL" => Some(vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            rest.join(" "),
+        ]),
+        Some((kind, rest)) if kind == "CMD" => Some(rest.to_vec()),
+        Some((kind, _)) if kind == "NONE" => None,
+        Some(_) => Some(test.to_vec()),
+        None => None,
+    }
+}
+
+fn is_no_new_privileges(opt: &str) -> bool {
+    matches!(
+        opt,
+        "no-new-privileges" | "no-new-privileges:true" | "no-new-privileges=true"
+    )
+}
+
+/// Kubernetes のマニフェストに対応する表現が無い設定名。
+fn unmapped_settings(service: &Service) -> Vec<&'static str> {
+    let mut unmapped = Vec::new();
+    if service.user.is_some() && service.numeric_user().is_none() {
+        unmapped.push("user（数値の uid[:gid] 以外）");
+    }
+    if service
+        .security_opt
+        .iter()
+        .any(|o| !is_no_new_privileges(o))
+    {
+        unmapped.push("security_opt（no-new-privileges 以外）");
+    }
+    if service.tmpfs.iter().any(|t| t.mode.is_some()) {
+        unmapped.push("tmpfs mode");
+    }
+    if !service.ulimits.is_empty() {
+        unmapped.push("ulimits");
+    }
+    if service.logging.is_some() {
+        unmapped.push("logging");
+    }
+    if matches!(
+        service.network_mode,
+        Some(NetworkMode::None | NetworkMode::Container(_))
+    ) {
+        unmapped.push("network_mode（host 以外）");
+    }
+    unmapped
+}
+
+/// Docker の capability 名（`CAP_` 接頭辞は任意）を Kubernetes の表記にそろえる。
+fn capability_names(caps: &[String]) -> Vec<String> {
+    caps.iter()
+        .map(|cap| cap.strip_prefix("CAP_").unwrap_or(cap).to_string())
+        .collect()
+}
+
+/// container の `securityContext`（設定が無ければ何も出力しない）。
+fn push_security_context(out: &mut String, service: &Service) {
+    let mut fields = String::new();
+    if let Some((uid, gid)) = service.numeric_user() {
+        fields.push_str(&format!("            runAsUser: {uid}\n"));
+        if let Some(gid) = gid {
+            fields.push_str(&format!("            runAsGroup: {gid}\n"));
+        }
+    }
+    if let Some(privileged) = service.privileged {
+        fields.push_str(&format!("            privileged: {privileged}\n"));
+    }
+    if let Some(read_only) = service.read_only {
+        fields.push_str(&format!(
+            "            readOnlyRootFilesystem: {read_only}\n"
+        ));
+    }
+    if service.security_opt.iter().any(|o| is_no_new_privileges(o)) {
+        fields.push_str("            allowPrivilegeEscalation: false\n");
+    }
+    if !service.cap_add.is_empty() || !service.cap_drop.is_empty() {
+        fields.push_str("            capabilities:\n");
+        if !service.cap_add.is_empty() {
+            fields.push_str(&format!(
+                "              add: {}\n",
+                yaml_list(&capability_names(&service.cap_add))
+            ));
+        }
+        if !service.cap_drop.is_empty() {
+            fields.push_str(&format!(
+                "              drop: {}\n",
+                yaml_list(&capability_names(&service.cap_drop))
+            ));
+        }
+    }
+    if !fields.is_empty() {
+        out.push_str("          securityContext:\n");
+        out.push_str(&fields);
+    }
+}
+
+/// メモリ上の `emptyDir` としてマウントする領域（volume 名, マウント先, サイズ上限）。
+///
+/// `tmpfs` と `shm_size`（`/dev/shm`）が対象。
+fn memory_volumes(service: &Service) -> Vec<(String, String, Option<u64>)> {
+    let mut volumes: Vec<(String, String, Option<u64>)> = service
+        .tmpfs
+        .iter()
+        .enumerate()
+        .map(|(i, tmpfs)| {
+            (
+                format!("tmpfs-{i}"),
+                tmpfs.target.to_string_lossy().into_owned(),
+                tmpfs.size.as_deref().and_then(parse_byte_size),
+            )
+        })
+        .collect();
+    if let Some(size) = &service.shm_size {
+        volumes.push((
+            "dshm".to_string(),
+            "/dev/shm".to_string(),
+            parse_byte_size(size),
+        ));
+    }
+    volumes
+}
+
+fn protocol_str(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Tcp => "TCP",
+        Protocol::Udp => "UDP",
+    }
+}
+
+/// `Service`/`Stage` から Kubernetes マニフェスト（複数ドキュメント YAML）を生成する（純粋関数）。
+///
+/// 静的サイト（`type "static"`）はコンテナではないためスキップする。
+/// マッピングできない設定（`unmapped_settings`）を持つサービスがあればエラー。
+pub fn generate_k8s_manifest(
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<String> {
+    let project = &config.name;
+    let namespace = k8s_namespace(project, stage_name);
+    let mut out = String::new();
+
+    out.push_str(&format!("# Generated by fleetflow — {namespace}\n"));
+    out.push_str("# DO NOT EDIT — `fleet up` で再生成される\n");
+    out.push_str("apiVersion: v1\n");
+    out.push_str("kind: Namespace\n");
+    out.push_str("metadata:\n");
+    out.push_str(&format!("  name: {}\n", yaml_quote(&namespace)));
+    out.push_str("  labels:\n");
+    push_labels(
+        &mut out,
+        "    ",
+        &[
+            ("fleetflow.project", project),
+            ("fleetflow.stage", stage_name),
+        ],
+    );
+
+    for service_name in &stage.services {
+        let service = config
+            .services
+            .get(service_name)
+            .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
+
+        // 静的サイトはコンテナではないため対象外
+        if service.is_static() {
+            continue;
+        }
+        if service.image.is_none() {
+            anyhow::bail!("サービス '{}' に image がありません", service_name);
+        }
+        let unmapped = unmapped_settings(service);
+        if !unmapped.is_empty() {
+            anyhow::bail!(
+                "backend \"kubernetes\" ではサービス '{}' の {} をマッピングできません",
+                service_name,
+                unmapped.join(", ")
+            );
+        }
+        let image = resolve_image(service_name, service);
+
+        let labels = [
+            ("fleetflow.project", project.as_str()),
+            ("fleetflow.stage", stage_name),
+            ("fleetflow.service", service_name.as_str()),
+        ];
+
+        // Deployment
+        out.push_str("---\n");
+        out.push_str("apiVersion: apps/v1\n");
+        out.push_str("kind: Deployment\n");
+        out.push_str("metadata:\n");
+        out.push_str(&format!("  name: {}\n", yaml_quote(service_name)));
+        out.push_str(&format!("  namespace: {}\n", yaml_quote(&namespace)));
+        out.push_str("  labels:\n");
+        push_labels(&mut out, "    ", &labels);
+        out.push_str("spec:\n");
+        out.push_str("  replicas: 1\n");
+        out.push_str("  selector:\n");
+        out.push_str("    matchLabels:\n");
+        push_labels(&mut out, "      ", &labels);
+        out.push_str("  template:\n");
+        out.push_str("    metadata:\n");
+        out.push_str("      labels:\n");
+        push_labels(&mut out, "        ", &labels);
+        out.push_str("    spec:\n");
+        if let Some(hostname) = &service.hostname {
+            out.push_str(&format!("      hostname: {}\n", yaml_quote(hostname)));
+        }
+        if service.network_mode == Some(NetworkMode::Host) {
+            out.push_str("      hostNetwork: true\n");
+        }
+        // sysctls は pod 単位（決定的出力のためキー順ソート）
+        if !service.sysctls.is_empty() {
+            out.push_str("      securityContext:\n");
+            out.push_str("        sysctls:\n");
+            let mut sysctls: Vec<(&String, &String)> = service.sysctls.iter().collect();
+            sysctls.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in sysctls {
+                out.push_str(&format!("          - name: {}\n", yaml_quote(key)));
+                out.push_str(&format!("            value: {}\n", yaml_quote(value)));
+            }
+        }
+        out.push_str("      containers:\n");
+        out.push_str(&format!("        - name: {}\n", yaml_quote(service_name)));
+        out.push_str(&format!("          image: {}\n", yaml_quote(&image)));
+
+        // エントリーポイントは command、実行コマンドは CMD 相当の args に
+        if let Some(entrypoint) = &service.entrypoint {
+            out.push_str(&format!("          command: {}\n", yaml_list(entrypoint)));
+        }
+        if let Some(args) = service.command_args() {
+            out.push_str(&format!("          args: {}\n", yaml_list(&args)));
+        }
+        if let Some(dir) = &service.working_dir {
+            out.push_str(&format!("          workingDir: {}\n", yaml_quote(dir)));
+        }
+
+        if !service.ports.is_empty() {
+            out.push_str("          ports:\n");
+            for port in &service.ports {
+                out.push_str(&format!(
+                    "            - containerPort: {}\n",
+                    port.container
+                ));
+                out.push_str(&format!(
+                    "              protocol: {}\n",
+                    yaml_quote(protocol_str(&port.protocol))
+                ));
+            }
+        }
+
+        // 環境変数（決定的出力のためキー順ソート）
+        if !service.environment.is_empty() {
+            out.push_str("          env:\n");
+            let mut env: Vec<(&String, &String)> = service.environment.iter().collect();
+            env.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in env {
+                out.push_str(&format!("            - name: {}\n", yaml_quote(key)));
+                out.push_str(&format!("              value: {}\n", yaml_quote(value)));
+            }
+        }
+
+        // ヘルスチェック → readinessProbe
+        if let Some(hc) = &service.healthcheck
+            && let Some(command) = probe_command(&hc.test)
+        {
+            out.push_str("          readinessProbe:\n");
+            out.push_str("            exec:\n");
+            out.push_str(&format!("              command: {}\n", yaml_list(&command)));
+            out.push_str(&format!("            periodSeconds: {}\n", hc.interval));
+            out.push_str(&format!("            timeoutSeconds: {}\n", hc.timeout));
+            out.push_str(&format!("            failureThreshold: {}\n", hc.retries));
+            out.push_str(&format!(
+                "            initialDelaySeconds: {}\n",
+                hc.start_period
+            ));
+        }
+
+        push_security_context(&mut out, service);
+
+        // tmpfs / shm_size → emptyDir（medium: Memory）
+        let memory_volumes = memory_volumes(service);
+        if !memory_volumes.is_empty() {
+            out.push_str("          volumeMounts:\n");
+            for (name, mount_path, _) in &memory_volumes {
+                out.push_str(&format!("            - name: {}\n", yaml_quote(name)));
+                out.push_str(&format!(
+                    "              mountPath: {}\n",
+                    yaml_quote(mount_path)
+                ));
+            }
+            out.push_str("      volumes:\n");
+            for (name, _, size) in &memory_volumes {
+                out.push_str(&format!("        - name: {}\n", yaml_quote(name)));
+                out.push_str("          emptyDir:\n");
+                out.push_str("            medium: \"Memory\"\n");
+                if let Some(size) = size {
+                    out.push_str(&format!("            sizeLimit: \"{size}\"\n"));
+                }
+            }
+        }
+
+        // Service（ポート公開があるときのみ）
+        if service.ports.is_empty() {
+            continue;
+        }
+        out.push_str("---\n");
+        out.push_str("apiVersion: v1\n");
+        out.push_str("kind: Service\n");
+        out.push_str("metadata:\n");
+        out.push_str(&format!("  name: {}\n", yaml_quote(service_name)));
+        out.push_str(&format!("  namespace: {}\n", yaml_quote(&namespace)));
+        out.push_str("  labels:\n");
+        push_labels(&mut out, "    ", &labels);
+        out.push_str("spec:\n");
+        out.push_str("  selector:\n");
+        push_labels(&mut out, "    ", &labels);
+        out.push_str("  ports:\n");
+        for port in &service.ports {
+            let proto = protocol_str(&port.protocol);
+            out.push_str(&format!(
+                "    - name: {}\n",
+                yaml_quote(&format!("{}-{}", proto.to_lowercase(), port.host))
+            ));
+            out.push_str(&format!("      port: {}\n", port.host));
+            out.push_str(&format!("      targetPort: {}\n", port.container));
+            out.push_str(&format!("      protocol: {}\n", yaml_quote(proto)));
+        }
+    }
+
+    Ok(out)
+}
+
+/// ホストパスの volume を持つ（クラスタではマッピングされない）サービス名。
+pub fn services_with_host_volumes(config: &Flow, stage: &Stage) -> Vec<String> {
+    stage
+        .services
+        .iter()
+        .filter(|name| {
+            config
+                .services
+                .get(*name)
+                .is_some_and(|s| !s.is_static() && !s.volumes.is_empty())
+        })
+        .cloned()
+        .collect()
+}
+
+/// 生成済みマニフェストの配置先（`{project_root}/.fleetflow/k8s.{stage}.generated.yaml`）。
+pub fn k8s_manifest_path(project_root: &Path, stage_name: &str) -> PathBuf {
+    project_root
+        .join(".fleetflow")
+        .join(format!("k8s.{stage_name}.generated.yaml"))
+}
+
+/// マニフェストを生成してファイルに書き出す。
+pub fn write_k8s_manifest(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<PathBuf> {
+    let yaml = generate_k8s_manifest(config, stage_name, stage)?;
+    let path = k8s_manifest_path(project_root, stage_name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, yaml)?;
+    Ok(path)
+}
+
+/// `kubectl <args...>` を実行する。
+fn run_kubectl(args: &[&str]) -> io::Result<()> {
+    let status = std::process::Command::new("kubectl").args(args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "`kubectl {}` failed (exit {:?})",
+            args.join(" "),
+            status.code()
+        )))
+    }
+}
+
+/// stage をクラスタに適用する（`kubectl apply -f`）。
+pub fn k8s_up(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<PathBuf> {
+    let file = write_k8s_manifest(project_root, config, stage_name, stage)?;
+    let file_arg = file.to_string_lossy();
+    run_kubectl(&["apply", "-f", &file_arg])?;
+    Ok(file)
+}
+
+/// stage の Deployment/Service をクラスタから削除する。
+///
+/// `remove` 指定時は namespace ごと削除する。
+pub fn k8s_down(config: &Flow, stage_name: &str, remove: bool) -> anyhow::Result<()> {
+    let namespace = k8s_namespace(&config.name, stage_name);
+    if remove {
+        run_kubectl(&["delete", "namespace", &namespace, "--ignore-not-found"])?;
+    } else {
+        let selector = format!(
+            "fleetflow.project={},fleetflow.stage={}",
+            config.name, stage_name
+        );
+        run_kubectl(&[
+            "delete",
+            "deployment,service",
+            "-n",
+            &namespace,
+            "-l",
+            &selector,
+            "--ignore-not-found",
+        ])?;
+    }
+    Ok(())
+}
+
+/// stage の Deployment/Service/Pod の状態を表示する（`kubectl get`）。
+pub fn k8s_status(config: &Flow, stage_name: &str) -> anyhow::Result<()> {
+    let namespace = k8s_namespace(&config.name, stage_name);
+    let selector = format!(
+        "fleetflow.project={},fleetflow.stage={}",
+        config.name, stage_name
+    );
+    run_kubectl(&[
+        "get",
+        "deployment,service,pod",
+        "-n",
+        &namespace,
+        "-l",
+        &selector,
+    ])?;
+    Ok(())
+}
+
+impl ClusterTarget for KubernetesTarget {
+    fn name(&self) -> &'static str {
+        "kubernetes"
+    }
+
+    fn resource(&self, config: &Flow, stage_name: &str) -> String {
+        format!("namespace {}", k8s_namespace(&config.name, stage_name))
+    }
+
+    fn render(&self, config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<String> {
+        generate_k8s_manifest(config, stage_name, stage)
+    }
+
+    fn up(
+        &self,
+        project_root: &Path,
+        config: &Flow,
+        stage_name: &str,
+        stage: &Stage,
+    ) -> anyhow::Result<PathBuf> {
+        k8s_up(project_root, config, stage_name, stage)
+    }
+
+    fn down(
+        &self,
+        config: &Flow,
+        stage_name: &str,
+        _stage: &Stage,
+        remove: bool,
+    ) -> anyhow::Result<()> {
+        k8s_down(config, stage_name, remove)
+    }
+
+    fn status(&self, config: &Flow, stage_name: &str, _stage: &Stage) -> anyhow::Result<()> {
+        k8s_status(config, stage_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{HealthCheck, Port, ServiceType, TmpfsMount, Ulimit, Volume};
+    use std::collections::HashMap;
+
+    fn flow_with(services: Vec<(&str, Service)>, stage_services: Vec<&str>) -> (Flow, Stage) {
+        let mut svc_map = HashMap::new();
+        for (name, svc) in services {
+            svc_map.insert(name.to_string(), svc);
+        }
+        let stage = Stage {
+            services: stage_services.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut stages = HashMap::new();
+        stages.insert("cluster".to_string(), stage.clone());
+        let flow = Flow {
+            name: "myapp".to_string(),
+            services: svc_map,
+            stages,
+            ..Default::default()
+        };
+        (flow, stage)
+    }
+
+    fn web_service() -> Service {
+        Service {
+            image: Some("nginx:1.27".to_string()),
+            ports: vec![Port {
+                host: 8080,
+                container: 80,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Service::default()
+        }
+    }
+
+    #[test]
+    fn generate_k8s_manifest_has_namespace_and_deployment() {
+        let (flow, stage) = flow_with(vec![("web", web_service())], vec!["web"]);
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert!(yaml.contains("kind: Namespace\nmetadata:\n  name: \"myapp-cluster\"\n"));
+        assert!(yaml.contains("kind: Deployment\n"));
+        assert!(yaml.contains("  namespace: \"myapp-cluster\"\n"));
+        assert!(yaml.contains("        - name: \"web\"\n          image: \"nginx:1.27\"\n"));
+        assert!(yaml.contains("      fleetflow.service: \"web\"\n"));
+        assert!(yaml.contains("            - containerPort: 80\n"));
+    }
+
+    #[test]
+    fn generate_k8s_manifest_uses_version_as_tag() {
+        let mut svc = web_service();
+        svc.image = Some("nginx".to_string());
+        svc.version = Some("1.2.3".to_string());
+        let worker = Service {
+            image: Some("worker".to_string()),
+            ..Service::default()
+        };
+        let (flow, stage) = flow_with(
+            vec![("web", svc), ("worker", worker)],
+            vec!["web", "worker"],
+        );
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert!(yaml.contains("          image: \"nginx:1.2.3\"\n"));
+        assert!(yaml.contains("          image: \"worker:latest\"\n"));
+    }
+
+    #[test]
+    fn generate_k8s_manifest_renders_service_for_ports() {
+        let (flow, stage) = flow_with(
+            vec![
+                ("web", web_service()),
+                (
+                    "worker",
+                    Service {
+                        image: Some("worker:latest".to_string()),
+                        ..Service::default()
+                    },
+                ),
+            ],
+            vec!["web", "worker"],
+        );
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert_eq!(yaml.matches("kind: Service\n").count(), 1);
+        assert!(yaml.contains("      port: 8080\n      targetPort: 80\n"));
+    }
+
+    #[test]
+    fn generate_k8s_manifest_renders_env_command_probe() {
+        let mut svc = web_service();
+        svc.command = Some("nginx -g daemon_off".to_string());
+        svc.environment.insert("B_KEY".into(), "2".into());
+        svc.environment.insert("A_KEY".into(), "1".into());
+        svc.healthcheck = Some(HealthCheck {
+            test: vec!["CMD-SHELL".into(), "curl -f localhost".into()],
+            interval: 30,
+            timeout: 3,
+            retries: 5,
+            start_period: 10,
+        });
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert!(yaml.contains("          args: [\"nginx\", \"-g\", \"daemon_off\"]\n"));
+        assert!(yaml.find("A_KEY").unwrap() < yaml.find("B_KEY").unwrap());
+        assert!(
+            yaml.contains("              command: [\"/bin/sh\", \"-c\", \"curl -f localhost\"]\n")
+        );
+        assert!(yaml.contains("            failureThreshold: 5\n"));
+    }
+
+    #[test]
+    fn generate_k8s_manifest_maps_security_settings() {
+        let mut svc = web_service();
+        svc.user = Some("1000:2000".to_string());
+        svc.cap_add = vec!["CAP_NET_ADMIN".to_string()];
+        svc.cap_drop = vec!["ALL".to_string()];
+        svc.read_only = Some(true);
+        svc.privileged = Some(false);
+        svc.security_opt = vec!["no-new-privileges:true".to_string()];
+        svc.network_mode = Some(NetworkMode::Host);
+        svc.sysctls
+            .insert("net.core.somaxconn".into(), "1024".into());
+        svc.tmpfs = vec![TmpfsMount {
+            target: "/tmp".into(),
+            size: Some("64m".to_string()),
+            mode: None,
+        }];
+        svc.shm_size = Some("1g".to_string());
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert!(yaml.contains("      hostNetwork: true\n"));
+        assert!(yaml.contains(
+            "        sysctls:\n          - name: \"net.core.somaxconn\"\n            value: \"1024\"\n"
+        ));
+        assert!(yaml.contains("            runAsUser: 1000\n            runAsGroup: 2000\n"));
+        assert!(yaml.contains("            privileged: false\n"));
+        assert!(yaml.contains("            readOnlyRootFilesystem: true\n"));
+        assert!(yaml.contains("            allowPrivilegeEscalation: false\n"));
+        assert!(
+            yaml.contains("              add: [\"NET_ADMIN\"]\n              drop: [\"ALL\"]\n")
+        );
+        assert!(
+            yaml.contains("            - name: \"tmpfs-0\"\n              mountPath: \"/tmp\"\n")
+        );
+        assert!(yaml.contains(
+            "        - name: \"tmpfs-0\"\n          emptyDir:\n            medium: \"Memory\"\n            sizeLimit: \"67108864\"\n"
+        ));
+        assert!(yaml.contains("              mountPath: \"/dev/shm\"\n"));
+    }
+
+    #[test]
+    fn generate_k8s_manifest_rejects_unmapped_settings() {
+        let mut svc = web_service();
+        svc.user = Some("app".to_string());
+        svc.ulimits = vec![Ulimit {
+            name: "nofile".to_string(),
+            soft: 1024,
+            hard: 1024,
+        }];
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        let err = generate_k8s_manifest(&flow, "cluster", &stage)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'web'"));
+        assert!(err.contains("user"));
+        assert!(err.contains("ulimits"));
+
+        let mut svc = web_service();
+        svc.network_mode = Some(NetworkMode::Container("vpn".to_string()));
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        assert!(generate_k8s_manifest(&flow, "cluster", &stage).is_err());
+    }
+
+    #[test]
+    fn generate_k8s_manifest_skips_static_services() {
+        let static_svc = Service {
+            service_type: Some(ServiceType::Static),
+            ..Service::default()
+        };
+        let (flow, stage) = flow_with(
+            vec![("web", web_service()), ("site", static_svc)],
+            vec!["web", "site"],
+        );
+        let yaml = generate_k8s_manifest(&flow, "cluster", &stage).unwrap();
+        assert!(!yaml.contains("\"site\""));
+    }
+
+    #[test]
+    fn probe_command_converts_docker_forms() {
+        assert_eq!(
+            probe_command(&["CMD".into(), "pg_isready".into()]),
+            Some(vec!["pg_isready".to_string()])
+        );
+        assert_eq!(probe_command(&["NONE".into()]), None);
+    }
+
+    #[test]
+    fn services_with_host_volumes_lists_unmapped() {
+        let mut svc = web_service();
+        svc.volumes = vec![Volume {
+            host: "./data".into(),
+            container: "/data".into(),
+            read_only: false,
+        }];
+        let (flow, stage) = flow_with(vec![("db", svc), ("web", web_service())], vec!["db", "web"]);
+        assert_eq!(services_with_host_volumes(&flow, &stage), vec!["db"]);
+    }
+}
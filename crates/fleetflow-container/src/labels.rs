@@ -0,0 +1,210 @@
+//! コンテナラベル — FleetFlow が作るすべてのコンテナに付ける標準ラベル
+//!
+//! プロジェクト・ステージ・サービス・設定ハッシュ・Git コミット・fleet のバージョンを記録する。
+//! ps / down / deploy の後片付け / `up --check` はコンテナ名ではなくこのラベルで対象を探すため、
+//! 命名規則が変わってもコンテナを管理し続けられる。
+
+use crate::config_files::{CONFIG_HASH_LABEL, FNV_OFFSET, fnv1a};
+use bollard::Docker;
+use bollard::models::ContainerSummary;
+use bollard::query_parameters::ListContainersOptions;
+use fleetflow_core::Service;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const PROJECT_LABEL: &str = "fleetflow.project";
+pub const STAGE_LABEL: &str = "fleetflow.stage";
+pub const SERVICE_LABEL: &str = "fleetflow.service";
+/// 作成した fleet のバージョン
+pub const VERSION_LABEL: &str = "fleetflow.version";
+/// 作成時のプロジェクトの Git コミット
+pub const GIT_SHA_LABEL: &str = "fleetflow.git-sha";
+
+/// 作成時の Git コミット（`FLEET_GIT_SHA` → `GITHUB_SHA` → `git rev-parse`。プロセス内でキャッシュ）
+pub fn git_sha() -> Option<&'static str> {
+    static SHA: OnceLock<Option<String>> = OnceLock::new();
+    SHA.get_or_init(|| {
+        std::env::var("FLEET_GIT_SHA")
+            .or_else(|_| std::env::var("GITHUB_SHA"))
+            .ok()
+            .filter(|sha| !sha.is_empty())
+            .or_else(|| {
+                let output = std::process::Command::new("git")
+                    .args(["rev-parse", "--short=12", "HEAD"])
+                    .stderr(std::process::Stdio::null())
+                    .output()
+                    .ok()?;
+                let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                (output.status.success() && !sha.is_empty()).then_some(sha)
+            })
+    })
+    .as_deref()
+}
+
+/// サービス定義のハッシュ（JSON にシリアライズした定義の FNV-1a）
+///
+/// 設定ファイル（configs）を使うサービスは、`fleet up` がレンダリング結果のハッシュで上書きする。
+pub fn service_hash(service: &Service) -> String {
+    let json = serde_json::to_value(service)
+        .map(|value| canonical_json(value).to_string())
+        .unwrap_or_default();
+    format!("{:016x}", fnv1a(FNV_OFFSET, json.as_bytes()))
+}
+
+/// オブジェクトのキーを再帰的にソートした JSON
+///
+/// serde_json の `preserve_order` が有効だと `HashMap` フィールド（environment 等）が
+/// 反復順のまま並び、同じ定義でもプロセスごとにハッシュが変わってしまうため。
+fn canonical_json(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonical_json(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical_json).collect()),
+        other => other,
+    }
+}
+
+/// コンテナに付ける標準ラベル
+pub fn standard_labels(
+    project_name: &str,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+) -> HashMap<String, String> {
+    let mut labels = HashMap::new();
+    // OrbStack / Docker Desktop でのグループ化
+    labels.insert(
+        "com.docker.compose.project".to_string(),
+        format!("{}-{}", project_name, stage_name),
+    );
+    labels.insert(
+        "com.docker.compose.service".to_string(),
+        service_name.to_string(),
+    );
+    labels.insert(PROJECT_LABEL.to_string(), project_name.to_string());
+    labels.insert(STAGE_LABEL.to_string(), stage_name.to_string());
+    labels.insert(SERVICE_LABEL.to_string(), service_name.to_string());
+    labels.insert(CONFIG_HASH_LABEL.to_string(), service_hash(service));
+    labels.insert(
+        VERSION_LABEL.to_string(),
+        env!("CARGO_PKG_VERSION").to_string(),
+    );
+    if let Some(sha) = git_sha() {
+        labels.insert(GIT_SHA_LABEL.to_string(), sha.to_string());
+    }
+    labels
+}
+
+/// `list_containers` のラベルフィルタ
+pub fn label_filters(
+    project_name: &str,
+    stage_name: Option<&str>,
+    service_name: Option<&str>,
+) -> HashMap<String, Vec<String>> {
+    let mut labels = vec![format!("{}={}", PROJECT_LABEL, project_name)];
+    if let Some(stage) = stage_name {
+        labels.push(format!("{}={}", STAGE_LABEL, stage));
+    }
+    if let Some(service) = service_name {
+        labels.push(format!("{}={}", SERVICE_LABEL, service));
+    }
+    HashMap::from([("label".to_string(), labels)])
+}
+
+/// ラベルでプロジェクト（・ステージ・サービス）のコンテナを探す（停止中も含む）
+pub async fn find_containers(
+    docker: &Docker,
+    project_name: &str,
+    stage_name: Option<&str>,
+    service_name: Option<&str>,
+) -> std::result::Result<Vec<ContainerSummary>, bollard::errors::Error> {
+    docker
+        .list_containers(Some(ListContainersOptions {
+            all: true,
+            filters: Some(label_filters(project_name, stage_name, service_name)),
+            ..Default::default()
+        }))
+        .await
+}
+
+/// コンテナのラベルの値
+pub fn label<'a>(container: &'a ContainerSummary, key: &str) -> Option<&'a str> {
+    container.labels.as_ref()?.get(key).map(String::as_str)
+}
+
+/// コンテナ名（先頭の `/` を除く）
+pub fn summary_name(container: &ContainerSummary) -> Option<&str> {
+    container
+        .names
+        .as_ref()?
+        .first()
+        .map(|name| name.trim_start_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_hash_tracks_definition() {
+        let mut service = Service {
+            image: Some("nginx".to_string()),
+            ..Default::default()
+        };
+        service.environment.insert("A".to_string(), "1".to_string());
+        service.environment.insert("B".to_string(), "2".to_string());
+        let hash = service_hash(&service);
+        assert_eq!(hash, service_hash(&service.clone()));
+
+        service.image = Some("nginx:1.27".to_string());
+        assert_ne!(hash, service_hash(&service));
+    }
+
+    #[test]
+    fn test_service_hash_ignores_map_insertion_order() {
+        let keys: Vec<String> = (0..32).map(|i| format!("KEY_{i:02}")).collect();
+        let mut forward = Service::default();
+        for key in &keys {
+            forward.environment.insert(key.clone(), key.to_lowercase());
+            forward.labels.insert(key.to_lowercase(), key.clone());
+        }
+        let mut reverse = Service::default();
+        for key in keys.iter().rev() {
+            reverse.labels.insert(key.to_lowercase(), key.clone());
+            reverse.environment.insert(key.clone(), key.to_lowercase());
+        }
+        assert_eq!(service_hash(&forward), service_hash(&reverse));
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_nested_keys() {
+        let mut inner = serde_json::Map::new();
+        inner.insert("z".to_string(), Value::from(1));
+        inner.insert("a".to_string(), Value::from(2));
+        let mut outer = serde_json::Map::new();
+        outer.insert("b".to_string(), Value::Array(vec![Value::Object(inner)]));
+        outer.insert("a".to_string(), Value::Null);
+        assert_eq!(
+            canonical_json(Value::Object(outer)).to_string(),
+            r#"{"a":null,"b":[{"a":2,"z":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_label_filters() {
+        let filters = label_filters("myapp", Some("local"), None);
+        assert_eq!(
+            filters["label"],
+            vec!["fleetflow.project=myapp", "fleetflow.stage=local"]
+        );
+    }
+}
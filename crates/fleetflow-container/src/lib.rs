@@ -1,19 +1,39 @@
+pub mod cluster_target;
 pub mod compose;
+pub mod config_files;
 pub mod converter;
 pub mod docker;
 pub mod engine;
 pub mod error;
+pub mod host_service;
+pub mod kubernetes;
+pub mod labels;
+pub mod log_shipping;
+pub mod maintenance;
+pub mod nomad;
 pub mod port;
 pub mod quadlet;
 pub mod runtime;
+pub mod secret_files;
+pub mod wait_inject;
 pub mod waiter;
 
+pub use cluster_target::*;
 pub use compose::*;
+pub use config_files::*;
 pub use converter::*;
 pub use docker::*;
 pub use engine::*;
 pub use error::*;
+pub use host_service::*;
+pub use kubernetes::*;
+pub use labels::*;
+pub use log_shipping::*;
+pub use maintenance::*;
+pub use nomad::*;
 pub use port::*;
 pub use quadlet::*;
 pub use runtime::*;
+pub use secret_files::*;
+pub use wait_inject::*;
 pub use waiter::*;
@@ -0,0 +1,548 @@
+//! ログ集約サイドカー生成 — stage `log_shipping` → `log-shipper` サービス
+//!
+//! ステージに `log_shipping` が宣言されている場合、Vector / Fluent Bit の
+//! コンテナを通常の FleetFlow サービスとして追加し、プロジェクトの全コンテナの
+//! ログを Loki / S3 / HTTP へ転送する。
+//!
+//! 規約:
+//! - サービス名は `log-shipper`（ユーザーが同名サービスを定義した場合は
+//!   生成値をベースにユーザー定義をマージする）
+//! - エージェント設定は `.fleetflow/log-shipping/{stage}/` に生成し read-only で bind mount
+//!   （リモートステージでは各サーバーの `/var/lib/fleetflow/log-shipping/{project}/{stage}/` に配置）
+//! - `fleetflow.project` / `fleetflow.stage` ラベルで収集対象を絞り込む。Fluent Bit は
+//!   ログファイルを直接読むため、json-file ドライバの `labels` オプションでラベルを各行に含める
+
+use std::path::{Path, PathBuf};
+
+use fleetflow_core::{
+    Flow, LogShippingAgent, LogShippingConfig, LogSinkKind, RestartPolicy, Service, Volume,
+};
+
+use crate::compose::yaml_quote;
+
+/// 自動追加されるログ集約サービスの名前。
+pub const LOG_SHIPPER_SERVICE: &str = "log-shipper";
+
+/// リモートサーバー上でエージェント設定を置くディレクトリ。
+pub const REMOTE_LOG_SHIPPING_DIR: &str = "/var/lib/fleetflow/log-shipping";
+
+/// Fluent Bit がログ行から参照するラベル（json-file ドライバの `labels` オプション）。
+const SHIPPED_LABELS: [&str; 2] = ["fleetflow.project", "fleetflow.stage"];
+
+fn agent_config_file(agent: LogShippingAgent) -> &'static str {
+    match agent {
+        LogShippingAgent::Vector => "vector.yaml",
+        LogShippingAgent::FluentBit => "fluent-bit.conf",
+    }
+}
+
+/// エージェント設定ファイルのホスト側パス（`{project_root}/.fleetflow/log-shipping/{stage}/...`）。
+pub fn agent_config_path(project_root: &Path, stage: &str, agent: LogShippingAgent) -> PathBuf {
+    project_root
+        .join(".fleetflow")
+        .join("log-shipping")
+        .join(stage)
+        .join(agent_config_file(agent))
+}
+
+/// リモートステージでのエージェント設定ファイルのパス
+/// （`/var/lib/fleetflow/log-shipping/{project}/{stage}/...`）。
+pub fn remote_agent_config_path(project: &str, stage: &str, agent: LogShippingAgent) -> PathBuf {
+    Path::new(REMOTE_LOG_SHIPPING_DIR)
+        .join(project)
+        .join(stage)
+        .join(agent_config_file(agent))
+}
+
+/// エージェント設定をレンダリングする（純粋関数）。
+pub fn render_agent_config(flow: &Flow, stage: &str, config: &LogShippingConfig) -> String {
+    let project = flow.name.as_str();
+    match config.agent {
+        LogShippingAgent::Vector => {
+            let shipper = flow.container_name(stage, LOG_SHIPPER_SERVICE);
+            render_vector_config(project, stage, &shipper, config)
+        }
+        LogShippingAgent::FluentBit => render_fluent_bit_config(project, stage, config),
+    }
+}
+
+fn render_vector_config(
+    project: &str,
+    stage: &str,
+    shipper_container: &str,
+    config: &LogShippingConfig,
+) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# Generated by fleetflow — {project}-{stage}\n"));
+    out.push_str("# DO NOT EDIT — `fleet up` で再生成される\n");
+    out.push_str("sources:\n");
+    out.push_str("  fleetflow:\n");
+    out.push_str("    type: \"docker_logs\"\n");
+    out.push_str("    include_labels:\n");
+    out.push_str(&format!(
+        "      - {}\n",
+        yaml_quote(&format!("fleetflow.project={project}"))
+    ));
+    out.push_str(&format!(
+        "      - {}\n",
+        yaml_quote(&format!("fleetflow.stage={stage}"))
+    ));
+    // 自身のログを再収集しないよう除外
+    out.push_str("    exclude_containers:\n");
+    out.push_str(&format!("      - {}\n", yaml_quote(shipper_container)));
+    out.push_str("sinks:\n");
+    out.push_str("  out:\n");
+    out.push_str("    inputs: [\"fleetflow\"]\n");
+
+    match config.sink {
+        LogSinkKind::Loki => {
+            out.push_str("    type: \"loki\"\n");
+            out.push_str(&format!(
+                "    endpoint: {}\n",
+                yaml_quote(config.endpoint.as_deref().unwrap_or_default())
+            ));
+            out.push_str("    labels:\n");
+            out.push_str(&format!("      project: {}\n", yaml_quote(project)));
+            out.push_str(&format!("      stage: {}\n", yaml_quote(stage)));
+            out.push_str("      container: \"{{ container_name }}\"\n");
+        }
+        LogSinkKind::Http => {
+            out.push_str("    type: \"http\"\n");
+            out.push_str(&format!(
+                "    uri: {}\n",
+                yaml_quote(config.endpoint.as_deref().unwrap_or_default())
+            ));
+        }
+        LogSinkKind::S3 => {
+            out.push_str("    type: \"aws_s3\"\n");
+            out.push_str(&format!(
+                "    bucket: {}\n",
+                yaml_quote(config.bucket.as_deref().unwrap_or_default())
+            ));
+            if let Some(region) = &config.region {
+                out.push_str(&format!("    region: {}\n", yaml_quote(region)));
+            }
+            if let Some(endpoint) = &config.endpoint {
+                out.push_str(&format!("    endpoint: {}\n", yaml_quote(endpoint)));
+            }
+            out.push_str(&format!(
+                "    key_prefix: {}\n",
+                yaml_quote(&format!("{project}/{stage}/%Y-%m-%d/"))
+            ));
+        }
+    }
+    out.push_str("    encoding:\n");
+    out.push_str("      codec: \"json\"\n");
+    out
+}
+
+/// Fluent Bit はホスト上の全コンテナログを tail し、各行の `attrs`
+/// （json-file ドライバの `labels` オプションで付与）でプロジェクト・ステージに絞り込む。
+fn render_fluent_bit_config(project: &str, stage: &str, config: &LogShippingConfig) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# Generated by fleetflow — {project}-{stage}\n"));
+    out.push_str("# DO NOT EDIT — `fleet up` で再生成される\n");
+    out.push_str("[SERVICE]\n");
+    out.push_str("    Flush        1\n");
+    out.push_str("    Parsers_File parsers.conf\n\n");
+    out.push_str("[INPUT]\n");
+    out.push_str("    Name   tail\n");
+    out.push_str("    Path   /var/lib/docker/containers/*/*.log\n");
+    out.push_str("    Parser docker\n");
+    out.push_str("    Tag    fleetflow.*\n\n");
+    out.push_str("[FILTER]\n");
+    out.push_str("    Name   grep\n");
+    out.push_str("    Match  fleetflow.*\n");
+    out.push_str(&format!(
+        "    Regex  $attrs['fleetflow.project'] ^{}$\n",
+        regex_escape(project)
+    ));
+    out.push_str(&format!(
+        "    Regex  $attrs['fleetflow.stage'] ^{}$\n\n",
+        regex_escape(stage)
+    ));
+    out.push_str("[OUTPUT]\n");
+    out.push_str("    Match  fleetflow.*\n");
+
+    match config.sink {
+        LogSinkKind::Loki | LogSinkKind::Http => {
+            let endpoint = config.endpoint.as_deref().unwrap_or_default();
+            let (tls, host, port, path) = split_endpoint(endpoint);
+            let name = if config.sink == LogSinkKind::Loki {
+                "loki"
+            } else {
+                "http"
+            };
+            out.push_str(&format!("    Name   {name}\n"));
+            out.push_str(&format!("    Host   {host}\n"));
+            out.push_str(&format!("    Port   {port}\n"));
+            out.push_str(&format!("    tls    {}\n", if tls { "on" } else { "off" }));
+            if config.sink == LogSinkKind::Loki {
+                if !path.is_empty() && path != "/" {
+                    out.push_str(&format!("    Uri    {path}\n"));
+                }
+                out.push_str(&format!("    Labels project={project}, stage={stage}\n"));
+            } else {
+                out.push_str(&format!(
+                    "    URI    {}\n",
+                    if path.is_empty() { "/" } else { path }
+                ));
+                out.push_str("    Format json\n");
+            }
+        }
+        LogSinkKind::S3 => {
+            out.push_str("    Name   s3\n");
+            out.push_str(&format!(
+                "    bucket {}\n",
+                config.bucket.as_deref().unwrap_or_default()
+            ));
+            if let Some(region) = &config.region {
+                out.push_str(&format!("    region {region}\n"));
+            }
+            if let Some(endpoint) = &config.endpoint {
+                out.push_str(&format!("    endpoint {endpoint}\n"));
+            }
+            out.push_str(&format!(
+                "    s3_key_format /{project}/{stage}/%Y-%m-%d/$UUID.gz\n"
+            ));
+            out.push_str("    compression gzip\n");
+        }
+    }
+    out
+}
+
+/// 英数字以外をエスケープして正規表現のリテラルにする
+fn regex_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if !c.is_ascii_alphanumeric() && c != '_' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// URL を (tls, host, port, path) に分解する（Fluent Bit の出力設定用）。
+fn split_endpoint(endpoint: &str) -> (bool, &str, u16, &str) {
+    let (tls, rest) = if let Some(rest) = endpoint.strip_prefix("https://") {
+        (true, rest)
+    } else {
+        (false, endpoint.strip_prefix("http://").unwrap_or(endpoint))
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, ""),
+    };
+    let default_port = if tls { 443 } else { 80 };
+    match authority.rsplit_once(':') {
+        Some((host, port)) => (tls, host, port.parse().unwrap_or(default_port), path),
+        None => (tls, authority, default_port, path),
+    }
+}
+
+/// `log-shipper` サービス定義を生成する（純粋関数）。
+///
+/// `config_path` はエージェント設定ファイルのホスト側パス。
+pub fn log_shipper_service(config_path: PathBuf, config: &LogShippingConfig) -> Service {
+    let (volumes, command) = match config.agent {
+        LogShippingAgent::Vector => (
+            vec![
+                Volume {
+                    host: PathBuf::from("/var/run/docker.sock"),
+                    container: PathBuf::from("/var/run/docker.sock"),
+                    read_only: true,
+                },
+                Volume {
+                    host: config_path,
+                    container: PathBuf::from("/etc/vector/vector.yaml"),
+                    read_only: true,
+                },
+            ],
+            Some("--config /etc/vector/vector.yaml".to_string()),
+        ),
+        LogShippingAgent::FluentBit => (
+            vec![
+                Volume {
+                    host: PathBuf::from("/var/lib/docker/containers"),
+                    container: PathBuf::from("/var/lib/docker/containers"),
+                    read_only: true,
+                },
+                Volume {
+                    host: config_path,
+                    container: PathBuf::from("/fluent-bit/etc/fluent-bit.conf"),
+                    read_only: true,
+                },
+            ],
+            None,
+        ),
+    };
+
+    Service {
+        image: Some(config.agent_image()),
+        volumes,
+        command,
+        restart: Some(RestartPolicy::UnlessStopped),
+        ..Default::default()
+    }
+}
+
+/// ステージの `log_shipping` 宣言に従い `log-shipper` サービスを Flow に追加する。
+///
+/// ユーザーが `log-shipper` を定義している場合は生成値にマージする（環境変数で
+/// 認証情報を渡す用途など）。リモートステージでは設定ファイルをサーバー上の
+/// [`remote_agent_config_path`] から mount する。`log_shipping` 未宣言なら何もしない。
+pub fn inject_log_shipper(flow: &mut Flow, project_root: &Path, stage_name: &str) {
+    let Some(stage) = flow.stages.get(stage_name) else {
+        return;
+    };
+    let Some(config) = stage.log_shipping.clone() else {
+        return;
+    };
+
+    let config_path = if stage.servers.is_empty() {
+        agent_config_path(project_root, stage_name, config.agent)
+    } else {
+        remote_agent_config_path(&flow.name, stage_name, config.agent)
+    };
+    if config.agent == LogShippingAgent::FluentBit {
+        for service_name in stage.services.clone() {
+            if let Some(service) = flow.services.get_mut(&service_name) {
+                add_log_labels(service);
+            }
+        }
+    }
+
+    let mut service = log_shipper_service(config_path, &config);
+    if let Some(user_defined) = flow.services.remove(LOG_SHIPPER_SERVICE) {
+        service.merge(user_defined);
+    }
+    flow.services
+        .insert(LOG_SHIPPER_SERVICE.to_string(), service);
+
+    if let Some(stage) = flow.stages.get_mut(stage_name)
+        && !stage.services.iter().any(|s| s == LOG_SHIPPER_SERVICE)
+    {
+        stage.services.push(LOG_SHIPPER_SERVICE.to_string());
+    }
+}
+
+/// ログの各行にプロジェクト・ステージのラベルを含めるよう json-file ドライバに指定する
+fn add_log_labels(service: &mut Service) {
+    let logging = service.logging.get_or_insert_with(Default::default);
+    if logging
+        .driver
+        .as_deref()
+        .is_some_and(|driver| driver != "json-file")
+    {
+        // tail 対象のファイルを出力しないドライバには意味がない
+        return;
+    }
+    let labels = logging.options.entry("labels".to_string()).or_default();
+    for label in SHIPPED_LABELS {
+        if !labels.split(',').any(|l| l.trim() == label) {
+            if !labels.is_empty() {
+                labels.push(',');
+            }
+            labels.push_str(label);
+        }
+    }
+}
+
+/// エージェント設定を `.fleetflow/log-shipping/{stage}/` に書き出す。
+pub fn write_agent_config(
+    project_root: &Path,
+    flow: &Flow,
+    stage: &str,
+    config: &LogShippingConfig,
+) -> anyhow::Result<PathBuf> {
+    let path = agent_config_path(project_root, stage, config.agent);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, render_agent_config(flow, stage, config))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::Stage;
+    use std::collections::HashMap;
+
+    fn loki_config() -> LogShippingConfig {
+        LogShippingConfig {
+            sink: LogSinkKind::Loki,
+            endpoint: Some("http://loki:3100".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn flow_with_stage(log_shipping: Option<LogShippingConfig>) -> Flow {
+        let mut services = HashMap::new();
+        services.insert("api".to_string(), Service::default());
+        let mut stages = HashMap::new();
+        stages.insert(
+            "prod".to_string(),
+            Stage {
+                services: vec!["api".to_string()],
+                log_shipping,
+                ..Default::default()
+            },
+        );
+        Flow {
+            name: "myapp".to_string(),
+            services,
+            stages,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn vector_config_filters_by_project_and_stage_labels() {
+        let yaml = render_agent_config(&flow_with_stage(None), "prod", &loki_config());
+        assert!(yaml.contains("type: \"docker_logs\""));
+        assert!(yaml.contains("- \"fleetflow.project=myapp\""));
+        assert!(yaml.contains("- \"fleetflow.stage=prod\""));
+        assert!(yaml.contains("- \"myapp-prod-log-shipper\""));
+        assert!(yaml.contains("type: \"loki\""));
+        assert!(yaml.contains("endpoint: \"http://loki:3100\""));
+    }
+
+    #[test]
+    fn vector_config_excludes_templated_shipper_container() {
+        let flow = Flow {
+            container_name_template: Some("{service}.{project}".to_string()),
+            ..flow_with_stage(None)
+        };
+        let yaml = render_agent_config(&flow, "prod", &loki_config());
+        assert!(yaml.contains("- \"log-shipper.myapp\""));
+    }
+
+    #[test]
+    fn vector_config_s3_sink() {
+        let config = LogShippingConfig {
+            sink: LogSinkKind::S3,
+            bucket: Some("my-logs".to_string()),
+            region: Some("ap-northeast-1".to_string()),
+            ..Default::default()
+        };
+        let yaml = render_agent_config(&flow_with_stage(None), "prod", &config);
+        assert!(yaml.contains("type: \"aws_s3\""));
+        assert!(yaml.contains("bucket: \"my-logs\""));
+        assert!(yaml.contains("region: \"ap-northeast-1\""));
+        assert!(yaml.contains("key_prefix: \"myapp/prod/%Y-%m-%d/\""));
+    }
+
+    #[test]
+    fn fluent_bit_config_splits_endpoint() {
+        let config = LogShippingConfig {
+            agent: LogShippingAgent::FluentBit,
+            sink: LogSinkKind::Http,
+            endpoint: Some("https://logs.example.com:8443/ingest".to_string()),
+            ..Default::default()
+        };
+        let conf = render_agent_config(&flow_with_stage(None), "prod", &config);
+        assert!(conf.contains("Name   http"));
+        assert!(conf.contains("Host   logs.example.com"));
+        assert!(conf.contains("Port   8443"));
+        assert!(conf.contains("tls    on"));
+        assert!(conf.contains("URI    /ingest"));
+    }
+
+    #[test]
+    fn fluent_bit_config_filters_by_project_and_stage_labels() {
+        let config = LogShippingConfig {
+            agent: LogShippingAgent::FluentBit,
+            ..loki_config()
+        };
+        let flow = Flow {
+            name: "my.app".to_string(),
+            ..flow_with_stage(None)
+        };
+        let conf = render_agent_config(&flow, "prod", &config);
+        assert!(conf.contains("Name   grep"));
+        assert!(conf.contains("Regex  $attrs['fleetflow.project'] ^my\\.app$"));
+        assert!(conf.contains("Regex  $attrs['fleetflow.stage'] ^prod$"));
+    }
+
+    #[test]
+    fn inject_fluent_bit_adds_log_labels() {
+        let config = LogShippingConfig {
+            agent: LogShippingAgent::FluentBit,
+            ..loki_config()
+        };
+        let mut flow = flow_with_stage(Some(config));
+        inject_log_shipper(&mut flow, Path::new("/srv/app"), "prod");
+
+        let logging = flow.services["api"].logging.as_ref().unwrap();
+        assert_eq!(
+            logging.options.get("labels").map(String::as_str),
+            Some("fleetflow.project,fleetflow.stage")
+        );
+    }
+
+    #[test]
+    fn inject_mounts_server_path_for_remote_stage() {
+        let mut flow = flow_with_stage(Some(loki_config()));
+        flow.stages.get_mut("prod").unwrap().servers = vec!["vps-1".to_string()];
+        inject_log_shipper(&mut flow, Path::new("/srv/app"), "prod");
+
+        assert_eq!(
+            flow.services[LOG_SHIPPER_SERVICE].volumes[1].host,
+            PathBuf::from("/var/lib/fleetflow/log-shipping/myapp/prod/vector.yaml")
+        );
+    }
+
+    #[test]
+    fn inject_adds_service_to_stage() {
+        let mut flow = flow_with_stage(Some(loki_config()));
+        inject_log_shipper(&mut flow, Path::new("/srv/app"), "prod");
+
+        let stage = &flow.stages["prod"];
+        assert_eq!(stage.services, vec!["api", LOG_SHIPPER_SERVICE]);
+
+        let shipper = &flow.services[LOG_SHIPPER_SERVICE];
+        assert_eq!(
+            shipper.image.as_deref(),
+            Some("timberio/vector:0.41.1-alpine")
+        );
+        assert_eq!(
+            shipper.volumes[1].host,
+            PathBuf::from("/srv/app/.fleetflow/log-shipping/prod/vector.yaml")
+        );
+    }
+
+    #[test]
+    fn inject_merges_user_defined_service() {
+        let mut flow = flow_with_stage(Some(loki_config()));
+        let mut env = HashMap::new();
+        env.insert("LOKI_TOKEN".to_string(), "secret".to_string());
+        flow.services.insert(
+            LOG_SHIPPER_SERVICE.to_string(),
+            Service {
+                environment: env,
+                ..Default::default()
+            },
+        );
+
+        inject_log_shipper(&mut flow, Path::new("/srv/app"), "prod");
+
+        let shipper = &flow.services[LOG_SHIPPER_SERVICE];
+        assert!(shipper.image.is_some());
+        assert_eq!(
+            shipper.environment.get("LOKI_TOKEN"),
+            Some(&"secret".to_string())
+        );
+        assert_eq!(flow.stages["prod"].services.len(), 2);
+    }
+
+    #[test]
+    fn inject_is_noop_without_log_shipping() {
+        let mut flow = flow_with_stage(None);
+        inject_log_shipper(&mut flow, Path::new("/srv/app"), "prod");
+
+        assert!(!flow.services.contains_key(LOG_SHIPPER_SERVICE));
+        assert_eq!(flow.stages["prod"].services, vec!["api"]);
+    }
+}
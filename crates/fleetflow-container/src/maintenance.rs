@@ -0,0 +1,142 @@
+//! デプロイ先ホストのイメージ保持ポリシー（`maintenance { image_retention N }`）
+//!
+//! プロジェクトでビルドしたイメージをリポジトリごとに新しい順で N 個だけ残し、
+//! それより古いタグを削除対象にする。コンテナ（停止中を含む）が参照するイメージは残す。
+//! デプロイエンジン（bollard）と `fleet remote prune`（SSH 越しの docker CLI）で共用する。
+
+use fleetflow_core::Flow;
+
+use crate::converter::resolve_image;
+
+/// ホスト上のイメージ 1 タグ分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageEntry {
+    /// `repository:tag`
+    pub repo_tag: String,
+    /// イメージ ID
+    pub id: String,
+    /// 作成日時（UNIX 秒）
+    pub created: i64,
+}
+
+/// `repository:tag` をリポジトリとタグに分ける（`localhost:5000/app` のポートはタグ扱いしない）
+fn split_repo_tag(image: &str) -> (&str, &str) {
+    match image.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => (repo, tag),
+        _ => (image, "latest"),
+    }
+}
+
+/// ステージでビルドするイメージのリポジトリ名（保持ポリシーの対象）
+///
+/// `build` を持つサービスのイメージと、既定のビルド名 `{project}-{stage}`。
+pub fn project_build_repositories(flow: &Flow, stage_name: &str) -> Vec<String> {
+    let mut repos = vec![format!("{}-{}", flow.name, stage_name)];
+    if let Some(stage) = flow.stages.get(stage_name) {
+        for name in &stage.services {
+            if let Some(service) = flow.services.get(name)
+                && service.build.is_some()
+            {
+                repos.push(split_repo_tag(&resolve_image(name, service)).0.to_string());
+            }
+        }
+    }
+    repos.sort();
+    repos.dedup();
+    repos
+}
+
+/// 保持数を超えた古いイメージ（`repository:tag`）を返す
+///
+/// リポジトリはレジストリ接頭辞を問わず照合する。`in_use` はコンテナが参照する
+/// イメージ名または ID で、該当するものは保持数に数えたうえで削除対象から外す。
+pub fn retention_candidates(
+    images: &[ImageEntry],
+    repositories: &[String],
+    keep: usize,
+    in_use: &[String],
+) -> Vec<String> {
+    let matches = |repo: &str| {
+        repositories
+            .iter()
+            .any(|r| repo == r || repo.ends_with(&format!("/{}", r)))
+    };
+
+    let mut by_repo: Vec<(&str, Vec<&ImageEntry>)> = Vec::new();
+    for image in images {
+        let (repo, _) = split_repo_tag(&image.repo_tag);
+        if !matches(repo) {
+            continue;
+        }
+        match by_repo.iter_mut().find(|(r, _)| *r == repo) {
+            Some((_, entries)) => entries.push(image),
+            None => by_repo.push((repo, vec![image])),
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (_, mut entries) in by_repo {
+        entries.sort_by(|a, b| b.created.cmp(&a.created));
+        for image in entries.into_iter().skip(keep) {
+            let used = in_use
+                .iter()
+                .any(|u| u == &image.repo_tag || u == &image.id);
+            if !used {
+                candidates.push(image.repo_tag.clone());
+            }
+        }
+    }
+    candidates.sort();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(repo_tag: &str, id: &str, created: i64) -> ImageEntry {
+        ImageEntry {
+            repo_tag: repo_tag.to_string(),
+            id: id.to_string(),
+            created,
+        }
+    }
+
+    #[test]
+    fn test_split_repo_tag() {
+        assert_eq!(split_repo_tag("app:v1"), ("app", "v1"));
+        assert_eq!(
+            split_repo_tag("localhost:5000/app"),
+            ("localhost:5000/app", "latest")
+        );
+        assert_eq!(
+            split_repo_tag("localhost:5000/app:v2"),
+            ("localhost:5000/app", "v2")
+        );
+    }
+
+    #[test]
+    fn test_retention_candidates() {
+        let images = vec![
+            image("ghcr.io/acme/myapp-prod:v1", "sha256:1", 100),
+            image("ghcr.io/acme/myapp-prod:v2", "sha256:2", 200),
+            image("ghcr.io/acme/myapp-prod:v3", "sha256:3", 300),
+            image("ghcr.io/acme/myapp-prod:v4", "sha256:4", 400),
+            image("postgres:15", "sha256:pg15", 50),
+            image("postgres:16", "sha256:pg16", 60),
+        ];
+        let repos = vec!["myapp-prod".to_string()];
+
+        // 新しい 2 個を残す。プロジェクト外（postgres）は対象外
+        assert_eq!(
+            retention_candidates(&images, &repos, 2, &[]),
+            vec!["ghcr.io/acme/myapp-prod:v1", "ghcr.io/acme/myapp-prod:v2"]
+        );
+        // コンテナが参照するイメージは残す
+        assert_eq!(
+            retention_candidates(&images, &repos, 2, &["sha256:1".to_string()]),
+            vec!["ghcr.io/acme/myapp-prod:v2"]
+        );
+        assert!(retention_candidates(&images, &repos, 10, &[]).is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+//! Nomad job 生成 — KDL `Service`/`Stage` → Nomad job（JSON）
+//!
+//! stage が `backend "nomad"`（別名 `target "nomad"`）を宣言しているとき、
+//! 同じ fleet.kdl から docker driver の job を生成し `nomad job run` に渡す。
+//! 接続先は stage の `address` プロパティ、未宣言なら `nomad` CLI の既定（`NOMAD_ADDR`）。
+//! Nomad API を直接叩かず CLI に委ねる理由は `cluster_target` を参照。
+//!
+//! 規約:
+//! - job ID = `{project}-{stage}`（ステージ全体を 1 job にまとめる）
+//! - image は Docker 経路と同じく `resolve_image`（`version` をタグに使う）
+//! - task group / task 名 = サービス名
+//! - attribution は `fleetflow.{project,stage,service}` の Meta
+//! - ホストパスの volume は Nomad クライアントの設定に依存するためマッピングしない
+//! - `user` は task の `User`、`cap_add` / `cap_drop` / `privileged` / `read_only` /
+//!   `security_opt` / `ulimits` / `sysctls` / `shm_size` / `network_mode`（host / none）は
+//!   docker driver の config にマッピングする。対応する表現が無い設定（`tmpfs` /
+//!   `logging` / コンテナ共有の `network_mode`）を持つサービスは黙って落とさずエラーにする
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fleetflow_core::{Flow, NetworkMode, Service, Stage, parse_byte_size};
+use serde_json::{Value, json};
+
+use crate::cluster_target::{ClusterTarget, NomadTarget};
+use crate::converter::resolve_image;
+
+/// job ID（`{project}-{stage}`）。
+pub fn nomad_job_id(project: &str, stage: &str) -> String {
+    format!("{project}-{stage}")
+}
+
+/// Nomad の docker driver に対応する表現が無い設定名。
+///
+/// コンテナ名は Nomad が割り当てる（`{task}-{alloc_id}`）ため、`network_mode "container:..."`
+/// の参照先を job 生成時点で決められない。
+fn unmapped_settings(service: &Service) -> Vec<&'static str> {
+    let mut unmapped = Vec::new();
+    if !service.tmpfs.is_empty() {
+        unmapped.push("tmpfs");
+    }
+    if service.logging.is_some() {
+        unmapped.push("logging");
+    }
+    if matches!(service.network_mode, Some(NetworkMode::Container(_))) {
+        unmapped.push("network_mode（container）");
+    }
+    unmapped
+}
+
+/// セキュリティ・リソース関連の設定を docker driver の config に反映する。
+fn apply_security_config(driver_config: &mut Value, service: &Service) {
+    if !service.cap_add.is_empty() {
+        driver_config["cap_add"] = json!(service.cap_add);
+    }
+    if !service.cap_drop.is_empty() {
+        driver_config["cap_drop"] = json!(service.cap_drop);
+    }
+    if let Some(privileged) = service.privileged {
+        driver_config["privileged"] = json!(privileged);
+    }
+    if let Some(read_only) = service.read_only {
+        driver_config["readonly_rootfs"] = json!(read_only);
+    }
+    if !service.security_opt.is_empty() {
+        driver_config["security_opt"] = json!(service.security_opt);
+    }
+    if !service.ulimits.is_empty() {
+        let ulimits: serde_json::Map<String, Value> = service
+            .ulimits
+            .iter()
+            .map(|u| (u.name.clone(), json!(format!("{}:{}", u.soft, u.hard))))
+            .collect();
+        driver_config["ulimit"] = Value::Object(ulimits);
+    }
+    if !service.sysctls.is_empty() {
+        driver_config["sysctl"] = json!(service.sysctls);
+    }
+    if let Some(size) = service.shm_size.as_deref().and_then(parse_byte_size) {
+        driver_config["shm_size"] = json!(size);
+    }
+    match &service.network_mode {
+        Some(NetworkMode::Host) => driver_config["network_mode"] = json!("host"),
+        Some(NetworkMode::None) => driver_config["network_mode"] = json!("none"),
+        Some(NetworkMode::Container(_)) | None => {}
+    }
+}
+
+/// `Service`/`Stage` から Nomad job（`nomad job run -json` 形式）を生成する（純粋関数）。
+///
+/// 静的サイト（`type "static"`）はコンテナではないためスキップする。
+/// マッピングできない設定（`unmapped_settings`）を持つサービスがあればエラー。
+pub fn generate_nomad_job(config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<Value> {
+    let project = &config.name;
+    let job_id = nomad_job_id(project, stage_name);
+    let mut groups = Vec::new();
+
+    for service_name in &stage.services {
+        let service = config
+            .services
+            .get(service_name)
+            .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
+
+        // 静的サイトはコンテナではないため対象外
+        if service.is_static() {
+            continue;
+        }
+        if service.image.is_none() {
+            anyhow::bail!("サービス '{}' に image がありません", service_name);
+        }
+        let unmapped = unmapped_settings(service);
+        if !unmapped.is_empty() {
+            anyhow::bail!(
+                "backend \"nomad\" ではサービス '{}' の {} をマッピングできません",
+                service_name,
+                unmapped.join(", ")
+            );
+        }
+        let image = resolve_image(service_name, service);
+
+        // ポートはホスト側を予約ポートとして固定し、コンテナ側へ転送
+        let reserved: Vec<Value> = service
+            .ports
+            .iter()
+            .map(|port| {
+                json!({
+                    "Label": format!("p{}", port.host),
+                    "Value": port.host,
+                    "To": port.container,
+                    "HostNetwork": "default",
+                })
+            })
+            .collect();
+        let port_labels: Vec<String> = service
+            .ports
+            .iter()
+            .map(|port| format!("p{}", port.host))
+            .collect();
+
+        let mut driver_config = json!({ "image": image });
+        if !port_labels.is_empty() {
+            driver_config["ports"] = json!(port_labels);
+        }
+        // 実行コマンド（Docker 経路と同じく引数に分割して CMD 相当の args に）
+        if let Some(args) = service.command_args() {
+            driver_config["args"] = json!(args);
+        }
+        if let Some(entrypoint) = &service.entrypoint {
+            driver_config["entrypoint"] = json!(entrypoint);
+        }
+        if let Some(dir) = &service.working_dir {
+            driver_config["work_dir"] = json!(dir);
+        }
+        if let Some(hostname) = &service.hostname {
+            driver_config["hostname"] = json!(hostname);
+        }
+        if !service.labels.is_empty() {
+            driver_config["labels"] = json!(service.labels);
+        }
+        apply_security_config(&mut driver_config, service);
+
+        let mut task = json!({
+            "Name": service_name,
+            "Driver": "docker",
+            "Config": driver_config,
+            "Env": service.environment,
+        });
+        if let Some(user) = &service.user {
+            task["User"] = json!(user);
+        }
+
+        let meta = json!({
+            "fleetflow.project": project,
+            "fleetflow.stage": stage_name,
+            "fleetflow.service": service_name,
+        });
+
+        groups.push(json!({
+            "Name": service_name,
+            "Count": 1,
+            "Meta": meta,
+            "Networks": [{ "ReservedPorts": reserved }],
+            "Tasks": [task],
+        }));
+    }
+
+    Ok(json!({
+        "Job": {
+            "ID": job_id,
+            "Name": job_id,
+            "Type": "service",
+            "Datacenters": ["*"],
+            "Meta": {
+                "fleetflow.project": project,
+                "fleetflow.stage": stage_name,
+            },
+            "TaskGroups": groups,
+        }
+    }))
+}
+
+/// 生成済み job の配置先（`{project_root}/.fleetflow/nomad.{stage}.generated.json`）。
+pub fn nomad_job_path(project_root: &Path, stage_name: &str) -> PathBuf {
+    project_root
+        .join(".fleetflow")
+        .join(format!("nomad.{stage_name}.generated.json"))
+}
+
+/// job を生成してファイルに書き出す。
+pub fn write_nomad_job(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<PathBuf> {
+    let job = generate_nomad_job(config, stage_name, stage)?;
+    let path = nomad_job_path(project_root, stage_name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&job)?)?;
+    Ok(path)
+}
+
+/// `nomad <args...>` コマンド（stage の address があれば `-address` を付与）。
+fn nomad_command(stage: &Stage, args: &[&str]) -> Command {
+    let mut cmd = Command::new("nomad");
+    // サブコマンド（`job run` 等）の後にフラグを置く必要がある
+    let (subcommand, rest) = args.split_at(args.len().min(2));
+    cmd.args(subcommand);
+    if let Some(address) = &stage.backend_address {
+        cmd.arg(format!("-address={address}"));
+    }
+    cmd.args(rest);
+    cmd
+}
+
+fn run_nomad(stage: &Stage, args: &[&str]) -> io::Result<()> {
+    let status = nomad_command(stage, args).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "`nomad {}` failed (exit {:?})",
+            args.join(" "),
+            status.code()
+        )))
+    }
+}
+
+/// stage の job を投入する（`nomad job run -json`）。
+pub fn nomad_up(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<PathBuf> {
+    let file = write_nomad_job(project_root, config, stage_name, stage)?;
+    let file_arg = file.to_string_lossy();
+    run_nomad(stage, &["job", "run", "-json", &file_arg])?;
+    Ok(file)
+}
+
+/// stage の job を停止する（`nomad job stop`）。
+///
+/// `remove` 指定時は `-purge` で job 定義ごと削除する。
+pub fn nomad_down(
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+    remove: bool,
+) -> anyhow::Result<()> {
+    let job_id = nomad_job_id(&config.name, stage_name);
+    let mut args = vec!["job", "stop"];
+    if remove {
+        args.push("-purge");
+    }
+    args.push(&job_id);
+    run_nomad(stage, &args)?;
+    Ok(())
+}
+
+/// stage の job の状態を表示する（`nomad job status`）。
+pub fn nomad_status(config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<()> {
+    let job_id = nomad_job_id(&config.name, stage_name);
+    run_nomad(stage, &["job", "status", &job_id])?;
+    Ok(())
+}
+
+impl ClusterTarget for NomadTarget {
+    fn name(&self) -> &'static str {
+        "nomad"
+    }
+
+    fn resource(&self, config: &Flow, stage_name: &str) -> String {
+        format!("job {}", nomad_job_id(&config.name, stage_name))
+    }
+
+    fn render(&self, config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<String> {
+        let job = generate_nomad_job(config, stage_name, stage)?;
+        Ok(serde_json::to_string_pretty(&job)?)
+    }
+
+    fn up(
+        &self,
+        project_root: &Path,
+        config: &Flow,
+        stage_name: &str,
+        stage: &Stage,
+    ) -> anyhow::Result<PathBuf> {
+        nomad_up(project_root, config, stage_name, stage)
+    }
+
+    fn down(
+        &self,
+        config: &Flow,
+        stage_name: &str,
+        stage: &Stage,
+        remove: bool,
+    ) -> anyhow::Result<()> {
+        nomad_down(config, stage_name, stage, remove)
+    }
+
+    fn status(&self, config: &Flow, stage_name: &str, stage: &Stage) -> anyhow::Result<()> {
+        nomad_status(config, stage_name, stage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{LoggingConfig, Port, Protocol, ServiceType, TmpfsMount, Ulimit};
+    use std::collections::HashMap;
+
+    fn flow_with(services: Vec<(&str, Service)>, stage_services: Vec<&str>) -> (Flow, Stage) {
+        let mut svc_map = HashMap::new();
+        for (name, svc) in services {
+            svc_map.insert(name.to_string(), svc);
+        }
+        let stage = Stage {
+            services: stage_services.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut stages = HashMap::new();
+        stages.insert("prod".to_string(), stage.clone());
+        let flow = Flow {
+            name: "myapp".to_string(),
+            services: svc_map,
+            stages,
+            ..Default::default()
+        };
+        (flow, stage)
+    }
+
+    fn web_service() -> Service {
+        Service {
+            image: Some("nginx:1.27".to_string()),
+            command: Some("nginx -g daemon_off".to_string()),
+            ports: vec![Port {
+                host: 8080,
+                container: 80,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            environment: HashMap::from([("MODE".to_string(), "prod".to_string())]),
+            ..Service::default()
+        }
+    }
+
+    #[test]
+    fn generate_nomad_job_maps_services_to_groups() {
+        let (flow, stage) = flow_with(vec![("web", web_service())], vec!["web"]);
+        let job = generate_nomad_job(&flow, "prod", &stage).unwrap();
+        let job = &job["Job"];
+        assert_eq!(job["ID"], "myapp-prod");
+        assert_eq!(job["Type"], "service");
+
+        let group = &job["TaskGroups"][0];
+        assert_eq!(group["Name"], "web");
+        assert_eq!(group["Meta"]["fleetflow.service"], "web");
+        assert_eq!(group["Networks"][0]["ReservedPorts"][0]["Value"], 8080);
+        assert_eq!(group["Networks"][0]["ReservedPorts"][0]["To"], 80);
+
+        let task = &group["Tasks"][0];
+        assert_eq!(task["Driver"], "docker");
+        assert_eq!(task["Config"]["image"], "nginx:1.27");
+        assert_eq!(task["Config"]["ports"], json!(["p8080"]));
+        assert_eq!(task["Config"]["args"], json!(["nginx", "-g", "daemon_off"]));
+        assert_eq!(task["Env"]["MODE"], "prod");
+    }
+
+    #[test]
+    fn generate_nomad_job_uses_version_as_tag() {
+        let mut svc = web_service();
+        svc.image = Some("nginx".to_string());
+        svc.version = Some("1.2.3".to_string());
+        let worker = Service {
+            image: Some("worker".to_string()),
+            ..Service::default()
+        };
+        let (flow, stage) = flow_with(
+            vec![("web", svc), ("worker", worker)],
+            vec!["web", "worker"],
+        );
+        let job = generate_nomad_job(&flow, "prod", &stage).unwrap();
+        let groups = &job["Job"]["TaskGroups"];
+        assert_eq!(groups[0]["Tasks"][0]["Config"]["image"], "nginx:1.2.3");
+        assert_eq!(groups[1]["Tasks"][0]["Config"]["image"], "worker:latest");
+    }
+
+    #[test]
+    fn generate_nomad_job_maps_security_settings() {
+        let mut svc = web_service();
+        svc.user = Some("1000:1000".to_string());
+        svc.cap_add = vec!["NET_ADMIN".to_string()];
+        svc.cap_drop = vec!["ALL".to_string()];
+        svc.privileged = Some(false);
+        svc.read_only = Some(true);
+        svc.security_opt = vec!["no-new-privileges:true".to_string()];
+        svc.ulimits = vec![Ulimit {
+            name: "nofile".to_string(),
+            soft: 1024,
+            hard: 4096,
+        }];
+        svc.sysctls
+            .insert("net.core.somaxconn".into(), "1024".into());
+        svc.shm_size = Some("64m".to_string());
+        svc.network_mode = Some(NetworkMode::Host);
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        let job = generate_nomad_job(&flow, "prod", &stage).unwrap();
+        let task = &job["Job"]["TaskGroups"][0]["Tasks"][0];
+        assert_eq!(task["User"], "1000:1000");
+        let config = &task["Config"];
+        assert_eq!(config["cap_add"], json!(["NET_ADMIN"]));
+        assert_eq!(config["cap_drop"], json!(["ALL"]));
+        assert_eq!(config["privileged"], false);
+        assert_eq!(config["readonly_rootfs"], true);
+        assert_eq!(config["security_opt"], json!(["no-new-privileges:true"]));
+        assert_eq!(config["ulimit"]["nofile"], "1024:4096");
+        assert_eq!(config["sysctl"]["net.core.somaxconn"], "1024");
+        assert_eq!(config["shm_size"], 64 * 1024 * 1024);
+        assert_eq!(config["network_mode"], "host");
+    }
+
+    #[test]
+    fn generate_nomad_job_rejects_unmapped_settings() {
+        let mut svc = web_service();
+        svc.tmpfs = vec![TmpfsMount {
+            target: "/tmp".into(),
+            size: None,
+            mode: None,
+        }];
+        svc.logging = Some(LoggingConfig::default());
+        let (flow, stage) = flow_with(vec![("web", svc)], vec!["web"]);
+        let err = generate_nomad_job(&flow, "prod", &stage)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'web'"));
+        assert!(err.contains("tmpfs"));
+        assert!(err.contains("logging"));
+    }
+
+    #[test]
+    fn generate_nomad_job_skips_static_services() {
+        let static_svc = Service {
+            service_type: Some(ServiceType::Static),
+            ..Service::default()
+        };
+        let (flow, stage) = flow_with(
+            vec![("web", web_service()), ("site", static_svc)],
+            vec!["web", "site"],
+        );
+        let job = generate_nomad_job(&flow, "prod", &stage).unwrap();
+        assert_eq!(job["Job"]["TaskGroups"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn nomad_command_places_address_after_subcommand() {
+        let stage = Stage {
+            backend_address: Some("http://nomad:4646".to_string()),
+            ..Default::default()
+        };
+        let cmd = nomad_command(&stage, &["job", "status", "myapp-prod"]);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            ["job", "status", "-address=http://nomad:4646", "myapp-prod"]
+        );
+    }
+}
@@ -84,7 +84,96 @@ pub async fn ensure_port_available(port: u16) -> Result<()> {
     Ok(())
 }
 
+/// サービスの公開ポートからローカルでアクセスする URL を組み立てる
+///
+/// `port_name` 指定時はその名前のポートを、省略時は `protocol="http"` / `"https"` の
+/// ポート、なければ最初の TCP ポートを使う。
+pub fn published_url(service: &fleetflow_core::Service, port_name: Option<&str>) -> Option<String> {
+    let port = match port_name {
+        Some(name) => service.port_by_name(name)?,
+        None => service
+            .ports
+            .iter()
+            .find(|p| p.app_protocol.is_some())
+            .or_else(|| {
+                service
+                    .ports
+                    .iter()
+                    .find(|p| p.protocol == fleetflow_core::Protocol::Tcp)
+            })?,
+    };
+    port.url()
+}
+
 fn is_process_alive(pid: i32) -> bool {
     // signal 0 を送ることで存在確認が可能
     signal::kill(Pid::from_raw(pid), None).is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{AppProtocol, Port, Protocol, Service};
+
+    fn port(host: u16, container: u16, protocol: Protocol, host_ip: Option<&str>) -> Port {
+        Port {
+            host,
+            container,
+            protocol,
+            host_ip: host_ip.map(str::to_string),
+            name: None,
+            app_protocol: None,
+        }
+    }
+
+    #[test]
+    fn test_published_url() {
+        let mut service = Service::default();
+        assert_eq!(published_url(&service, None), None);
+
+        service.ports = vec![
+            port(5353, 53, Protocol::Udp, None),
+            port(3000, 80, Protocol::Tcp, Some("0.0.0.0")),
+        ];
+        assert_eq!(
+            published_url(&service, None).as_deref(),
+            Some("http://localhost:3000")
+        );
+
+        service.ports = vec![port(8443, 443, Protocol::Tcp, Some("127.0.0.1"))];
+        assert_eq!(
+            published_url(&service, None).as_deref(),
+            Some("https://127.0.0.1:8443")
+        );
+
+        service.ports = vec![port(8080, 80, Protocol::Tcp, Some("::1"))];
+        assert_eq!(
+            published_url(&service, None).as_deref(),
+            Some("http://[::1]:8080")
+        );
+    }
+
+    #[test]
+    fn test_published_url_named_port() {
+        let mut db = port(15432, 5432, Protocol::Tcp, None);
+        db.name = Some("db".to_string());
+        let mut admin = port(18443, 8443, Protocol::Tcp, None);
+        admin.name = Some("admin".to_string());
+        admin.app_protocol = Some(AppProtocol::Https);
+        let service = Service {
+            ports: vec![db, admin],
+            ..Default::default()
+        };
+
+        // 省略時は http / https 指定のポートを優先する
+        assert_eq!(
+            published_url(&service, None).as_deref(),
+            Some("https://localhost:18443")
+        );
+        assert_eq!(
+            published_url(&service, Some("db")).as_deref(),
+            Some("http://localhost:15432")
+        );
+        assert_eq!(published_url(&service, Some("metrics")), None);
+    }
+}
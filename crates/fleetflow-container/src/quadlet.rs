@@ -23,7 +23,7 @@ use fleetflow_core::{Flow, Protocol, RestartPolicy, Service, Stage};
 
 /// `{project}-{stage}-{service}` 形式の正準名を組み立てる。
 ///
-/// Quadlet `.container` ファイル名・`ContainerName=`・systemd unit 名の基底。
+/// Quadlet `.container` ファイル名・systemd unit 名の基底（`ContainerName=` の既定値でもある）。
 pub fn unit_base_name(project: &str, stage: &str, service: &str) -> String {
     format!("{project}-{stage}-{service}")
 }
@@ -74,12 +74,13 @@ fn health_cmd(test: &[String]) -> Option<String> {
 /// `service` には KDL `service` ブロックの定義、`service_name` は KDL 上の
 /// サービス名を渡す。
 pub fn generate_container_unit(
-    project: &str,
+    config: &Flow,
     stage: &str,
     service_name: &str,
     service: &Service,
     depends_on: &[String],
 ) -> String {
+    let project = config.name.as_str();
     let base = unit_base_name(project, stage, service_name);
     let mut out = String::new();
 
@@ -102,11 +103,23 @@ pub fn generate_container_unit(
 
     // ── [Container] ──
     out.push_str("[Container]\n");
-    out.push_str(&format!("ContainerName={base}\n"));
+    out.push_str(&format!(
+        "ContainerName={}\n",
+        fleetflow_core::container_name(
+            service.container_name.as_deref(),
+            project,
+            stage,
+            service_name
+        )
+    ));
     if let Some(image) = &service.image {
         out.push_str(&format!("Image={image}\n"));
     }
-    out.push_str(&format!("Network={}\n", network_file_name(project, stage)));
+    // network_mode 指定時は host / none / container:<コンテナ名> をそのまま渡す
+    match &service.network_mode {
+        Some(mode) => out.push_str(&format!("Network={}\n", mode.docker_value(config, stage))),
+        None => out.push_str(&format!("Network={}\n", network_file_name(project, stage))),
+    }
 
     // ポート公開
     for port in &service.ports {
@@ -147,11 +160,29 @@ pub fn generate_container_unit(
     out.push_str(&format!("Label=fleetflow.project={project}\n"));
     out.push_str(&format!("Label=fleetflow.stage={stage}\n"));
     out.push_str(&format!("Label=fleetflow.service={service_name}\n"));
+    let mut labels: Vec<(&String, &String)> = service.labels.iter().collect();
+    labels.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in labels {
+        out.push_str(&format!("Label={key}={value}\n"));
+    }
 
     // 実行コマンド上書き
     if let Some(command) = &service.command {
         out.push_str(&format!("Exec={command}\n"));
     }
+    // podman の --entrypoint は JSON 配列で複数の引数を受け付ける
+    if let Some(entrypoint) = &service.entrypoint {
+        out.push_str(&format!(
+            "Entrypoint={}\n",
+            serde_json::to_string(entrypoint).unwrap_or_default()
+        ));
+    }
+    if let Some(dir) = &service.working_dir {
+        out.push_str(&format!("WorkingDir={dir}\n"));
+    }
+    if let Some(hostname) = &service.hostname {
+        out.push_str(&format!("HostName={hostname}\n"));
+    }
 
     // ヘルスチェック
     if let Some(hc) = &service.healthcheck
@@ -351,7 +382,7 @@ pub fn build_stage_units(
         units.push(QuadletUnit {
             file_name: container_file_name(project, stage_name, service_name),
             content: generate_container_unit(
-                project,
+                config,
                 stage_name,
                 service_name,
                 service,
@@ -433,6 +464,13 @@ mod tests {
         }
     }
 
+    fn flow(name: &str) -> Flow {
+        Flow {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn unit_naming_follows_project_stage_service() {
         assert_eq!(unit_base_name("myapp", "live", "db"), "myapp-live-db");
@@ -467,7 +505,7 @@ mod tests {
 
     #[test]
     fn container_unit_has_required_sections_and_labels() {
-        let unit = generate_container_unit("myapp", "live", "db", &base_service(), &[]);
+        let unit = generate_container_unit(&flow("myapp"), "live", "db", &base_service(), &[]);
         assert!(unit.contains("[Unit]"));
         assert!(unit.contains("[Container]"));
         assert!(unit.contains("[Service]"));
@@ -482,6 +520,20 @@ mod tests {
         assert!(unit.contains("WantedBy=default.target"));
     }
 
+    #[test]
+    fn container_unit_uses_network_mode() {
+        let mut svc = base_service();
+        svc.network_mode = Some(fleetflow_core::NetworkMode::Container("vpn".to_string()));
+        let unit =
+            generate_container_unit(&flow("myapp"), "live", "app", &svc, &["vpn".to_string()]);
+        assert!(unit.contains("Network=container:myapp-live-vpn\n"));
+        assert!(!unit.contains("Network=myapp-live.network"));
+
+        svc.network_mode = Some(fleetflow_core::NetworkMode::Host);
+        let unit = generate_container_unit(&flow("myapp"), "live", "app", &svc, &[]);
+        assert!(unit.contains("Network=host\n"));
+    }
+
     #[test]
     fn container_unit_renders_ports_with_protocol() {
         let mut svc = base_service();
@@ -491,15 +543,19 @@ mod tests {
                 container: 80,
                 protocol: Protocol::Tcp,
                 host_ip: None,
+                name: None,
+                app_protocol: None,
             },
             Port {
                 host: 53,
                 container: 53,
                 protocol: Protocol::Udp,
                 host_ip: Some("127.0.0.1".to_string()),
+                name: None,
+                app_protocol: None,
             },
         ];
-        let unit = generate_container_unit("myapp", "live", "web", &svc, &[]);
+        let unit = generate_container_unit(&flow("myapp"), "live", "web", &svc, &[]);
         assert!(unit.contains("PublishPort=8080:80\n"));
         assert!(unit.contains("PublishPort=127.0.0.1:53:53/udp\n"));
     }
@@ -516,7 +572,7 @@ mod tests {
             read_only: true,
         }];
         svc.command = Some("npm start".into());
-        let unit = generate_container_unit("myapp", "live", "api", &svc, &[]);
+        let unit = generate_container_unit(&flow("myapp"), "live", "api", &svc, &[]);
         // env はキー順にソートされる（決定的出力）
         let port_idx = unit.find("Environment=PORT=3000").unwrap();
         let node_idx = unit.find("Environment=NODE_ENV=production").unwrap();
@@ -549,7 +605,7 @@ mod tests {
             retries: 5,
             start_period: 10,
         });
-        let unit = generate_container_unit("myapp", "live", "db", &svc, &[]);
+        let unit = generate_container_unit(&flow("myapp"), "live", "db", &svc, &[]);
         assert!(unit.contains("HealthCmd=pg_isready\n"));
         assert!(unit.contains("HealthInterval=30s\n"));
         assert!(unit.contains("HealthRetries=5\n"));
@@ -741,11 +797,7 @@ mod tests {
             name: "myapp".to_string(),
             services: svc_map,
             stages,
-            providers: std::collections::HashMap::new(),
-            servers: std::collections::HashMap::new(),
-            registry: None,
-            variables: std::collections::HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
         (flow, stage)
     }
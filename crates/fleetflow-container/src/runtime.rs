@@ -37,8 +37,17 @@ impl Runtime {
                 .services
                 .get(service_name)
                 .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", service_name))?;
-
-            self.up_service(service_name, service, stage_name, &flow.name, pull)
+            let (service, _) = crate::materialize_configs(
+                &self.project_root,
+                flow,
+                stage_name,
+                service_name,
+                service,
+            )?;
+            // tmpfs のシークレットはホスト再起動で消えるため起動のたびに書き出す
+            let service = crate::materialize_secrets(flow, stage_name, service_name, &service)?;
+
+            self.up_service(service_name, &service, stage_name, flow, pull)
                 .await?;
         }
 
@@ -69,7 +78,7 @@ impl Runtime {
         name: &str,
         service: &fleetflow_core::model::Service,
         stage_name: &str,
-        project_name: &str,
+        flow: &Flow,
         _pull: bool, // TODO: Pull ロジックの実装
     ) -> Result<()> {
         info!("Starting service: {}", name);
@@ -79,9 +88,9 @@ impl Runtime {
             crate::port::ensure_port_available(port.host).await?;
         }
 
-        let container_name = format!("{}-{}-{}", project_name, stage_name, name);
         let (container_config, create_options) =
-            crate::service_to_container_config(name, service, stage_name, project_name);
+            crate::service_to_container_config(name, service, stage_name, flow);
+        let container_name = create_options.name.clone().unwrap_or_default();
 
         // コンテナの作成と起動
         match self
@@ -124,7 +133,7 @@ impl Runtime {
             .ok_or_else(|| anyhow::anyhow!("Stage '{}' not found", stage_name))?;
 
         for service_name in &stage.services {
-            let container_name = format!("{}-{}-{}", flow.name, stage_name, service_name);
+            let container_name = flow.container_name(stage_name, service_name);
 
             info!("Stopping container: {}", container_name);
             let _ = self
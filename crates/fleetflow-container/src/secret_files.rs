@@ -0,0 +1,522 @@
+//! シークレットのファイルマウント — `secrets` → tmpfs 上のファイル
+//!
+//! トップレベル `secrets` で宣言した値を解決し、ホストの tmpfs
+//! （`$XDG_RUNTIME_DIR` または `/dev/shm`）にファイルとして書き出して
+//! read-only で bind mount する。値は環境変数やディスクには残らない。
+//!
+//! tmpfs が無い環境（macOS 等）では平文をディスクに書くことになるため既定ではエラーにし、
+//! `FLEETFLOW_SECRETS_DIR` で書き出し先を明示したときだけ使う。
+//!
+//! 書き出したファイルは `fleet down` で削除する。tmpfs はホストの再起動で消えるため、
+//! `fleet up` / `fleet restart` のたびに書き戻す。再起動後にコンテナだけが自動起動した
+//! 経路（Compose / Quadlet の systemd ユニット、リモートサーバー）ではファイルを
+//! 書き戻せないので、これらの経路では `secret` マウントを拒否する。
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use fleetflow_core::{Flow, SecretDef, Service, Volume, onepassword};
+
+/// 書き出し先を明示する環境変数（tmpfs が無い環境でのオプトイン）
+pub const SECRETS_DIR_ENV: &str = "FLEETFLOW_SECRETS_DIR";
+
+/// シークレットファイルのルートディレクトリ
+///
+/// 1. `$FLEETFLOW_SECRETS_DIR`（明示指定。tmpfs かどうかは利用者の責任）
+/// 2. `$XDG_RUNTIME_DIR/fleetflow/secrets`（systemd 環境ではユーザー専用 tmpfs）
+/// 3. `/dev/shm/fleetflow/secrets`
+///
+/// いずれも無ければエラー（平文を黙ってディスクに書かない）。
+pub fn secrets_root() -> anyhow::Result<PathBuf> {
+    resolve_secrets_root(
+        std::env::var_os(SECRETS_DIR_ENV),
+        std::env::var_os("XDG_RUNTIME_DIR"),
+        Path::new("/dev/shm"),
+    )
+}
+
+fn resolve_secrets_root(
+    explicit: Option<OsString>,
+    runtime_dir: Option<OsString>,
+    shm: &Path,
+) -> anyhow::Result<PathBuf> {
+    if let Some(dir) = explicit.filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(runtime_dir) = runtime_dir.filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(runtime_dir).join("fleetflow").join("secrets"));
+    }
+    if shm.is_dir() {
+        return Ok(shm.join("fleetflow").join("secrets"));
+    }
+    anyhow::bail!(
+        "シークレットを書き出す tmpfs が見つかりません（XDG_RUNTIME_DIR も /dev/shm もありません）。\
+         ディスク上のディレクトリに平文で書き出してよい場合は {} で書き出し先を指定してください",
+        SECRETS_DIR_ENV
+    )
+}
+
+/// ステージ単位のシークレットディレクトリ
+pub fn stage_secrets_dir(root: &Path, project: &str, stage: &str) -> PathBuf {
+    root.join(format!("{}-{}", project, stage))
+}
+
+/// シークレットの値を解決する
+///
+/// `from` が `op://` 参照なら 1Password から、それ以外はリテラルとして扱う。
+/// `env` はホスト側の環境変数から読み取る。
+pub fn resolve_secret(name: &str, secret: &SecretDef) -> anyhow::Result<String> {
+    match (&secret.from, &secret.env) {
+        (Some(from), _) if onepassword::is_op_reference(from) => {
+            onepassword::resolve_reference(from)
+                .map_err(|e| anyhow::anyhow!("secret '{}' の解決に失敗: {}", name, e))
+        }
+        (Some(from), _) => Ok(from.clone()),
+        (None, Some(env)) => std::env::var(env).map_err(|_| {
+            anyhow::anyhow!(
+                "secret '{}' の環境変数 '{}' が設定されていません",
+                name,
+                env
+            )
+        }),
+        (None, None) => anyhow::bail!("secret '{}' には from または env が必要です", name),
+    }
+}
+
+/// サービスの `secret` マウントを書き出し、bind mount を追加したサービスを返す。
+pub fn materialize_secrets(
+    flow: &Flow,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+) -> anyhow::Result<Service> {
+    // シークレットを使わないサービスは tmpfs の有無に関係なく通す
+    if service.secrets.is_empty() {
+        return Ok(service.clone());
+    }
+    materialize_secrets_in(&secrets_root()?, flow, stage_name, service_name, &service)
+}
+
+/// `materialize_secrets` の書き出し先ルートを指定できる版
+pub fn materialize_secrets_in(
+    root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+) -> anyhow::Result<Service> {
+    let mut service = service.clone();
+    if service.secrets.is_empty() {
+        return Ok(service);
+    }
+
+    let stage_dir = stage_secrets_dir(root, &flow.name, stage_name);
+    create_private_dir(&stage_dir)?;
+    let dir = stage_dir.join(service_name);
+    create_private_dir(&dir)?;
+
+    for mount in &service.secrets {
+        let secret = flow.secrets.get(&mount.source).ok_or_else(|| {
+            anyhow::anyhow!(
+                "サービス '{}' が未定義の secret '{}' を参照しています",
+                service_name,
+                mount.source
+            )
+        })?;
+        let value = resolve_secret(&mount.source, secret)?;
+
+        let path = dir.join(&mount.source);
+        write_secret_file(&path, &value, service.numeric_user())?;
+
+        service.volumes.push(Volume {
+            host: path,
+            container: mount.target_path(),
+            read_only: true,
+        });
+    }
+
+    Ok(service)
+}
+
+/// ステージの全サービスのシークレットファイルを書き戻す（`fleet restart` 用）
+///
+/// 既存コンテナの bind mount 元と同じパスに書くので、再起動したコンテナから読める。
+pub fn rematerialize_stage_secrets(flow: &Flow, stage_name: &str) -> anyhow::Result<()> {
+    let uses_secrets = flow.stages.get(stage_name).is_some_and(|stage| {
+        stage.services.iter().any(|name| {
+            flow.services
+                .get(name)
+                .is_some_and(|s| !s.secrets.is_empty())
+        })
+    });
+    if !uses_secrets {
+        return Ok(());
+    }
+    rematerialize_stage_secrets_in(&secrets_root()?, flow, stage_name)
+}
+
+/// `rematerialize_stage_secrets` の書き出し先ルートを指定できる版
+pub fn rematerialize_stage_secrets_in(
+    root: &Path,
+    flow: &Flow,
+    stage_name: &str,
+) -> anyhow::Result<()> {
+    let Some(stage) = flow.stages.get(stage_name) else {
+        return Ok(());
+    };
+    for service_name in &stage.services {
+        if let Some(service) = flow.services.get(service_name) {
+            materialize_secrets_in(root, flow, stage_name, service_name, service)?;
+        }
+    }
+    Ok(())
+}
+
+/// `secret` マウントを扱えない経路（`target`）で、シークレットを使うサービスを拒否する。
+///
+/// `services` は対象サービス名。ファイルが無いまま起動させないよう黙って落とさない。
+pub fn reject_secrets(flow: &Flow, services: &[String], target: &str) -> anyhow::Result<()> {
+    let using: Vec<&str> = services
+        .iter()
+        .filter(|name| {
+            flow.services
+                .get(name.as_str())
+                .is_some_and(|s| !s.secrets.is_empty())
+        })
+        .map(String::as_str)
+        .collect();
+    if using.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{} では secret マウントを扱えません（対象サービス: {}）。docker バックエンドのローカルステージで使用してください",
+        target,
+        using.join(", ")
+    )
+}
+
+/// ステージのシークレットファイルを削除する（`fleet down` 用）
+///
+/// 削除した場合は `true` を返す。
+///
+/// 書き出し先が決まらない環境では何も書き出していないので `false`。
+pub fn remove_stage_secrets(project: &str, stage: &str) -> anyhow::Result<bool> {
+    let Ok(root) = secrets_root() else {
+        return Ok(false);
+    };
+    remove_stage_secrets_in(&root, project, stage)
+}
+
+/// `remove_stage_secrets` の対象ルートを指定できる版
+pub fn remove_stage_secrets_in(root: &Path, project: &str, stage: &str) -> anyhow::Result<bool> {
+    let dir = stage_secrets_dir(root, project, stage);
+    if !dir.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_dir_all(&dir)?;
+    Ok(true)
+}
+
+/// シークレットファイルを 0600 で書き出す
+///
+/// 既存ファイルへ上書きする（inode を保つため稼働中コンテナにも反映される）。
+/// サービスの `user` が数値の uid なら、コンテナ内の非 root ユーザーが読めるよう
+/// その uid（と gid）を所有者にする。それ以外はコンテナが root で動く前提。
+fn write_secret_file(
+    path: &Path,
+    value: &str,
+    owner: Option<(u32, Option<u32>)>,
+) -> anyhow::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        // 以前の版が 0644 で書いたファイルも絞り直す
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        if let Some((uid, gid)) = owner
+            && file.metadata()?.uid() != uid
+        {
+            std::os::unix::fs::chown(path, Some(uid), gid).map_err(|e| {
+                anyhow::anyhow!(
+                    "シークレットファイル {} の所有者を uid {} に変更できません: {}",
+                    path.display(),
+                    uid,
+                    e
+                )
+            })?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = owner;
+    file.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+/// ディレクトリを作成し、所有者のみアクセス可能にする
+fn create_private_dir(dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::SecretMount;
+    use std::collections::HashMap;
+
+    fn flow_with_secret(secret: SecretDef) -> Flow {
+        let mut secrets = HashMap::new();
+        secrets.insert("db_password".to_string(), secret);
+        Flow {
+            name: "myapp".to_string(),
+            secrets,
+            ..Default::default()
+        }
+    }
+
+    fn service_with_secret(target: Option<&str>) -> Service {
+        Service {
+            secrets: vec![SecretMount {
+                source: "db_password".to_string(),
+                target: target.map(PathBuf::from),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolves_literal_value() {
+        let secret = SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_secret("db_password", &secret).unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn missing_env_is_error() {
+        let secret = SecretDef {
+            env: Some("FLEETFLOW_TEST_SECRET_SURELY_UNSET".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_secret("db_password", &secret).is_err());
+    }
+
+    #[test]
+    fn materialize_writes_file_and_adds_read_only_bind() {
+        let root = tempfile::tempdir().unwrap();
+        let flow = flow_with_secret(SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        });
+
+        let service = materialize_secrets_in(
+            root.path(),
+            &flow,
+            "prod",
+            "api",
+            &service_with_secret(Some("/etc/app/db_password")),
+        )
+        .unwrap();
+
+        let expected = root.path().join("myapp-prod/api/db_password");
+        assert_eq!(std::fs::read_to_string(&expected).unwrap(), "hunter2");
+        assert_eq!(service.volumes.len(), 1);
+        assert_eq!(service.volumes[0].host, expected);
+        assert_eq!(
+            service.volumes[0].container,
+            PathBuf::from("/etc/app/db_password")
+        );
+        assert!(service.volumes[0].read_only);
+        assert!(service.environment.is_empty());
+    }
+
+    #[test]
+    fn default_target_is_run_secrets() {
+        let root = tempfile::tempdir().unwrap();
+        let flow = flow_with_secret(SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        });
+
+        let service = materialize_secrets_in(
+            root.path(),
+            &flow,
+            "prod",
+            "api",
+            &service_with_secret(None),
+        )
+        .unwrap();
+
+        assert_eq!(
+            service.volumes[0].container,
+            PathBuf::from("/run/secrets/db_password")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let flow = flow_with_secret(SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        });
+        materialize_secrets_in(
+            root.path(),
+            &flow,
+            "prod",
+            "api",
+            &service_with_secret(None),
+        )
+        .unwrap();
+
+        let mode = std::fs::metadata(root.path().join("myapp-prod"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_file_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let flow = flow_with_secret(SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        });
+        let service = materialize_secrets_in(
+            root.path(),
+            &flow,
+            "prod",
+            "api",
+            &service_with_secret(None),
+        )
+        .unwrap();
+
+        let mode = std::fs::metadata(&service.volumes[0].host)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn secrets_root_prefers_explicit_then_tmpfs() {
+        let missing = Path::new("/nonexistent/shm");
+        assert_eq!(
+            resolve_secrets_root(
+                Some("/var/secrets".into()),
+                Some("/run/user/1000".into()),
+                missing
+            )
+            .unwrap(),
+            PathBuf::from("/var/secrets")
+        );
+        assert_eq!(
+            resolve_secrets_root(None, Some("/run/user/1000".into()), missing).unwrap(),
+            PathBuf::from("/run/user/1000/fleetflow/secrets")
+        );
+
+        let shm = tempfile::tempdir().unwrap();
+        assert_eq!(
+            resolve_secrets_root(None, None, shm.path()).unwrap(),
+            shm.path().join("fleetflow").join("secrets")
+        );
+    }
+
+    #[test]
+    fn secrets_root_without_tmpfs_is_error() {
+        let err = resolve_secrets_root(None, None, Path::new("/nonexistent/shm"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(SECRETS_DIR_ENV));
+    }
+
+    #[test]
+    fn remove_stage_secrets_cleans_up() {
+        let root = tempfile::tempdir().unwrap();
+        let flow = flow_with_secret(SecretDef {
+            from: Some("hunter2".to_string()),
+            ..Default::default()
+        });
+        materialize_secrets_in(
+            root.path(),
+            &flow,
+            "prod",
+            "api",
+            &service_with_secret(None),
+        )
+        .unwrap();
+
+        assert!(remove_stage_secrets_in(root.path(), "myapp", "prod").unwrap());
+        assert!(!root.path().join("myapp-prod").exists());
+        assert!(!remove_stage_secrets_in(root.path(), "myapp", "prod").unwrap());
+    }
+
+    #[test]
+    fn rematerialize_restores_removed_files() {
+        let root = tempfile::tempdir().unwrap();
+        let mut flow = flow_with_secret(SecretDef {
+            from: Some("s3cr3t".to_string()),
+            ..Default::default()
+        });
+        flow.services
+            .insert("db".to_string(), service_with_secret(None));
+        flow.stages.insert(
+            "prod".to_string(),
+            fleetflow_core::Stage {
+                services: vec!["db".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let service =
+            materialize_secrets_in(root.path(), &flow, "prod", "db", &flow.services["db"]).unwrap();
+        let path = service.volumes[0].host.clone();
+        // ホスト再起動で tmpfs が消えた状態
+        remove_stage_secrets_in(root.path(), "myapp", "prod").unwrap();
+        assert!(!path.exists());
+
+        rematerialize_stage_secrets_in(root.path(), &flow, "prod").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "s3cr3t");
+    }
+
+    #[test]
+    fn reject_secrets_names_services() {
+        let mut flow = flow_with_secret(SecretDef {
+            from: Some("s3cr3t".to_string()),
+            ..Default::default()
+        });
+        flow.services
+            .insert("db".to_string(), service_with_secret(None));
+        flow.services.insert("web".to_string(), Service::default());
+
+        assert!(reject_secrets(&flow, &["web".to_string()], "backend \"compose\"").is_ok());
+        let err = reject_secrets(
+            &flow,
+            &["web".to_string(), "db".to_string()],
+            "backend \"compose\"",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("対象サービス: db"));
+    }
+}
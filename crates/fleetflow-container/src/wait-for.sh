@@ -0,0 +1,72 @@
+# fleetflow wait-for: 依存サービスの準備完了を待ってから本来のコマンドを exec する
+#
+# FLEETFLOW_WAIT_TARGETS    空白区切りの "tcp:host:port" / "http://host:port/path"
+# FLEETFLOW_WAIT_RETRIES    最大試行回数
+# FLEETFLOW_WAIT_DELAY      初回の待機秒数
+# FLEETFLOW_WAIT_MAX_DELAY  待機秒数の上限
+# FLEETFLOW_WAIT_MULTIPLIER 待機秒数の倍率（整数）
+
+check_tcp() {
+    if command -v nc >/dev/null 2>&1; then
+        nc -z -w 2 "$1" "$2" >/dev/null 2>&1
+        return $?
+    fi
+    if command -v bash >/dev/null 2>&1; then
+        bash -c "exec 3<>/dev/tcp/$1/$2" >/dev/null 2>&1
+        return $?
+    fi
+    echo "fleetflow-wait: nc / bash が無いため tcp:$1:$2 の確認をスキップします" >&2
+    return 0
+}
+
+check_http() {
+    if command -v curl >/dev/null 2>&1; then
+        curl -fsS -o /dev/null --max-time 2 "$1" >/dev/null 2>&1
+        return $?
+    fi
+    if command -v wget >/dev/null 2>&1; then
+        wget -q -O /dev/null -T 2 "$1" >/dev/null 2>&1
+        return $?
+    fi
+    echo "fleetflow-wait: curl / wget が無いため $1 の確認をスキップします" >&2
+    return 0
+}
+
+wait_target() {
+    attempt=0
+    delay=${FLEETFLOW_WAIT_DELAY:-1}
+    retries=${FLEETFLOW_WAIT_RETRIES:-23}
+    max_delay=${FLEETFLOW_WAIT_MAX_DELAY:-30}
+    while :; do
+        case "$1" in
+            tcp:*)
+                rest=${1#tcp:}
+                check_tcp "${rest%:*}" "${rest##*:}" && return 0
+                ;;
+            http://* | https://*)
+                check_http "$1" && return 0
+                ;;
+            *)
+                echo "fleetflow-wait: 不明な待機対象 $1" >&2
+                return 0
+                ;;
+        esac
+        attempt=$((attempt + 1))
+        if [ "$attempt" -ge "$retries" ]; then
+            echo "fleetflow-wait: $1 が準備完了になりませんでした" >&2
+            return 1
+        fi
+        echo "fleetflow-wait: $1 を待機中... ($attempt/$retries)" >&2
+        sleep "$delay"
+        delay=$((delay * ${FLEETFLOW_WAIT_MULTIPLIER:-2}))
+        if [ "$delay" -gt "$max_delay" ]; then
+            delay=$max_delay
+        fi
+    done
+}
+
+for target in $FLEETFLOW_WAIT_TARGETS; do
+    wait_target "$target" || exit 1
+done
+
+exec "$@"
@@ -0,0 +1,249 @@
+//! 依存待ちのエントリーポイント注入 — `inject_wait #true`
+//!
+//! DB 等の準備前に起動するとクラッシュするイメージ向けに、コンテナの
+//! エントリーポイントを組み込みの待機スクリプトでラップする。待機対象は
+//! `depends_on` の各サービスから生成し（`readiness` があれば HTTP、なければ公開 TCP ポート）、
+//! 待機間隔は自サービスの `wait_for` 設定に従う。
+//!
+//! スクリプトは `sh -c` の引数として渡すため、ホストにファイルを置かずリモートでも動作する。
+//! イメージに `/bin/sh` が必要（distroless 等では使えない）。
+
+use bollard::Docker;
+use bollard::models::ContainerCreateBody;
+use fleetflow_core::{Flow, Protocol, Service};
+
+/// 組み込みの待機スクリプト
+pub const WAIT_SCRIPT: &str = include_str!("wait-for.sh");
+
+/// サービスの依存先から待機対象（`tcp:host:port` / `http://host:port/path`）を生成
+pub fn wait_targets(service: &Service, flow: &Flow) -> Vec<String> {
+    let mut targets = Vec::new();
+    for dep_name in &service.depends_on {
+        let Some(dep) = flow.services.get(dep_name) else {
+            continue;
+        };
+        if dep.is_static() {
+            continue;
+        }
+        match &dep.readiness {
+            Some(readiness) => targets.push(format!(
+                "http://{}:{}{}",
+                dep_name, readiness.port, readiness.path
+            )),
+            None => targets.extend(
+                dep.ports
+                    .iter()
+                    .filter(|p| p.protocol == Protocol::Tcp)
+                    .map(|p| format!("tcp:{}:{}", dep_name, p.container)),
+            ),
+        }
+    }
+    targets
+}
+
+/// 待機スクリプトに渡す環境変数
+fn wait_env(service: &Service, targets: &[String]) -> Vec<String> {
+    let wait = service.wait_for.clone().unwrap_or_default();
+    let secs = |ms: u64| (ms / 1000).max(1);
+    vec![
+        format!("FLEETFLOW_WAIT_TARGETS={}", targets.join(" ")),
+        format!("FLEETFLOW_WAIT_RETRIES={}", wait.max_retries.max(1)),
+        format!("FLEETFLOW_WAIT_DELAY={}", secs(wait.initial_delay_ms)),
+        format!("FLEETFLOW_WAIT_MAX_DELAY={}", secs(wait.max_delay_ms)),
+        format!(
+            "FLEETFLOW_WAIT_MULTIPLIER={}",
+            (wait.multiplier.round() as u64).max(1)
+        ),
+    ]
+}
+
+/// コンテナ設定のエントリーポイントを待機スクリプトでラップする
+///
+/// 元の argv は Docker と同じ規則で決める: エントリーポイントは設定値またはイメージの値、
+/// コマンドは設定値、なければ（エントリーポイントを上書きしていない場合に限り）イメージの値。
+pub fn wrap_entrypoint(
+    config: &mut ContainerCreateBody,
+    image_entrypoint: Option<Vec<String>>,
+    image_cmd: Option<Vec<String>>,
+    env: Vec<String>,
+) -> anyhow::Result<()> {
+    let entrypoint_overridden = config.entrypoint.is_some();
+    let mut argv = config
+        .entrypoint
+        .take()
+        .or(image_entrypoint)
+        .unwrap_or_default();
+    match config.cmd.take() {
+        Some(cmd) => argv.extend(cmd),
+        None if !entrypoint_overridden => argv.extend(image_cmd.unwrap_or_default()),
+        None => {}
+    }
+    if argv.is_empty() {
+        anyhow::bail!(
+            "イメージにエントリーポイントもコマンドも無いため inject_wait を適用できません"
+        );
+    }
+
+    config.entrypoint = Some(vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        WAIT_SCRIPT.to_string(),
+        "fleetflow-wait".to_string(),
+    ]);
+    config.cmd = Some(argv);
+    config.env.get_or_insert_with(Vec::new).extend(env);
+    Ok(())
+}
+
+/// `inject_wait` が有効なサービスのコンテナ設定に待機スクリプトを注入する
+///
+/// 元のエントリーポイント・コマンドを得るためにイメージを inspect するので、
+/// イメージはローカルに存在している必要がある。依存先に待機対象が無ければ何もしない。
+pub async fn inject_wait(
+    docker: &Docker,
+    service: &Service,
+    flow: &Flow,
+    config: &mut ContainerCreateBody,
+) -> anyhow::Result<()> {
+    if service.inject_wait != Some(true) {
+        return Ok(());
+    }
+    let targets = wait_targets(service, flow);
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let image = config
+        .image
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
+    let image_config = docker
+        .inspect_image(image)
+        .await
+        .map_err(|e| anyhow::anyhow!("イメージ {} の inspect に失敗: {}", image, e))?
+        .config;
+    let (image_entrypoint, image_cmd) = image_config
+        .map(|c| (c.entrypoint, c.cmd))
+        .unwrap_or_default();
+
+    wrap_entrypoint(
+        config,
+        image_entrypoint,
+        image_cmd,
+        wait_env(service, &targets),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Port, ReadinessCheck, Stage};
+    use std::collections::HashMap;
+
+    fn flow(services: Vec<(&str, Service)>) -> Flow {
+        Flow {
+            name: "test".to_string(),
+            services: services
+                .into_iter()
+                .map(|(name, svc)| (name.to_string(), svc))
+                .collect(),
+            stages: HashMap::from([("local".to_string(), Stage::default())]),
+            ..Default::default()
+        }
+    }
+
+    fn app() -> Service {
+        Service {
+            image: Some("app".to_string()),
+            depends_on: vec!["db".to_string(), "api".to_string()],
+            inject_wait: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_wait_targets_from_ports_and_readiness() {
+        let db = Service {
+            ports: vec![Port {
+                host: 15432,
+                container: 5432,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Default::default()
+        };
+        let api = Service {
+            readiness: Some(ReadinessCheck {
+                path: "/health".to_string(),
+                port: 3000,
+                timeout: 30,
+                interval: 2,
+            }),
+            ..Default::default()
+        };
+        let flow = flow(vec![("app", app()), ("db", db), ("api", api)]);
+
+        assert_eq!(
+            wait_targets(&flow.services["app"], &flow),
+            vec!["tcp:db:5432", "http://api:3000/health"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_entrypoint_uses_image_defaults() {
+        let mut config = ContainerCreateBody::default();
+        wrap_entrypoint(
+            &mut config,
+            Some(vec!["docker-entrypoint.sh".to_string()]),
+            Some(vec!["postgres".to_string()]),
+            vec!["FLEETFLOW_WAIT_TARGETS=tcp:db:5432".to_string()],
+        )
+        .unwrap();
+
+        let entrypoint = config.entrypoint.unwrap();
+        assert_eq!(entrypoint[..2], ["/bin/sh", "-c"]);
+        assert_eq!(entrypoint[3], "fleetflow-wait");
+        assert_eq!(
+            config.cmd.unwrap(),
+            vec!["docker-entrypoint.sh", "postgres"]
+        );
+        assert_eq!(
+            config.env.unwrap(),
+            vec!["FLEETFLOW_WAIT_TARGETS=tcp:db:5432"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_entrypoint_prefers_service_command() {
+        let mut config = ContainerCreateBody {
+            cmd: Some(vec!["npm".to_string(), "start".to_string()]),
+            ..Default::default()
+        };
+        wrap_entrypoint(
+            &mut config,
+            Some(vec!["tini".to_string(), "--".to_string()]),
+            Some(vec!["node".to_string()]),
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(config.cmd.unwrap(), vec!["tini", "--", "npm", "start"]);
+    }
+
+    #[test]
+    fn test_wrap_entrypoint_requires_command() {
+        let mut config = ContainerCreateBody::default();
+        assert!(wrap_entrypoint(&mut config, None, None, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_wait_env_uses_wait_for() {
+        let env = wait_env(&app(), &["tcp:db:5432".to_string()]);
+        assert!(env.contains(&"FLEETFLOW_WAIT_RETRIES=23".to_string()));
+        assert!(env.contains(&"FLEETFLOW_WAIT_DELAY=1".to_string()));
+        assert!(env.contains(&"FLEETFLOW_WAIT_MAX_DELAY=30".to_string()));
+        assert!(env.contains(&"FLEETFLOW_WAIT_MULTIPLIER=2".to_string()));
+    }
+}
@@ -2,13 +2,15 @@
 //!
 //! K8sのReadiness Probeのコンセプトを取り入れた、
 //! 依存サービスの準備完了を待機する機能を提供します。
+//! `fleet wait` 向けに、期限付きで任意の条件を待つ [`poll_until`] / [`wait_for_condition`] も提供します。
 
 use crate::error::{ContainerError, Result};
 use bollard::Docker;
 use bollard::models::HealthStatusEnum;
 use bollard::query_parameters::InspectContainerOptions;
 use fleetflow_core::WaitConfig;
-use std::time::Duration;
+use std::future::Future;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 /// 依存サービスの準備完了を待機
@@ -64,6 +66,102 @@ pub async fn wait_for_services(
     Ok(())
 }
 
+/// コンテナの待機条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerCondition {
+    /// 起動済みでヘルスチェックが healthy（ヘルスチェックがなければ running）
+    Healthy,
+    /// 終了済み
+    Exited,
+}
+
+impl std::fmt::Display for ContainerCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContainerCondition::Healthy => write!(f, "healthy"),
+            ContainerCondition::Exited => write!(f, "exited"),
+        }
+    }
+}
+
+/// `check` が値を返すまで exponential backoff で繰り返す（`timeout` を過ぎたら None）
+///
+/// `max_retries` は使わず、待機時間の上限は `timeout` で決まる。
+pub async fn poll_until<T, F, Fut>(
+    timeout: Duration,
+    config: &WaitConfig,
+    mut check: F,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<T>>,
+{
+    let deadline = Instant::now() + timeout;
+    let mut attempt = 0;
+    loop {
+        if let Some(value) = check().await {
+            return Some(value);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let delay = Duration::from_millis(config.delay_for_attempt(attempt));
+        sleep(delay.min(remaining)).await;
+        attempt += 1;
+    }
+}
+
+/// コンテナが条件を満たすまで待機
+///
+/// # Returns
+/// * `Ok(Some(code))` - `Exited` の場合はコンテナの終了コード
+/// * `Ok(None)` - `Healthy` を満たした
+/// * `Err(ContainerError::WaitTimeout)` - `timeout` 以内に満たさなかった
+pub async fn wait_for_condition(
+    docker: &Docker,
+    container_name: &str,
+    condition: ContainerCondition,
+    timeout: Duration,
+    config: &WaitConfig,
+) -> Result<Option<i64>> {
+    poll_until(timeout, config, || async move {
+        match condition {
+            ContainerCondition::Healthy => check_container_health(docker, container_name)
+                .await
+                .ok()
+                .filter(|ready| *ready)
+                .map(|_| None),
+            ContainerCondition::Exited => container_exit_code(docker, container_name)
+                .await
+                .ok()
+                .flatten()
+                .map(Some),
+        }
+    })
+    .await
+    .ok_or_else(|| ContainerError::WaitTimeout {
+        target: container_name.to_string(),
+        condition: condition.to_string(),
+        timeout_secs: timeout.as_secs(),
+    })
+}
+
+/// 終了済みコンテナの終了コード（実行中・未作成なら None）
+async fn container_exit_code(docker: &Docker, container_name: &str) -> Result<Option<i64>> {
+    let inspect_result = docker
+        .inspect_container(container_name, None::<InspectContainerOptions>)
+        .await
+        .map_err(|e| ContainerError::DockerApiError(e.to_string()))?;
+    let Some(state) = inspect_result.state else {
+        return Ok(None);
+    };
+    if state.running.unwrap_or(false) || state.finished_at.is_none_or(|t| t.starts_with("0001-")) {
+        return Ok(None);
+    }
+    Ok(state.exit_code)
+}
+
 /// コンテナのヘルス状態を確認
 async fn check_container_health(docker: &Docker, container_name: &str) -> Result<bool> {
     let inspect_result = docker
@@ -100,6 +198,28 @@ async fn check_container_health(docker: &Docker, container_name: &str) -> Result
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_poll_until() {
+        let config = WaitConfig {
+            max_retries: 1,
+            initial_delay_ms: 1,
+            max_delay_ms: 5,
+            multiplier: 2.0,
+        };
+        let mut calls = 0;
+        let result = poll_until(Duration::from_secs(5), &config, || {
+            calls += 1;
+            let value = (calls == 3).then_some(calls);
+            async move { value }
+        })
+        .await;
+        assert_eq!(result, Some(3));
+
+        let result: Option<()> =
+            poll_until(Duration::from_millis(20), &config, || async { None }).await;
+        assert_eq!(result, None);
+    }
+
     #[test]
     fn test_delay_calculation() {
         let config = WaitConfig {
@@ -28,11 +28,7 @@ fn make_flow(services: Vec<(&str, Service)>, stage_services: Vec<&str>) -> Flow
         name: "engine-test".to_string(),
         services: svc_map,
         stages,
-        providers: HashMap::new(),
-        servers: HashMap::new(),
-        registry: None,
-        variables: HashMap::new(),
-        tenant: None,
+        ..Default::default()
     }
 }
 
@@ -27,6 +27,13 @@ pub async fn register(server: &ProtocolServer, state: Arc<AppState>) {
                             let record_type = payload["record_type"].as_str().unwrap_or("A");
                             let content = payload["content"].as_str().unwrap_or_default();
                             let proxied = payload["proxied"].as_bool().unwrap_or(false);
+                            let options = fleetflow_cloud_cloudflare::dns::RecordOptions {
+                                ttl: payload["ttl"]
+                                    .as_u64()
+                                    .map(|ttl| ttl as u32)
+                                    .unwrap_or(fleetflow_cloud_cloudflare::dns::AUTO_TTL),
+                                proxied,
+                            };
 
                             let tenant = match state.db.get_tenant_by_slug(tenant_slug).await {
                                 Ok(Some(t)) => t,
@@ -83,7 +90,14 @@ pub async fn register(server: &ProtocolServer, state: Arc<AppState>) {
 
                                 info!(subdomain, content, "dns.create: Cloudflare にレコード作成中");
 
-                                match cf.ensure_record(subdomain, content).await {
+                                let result = match record_type.parse() {
+                                    Ok(cf_type) => {
+                                        cf.ensure_typed_record(cf_type, subdomain, content, &options)
+                                            .await
+                                    }
+                                    Err(e) => Err(e),
+                                };
+                                match result {
                                     Ok(cf_rec) => {
                                         cf_record_id = Some(cf_rec.id.clone());
                                         info!(
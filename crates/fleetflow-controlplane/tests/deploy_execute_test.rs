@@ -84,11 +84,7 @@ fn make_test_flow() -> Flow {
         name: "deploy-test".to_string(),
         services,
         stages,
-        providers: HashMap::new(),
-        servers: HashMap::new(),
-        registry: None,
-        variables: HashMap::new(),
-        tenant: None,
+        ..Default::default()
     }
 }
 
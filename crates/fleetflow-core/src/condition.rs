@@ -0,0 +1,401 @@
+//! 条件式 — サービス・サーバーの `when` / `enabled`
+//!
+//! 設定の解決時にステージごとに評価し、偽になったステージのサービス（サーバー）一覧から外す。
+//! ログ転送やメトリクスのエクスポーターのように一部のステージだけで動かすものを、
+//! ステージのサービス一覧を複製せずに宣言できる。
+//!
+//! ```kdl
+//! service "vector" {
+//!     image "timberio/vector:0.39.0-alpine"
+//!     when "{{ stage }} != 'local'"
+//! }
+//!
+//! service "debug-toolbox" {
+//!     enabled #false
+//! }
+//! ```
+//!
+//! - 比較: `a == b` / `a != b` / `a in ['dev', 'prod']`
+//! - 論理: `&&` / `||` / `!`、括弧でグループ化
+//! - 値: 引用符で囲んだ文字列、`true` / `false`、引用符なしの語（文字列として扱う）
+//! - `{{ stage }}` / `{{ project }}` は評価するステージ名・プロジェクト名に置き換える。
+//!   その他のテンプレート変数（`{{ ENABLE_METRICS }}` など）はテンプレート展開で置き換わる
+
+use crate::error::{FlowError, Result};
+use crate::model::{ServerResource, Service, Stage};
+use crate::reference::VARIABLE_PATTERN;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+/// 条件式をステージに対して評価する
+pub fn evaluate_condition(expr: &str, project: &str, stage: &str) -> Result<bool> {
+    is_active(None, Some(expr), project, stage).map_err(FlowError::InvalidConfig)
+}
+
+/// `enabled` / `when` が偽のサービス・サーバーを各ステージから外す
+pub(crate) fn apply_conditions(
+    project: &str,
+    stages: &mut HashMap<String, Stage>,
+    services: &HashMap<String, Service>,
+    servers: &HashMap<String, ServerResource>,
+) -> Result<()> {
+    for (stage_name, stage) in stages.iter_mut() {
+        let mut kept = Vec::with_capacity(stage.services.len());
+        for name in std::mem::take(&mut stage.services) {
+            // 未定義のサービスは validate_stage が報告する
+            let active = match services.get(&name) {
+                Some(service) => is_active(
+                    service.enabled,
+                    service.when.as_deref(),
+                    project,
+                    stage_name,
+                )
+                .map_err(|e| condition_error("service", &name, stage_name, e))?,
+                None => true,
+            };
+            if active {
+                kept.push(name);
+            }
+        }
+        stage.services = kept;
+
+        let mut kept = Vec::with_capacity(stage.servers.len());
+        for name in std::mem::take(&mut stage.servers) {
+            let active = match servers.get(&name) {
+                Some(server) => {
+                    is_active(server.enabled, server.when.as_deref(), project, stage_name)
+                        .map_err(|e| condition_error("server", &name, stage_name, e))?
+                }
+                None => true,
+            };
+            if active {
+                kept.push(name);
+            }
+        }
+        stage.servers = kept;
+    }
+    Ok(())
+}
+
+fn is_active(
+    enabled: Option<bool>,
+    when: Option<&str>,
+    project: &str,
+    stage: &str,
+) -> std::result::Result<bool, String> {
+    if enabled == Some(false) {
+        return Ok(false);
+    }
+    match when {
+        Some(expr) => evaluate_str(expr, project, stage)
+            .map_err(|reason| format!("invalid when expression '{}': {}", expr, reason)),
+        None => Ok(true),
+    }
+}
+
+fn condition_error(kind: &str, name: &str, stage: &str, reason: String) -> FlowError {
+    FlowError::InvalidConfig(format!(
+        "{} '{}' (stage '{}'): {}",
+        kind, name, stage, reason
+    ))
+}
+
+fn evaluate_str(expr: &str, project: &str, stage: &str) -> std::result::Result<bool, String> {
+    let variables = Regex::new(VARIABLE_PATTERN).expect("valid regex");
+    let expr = variables.replace_all(expr, |caps: &Captures| match &caps[1] {
+        "project" => quote(project),
+        _ => quote(stage),
+    });
+
+    let tokens = tokenize(&expr)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.or()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected {}", token));
+    }
+    value.as_bool()
+}
+
+/// 置き換えた値を文字列リテラルにする（語として解釈されないように）
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "\\'"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Word(String),
+    Eq,
+    Ne,
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Str(s) => write!(f, "'{}'", s),
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::Eq => write!(f, "'=='"),
+            Token::Ne => write!(f, "'!='"),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::LBracket => write!(f, "'['"),
+            Token::RBracket => write!(f, "']'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some(ch) if ch == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '=' | '!' | '&' | '|' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let token = match (c, next) {
+                    ('=', Some('=')) => Token::Eq,
+                    ('!', Some('=')) => Token::Ne,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    ('!', _) => {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                    _ => return Err(format!("unexpected '{}'", c)),
+                };
+                chars.next();
+                tokens.push(token);
+            }
+            '(' | ')' | '[' | ']' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    _ => Token::Comma,
+                });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "'\"=!&|()[],".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// 評価中の値
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    Str(String),
+}
+
+impl Value {
+    fn as_bool(&self) -> std::result::Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::Str(s) => Err(format!(
+                "'{}' is not a condition (compare it with == / != / in)",
+                s
+            )),
+        }
+    }
+
+    fn as_string(&self) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Str(s) => s.clone(),
+        }
+    }
+}
+
+/// 再帰下降パーサー（`||` < `&&` < `!` < 比較）
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> std::result::Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, found {}", expected, token)),
+            None => Err(format!("expected {}", expected)),
+        }
+    }
+
+    fn or(&mut self) -> std::result::Result<Value, String> {
+        let mut value = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let rhs = self.and()?;
+            value = Value::Bool(value.as_bool()? || rhs.as_bool()?);
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> std::result::Result<Value, String> {
+        let mut value = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = Value::Bool(value.as_bool()? && rhs.as_bool()?);
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> std::result::Result<Value, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Value::Bool(!self.unary()?.as_bool()?));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> std::result::Result<Value, String> {
+        let lhs = self.primary()?;
+        match self.peek() {
+            Some(Token::Eq) | Some(Token::Ne) => {
+                let negate = self.next() == Some(Token::Ne);
+                let rhs = self.primary()?;
+                Ok(Value::Bool((lhs.as_string() == rhs.as_string()) != negate))
+            }
+            Some(Token::Word(w)) if w == "in" => {
+                self.pos += 1;
+                let list = self.list()?;
+                Ok(Value::Bool(list.contains(&lhs.as_string())))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn list(&mut self) -> std::result::Result<Vec<String>, String> {
+        self.expect(Token::LBracket)?;
+        let mut items = Vec::new();
+        if self.peek() == Some(&Token::RBracket) {
+            self.pos += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(self.primary()?.as_string());
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::RBracket) => return Ok(items),
+                Some(token) => return Err(format!("expected ',' or ']', found {}", token)),
+                None => return Err("unclosed '['".to_string()),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> std::result::Result<Value, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let value = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(value)
+            }
+            Some(Token::Str(s)) => Ok(Value::Str(s)),
+            Some(Token::Word(w)) => Ok(match w.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => Value::Str(w),
+            }),
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let cases = [
+            ("{{ stage }} != 'local'", "prod", true),
+            ("{{ stage }} != 'local'", "local", false),
+            ("prod == \"prod\"", "local", true),
+            ("{{ stage }} in ['stg', 'prod']", "stg", true),
+            ("{{ stage }} in ['stg', 'prod']", "dev", false),
+            (
+                "{{project}} == 'shop' && !({{ stage }} == 'local')",
+                "dev",
+                true,
+            ),
+            ("false || {{ stage }} == 'local'", "local", true),
+            ("true", "local", true),
+        ];
+        for (expr, stage, expected) in cases {
+            assert_eq!(
+                evaluate_condition(expr, "shop", stage).unwrap(),
+                expected,
+                "{}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_rejects_invalid_expressions() {
+        for expr in [
+            "{{ stage }}",
+            "stage = 'prod'",
+            "(true",
+            "'prod",
+            "a in 'b'",
+        ] {
+            let err = evaluate_condition(expr, "shop", "prod")
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("invalid when expression"), "{}: {}", expr, err);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! 解決済み設定の比較 — `fleet config diff`
+//!
+//! 2 つのステージ（または別リビジョンの同じステージ）を解決した結果を比べ、
+//! サービスの追加・削除と、サービス・ステージ設定のフィールド単位の変更を返す。
+//! プロモーション（stg → prod など）で実際に何が変わるかをレビューするために使う。
+
+use crate::error::{FlowError, Result};
+use crate::model::{Flow, Port, Service, Stage, Volume};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// フィールドの変更（値は表示用の文字列、`None` は未設定）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    /// フィールド名（ネストは `.` 区切り、例: `environment.DATABASE_URL`）
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// 解決済み設定の差分
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigDiff {
+    /// ステージ設定（servers / variables / registry など）の変更
+    pub stage: Vec<FieldChange>,
+    /// 比較先にだけあるサービス
+    pub added: Vec<String>,
+    /// 比較元にだけあるサービス
+    pub removed: Vec<String>,
+    /// 両方にあり設定が異なるサービス（サービス名 → 変更）
+    pub changed: BTreeMap<String, Vec<FieldChange>>,
+}
+
+impl ConfigDiff {
+    /// 差分が無いかどうか
+    pub fn is_empty(&self) -> bool {
+        self.stage.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// 2 つのステージの解決済み設定を比較する
+///
+/// `before` / `after` はそれぞれのステージ指定でロードした Flow
+/// （ステージ固有の上書きが適用済みのもの）を渡す。
+pub fn diff_stages(
+    before: &Flow,
+    before_stage: &str,
+    after: &Flow,
+    after_stage: &str,
+) -> Result<ConfigDiff> {
+    let stage_a = before
+        .stages
+        .get(before_stage)
+        .ok_or_else(|| FlowError::EnvironmentNotFound(before_stage.to_string()))?;
+    let stage_b = after
+        .stages
+        .get(after_stage)
+        .ok_or_else(|| FlowError::EnvironmentNotFound(after_stage.to_string()))?;
+
+    let services_a = stage_services(before, stage_a);
+    let services_b = stage_services(after, stage_b);
+
+    let mut diff = ConfigDiff {
+        stage: diff_stage_settings(stage_a, stage_b),
+        ..Default::default()
+    };
+    for (name, service) in &services_b {
+        match services_a.get(name) {
+            None => diff.added.push(name.to_string()),
+            Some(previous) => {
+                let changes = diff_service(previous, service);
+                if !changes.is_empty() {
+                    diff.changed.insert(name.to_string(), changes);
+                }
+            }
+        }
+    }
+    diff.removed = services_a
+        .keys()
+        .filter(|name| !services_b.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    Ok(diff)
+}
+
+/// ステージのサービス（定義が無いものは既定値として扱う）
+fn stage_services<'a>(flow: &'a Flow, stage: &'a Stage) -> BTreeMap<&'a str, Service> {
+    stage
+        .services
+        .iter()
+        .map(|name| {
+            let service = flow.services.get(name).cloned().unwrap_or_default();
+            (name.as_str(), service)
+        })
+        .collect()
+}
+
+fn diff_stage_settings(before: &Stage, after: &Stage) -> Vec<FieldChange> {
+    let mut a = serde_json::to_value(before).unwrap_or_default();
+    let mut b = serde_json::to_value(after).unwrap_or_default();
+    // サービスの増減は added / removed で報告する
+    for value in [&mut a, &mut b] {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("services");
+        }
+    }
+    let mut changes = Vec::new();
+    diff_values("", &a, &b, &mut changes);
+    changes
+}
+
+fn diff_service(before: &Service, after: &Service) -> Vec<FieldChange> {
+    let mut a = serde_json::to_value(before).unwrap_or_default();
+    let mut b = serde_json::to_value(after).unwrap_or_default();
+    // ポート・ボリュームは 1 件ずつの追加・削除として報告する
+    for value in [&mut a, &mut b] {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("ports");
+            object.remove("volumes");
+        }
+    }
+    let mut changes = Vec::new();
+    diff_values("", &a, &b, &mut changes);
+    diff_sets(
+        "ports",
+        before.ports.iter().map(port_label).collect(),
+        after.ports.iter().map(port_label).collect(),
+        &mut changes,
+    );
+    diff_sets(
+        "volumes",
+        before.volumes.iter().map(volume_label).collect(),
+        after.volumes.iter().map(volume_label).collect(),
+        &mut changes,
+    );
+    changes
+}
+
+fn port_label(port: &Port) -> String {
+    let protocol = port.protocol.as_str();
+    let mut label = match &port.host_ip {
+        Some(ip) => format!("{}:{}:{}/{}", ip, port.host, port.container, protocol),
+        None => format!("{}:{}/{}", port.host, port.container, protocol),
+    };
+    if let Some(name) = &port.name {
+        label.push_str(&format!(" ({})", name));
+    }
+    label
+}
+
+fn volume_label(volume: &Volume) -> String {
+    let mut label = format!("{}:{}", volume.host.display(), volume.container.display());
+    if volume.read_only {
+        label.push_str(":ro");
+    }
+    label
+}
+
+/// 集合として比較し、要素ごとの追加・削除を記録する
+fn diff_sets(
+    field: &str,
+    before: BTreeSet<String>,
+    after: BTreeSet<String>,
+    changes: &mut Vec<FieldChange>,
+) {
+    for removed in before.difference(&after) {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            before: Some(removed.clone()),
+            after: None,
+        });
+    }
+    for added in after.difference(&before) {
+        changes.push(FieldChange {
+            field: field.to_string(),
+            before: None,
+            after: Some(added.clone()),
+        });
+    }
+}
+
+/// オブジェクトは再帰的に、それ以外は値として比較する
+fn diff_values(path: &str, before: &Value, after: &Value, changes: &mut Vec<FieldChange>) {
+    if let (Value::Object(a), Value::Object(b)) = (before, after) {
+        let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+        for key in keys {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            diff_values(
+                &child,
+                a.get(key).unwrap_or(&Value::Null),
+                b.get(key).unwrap_or(&Value::Null),
+                changes,
+            );
+        }
+        return;
+    }
+
+    let (before, after) = (display_value(before), display_value(after));
+    if before != after {
+        changes.push(FieldChange {
+            field: path.to_string(),
+            before,
+            after,
+        });
+    }
+}
+
+/// 表示用の文字列（null・空の配列やオブジェクトは未設定扱い）
+fn display_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Array(items) if items.is_empty() => None,
+        Value::Object(map) if map.is_empty() => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_kdl_string_with_stage;
+
+    const CONFIG: &str = r#"
+        project "app"
+
+        stage "stg" {
+            service "api"
+            service "db"
+            service "debug"
+            variables {
+                LOG_LEVEL "debug"
+            }
+        }
+
+        stage "prod" {
+            service "api" {
+                image "app/api:1.2.0"
+                env {
+                    RUST_LOG "info"
+                }
+            }
+            service "db"
+            service "worker"
+            variables {
+                LOG_LEVEL "info"
+            }
+        }
+
+        service "api" {
+            image "app/api:latest"
+            ports {
+                port host=8080 container=3000
+            }
+            env {
+                RUST_LOG "debug"
+                PORT "3000"
+            }
+        }
+        service "db" {
+            image "postgres:16"
+        }
+        service "debug" {
+            image "busybox"
+        }
+        service "worker" {
+            image "app/worker:latest"
+        }
+    "#;
+
+    fn load(stage: &str) -> Flow {
+        parse_kdl_string_with_stage(CONFIG, "app".to_string(), Some(stage)).unwrap()
+    }
+
+    #[test]
+    fn test_diff_stages() {
+        let diff = diff_stages(&load("stg"), "stg", &load("prod"), "prod").unwrap();
+        assert_eq!(diff.added, vec!["worker"]);
+        assert_eq!(diff.removed, vec!["debug"]);
+        assert!(!diff.changed.contains_key("db"));
+
+        let api = &diff.changed["api"];
+        assert!(api.contains(&FieldChange {
+            field: "image".to_string(),
+            before: Some("app/api:latest".to_string()),
+            after: Some("app/api:1.2.0".to_string()),
+        }));
+        assert!(api.iter().any(|c| c.field == "environment.RUST_LOG"));
+        // 変わっていない env は報告しない
+        assert!(!api.iter().any(|c| c.field == "environment.PORT"));
+
+        assert!(diff.stage.contains(&FieldChange {
+            field: "variables.LOG_LEVEL".to_string(),
+            before: Some("debug".to_string()),
+            after: Some("info".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_diff_ports_as_sets() {
+        let before = Service {
+            ports: vec![Port {
+                host: 8080,
+                container: 3000,
+                protocol: Default::default(),
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Default::default()
+        };
+        let mut after = before.clone();
+        after.ports[0].host = 9090;
+
+        let changes = diff_service(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].before.as_deref(), Some("8080:3000/tcp"));
+        assert_eq!(changes[1].after.as_deref(), Some("9090:3000/tcp"));
+
+        assert!(diff_service(&before, &before.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_stage() {
+        let flow = load("stg");
+        assert!(diff_stages(&flow, "stg", &flow, "nope").is_err());
+    }
+}
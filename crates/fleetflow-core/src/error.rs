@@ -33,6 +33,11 @@ pub enum FlowError {
     )]
     ProjectRootNotFound(PathBuf),
 
+    #[error(
+        "ワークスペースが見つかりません\n探索開始位置: {0}\nヒント: workspace.kdl を含むディレクトリで実行するか -C で指定してください"
+    )]
+    WorkspaceNotFound(PathBuf),
+
     #[error("サービスが見つかりません: {0}")]
     ServiceNotFound(String),
 
@@ -49,4 +54,46 @@ pub enum FlowError {
     OnePasswordError(String),
 }
 
+impl FlowError {
+    /// 診断コード（`fleet::config::*`）
+    pub fn code(&self) -> &'static str {
+        match self {
+            FlowError::KdlParse(_) => "fleet::config::kdl_parse",
+            FlowError::Io(_) | FlowError::IoError { .. } => "fleet::config::io",
+            FlowError::InvalidConfig(_) => "fleet::config::invalid",
+            FlowError::TemplateError { .. } | FlowError::TemplateRenderError(_) => {
+                "fleet::config::template"
+            }
+            FlowError::DiscoveryError { .. } => "fleet::config::discovery",
+            FlowError::ProjectRootNotFound(_) => "fleet::config::project_not_found",
+            FlowError::WorkspaceNotFound(_) => "fleet::config::workspace_not_found",
+            FlowError::ServiceNotFound(_) => "fleet::config::service_not_found",
+            FlowError::EnvironmentNotFound(_) => "fleet::config::stage_not_found",
+            FlowError::CircularDependency(_) => "fleet::config::circular_dependency",
+            FlowError::MissingImage(_) => "fleet::config::missing_image",
+            FlowError::OnePasswordError(_) => "fleet::config::secret",
+        }
+    }
+
+    /// 解決のヒント（メッセージに含まれていないもの）
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            FlowError::KdlParse(_) | FlowError::InvalidConfig(_) => {
+                Some("fleet validate で設定ファイルを検証できます")
+            }
+            FlowError::ServiceNotFound(_) | FlowError::EnvironmentNotFound(_) => {
+                Some("fleet.kdl の service / stage の名前を確認してください")
+            }
+            FlowError::CircularDependency(_) => Some("depends_on の依存関係を見直してください"),
+            FlowError::MissingImage(_) => {
+                Some("image を指定するか、build ブロックでビルド方法を宣言してください")
+            }
+            FlowError::OnePasswordError(_) => {
+                Some("op signin で 1Password CLI にサインインしているか確認してください")
+            }
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, FlowError>;
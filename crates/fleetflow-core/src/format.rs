@@ -0,0 +1,124 @@
+//! KDL 設定ファイルの正規化フォーマッタ（`fleet kdl fmt`）
+//!
+//! kdl のドキュメントモデル上で並べ替えと整形を行うため、コメントは保持される。
+//!
+//! - トップレベルの `service` ノードをサービス名順に並べる（他のノードの位置は変えない）
+//! - インデントを 4 スペースに揃える（kdl の autoformat）
+//! - オプションで `env` ブロックのキーを名前順に並べる
+
+use crate::error::Result;
+use kdl::{KdlDocument, KdlNode};
+
+/// フォーマットのオプション
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// `env` ブロックのキーを名前順に並べる
+    pub sort_env: bool,
+}
+
+/// ノードの最初の引数（文字列）
+fn first_argument(node: &KdlNode) -> Option<&str> {
+    node.entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+}
+
+/// `service` ノードを、元の位置（スロット）を保ったままサービス名順に並べる
+fn sort_services(doc: &mut KdlDocument) {
+    let nodes = doc.nodes_mut();
+    let slots: Vec<usize> = nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| n.name().value() == "service")
+        .map(|(i, _)| i)
+        .collect();
+    let mut services: Vec<KdlNode> = slots.iter().map(|&i| nodes[i].clone()).collect();
+    services.sort_by(|a, b| first_argument(a).cmp(&first_argument(b)));
+    for (slot, service) in slots.into_iter().zip(services) {
+        nodes[slot] = service;
+    }
+}
+
+/// `env` ブロックのキーを名前順に並べる（ネストしたノードも対象）
+fn sort_env_keys(doc: &mut KdlDocument) {
+    for node in doc.nodes_mut() {
+        let is_env = node.name().value() == "env";
+        if let Some(children) = node.children_mut() {
+            if is_env {
+                children
+                    .nodes_mut()
+                    .sort_by(|a, b| a.name().value().cmp(b.name().value()));
+            } else {
+                sort_env_keys(children);
+            }
+        }
+    }
+}
+
+/// KDL 文字列を正規化する
+pub fn format_kdl_string(content: &str, options: FormatOptions) -> Result<String> {
+    let mut doc: KdlDocument = content.parse()?;
+    sort_services(&mut doc);
+    if options.sort_env {
+        sort_env_keys(&mut doc);
+    }
+    doc.autoformat();
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_sorts_services_and_keeps_comments() {
+        let input = r#"project "app"
+
+// web フロント
+service "web" {
+  image "nginx"
+}
+stage "local" {
+        service "web"
+        service "db"
+}
+// データベース
+service "db" {
+image "postgres"
+}
+"#;
+        let output = format_kdl_string(input, FormatOptions::default()).unwrap();
+
+        let db = output.find("service \"db\" {").unwrap();
+        let web = output.find("service \"web\" {").unwrap();
+        let stage = output.find("stage \"local\"").unwrap();
+        assert!(db < stage && stage < web, "{}", output);
+        assert!(output.contains("// データベース"));
+        assert!(output.contains("// web フロント"));
+        assert!(output.contains("    image \"postgres\""));
+        // ステージ内のサービス順は変えない
+        assert!(
+            output.find("    service \"web\"").unwrap()
+                < output.find("    service \"db\"").unwrap()
+        );
+
+        // 冪等
+        assert_eq!(
+            format_kdl_string(&output, FormatOptions::default()).unwrap(),
+            output
+        );
+    }
+
+    #[test]
+    fn test_format_sort_env_is_optional() {
+        let input =
+            "service \"api\" {\n    env {\n        ZETA \"1\"\n        ALPHA \"2\"\n    }\n}\n";
+
+        let output = format_kdl_string(input, FormatOptions::default()).unwrap();
+        assert!(output.find("ZETA").unwrap() < output.find("ALPHA").unwrap());
+
+        let output = format_kdl_string(input, FormatOptions { sort_env: true }).unwrap();
+        assert!(output.find("ALPHA").unwrap() < output.find("ZETA").unwrap());
+    }
+}
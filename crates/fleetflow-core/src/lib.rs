@@ -1,14 +1,26 @@
+pub mod condition;
+pub mod config_diff;
 pub mod discovery;
 pub mod error;
+pub mod format;
 pub mod loader;
 pub mod model;
 pub mod onepassword;
 pub mod parser;
+pub mod reference;
 pub mod template;
+pub mod upgrade;
+pub mod workspace;
 
+pub use condition::*;
+pub use config_diff::*;
 pub use discovery::*;
 pub use error::*;
+pub use format::*;
 pub use loader::*;
 pub use model::*;
 pub use parser::*;
+pub use reference::*;
 pub use template::*;
+pub use upgrade::*;
+pub use workspace::*;
@@ -5,7 +5,7 @@
 use crate::discovery::{DiscoveredFiles, discover_files_with_stage, find_project_root};
 use crate::error::{FlowError, Result};
 use crate::model::Flow;
-use crate::parser::parse_kdl_string_with_stage;
+use crate::parser::{expand_rendered_includes, parse_kdl_string_with_stage};
 use crate::template::{TemplateProcessor, Variables, extract_variables_with_stage};
 use std::path::Path;
 use tracing::{debug, info, instrument};
@@ -40,21 +40,9 @@ pub fn load_project_from_root(project_root: &Path) -> Result<Flow> {
 /// 読み込み順序: fleet.kdl → flow.{stage}.kdl → flow.local.kdl
 #[instrument(skip(project_root), fields(project_root = %project_root.display()))]
 pub fn load_project_from_root_with_stage(project_root: &Path, stage: Option<&str>) -> Result<Flow> {
-    // 1. ファイル発見
-    debug!("Step 1: Discovering files");
-    let discovered = discover_files_with_stage(project_root, stage)?;
-
-    // 2. 変数収集とテンプレート準備
-    debug!("Step 2: Preparing template processor");
-    let mut processor = prepare_template_processor(&discovered, project_root, stage)?;
-
-    // 3. テンプレート展開
-    debug!("Step 3: Expanding templates");
-    let expanded_content = expand_all_files(&discovered, &mut processor)?;
-    info!(
-        content_size = expanded_content.len(),
-        "Template expansion complete"
-    );
+    // 1〜3. ファイル発見・テンプレート展開
+    let mut warnings = Vec::new();
+    let expanded_content = expand_project(project_root, stage, &mut warnings)?;
 
     // 4. KDLパース
     debug!("Step 4: Parsing KDL");
@@ -63,7 +51,8 @@ pub fn load_project_from_root_with_stage(project_root: &Path, stage: Option<&str
         .and_then(|n| n.to_str())
         .unwrap_or("unnamed")
         .to_string();
-    let flow = parse_kdl_string_with_stage(&expanded_content, name, stage)?;
+    let mut flow = parse_kdl_string_with_stage(&expanded_content, name, stage)?;
+    flow.warnings = warnings;
     info!(
         services = flow.services.len(),
         stages = flow.stages.len(),
@@ -73,6 +62,38 @@ pub fn load_project_from_root_with_stage(project_root: &Path, stage: Option<&str
     Ok(flow)
 }
 
+/// プロジェクトの全設定ファイルをテンプレート展開して結合した KDL を返す
+///
+/// パース前の内容が必要な検証（`fleet validate --strict`）で使用する。
+pub fn expand_project_with_stage(project_root: &Path, stage: Option<&str>) -> Result<String> {
+    expand_project(project_root, stage, &mut Vec::new())
+}
+
+/// `expand_project_with_stage` の本体。展開中の警告を `warnings` に追加する
+fn expand_project(
+    project_root: &Path,
+    stage: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    // 1. ファイル発見
+    debug!("Step 1: Discovering files");
+    let discovered = discover_files_with_stage(project_root, stage)?;
+
+    // 2. 変数収集とテンプレート準備
+    debug!("Step 2: Preparing template processor");
+    let mut processor = prepare_template_processor(&discovered, project_root, stage)?;
+
+    // 3. テンプレート展開
+    debug!("Step 3: Expanding templates");
+    let expanded_content = expand_all_files(&discovered, &mut processor, warnings)?;
+    info!(
+        content_size = expanded_content.len(),
+        "Template expansion complete"
+    );
+
+    Ok(expanded_content)
+}
+
 /// テンプレートプロセッサを準備
 fn prepare_template_processor(
     discovered: &DiscoveredFiles,
@@ -87,6 +108,8 @@ fn prepare_template_processor(
         "PROJECT_ROOT",
         serde_json::Value::String(project_root.to_string_lossy().to_string()),
     );
+    // port() / service_host() / project / stage はパース後に解決
+    processor.defer_references(stage);
 
     // 1. グローバル変数（fleet.kdl）とステージ固有変数
     if let Some(root_file) = &discovered.root {
@@ -133,10 +156,23 @@ fn prepare_template_processor(
     Ok(processor)
 }
 
+/// ファイルをテンプレート展開し、include（ローカルパス・https URL）を展開する
+///
+/// include したファイルも同じ変数でテンプレート展開してから取り込む。
+fn render_with_includes(
+    processor: &mut TemplateProcessor,
+    file: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let rendered = processor.render_file(file)?;
+    expand_rendered_includes(&rendered, file, processor, warnings)
+}
+
 /// 全ファイルをテンプレート展開して結合
 fn expand_all_files(
     discovered: &DiscoveredFiles,
     processor: &mut TemplateProcessor,
+    warnings: &mut Vec<String>,
 ) -> Result<String> {
     // ファイル数から概算容量を計算
     let file_count = discovered.services.len()
@@ -160,7 +196,7 @@ fn expand_all_files(
     // 0. cloud.kdl（クラウドインフラ定義 - プロバイダー、サーバー）
     if let Some(cloud_file) = &discovered.cloud {
         debug!(file = %cloud_file.display(), "Rendering cloud config file");
-        let rendered = processor.render_file(cloud_file)?;
+        let rendered = render_with_includes(processor, cloud_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -168,7 +204,7 @@ fn expand_all_files(
     // 1. fleet.kdl
     if let Some(root_file) = &discovered.root {
         debug!(file = %root_file.display(), "Rendering root file");
-        let rendered = processor.render_file(root_file)?;
+        let rendered = render_with_includes(processor, root_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -176,7 +212,7 @@ fn expand_all_files(
     // 2. services/**/*.kdl
     for service_file in &discovered.services {
         debug!(file = %service_file.display(), "Rendering service file");
-        let rendered = processor.render_file(service_file)?;
+        let rendered = render_with_includes(processor, service_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -184,7 +220,7 @@ fn expand_all_files(
     // 3. stages/**/*.kdl
     for stage_file in &discovered.stages {
         debug!(file = %stage_file.display(), "Rendering stage file");
-        let rendered = processor.render_file(stage_file)?;
+        let rendered = render_with_includes(processor, stage_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -192,7 +228,7 @@ fn expand_all_files(
     // 4. flow.{stage}.kdl（ステージオーバーライド）
     if let Some(stage_file) = &discovered.stage_override {
         debug!(file = %stage_file.display(), "Rendering stage override file");
-        let rendered = processor.render_file(stage_file)?;
+        let rendered = render_with_includes(processor, stage_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -200,7 +236,7 @@ fn expand_all_files(
     // 5. flow.local.kdl（ローカルオーバーライド）
     if let Some(local_file) = &discovered.local_override {
         debug!(file = %local_file.display(), "Rendering local override file");
-        let rendered = processor.render_file(local_file)?;
+        let rendered = render_with_includes(processor, local_file, warnings)?;
         expanded.push_str(&rendered);
         expanded.push_str("\n\n");
     }
@@ -298,8 +334,12 @@ pub fn load_project_with_debug(project_root: &Path) -> Result<Flow> {
     println!("  ✓ 完了");
 
     println!("\n📝 テンプレート展開");
-    let expanded = expand_all_files(&discovered, &mut processor)?;
+    let mut warnings = Vec::new();
+    let expanded = expand_all_files(&discovered, &mut processor, &mut warnings)?;
     println!("  ✓ 完了 ({}バイト)", expanded.len());
+    for warning in &warnings {
+        println!("  ⚠ {}", warning);
+    }
 
     println!("\n⚙️  KDLパース");
     let name = project_root
@@ -307,7 +347,8 @@ pub fn load_project_with_debug(project_root: &Path) -> Result<Flow> {
         .and_then(|n| n.to_str())
         .unwrap_or("unnamed")
         .to_string();
-    let flow = parse_kdl_string_with_stage(&expanded, name, stage_ref)?;
+    let mut flow = parse_kdl_string_with_stage(&expanded, name, stage_ref)?;
+    flow.warnings = warnings;
     println!("  サービス: {}個", flow.services.len());
     println!("  ステージ: {}個", flow.stages.len());
 
@@ -400,6 +441,55 @@ stage "local" {
         Ok(())
     }
 
+    #[test]
+    fn test_load_project_expands_includes() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project_root = temp_dir.path();
+
+        create_test_project(project_root)?;
+        fs::create_dir_all(project_root.join("shared"))?;
+        fs::write(
+            project_root.join("shared/redis.kdl"),
+            "service \"redis\" {\n    image \"redis:7\"\n}\n",
+        )?;
+        fs::write(
+            project_root.join("stages/ops.kdl"),
+            "include \"../shared/redis.kdl\"\n\nstage \"ops\" {\n    service \"redis\"\n}\n",
+        )?;
+
+        let config = load_project_from_root(project_root)?;
+        assert_eq!(config.services["redis"].image.as_deref(), Some("redis:7"));
+        assert_eq!(config.stages["ops"].services, vec!["redis"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_project_renders_variables_in_included_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project_root = temp_dir.path();
+
+        create_test_project(project_root)?;
+        fs::create_dir_all(project_root.join("shared"))?;
+        fs::write(
+            project_root.join("shared/cache.kdl"),
+            "service \"cache\" {\n    image \"{{ registry }}/cache:{{ app_version }}\"\n}\n",
+        )?;
+        fs::write(
+            project_root.join("stages/ops.kdl"),
+            "include \"../shared/cache.kdl\"\n\nstage \"ops\" {\n    service \"cache\"\n}\n",
+        )?;
+
+        let config = load_project_from_root(project_root)?;
+        assert_eq!(
+            config.services["cache"].image.as_deref(),
+            Some("ghcr.io/myorg/cache:1.0.0")
+        );
+        assert!(config.warnings.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_load_project_with_variables_dir() -> Result<()> {
         let temp_dir = tempfile::tempdir().unwrap();
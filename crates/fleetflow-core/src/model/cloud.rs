@@ -14,10 +14,93 @@ pub struct CloudProvider {
     /// ゾーン/リージョン（tk1a, is1b など）
     pub zone: Option<String>,
 
+    /// DNS ゾーン ID（cloudflare。未設定時は `CLOUDFLARE_ZONE_ID`）
+    #[serde(default)]
+    pub zone_id: Option<String>,
+
+    /// 管理するドメイン（cloudflare。ステージの `dns_domain` が優先、未設定時は `CLOUDFLARE_DOMAIN`）
+    #[serde(default)]
+    pub domain: Option<String>,
+
+    /// API トークンの参照（未設定時は `CLOUDFLARE_API_TOKEN`）
+    #[serde(default)]
+    pub api_token: Option<CredentialRef>,
+
+    /// 追加の DNS ゾーン（cloudflare の `dns { zone "..." }`）
+    #[serde(default)]
+    pub dns_zones: Vec<DnsZone>,
+
+    /// サブドメインの命名テンプレート（`{service}` / `{stage}`。未設定時は `<service>-<stage>`）
+    #[serde(default)]
+    pub subdomain_template: Option<String>,
+
     /// 追加設定（プロバイダー固有）
     pub config: HashMap<String, String>,
 }
 
+/// DNS ゾーン
+///
+/// KDL形式：
+/// ```kdl
+/// dns {
+///     zone "example.com" id="0123abcd..."  // id 省略時は API でゾーン名から引く
+///     zone "internal.example.net"
+///     subdomain "{service}.{stage}"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsZone {
+    /// ゾーンのドメイン
+    pub domain: String,
+    /// ゾーン ID
+    pub zone_id: Option<String>,
+}
+
+/// DNS レコードのオプション（未指定は TTL 自動・プロキシなし）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecordOptions {
+    /// TTL（秒。1 は自動）
+    pub ttl: Option<u32>,
+    /// Cloudflare のプロキシを通すか
+    pub proxied: Option<bool>,
+}
+
+/// サーバーに付ける追加の DNS レコード
+///
+/// KDL形式：
+/// ```kdl
+/// record "AAAA" "2001:db8::1"
+/// record "TXT" "google-site-verification=..." name="_verify" ttl=3600
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecordDef {
+    /// レコードタイプ（A / AAAA / CNAME / TXT）
+    pub record_type: String,
+    /// レコード名（ドメインからの相対。未指定時はサーバー名）
+    pub name: Option<String>,
+    /// レコードの値
+    pub content: String,
+    /// TTL / プロキシ
+    #[serde(default)]
+    pub options: DnsRecordOptions,
+}
+
+/// 認証情報の参照（値そのものは fleet.kdl に書かない）
+///
+/// KDL形式：
+/// ```kdl
+/// api_token env="CF_API_TOKEN"        // ホストの環境変数
+/// api_token secret="cloudflare_token" // トップレベル secrets のキー
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialRef {
+    /// ホストの環境変数名
+    Env(String),
+    /// トップレベル `secrets` で宣言したシークレット名
+    Secret(String),
+}
+
 /// サーバーリソース設定
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ServerResource {
@@ -54,6 +137,23 @@ pub struct ServerResource {
     /// 例: ["app", "api"] -> app.{domain} と api.{domain} が {server-hostname}.{domain} を参照
     pub dns_aliases: Vec<String>,
 
+    /// サーバーの A レコードのオプション（TTL / プロキシ）
+    #[serde(default)]
+    pub dns_options: DnsRecordOptions,
+
+    /// エイリアスごとの CNAME オプション（未指定のエイリアスは `dns_options` を使う）
+    #[serde(default)]
+    pub dns_alias_options: HashMap<String, DnsRecordOptions>,
+
+    /// 追加の DNS レコード（AAAA / TXT など）
+    #[serde(default)]
+    pub dns_records: Vec<DnsRecordDef>,
+
+    /// サーバーの DNS ドメイン（未設定時はステージ / provider のドメイン）
+    /// 例: "internal.example.net" -> {server-hostname}.internal.example.net
+    #[serde(default)]
+    pub domain: Option<String>,
+
     /// デプロイ先パス
     /// 例: "/opt/myapp" - CI/CDやmiseタスクでデプロイ先を参照
     pub deploy_path: Option<String>,
@@ -65,11 +165,61 @@ pub struct ServerResource {
     /// SSHユーザー名（デフォルト: "root"）
     pub ssh_user: Option<String>,
 
+    /// ホストで直接動かすサービス（systemd ユニットとして導入）
+    #[serde(default)]
+    pub host_services: HashMap<String, HostService>,
+
+    /// `false` ならどのステージでも使わない
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// ステージごとに評価する条件式（偽になったステージのサーバー一覧から外す）
+    #[serde(default)]
+    pub when: Option<String>,
+
     /// 追加設定
     pub config: HashMap<String, String>,
 }
 
+/// ホストサービス定義（コンテナではなく systemd で動かすもの）
+///
+/// cloudflared や node_exporter など、ホストのネットワーク・デバイスに
+/// 直接アクセスしたいコンポーネント向け。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HostService {
+    /// 説明（`Description=`）
+    pub description: Option<String>,
+
+    /// 起動コマンド（`ExecStart=`、必須）
+    pub exec_start: String,
+
+    /// 実行ユーザー（`User=`、未指定時は root）
+    pub user: Option<String>,
+
+    /// 作業ディレクトリ（`WorkingDirectory=`）
+    pub working_directory: Option<String>,
+
+    /// 環境変数（`Environment=`）
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+
+    /// 再起動ポリシー（`Restart=`、未指定時は always）
+    pub restart: Option<String>,
+
+    /// 起動順序の依存ユニット（`After=` / `Wants=`）
+    #[serde(default)]
+    pub after: Vec<String>,
+}
+
 impl ServerResource {
+    /// エイリアスの CNAME オプション
+    pub fn alias_options(&self, alias: &str) -> DnsRecordOptions {
+        self.dns_alias_options
+            .get(alias)
+            .copied()
+            .unwrap_or(self.dns_options)
+    }
+
     /// デフォルト値でサーバーリソースを作成
     pub fn with_provider(provider: impl Into<String>) -> Self {
         Self {
@@ -77,4 +227,18 @@ impl ServerResource {
             ..Default::default()
         }
     }
+
+    /// SSH 経由でサーバーの Docker デーモンに接続する DOCKER_HOST（例: ssh://root@host）
+    ///
+    /// `ssh_host` 未設定なら None。ユーザーのデフォルトは "root"。
+    pub fn docker_host(&self) -> Option<String> {
+        Some(format!("ssh://{}", self.ssh_target()?))
+    }
+
+    /// ssh コマンドの接続先（例: root@host）。`ssh_host` 未設定なら None
+    pub fn ssh_target(&self) -> Option<String> {
+        let host = self.ssh_host.as_deref()?;
+        let user = self.ssh_user.as_deref().unwrap_or("root");
+        Some(format!("{}@{}", user, host))
+    }
 }
@@ -0,0 +1,46 @@
+//! 設定ファイル（configs）定義
+
+use club_kdl::{KdlDeserialize, KdlSerialize};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 名前付き設定ファイル（トップレベル `configs` ブロック）
+///
+/// ファイルまたはインライン内容を宣言し、テンプレート展開した結果を
+/// `.fleetflow/rendered/` に書き出してサービスへ bind mount する。
+///
+/// KDL形式：
+/// ```kdl
+/// configs {
+///     config "nginx.conf" file="./config/nginx.conf"
+///     config "app.env" {
+///         content "PORT={{ PORT }}"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFile {
+    /// テンプレートファイルのパス（プロジェクトルートからの相対パス）
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// インライン内容（`file` と排他）
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// サービスへの設定ファイルマウント
+///
+/// KDL形式：
+/// ```kdl
+/// config "nginx.conf" target="/etc/nginx/nginx.conf"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, KdlDeserialize, KdlSerialize)]
+#[kdl(name = "config")]
+pub struct ConfigMount {
+    /// `configs` で宣言した設定名（第1引数）
+    #[kdl(argument)]
+    pub source: String,
+    /// コンテナ内のマウント先
+    #[kdl(property)]
+    pub target: PathBuf,
+}
@@ -1,7 +1,15 @@
 //! Flow定義
 
 use super::cloud::{CloudProvider, ServerResource};
+use super::config_file::ConfigFile;
+use super::maintenance::MaintenanceConfig;
+use super::mcp::McpConfig;
+use super::port::{Port, format_port_ranges};
+use super::secret::SecretDef;
 use super::service::Service;
+use super::setup::SetupStep;
+use super::site::SiteResource;
+use super::smoke_test::SmokeTarget;
 use super::stage::Stage;
 use super::tenant::TenantSpec;
 use serde::{Deserialize, Serialize};
@@ -11,7 +19,7 @@ use std::collections::HashMap;
 ///
 /// Flowは複数のサービスとステージを定義し、
 /// それらがどのように起動・管理されるかを記述します。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Flow {
     /// Flow名（プロジェクト名）
     pub name: String,
@@ -25,6 +33,9 @@ pub struct Flow {
     /// サーバーリソース
     #[serde(default)]
     pub servers: HashMap<String, ServerResource>,
+    /// 静的サイト（`site` ブロック）。`fleet cloud up` でビルドして R2 に配置する
+    #[serde(default)]
+    pub sites: HashMap<String, SiteResource>,
     /// デフォルトのコンテナレジストリURL（例: ghcr.io/owner）
     #[serde(default)]
     pub registry: Option<String>,
@@ -38,4 +49,316 @@ pub struct Flow {
     /// この値が optimal な権威を持つ (CLI flag による override は可)。
     #[serde(default)]
     pub tenant: Option<TenantSpec>,
+    /// 名前付き設定ファイル（`configs` ブロック）。サービスの `config` でマウントする
+    #[serde(default)]
+    pub configs: HashMap<String, ConfigFile>,
+    /// 名前付きシークレット（`secrets` ブロック）。サービスの `secret` でファイルとしてマウントする
+    #[serde(default)]
+    pub secrets: HashMap<String, SecretDef>,
+    /// `fleet up` の後に実行するセットアップステップ（`setup` ブロック、宣言順）
+    #[serde(default)]
+    pub setup: Vec<SetupStep>,
+    /// ビルドイメージ名のテンプレート（例: `{registry}/{project}/{service}:{stage}-{git_sha}`）。
+    /// ステージの `image_template` が優先。未設定時は `{registry}/{project}-{stage}:{tag}`
+    #[serde(default)]
+    pub image_template: Option<String>,
+    /// コンテナ名のテンプレート（例: `{project}_{service}_{stage}`）。
+    /// サービスの `container_name` が優先。未設定時は `{project}-{stage}-{service}`
+    #[serde(default)]
+    pub container_name_template: Option<String>,
+    /// デプロイ先サーバーのイメージ保持・削除ポリシー（`maintenance` ブロック）
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceConfig>,
+    /// MCP サーバーの設定（`mcp` ブロック）
+    #[serde(default)]
+    pub mcp: Option<McpConfig>,
+    /// ロード時の警告（固定していないリモート include 等）。表示は呼び出し側に任せる
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// `image_template` で使える変数
+pub const IMAGE_TEMPLATE_VARS: &[&str] = &[
+    "registry",
+    "project",
+    "stage",
+    "service",
+    "tag",
+    "git_sha",
+    "git_short_sha",
+    "git_branch",
+    "timestamp",
+];
+
+/// `container_name_template` / `container_name` で使える変数
+pub const CONTAINER_NAME_VARS: &[&str] = &["project", "stage", "service"];
+
+/// コンテナ名を決める
+///
+/// テンプレートの `{project}` / `{stage}` / `{service}`（`{{ name }}` も可）を展開する。
+/// テンプレートが無ければ OrbStack 連携の命名規則 `{project}-{stage}-{service}`。
+pub fn container_name(template: Option<&str>, project: &str, stage: &str, service: &str) -> String {
+    let Some(template) = template else {
+        return format!("{}-{}-{}", project, stage, service);
+    };
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let var = rest[start..start + end].trim_start_matches('{').trim();
+        match var {
+            "project" => name.push_str(project),
+            "stage" => name.push_str(stage),
+            "service" => name.push_str(service),
+            _ => name.push_str(&rest[start..=start + end]),
+        }
+        rest = rest[start + end..].trim_start_matches('}');
+    }
+    name.push_str(rest);
+    name
+}
+
+impl Flow {
+    /// サービスのコンテナ名
+    ///
+    /// サービスの `container_name`、プロジェクトの `container_name_template`、
+    /// 既定の `{project}-{stage}-{service}` の順に決める。
+    pub fn container_name(&self, stage: &str, service: &str) -> String {
+        let template = self
+            .services
+            .get(service)
+            .and_then(|s| s.container_name.as_deref())
+            .or(self.container_name_template.as_deref());
+        container_name(template, &self.name, stage, service)
+    }
+
+    /// 定義済みステージ名の一覧（名前順）
+    pub fn stage_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.stages.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// サービス名の一覧
+    ///
+    /// `stage` 指定時はそのステージに含まれるサービスを宣言順で、未指定時は
+    /// 定義済みの全サービスを名前順で返す。ステージが存在しなければ `None`。
+    pub fn service_names(&self, stage: Option<&str>) -> Option<Vec<&str>> {
+        match stage {
+            Some(stage) => Some(
+                self.stages
+                    .get(stage)?
+                    .services
+                    .iter()
+                    .map(String::as_str)
+                    .collect(),
+            ),
+            None => {
+                let mut names: Vec<&str> = self.services.keys().map(String::as_str).collect();
+                names.sort();
+                Some(names)
+            }
+        }
+    }
+
+    /// サービスを含むステージ名の一覧（名前順）
+    pub fn stages_of_service(&self, service: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .stages
+            .iter()
+            .filter(|(_, stage)| stage.services.iter().any(|s| s == service))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// 指定ステージを解決したときの整合性を検証する
+    ///
+    /// ステージが参照するサービス・サーバーの存在、イメージまたはビルド設定の有無、
+    /// ポート番号、depends_on の解決可能性（未定義・ステージ外・循環）を確認し、
+    /// 見つかった問題をすべて返す。問題がなければ空の Vec を返す。
+    pub fn validate_stage(&self, stage_name: &str) -> Vec<String> {
+        let Some(stage) = self.stages.get(stage_name) else {
+            let mut available: Vec<&str> = self.stages.keys().map(String::as_str).collect();
+            available.sort();
+            return vec![format!(
+                "ステージ '{}' が見つかりません（定義済み: {}）",
+                stage_name,
+                available.join(", ")
+            )];
+        };
+
+        let mut errors = Vec::new();
+
+        for server in &stage.servers {
+            if !self.servers.contains_key(server) {
+                errors.push(format!("未定義のサーバー '{}' を参照しています", server));
+            }
+        }
+
+        let mut host_ports: Vec<(&Port, &str)> = Vec::new();
+        // (先に使っていたサービス, 重複したサービス, ホストポート)
+        let mut overlaps: Vec<(&str, &str, Vec<u16>)> = Vec::new();
+        for name in &stage.services {
+            let Some(service) = self.services.get(name) else {
+                errors.push(format!("未定義のサービス '{}' を参照しています", name));
+                continue;
+            };
+
+            if !service.is_static() && service.image.is_none() && service.build.is_none() {
+                errors.push(format!(
+                    "サービス '{}' に image も build も指定されていません",
+                    name
+                ));
+            }
+
+            if let Some(mode) = &service.network_mode
+                && !service.ports.is_empty()
+            {
+                errors.push(format!(
+                    "サービス '{}' は network_mode \"{}\" のため port は指定できません",
+                    name, mode
+                ));
+            }
+
+            for (i, port) in service.ports.iter().enumerate() {
+                if let Some(port_name) = &port.name
+                    && service.ports[..i]
+                        .iter()
+                        .any(|p| p.name.as_ref() == Some(port_name))
+                {
+                    errors.push(format!(
+                        "サービス '{}' のポート名 '{}' が重複しています",
+                        name, port_name
+                    ));
+                }
+                if port.container == 0 {
+                    errors.push(format!("サービス '{}' のコンテナポートが 0 です", name));
+                }
+                if port.host == 0 {
+                    continue;
+                }
+                if let Some(&(_, other)) = host_ports.iter().find(|(p, _)| p.conflicts_with(port)) {
+                    match overlaps
+                        .iter_mut()
+                        .find(|(a, b, _)| *a == other && *b == name.as_str())
+                    {
+                        Some((_, _, ports)) => ports.push(port.host),
+                        None => overlaps.push((other, name, vec![port.host])),
+                    }
+                }
+                host_ports.push((port, name));
+            }
+
+            for dep in &service.depends_on {
+                if !self.services.contains_key(dep) {
+                    errors.push(format!(
+                        "サービス '{}' の depends_on が未定義のサービス '{}' を参照しています",
+                        name, dep
+                    ));
+                } else if !stage.services.contains(dep) {
+                    errors.push(format!(
+                        "サービス '{}' の depends_on '{}' はこのステージに含まれていません",
+                        name, dep
+                    ));
+                }
+            }
+        }
+
+        let mut container_names: Vec<(String, &str)> = Vec::new();
+        for name in stage
+            .services
+            .iter()
+            .filter(|s| self.services.contains_key(*s))
+        {
+            let container = self.container_name(stage_name, name);
+            if let Some((_, other)) = container_names.iter().find(|(c, _)| *c == container) {
+                errors.push(format!(
+                    "コンテナ名 '{}' がサービス '{}' と '{}' で重複しています",
+                    container, other, name
+                ));
+            }
+            container_names.push((container, name));
+        }
+
+        for (other, name, ports) in &overlaps {
+            errors.push(format!(
+                "ホストポート {} がサービス '{}' と '{}' で重複しています",
+                format_port_ranges(ports),
+                other,
+                name
+            ));
+        }
+
+        for test in &stage.smoke_tests {
+            let SmokeTarget::Port { service, port, .. } = &test.target else {
+                continue;
+            };
+            if !stage.services.contains(service) {
+                errors.push(format!(
+                    "smoke_test がこのステージに含まれていないサービス '{}' を参照しています",
+                    service
+                ));
+            } else if self
+                .services
+                .get(service)
+                .is_some_and(|s| s.port_by_name(port).is_none())
+            {
+                errors.push(format!(
+                    "smoke_test がサービス '{}' の未定義のポート名 '{}' を参照しています",
+                    service, port
+                ));
+            }
+        }
+
+        if let Some(cycle) = self.find_dependency_cycle(&stage.services) {
+            errors.push(format!(
+                "depends_on が循環しています: {}",
+                cycle.join(" -> ")
+            ));
+        }
+
+        errors
+    }
+
+    /// 指定サービス群の depends_on に循環があれば、その経路を返す
+    fn find_dependency_cycle(&self, services: &[String]) -> Option<Vec<String>> {
+        fn visit(
+            flow: &Flow,
+            name: &str,
+            services: &[String],
+            done: &mut Vec<String>,
+            path: &mut Vec<String>,
+        ) -> Option<Vec<String>> {
+            if let Some(pos) = path.iter().position(|n| n == name) {
+                let mut cycle = path[pos..].to_vec();
+                cycle.push(name.to_string());
+                return Some(cycle);
+            }
+            if done.iter().any(|n| n == name) {
+                return None;
+            }
+
+            path.push(name.to_string());
+            if let Some(service) = flow.services.get(name) {
+                for dep in service.depends_on.iter().filter(|d| services.contains(d)) {
+                    if let Some(cycle) = visit(flow, dep, services, done, path) {
+                        return Some(cycle);
+                    }
+                }
+            }
+            path.pop();
+            done.push(name.to_string());
+            None
+        }
+
+        let mut done = Vec::new();
+        services
+            .iter()
+            .find_map(|name| visit(self, name, services, &mut done, &mut Vec::new()))
+    }
 }
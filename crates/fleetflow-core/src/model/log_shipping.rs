@@ -0,0 +1,120 @@
+//! ログ集約（log shipping）定義
+
+use serde::{Deserialize, Serialize};
+
+/// ログ転送エージェント
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogShippingAgent {
+    /// Vector（Docker API 経由で fleetflow ラベル付きコンテナのみを収集）— 既定
+    #[default]
+    Vector,
+    /// Fluent Bit（ホストのコンテナログファイルを tail し、ログ行のラベルで絞り込む）
+    FluentBit,
+}
+
+impl LogShippingAgent {
+    /// 文字列からパース
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "vector" => Some(Self::Vector),
+            "fluent-bit" | "fluent_bit" | "fluentbit" => Some(Self::FluentBit),
+            _ => None,
+        }
+    }
+
+    /// 文字列表現
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Vector => "vector",
+            Self::FluentBit => "fluent-bit",
+        }
+    }
+}
+
+/// ログ転送先の種別
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSinkKind {
+    /// Grafana Loki — 既定
+    #[default]
+    Loki,
+    /// S3 互換オブジェクトストレージ
+    S3,
+    /// 任意の HTTP エンドポイント（JSON で POST）
+    Http,
+}
+
+impl LogSinkKind {
+    /// 文字列からパース
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "loki" => Some(Self::Loki),
+            "s3" => Some(Self::S3),
+            "http" => Some(Self::Http),
+            _ => None,
+        }
+    }
+
+    /// 文字列表現
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Loki => "loki",
+            Self::S3 => "s3",
+            Self::Http => "http",
+        }
+    }
+}
+
+/// ステージ単位のログ集約設定（opt-in）
+///
+/// 宣言されたステージには `log-shipper` サービスが自動追加され、
+/// プロジェクトの全コンテナのログを指定先へ転送する。
+///
+/// KDL形式：
+/// ```kdl
+/// stage "prod" {
+///     log_shipping agent="vector" sink="loki" endpoint="http://loki:3100"
+/// }
+///
+/// stage "live" {
+///     log_shipping sink="s3" {
+///         bucket "my-logs"
+///         region "ap-northeast-1"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogShippingConfig {
+    /// 転送エージェント（vector / fluent-bit）
+    #[serde(default)]
+    pub agent: LogShippingAgent,
+    /// 転送先の種別（loki / s3 / http）
+    #[serde(default)]
+    pub sink: LogSinkKind,
+    /// 転送先 URL（loki / http で必須、s3 では S3 互換エンドポイントの上書き）
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// S3 バケット名（s3 で必須）
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 リージョン
+    #[serde(default)]
+    pub region: Option<String>,
+    /// エージェントイメージの上書き（未指定時はエージェントごとの既定イメージ）
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+impl LogShippingConfig {
+    /// 使用するエージェントイメージ
+    pub fn agent_image(&self) -> String {
+        if let Some(image) = &self.image {
+            return image.clone();
+        }
+        match self.agent {
+            LogShippingAgent::Vector => "timberio/vector:0.41.1-alpine".to_string(),
+            LogShippingAgent::FluentBit => "fluent/fluent-bit:3.1".to_string(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! サーバーメンテナンス（イメージ保持・削除）ポリシー定義
+
+use serde::{Deserialize, Serialize};
+
+/// デプロイ後に未使用イメージ・ビルドキャッシュを削除する間隔
+///
+/// 指定した期間より古い未使用のものだけを削除する。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneSchedule {
+    /// デプロイのたびに経過時間を問わず削除
+    Always,
+    /// 1 日以上経過したものを削除
+    Daily,
+    /// 1 週間以上経過したものを削除 — 既定
+    #[default]
+    Weekly,
+    /// デプロイ時には削除しない（`fleet remote prune` のみ）
+    Never,
+}
+
+impl PruneSchedule {
+    /// 文字列からパース
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "always" => Some(Self::Always),
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// 文字列表現
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Never => "never",
+        }
+    }
+
+    /// Docker の prune に渡す `until` フィルタ（Always / Never は None）
+    pub fn until_filter(&self) -> Option<&'static str> {
+        match self {
+            Self::Daily => Some("24h"),
+            Self::Weekly => Some("168h"),
+            Self::Always | Self::Never => None,
+        }
+    }
+}
+
+/// デプロイ先サーバーのディスク管理ポリシー
+///
+/// `fleet deploy` の成功後と `fleet remote prune` で適用される。
+///
+/// KDL形式：
+/// ```kdl
+/// maintenance {
+///     image_retention 5
+///     prune_schedule "daily"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// プロジェクトでビルドしたイメージをリポジトリごとに新しい順で何個残すか
+    /// （未指定時は古いタグを削除しない）
+    #[serde(default)]
+    pub image_retention: Option<u32>,
+    /// 未使用イメージ・ビルドキャッシュの削除間隔
+    #[serde(default)]
+    pub prune_schedule: PruneSchedule,
+}
@@ -0,0 +1,31 @@
+//! MCP サーバー設定（`mcp` ブロック）
+
+use serde::{Deserialize, Serialize};
+
+/// MCP サーバーの設定
+///
+/// ```kdl
+/// mcp {
+///     enable_destructive_tools #false
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpConfig {
+    /// 破壊的なツール（`fleetflow_deploy`、`remove=true` の `fleetflow_down`）を有効にするか
+    ///
+    /// 既定は true。false にするとツール一覧から外し、呼び出しも拒否する。
+    #[serde(default = "default_enable_destructive_tools")]
+    pub enable_destructive_tools: bool,
+}
+
+fn default_enable_destructive_tools() -> bool {
+    true
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            enable_destructive_tools: default_enable_destructive_tools(),
+        }
+    }
+}
@@ -4,20 +4,36 @@
 //! 各モデルは機能ごとにモジュールに分離されています。
 
 mod cloud;
+mod config_file;
 mod flow;
+mod log_shipping;
+mod maintenance;
+mod mcp;
 mod port;
 mod process;
+mod secret;
 mod service;
+mod setup;
+mod site;
+mod smoke_test;
 mod stage;
 mod tenant;
 mod volume;
 
 // Re-exports
 pub use cloud::*;
+pub use config_file::*;
 pub use flow::*;
+pub use log_shipping::*;
+pub use maintenance::*;
+pub use mcp::*;
 pub use port::*;
 pub use process::*;
+pub use secret::*;
 pub use service::*;
+pub use setup::*;
+pub use site::*;
+pub use smoke_test::*;
 pub use stage::*;
 pub use tenant::*;
 pub use volume::*;
@@ -54,11 +70,7 @@ mod tests {
             name: "my-project".to_string(),
             services,
             stages,
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
 
         assert_eq!(flow.name, "my-project");
@@ -80,11 +92,7 @@ mod tests {
             name: "test-flow".to_string(),
             services: services.clone(),
             stages: stages.clone(),
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
-            tenant: None,
+            ..Default::default()
         };
 
         assert_eq!(flow.services.len(), 1);
@@ -171,6 +179,8 @@ mod tests {
                 container: 5432,
                 protocol: Protocol::Tcp,
                 host_ip: None,
+                name: None,
+                app_protocol: None,
             }],
         };
 
@@ -209,4 +219,231 @@ mod tests {
         assert_eq!(deserialized.flow_name, process.flow_name);
         assert_eq!(deserialized.state, process.state);
     }
+
+    fn stage_flow(services: Vec<(&str, Service)>, stage_services: &[&str]) -> Flow {
+        let mut stages = HashMap::new();
+        stages.insert(
+            "prod".to_string(),
+            Stage {
+                services: stage_services.iter().map(|s| s.to_string()).collect(),
+                ..Default::default()
+            },
+        );
+        Flow {
+            name: "test".to_string(),
+            services: services
+                .into_iter()
+                .map(|(name, svc)| (name.to_string(), svc))
+                .collect(),
+            stages,
+            ..Default::default()
+        }
+    }
+
+    fn image(name: &str) -> Service {
+        Service {
+            image: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_introspection_names() {
+        let mut flow = stage_flow(
+            vec![
+                ("web", image("nginx")),
+                ("db", image("postgres:16")),
+                ("api", image("api:1")),
+            ],
+            &["web", "api"],
+        );
+        flow.stages.insert(
+            "dev".to_string(),
+            Stage {
+                services: vec!["db".to_string(), "api".to_string()],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(flow.stage_names(), vec!["dev", "prod"]);
+        assert_eq!(flow.service_names(None), Some(vec!["api", "db", "web"]));
+        assert_eq!(flow.service_names(Some("prod")), Some(vec!["web", "api"]));
+        assert_eq!(flow.service_names(Some("missing")), None);
+        assert_eq!(flow.stages_of_service("api"), vec!["dev", "prod"]);
+        assert_eq!(flow.stages_of_service("db"), vec!["dev"]);
+    }
+
+    #[test]
+    fn test_validate_stage_ok() {
+        let mut api = image("api:1");
+        api.depends_on = vec!["db".to_string()];
+        let flow = stage_flow(
+            vec![("api", api), ("db", image("postgres:16"))],
+            &["api", "db"],
+        );
+
+        assert!(flow.validate_stage("prod").is_empty());
+    }
+
+    #[test]
+    fn test_validate_stage_unknown_stage() {
+        let flow = stage_flow(vec![], &[]);
+        let errors = flow.validate_stage("staging");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("staging"));
+    }
+
+    #[test]
+    fn test_validate_stage_reports_resolution_errors() {
+        let mut api = image("api:1");
+        api.depends_on = vec!["cache".to_string(), "worker".to_string()];
+        api.ports = vec![Port {
+            host: 8080,
+            container: 3000,
+            protocol: Protocol::Tcp,
+            host_ip: None,
+            name: None,
+            app_protocol: None,
+        }];
+        let web = Service {
+            ports: vec![Port {
+                host: 8080,
+                container: 0,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Default::default()
+        };
+        let mut flow = stage_flow(
+            vec![("api", api), ("web", web), ("worker", image("worker:1"))],
+            &["api", "web", "missing"],
+        );
+        flow.stages.get_mut("prod").unwrap().servers = vec!["vps-1".to_string()];
+
+        let errors = flow.validate_stage("prod");
+        let has = |needle: &str| errors.iter().any(|e| e.contains(needle));
+        assert!(has("未定義のサーバー 'vps-1'"));
+        assert!(has("未定義のサービス 'missing'"));
+        assert!(has("'web' に image も build も指定されていません"));
+        assert!(has("コンテナポートが 0"));
+        assert!(has("ホストポート 8080"));
+        assert!(has("未定義のサービス 'cache'"));
+        assert!(has("'worker' はこのステージに含まれていません"));
+        assert_eq!(errors.len(), 7);
+    }
+
+    #[test]
+    fn test_validate_stage_checks_named_ports() {
+        let named = |host: u16, name: &str| Port {
+            host,
+            container: host,
+            protocol: Protocol::Tcp,
+            host_ip: None,
+            name: Some(name.to_string()),
+            app_protocol: None,
+        };
+        let mut web = image("nginx");
+        web.ports = vec![named(8080, "http"), named(8081, "http")];
+        let mut flow = stage_flow(vec![("web", web), ("db", image("postgres:16"))], &["web"]);
+        let smoke = |service: &str, port: &str| SmokeTest {
+            target: SmokeTarget::Port {
+                service: service.to_string(),
+                port: port.to_string(),
+                path: "/".to_string(),
+            },
+            expect: SmokeTest::DEFAULT_EXPECT,
+            timeout: SmokeTest::DEFAULT_TIMEOUT,
+        };
+        flow.stages.get_mut("prod").unwrap().smoke_tests = vec![
+            smoke("web", "http"),
+            smoke("web", "admin"),
+            smoke("db", "sql"),
+        ];
+
+        let errors = flow.validate_stage("prod");
+        let has = |needle: &str| errors.iter().any(|e| e.contains(needle));
+        assert!(has("ポート名 'http' が重複しています"));
+        assert!(has("未定義のポート名 'admin'"));
+        assert!(has("含まれていないサービス 'db'"));
+        assert_eq!(errors.len(), 3);
+    }
+
+    #[test]
+    fn test_validate_stage_reports_port_range_overlap() {
+        let port = |host: u16, protocol: Protocol, host_ip: Option<&str>| Port {
+            host,
+            container: host,
+            protocol,
+            host_ip: host_ip.map(str::to_string),
+            name: None,
+            app_protocol: None,
+        };
+        let mut rtp = image("rtp:1");
+        rtp.ports = (7000..=7010)
+            .map(|p| port(p, Protocol::Udp, None))
+            .collect();
+        let mut relay = image("relay:1");
+        relay.ports = (7005..=7020)
+            .map(|p| port(p, Protocol::Udp, None))
+            .chain([port(7000, Protocol::Tcp, None)])
+            .collect();
+        let mut admin = image("admin:1");
+        admin.ports = vec![port(7020, Protocol::Udp, Some("127.0.0.1"))];
+        let flow = stage_flow(
+            vec![("rtp", rtp), ("relay", relay), ("admin", admin)],
+            &["rtp", "relay", "admin"],
+        );
+
+        // TCP と UDP は別扱い、0.0.0.0 と特定 IP は重複とみなす
+        assert_eq!(
+            flow.validate_stage("prod"),
+            vec![
+                "ホストポート 7005-7010 がサービス 'rtp' と 'relay' で重複しています",
+                "ホストポート 7020 がサービス 'relay' と 'admin' で重複しています",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_port_ranges() {
+        assert_eq!(
+            port::format_port_ranges(&[7002, 7000, 7001, 8080, 9000, 9001]),
+            "7000-7002, 8080, 9000-9001"
+        );
+        assert_eq!(port::format_port_ranges(&[]), "");
+    }
+
+    #[test]
+    fn test_validate_stage_rejects_ports_with_network_mode() {
+        let mut sniffer = image("tcpdump");
+        sniffer.network_mode = Some(NetworkMode::Host);
+        sniffer.ports = vec![Port {
+            host: 8080,
+            container: 8080,
+            protocol: Protocol::Tcp,
+            host_ip: None,
+            name: None,
+            app_protocol: None,
+        }];
+        let flow = stage_flow(vec![("sniffer", sniffer)], &["sniffer"]);
+
+        assert_eq!(
+            flow.validate_stage("prod"),
+            vec!["サービス 'sniffer' は network_mode \"host\" のため port は指定できません"]
+        );
+    }
+
+    #[test]
+    fn test_validate_stage_detects_dependency_cycle() {
+        let mut a = image("a:1");
+        a.depends_on = vec!["b".to_string()];
+        let mut b = image("b:1");
+        b.depends_on = vec!["a".to_string()];
+        let flow = stage_flow(vec![("a", a), ("b", b)], &["a", "b"]);
+
+        let errors = flow.validate_stage("prod");
+        assert_eq!(errors, vec!["depends_on が循環しています: a -> b -> a"]);
+    }
 }
@@ -18,6 +18,99 @@ pub struct Port {
     pub protocol: Protocol,
     #[kdl(property)]
     pub host_ip: Option<String>,
+    /// ポート名（`name="http"`）。smoke_test や `fleet open --port` から参照する
+    #[serde(default)]
+    #[kdl(property)]
+    pub name: Option<String>,
+    /// アプリケーションプロトコル（`protocol="http"` / `"https"`）。URL の表示に使う
+    #[serde(default)]
+    #[kdl(skip)]
+    pub app_protocol: Option<AppProtocol>,
+}
+
+impl Port {
+    /// ブラウザでアクセスする際の URL スキーム
+    ///
+    /// `protocol="http"` / `"https"` 指定があればそれを、なければコンテナポート 443 を https とみなす。
+    /// UDP ポートは None。
+    pub fn scheme(&self) -> Option<&'static str> {
+        match (&self.protocol, &self.app_protocol) {
+            (Protocol::Udp, _) => None,
+            (_, Some(app)) => Some(app.as_str()),
+            _ if self.container == 443 => Some("https"),
+            _ => Some("http"),
+        }
+    }
+
+    /// ホストから見た URL（例: `http://localhost:18080`）
+    ///
+    /// 全インターフェースで公開されている場合は `localhost` を使う。
+    pub fn url(&self) -> Option<String> {
+        let scheme = self.scheme()?;
+        let host = match self.host_ip.as_deref() {
+            None | Some("") | Some("0.0.0.0") | Some("::") => "localhost".to_string(),
+            Some(ip) if ip.contains(':') => format!("[{}]", ip),
+            Some(ip) => ip.to_string(),
+        };
+        Some(format!("{}://{}:{}", scheme, host, self.host))
+    }
+}
+
+impl Port {
+    /// 同じホストポートを奪い合うかどうか（プロトコルが同じで、バインド先 IP が重なる）
+    ///
+    /// `host_ip` 未指定・`0.0.0.0`・`::` は全インターフェースなので、どの IP とも重なる。
+    pub fn conflicts_with(&self, other: &Port) -> bool {
+        let wildcard = |ip: &Option<String>| matches!(ip.as_deref(), None | Some("0.0.0.0" | "::"));
+        self.host == other.host
+            && self.protocol == other.protocol
+            && (self.host_ip == other.host_ip
+                || wildcard(&self.host_ip)
+                || wildcard(&other.host_ip))
+    }
+}
+
+/// ポート番号の一覧を連続範囲にまとめて表示する（例: `7000-7010, 8080`）
+pub(crate) fn format_port_ranges(ports: &[u16]) -> String {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for port in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == port => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// アプリケーションプロトコル（トランスポートは TCP）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AppProtocol {
+    Http,
+    Https,
+}
+
+impl AppProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+        }
+    }
 }
 
 /// プロトコル種別
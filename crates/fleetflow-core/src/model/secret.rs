@@ -0,0 +1,56 @@
+//! シークレット（secrets）定義
+
+use club_kdl::{KdlDeserialize, KdlSerialize};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// シークレットのデフォルトマウント先ディレクトリ
+pub const DEFAULT_SECRET_DIR: &str = "/run/secrets";
+
+/// 名前付きシークレット（トップレベル `secrets` ブロック）
+///
+/// 値は環境変数ではなく、tmpfs 上のファイルとしてコンテナへマウントされる。
+///
+/// KDL形式：
+/// ```kdl
+/// secrets {
+///     secret "db_password" from="op://Vault/db/password"
+///     secret "api_key" env="API_KEY"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretDef {
+    /// 値の参照元（`op://` 参照またはリテラル。テンプレート変数も使用可）
+    #[serde(default)]
+    pub from: Option<String>,
+    /// 値を読み取るホスト側の環境変数名（`from` と排他）
+    #[serde(default)]
+    pub env: Option<String>,
+}
+
+/// サービスへのシークレットマウント
+///
+/// KDL形式：
+/// ```kdl
+/// secret "db_password" target="/run/secrets/db_password"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, KdlDeserialize, KdlSerialize)]
+#[kdl(name = "secret")]
+pub struct SecretMount {
+    /// `secrets` で宣言したシークレット名（第1引数）
+    #[kdl(argument)]
+    pub source: String,
+    /// コンテナ内のマウント先（省略時は `/run/secrets/{source}`）
+    #[serde(default)]
+    #[kdl(property)]
+    pub target: Option<PathBuf>,
+}
+
+impl SecretMount {
+    /// コンテナ内のマウント先パス
+    pub fn target_path(&self) -> PathBuf {
+        self.target
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SECRET_DIR).join(&self.source))
+    }
+}
@@ -1,6 +1,9 @@
 //! サービス定義
 
+use super::config_file::ConfigMount;
+use super::flow::Flow;
 use super::port::Port;
+use super::secret::SecretMount;
 use super::volume::Volume;
 use club_kdl::{
     Error as KdlError, FromKdlValue, KdlDeserialize, KdlSerialize, KdlValue, ToKdlValue,
@@ -15,6 +18,9 @@ use std::path::PathBuf;
 /// ```kdl
 /// service "name" image="..." restart="unless-stopped" {
 ///     port host=8080 container=80
+///     user "1000:1000"
+///     cap_drop "ALL"
+///     read_only #true
 ///     volume host="/data" container="/data"
 ///     env {
 ///         KEY "value"
@@ -28,12 +34,40 @@ pub struct Service {
     #[serde(default, alias = "type")]
     #[kdl(property(rename = "type"))]
     pub service_type: Option<ServiceType>,
+    /// 継承元サービス名（共通設定を `extends` で再利用）
+    #[serde(default)]
+    #[kdl(property)]
+    pub extends: Option<String>,
     #[kdl(property)]
     pub image: Option<String>,
+    /// コンテナ名（`{project}` / `{stage}` / `{service}` を展開）。
+    /// プロジェクトの `container_name_template` より優先
+    #[serde(default)]
+    #[kdl(property)]
+    pub container_name: Option<String>,
+    /// `#false` ならどのステージでも起動しない
+    #[kdl(property)]
+    pub enabled: Option<bool>,
+    /// ステージごとに評価する条件式（例: `"{{ stage }} != 'local'"`）。
+    /// 偽になったステージのサービス一覧から外す
+    #[kdl(property)]
+    pub when: Option<String>,
     #[kdl(property)]
     pub version: Option<String>,
+    /// 起動コマンド（イメージの CMD を上書き）。
+    /// 配列形式 `command "npm" "run" "dev"` は空白を含む引数をクォートして 1 行に連結する
     #[kdl(property)]
     pub command: Option<String>,
+    /// エントリーポイント（イメージの ENTRYPOINT を上書き）
+    #[serde(default)]
+    #[kdl(skip)] // 複数の引数を取るため別途パース
+    pub entrypoint: Option<Vec<String>>,
+    /// コンテナ内の作業ディレクトリ（イメージの WORKDIR を上書き）
+    #[kdl(property)]
+    pub working_dir: Option<String>,
+    /// コンテナのホスト名
+    #[kdl(property)]
+    pub hostname: Option<String>,
     #[serde(default)]
     #[kdl(children, name = "port")]
     pub ports: Vec<Port>,
@@ -67,6 +101,88 @@ pub struct Service {
     /// サービス固有のコンテナレジストリURL（例: ghcr.io/owner）
     #[kdl(property)]
     pub registry: Option<String>,
+    /// コンテナログのドライバ・ローテーション設定
+    #[kdl(child)]
+    pub logging: Option<LoggingConfig>,
+    /// コンテナ内の実行ユーザー（例: "1000:1000", "app"）
+    #[kdl(property)]
+    pub user: Option<String>,
+    /// 追加する Linux capability（例: NET_ADMIN）
+    #[serde(default)]
+    #[kdl(skip)]
+    pub cap_add: Vec<String>,
+    /// 削除する Linux capability（例: ALL）
+    #[serde(default)]
+    #[kdl(skip)]
+    pub cap_drop: Vec<String>,
+    /// ルートファイルシステムを読み取り専用でマウント
+    #[kdl(property)]
+    pub read_only: Option<bool>,
+    /// セキュリティオプション（例: "no-new-privileges:true", "seccomp=unconfined"）
+    #[serde(default)]
+    #[kdl(skip)]
+    pub security_opt: Vec<String>,
+    /// 特権モードで実行
+    #[kdl(property)]
+    pub privileged: Option<bool>,
+    /// ネットワークモード（未指定時はステージのネットワークに接続）
+    #[serde(default)]
+    #[kdl(skip)]
+    pub network_mode: Option<NetworkMode>,
+    /// 擬似 TTY を割り当てる（REPL 型サービスを `fleet attach` で操作する場合など）
+    #[kdl(property)]
+    pub tty: Option<bool>,
+    /// 標準入力を開いたままにする
+    #[kdl(property)]
+    pub stdin_open: Option<bool>,
+    /// 依存サービスの準備完了を待つスクリプトでエントリーポイントをラップする
+    ///
+    /// 待機対象は `depends_on` から生成し、待機間隔は `wait_for` に従う。
+    #[kdl(property)]
+    pub inject_wait: Option<bool>,
+    /// depends_on の各サービスへの接続先を環境変数として注入する
+    ///
+    /// `<SERVICE>_HOST` と `<SERVICE>_PORT_<NAME>`（ポート名が無ければコンテナポート番号）、
+    /// 最初のポートを `<SERVICE>_PORT` として追加する。明示した env が優先。
+    #[kdl(property)]
+    pub inject_links: Option<bool>,
+    /// tmpfs マウント
+    #[serde(default)]
+    #[kdl(children, name = "tmpfs")]
+    pub tmpfs: Vec<TmpfsMount>,
+    /// /dev/shm のサイズ（例: "256m"）。headless ブラウザや DB で必要
+    #[kdl(property)]
+    pub shm_size: Option<String>,
+    /// 停止時に送るシグナル（例: "SIGQUIT"）。省略時はイメージの STOPSIGNAL
+    #[kdl(property)]
+    pub stop_signal: Option<String>,
+    /// 停止シグナル送信後、強制終了（SIGKILL）までの猶予秒数
+    #[kdl(property)]
+    pub stop_grace_period: Option<u64>,
+    /// リソース制限（nofile, nproc 等）
+    #[serde(default)]
+    #[kdl(skip)] // ノード名がリミット名になるため別途パース
+    pub ulimits: Vec<Ulimit>,
+    /// カーネルパラメータ（例: net.core.somaxconn）
+    #[serde(default)]
+    #[kdl(child_map, name = "sysctls")]
+    pub sysctls: HashMap<String, String>,
+    /// コンテナに付ける任意のラベル（`fleetflow.` / `com.docker.compose.` で始まるキーは予約）
+    #[serde(default)]
+    #[kdl(child_map, name = "labels")]
+    pub labels: HashMap<String, String>,
+    /// 設定ファイルのマウント（トップレベル `configs` を参照）
+    #[serde(default)]
+    #[kdl(children, name = "config")]
+    pub configs: Vec<ConfigMount>,
+    /// シークレットのファイルマウント（トップレベル `secrets` を参照）
+    #[serde(default)]
+    #[kdl(children, name = "secret")]
+    pub secrets: Vec<SecretMount>,
+    /// テスト設定（`fleet test` で使い捨てコンテナ内に実行）
+    #[serde(default)]
+    #[kdl(skip)]
+    pub test: Option<TestConfig>,
 }
 
 /// サービスタイプ
@@ -116,6 +232,65 @@ impl ToKdlValue for &ServiceType {
     }
 }
 
+/// ネットワークモード
+///
+/// KDL形式：
+/// ```kdl
+/// service "sniffer" {
+///     network_mode "host"          // ホストのネットワーク名前空間を共有
+/// }
+/// service "proxy-sidecar" {
+///     network_mode "container:app" // 他のサービスの名前空間を共有（depends_on に自動追加）
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkMode {
+    /// ホストのネットワークを直接使う
+    Host,
+    /// ネットワークなし（ループバックのみ）
+    None,
+    /// 指定サービスのコンテナとネットワーク名前空間を共有
+    Container(String),
+}
+
+impl NetworkMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "host" => Some(Self::Host),
+            "none" => Some(Self::None),
+            _ => s
+                .strip_prefix("container:")
+                .filter(|svc| !svc.is_empty())
+                .map(|svc| Self::Container(svc.to_string())),
+        }
+    }
+
+    /// Docker の `HostConfig.network_mode` に渡す値
+    ///
+    /// サービス名は参照先の `container_name` / `container_name_template` に従って
+    /// コンテナ名に解決する。
+    pub fn docker_value(&self, flow: &Flow, stage: &str) -> String {
+        match self {
+            Self::Host => "host".to_string(),
+            Self::None => "none".to_string(),
+            Self::Container(service) => {
+                format!("container:{}", flow.container_name(stage, service))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NetworkMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Host => write!(f, "host"),
+            Self::None => write!(f, "none"),
+            Self::Container(service) => write!(f, "container:{}", service),
+        }
+    }
+}
+
 /// デプロイ先設定
 ///
 /// KDL形式：
@@ -138,6 +313,210 @@ pub struct DeployConfig {
     pub output: Option<String>,
 }
 
+/// コンテナログ設定
+///
+/// 長時間稼働するステージでログがディスクを埋め尽くさないよう、
+/// ドライバとローテーションを指定する。Docker の `HostConfig.LogConfig` に対応。
+///
+/// KDL形式：
+/// ```kdl
+/// logging {
+///     driver "json-file"
+///     max_size "10m"
+///     max_file 3
+/// }
+///
+/// logging driver="syslog" {
+///     options {
+///         syslog-address "udp://192.168.0.42:514"
+///     }
+/// }
+/// ```
+#[derive(
+    Debug, Clone, Default, PartialEq, Serialize, Deserialize, KdlDeserialize, KdlSerialize,
+)]
+#[kdl(name = "logging")]
+pub struct LoggingConfig {
+    /// ログドライバ（json-file / local / journald / syslog 等）。省略時は Docker デーモンの既定
+    #[kdl(property)]
+    pub driver: Option<String>,
+    /// ローテーションするファイルサイズ（例: "10m"）。json-file / local のみ有効
+    #[kdl(property)]
+    pub max_size: Option<String>,
+    /// 保持するローテーションファイル数。json-file / local のみ有効
+    #[kdl(property)]
+    pub max_file: Option<u32>,
+    /// ドライバ固有の追加オプション（syslog-address, tag 等）
+    #[serde(default)]
+    #[kdl(child_map, name = "options")]
+    pub options: HashMap<String, String>,
+}
+
+impl LoggingConfig {
+    /// Docker の `--log-opt` 相当のオプションマップを生成する
+    ///
+    /// `max_size` / `max_file` は `max-size` / `max-file` に変換され、
+    /// `options` で同じキーが指定されている場合はそちらが優先される。
+    pub fn docker_options(&self) -> HashMap<String, String> {
+        let mut opts = HashMap::new();
+        if let Some(size) = &self.max_size {
+            opts.insert("max-size".to_string(), size.clone());
+        }
+        if let Some(count) = self.max_file {
+            opts.insert("max-file".to_string(), count.to_string());
+        }
+        for (key, value) in &self.options {
+            opts.insert(key.clone(), value.clone());
+        }
+        opts
+    }
+}
+
+/// tmpfs マウント定義
+///
+/// KDL形式：
+/// ```kdl
+/// tmpfs "/tmp" size="64m" mode="1777"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, KdlDeserialize, KdlSerialize)]
+#[kdl(name = "tmpfs")]
+pub struct TmpfsMount {
+    /// コンテナ内のマウント先（第1引数）
+    #[kdl(argument)]
+    pub target: PathBuf,
+    /// サイズ上限（例: "64m"）。未指定時はホストメモリの半分
+    #[kdl(property)]
+    pub size: Option<String>,
+    /// パーミッション（8進数、例: "1777"）
+    #[kdl(property)]
+    pub mode: Option<String>,
+}
+
+impl TmpfsMount {
+    /// Docker の `HostConfig.Tmpfs` に渡すマウントオプション文字列
+    pub fn docker_options(&self) -> String {
+        let mut opts = Vec::new();
+        if let Some(size) = &self.size {
+            opts.push(format!("size={}", size));
+        }
+        if let Some(mode) = &self.mode {
+            opts.push(format!("mode={}", mode));
+        }
+        opts.join(",")
+    }
+}
+
+/// リソース制限（ulimit）
+///
+/// KDL形式：
+/// ```kdl
+/// ulimits {
+///     nofile soft=65536 hard=65536
+///     nproc 4096
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ulimit {
+    /// リミット名（nofile, nproc, memlock 等）
+    pub name: String,
+    /// ソフトリミット
+    pub soft: i64,
+    /// ハードリミット
+    pub hard: i64,
+}
+
+/// サイズ文字列をバイト数に変換する
+///
+/// `"64m"`, `"1g"`, `"512k"`, `"1024"`, `"2GB"` のような形式を受け付ける
+/// （大文字小文字は区別しない、単位は 1024 進）。
+pub fn parse_byte_size(s: &str) -> Option<u64> {
+    let s = s.trim().to_lowercase();
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digits_end == 0 {
+        return None;
+    }
+    let value: u64 = s[..digits_end].parse().ok()?;
+    let multiplier: u64 = match &s[digits_end..] {
+        "" | "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    value.checked_mul(multiplier)
+}
+
+/// コマンド文字列を引数に分割する
+///
+/// 空白で区切り、シングル・ダブルクォートの中の空白は引数の一部として扱う。
+/// クォートの外の `\` は次の 1 文字をそのまま使う。
+pub fn split_command_line(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                Some(next @ ('"' | '\\')) => current.push(next),
+                Some(next) => {
+                    current.push('\\');
+                    current.push(next);
+                }
+                None => current.push('\\'),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// 引数のリストを `split_command_line` で元に戻せるコマンド文字列に連結する
+pub fn join_command_line<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter()
+        .map(|arg| {
+            let arg = arg.as_ref();
+            let plain = !arg.is_empty()
+                && !arg
+                    .chars()
+                    .any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\'));
+            if plain {
+                arg.to_string()
+            } else if !arg.contains('\'') {
+                format!("'{}'", arg)
+            } else {
+                format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// 再起動ポリシー
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -223,6 +602,15 @@ pub struct BuildConfig {
     /// イメージタグの明示的指定
     #[kdl(property)]
     pub image_tag: Option<String>,
+    /// ステージのサーバー上でビルドする（SSH 経由でサーバーの Docker デーモンを使用）
+    #[serde(default)]
+    #[kdl(property, default)]
+    pub remote: bool,
+    /// 変更検知の対象パス（glob、プロジェクトルートからの相対パス）
+    /// 未指定の場合はビルドコンテキスト配下
+    #[serde(default)]
+    #[kdl(skip)]
+    pub watch_paths: Vec<String>,
 }
 
 /// ヘルスチェック設定
@@ -307,6 +695,46 @@ fn default_readiness_interval() -> u64 {
     2
 }
 
+/// テスト設定
+///
+/// `fleet test` がステージのネットワーク上に使い捨てコンテナを作り、
+/// プロジェクトルートを `workdir` にマウントして `command` を `sh -c` で実行する。
+/// サービスの `env` も引き継ぐ（`env` ブロックで上書き可能）。
+///
+/// KDL形式：
+/// ```kdl
+/// test {
+///     command "cargo test"
+///     image "rust:1.79"
+///     workdir "/app"
+///     env {
+///         RUST_LOG "debug"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestConfig {
+    /// 実行するシェルコマンド
+    pub command: String,
+    /// テストを実行するイメージ（未指定ならサービスのイメージ）
+    #[serde(default)]
+    pub image: Option<String>,
+    /// プロジェクトルートのマウント先・作業ディレクトリ（未指定なら `/workspace`）
+    #[serde(default)]
+    pub workdir: Option<String>,
+    /// 追加の環境変数
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl TestConfig {
+    pub const DEFAULT_WORKDIR: &'static str = "/workspace";
+
+    pub fn workdir(&self) -> &str {
+        self.workdir.as_deref().unwrap_or(Self::DEFAULT_WORKDIR)
+    }
+}
+
 /// 依存サービス待機設定（Exponential Backoff）
 ///
 /// KDL形式：
@@ -372,6 +800,38 @@ impl Service {
         matches!(self.service_type, Some(ServiceType::Static))
     }
 
+    /// `network_mode "container:<svc>"` の共有先を depends_on に含める
+    ///
+    /// 共有先コンテナが先に存在しないと作成できないため、起動順序を保証する。
+    pub(crate) fn add_network_dependency(&mut self) {
+        if let Some(NetworkMode::Container(target)) = &self.network_mode
+            && !self.depends_on.contains(target)
+        {
+            self.depends_on.push(target.clone());
+        }
+    }
+
+    /// `command` を引数のリストに分割する（クォートで空白を含む引数を表せる）
+    pub fn command_args(&self) -> Option<Vec<String>> {
+        self.command.as_deref().map(split_command_line)
+    }
+
+    /// `user` が数値（`uid` / `uid:gid`）ならその uid / gid を返す
+    ///
+    /// ユーザー名はイメージ内の `/etc/passwd` を引かないと解決できないため `None`。
+    pub fn numeric_user(&self) -> Option<(u32, Option<u32>)> {
+        let user = self.user.as_deref()?;
+        match user.split_once(':') {
+            Some((uid, gid)) => Some((uid.parse().ok()?, Some(gid.parse().ok()?))),
+            None => Some((user.parse().ok()?, None)),
+        }
+    }
+
+    /// 名前付きポート（`port ... name="http"`）を取得
+    pub fn port_by_name(&self, name: &str) -> Option<&Port> {
+        self.ports.iter().find(|p| p.name.as_deref() == Some(name))
+    }
+
     /// 他のServiceをマージする
     ///
     /// otherで定義されたフィールドが優先される（オーバーライド）。
@@ -383,6 +843,9 @@ impl Service {
         if other.service_type.is_some() {
             self.service_type = other.service_type;
         }
+        if other.extends.is_some() {
+            self.extends = other.extends;
+        }
         if other.image.is_some() {
             self.image = other.image;
         }
@@ -392,6 +855,15 @@ impl Service {
         if other.command.is_some() {
             self.command = other.command;
         }
+        if other.entrypoint.is_some() {
+            self.entrypoint = other.entrypoint;
+        }
+        if other.working_dir.is_some() {
+            self.working_dir = other.working_dir;
+        }
+        if other.hostname.is_some() {
+            self.hostname = other.hostname;
+        }
         if other.build.is_some() {
             self.build = other.build;
         }
@@ -413,6 +885,54 @@ impl Service {
         if other.registry.is_some() {
             self.registry = other.registry;
         }
+        if other.logging.is_some() {
+            self.logging = other.logging;
+        }
+        if other.user.is_some() {
+            self.user = other.user;
+        }
+        if other.container_name.is_some() {
+            self.container_name = other.container_name;
+        }
+        if other.enabled.is_some() {
+            self.enabled = other.enabled;
+        }
+        if other.when.is_some() {
+            self.when = other.when;
+        }
+        if other.read_only.is_some() {
+            self.read_only = other.read_only;
+        }
+        if other.privileged.is_some() {
+            self.privileged = other.privileged;
+        }
+        if other.network_mode.is_some() {
+            self.network_mode = other.network_mode;
+        }
+        if other.tty.is_some() {
+            self.tty = other.tty;
+        }
+        if other.stdin_open.is_some() {
+            self.stdin_open = other.stdin_open;
+        }
+        if other.inject_wait.is_some() {
+            self.inject_wait = other.inject_wait;
+        }
+        if other.inject_links.is_some() {
+            self.inject_links = other.inject_links;
+        }
+        if other.shm_size.is_some() {
+            self.shm_size = other.shm_size;
+        }
+        if other.stop_signal.is_some() {
+            self.stop_signal = other.stop_signal;
+        }
+        if other.stop_grace_period.is_some() {
+            self.stop_grace_period = other.stop_grace_period;
+        }
+        if other.test.is_some() {
+            self.test = other.test;
+        }
 
         // Vec<T>フィールド: otherが空でなければ上書き
         if !other.ports.is_empty() {
@@ -424,10 +944,93 @@ impl Service {
         if !other.depends_on.is_empty() {
             self.depends_on = other.depends_on;
         }
+        if !other.cap_add.is_empty() {
+            self.cap_add = other.cap_add;
+        }
+        if !other.cap_drop.is_empty() {
+            self.cap_drop = other.cap_drop;
+        }
+        if !other.security_opt.is_empty() {
+            self.security_opt = other.security_opt;
+        }
+        if !other.tmpfs.is_empty() {
+            self.tmpfs = other.tmpfs;
+        }
+        if !other.ulimits.is_empty() {
+            self.ulimits = other.ulimits;
+        }
+        if !other.configs.is_empty() {
+            self.configs = other.configs;
+        }
+        if !other.secrets.is_empty() {
+            self.secrets = other.secrets;
+        }
 
         // HashMap<K, V>フィールド: マージ（otherの値が優先）
         for (key, value) in other.environment {
             self.environment.insert(key, value);
         }
+        for (key, value) in other.sysctls {
+            self.sysctls.insert(key, value);
+        }
+        for (key, value) in other.labels {
+            self.labels.insert(key, value);
+        }
+
+        self.add_network_dependency();
+    }
+
+    /// 継承元サービス（`extends`）の設定を取り込む
+    ///
+    /// `merge` と異なり、リストは置き換えずに結合する（deep-merge）。
+    /// - Option<T>: 自身が None の場合のみ継承元の値を使用
+    /// - HashMap<K, V>: 継承元に自身の値をマージ（自身が優先）
+    /// - キーを持つリスト（ports / volumes / tmpfs / ulimits / configs / secrets）:
+    ///   同じキー（コンテナ側ポート、マウント先、リミット名）は自身の定義で置き換え、
+    ///   それ以外は継承元 → 自身の順に結合
+    /// - 値のリスト（depends_on / cap_add / cap_drop / security_opt）: 重複を除いて結合
+    pub fn inherit(&mut self, base: &Service) {
+        let mut child = std::mem::take(self);
+
+        child.ports = merge_keyed(&base.ports, child.ports, |p| {
+            (p.container, p.protocol.clone())
+        });
+        child.volumes = merge_keyed(&base.volumes, child.volumes, |v| v.container.clone());
+        child.tmpfs = merge_keyed(&base.tmpfs, child.tmpfs, |t| t.target.clone());
+        child.ulimits = merge_keyed(&base.ulimits, child.ulimits, |u| u.name.clone());
+        child.configs = merge_keyed(&base.configs, child.configs, |c| c.target.clone());
+        child.secrets = merge_keyed(&base.secrets, child.secrets, |s| s.target_path());
+        child.depends_on = merge_unique(&base.depends_on, child.depends_on);
+        child.cap_add = merge_unique(&base.cap_add, child.cap_add);
+        child.cap_drop = merge_unique(&base.cap_drop, child.cap_drop);
+        child.security_opt = merge_unique(&base.security_opt, child.security_opt);
+
+        let mut merged = base.clone();
+        // コンテナ名は一意でなければならないため継承しない
+        merged.container_name = None;
+        merged.merge(child);
+        *self = merged;
+    }
+}
+
+/// キーが一致する要素は `child` で置き換え、それ以外は `base` → `child` の順に結合
+fn merge_keyed<T: Clone, K: PartialEq>(base: &[T], child: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut result: Vec<T> = base
+        .iter()
+        .filter(|b| !child.iter().any(|c| key(c) == key(b)))
+        .cloned()
+        .collect();
+    result.extend(child);
+    result
+}
+
+/// 重複を除いて `base` → `child` の順に結合
+fn merge_unique(base: &[String], child: Vec<String>) -> Vec<String> {
+    let mut result = base.to_vec();
+    for item in child {
+        if !result.contains(&item) {
+            result.push(item);
+        }
     }
+    result
 }
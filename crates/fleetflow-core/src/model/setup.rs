@@ -0,0 +1,145 @@
+//! セットアップステップ（setup）定義
+
+use serde::{Deserialize, Serialize};
+
+/// `fleet up` の後に実行するユーザー定義ステップ（トップレベル `setup` ブロック）
+///
+/// `run` はプロジェクトルートをカレントディレクトリとして `sh -c` で実行される。
+///
+/// KDL形式：
+/// ```kdl
+/// setup {
+///     step "init-db" run="./scripts/init-db.sh" retries=3 retry_delay=5
+///     step "seed-data" run="./scripts/seed.sh" after="init-db" skip_if="test -f .seeded"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupStep {
+    /// ステップ名（第1引数）
+    pub name: String,
+    /// 実行するシェルコマンド
+    pub run: String,
+    /// 先に完了している必要があるステップ名
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// 失敗時の再試行回数（0 なら再試行しない）
+    #[serde(default)]
+    pub retries: u32,
+    /// 再試行までの待機秒数（未指定時は実行側の既定値）
+    #[serde(default)]
+    pub retry_delay: Option<u64>,
+    /// タイムアウト秒数
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// 成功（exit 0）したらステップをスキップする判定コマンド
+    #[serde(default)]
+    pub skip_if: Option<String>,
+    /// 実行対象のステージ（空なら全ステージ）
+    #[serde(default)]
+    pub stages: Vec<String>,
+}
+
+impl SetupStep {
+    /// 指定ステージで実行対象か
+    pub fn applies_to(&self, stage: &str) -> bool {
+        self.stages.is_empty() || self.stages.iter().any(|s| s == stage)
+    }
+}
+
+/// `after` を解決した実行順を返す（同順位は宣言順を保つ）
+///
+/// 未定義のステップ参照や循環があればエラーメッセージを返す。
+pub fn order_setup_steps(steps: &[SetupStep]) -> Result<Vec<&SetupStep>, String> {
+    for step in steps {
+        for dep in &step.after {
+            if !steps.iter().any(|s| &s.name == dep) {
+                return Err(format!(
+                    "setup step '{}' is after undefined step '{}'",
+                    step.name, dep
+                ));
+            }
+        }
+    }
+
+    let mut ordered: Vec<&SetupStep> = Vec::with_capacity(steps.len());
+    while ordered.len() < steps.len() {
+        let ready = steps.iter().find(|step| {
+            !ordered.iter().any(|done| done.name == step.name)
+                && step
+                    .after
+                    .iter()
+                    .all(|dep| ordered.iter().any(|done| &done.name == dep))
+        });
+        match ready {
+            Some(step) => ordered.push(step),
+            None => {
+                let mut pending: Vec<&str> = steps
+                    .iter()
+                    .filter(|s| !ordered.iter().any(|done| done.name == s.name))
+                    .map(|s| s.name.as_str())
+                    .collect();
+                pending.sort_unstable();
+                return Err(format!(
+                    "setup steps have a dependency cycle: {}",
+                    pending.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str, after: &[&str]) -> SetupStep {
+        SetupStep {
+            name: name.to_string(),
+            run: "true".to_string(),
+            after: after.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_order_setup_steps_respects_after() {
+        let steps = vec![
+            step("seed-data", &["init-db"]),
+            step("warm-cache", &[]),
+            step("init-db", &[]),
+        ];
+        let names: Vec<&str> = order_setup_steps(&steps)
+            .unwrap()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["warm-cache", "init-db", "seed-data"]);
+    }
+
+    #[test]
+    fn test_order_setup_steps_errors() {
+        let undefined = vec![step("seed-data", &["init-db"])];
+        assert!(
+            order_setup_steps(&undefined)
+                .unwrap_err()
+                .contains("undefined step 'init-db'")
+        );
+
+        let cycle = vec![step("a", &["b"]), step("b", &["a"])];
+        assert!(
+            order_setup_steps(&cycle)
+                .unwrap_err()
+                .contains("cycle: a, b")
+        );
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let mut s = step("seed", &[]);
+        assert!(s.applies_to("prod"));
+        s.stages = vec!["local".to_string()];
+        assert!(s.applies_to("local"));
+        assert!(!s.applies_to("prod"));
+    }
+}
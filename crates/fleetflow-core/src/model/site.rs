@@ -0,0 +1,56 @@
+//! 静的サイト定義（`site` ブロック）
+
+use serde::{Deserialize, Serialize};
+
+/// 静的サイト（ビルド成果物を R2 に配置してカスタムドメインで配信する）
+///
+/// ```kdl
+/// site "docs" {
+///     build "npm run build"        // 省略時はビルドしない
+///     dir "web"                    // ビルドを実行するディレクトリ
+///     output "web/dist"            // アップロードするディレクトリ
+///     domain "docs.{stage}.example.com"
+///     bucket "myapp-docs-{stage}"  // 省略時は <project>-<site>[-<stage>]
+/// }
+/// ```
+///
+/// `domain` / `bucket` の `{stage}` はデプロイ先のステージ名に置き換える。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteResource {
+    /// ビルドコマンド（シェルで実行）
+    #[serde(default)]
+    pub build: Option<String>,
+    /// ビルドを実行するディレクトリ（プロジェクトルートからの相対パス）
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// アップロードするディレクトリ（プロジェクトルートからの相対パス）
+    pub output: String,
+    /// 配信するドメイン（R2 バケットのカスタムドメインとして接続）
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// R2 バケット名
+    #[serde(default)]
+    pub bucket: Option<String>,
+}
+
+impl SiteResource {
+    /// R2 バケット名（未指定時は `<project>-<site>[-<stage>]`）
+    pub fn bucket_name(&self, project: &str, site: &str, stage: Option<&str>) -> String {
+        match &self.bucket {
+            Some(bucket) => render_stage(bucket, stage),
+            None => match stage {
+                Some(stage) => format!("{}-{}-{}", project, site, stage),
+                None => format!("{}-{}", project, site),
+            },
+        }
+    }
+
+    /// 配信するドメイン（`{stage}` を展開）
+    pub fn domain_name(&self, stage: Option<&str>) -> Option<String> {
+        self.domain.as_deref().map(|d| render_stage(d, stage))
+    }
+}
+
+fn render_stage(template: &str, stage: Option<&str>) -> String {
+    template.replace("{stage}", stage.unwrap_or_default())
+}
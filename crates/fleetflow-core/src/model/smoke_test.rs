@@ -0,0 +1,72 @@
+//! デプロイ後のスモークテスト（smoke_test）定義
+
+use serde::{Deserialize, Serialize};
+
+/// `fleet deploy` の最後に実行する HTTP チェック（ステージの `smoke_test` ブロック）
+///
+/// `timeout` 秒以内に `expect` のステータスが返るまで再試行し、返らなければデプロイを失敗にする。
+///
+/// KDL形式：
+/// ```kdl
+/// stage "prod" {
+///     smoke_test {
+///         http "https://api.example.com/health" expect=200 timeout="30s"
+///         http "https://example.com/" expect=200
+///         http service="web" port="http" path="/health"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmokeTest {
+    /// チェック対象
+    pub target: SmokeTarget,
+    /// 期待する HTTP ステータスコード
+    #[serde(default = "default_expect")]
+    pub expect: u16,
+    /// 全体タイムアウト（秒）
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+impl SmokeTest {
+    pub const DEFAULT_EXPECT: u16 = 200;
+    pub const DEFAULT_TIMEOUT: u64 = 30;
+    /// 再試行の間隔（秒）
+    pub const RETRY_INTERVAL: u64 = 2;
+}
+
+/// スモークテストのチェック対象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmokeTarget {
+    /// 固定の URL
+    Url(String),
+    /// サービスの名前付きポート（`port ... name="http"`）。URL はデプロイ時に解決する
+    Port {
+        service: String,
+        port: String,
+        /// URL のパス（`/` 始まり）
+        path: String,
+    },
+}
+
+impl std::fmt::Display for SmokeTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{}", url),
+            Self::Port {
+                service,
+                port,
+                path,
+            } => write!(f, "{}:{}{}", service, port, path),
+        }
+    }
+}
+
+fn default_expect() -> u16 {
+    SmokeTest::DEFAULT_EXPECT
+}
+
+fn default_timeout() -> u64 {
+    SmokeTest::DEFAULT_TIMEOUT
+}
@@ -1,5 +1,8 @@
 //! ステージ定義
 
+use super::log_shipping::LogShippingConfig;
+use super::smoke_test::SmokeTest;
+use crate::error::{FlowError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -20,15 +23,21 @@ pub enum Backend {
     Quadlet,
     /// Compose（`podman compose` / `docker compose`）。
     Compose,
+    /// Kubernetes クラスタ（Deployment/Service マニフェストを `kubectl apply`）。
+    Kubernetes,
+    /// HashiCorp Nomad（docker driver の job を `nomad job run`）。
+    Nomad,
 }
 
 impl Backend {
-    /// 文字列からパースする（KDL `backend "..."` ノード用）。
+    /// 文字列からパースする（KDL `backend "..."` / `target "..."` ノード用）。
     pub fn parse(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "docker" => Some(Self::Docker),
             "quadlet" => Some(Self::Quadlet),
             "compose" => Some(Self::Compose),
+            "kubernetes" | "k8s" => Some(Self::Kubernetes),
+            "nomad" => Some(Self::Nomad),
             _ => None,
         }
     }
@@ -39,6 +48,8 @@ impl Backend {
             Self::Docker => "docker",
             Self::Quadlet => "quadlet",
             Self::Compose => "compose",
+            Self::Kubernetes => "kubernetes",
+            Self::Nomad => "nomad",
         }
     }
 }
@@ -58,7 +69,146 @@ pub struct Stage {
     /// ステージ固有のコンテナレジストリURL（例: ghcr.io/owner）
     #[serde(default)]
     pub registry: Option<String>,
+    /// `registry` の認証情報を読む環境変数（未指定時は ~/.docker/config.json などを使う）
+    #[serde(default)]
+    pub registry_auth: Option<RegistryAuthEnv>,
+    /// ステージ固有のビルドイメージ名テンプレート（`Flow::image_template` より優先）
+    #[serde(default)]
+    pub image_template: Option<String>,
     /// 実行 backend。KDL `backend "quadlet"` で宣言。未宣言時は `Docker`。
     #[serde(default)]
     pub backend: Backend,
+    /// backend の接続先。KDL `target "nomad" address="http://nomad:4646"` で宣言。
+    /// 未宣言時は backend のクライアント既定（`NOMAD_ADDR` 等）に従う。
+    #[serde(default)]
+    pub backend_address: Option<String>,
+    /// ログ集約設定。宣言時は `log-shipper` サービスがステージに自動追加される
+    #[serde(default)]
+    pub log_shipping: Option<LogShippingConfig>,
+    /// `fleet deploy` の最後に実行するスモークテスト
+    #[serde(default)]
+    pub smoke_tests: Vec<SmokeTest>,
+    /// ステージ固有の DNS ドメイン（cloudflare provider の `domain` より優先）
+    #[serde(default)]
+    pub dns_domain: Option<String>,
+}
+
+/// レジストリの認証情報を読む環境変数
+///
+/// ```kdl
+/// stage "prod" {
+///     registry {
+///         url "ghcr.io/acme"
+///         username_env "GHCR_USER"
+///         password_env "GHCR_TOKEN"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryAuthEnv {
+    pub username_env: String,
+    pub password_env: String,
+}
+
+/// 環境変数から読んだレジストリの認証情報
+#[derive(Clone, PartialEq, Eq)]
+pub struct RegistryCredentials {
+    /// レジストリのホスト（例: `ghcr.io`）
+    pub registry: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryCredentials")
+            .field("registry", &self.registry)
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+impl Stage {
+    /// `registry` ブロックで宣言した認証情報（build / push / pull で使う）
+    ///
+    /// 環境変数を宣言していなければ `None`。宣言した環境変数が未設定ならエラー。
+    pub fn registry_credentials(&self) -> Result<Option<RegistryCredentials>> {
+        let (Some(url), Some(auth)) = (&self.registry, &self.registry_auth) else {
+            return Ok(None);
+        };
+        let read = |name: &str, key: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    FlowError::InvalidConfig(format!(
+                        "registry '{}': environment variable '{}' ({}) is not set",
+                        url, name, key
+                    ))
+                })
+        };
+        Ok(Some(RegistryCredentials {
+            registry: registry_host(url).to_string(),
+            username: read(&auth.username_env, "username_env")?,
+            password: read(&auth.password_env, "password_env")?,
+        }))
+    }
+}
+
+/// レジストリ URL（`ghcr.io/acme`、`https://registry.example.com:5000`）のホスト部分
+pub fn registry_host(url: &str) -> &str {
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    url.split('/').next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_host() {
+        assert_eq!(registry_host("ghcr.io/acme"), "ghcr.io");
+        assert_eq!(
+            registry_host("https://registry.example.com:5000/team"),
+            "registry.example.com:5000"
+        );
+        assert_eq!(registry_host("localhost:5000"), "localhost:5000");
+    }
+
+    #[test]
+    fn test_registry_credentials_from_env() {
+        let stage = Stage {
+            registry: Some("ghcr.io/acme".to_string()),
+            registry_auth: Some(RegistryAuthEnv {
+                username_env: "STAGE_TEST_REGISTRY_USER".to_string(),
+                password_env: "STAGE_TEST_REGISTRY_TOKEN".to_string(),
+            }),
+            ..Default::default()
+        };
+        // SAFETY: テスト環境での環境変数操作（他のテストと競合しないユニークな変数名）
+        unsafe {
+            std::env::set_var("STAGE_TEST_REGISTRY_USER", "bot");
+            std::env::set_var("STAGE_TEST_REGISTRY_TOKEN", "secret");
+        }
+        let creds = stage.registry_credentials().unwrap().unwrap();
+        assert_eq!(creds.registry, "ghcr.io");
+        assert_eq!(creds.username, "bot");
+        assert_eq!(creds.password, "secret");
+        assert!(!format!("{:?}", creds).contains("secret"));
+
+        unsafe {
+            std::env::remove_var("STAGE_TEST_REGISTRY_TOKEN");
+        }
+        let err = stage.registry_credentials().unwrap_err().to_string();
+        assert!(err.contains("STAGE_TEST_REGISTRY_TOKEN"));
+        unsafe {
+            std::env::remove_var("STAGE_TEST_REGISTRY_USER");
+        }
+
+        assert!(Stage::default().registry_credentials().unwrap().is_none());
+    }
 }
@@ -1,7 +1,10 @@
 //! クラウドリソースノードのパース
 
 use crate::error::{FlowError, Result};
-use crate::model::{CloudProvider, ServerResource};
+use crate::model::{
+    CloudProvider, CredentialRef, DnsRecordDef, DnsRecordOptions, DnsZone, HostService,
+    ServerResource,
+};
 use kdl::KdlNode;
 
 /// provider ノードをパース
@@ -28,6 +31,59 @@ pub fn parse_provider(node: &KdlNode) -> Result<(String, CloudProvider)> {
                         .and_then(|e| e.value().as_string())
                         .map(|s| s.to_string());
                 }
+                "zone_id" | "zone-id" => {
+                    provider.zone_id = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "domain" => {
+                    provider.domain = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "api_token" | "api-token" => {
+                    provider.api_token = Some(parse_credential_ref(child)?);
+                }
+                "dns" => {
+                    // dnsブロックをパース（zone, subdomainを含む）
+                    if let Some(dns_children) = child.children() {
+                        for dns_child in dns_children.nodes() {
+                            let value = dns_child
+                                .entries()
+                                .first()
+                                .and_then(|e| e.value().as_string())
+                                .map(|s| s.to_string());
+                            match dns_child.name().value() {
+                                "zone" => {
+                                    let domain = value.ok_or_else(|| {
+                                        FlowError::InvalidConfig(
+                                            "dns zone requires a domain: zone \"example.com\""
+                                                .to_string(),
+                                        )
+                                    })?;
+                                    provider.dns_zones.push(DnsZone {
+                                        domain,
+                                        zone_id: dns_child
+                                            .get("id")
+                                            .and_then(|v| v.as_string())
+                                            .map(|s| s.to_string()),
+                                    });
+                                }
+                                "subdomain" => provider.subdomain_template = value,
+                                other => {
+                                    return Err(FlowError::InvalidConfig(format!(
+                                        "unknown node in provider dns block: {} (expected zone / subdomain)",
+                                        other
+                                    )));
+                                }
+                            }
+                        }
+                    }
+                }
                 // 追加設定はconfigに保存
                 other => {
                     if let Some(value) = child.entries().first().and_then(|e| e.value().as_string())
@@ -42,6 +98,20 @@ pub fn parse_provider(node: &KdlNode) -> Result<(String, CloudProvider)> {
     Ok((name, provider))
 }
 
+/// `env="NAME"` / `secret="key"` 形式の認証情報参照をパース
+fn parse_credential_ref(node: &KdlNode) -> Result<CredentialRef> {
+    let env = node.get("env").and_then(|v| v.as_string());
+    let secret = node.get("secret").and_then(|v| v.as_string());
+    match (env, secret) {
+        (Some(env), None) => Ok(CredentialRef::Env(env.to_string())),
+        (None, Some(secret)) => Ok(CredentialRef::Secret(secret.to_string())),
+        _ => Err(FlowError::InvalidConfig(format!(
+            "{} requires exactly one of env=\"NAME\" or secret=\"key\"",
+            node.name().value()
+        ))),
+    }
+}
+
 /// server ノードをパース
 pub fn parse_server(node: &KdlNode) -> Result<(String, ServerResource)> {
     let name = node
@@ -166,11 +236,47 @@ pub fn parse_server(node: &KdlNode) -> Result<(String, ServerResource)> {
                                         })
                                         .collect();
                                 }
+                                "alias" => {
+                                    // 例: alias "www" proxied=#true ttl=120
+                                    let alias = first_string(dns_child).ok_or_else(|| {
+                                        FlowError::InvalidConfig(
+                                            "dns alias requires a name: alias \"www\"".to_string(),
+                                        )
+                                    })?;
+                                    server
+                                        .dns_alias_options
+                                        .insert(alias.clone(), parse_record_options(dns_child)?);
+                                    if !server.dns_aliases.contains(&alias) {
+                                        server.dns_aliases.push(alias);
+                                    }
+                                }
+                                "ttl" => {
+                                    server.dns_options.ttl = Some(parse_ttl(
+                                        dns_child,
+                                        dns_child.entries().first().map(|e| e.value()),
+                                    )?);
+                                }
+                                "proxied" => {
+                                    server.dns_options.proxied = dns_child
+                                        .entries()
+                                        .first()
+                                        .and_then(|e| e.value().as_bool());
+                                }
+                                "record" => {
+                                    server.dns_records.push(parse_record_def(dns_child)?);
+                                }
                                 _ => {}
                             }
                         }
                     }
                 }
+                "domain" => {
+                    server.domain = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
                 "deploy_path" | "deploy-path" => {
                     server.deploy_path = child
                         .entries()
@@ -192,6 +298,21 @@ pub fn parse_server(node: &KdlNode) -> Result<(String, ServerResource)> {
                         .and_then(|e| e.value().as_string())
                         .map(|s| s.to_string());
                 }
+                // ステージごとの有効・無効
+                "enabled" => {
+                    server.enabled = child.entries().first().and_then(|e| e.value().as_bool());
+                }
+                "when" => {
+                    server.when = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "host_service" | "host-service" => {
+                    let (name, host_service) = parse_host_service(child)?;
+                    server.host_services.insert(name, host_service);
+                }
                 // 追加設定はconfigに保存
                 other => {
                     if let Some(value) = child.entries().first().and_then(|e| e.value().as_string())
@@ -206,6 +327,139 @@ pub fn parse_server(node: &KdlNode) -> Result<(String, ServerResource)> {
     Ok((name, server))
 }
 
+fn first_string(node: &KdlNode) -> Option<String> {
+    node.entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+        .map(|s| s.to_string())
+}
+
+/// TTL をパース（1 = 自動、それ以外は 60〜86400 秒）
+fn parse_ttl(node: &KdlNode, value: Option<&kdl::KdlValue>) -> Result<u32> {
+    let ttl = value.and_then(|v| v.as_integer()).ok_or_else(|| {
+        FlowError::InvalidConfig(format!("{}: ttl must be an integer", node.name().value()))
+    })?;
+    if ttl == 1 || (60..=86400).contains(&ttl) {
+        Ok(ttl as u32)
+    } else {
+        Err(FlowError::InvalidConfig(format!(
+            "{}: ttl must be 1 (automatic) or between 60 and 86400 seconds, got {}",
+            node.name().value(),
+            ttl
+        )))
+    }
+}
+
+/// `ttl=` / `proxied=` プロパティをパース
+fn parse_record_options(node: &KdlNode) -> Result<DnsRecordOptions> {
+    let ttl = node
+        .get("ttl")
+        .map(|value| parse_ttl(node, Some(value)))
+        .transpose()?;
+    Ok(DnsRecordOptions {
+        ttl,
+        proxied: node.get("proxied").and_then(|v| v.as_bool()),
+    })
+}
+
+/// dns 内の record ノードをパース
+///
+/// 例: `record "TXT" "google-site-verification=..." name="_verify"`
+fn parse_record_def(node: &KdlNode) -> Result<DnsRecordDef> {
+    let mut args = node
+        .entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .filter_map(|e| e.value().as_string());
+    let (Some(record_type), Some(content)) = (args.next(), args.next()) else {
+        return Err(FlowError::InvalidConfig(
+            "dns record requires a type and a value: record \"TXT\" \"...\"".to_string(),
+        ));
+    };
+    let record_type = record_type.to_ascii_uppercase();
+    if !matches!(record_type.as_str(), "A" | "AAAA" | "CNAME" | "TXT") {
+        return Err(FlowError::InvalidConfig(format!(
+            "unsupported dns record type: {} (expected A / AAAA / CNAME / TXT)",
+            record_type
+        )));
+    }
+    Ok(DnsRecordDef {
+        record_type,
+        name: node
+            .get("name")
+            .and_then(|v| v.as_string())
+            .map(|s| s.to_string()),
+        content: content.to_string(),
+        options: parse_record_options(node)?,
+    })
+}
+
+/// server 内の host_service ノードをパース
+///
+/// 例: `host_service "cloudflared" { exec_start "/usr/bin/cloudflared tunnel run" }`
+fn parse_host_service(node: &KdlNode) -> Result<(String, HostService)> {
+    let name = node
+        .entries()
+        .first()
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| FlowError::InvalidConfig("host_service requires a name".to_string()))?
+        .to_string();
+
+    let mut host_service = HostService::default();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            let value = child
+                .entries()
+                .first()
+                .and_then(|e| e.value().as_string())
+                .map(|s| s.to_string());
+            match child.name().value() {
+                "description" => host_service.description = value,
+                "exec_start" | "exec-start" => {
+                    host_service.exec_start = value.unwrap_or_default();
+                }
+                "user" => host_service.user = value,
+                "working_directory" | "working-directory" => {
+                    host_service.working_directory = value;
+                }
+                "restart" => host_service.restart = value,
+                "after" => {
+                    host_service.after = child
+                        .entries()
+                        .iter()
+                        .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+                        .collect();
+                }
+                "environment" | "env" => {
+                    if let Some(vars) = child.children() {
+                        for var in vars.nodes() {
+                            if let Some(value) =
+                                var.entries().first().and_then(|e| e.value().as_string())
+                            {
+                                host_service
+                                    .environment
+                                    .insert(var.name().value().to_string(), value.to_string());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if host_service.exec_start.is_empty() {
+        return Err(FlowError::InvalidConfig(format!(
+            "host_service '{}' requires exec_start",
+            name
+        )));
+    }
+
+    Ok((name, host_service))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,6 +479,156 @@ mod tests {
         assert_eq!(provider.zone, Some("tk1a".to_string()));
     }
 
+    #[test]
+    fn test_parse_provider_cloudflare_dns() {
+        let kdl = r#"
+            provider "cloudflare" {
+                zone-id "0123abcd"
+                domain "example.com"
+                api-token secret="cloudflare_token"
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let (_, provider) = parse_provider(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(provider.zone_id.as_deref(), Some("0123abcd"));
+        assert_eq!(provider.domain.as_deref(), Some("example.com"));
+        assert_eq!(
+            provider.api_token,
+            Some(CredentialRef::Secret("cloudflare_token".to_string()))
+        );
+
+        let doc: kdl::KdlDocument = r#"provider "cloudflare" { api_token "raw-token" }"#
+            .parse()
+            .unwrap();
+        assert!(parse_provider(doc.nodes().first().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_provider_dns_zones() {
+        let kdl = r#"
+            provider "cloudflare" {
+                dns {
+                    zone "example.com" id="0123abcd"
+                    zone "internal.example.net"
+                    subdomain "{service}.{stage}"
+                }
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let (_, provider) = parse_provider(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(
+            provider.dns_zones,
+            vec![
+                DnsZone {
+                    domain: "example.com".to_string(),
+                    zone_id: Some("0123abcd".to_string()),
+                },
+                DnsZone {
+                    domain: "internal.example.net".to_string(),
+                    zone_id: None,
+                },
+            ]
+        );
+        assert_eq!(
+            provider.subdomain_template.as_deref(),
+            Some("{service}.{stage}")
+        );
+
+        let doc: kdl::KdlDocument = r#"server "db-1" { domain "internal.example.net" }"#
+            .parse()
+            .unwrap();
+        let (_, server) = parse_server(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(server.domain.as_deref(), Some("internal.example.net"));
+    }
+
+    #[test]
+    fn test_parse_server_dns_record_options() {
+        let kdl = r#"
+            server "web-1" {
+                dns {
+                    ttl 300
+                    aliases "app"
+                    alias "www" proxied=#true
+                    record "aaaa" "2001:db8::1"
+                    record "TXT" "verify=abc" name="_verify" ttl=3600
+                }
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let (_, server) = parse_server(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(server.dns_aliases, vec!["app", "www"]);
+        assert_eq!(server.alias_options("app").ttl, Some(300));
+        assert_eq!(
+            server.alias_options("www"),
+            DnsRecordOptions {
+                ttl: None,
+                proxied: Some(true)
+            }
+        );
+        assert_eq!(server.dns_records[0].record_type, "AAAA");
+        assert_eq!(server.dns_records[0].name, None);
+        assert_eq!(server.dns_records[1].name.as_deref(), Some("_verify"));
+        assert_eq!(server.dns_records[1].options.ttl, Some(3600));
+
+        for invalid in [
+            r#"server "s" { dns { ttl 5 } }"#,
+            r#"server "s" { dns { record "MX" "mail.example.com" } }"#,
+            r#"server "s" { dns { record "TXT" } }"#,
+        ] {
+            let doc: kdl::KdlDocument = invalid.parse().unwrap();
+            assert!(
+                parse_server(doc.nodes().first().unwrap()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_server_host_service() {
+        let kdl = r#"
+            server "edge-1" {
+                provider "sakura-cloud"
+                host_service "cloudflared" {
+                    description "Cloudflare Tunnel"
+                    exec_start "/usr/bin/cloudflared tunnel run"
+                    after "network-online.target"
+                    environment {
+                        TUNNEL_TOKEN "xxx"
+                    }
+                }
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, server) = parse_server(node).unwrap();
+        let host_service = &server.host_services["cloudflared"];
+        assert_eq!(host_service.exec_start, "/usr/bin/cloudflared tunnel run");
+        assert_eq!(
+            host_service.description.as_deref(),
+            Some("Cloudflare Tunnel")
+        );
+        assert_eq!(host_service.after, vec!["network-online.target"]);
+        assert_eq!(host_service.environment["TUNNEL_TOKEN"], "xxx");
+        assert!(!server.config.contains_key("host_service"));
+    }
+
+    #[test]
+    fn test_parse_host_service_requires_exec_start() {
+        let kdl = r#"
+            server "edge-1" {
+                host_service "node-exporter" {
+                    user "nobody"
+                }
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        assert!(parse_server(node).is_err());
+    }
+
     #[test]
     fn test_parse_server() {
         let kdl = r#"
@@ -248,6 +652,28 @@ mod tests {
         assert_eq!(server.ssh_keys.len(), 2);
     }
 
+    #[test]
+    fn test_server_docker_host() {
+        let kdl = r#"
+            server "build-box" {
+                provider "sakura-cloud"
+                ssh_host "10.0.0.5"
+                ssh_user "deploy"
+            }
+        "#;
+        let doc: kdl::KdlDocument = kdl.parse().unwrap();
+        let (_, server) = parse_server(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(
+            server.docker_host(),
+            Some("ssh://deploy@10.0.0.5".to_string())
+        );
+        assert_eq!(server.ssh_target(), Some("deploy@10.0.0.5".to_string()));
+
+        let server = ServerResource::with_provider("sakura-cloud");
+        assert_eq!(server.docker_host(), None);
+        assert_eq!(server.ssh_target(), None);
+    }
+
     #[test]
     fn test_parse_server_with_dns_aliases() {
         let kdl = r#"
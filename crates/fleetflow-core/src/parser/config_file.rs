@@ -0,0 +1,106 @@
+//! configs / config ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{ConfigFile, ConfigMount};
+use kdl::KdlNode;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// トップレベル `configs` ブロックをパース
+///
+/// ```kdl
+/// configs {
+///     config "nginx.conf" file="./config/nginx.conf"
+///     config "app.env" content="PORT=3000"
+///     config "motd" {
+///         content "welcome"
+///     }
+/// }
+/// ```
+pub fn parse_configs(node: &KdlNode) -> Result<HashMap<String, ConfigFile>> {
+    let mut configs = HashMap::new();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if child.name().value() != "config" {
+                continue;
+            }
+            let name = child
+                .entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string())
+                .ok_or_else(|| FlowError::InvalidConfig("config requires a name".to_string()))?
+                .to_string();
+
+            let mut config = ConfigFile {
+                file: child
+                    .get("file")
+                    .and_then(|v| v.as_string())
+                    .map(PathBuf::from),
+                content: child
+                    .get("content")
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.to_string()),
+            };
+
+            if let Some(grandchildren) = child.children() {
+                for field in grandchildren.nodes() {
+                    let value = field.entries().first().and_then(|e| e.value().as_string());
+                    match field.name().value() {
+                        "file" => config.file = value.map(PathBuf::from),
+                        "content" => config.content = value.map(|s| s.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+
+            match (&config.file, &config.content) {
+                (Some(_), Some(_)) => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "config '{name}': file and content are mutually exclusive"
+                    )));
+                }
+                (None, None) => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "config '{name}' requires file or content"
+                    )));
+                }
+                _ => {}
+            }
+
+            configs.insert(name, config);
+        }
+    }
+
+    Ok(configs)
+}
+
+/// サービス内の `config` ノードをパース
+///
+/// ```kdl
+/// config "nginx.conf" target="/etc/nginx/nginx.conf"
+/// ```
+pub fn parse_config_mount(service_name: &str, node: &KdlNode) -> Result<ConfigMount> {
+    let source = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!("service '{service_name}': config requires a name"))
+        })?;
+    let target = node
+        .get("target")
+        .and_then(|v| v.as_string())
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!(
+                "service '{service_name}': config '{source}' requires target"
+            ))
+        })?;
+
+    Ok(ConfigMount {
+        source: source.to_string(),
+        target: PathBuf::from(target),
+    })
+}
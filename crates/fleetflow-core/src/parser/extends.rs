@@ -0,0 +1,65 @@
+//! service extends の解決
+
+use crate::error::{FlowError, Result};
+use crate::model::Service;
+use std::collections::HashMap;
+
+/// `extends` を持つサービスに継承元の設定を取り込む
+///
+/// 継承は多段（a → b → c）でもよい。未定義の継承元と循環参照はエラー。
+pub fn resolve_extends(services: &mut HashMap<String, Service>) -> Result<()> {
+    if services.values().all(|s| s.extends.is_none()) {
+        return Ok(());
+    }
+
+    let mut resolved = HashMap::new();
+    let mut names: Vec<String> = services.keys().cloned().collect();
+    names.sort();
+    for name in &names {
+        resolve_one(name, services, &mut resolved, &mut Vec::new())?;
+    }
+
+    *services = resolved;
+    Ok(())
+}
+
+fn resolve_one(
+    name: &str,
+    services: &HashMap<String, Service>,
+    resolved: &mut HashMap<String, Service>,
+    chain: &mut Vec<String>,
+) -> Result<Service> {
+    if let Some(service) = resolved.get(name) {
+        return Ok(service.clone());
+    }
+    if chain.iter().any(|n| n == name) {
+        chain.push(name.to_string());
+        return Err(FlowError::InvalidConfig(format!(
+            "circular service extends: {}",
+            chain.join(" -> ")
+        )));
+    }
+
+    let service = &services[name];
+    let result = match &service.extends {
+        None => service.clone(),
+        Some(base_name) => {
+            if !services.contains_key(base_name) {
+                return Err(FlowError::InvalidConfig(format!(
+                    "service '{}' extends undefined service '{}'",
+                    name, base_name
+                )));
+            }
+            chain.push(name.to_string());
+            let base = resolve_one(base_name, services, resolved, chain)?;
+            chain.pop();
+
+            let mut service = service.clone();
+            service.inherit(&base);
+            service
+        }
+    };
+
+    resolved.insert(name.to_string(), result.clone());
+    Ok(result)
+}
@@ -0,0 +1,116 @@
+//! 依存サービスの接続先の env 注入 — `inject_links #true`
+//!
+//! `depends_on` の各サービスについて、ネットワークエイリアスとコンテナポートから
+//! `<SERVICE>_HOST` / `<SERVICE>_PORT_<NAME>` / `<SERVICE>_PORT` を生成する。
+//! 同名の env を明示していればそちらを優先する。
+
+use crate::model::Service;
+use crate::reference::service_host;
+use std::collections::HashMap;
+
+/// 環境変数名に使えるよう大文字化し、英数字以外を `_` に置き換える
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 依存サービスの接続先を表す環境変数
+fn link_env(name: &str, target: &Service) -> Vec<(String, String)> {
+    let prefix = env_name(name);
+    let mut env = vec![(format!("{}_HOST", prefix), service_host(name, target))];
+    if let Some(first) = target.ports.first() {
+        env.push((format!("{}_PORT", prefix), first.container.to_string()));
+    }
+    for port in &target.ports {
+        let suffix = match &port.name {
+            Some(port_name) => env_name(port_name),
+            None => port.container.to_string(),
+        };
+        env.push((
+            format!("{}_PORT_{}", prefix, suffix),
+            port.container.to_string(),
+        ));
+    }
+    env
+}
+
+/// `inject_links` が有効なサービスに依存先の接続情報を注入する
+pub fn inject_links(services: &mut HashMap<String, Service>) {
+    if services.values().all(|s| s.inject_links != Some(true)) {
+        return;
+    }
+
+    let snapshot = services.clone();
+    for service in services.values_mut() {
+        if service.inject_links != Some(true) {
+            continue;
+        }
+        for dependency in &service.depends_on {
+            // 未定義の依存先は validate_stage が報告する
+            let Some(target) = snapshot.get(dependency) else {
+                continue;
+            };
+            for (key, value) in link_env(dependency, target) {
+                service.environment.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_kdl_string;
+
+    #[test]
+    fn test_inject_links() {
+        let flow = parse_kdl_string(
+            r#"
+            service "auth-db" {
+                image "postgres:16"
+                ports {
+                    port host=15432 container=5432 name="pg"
+                    port host=19187 container=9187
+                }
+            }
+            service "cache" {
+                image "redis:7"
+                network_mode "host"
+            }
+            service "api" {
+                image "app:latest"
+                depends_on "auth-db" "cache"
+                inject_links
+                env {
+                    CACHE_HOST "redis.internal"
+                }
+            }
+            service "worker" {
+                image "app:latest"
+                depends_on "auth-db"
+            }
+            "#,
+            "app".to_string(),
+        )
+        .unwrap();
+
+        let env = &flow.services["api"].environment;
+        assert_eq!(env["AUTH_DB_HOST"], "auth-db");
+        assert_eq!(env["AUTH_DB_PORT"], "5432");
+        assert_eq!(env["AUTH_DB_PORT_PG"], "5432");
+        assert_eq!(env["AUTH_DB_PORT_9187"], "9187");
+        // 明示した env が優先
+        assert_eq!(env["CACHE_HOST"], "redis.internal");
+        assert!(!env.contains_key("CACHE_PORT"));
+
+        // inject_links の無いサービスには注入しない
+        assert!(flow.services["worker"].environment.is_empty());
+    }
+}
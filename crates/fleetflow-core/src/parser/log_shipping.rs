@@ -0,0 +1,72 @@
+//! log_shipping ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{LogShippingAgent, LogShippingConfig, LogSinkKind};
+use kdl::KdlNode;
+
+/// log_shipping ノードをパース
+///
+/// プロパティ形式とブロック形式の両方をサポートし、
+/// 転送先ごとの必須項目（loki/http の endpoint、s3 の bucket）を検証する。
+pub fn parse_log_shipping(node: &KdlNode) -> Result<LogShippingConfig> {
+    let mut config = LogShippingConfig::default();
+
+    let mut fields: Vec<(String, String)> = node
+        .entries()
+        .iter()
+        .filter_map(|e| {
+            let key = e.name()?.value().to_string();
+            let value = e.value().as_string()?.to_string();
+            Some((key, value))
+        })
+        .collect();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if let Some(value) = child.entries().first().and_then(|e| e.value().as_string()) {
+                fields.push((child.name().value().to_string(), value.to_string()));
+            }
+        }
+    }
+
+    for (key, value) in fields {
+        match key.as_str() {
+            "agent" => {
+                config.agent = LogShippingAgent::parse(&value).ok_or_else(|| {
+                    FlowError::InvalidConfig(format!(
+                        "unknown log_shipping agent '{value}' (expected vector|fluent-bit)"
+                    ))
+                })?;
+            }
+            "sink" => {
+                config.sink = LogSinkKind::parse(&value).ok_or_else(|| {
+                    FlowError::InvalidConfig(format!(
+                        "unknown log_shipping sink '{value}' (expected loki|s3|http)"
+                    ))
+                })?;
+            }
+            "endpoint" => config.endpoint = Some(value),
+            "bucket" => config.bucket = Some(value),
+            "region" => config.region = Some(value),
+            "image" => config.image = Some(value),
+            _ => {}
+        }
+    }
+
+    match config.sink {
+        LogSinkKind::Loki | LogSinkKind::Http if config.endpoint.is_none() => {
+            return Err(FlowError::InvalidConfig(format!(
+                "log_shipping sink '{}' requires endpoint",
+                config.sink.as_str()
+            )));
+        }
+        LogSinkKind::S3 if config.bucket.is_none() => {
+            return Err(FlowError::InvalidConfig(
+                "log_shipping sink 's3' requires bucket".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    Ok(config)
+}
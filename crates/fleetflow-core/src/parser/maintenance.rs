@@ -0,0 +1,56 @@
+//! maintenance ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{MaintenanceConfig, PruneSchedule};
+use kdl::KdlNode;
+
+/// maintenance ノードをパース
+///
+/// プロパティ形式（`maintenance image_retention=5`）とブロック形式の両方をサポートする。
+pub fn parse_maintenance(node: &KdlNode) -> Result<MaintenanceConfig> {
+    let mut config = MaintenanceConfig::default();
+
+    let mut fields: Vec<(String, &kdl::KdlValue)> = node
+        .entries()
+        .iter()
+        .filter_map(|e| Some((e.name()?.value().to_string(), e.value())))
+        .collect();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if let Some(entry) = child.entries().first() {
+                fields.push((child.name().value().to_string(), entry.value()));
+            }
+        }
+    }
+
+    for (key, value) in fields {
+        match key.as_str() {
+            "image_retention" => {
+                let count = value
+                    .as_integer()
+                    .filter(|n| *n >= 1)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .ok_or_else(|| {
+                        FlowError::InvalidConfig(format!(
+                            "maintenance image_retention must be a positive integer (got {value})"
+                        ))
+                    })?;
+                config.image_retention = Some(count);
+            }
+            "prune_schedule" => {
+                config.prune_schedule = value
+                    .as_string()
+                    .and_then(PruneSchedule::parse)
+                    .ok_or_else(|| {
+                        FlowError::InvalidConfig(format!(
+                            "unknown maintenance prune_schedule {value} (expected always|daily|weekly|never)"
+                        ))
+                    })?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,38 @@
+//! mcp ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::McpConfig;
+use kdl::KdlNode;
+
+/// mcp ノードをパース
+///
+/// プロパティ形式（`mcp enable_destructive_tools=#false`）とブロック形式の両方をサポートする。
+pub fn parse_mcp(node: &KdlNode) -> Result<McpConfig> {
+    let mut config = McpConfig::default();
+
+    let mut fields: Vec<(String, &kdl::KdlValue)> = node
+        .entries()
+        .iter()
+        .filter_map(|e| Some((e.name()?.value().to_string(), e.value())))
+        .collect();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if let Some(entry) = child.entries().first() {
+                fields.push((child.name().value().to_string(), entry.value()));
+            }
+        }
+    }
+
+    for (key, value) in fields {
+        if key == "enable_destructive_tools" {
+            config.enable_destructive_tools = value.as_bool().ok_or_else(|| {
+                FlowError::InvalidConfig(format!(
+                    "mcp enable_destructive_tools must be a boolean (got {value})"
+                ))
+            })?;
+        }
+    }
+
+    Ok(config)
+}
@@ -4,32 +4,60 @@
 //! 各ノードタイプのパース処理はモジュールに分離されています。
 
 mod cloud;
+mod config_file;
+mod extends;
+mod links;
+mod log_shipping;
+mod maintenance;
+mod mcp;
 mod port;
+mod remote;
+mod schema;
+mod secret;
 mod service;
+mod setup;
+mod site;
+mod smoke_test;
 mod stage;
 mod tenant;
 mod volume;
 
 // 内部で使用するパース関数
 use cloud::parse_provider;
+use config_file::parse_configs;
+use extends::resolve_extends;
+use links::inject_links;
+use maintenance::parse_maintenance;
+use mcp::parse_mcp;
+use secret::parse_secrets;
 use service::parse_service;
+use setup::{parse_setup, validate_setup};
+use site::parse_site;
 use stage::parse_stage;
 use tenant::parse_tenant;
 
 // 外部クレートから再利用可能なパース関数
 pub use cloud::parse_server;
+pub use schema::{
+    UnknownKey, UnknownKeyKind, check_unknown_keys, export_json_schema, export_markdown, node_doc,
+    schema_child_nodes, schema_properties,
+};
 
+use crate::condition::apply_conditions;
 use crate::error::{FlowError, Result};
-use crate::model::{Flow, Service, TenantSpec};
+use crate::model::{CONTAINER_NAME_VARS, Flow, IMAGE_TEMPLATE_VARS, Service, TenantSpec};
+use crate::reference::resolve_service_references;
 use crate::template::{TemplateProcessor, extract_variables};
-use kdl::KdlDocument;
+use crate::upgrade::{CURRENT_SCHEMA_VERSION, declared_schema_version, deprecated_names};
+use kdl::{KdlDocument, KdlNode};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// KDLファイルをパースしてFlowを生成（include展開・変数展開対応）
 pub fn parse_kdl_file<P: AsRef<Path>>(path: P) -> Result<Flow> {
-    let mut visited = HashSet::new();
+    // 変数展開はファイル全体を結合してから行うため、include 時点では展開しない
+    let mut ctx = IncludeContext::new(None);
     let base_dir = path
         .as_ref()
         .parent()
@@ -37,7 +65,7 @@ pub fn parse_kdl_file<P: AsRef<Path>>(path: P) -> Result<Flow> {
         .to_path_buf();
 
     // include ディレクティブを再帰的に展開
-    let content = read_kdl_with_includes(path.as_ref(), &base_dir, &mut visited)?;
+    let content = read_kdl_with_includes(path.as_ref(), &base_dir, &mut ctx)?;
 
     let name = path
         .as_ref()
@@ -47,14 +75,44 @@ pub fn parse_kdl_file<P: AsRef<Path>>(path: P) -> Result<Flow> {
         .unwrap_or("unnamed")
         .to_string();
 
-    parse_kdl_with_variables(&content, name)
+    let mut flow = parse_kdl_with_variables(&content, name)?;
+    flow.warnings = ctx.warnings;
+    Ok(flow)
+}
+
+/// include 展開の状態
+struct IncludeContext<'a> {
+    /// 循環参照の検出用（ローカルは絶対パス、リモートは URL）
+    visited: HashSet<PathBuf>,
+    /// include したファイルを展開前にテンプレート展開するプロセッサ（loader 経由のとき）
+    processor: Option<&'a mut TemplateProcessor>,
+    /// 呼び出し側に返す警告
+    warnings: Vec<String>,
+}
+
+impl<'a> IncludeContext<'a> {
+    fn new(processor: Option<&'a mut TemplateProcessor>) -> Self {
+        Self {
+            visited: HashSet::new(),
+            processor,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// include したファイルの内容を、取り込み元と同じ変数でテンプレート展開する
+    fn render(&mut self, content: String, origin: &Path) -> Result<String> {
+        match self.processor.as_deref_mut() {
+            Some(processor) => processor.render_source(&content, origin),
+            None => Ok(content),
+        }
+    }
 }
 
 /// includeディレクティブを展開してKDLコンテンツを読み込む
 fn read_kdl_with_includes(
     path: &Path,
     base_dir: &Path,
-    visited: &mut HashSet<PathBuf>,
+    ctx: &mut IncludeContext,
 ) -> Result<String> {
     // 絶対パスに変換
     let abs_path = if path.is_absolute() {
@@ -70,57 +128,144 @@ fn read_kdl_with_includes(
     };
 
     // 循環参照チェック
-    if visited.contains(&abs_path) {
+    if ctx.visited.contains(&abs_path) {
         return Err(FlowError::InvalidConfig(format!(
             "Circular include detected: {}",
             abs_path.display()
         )));
     }
-    visited.insert(abs_path.clone());
+    ctx.visited.insert(abs_path.clone());
 
     // ファイルを読み込む
     let content = fs::read_to_string(&abs_path).map_err(|e| FlowError::IoError {
         path: abs_path.clone(),
         message: e.to_string(),
     })?;
+    let content = ctx.render(content, &abs_path)?;
+
+    let current_dir = abs_path.parent().unwrap_or(base_dir);
+    expand_includes(
+        &content,
+        &abs_path.display().to_string(),
+        Some(current_dir),
+        ctx,
+    )
+}
+
+/// テンプレート展開済みのファイル内容の include を展開する（loader 用）
+///
+/// include したファイルも `processor` で同じようにテンプレート展開してから取り込む。
+/// include が無ければ内容をそのまま返す。リモート include の警告は `warnings` に追加する。
+pub(crate) fn expand_rendered_includes(
+    content: &str,
+    path: &Path,
+    processor: &mut TemplateProcessor,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let has_include = content
+        .parse::<KdlDocument>()
+        .is_ok_and(|doc| doc.nodes().iter().any(|n| n.name().value() == "include"));
+    if !has_include {
+        return Ok(content.to_string());
+    }
+
+    let mut ctx = IncludeContext::new(Some(processor));
+    if let Ok(abs_path) = path.canonicalize() {
+        ctx.visited.insert(abs_path);
+    }
+    let expanded = expand_includes(
+        content,
+        &path.display().to_string(),
+        path.parent(),
+        &mut ctx,
+    );
+    warnings.append(&mut ctx.warnings);
+    expanded
+}
 
+/// URL の include を取得して展開する
+///
+/// リモートのファイルからはローカルパスを include できない（URL のみ）。
+fn read_remote_kdl_with_includes(
+    url: &str,
+    sha256: Option<&str>,
+    ctx: &mut IncludeContext,
+) -> Result<String> {
+    let key = PathBuf::from(url);
+    if ctx.visited.contains(&key) {
+        return Err(FlowError::InvalidConfig(format!(
+            "Circular include detected: {}",
+            url
+        )));
+    }
+    ctx.visited.insert(key.clone());
+
+    let content = remote::fetch(url, sha256, &mut ctx.warnings)?;
+    let content = ctx.render(content, &key)?;
+    expand_includes(&content, url, None, ctx)
+}
+
+/// KDLドキュメント中のincludeノードを展開する
+///
+/// `current_dir` が None（リモートのファイル）の場合、ローカルパスの include はエラー。
+fn expand_includes(
+    content: &str,
+    origin: &str,
+    current_dir: Option<&Path>,
+    ctx: &mut IncludeContext,
+) -> Result<String> {
     // KDLドキュメントをパースしてincludeノードを処理
-    let doc: KdlDocument = content.parse().map_err(|e| {
-        FlowError::InvalidConfig(format!("KDL parse error in {}: {}", abs_path.display(), e))
-    })?;
+    let doc: KdlDocument = content
+        .parse()
+        .map_err(|e| FlowError::InvalidConfig(format!("KDL parse error in {}: {}", origin, e)))?;
 
     let mut result = String::new();
-    let current_dir = abs_path.parent().unwrap_or(base_dir);
 
     for node in doc.nodes() {
         if node.name().value() == "include" {
-            if let Some(include_path) = node.entries().first().and_then(|e| e.value().as_string()) {
-                if include_path.contains('*') {
-                    // グロブパターンで展開
-                    let pattern = current_dir.join(include_path);
-                    let pattern_str = pattern.to_str().ok_or_else(|| {
-                        FlowError::InvalidConfig(format!(
-                            "Invalid include pattern: {}",
-                            include_path
-                        ))
-                    })?;
-
-                    for entry in glob::glob(pattern_str).map_err(|e| {
-                        FlowError::InvalidConfig(format!("Invalid glob pattern: {}", e))
-                    })? {
-                        let entry_path = entry
-                            .map_err(|e| FlowError::InvalidConfig(format!("Glob error: {}", e)))?;
-                        let included = read_kdl_with_includes(&entry_path, current_dir, visited)?;
-                        result.push_str(&included);
-                        result.push('\n');
-                    }
-                } else {
-                    // 単一ファイル
-                    let include_file = current_dir.join(include_path);
-                    let included = read_kdl_with_includes(&include_file, current_dir, visited)?;
+            let Some(include_path) = node.entries().first().and_then(|e| e.value().as_string())
+            else {
+                continue;
+            };
+
+            if remote::is_remote(include_path) {
+                // https URL（sha256 で固定するとキャッシュされる）
+                let sha256 = node.get("sha256").and_then(|v| v.as_string());
+                let included = read_remote_kdl_with_includes(include_path, sha256, ctx)?;
+                result.push_str(&included);
+                result.push('\n');
+                continue;
+            }
+
+            let current_dir = current_dir.ok_or_else(|| {
+                FlowError::InvalidConfig(format!(
+                    "Remote file {} cannot include local path '{}'",
+                    origin, include_path
+                ))
+            })?;
+
+            if include_path.contains('*') {
+                // グロブパターンで展開
+                let pattern = current_dir.join(include_path);
+                let pattern_str = pattern.to_str().ok_or_else(|| {
+                    FlowError::InvalidConfig(format!("Invalid include pattern: {}", include_path))
+                })?;
+
+                for entry in glob::glob(pattern_str)
+                    .map_err(|e| FlowError::InvalidConfig(format!("Invalid glob pattern: {}", e)))?
+                {
+                    let entry_path = entry
+                        .map_err(|e| FlowError::InvalidConfig(format!("Glob error: {}", e)))?;
+                    let included = read_kdl_with_includes(&entry_path, current_dir, ctx)?;
                     result.push_str(&included);
                     result.push('\n');
                 }
+            } else {
+                // 単一ファイル
+                let include_file = current_dir.join(include_path);
+                let included = read_kdl_with_includes(&include_file, current_dir, ctx)?;
+                result.push_str(&included);
+                result.push('\n');
             }
         } else {
             // include以外のノードはそのまま保持
@@ -140,6 +285,7 @@ fn parse_kdl_with_variables(content: &str, default_name: String) -> Result<Flow>
     // 2. 変数がある場合はテンプレート展開
     let expanded = if !variables.is_empty() {
         let mut processor = TemplateProcessor::new();
+        processor.defer_references(None);
         processor.add_variables(variables);
         processor.add_env_variables();
         processor.render_str(content)?
@@ -166,6 +312,7 @@ pub fn parse_kdl_string_with_stage(
     let variables = extract_variables(content)?;
     let expanded = if !variables.is_empty() {
         let mut processor = TemplateProcessor::new();
+        processor.defer_references(target_stage);
         processor.add_variables(variables);
         processor.add_env_variables();
         processor.render_str(content)?
@@ -188,15 +335,69 @@ fn parse_kdl_string_raw_with_stage(
 ) -> Result<Flow> {
     let doc: KdlDocument = content.parse()?;
 
+    // strict #true が宣言されていれば未知のキーをエラーにする
+    if schema::is_strict(&doc) {
+        let issues = schema::check_document(&doc);
+        if !issues.is_empty() {
+            return Err(FlowError::InvalidConfig(format!(
+                "strict mode: unknown keys found\n{}",
+                issues
+                    .iter()
+                    .map(|issue| format!("  - {}", issue))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )));
+        }
+    }
+
+    // schema "N" の宣言を確認（未宣言は現行として扱う）
+    match declared_schema_version(&doc)? {
+        Some(version) if version > CURRENT_SCHEMA_VERSION => {
+            return Err(FlowError::InvalidConfig(format!(
+                "schema \"{version}\" is newer than this fleet supports (up to \"{CURRENT_SCHEMA_VERSION}\")\n\
+                 Hint: run `fleet self-update` to upgrade fleet"
+            )));
+        }
+        Some(version) if version < CURRENT_SCHEMA_VERSION => {
+            eprintln!(
+                "Warning: schema \"{version}\" is deprecated (current: \"{CURRENT_SCHEMA_VERSION}\").\n\
+                 Hint: run `fleet upgrade-config` to migrate."
+            );
+        }
+        _ => {}
+    }
+
+    // 非推奨の名前は別名として読み込むが、移行を促す
+    let deprecated = deprecated_names(&doc);
+    if !deprecated.is_empty() {
+        eprintln!(
+            "Warning: deprecated keys found (still accepted):\n{}\n\
+             Hint: run `fleet upgrade-config` to rewrite them.",
+            deprecated
+                .iter()
+                .map(|r| format!("  - {} (use '{}')", r.location, r.to))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
     let mut stages = HashMap::new();
     let mut services: HashMap<String, Service> = HashMap::new();
     let mut stage_service_overrides: HashMap<String, HashMap<String, Service>> = HashMap::new();
     let mut providers = HashMap::new();
     let mut servers = HashMap::new();
+    let mut sites = HashMap::new();
     let mut variables: HashMap<String, String> = HashMap::new();
     let mut name = default_name;
     let mut registry: Option<String> = None;
     let mut tenant: Option<TenantSpec> = None;
+    let mut configs = HashMap::new();
+    let mut secrets = HashMap::new();
+    let mut setup = Vec::new();
+    let mut image_template: Option<String> = None;
+    let mut container_name_template: Option<String> = None;
+    let mut maintenance = None;
+    let mut mcp = None;
 
     for node in doc.nodes() {
         match node.name().value() {
@@ -234,6 +435,10 @@ fn parse_kdl_string_raw_with_stage(
                 let (server_name, server) = parse_server(node)?;
                 servers.insert(server_name, server);
             }
+            "site" => {
+                let (site_name, site) = parse_site(node)?;
+                sites.insert(site_name, site);
+            }
             "include" => {
                 // parse_kdl_file() 経由の場合は read_kdl_with_includes() で既に展開済み
                 // parse_kdl_string() 直接呼び出しの場合はスキップ
@@ -264,6 +469,48 @@ fn parse_kdl_string_raw_with_stage(
                 // (last-wins、 同 file 内に複数あれば最後のものが採用される)
                 tenant = Some(parse_tenant(node)?);
             }
+            "configs" => {
+                // 名前付き設定ファイル（複数ブロックはマージ、同名は後勝ち）
+                configs.extend(parse_configs(node)?);
+            }
+            "strict" => {
+                // strict モード（上で検証済み）
+            }
+            "schema" => {
+                // スキーマバージョン宣言（上で検証済み）
+            }
+            "secrets" => {
+                // 名前付きシークレット（複数ブロックはマージ、同名は後勝ち）
+                secrets.extend(parse_secrets(node)?);
+            }
+            "setup" => {
+                // セットアップステップ（複数ブロックは宣言順に連結）
+                setup.extend(parse_setup(node)?);
+            }
+            "image_template" => {
+                // ビルドイメージ名のテンプレート
+                image_template = Some(parse_name_template(
+                    node,
+                    "image_template",
+                    IMAGE_TEMPLATE_VARS,
+                )?);
+            }
+            "container_name_template" => {
+                // コンテナ名のテンプレート
+                container_name_template = Some(parse_name_template(
+                    node,
+                    "container_name_template",
+                    CONTAINER_NAME_VARS,
+                )?);
+            }
+            "maintenance" => {
+                // デプロイ先サーバーのイメージ保持・削除ポリシー
+                maintenance = Some(parse_maintenance(node)?);
+            }
+            "mcp" => {
+                // MCP サーバーの設定
+                mcp = Some(parse_mcp(node)?);
+            }
             _ => {
                 // 不明なノードはスキップ（projectなどの追加ノードも許可）
             }
@@ -283,6 +530,50 @@ fn parse_kdl_string_raw_with_stage(
         }
     }
 
+    // extends で指定された継承元の設定を取り込む（ステージオーバーライド適用後）
+    resolve_extends(&mut services)?;
+
+    // port() / service_host() / project / stage の参照を解決（サービス定義がそろった後）
+    resolve_service_references(&mut services, &name, target_stage)?;
+
+    // inject_links: depends_on の接続先を env に注入（参照解決後の ports を使う）
+    inject_links(&mut services);
+
+    // サービスが参照する config / secret が宣言されているか検証
+    for (service_name, service) in &services {
+        for mount in &service.configs {
+            if !configs.contains_key(&mount.source) {
+                return Err(FlowError::InvalidConfig(format!(
+                    "service '{}' references undefined config '{}'",
+                    service_name, mount.source
+                )));
+            }
+        }
+        for mount in &service.secrets {
+            if !secrets.contains_key(&mount.source) {
+                return Err(FlowError::InvalidConfig(format!(
+                    "service '{}' references undefined secret '{}'",
+                    service_name, mount.source
+                )));
+            }
+        }
+    }
+
+    validate_setup(&setup)?;
+
+    // enabled / when が偽のサービス・サーバーをステージから外す
+    apply_conditions(&name, &mut stages, &services, &servers)?;
+
+    // container_name を持たないサービスにはプロジェクトのテンプレートを適用する
+    // （コンテナ設定への変換はサービス定義だけで名前を決められるように）
+    if let Some(template) = &container_name_template {
+        for service in services.values_mut() {
+            service
+                .container_name
+                .get_or_insert_with(|| template.clone());
+        }
+    }
+
     // Note: imageのバリデーションはstageフィルタリング後に行う
     // （buildのみ指定されたサービスがstageに含まれない場合のエラーを防ぐため）
 
@@ -292,11 +583,58 @@ fn parse_kdl_string_raw_with_stage(
         services,
         providers,
         servers,
+        sites,
         registry,
         variables,
         tenant,
+        configs,
+        secrets,
+        setup,
+        image_template,
+        container_name_template,
+        maintenance,
+        mcp,
+        warnings: Vec::new(),
     })
 }
 
+/// `image_template` / `container_name_template` ノードの値を取得し、変数名を検証する
+///
+/// 変数は `{name}`（Tera の `{% raw %}` で囲んだ `{{name}}` も可）。
+/// 値は build 時の resolver やコンテナ名の決定時に展開する。
+pub(crate) fn parse_name_template(node: &KdlNode, key: &str, vars: &[&str]) -> Result<String> {
+    let template = node
+        .entries()
+        .first()
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| FlowError::InvalidConfig(format!("{} requires a string", key)))?;
+    validate_name_template(template, key, vars)?;
+    Ok(template.to_string())
+}
+
+/// テンプレートの変数名を検証する
+pub(crate) fn validate_name_template(template: &str, key: &str, vars: &[&str]) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err(FlowError::InvalidConfig(format!(
+                "{} has an unclosed '{{': {}",
+                key, template
+            )));
+        };
+        let name = rest[start..start + end].trim_start_matches('{').trim();
+        if !vars.contains(&name) {
+            return Err(FlowError::InvalidConfig(format!(
+                "{} references unknown variable '{}' (available: {})",
+                key,
+                name,
+                vars.join(", ")
+            )));
+        }
+        rest = rest[start + end..].trim_start_matches('}');
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests;
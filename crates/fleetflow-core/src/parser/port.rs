@@ -1,61 +1,152 @@
 //! ポートノードのパース
 
-use crate::model::{Port, Protocol};
-use kdl::KdlNode;
+use crate::error::{FlowError, Result};
+use crate::model::{AppProtocol, Port, Protocol};
+use kdl::{KdlNode, KdlValue};
 
 /// port ノードをパース
 ///
 /// サポートされる形式:
 /// - 名前付き引数: port host=8080 container=3000
 /// - 位置引数（後方互換）: port 8080 3000
+/// - コンテナポート + 名前付き host: port 8080 host=18080 name="http" protocol="http"
 pub fn parse_port(node: &KdlNode) -> Option<Port> {
+    // 位置引数（プロパティを除く）
+    let args: Vec<u16> = node
+        .entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .filter_map(|e| e.value().as_integer())
+        .map(|v| v as u16)
+        .collect();
+
     // 名前付き引数を優先
-    let host = node
+    let host_prop = node
         .get("host")
         .and_then(|e| e.as_integer())
-        .map(|v| v as u16)
-        .or_else(|| {
-            // フォールバック: 位置引数
-            node.entries()
-                .first()?
-                .value()
-                .as_integer()
-                .map(|v| v as u16)
-        })?;
+        .map(|v| v as u16);
+    let host = host_prop.or_else(|| args.first().copied())?;
 
     let container = node
         .get("container")
         .and_then(|e| e.as_integer())
         .map(|v| v as u16)
         .or_else(|| {
-            // フォールバック: 位置引数の2番目、なければ1番目を使用
-            node.entries()
-                .get(1)
-                .or_else(|| node.entries().first())?
-                .value()
-                .as_integer()
-                .map(|v| v as u16)
-        })?;
-
-    let protocol = node
-        .get("protocol")
-        .and_then(|e| e.as_string())
-        .and_then(|s| match s {
-            "tcp" => Some(Protocol::Tcp),
-            "udp" => Some(Protocol::Udp),
-            _ => None,
+            if host_prop.is_some() {
+                // host が名前付きなら位置引数はコンテナポート
+                args.first().copied()
+            } else {
+                // フォールバック: 位置引数の2番目、なければ1番目を使用
+                args.get(1).or_else(|| args.first()).copied()
+            }
         })
-        .unwrap_or_default();
+        .unwrap_or(host);
+
+    let (protocol, app_protocol) = parse_protocol(node);
 
     let host_ip = node
         .get("host_ip")
         .and_then(|e| e.as_string())
         .map(|s| s.to_string());
 
+    let name = node
+        .get("name")
+        .and_then(|e| e.as_string())
+        .map(|s| s.to_string());
+
     Some(Port {
         host,
         container,
         protocol,
         host_ip,
+        name,
+        app_protocol,
     })
 }
+
+/// port ノードをパースし、範囲指定を個々のポートに展開する
+///
+/// 範囲指定の形式:
+/// - port "7000-7010"                  // host:7000-7010 → container:7000-7010
+/// - port "17000-17010" "7000-7010"    // 長さが同じ範囲同士を対応付ける
+/// - port "7000-7010" protocol="udp"
+pub fn parse_ports(service_name: &str, node: &KdlNode) -> Result<Vec<Port>> {
+    let args: Vec<&KdlValue> = node
+        .entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .map(|e| e.value())
+        .collect();
+
+    // 範囲指定は文字列の位置引数で書く
+    let Some(host_spec) = args.first().and_then(|v| v.as_string()) else {
+        return Ok(parse_port(node).into_iter().collect());
+    };
+
+    let invalid =
+        |message: String| FlowError::InvalidConfig(format!("service '{service_name}': {message}"));
+
+    let hosts = parse_range(host_spec)
+        .ok_or_else(|| invalid(format!("invalid port range '{host_spec}'")))?;
+    let containers = match args.get(1) {
+        Some(value) => {
+            let spec = value
+                .as_string()
+                .map(str::to_string)
+                .or_else(|| value.as_integer().map(|v| v.to_string()))
+                .unwrap_or_default();
+            parse_range(&spec).ok_or_else(|| invalid(format!("invalid port range '{value}'")))?
+        }
+        None => hosts,
+    };
+    if hosts.1 - hosts.0 != containers.1 - containers.0 {
+        return Err(invalid(format!(
+            "port ranges '{}' and '{}-{}' have different lengths",
+            host_spec, containers.0, containers.1
+        )));
+    }
+    if node.get("name").is_some() {
+        return Err(invalid(format!(
+            "port range '{host_spec}' cannot have a name"
+        )));
+    }
+
+    let (protocol, app_protocol) = parse_protocol(node);
+    let host_ip = node
+        .get("host_ip")
+        .and_then(|e| e.as_string())
+        .map(|s| s.to_string());
+
+    Ok((0..=hosts.1 - hosts.0)
+        .map(|offset| Port {
+            host: hosts.0 + offset,
+            container: containers.0 + offset,
+            protocol: protocol.clone(),
+            host_ip: host_ip.clone(),
+            name: None,
+            app_protocol,
+        })
+        .collect())
+}
+
+/// `"7000-7010"` / `"7000"` を (開始, 終了) にパース
+fn parse_range(spec: &str) -> Option<(u16, u16)> {
+    let (start, end) = match spec.split_once('-') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let port = spec.trim().parse().ok()?;
+            (port, port)
+        }
+    };
+    (start != 0 && start <= end).then_some((start, end))
+}
+
+/// protocol は tcp / udp のほか、アプリケーションプロトコル http / https も受け付ける
+fn parse_protocol(node: &KdlNode) -> (Protocol, Option<AppProtocol>) {
+    match node.get("protocol").and_then(|e| e.as_string()) {
+        Some("udp") => (Protocol::Udp, None),
+        Some("http") => (Protocol::Tcp, Some(AppProtocol::Http)),
+        Some("https") => (Protocol::Tcp, Some(AppProtocol::Https)),
+        _ => (Protocol::default(), None),
+    }
+}
@@ -0,0 +1,279 @@
+//! リモート include（https URL）の取得とキャッシュ
+//!
+//! `sha256` で固定した include は内容アドレスでキャッシュし、2 回目以降（オフライン時も）
+//! はキャッシュから読む。固定していない include は URL 単位でキャッシュし、
+//! `UNPINNED_CACHE_TTL` を過ぎたら取得し直す（取得に失敗したら古いキャッシュを使う）。
+//! 固定用のハッシュは警告として呼び出し側に返す。
+
+use crate::error::{FlowError, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// ダウンロードのタイムアウト（秒）
+const FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// 固定していない include のキャッシュを使い回す期間
+const UNPINNED_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// include パスが URL か
+pub fn is_remote(path: &str) -> bool {
+    path.starts_with("https://") || path.starts_with("http://")
+}
+
+/// キャッシュディレクトリ（`$XDG_CACHE_HOME/fleetflow/includes` または `~/.cache/fleetflow/includes`）
+fn cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("fleetflow").join("includes"))
+}
+
+fn digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn download(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--proto", "=https", "--max-time"])
+        .arg(FETCH_TIMEOUT_SECS.to_string())
+        .arg(url)
+        .output()
+        .map_err(|e| {
+            FlowError::InvalidConfig(format!(
+                "Failed to fetch remote include {url}: curl is required ({e})"
+            ))
+        })?;
+    if !output.status.success() {
+        return Err(FlowError::InvalidConfig(format!(
+            "Failed to fetch remote include {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| FlowError::InvalidConfig(format!("Remote include {url} is not valid UTF-8")))
+}
+
+/// リモート include の内容を取得する
+///
+/// 固定していない include の警告は `warnings` に追加する。
+pub fn fetch(url: &str, sha256: Option<&str>, warnings: &mut Vec<String>) -> Result<String> {
+    fetch_with_cache(url, sha256, cache_dir().as_deref(), warnings, download)
+}
+
+fn fetch_with_cache(
+    url: &str,
+    sha256: Option<&str>,
+    cache_dir: Option<&Path>,
+    warnings: &mut Vec<String>,
+    download: impl FnOnce(&str) -> Result<String>,
+) -> Result<String> {
+    if !url.starts_with("https://") {
+        return Err(FlowError::InvalidConfig(format!(
+            "Remote include must use https: {url}"
+        )));
+    }
+
+    let expected = sha256.map(|s| s.trim().to_lowercase());
+    let Some(expected) = expected else {
+        let content = fetch_unpinned(url, cache_dir, warnings, download)?;
+        warnings.push(format!(
+            "remote include {url} is not pinned; add sha256=\"{}\" to verify it",
+            digest(&content)
+        ));
+        return Ok(content);
+    };
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(FlowError::InvalidConfig(format!(
+            "Invalid sha256 for remote include {url}: '{expected}'"
+        )));
+    }
+
+    let cached = cache_dir.map(|dir| dir.join(format!("{expected}.kdl")));
+    if let Some(path) = &cached
+        && let Ok(content) = std::fs::read_to_string(path)
+        && digest(&content) == expected
+    {
+        return Ok(content);
+    }
+
+    let content = download(url)?;
+    let actual = digest(&content);
+    if expected != actual {
+        return Err(FlowError::InvalidConfig(format!(
+            "Checksum mismatch for remote include {url}: expected sha256 {expected}, got {actual}"
+        )));
+    }
+    if let Some(path) = &cached {
+        write_cache(path, &content);
+    }
+    Ok(content)
+}
+
+/// 固定していない include を URL 単位のキャッシュ経由で取得する
+fn fetch_unpinned(
+    url: &str,
+    cache_dir: Option<&Path>,
+    warnings: &mut Vec<String>,
+    download: impl FnOnce(&str) -> Result<String>,
+) -> Result<String> {
+    let cached = cache_dir.map(|dir| dir.join(format!("url-{}.kdl", digest(url))));
+    let cached_content = cached.as_ref().and_then(|path| {
+        let age = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        std::fs::read_to_string(path).ok().map(|c| (c, age))
+    });
+    if let Some((content, age)) = &cached_content
+        && *age < UNPINNED_CACHE_TTL
+    {
+        return Ok(content.clone());
+    }
+
+    match download(url) {
+        Ok(content) => {
+            if let Some(path) = &cached {
+                write_cache(path, &content);
+            }
+            Ok(content)
+        }
+        Err(e) => match cached_content {
+            Some((content, _)) => {
+                warnings.push(format!(
+                    "failed to refresh remote include {url}; using the cached copy ({e})"
+                ));
+                Ok(content)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// キャッシュに書き込む（書けなくても取得結果は使う）
+fn write_cache(path: &Path, content: &str) {
+    if let Some(parent) = path.parent()
+        && std::fs::create_dir_all(parent).is_ok()
+    {
+        let _ = std::fs::write(path, content);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/playbooks/common.kdl";
+    const CONTENT: &str = "service \"redis\" {\n    image \"redis:7\"\n}\n";
+
+    fn unreachable_download(_: &str) -> Result<String> {
+        panic!("should not download");
+    }
+
+    #[test]
+    fn test_pinned_include_is_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let sha = digest(CONTENT);
+
+        let mut warnings = Vec::new();
+        let fetched = fetch_with_cache(URL, Some(&sha), Some(dir.path()), &mut warnings, |_| {
+            Ok(CONTENT.into())
+        })
+        .unwrap();
+        assert_eq!(fetched, CONTENT);
+        assert!(dir.path().join(format!("{sha}.kdl")).exists());
+
+        // 2 回目はキャッシュから（ダウンロードしない）
+        let cached = fetch_with_cache(
+            URL,
+            Some(&sha.to_uppercase()),
+            Some(dir.path()),
+            &mut warnings,
+            unreachable_download,
+        )
+        .unwrap();
+        assert_eq!(cached, CONTENT);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let sha = digest("something else");
+
+        // 壊れたキャッシュは使わずに取得し直す
+        std::fs::write(dir.path().join(format!("{sha}.kdl")), CONTENT).unwrap();
+        let err = fetch_with_cache(URL, Some(&sha), Some(dir.path()), &mut Vec::new(), |_| {
+            Ok(CONTENT.into())
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+    }
+
+    #[test]
+    fn test_remote_include_requires_https_and_valid_sha() {
+        let mut warnings = Vec::new();
+        let err = fetch_with_cache(
+            "http://example.com/a.kdl",
+            None,
+            None,
+            &mut warnings,
+            unreachable_download,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("must use https"));
+
+        let err = fetch_with_cache(URL, Some("abc"), None, &mut warnings, unreachable_download)
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid sha256"));
+    }
+
+    #[test]
+    fn test_unpinned_include_is_cached_and_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut warnings = Vec::new();
+
+        let fetched = fetch_with_cache(URL, None, Some(dir.path()), &mut warnings, |_| {
+            Ok(CONTENT.into())
+        })
+        .unwrap();
+        assert_eq!(fetched, CONTENT);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(&format!("sha256=\"{}\"", digest(CONTENT))));
+
+        // TTL 内はキャッシュから（ダウンロードしない）
+        let cached = fetch_with_cache(
+            URL,
+            None,
+            Some(dir.path()),
+            &mut warnings,
+            unreachable_download,
+        )
+        .unwrap();
+        assert_eq!(cached, CONTENT);
+    }
+
+    #[test]
+    fn test_unpinned_include_falls_back_to_stale_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("url-{}.kdl", digest(URL)));
+        std::fs::write(&path, CONTENT).unwrap();
+        let stale = SystemTime::now() - UNPINNED_CACHE_TTL - Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+
+        let mut warnings = Vec::new();
+        let fetched = fetch_with_cache(URL, None, Some(dir.path()), &mut warnings, |_| {
+            Err(FlowError::InvalidConfig("offline".into()))
+        })
+        .unwrap();
+        assert_eq!(fetched, CONTENT);
+        assert!(warnings.iter().any(|w| w.contains("using the cached copy")));
+    }
+}
@@ -0,0 +1,1033 @@
+//! strict モードのスキーマ検証 — 未知のノード名・プロパティを検出
+//!
+//! 通常のパースでは未知のノードは無視される（前方互換）ため、
+//! `prots` のような typo に気づけない。strict モードではスキーマに無い
+//! ノード名・プロパティを編集距離に基づく候補付きで報告する。
+//!
+//! ```kdl
+//! strict #true   // プロジェクト設定: 読み込み時に常に検証
+//! ```
+
+use crate::error::Result;
+use kdl::{KdlDocument, KdlNode};
+use std::fmt;
+
+/// ノードのスキーマ
+#[derive(PartialEq)]
+struct NodeSchema {
+    /// 許可されるプロパティ（`None` は検証しない）
+    props: Option<&'static [&'static str]>,
+    /// 許可される子ノード（`None` は検証しない）
+    children: Option<&'static [(&'static str, &'static NodeSchema)]>,
+}
+
+/// 検証しないノード（自由形式の値、env / variables など）
+const ANY: NodeSchema = NodeSchema {
+    props: None,
+    children: None,
+};
+
+const PORT: NodeSchema = NodeSchema {
+    props: Some(&["host", "container", "protocol", "host_ip", "name"]),
+    children: None,
+};
+
+const PORTS: NodeSchema = NodeSchema {
+    props: Some(&["protocol", "host_ip"]),
+    children: Some(&[("port", &PORT)]),
+};
+
+const VOLUME: NodeSchema = NodeSchema {
+    props: Some(&["read_only"]),
+    children: None,
+};
+
+const VOLUMES: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[("volume", &VOLUME)]),
+};
+
+const BUILD: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("dockerfile", &ANY),
+        ("context", &ANY),
+        ("args", &ANY),
+        ("target", &ANY),
+        ("no_cache", &ANY),
+        ("image_tag", &ANY),
+        ("remote", &ANY),
+        ("watch_paths", &ANY),
+    ]),
+};
+
+const HEALTHCHECK: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("test", &ANY),
+        ("interval", &ANY),
+        ("timeout", &ANY),
+        ("retries", &ANY),
+        ("start_period", &ANY),
+    ]),
+};
+
+const WAIT_FOR: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("max_retries", &ANY),
+        ("initial_delay", &ANY),
+        ("max_delay", &ANY),
+        ("multiplier", &ANY),
+    ]),
+};
+
+const READINESS_KEYS: &[&str] = &["path", "port", "timeout", "interval"];
+
+const READINESS: NodeSchema = NodeSchema {
+    props: Some(READINESS_KEYS),
+    children: Some(&[
+        ("path", &ANY),
+        ("port", &ANY),
+        ("timeout", &ANY),
+        ("interval", &ANY),
+    ]),
+};
+
+const TEST: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("command", &ANY),
+        ("image", &ANY),
+        ("workdir", &ANY),
+        ("env", &ANY),
+        ("environment", &ANY),
+    ]),
+};
+
+const DEPLOY: NodeSchema = NodeSchema {
+    props: Some(&["provider", "project", "output"]),
+    children: Some(&[("provider", &ANY), ("project", &ANY), ("output", &ANY)]),
+};
+
+const LOGGING: NodeSchema = NodeSchema {
+    props: Some(&["driver", "max_size", "max_file"]),
+    children: Some(&[
+        ("driver", &ANY),
+        ("max_size", &ANY),
+        ("max_file", &ANY),
+        ("options", &ANY),
+    ]),
+};
+
+const TMPFS: NodeSchema = NodeSchema {
+    props: Some(&["size", "mode"]),
+    children: None,
+};
+
+const MOUNT_TARGET: NodeSchema = NodeSchema {
+    props: Some(&["target"]),
+    children: None,
+};
+
+const SERVICE: NodeSchema = NodeSchema {
+    props: Some(&[
+        "type",
+        "service_type",
+        "command",
+        "image",
+        "extends",
+        "version",
+        "restart",
+        "registry",
+        "user",
+        "entrypoint",
+        "working_dir",
+        "hostname",
+        "container_name",
+        "enabled",
+        "when",
+        "read_only",
+        "privileged",
+        "tty",
+        "stdin_open",
+        "inject_wait",
+        "inject_links",
+        "shm_size",
+        "stop_signal",
+        "stop_grace_period",
+    ]),
+    children: Some(&[
+        ("image", &ANY),
+        ("version", &ANY),
+        ("command", &ANY),
+        ("ports", &PORTS),
+        ("port", &PORT),
+        ("environment", &ANY),
+        ("env", &ANY),
+        ("volumes", &VOLUMES),
+        ("depends_on", &ANY),
+        ("dockerfile", &ANY),
+        ("context", &ANY),
+        ("target", &ANY),
+        ("build_args", &ANY),
+        ("image_tag", &ANY),
+        ("build", &BUILD),
+        ("healthcheck", &HEALTHCHECK),
+        ("restart", &ANY),
+        ("wait_for", &WAIT_FOR),
+        ("readiness", &READINESS),
+        ("registry", &ANY),
+        ("deploy", &DEPLOY),
+        ("logging", &LOGGING),
+        ("user", &ANY),
+        ("entrypoint", &ANY),
+        ("working_dir", &ANY),
+        ("hostname", &ANY),
+        ("labels", &ANY),
+        ("container_name", &ANY),
+        ("enabled", &ANY),
+        ("when", &ANY),
+        ("cap_add", &ANY),
+        ("cap_drop", &ANY),
+        ("security_opt", &ANY),
+        ("read_only", &ANY),
+        ("privileged", &ANY),
+        ("tty", &ANY),
+        ("stdin_open", &ANY),
+        ("inject_wait", &ANY),
+        ("inject_links", &ANY),
+        ("tmpfs", &TMPFS),
+        ("shm_size", &ANY),
+        ("stop_signal", &ANY),
+        ("stop_grace_period", &ANY),
+        ("config", &MOUNT_TARGET),
+        ("secret", &MOUNT_TARGET),
+        ("ulimits", &ANY),
+        ("sysctls", &ANY),
+        ("test", &TEST),
+        ("network_mode", &ANY),
+    ]),
+};
+
+const LOG_SHIPPING_KEYS: &[&str] = &["agent", "sink", "endpoint", "bucket", "region", "image"];
+
+const LOG_SHIPPING: NodeSchema = NodeSchema {
+    props: Some(LOG_SHIPPING_KEYS),
+    children: Some(&[
+        ("agent", &ANY),
+        ("sink", &ANY),
+        ("endpoint", &ANY),
+        ("bucket", &ANY),
+        ("region", &ANY),
+        ("image", &ANY),
+    ]),
+};
+
+const STAGE_REGISTRY_KEYS: &[&str] = &["url", "username_env", "password_env"];
+
+const STAGE_REGISTRY: NodeSchema = NodeSchema {
+    props: Some(STAGE_REGISTRY_KEYS),
+    children: Some(&[
+        ("url", &ANY),
+        ("username_env", &ANY),
+        ("password_env", &ANY),
+    ]),
+};
+
+const STAGE: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("service", &SERVICE),
+        ("server", &ANY),
+        ("variables", &ANY),
+        ("registry", &STAGE_REGISTRY),
+        ("image_template", &ANY),
+        ("backend", &ANY),
+        ("target", &ANY),
+        ("log_shipping", &LOG_SHIPPING),
+        ("smoke_test", &SMOKE_TEST),
+        ("dns_domain", &ANY),
+    ]),
+};
+
+const SMOKE_TEST: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[("http", &SMOKE_HTTP)]),
+};
+
+const SMOKE_HTTP: NodeSchema = NodeSchema {
+    props: Some(&["expect", "timeout", "service", "port", "path"]),
+    children: None,
+};
+
+const CONFIG_DEF: NodeSchema = NodeSchema {
+    props: Some(&["file", "content"]),
+    children: Some(&[("file", &ANY), ("content", &ANY)]),
+};
+
+const CONFIGS: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[("config", &CONFIG_DEF)]),
+};
+
+const SECRET_DEF: NodeSchema = NodeSchema {
+    props: Some(&["from", "env"]),
+    children: None,
+};
+
+const SECRETS: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[("secret", &SECRET_DEF)]),
+};
+
+const SETUP_STEP: NodeSchema = NodeSchema {
+    props: Some(&[
+        "run",
+        "after",
+        "retries",
+        "retry_delay",
+        "timeout",
+        "skip_if",
+        "stage",
+    ]),
+    children: None,
+};
+
+const MAINTENANCE: NodeSchema = NodeSchema {
+    props: Some(&["image_retention", "prune_schedule"]),
+    children: Some(&[("image_retention", &ANY), ("prune_schedule", &ANY)]),
+};
+
+const SITE: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("build", &ANY),
+        ("dir", &ANY),
+        ("output", &ANY),
+        ("domain", &ANY),
+        ("bucket", &ANY),
+    ]),
+};
+
+const MCP: NodeSchema = NodeSchema {
+    props: Some(&["enable_destructive_tools"]),
+    children: Some(&[("enable_destructive_tools", &ANY)]),
+};
+
+const SETUP: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[("step", &SETUP_STEP)]),
+};
+
+/// トップレベル（provider / server / tenant は任意キーを許容するため検証しない）
+const ROOT: NodeSchema = NodeSchema {
+    props: None,
+    children: Some(&[
+        ("project", &ANY),
+        ("stage", &STAGE),
+        ("service", &SERVICE),
+        ("provider", &ANY),
+        ("server", &ANY),
+        ("site", &SITE),
+        ("include", &ANY),
+        ("variables", &ANY),
+        ("registry", &ANY),
+        ("image_template", &ANY),
+        ("container_name_template", &ANY),
+        ("tenant", &ANY),
+        ("configs", &CONFIGS),
+        ("secrets", &SECRETS),
+        ("setup", &SETUP),
+        ("maintenance", &MAINTENANCE),
+        ("mcp", &MCP),
+        ("strict", &ANY),
+        ("schema", &ANY),
+    ]),
+};
+
+/// 未知のキーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownKeyKind {
+    /// ノード名
+    Node,
+    /// プロパティ名
+    Property,
+}
+
+/// strict モードで検出された未知のキー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// 検出位置（例: `stage "prod" > service "api"`）
+    pub path: String,
+    /// 種類
+    pub kind: UnknownKeyKind,
+    /// 未知のキー名
+    pub name: String,
+    /// 編集距離に基づく候補
+    pub suggestion: Option<String>,
+    /// ソース上の位置（バイトオフセット, 長さ）
+    pub span: (usize, usize),
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            UnknownKeyKind::Node => "node",
+            UnknownKeyKind::Property => "property",
+        };
+        let location = if self.path.is_empty() {
+            "top level".to_string()
+        } else {
+            self.path.clone()
+        };
+        write!(f, "{}: unknown {} '{}'", location, kind, self.name)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean '{}'?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// KDL 文字列から未知のノード名・プロパティを検出する
+pub fn check_unknown_keys(content: &str) -> Result<Vec<UnknownKey>> {
+    let doc: KdlDocument = content.parse()?;
+    Ok(check_document(&doc))
+}
+
+/// トップレベルに `strict #true` が宣言されているか
+pub(crate) fn is_strict(doc: &KdlDocument) -> bool {
+    doc.nodes()
+        .iter()
+        .filter(|n| n.name().value() == "strict")
+        .filter_map(|n| n.entries().first().and_then(|e| e.value().as_bool()))
+        .next_back()
+        .unwrap_or(false)
+}
+
+/// パース済みドキュメントから未知のノード名・プロパティを検出する
+pub(crate) fn check_document(doc: &KdlDocument) -> Vec<UnknownKey> {
+    let mut issues = Vec::new();
+    check_children(doc, &ROOT, "", &mut issues);
+    issues
+}
+
+fn check_children(
+    doc: &KdlDocument,
+    schema: &NodeSchema,
+    path: &str,
+    issues: &mut Vec<UnknownKey>,
+) {
+    let Some(known) = schema.children else {
+        return;
+    };
+
+    for node in doc.nodes() {
+        let name = node.name().value();
+        match known.iter().find(|(n, _)| *n == name) {
+            Some((_, child_schema)) => check_node(node, child_schema, path, issues),
+            None => issues.push(UnknownKey {
+                path: path.to_string(),
+                kind: UnknownKeyKind::Node,
+                name: name.to_string(),
+                suggestion: suggest(name, known.iter().map(|(n, _)| *n)),
+                span: (node.name().span().offset(), node.name().span().len()),
+            }),
+        }
+    }
+}
+
+fn check_node(node: &KdlNode, schema: &NodeSchema, parent: &str, issues: &mut Vec<UnknownKey>) {
+    let path = node_path(parent, node);
+
+    if let Some(props) = schema.props {
+        for entry in node.entries() {
+            let Some(key) = entry.name() else {
+                continue;
+            };
+            let span = (key.span().offset(), key.span().len());
+            let key = key.value();
+            if !props.contains(&key) {
+                issues.push(UnknownKey {
+                    path: path.clone(),
+                    kind: UnknownKeyKind::Property,
+                    name: key.to_string(),
+                    suggestion: suggest(key, props.iter().copied()),
+                    span,
+                });
+            }
+        }
+    }
+
+    if let Some(children) = node.children() {
+        check_children(children, schema, &path, issues);
+    }
+}
+
+/// ノードの階層（例: `["stage", "service"]`）のスキーマ
+fn resolve(path: &[&str]) -> Option<&'static NodeSchema> {
+    let mut schema: &'static NodeSchema = &ROOT;
+    for name in path {
+        schema = schema.children?.iter().find(|(n, _)| n == name)?.1;
+    }
+    Some(schema)
+}
+
+/// ノードの階層で使える子ノード名（自由形式のノードは None）
+///
+/// 空の階層はトップレベルを表す。エディタの補完に使う。
+pub fn schema_child_nodes(path: &[&str]) -> Option<Vec<&'static str>> {
+    Some(resolve(path)?.children?.iter().map(|(n, _)| *n).collect())
+}
+
+/// ノードの階層で使えるプロパティ名（自由形式のノードは None）
+pub fn schema_properties(path: &[&str]) -> Option<&'static [&'static str]> {
+    resolve(path)?.props
+}
+
+/// ノード名・プロパティ名の説明（エディタのホバー表示用）
+const NODE_DOCS: &[(&str, &str)] = &[
+    (
+        "project",
+        "プロジェクト名。コンテナ名 `{project}-{stage}-{service}` の接頭辞になる",
+    ),
+    (
+        "stage",
+        "ステージ（local / dev / prod など）。起動するサービスとデプロイ先サーバーを束ねる",
+    ),
+    (
+        "service",
+        "サービス定義。トップレベルでは定義、stage 内では参加するサービスの指定（と上書き）",
+    ),
+    (
+        "server",
+        "デプロイ先サーバー（ssh_host / ssh_user など）。stage 内では使用するサーバー名",
+    ),
+    (
+        "provider",
+        "クラウドプロバイダー（sakura / cloudflare / aws）の設定",
+    ),
+    ("include", "他の KDL ファイルを読み込む"),
+    (
+        "variables",
+        "テンプレート変数（`{{ NAME }}` で参照）。stage 内の定義が優先",
+    ),
+    ("registry", "コンテナレジストリ URL（例: ghcr.io/owner）"),
+    (
+        "username_env",
+        "レジストリのユーザー名を読む環境変数（stage の registry ブロック）",
+    ),
+    (
+        "password_env",
+        "レジストリのパスワード・トークンを読む環境変数（stage の registry ブロック）",
+    ),
+    (
+        "image_template",
+        "ビルドイメージ名のテンプレート（既定 `{registry}/{project}-{stage}:{tag}`）",
+    ),
+    (
+        "container_name_template",
+        "コンテナ名のテンプレート（既定 `{project}-{stage}-{service}`）",
+    ),
+    ("tenant", "Control Plane のテナント"),
+    (
+        "configs",
+        "テンプレート展開してコンテナにマウントする設定ファイルの定義",
+    ),
+    (
+        "secrets",
+        "シークレットの定義（`from` は 1Password 参照またはリテラル）",
+    ),
+    ("setup", "`fleet setup` で実行するサーバー初期化ステップ"),
+    (
+        "maintenance",
+        "デプロイ先のイメージ保持数（image_retention）と削除間隔（prune_schedule）",
+    ),
+    ("mcp", "MCP サーバーの設定（enable_destructive_tools）"),
+    (
+        "site",
+        "静的サイト（build / output / domain）。`fleet cloud up` で R2 に配置",
+    ),
+    (
+        "strict",
+        "`#true` で未知のノード名・プロパティをエラーにする",
+    ),
+    ("schema", "設定スキーマのバージョン宣言"),
+    (
+        "image",
+        "コンテナイメージ（`name:tag`）。未指定時はサービス名",
+    ),
+    ("version", "イメージのタグ"),
+    (
+        "command",
+        "コンテナの起動コマンド（イメージの CMD を上書き）。`command \"npm\" \"run\" \"dev\"` の配列形式も可",
+    ),
+    (
+        "entrypoint",
+        "エントリーポイント（イメージの ENTRYPOINT を上書き）",
+    ),
+    ("working_dir", "コンテナ内の作業ディレクトリ（絶対パス）"),
+    ("hostname", "コンテナのホスト名"),
+    (
+        "labels",
+        "コンテナに付ける任意のラベル（`fleetflow.` / `com.docker.compose.` は予約）",
+    ),
+    ("type", "サービスタイプ（container / static）"),
+    ("extends", "共通設定を継承するサービス名"),
+    (
+        "restart",
+        "再起動ポリシー（no / always / on-failure / unless-stopped）",
+    ),
+    (
+        "port",
+        "ポート公開（host / container / protocol / host_ip / name）",
+    ),
+    (
+        "ports",
+        "ポート公開のグループ（配下の port に protocol / host_ip を適用）",
+    ),
+    ("env", "環境変数（`KEY \"value\"`）"),
+    ("environment", "環境変数（`env` の別名）"),
+    (
+        "volume",
+        "bind mount（`volume \"./data\" \"/data\" read_only=#true`）",
+    ),
+    ("volumes", "bind mount のグループ"),
+    ("depends_on", "先に起動するサービス名"),
+    (
+        "build",
+        "イメージのビルド設定（dockerfile / context / args / target）",
+    ),
+    (
+        "healthcheck",
+        "コンテナのヘルスチェック（test / interval / timeout / retries / start_period）",
+    ),
+    ("wait_for", "依存サービスの待機（exponential backoff）"),
+    (
+        "inject_links",
+        "depends_on の接続先を `<SERVICE>_HOST` / `<SERVICE>_PORT_<NAME>` として env に注入する",
+    ),
+    ("readiness", "fleet up 後の HTTP readiness チェック"),
+    ("deploy", "静的サイトなどのデプロイ先設定"),
+    ("logging", "ログドライバとローテーション設定"),
+    ("user", "コンテナの実行ユーザー（`uid:gid`）"),
+    (
+        "container_name",
+        "コンテナ名（`container_name_template` より優先）",
+    ),
+    (
+        "enabled",
+        "`#false` でどのステージでも使わない（サービス・サーバー）",
+    ),
+    (
+        "when",
+        "ステージごとの条件式（例: `\"{{ stage }} != 'local'\"`）。偽のステージから外す",
+    ),
+    ("cap_add", "追加する Linux capability"),
+    ("cap_drop", "削除する Linux capability（例: `ALL`）"),
+    (
+        "security_opt",
+        "セキュリティオプション（例: `no-new-privileges`）",
+    ),
+    ("read_only", "ルートファイルシステムを読み取り専用にする"),
+    ("privileged", "特権モードで起動する"),
+    ("tmpfs", "tmpfs マウント（size / mode）"),
+    ("shm_size", "/dev/shm のサイズ（例: `256m`）"),
+    ("stop_signal", "停止時に送るシグナル（例: `SIGINT`）"),
+    ("stop_grace_period", "停止時に強制終了するまでの秒数"),
+    (
+        "config",
+        "configs で定義した設定ファイルのマウント（target）",
+    ),
+    (
+        "secret",
+        "secrets で定義したシークレットのマウント（target）",
+    ),
+    ("ulimits", "リソース制限（nofile など）"),
+    ("sysctls", "カーネルパラメータ"),
+    ("test", "`fleet test` で実行するテスト設定"),
+    (
+        "network_mode",
+        "ネットワークモード（host / none / container:<service>）",
+    ),
+    (
+        "log_shipping",
+        "ステージのログ集約エージェント（agent / sink / endpoint）",
+    ),
+    ("smoke_test", "デプロイ後のスモークテスト（http）"),
+    (
+        "dns_domain",
+        "ステージの DNS ドメイン（cloudflare provider の domain を上書き）",
+    ),
+];
+
+/// ノード名・プロパティ名の説明
+pub fn node_doc(name: &str) -> Option<&'static str> {
+    NODE_DOCS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, doc)| *doc)
+}
+
+/// キーの種類（プロパティ / 子ノード、両方の書き方を許すキーもある）
+fn key_kinds(schema: &NodeSchema, name: &str) -> (bool, bool) {
+    let is_prop = schema.props.unwrap_or_default().contains(&name);
+    let is_child = schema
+        .children
+        .unwrap_or_default()
+        .iter()
+        .any(|(n, _)| *n == name);
+    (is_prop, is_child)
+}
+
+/// スキーマのキー（プロパティ → 子ノードの順、重複は 1 つにまとめる）
+fn schema_keys(schema: &NodeSchema) -> Vec<&'static str> {
+    let mut keys: Vec<&'static str> = schema.props.unwrap_or_default().to_vec();
+    for (name, _) in schema.children.unwrap_or_default() {
+        if !keys.contains(name) {
+            keys.push(name);
+        }
+    }
+    keys
+}
+
+fn node_json_schema(name: &str, schema: &NodeSchema) -> serde_json::Value {
+    let mut value = serde_json::json!({});
+    if let Some(doc) = node_doc(name) {
+        value["description"] = doc.into();
+    }
+    if schema.props.is_none() && schema.children.is_none() {
+        return value;
+    }
+
+    let mut properties = serde_json::Map::new();
+    for key in schema_keys(schema) {
+        let child = schema
+            .children
+            .unwrap_or_default()
+            .iter()
+            .find(|(n, _)| *n == key);
+        let mut property = match child {
+            Some((_, child_schema)) => node_json_schema(key, child_schema),
+            None => node_doc(key).map_or_else(
+                || serde_json::json!({}),
+                |doc| serde_json::json!({ "description": doc }),
+            ),
+        };
+        let (is_prop, is_child) = key_kinds(schema, key);
+        let kinds: Vec<&str> = [(is_prop, "property"), (is_child, "node")]
+            .into_iter()
+            .filter_map(|(yes, kind)| yes.then_some(kind))
+            .collect();
+        property["x-kdl-kind"] = kinds.into();
+        properties.insert(key.to_string(), property);
+    }
+    value["type"] = "object".into();
+    value["properties"] = properties.into();
+    // プロパティ・子ノードの片方しか検証しないノードは未知のキーを許容する
+    value["additionalProperties"] = (schema.props.is_none() || schema.children.is_none()).into();
+    value
+}
+
+/// 設定ファイルの JSON Schema（draft 2020-12）
+///
+/// KDL のノードをオブジェクト、プロパティと子ノードをそのキーとして表現する。
+/// ノードの引数や同名ノードの繰り返しは表現しない。キーがプロパティ・子ノードの
+/// どちらとして書けるかは `x-kdl-kind` に入る。strict モードの検証と同じスキーマから
+/// 生成するため、パーサーが受け付けるキーと一致する。
+pub fn export_json_schema() -> serde_json::Value {
+    let mut value = node_json_schema("", &ROOT);
+    value["$schema"] = "https://json-schema.org/draft/2020-12/schema".into();
+    value["title"] = "fleet.kdl".into();
+    value["description"] = "FleetFlow の設定ファイル（fleet.kdl）".into();
+    value
+}
+
+/// 設定ファイルのリファレンス（Markdown）
+///
+/// ノードごとに節を作り、プロパティ・子ノードを説明付きの表で列挙する。
+/// 同じスキーマのノード（トップレベルと stage 内の `service` など）は最初の節を参照する。
+pub fn export_markdown() -> String {
+    let mut out = String::from("# fleet.kdl スキーマ\n\n");
+    out.push_str("`fleet schema export --format markdown` で生成。\n");
+
+    let mut written: Vec<(&NodeSchema, String)> = Vec::new();
+    let mut queue: std::collections::VecDeque<(Vec<&str>, &NodeSchema)> =
+        std::collections::VecDeque::from([(Vec::new(), &ROOT)]);
+    while let Some((path, schema)) = queue.pop_front() {
+        if schema.props.is_none() && schema.children.is_none() {
+            continue;
+        }
+        let title = if path.is_empty() {
+            "トップレベル".to_string()
+        } else {
+            format!("`{}`", path.join(" > "))
+        };
+        out.push_str(&format!("\n## {}\n\n", title));
+
+        if let Some(name) = path.last()
+            && let Some(doc) = node_doc(name)
+        {
+            out.push_str(&format!("{}\n\n", doc));
+        }
+        if let Some((_, same)) = written.iter().find(|(s, _)| *s == schema) {
+            out.push_str(&format!("{} と同じ。\n", same));
+            continue;
+        }
+        written.push((schema, title));
+
+        if schema.props.is_none() {
+            out.push_str("プロパティは検証されない（任意のキーを書ける）。\n\n");
+        } else if schema.children.is_none() {
+            out.push_str("子ノードは検証されない（任意のノードを書ける）。\n\n");
+        }
+        out.push_str("| キー | 書き方 | 説明 |\n|---|---|---|\n");
+        for key in schema_keys(schema) {
+            let kind = match key_kinds(schema, key) {
+                (true, true) => "プロパティ / 子ノード",
+                (true, false) => "プロパティ",
+                _ => "子ノード",
+            };
+            let doc = node_doc(key).unwrap_or_default().replace('|', "\\|");
+            out.push_str(&format!("| `{}` | {} | {} |\n", key, kind, doc));
+        }
+
+        for (name, child) in schema.children.unwrap_or_default() {
+            let mut child_path = path.clone();
+            child_path.push(name);
+            queue.push_back((child_path, child));
+        }
+    }
+    out
+}
+
+/// `parent > name "arg"` 形式の位置表示
+fn node_path(parent: &str, node: &KdlNode) -> String {
+    let mut label = node.name().value().to_string();
+    if let Some(arg) = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+    {
+        label = format!("{} \"{}\"", label, arg);
+    }
+    if parent.is_empty() {
+        label
+    } else {
+        format!("{} > {}", parent, label)
+    }
+}
+
+/// 編集距離が近い候補を返す（距離 2 以内、かつ名前の長さ未満）
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    candidates
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= 2 && *d < name.chars().count())
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.to_string())
+}
+
+/// Levenshtein 距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ports", "ports"), 0);
+        assert_eq!(edit_distance("prots", "ports"), 2);
+        assert_eq!(edit_distance("imgae", "image"), 2);
+        assert_eq!(edit_distance("volume", "volumes"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_unknown_service_child_with_suggestion() {
+        let issues = check_unknown_keys(
+            r#"
+            service "api" {
+                image "myapp:latest"
+                prots {
+                    port 8080 3000
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "service \"api\"");
+        assert_eq!(issues[0].kind, UnknownKeyKind::Node);
+        assert_eq!(issues[0].name, "prots");
+        assert_eq!(issues[0].suggestion.as_deref(), Some("ports"));
+        assert_eq!(
+            issues[0].to_string(),
+            "service \"api\": unknown node 'prots' (did you mean 'ports'?)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_span() {
+        let content = "service \"api\" imgae=\"app\" {\n    prots 1\n}\n";
+        let issues = check_unknown_keys(content).unwrap();
+        let spans: Vec<&str> = issues
+            .iter()
+            .map(|i| &content[i.span.0..i.span.0 + i.span.1])
+            .collect();
+        assert_eq!(spans, vec!["imgae", "prots"]);
+    }
+
+    #[test]
+    fn test_schema_lookup() {
+        assert!(schema_child_nodes(&[]).unwrap().contains(&"stage"));
+        assert!(schema_child_nodes(&["stage"]).unwrap().contains(&"service"));
+        assert!(schema_properties(&["service"]).unwrap().contains(&"image"));
+        // 自由形式・未知のノード
+        assert!(schema_child_nodes(&["service", "env"]).is_none());
+        assert!(schema_child_nodes(&["unknown"]).is_none());
+        assert!(node_doc("healthcheck").is_some());
+    }
+
+    #[test]
+    fn test_export_json_schema() {
+        let schema = export_json_schema();
+        let service = &schema["properties"]["stage"]["properties"]["service"];
+        assert_eq!(service["type"], "object");
+        assert_eq!(service["additionalProperties"], false);
+        // image はプロパティ・子ノードどちらでも書ける
+        assert_eq!(
+            service["properties"]["image"]["x-kdl-kind"],
+            serde_json::json!(["property", "node"])
+        );
+        assert_eq!(
+            service["properties"]["ports"]["properties"]["port"]["x-kdl-kind"],
+            serde_json::json!(["node"])
+        );
+        // 自由形式のノードは制約なし
+        assert!(service["properties"]["env"].get("type").is_none());
+        assert_eq!(schema["additionalProperties"], true);
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let markdown = export_markdown();
+        for name in schema_child_nodes(&[]).unwrap() {
+            assert!(markdown.contains(&format!("| `{}` |", name)), "{}", name);
+        }
+        assert!(markdown.contains("\n## `service > healthcheck`\n"));
+        // stage 内の service はトップレベルの節を参照する
+        assert!(markdown.contains("`service` と同じ。"));
+        assert!(!markdown.contains("## `stage > service > healthcheck`"));
+    }
+
+    #[test]
+    fn test_unknown_property() {
+        let issues = check_unknown_keys(
+            r#"
+            service "api" imgae="myapp:latest" {
+                port 8080 3000 protocl="udp"
+            }
+            "#,
+        )
+        .unwrap();
+
+        let names: Vec<_> = issues
+            .iter()
+            .map(|i| (i.kind, i.name.as_str(), i.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (UnknownKeyKind::Property, "imgae", Some("image")),
+                (UnknownKeyKind::Property, "protocl", Some("protocol")),
+            ]
+        );
+        assert_eq!(issues[1].path, "service \"api\" > port");
+    }
+
+    #[test]
+    fn test_unknown_top_level_and_stage_nodes() {
+        let issues = check_unknown_keys(
+            r#"
+            servce "api" {}
+            stage "prod" {
+                service "api" {
+                    enviroment {
+                        ANY_KEY "ok"
+                    }
+                }
+                totally_unrelated "x"
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].path, "");
+        assert_eq!(issues[0].suggestion.as_deref(), Some("service"));
+        assert_eq!(issues[1].path, "stage \"prod\" > service \"api\"");
+        assert_eq!(issues[1].suggestion.as_deref(), Some("environment"));
+        assert_eq!(issues[2].name, "totally_unrelated");
+        assert_eq!(issues[2].suggestion, None);
+    }
+
+    #[test]
+    fn test_free_form_blocks_are_not_checked() {
+        let issues = check_unknown_keys(
+            r#"
+            variables {
+                WHATEVER "1"
+            }
+            provider "sakura-cloud" {
+                custom_key "x"
+            }
+            service "api" {
+                image "myapp:latest"
+                env {
+                    SOME_RANDOM_KEY "v"
+                }
+                build {
+                    args {
+                        ANY_ARG "1"
+                    }
+                }
+                sysctls {
+                    net.core.somaxconn 1024
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn test_is_strict() {
+        let strict: KdlDocument = "strict #true".parse().unwrap();
+        let lax: KdlDocument = "strict #false".parse().unwrap();
+        let none: KdlDocument = "project \"x\"".parse().unwrap();
+        assert!(is_strict(&strict));
+        assert!(!is_strict(&lax));
+        assert!(!is_strict(&none));
+    }
+}
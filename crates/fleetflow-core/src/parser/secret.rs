@@ -0,0 +1,96 @@
+//! secrets / secret ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{SecretDef, SecretMount};
+use kdl::KdlNode;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// トップレベル `secrets` ブロックをパース
+///
+/// ```kdl
+/// secrets {
+///     secret "db_password" from="op://Vault/db/password"
+///     secret "api_key" env="API_KEY"
+/// }
+/// ```
+pub fn parse_secrets(node: &KdlNode) -> Result<HashMap<String, SecretDef>> {
+    let mut secrets = HashMap::new();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if child.name().value() != "secret" {
+                continue;
+            }
+            let name = child
+                .entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string())
+                .ok_or_else(|| FlowError::InvalidConfig("secret requires a name".to_string()))?
+                .to_string();
+
+            let secret = SecretDef {
+                from: child
+                    .get("from")
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.to_string()),
+                env: child
+                    .get("env")
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.to_string()),
+            };
+
+            match (&secret.from, &secret.env) {
+                (Some(_), Some(_)) => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "secret '{name}': from and env are mutually exclusive"
+                    )));
+                }
+                (None, None) => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "secret '{name}' requires from or env"
+                    )));
+                }
+                _ => {}
+            }
+
+            secrets.insert(name, secret);
+        }
+    }
+
+    Ok(secrets)
+}
+
+/// サービス内の `secret` ノードをパース
+///
+/// ```kdl
+/// secret "db_password" target="/run/secrets/db_password"
+/// ```
+pub fn parse_secret_mount(service_name: &str, node: &KdlNode) -> Result<SecretMount> {
+    let source = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!("service '{service_name}': secret requires a name"))
+        })?;
+    let target = node
+        .get("target")
+        .and_then(|v| v.as_string())
+        .map(PathBuf::from);
+
+    if let Some(target) = &target
+        && !target.is_absolute()
+    {
+        return Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': secret '{source}' target must be an absolute path"
+        )));
+    }
+
+    Ok(SecretMount {
+        source: source.to_string(),
+        target,
+    })
+}
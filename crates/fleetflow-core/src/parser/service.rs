@@ -1,10 +1,17 @@
 //! サービスノードのパース
 
-use super::port::parse_port;
+use super::config_file::parse_config_mount;
+use super::port::parse_ports;
+use super::secret::parse_secret_mount;
 use super::volume::parse_volume;
 use crate::error::{FlowError, Result};
-use crate::model::{BuildConfig, DeployConfig, RestartPolicy, Service, ServiceType, WaitConfig};
-use kdl::{KdlDocument, KdlNode};
+use crate::model::{
+    BuildConfig, DeployConfig, LoggingConfig, NetworkMode, RestartPolicy, Service, ServiceType,
+    TestConfig, TmpfsMount, Ulimit, WaitConfig, join_command_line, parse_byte_size,
+    split_command_line,
+};
+use crate::parser::validate_name_template;
+use kdl::{KdlDocument, KdlNode, KdlValue};
 use std::path::PathBuf;
 
 /// service ノードをパース
@@ -31,6 +38,9 @@ pub fn parse_service(node: &KdlNode) -> Result<(String, Service)> {
                 "image" => {
                     service.image = entry.value().as_string().map(|s| s.to_string());
                 }
+                "extends" => {
+                    service.extends = entry.value().as_string().map(|s| s.to_string());
+                }
                 "version" => {
                     service.version = entry.value().as_string().map(|s| s.to_string());
                 }
@@ -40,6 +50,60 @@ pub fn parse_service(node: &KdlNode) -> Result<(String, Service)> {
                 "registry" => {
                     service.registry = entry.value().as_string().map(|s| s.to_string());
                 }
+                "user" => {
+                    service.user = entry.value().as_string().map(|s| s.to_string());
+                }
+                "entrypoint" => {
+                    service.entrypoint = entry.value().as_string().map(split_command_line);
+                }
+                "working_dir" => {
+                    if let Some(dir) = entry.value().as_string() {
+                        service.working_dir = Some(validate_working_dir(&name, dir)?);
+                    }
+                }
+                "hostname" => {
+                    service.hostname = entry.value().as_string().map(|s| s.to_string());
+                }
+                "container_name" => {
+                    service.container_name = entry.value().as_string().map(|s| s.to_string());
+                }
+                "enabled" => {
+                    service.enabled = entry.value().as_bool();
+                }
+                "when" => {
+                    service.when = entry.value().as_string().map(|s| s.to_string());
+                }
+                "read_only" => {
+                    service.read_only = entry.value().as_bool();
+                }
+                "privileged" => {
+                    service.privileged = entry.value().as_bool();
+                }
+                "tty" => {
+                    service.tty = entry.value().as_bool();
+                }
+                "stdin_open" => {
+                    service.stdin_open = entry.value().as_bool();
+                }
+                "inject_wait" => {
+                    service.inject_wait = entry.value().as_bool();
+                }
+                "inject_links" => {
+                    service.inject_links = entry.value().as_bool();
+                }
+                "shm_size" => {
+                    if let Some(size) = entry.value().as_string() {
+                        service.shm_size = Some(validate_size(&name, "shm_size", size)?);
+                    }
+                }
+                "stop_signal" => {
+                    if let Some(signal) = entry.value().as_string() {
+                        service.stop_signal = Some(validate_stop_signal(&name, signal)?);
+                    }
+                }
+                "stop_grace_period" => {
+                    service.stop_grace_period = Some(parse_grace_period(&name, entry.value())?);
+                }
                 _ => {}
             }
         }
@@ -62,28 +126,53 @@ pub fn parse_service(node: &KdlNode) -> Result<(String, Service)> {
                         .and_then(|e| e.value().as_string())
                         .map(|s| s.to_string());
                 }
+                // 配列形式（`command "npm" "run" "dev"`）は 1 行に連結する
                 "command" => {
-                    service.command = child
+                    let args = string_arguments(child);
+                    service.command = match args.as_slice() {
+                        [] => None,
+                        [command] => Some(command.clone()),
+                        args => Some(join_command_line(args)),
+                    };
+                }
+                // 文字列 1 つならコマンド行として分割、複数なら配列形式
+                "entrypoint" => {
+                    let mut args = string_arguments(child);
+                    if args.len() == 1 {
+                        args = split_command_line(&args[0]);
+                    }
+                    service.entrypoint = Some(args);
+                }
+                "working_dir" => {
+                    if let Some(dir) = child.entries().first().and_then(|e| e.value().as_string()) {
+                        service.working_dir = Some(validate_working_dir(&name, dir)?);
+                    }
+                }
+                "hostname" => {
+                    service.hostname = child
                         .entries()
                         .first()
                         .and_then(|e| e.value().as_string())
                         .map(|s| s.to_string());
                 }
+                "labels" => {
+                    service.labels.extend(parse_labels(&name, child)?);
+                }
                 "ports" => {
+                    // ports "7000-7010" protocol="udp" のような一行形式
+                    if child.entries().iter().any(|e| e.name().is_none()) {
+                        service.ports.extend(parse_ports(&name, child)?);
+                    }
                     if let Some(ports) = child.children() {
                         for port_node in ports.nodes() {
-                            if port_node.name().value() == "port"
-                                && let Some(port) = parse_port(port_node)
-                            {
-                                service.ports.push(port);
+                            if port_node.name().value() == "port" {
+                                service.ports.extend(parse_ports(&name, port_node)?);
                             }
                         }
                     }
                 }
                 "port" => {
-                    if let Some(port) = parse_port(child) {
-                        service.ports.push(port);
-                    }
+                    service.ports.extend(parse_ports(&name, child)?);
                 }
                 // env と environment 両方をサポート (#12)
                 "environment" | "env" => {
@@ -219,20 +308,431 @@ pub fn parse_service(node: &KdlNode) -> Result<(String, Service)> {
                 "deploy" => {
                     service.deploy = Some(parse_deploy(child));
                 }
+                // ログドライバ・ローテーション設定
+                "logging" => {
+                    service.logging = Some(parse_logging(&name, child)?);
+                }
+                "container_name" => {
+                    service.container_name = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                // ステージごとの有効・無効
+                "enabled" => {
+                    service.enabled = child.entries().first().and_then(|e| e.value().as_bool());
+                }
+                "when" => {
+                    service.when = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                // セキュリティ設定
+                "user" => {
+                    service.user = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "cap_add" => {
+                    service.cap_add = string_arguments(child);
+                }
+                "cap_drop" => {
+                    service.cap_drop = string_arguments(child);
+                }
+                "security_opt" => {
+                    service.security_opt = string_arguments(child);
+                }
+                "read_only" => {
+                    // 引数なしの `read_only` は有効化とみなす
+                    service.read_only = Some(
+                        child
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_bool())
+                            .unwrap_or(true),
+                    );
+                }
+                "privileged" => {
+                    service.privileged = Some(
+                        child
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_bool())
+                            .unwrap_or(true),
+                    );
+                }
+                // 対話用（引数なしは有効化）
+                "tty" | "stdin_open" => {
+                    let enabled = Some(
+                        child
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_bool())
+                            .unwrap_or(true),
+                    );
+                    if child.name().value() == "tty" {
+                        service.tty = enabled;
+                    } else {
+                        service.stdin_open = enabled;
+                    }
+                }
+                // 依存待ちのエントリーポイント注入（引数なしは有効化）
+                "inject_wait" => {
+                    service.inject_wait = Some(
+                        child
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_bool())
+                            .unwrap_or(true),
+                    );
+                }
+                // 依存サービスの接続先を env に注入（引数なしは有効化）
+                "inject_links" => {
+                    service.inject_links = Some(
+                        child
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_bool())
+                            .unwrap_or(true),
+                    );
+                }
+                // tmpfs / 共有メモリ
+                "tmpfs" => {
+                    service.tmpfs.push(parse_tmpfs(&name, child)?);
+                }
+                "shm_size" => {
+                    if let Some(size) = child.entries().first().and_then(|e| e.value().as_string())
+                    {
+                        service.shm_size = Some(validate_size(&name, "shm_size", size)?);
+                    }
+                }
+                "stop_signal" => {
+                    if let Some(signal) =
+                        child.entries().first().and_then(|e| e.value().as_string())
+                    {
+                        service.stop_signal = Some(validate_stop_signal(&name, signal)?);
+                    }
+                }
+                "stop_grace_period" => {
+                    if let Some(entry) = child.entries().first() {
+                        service.stop_grace_period = Some(parse_grace_period(&name, entry.value())?);
+                    }
+                }
+                // 設定ファイルのマウント
+                "config" => {
+                    service.configs.push(parse_config_mount(&name, child)?);
+                }
+                // シークレットのファイルマウント
+                "secret" => {
+                    service.secrets.push(parse_secret_mount(&name, child)?);
+                }
+                // リソース制限・カーネルパラメータ
+                "ulimits" => {
+                    if let Some(limits) = child.children() {
+                        for limit_node in limits.nodes() {
+                            service.ulimits.push(parse_ulimit(&name, limit_node)?);
+                        }
+                    }
+                }
+                "sysctls" => {
+                    if let Some(sysctls) = child.children() {
+                        for sysctl_node in sysctls.nodes() {
+                            let key = sysctl_node.name().value().to_string();
+                            let value = sysctl_node
+                                .entries()
+                                .first()
+                                .and_then(|e| match e.value().as_string() {
+                                    Some(s) => Some(s.to_string()),
+                                    None => e.value().as_integer().map(|i| i.to_string()),
+                                })
+                                .ok_or_else(|| {
+                                    FlowError::InvalidConfig(format!(
+                                        "service '{name}': sysctl '{key}' requires a value"
+                                    ))
+                                })?;
+                            service.sysctls.insert(key, value);
+                        }
+                    }
+                }
+                "test" => {
+                    service.test = Some(parse_test_config(&name, child)?);
+                }
+                "network_mode" => {
+                    let value = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .unwrap_or_default();
+                    let mode = NetworkMode::parse(value).ok_or_else(|| {
+                        FlowError::InvalidConfig(format!(
+                            "service '{name}': invalid network_mode '{value}' (expected host, none or container:<service>)"
+                        ))
+                    })?;
+                    if mode == NetworkMode::Container(name.clone()) {
+                        return Err(FlowError::InvalidConfig(format!(
+                            "service '{name}': network_mode cannot reference itself"
+                        )));
+                    }
+                    service.network_mode = Some(mode);
+                }
                 _ => {}
             }
         }
     }
 
+    service.add_network_dependency();
+
     // プロパティで設定されなかった場合、子ノードの command も読む
     // （子ノード match 内で既に処理されているので、ここでは不要）
 
     // 注意: イメージ名の自動推測は parse_kdl_string() で全てのマージが完了した後に行う
     // ここでは行わない（マージ時に上書きされてしまうため）
 
+    if let Some(container_name) = &service.container_name {
+        validate_name_template(
+            container_name,
+            &format!("service '{}': container_name", name),
+            crate::model::CONTAINER_NAME_VARS,
+        )?;
+    }
+
     Ok((name, service))
 }
 
+/// ノードの文字列引数をすべて取得（`cap_add "NET_ADMIN" "SYS_TIME"` 形式）
+fn string_arguments(node: &KdlNode) -> Vec<String> {
+    node.entries()
+        .iter()
+        .filter(|e| e.name().is_none())
+        .filter_map(|e| e.value().as_string().map(|s| s.to_string()))
+        .collect()
+}
+
+/// コンテナの作業ディレクトリは絶対パスのみ（Docker が相対パスを受け付けない）
+fn validate_working_dir(service_name: &str, dir: &str) -> Result<String> {
+    if !dir.starts_with('/') {
+        return Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': working_dir must be an absolute path (got '{dir}')"
+        )));
+    }
+    Ok(dir.to_string())
+}
+
+/// FleetFlow と Compose 互換のグループ化が使うラベルの接頭辞
+const RESERVED_LABEL_PREFIXES: &[&str] = &["fleetflow.", "com.docker.compose."];
+
+/// `labels { "com.example.team" "api" }` をパース
+///
+/// 値は文字列・数値・真偽値を受け付ける。管理用ラベルと衝突するキーはエラー。
+fn parse_labels(service_name: &str, node: &KdlNode) -> Result<Vec<(String, String)>> {
+    let Some(children) = node.children() else {
+        return Ok(Vec::new());
+    };
+    children
+        .nodes()
+        .iter()
+        .map(|label_node| {
+            let key = label_node.name().value().to_string();
+            if let Some(prefix) = RESERVED_LABEL_PREFIXES
+                .iter()
+                .find(|prefix| key.starts_with(*prefix))
+            {
+                return Err(FlowError::InvalidConfig(format!(
+                    "service '{service_name}': label '{key}' uses the reserved prefix '{prefix}'"
+                )));
+            }
+            let value = label_node
+                .entries()
+                .first()
+                .and_then(|e| match e.value() {
+                    KdlValue::String(s) => Some(s.clone()),
+                    KdlValue::Integer(i) => Some(i.to_string()),
+                    KdlValue::Bool(b) => Some(b.to_string()),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    FlowError::InvalidConfig(format!(
+                        "service '{service_name}': label '{key}' requires a value"
+                    ))
+                })?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// サイズ文字列（"64m" 等）を検証
+fn validate_size(service_name: &str, field: &str, size: &str) -> Result<String> {
+    if parse_byte_size(size).is_none() {
+        return Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': invalid {field} '{size}' (expected e.g. \"64m\", \"1g\", \"512k\")"
+        )));
+    }
+    Ok(size.to_string())
+}
+
+/// 停止シグナル名を検証して大文字に正規化（"SIGQUIT", "QUIT", "9" を受け付ける）
+fn validate_stop_signal(service_name: &str, signal: &str) -> Result<String> {
+    let signal = signal.trim().to_uppercase();
+    if signal.is_empty()
+        || !signal
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-')
+    {
+        return Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': invalid stop_signal '{signal}' (expected e.g. \"SIGQUIT\")"
+        )));
+    }
+    Ok(signal)
+}
+
+/// 停止猶予時間を秒数でパース（整数、または "30s" / "2m" 形式）
+fn parse_grace_period(service_name: &str, value: &KdlValue) -> Result<u64> {
+    let invalid = || {
+        FlowError::InvalidConfig(format!(
+            "service '{service_name}': invalid stop_grace_period '{value}' (expected seconds or e.g. \"30s\", \"2m\")"
+        ))
+    };
+
+    if let Some(secs) = value.as_integer() {
+        return u64::try_from(secs).map_err(|_| invalid());
+    }
+    let text = value.as_string().ok_or_else(invalid)?.trim();
+    let (num, multiplier) = if let Some(num) = text.strip_suffix('m') {
+        (num, 60)
+    } else if let Some(num) = text.strip_suffix('s') {
+        (num, 1)
+    } else {
+        (text, 1)
+    };
+    num.parse::<u64>()
+        .map(|n| n * multiplier)
+        .map_err(|_| invalid())
+}
+
+/// tmpfs ノードをパース
+///
+/// ```kdl
+/// tmpfs "/tmp" size="64m" mode="1777"
+/// ```
+fn parse_tmpfs(service_name: &str, node: &KdlNode) -> Result<TmpfsMount> {
+    let target = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!(
+                "service '{service_name}': tmpfs requires a target path"
+            ))
+        })?;
+
+    let size = node
+        .get("size")
+        .and_then(|v| v.as_string())
+        .map(|size| validate_size(service_name, "tmpfs size", size))
+        .transpose()?;
+    let mode = node.get("mode").and_then(|v| match v.as_string() {
+        Some(s) => Some(s.to_string()),
+        None => v.as_integer().map(|i| i.to_string()),
+    });
+
+    Ok(TmpfsMount {
+        target: PathBuf::from(target),
+        size,
+        mode,
+    })
+}
+
+/// ulimits ブロック内のノードをパース
+///
+/// ```kdl
+/// nofile soft=65536 hard=65536
+/// nproc 4096            // soft = hard
+/// ```
+/// testブロックをパース（`command` は必須）
+fn parse_test_config(service_name: &str, node: &KdlNode) -> Result<TestConfig> {
+    let mut test = TestConfig::default();
+    let string_arg = |child: &KdlNode| {
+        child
+            .entries()
+            .first()
+            .and_then(|e| e.value().as_string())
+            .map(String::from)
+    };
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            match child.name().value() {
+                "command" => test.command = string_arg(child).unwrap_or_default(),
+                "image" => test.image = string_arg(child),
+                "workdir" => test.workdir = string_arg(child),
+                "env" | "environment" => {
+                    if let Some(vars) = child.children() {
+                        for var in vars.nodes() {
+                            test.env.insert(
+                                var.name().value().to_string(),
+                                string_arg(var).unwrap_or_default(),
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if test.command.trim().is_empty() {
+        return Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': test block requires a command"
+        )));
+    }
+    Ok(test)
+}
+
+fn parse_ulimit(service_name: &str, node: &KdlNode) -> Result<Ulimit> {
+    let limit_name = node.name().value().to_string();
+    let single = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_integer());
+    let soft = node
+        .get("soft")
+        .and_then(|v| v.as_integer())
+        .or(single)
+        .map(|v| v as i64);
+    let hard = node
+        .get("hard")
+        .and_then(|v| v.as_integer())
+        .or(single)
+        .map(|v| v as i64);
+
+    match (soft, hard) {
+        (Some(soft), Some(hard)) if soft <= hard => Ok(Ulimit {
+            name: limit_name,
+            soft,
+            hard,
+        }),
+        (Some(soft), Some(hard)) => Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': ulimit '{limit_name}' soft ({soft}) exceeds hard ({hard})"
+        ))),
+        // 片方のみ指定された場合は同じ値を使う
+        (Some(value), None) | (None, Some(value)) => Ok(Ulimit {
+            name: limit_name,
+            soft: value,
+            hard: value,
+        }),
+        (None, None) => Err(FlowError::InvalidConfig(format!(
+            "service '{service_name}': ulimit '{limit_name}' requires soft/hard values"
+        ))),
+    }
+}
+
 /// buildブロックをパース（ネスト記法用）
 pub fn parse_build_config(doc: &KdlDocument) -> BuildConfig {
     let mut config = BuildConfig::default();
@@ -278,6 +778,24 @@ pub fn parse_build_config(doc: &KdlDocument) -> BuildConfig {
                     config.image_tag = Some(tag.to_string());
                 }
             }
+            "remote" => {
+                // 引数なしの `remote` は有効化とみなす
+                config.remote = node
+                    .entries()
+                    .first()
+                    .and_then(|e| e.value().as_bool())
+                    .unwrap_or(true);
+            }
+            "watch_paths" => {
+                // `watch_paths "a/**" "b/**"` と複数回の記述のどちらも可
+                config.watch_paths.extend(
+                    node.entries()
+                        .iter()
+                        .filter(|e| e.name().is_none())
+                        .filter_map(|e| e.value().as_string())
+                        .map(String::from),
+                );
+            }
             _ => {}
         }
     }
@@ -537,6 +1055,103 @@ pub fn parse_deploy(node: &KdlNode) -> DeployConfig {
     config
 }
 
+/// logging ノードをパース
+///
+/// プロパティ形式とブロック形式の両方をサポート:
+/// ```kdl
+/// logging driver="json-file" max_size="10m" max_file=3
+///
+/// logging {
+///     driver "journald"
+///     options {
+///         tag "api"
+///     }
+/// }
+/// ```
+///
+/// `max_file` は 1 以上の整数のみ受け付ける（負数を u32 に変換して巨大な値に
+/// ならないよう、パース時に弾く）。
+pub fn parse_logging(name: &str, node: &KdlNode) -> Result<LoggingConfig> {
+    let mut config = LoggingConfig::default();
+
+    for entry in node.entries() {
+        if let Some(key) = entry.name() {
+            match key.value() {
+                "driver" => {
+                    config.driver = entry.value().as_string().map(|s| s.to_string());
+                }
+                "max_size" => {
+                    config.max_size = entry.value().as_string().map(|s| s.to_string());
+                }
+                "max_file" => {
+                    config.max_file = Some(parse_max_file(name, entry.value())?);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            match child.name().value() {
+                "driver" => {
+                    config.driver = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "max_size" => {
+                    config.max_size = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "max_file" => {
+                    config.max_file = child
+                        .entries()
+                        .first()
+                        .map(|e| parse_max_file(name, e.value()))
+                        .transpose()?;
+                }
+                "options" => {
+                    if let Some(opts) = child.children() {
+                        for opt_node in opts.nodes() {
+                            let key = opt_node.name().value().to_string();
+                            let value = opt_node
+                                .entries()
+                                .first()
+                                .map(|e| match e.value().as_string() {
+                                    Some(s) => s.to_string(),
+                                    None => e.value().to_string(),
+                                })
+                                .unwrap_or_default();
+                            config.options.insert(key, value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// logging の `max_file`（保持するログファイル数、1 以上）
+fn parse_max_file(name: &str, value: &KdlValue) -> Result<u32> {
+    value
+        .as_integer()
+        .filter(|n| *n >= 1)
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!(
+                "service '{name}': logging max_file must be a positive integer (got {value})"
+            ))
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -879,4 +1494,352 @@ mod tests {
         // 4回目: 16000ms -> max_delay(10000ms)でキャップ
         assert_eq!(config.delay_for_attempt(4), 10000);
     }
+
+    #[test]
+    fn test_parse_logging_block_style() {
+        let kdl = r#"
+            service "api" {
+                image "myapp:latest"
+                logging {
+                    driver "json-file"
+                    max_size "10m"
+                    max_file 3
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        let logging = service.logging.unwrap();
+        assert_eq!(logging.driver, Some("json-file".to_string()));
+        assert_eq!(logging.max_size, Some("10m".to_string()));
+        assert_eq!(logging.max_file, Some(3));
+    }
+
+    #[test]
+    fn test_parse_logging_rejects_non_positive_max_file() {
+        for kdl in [
+            r#"service "api" { logging max_file=-1 }"#,
+            r#"service "api" { logging { max_file 0 } }"#,
+            r#"service "api" { logging { max_file "3" } }"#,
+        ] {
+            let doc: KdlDocument = kdl.parse().unwrap();
+            let err = parse_service(doc.nodes().first().unwrap()).unwrap_err();
+            assert!(err.to_string().contains("max_file"), "{kdl}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_logging_property_style_with_options() {
+        let kdl = r#"
+            service "api" {
+                image "myapp:latest"
+                logging driver="syslog" {
+                    options {
+                        syslog-address "udp://192.168.0.42:514"
+                        tag "api"
+                    }
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        let logging = service.logging.unwrap();
+        assert_eq!(logging.driver, Some("syslog".to_string()));
+        assert_eq!(logging.max_size, None);
+        assert_eq!(
+            logging.options.get("syslog-address"),
+            Some(&"udp://192.168.0.42:514".to_string())
+        );
+        assert_eq!(logging.options.get("tag"), Some(&"api".to_string()));
+    }
+
+    #[test]
+    fn test_parse_security_options() {
+        let kdl = r#"
+            service "api" {
+                image "myapp:latest"
+                user "1000:1000"
+                cap_drop "ALL"
+                cap_add "NET_BIND_SERVICE" "CHOWN"
+                read_only #true
+                security_opt "no-new-privileges:true"
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(service.user, Some("1000:1000".to_string()));
+        assert_eq!(service.cap_drop, vec!["ALL".to_string()]);
+        assert_eq!(
+            service.cap_add,
+            vec!["NET_BIND_SERVICE".to_string(), "CHOWN".to_string()]
+        );
+        assert_eq!(service.read_only, Some(true));
+        assert_eq!(
+            service.security_opt,
+            vec!["no-new-privileges:true".to_string()]
+        );
+        assert_eq!(service.privileged, None);
+    }
+
+    #[test]
+    fn test_parse_privileged_flag_without_value() {
+        let kdl = r#"
+            service "dind" user="root" {
+                image "docker:dind"
+                privileged
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(service.user, Some("root".to_string()));
+        assert_eq!(service.privileged, Some(true));
+    }
+
+    #[test]
+    fn test_parse_tty_and_stdin_open() {
+        let kdl = r#"
+            service "repl" tty=#true {
+                image "python:3.12"
+                stdin_open
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(service.tty, Some(true));
+        assert_eq!(service.stdin_open, Some(true));
+    }
+
+    #[test]
+    fn test_parse_inject_wait() {
+        let kdl = r#"
+            service "app" {
+                image "app:latest"
+                depends_on "db"
+                inject_wait
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(service.inject_wait, Some(true));
+
+        let doc: KdlDocument = r#"service "app" inject_wait=#false"#.parse().unwrap();
+        let (_, service) = parse_service(doc.nodes().first().unwrap()).unwrap();
+        assert_eq!(service.inject_wait, Some(false));
+    }
+
+    #[test]
+    fn test_parse_tmpfs_and_shm_size() {
+        let kdl = r#"
+            service "chrome" {
+                image "browserless/chrome:latest"
+                tmpfs "/tmp" size="64m"
+                tmpfs "/run" mode="1777"
+                shm_size "1g"
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(service.tmpfs.len(), 2);
+        assert_eq!(service.tmpfs[0].target, PathBuf::from("/tmp"));
+        assert_eq!(service.tmpfs[0].size, Some("64m".to_string()));
+        assert_eq!(service.tmpfs[1].mode, Some("1777".to_string()));
+        assert_eq!(service.shm_size, Some("1g".to_string()));
+    }
+
+    #[test]
+    fn test_parse_invalid_size_is_error() {
+        let kdl = r#"
+            service "db" {
+                image "postgres:16"
+                tmpfs "/tmp" size="lots"
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+        assert!(parse_service(node).is_err());
+
+        let kdl = r#"
+            service "db" shm_size="64x" {
+                image "postgres:16"
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+        assert!(parse_service(node).is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("1024"), Some(1024));
+        assert_eq!(parse_byte_size("512k"), Some(512 * 1024));
+        assert_eq!(parse_byte_size("64m"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_byte_size("2GB"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_byte_size("m"), None);
+        assert_eq!(parse_byte_size("10t"), None);
+        assert_eq!(parse_byte_size(""), None);
+    }
+
+    #[test]
+    fn test_parse_ulimits_and_sysctls() {
+        let kdl = r#"
+            service "redis" {
+                image "redis:7"
+                ulimits {
+                    nofile soft=65536 hard=65536
+                    nproc 4096
+                }
+                sysctls {
+                    "net.core.somaxconn" "1024"
+                    "net.ipv4.tcp_syncookies" 0
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(
+            service.ulimits,
+            vec![
+                Ulimit {
+                    name: "nofile".to_string(),
+                    soft: 65536,
+                    hard: 65536,
+                },
+                Ulimit {
+                    name: "nproc".to_string(),
+                    soft: 4096,
+                    hard: 4096,
+                },
+            ]
+        );
+        assert_eq!(
+            service.sysctls.get("net.core.somaxconn"),
+            Some(&"1024".to_string())
+        );
+        assert_eq!(
+            service.sysctls.get("net.ipv4.tcp_syncookies"),
+            Some(&"0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_ulimit_soft_exceeds_hard_is_error() {
+        let kdl = r#"
+            service "nginx" {
+                image "nginx:latest"
+                ulimits {
+                    nofile soft=100000 hard=1024
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        assert!(parse_service(node).is_err());
+    }
+
+    #[test]
+    fn test_parse_process_settings() {
+        let kdl = r#"
+            service "api" working_dir="/app" {
+                image "node:22"
+                entrypoint "/usr/bin/tini" "--"
+                command "npm" "run" "dev server"
+                hostname "api-host"
+                labels {
+                    "com.example.team" "api"
+                    "com.example.tier" 2
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let node = doc.nodes().first().unwrap();
+
+        let (_, service) = parse_service(node).unwrap();
+
+        assert_eq!(
+            service.entrypoint,
+            Some(vec!["/usr/bin/tini".to_string(), "--".to_string()])
+        );
+        assert_eq!(service.command.as_deref(), Some("npm run 'dev server'"));
+        assert_eq!(
+            service.command_args().unwrap(),
+            vec!["npm", "run", "dev server"]
+        );
+        assert_eq!(service.working_dir.as_deref(), Some("/app"));
+        assert_eq!(service.hostname.as_deref(), Some("api-host"));
+        assert_eq!(service.labels["com.example.team"], "api");
+        assert_eq!(service.labels["com.example.tier"], "2");
+    }
+
+    #[test]
+    fn test_parse_entrypoint_string_is_split() {
+        let kdl = r#"service "app" entrypoint="/bin/sh -c""#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let (_, service) = parse_service(doc.nodes().first().unwrap()).unwrap();
+
+        assert_eq!(
+            service.entrypoint,
+            Some(vec!["/bin/sh".to_string(), "-c".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_reserved_label_is_error() {
+        let kdl = r#"
+            service "api" {
+                labels {
+                    "fleetflow.stage" "prod"
+                }
+            }
+        "#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let err = parse_service(doc.nodes().first().unwrap()).unwrap_err();
+
+        assert!(err.to_string().contains("reserved prefix 'fleetflow.'"));
+    }
+
+    #[test]
+    fn test_parse_relative_working_dir_is_error() {
+        let kdl = r#"service "api" working_dir="app""#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+
+        assert!(parse_service(doc.nodes().first().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_split_and_join_command_line() {
+        assert_eq!(
+            split_command_line(r#"sh -c "echo 'hi there'" a\ b"#),
+            vec!["sh", "-c", "echo 'hi there'", "a b"]
+        );
+        assert_eq!(split_command_line("  npm   start "), vec!["npm", "start"]);
+        assert_eq!(split_command_line("echo ''"), vec!["echo", ""]);
+
+        let args = ["sh", "-c", "echo \"it's\" done", "", "plain"];
+        assert_eq!(split_command_line(&join_command_line(&args)), args);
+    }
 }
@@ -0,0 +1,99 @@
+//! setup / step ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{SetupStep, order_setup_steps};
+use kdl::KdlNode;
+
+/// カンマ区切りのプロパティ値を分割
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// トップレベル `setup` ブロックをパース
+///
+/// ```kdl
+/// setup {
+///     step "init-db" run="./scripts/init-db.sh" retries=3 retry_delay=5 timeout=120
+///     step "seed-data" run="./scripts/seed.sh" after="init-db" stage="local,dev"
+/// }
+/// ```
+pub fn parse_setup(node: &KdlNode) -> Result<Vec<SetupStep>> {
+    let mut steps: Vec<SetupStep> = Vec::new();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if child.name().value() != "step" {
+                continue;
+            }
+            let name = child
+                .entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string())
+                .ok_or_else(|| FlowError::InvalidConfig("setup step requires a name".to_string()))?
+                .to_string();
+
+            let run = child
+                .get("run")
+                .and_then(|v| v.as_string())
+                .ok_or_else(|| {
+                    FlowError::InvalidConfig(format!("setup step '{name}' requires run"))
+                })?
+                .to_string();
+
+            let step = SetupStep {
+                run,
+                after: child
+                    .get("after")
+                    .and_then(|v| v.as_string())
+                    .map(split_list)
+                    .unwrap_or_default(),
+                retries: child
+                    .get("retries")
+                    .and_then(|v| v.as_integer())
+                    .map(|v| v as u32)
+                    .unwrap_or(0),
+                retry_delay: child
+                    .get("retry_delay")
+                    .and_then(|v| v.as_integer())
+                    .map(|v| v as u64),
+                timeout: child
+                    .get("timeout")
+                    .and_then(|v| v.as_integer())
+                    .map(|v| v as u64),
+                skip_if: child
+                    .get("skip_if")
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.to_string()),
+                stages: child
+                    .get("stage")
+                    .and_then(|v| v.as_string())
+                    .map(split_list)
+                    .unwrap_or_default(),
+                name,
+            };
+
+            if steps.iter().any(|s| s.name == step.name) {
+                return Err(FlowError::InvalidConfig(format!(
+                    "setup step '{}' is defined more than once",
+                    step.name
+                )));
+            }
+            steps.push(step);
+        }
+    }
+
+    Ok(steps)
+}
+
+/// `after` の参照先と循環を検証
+pub fn validate_setup(steps: &[SetupStep]) -> Result<()> {
+    order_setup_steps(steps)
+        .map(|_| ())
+        .map_err(FlowError::InvalidConfig)
+}
@@ -0,0 +1,59 @@
+//! site ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::SiteResource;
+use kdl::KdlNode;
+
+/// site ノードをパース
+pub fn parse_site(node: &KdlNode) -> Result<(String, SiteResource)> {
+    let name = node
+        .entries()
+        .first()
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| FlowError::InvalidConfig("site requires a name".to_string()))?
+        .to_string();
+
+    let mut site = SiteResource::default();
+    let mut output = None;
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            let text = child
+                .entries()
+                .first()
+                .and_then(|e| e.value().as_string())
+                .map(|s| s.to_string());
+            let key = child.name().value();
+            let value = || {
+                text.clone().ok_or_else(|| {
+                    FlowError::InvalidConfig(format!(
+                        "site '{}': {} requires a string value",
+                        name, key
+                    ))
+                })
+            };
+            match key {
+                "build" => site.build = Some(value()?),
+                "dir" => site.dir = Some(value()?),
+                "output" => output = Some(value()?),
+                "domain" => site.domain = Some(value()?),
+                "bucket" => site.bucket = Some(value()?),
+                other => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "unknown node in site '{}': {} (expected build / dir / output / domain / bucket)",
+                        name, other
+                    )));
+                }
+            }
+        }
+    }
+
+    site.output = output.ok_or_else(|| {
+        FlowError::InvalidConfig(format!(
+            "site '{}' requires output (the directory to upload)",
+            name
+        ))
+    })?;
+
+    Ok((name, site))
+}
@@ -0,0 +1,125 @@
+//! smoke_test ノードのパース
+
+use crate::error::{FlowError, Result};
+use crate::model::{SmokeTarget, SmokeTest};
+use kdl::{KdlNode, KdlValue};
+
+/// ステージの `smoke_test` ブロックをパース
+///
+/// ```kdl
+/// smoke_test {
+///     http "https://api.example.com/health" expect=200 timeout="30s"
+///     http service="web" port="http" path="/health"
+/// }
+/// ```
+pub fn parse_smoke_tests(stage_name: &str, node: &KdlNode) -> Result<Vec<SmokeTest>> {
+    let mut tests = Vec::new();
+
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            match child.name().value() {
+                "http" => tests.push(parse_http_check(stage_name, child)?),
+                other => {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "stage '{stage_name}': unknown smoke_test check '{other}' (expected http)"
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(tests)
+}
+
+fn parse_http_check(stage_name: &str, node: &KdlNode) -> Result<SmokeTest> {
+    let target = match node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+    {
+        Some(url) => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(FlowError::InvalidConfig(format!(
+                    "stage '{stage_name}': smoke_test URL '{url}' must start with http:// or https://"
+                )));
+            }
+            SmokeTarget::Url(url.to_string())
+        }
+        None => parse_port_target(stage_name, node)?,
+    };
+
+    let expect = match node.get("expect") {
+        Some(value) => value
+            .as_integer()
+            .and_then(|code| u16::try_from(code).ok())
+            .filter(|code| (100..=599).contains(code))
+            .ok_or_else(|| {
+                FlowError::InvalidConfig(format!(
+                    "stage '{stage_name}': invalid smoke_test expect '{value}' (expected an HTTP status code)"
+                ))
+            })?,
+        None => SmokeTest::DEFAULT_EXPECT,
+    };
+
+    let timeout = match node.get("timeout") {
+        Some(value) => parse_timeout(stage_name, value)?,
+        None => SmokeTest::DEFAULT_TIMEOUT,
+    };
+
+    Ok(SmokeTest {
+        target,
+        expect,
+        timeout,
+    })
+}
+
+/// 名前付きポート参照（`service="web" port="http" path="/health"`）をパース
+fn parse_port_target(stage_name: &str, node: &KdlNode) -> Result<SmokeTarget> {
+    let prop = |key: &str| {
+        node.get(key)
+            .and_then(|v| v.as_string())
+            .map(str::to_string)
+    };
+    let (Some(service), Some(port)) = (prop("service"), prop("port")) else {
+        return Err(FlowError::InvalidConfig(format!(
+            "stage '{stage_name}': smoke_test http requires a URL or service=/port= properties"
+        )));
+    };
+    let path = prop("path").unwrap_or_else(|| "/".to_string());
+    if !path.starts_with('/') {
+        return Err(FlowError::InvalidConfig(format!(
+            "stage '{stage_name}': smoke_test path '{path}' must start with /"
+        )));
+    }
+
+    Ok(SmokeTarget::Port {
+        service,
+        port,
+        path,
+    })
+}
+
+/// タイムアウトを秒数でパース（整数、または "30s" / "2m" 形式）
+fn parse_timeout(stage_name: &str, value: &KdlValue) -> Result<u64> {
+    let invalid = || {
+        FlowError::InvalidConfig(format!(
+            "stage '{stage_name}': invalid smoke_test timeout '{value}' (expected seconds or e.g. \"30s\", \"2m\")"
+        ))
+    };
+
+    if let Some(secs) = value.as_integer() {
+        return u64::try_from(secs).map_err(|_| invalid());
+    }
+    let text = value.as_string().ok_or_else(invalid)?.trim();
+    let (num, multiplier) = if let Some(num) = text.strip_suffix('m') {
+        (num, 60)
+    } else if let Some(num) = text.strip_suffix('s') {
+        (num, 1)
+    } else {
+        (text, 1)
+    };
+    num.parse::<u64>()
+        .map(|n| n * multiplier)
+        .map_err(|_| invalid())
+}
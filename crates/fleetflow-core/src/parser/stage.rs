@@ -1,8 +1,11 @@
 //! ステージノードのパース
 
 use crate::error::{FlowError, Result};
-use crate::model::{Backend, Service, Stage};
+use crate::model::{Backend, IMAGE_TEMPLATE_VARS, RegistryAuthEnv, Service, Stage};
+use crate::parser::log_shipping::parse_log_shipping;
+use crate::parser::parse_name_template;
 use crate::parser::service::parse_service;
+use crate::parser::smoke_test::parse_smoke_tests;
 use kdl::KdlNode;
 use std::collections::HashMap;
 
@@ -59,30 +62,56 @@ pub fn parse_stage(node: &KdlNode) -> Result<(String, Stage, HashMap<String, Ser
                         }
                     }
                 }
-                // コンテナレジストリURL
+                // コンテナレジストリURL（ブロック形式では認証情報の環境変数も指定できる）
                 "registry" => {
-                    stage.registry = child
-                        .entries()
-                        .first()
-                        .and_then(|e| e.value().as_string())
-                        .map(|s| s.to_string());
+                    (stage.registry, stage.registry_auth) = parse_registry(child)?;
                 }
-                // 実行 backend（WS2: docker | quadlet | compose、未宣言時 docker）
-                "backend" => {
+                // ビルドイメージ名のテンプレート（トップレベルより優先）
+                "image_template" => {
+                    stage.image_template = Some(parse_name_template(
+                        child,
+                        "image_template",
+                        IMAGE_TEMPLATE_VARS,
+                    )?);
+                }
+                // 実行 backend（WS2: docker | quadlet | compose | kubernetes | nomad、未宣言時 docker）
+                // `target` は `backend` の別名
+                "backend" | "target" => {
                     let raw = child
                         .entries()
                         .first()
                         .and_then(|e| e.value().as_string())
                         .ok_or_else(|| {
-                            FlowError::InvalidConfig(
-                                "backend requires a value (docker|quadlet|compose)".to_string(),
-                            )
+                            FlowError::InvalidConfig(format!(
+                                "{} requires a value (docker|quadlet|compose|kubernetes|nomad)",
+                                child.name().value()
+                            ))
                         })?;
                     stage.backend = Backend::parse(raw).ok_or_else(|| {
                         FlowError::InvalidConfig(format!(
-                            "unknown backend '{raw}' (expected docker|quadlet|compose)"
+                            "unknown backend '{raw}' (expected docker|quadlet|compose|kubernetes|nomad)"
                         ))
                     })?;
+                    stage.backend_address = child
+                        .get("address")
+                        .and_then(|v| v.as_string())
+                        .map(|s| s.to_string());
+                }
+                // ログ集約（opt-in、log-shipper サービスを自動追加）
+                "log_shipping" => {
+                    stage.log_shipping = Some(parse_log_shipping(child)?);
+                }
+                // DNS ドメイン（cloudflare provider の domain を上書き）
+                "dns_domain" => {
+                    stage.dns_domain = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                // デプロイ後のスモークテスト（複数ブロックは結合）
+                "smoke_test" => {
+                    stage.smoke_tests.extend(parse_smoke_tests(&name, child)?);
                 }
                 _ => {}
             }
@@ -91,3 +120,57 @@ pub fn parse_stage(node: &KdlNode) -> Result<(String, Stage, HashMap<String, Ser
 
     Ok((name, stage, stage_services))
 }
+
+/// ステージの registry ノードをパース
+///
+/// `registry "ghcr.io/acme"` のほか、プロパティ形式・ブロック形式で
+/// `url` / `username_env` / `password_env` を指定できる。
+fn parse_registry(node: &KdlNode) -> Result<(Option<String>, Option<RegistryAuthEnv>)> {
+    let mut url = None;
+    let mut username_env = None;
+    let mut password_env = None;
+
+    let mut fields: Vec<(Option<String>, String)> = node
+        .entries()
+        .iter()
+        .filter_map(|e| {
+            let key = e.name().map(|n| n.value().to_string());
+            Some((key, e.value().as_string()?.to_string()))
+        })
+        .collect();
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            if let Some(value) = child.entries().first().and_then(|e| e.value().as_string()) {
+                fields.push((Some(child.name().value().to_string()), value.to_string()));
+            }
+        }
+    }
+
+    for (key, value) in fields {
+        match key.as_deref() {
+            None | Some("url") => url = Some(value),
+            Some("username_env") => username_env = Some(value),
+            Some("password_env") => password_env = Some(value),
+            _ => {}
+        }
+    }
+
+    let auth = match (username_env, password_env) {
+        (Some(username_env), Some(password_env)) => Some(RegistryAuthEnv {
+            username_env,
+            password_env,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(FlowError::InvalidConfig(
+                "registry requires both username_env and password_env".to_string(),
+            ));
+        }
+    };
+    if auth.is_some() && url.is_none() {
+        return Err(FlowError::InvalidConfig(
+            "registry with username_env / password_env requires url".to_string(),
+        ));
+    }
+    Ok((url, auth))
+}
@@ -1,5 +1,8 @@
 use super::*;
-use crate::model::{Port, Protocol, ServiceType, Volume};
+use crate::model::{
+    AppProtocol, CredentialRef, NetworkMode, Port, Protocol, RestartPolicy, ServiceType,
+    SmokeTarget, Volume,
+};
 use club_kdl::{KdlDeserialize, KdlNodeExt, KdlSerialize};
 
 #[test]
@@ -300,6 +303,120 @@ fn test_parse_port_with_host_ip() {
     assert_eq!(port.host_ip, Some("127.0.0.1".to_string()));
 }
 
+#[test]
+fn test_parse_named_port() {
+    let kdl = r#"
+        service "web" {
+            image "nginx:latest"
+            ports {
+                port 8080 host=18080 name="http" protocol="http"
+                port 9090 name="metrics"
+                port 53 protocol="udp"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["web"];
+
+    let http = service.port_by_name("http").unwrap();
+    assert_eq!((http.host, http.container), (18080, 8080));
+    assert_eq!(http.protocol, Protocol::Tcp);
+    assert_eq!(http.app_protocol, Some(AppProtocol::Http));
+    assert_eq!(http.url().as_deref(), Some("http://localhost:18080"));
+
+    let metrics = service.port_by_name("metrics").unwrap();
+    assert_eq!((metrics.host, metrics.container), (9090, 9090));
+    assert_eq!(metrics.app_protocol, None);
+
+    assert_eq!(service.ports[2].protocol, Protocol::Udp);
+    assert_eq!(service.ports[2].url(), None);
+    assert!(service.port_by_name("admin").is_none());
+}
+
+#[test]
+fn test_parse_port_ranges() {
+    let kdl = r#"
+        service "media" {
+            image "media:latest"
+            ports "7000-7002" protocol="udp"
+            ports {
+                port "18000-18001" "8000-8001"
+                port 9090 9090
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let ports = &flow.services["media"].ports;
+    let mapped: Vec<(u16, u16, Protocol)> = ports
+        .iter()
+        .map(|p| (p.host, p.container, p.protocol.clone()))
+        .collect();
+    assert_eq!(
+        mapped,
+        vec![
+            (7000, 7000, Protocol::Udp),
+            (7001, 7001, Protocol::Udp),
+            (7002, 7002, Protocol::Udp),
+            (18000, 8000, Protocol::Tcp),
+            (18001, 8001, Protocol::Tcp),
+            (9090, 9090, Protocol::Tcp),
+        ]
+    );
+
+    for (invalid, message) in [
+        (r#"port "7010-7000""#, "invalid port range '7010-7000'"),
+        (r#"port "7000-7010" "8000-8005""#, "different lengths"),
+        (r#"port "7000-7001" name="rtp""#, "cannot have a name"),
+    ] {
+        let kdl = format!("service \"media\" {{\n image \"media\"\n {invalid}\n}}");
+        let err = parse_kdl_string(&kdl, "test".to_string()).unwrap_err();
+        assert!(err.to_string().contains(message), "{invalid}: {err}");
+    }
+}
+
+#[test]
+fn test_parse_network_mode() {
+    let kdl = r#"
+        service "vpn" {
+            image "wireguard"
+        }
+        service "app" {
+            image "app"
+            depends_on "db"
+            network_mode "container:vpn"
+        }
+        service "sniffer" {
+            image "tcpdump"
+            network_mode "host"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let app = &flow.services["app"];
+    assert_eq!(
+        app.network_mode,
+        Some(NetworkMode::Container("vpn".to_string()))
+    );
+    // 共有先は depends_on に自動追加される
+    assert_eq!(app.depends_on, vec!["db", "vpn"]);
+    assert_eq!(
+        flow.services["sniffer"].network_mode,
+        Some(NetworkMode::Host)
+    );
+
+    for (invalid, message) in [
+        (r#"network_mode "bridge""#, "invalid network_mode 'bridge'"),
+        (r#"network_mode "container:""#, "invalid network_mode"),
+        (r#"network_mode "container:app""#, "cannot reference itself"),
+    ] {
+        let kdl = format!("service \"app\" {{\n image \"app\"\n {invalid}\n}}");
+        let err = parse_kdl_string(&kdl, "test".to_string()).unwrap_err();
+        assert!(err.to_string().contains(message), "{invalid}: {err}");
+    }
+}
+
 #[test]
 fn test_parse_minimal_service() {
     let kdl = r#"
@@ -533,7 +650,7 @@ fn test_parse_stage_backend_invalid_is_error() {
         service "api" { image "node:20" }
 
         stage "live" {
-            backend "k8s"
+            backend "swarm"
             service "api"
         }
     "#;
@@ -542,270 +659,995 @@ fn test_parse_stage_backend_invalid_is_error() {
 }
 
 #[test]
-fn test_parse_full_cloud_config() {
+fn test_parse_stage_target_kubernetes() {
+    // `target` は `backend` の別名、`k8s` は `kubernetes` の別名
     let kdl = r#"
-        project "creo-memories"
+        service "api" { image "node:20" }
 
-        provider "sakura-cloud" {
-            zone "tk1a"
+        stage "cluster" {
+            target "kubernetes"
+            service "api"
         }
 
-        server "creo-vps" {
-            provider "sakura-cloud"
-            plan "2core-4gb"
-            disk_size 100
+        stage "cluster2" {
+            backend "k8s"
+            service "api"
         }
+    "#;
 
-        service "surrealdb" {
-            image "surrealdb/surrealdb"
-            version "latest"
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(
+        flow.stages["cluster"].backend,
+        crate::model::Backend::Kubernetes
+    );
+    assert_eq!(
+        flow.stages["cluster2"].backend,
+        crate::model::Backend::Kubernetes
+    );
+}
+
+#[test]
+fn test_parse_stage_target_nomad_with_address() {
+    let kdl = r#"
+        service "api" { image "node:20" }
+
+        stage "prod" {
+            target "nomad" address="http://nomad.internal:4646"
+            service "api"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let stage = &flow.stages["prod"];
+    assert_eq!(stage.backend, crate::model::Backend::Nomad);
+    assert_eq!(
+        stage.backend_address.as_deref(),
+        Some("http://nomad.internal:4646")
+    );
+}
+
+#[test]
+fn test_parse_stage_log_shipping() {
+    let kdl = r#"
+        service "api" { image "node:20" }
+
+        stage "prod" {
+            service "api"
+            log_shipping agent="vector" sink="loki" endpoint="http://loki:3100"
         }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let log_shipping = flow.stages["prod"].log_shipping.as_ref().unwrap();
+    assert_eq!(log_shipping.agent, crate::model::LogShippingAgent::Vector);
+    assert_eq!(log_shipping.sink, crate::model::LogSinkKind::Loki);
+    assert_eq!(log_shipping.endpoint.as_deref(), Some("http://loki:3100"));
+}
 
+#[test]
+fn test_parse_stage_log_shipping_block_style() {
+    let kdl = r#"
         stage "live" {
-            server "creo-vps"
-            service "surrealdb"
+            log_shipping sink="s3" {
+                agent "fluent-bit"
+                bucket "my-logs"
+                region "ap-northeast-1"
+            }
         }
     "#;
 
-    let flow = parse_kdl_string(kdl, "default".to_string()).unwrap();
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let log_shipping = flow.stages["live"].log_shipping.as_ref().unwrap();
+    assert_eq!(
+        log_shipping.agent,
+        crate::model::LogShippingAgent::FluentBit
+    );
+    assert_eq!(log_shipping.sink, crate::model::LogSinkKind::S3);
+    assert_eq!(log_shipping.bucket.as_deref(), Some("my-logs"));
+    assert_eq!(log_shipping.region.as_deref(), Some("ap-northeast-1"));
+}
 
-    assert_eq!(flow.name, "creo-memories");
-    assert_eq!(flow.providers.len(), 1);
-    assert_eq!(flow.servers.len(), 1);
-    assert_eq!(flow.services.len(), 1);
-    assert_eq!(flow.stages.len(), 1);
+#[test]
+fn test_parse_stage_log_shipping_requires_endpoint() {
+    // loki は endpoint 必須
+    let kdl = r#"
+        stage "prod" {
+            log_shipping sink="loki"
+        }
+    "#;
 
-    let stage = &flow.stages["live"];
-    assert_eq!(stage.servers.len(), 1);
-    assert_eq!(stage.services.len(), 1);
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
 }
 
-// Issue: サービスマージロジックのテスト
-// 複数のファイルで同じサービスを定義した場合、マージされることを確認
+#[test]
+fn test_parse_maintenance() {
+    let kdl = r#"
+        maintenance {
+            image_retention 5
+            prune_schedule "daily"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let maintenance = flow.maintenance.unwrap();
+    assert_eq!(maintenance.image_retention, Some(5));
+    assert_eq!(
+        maintenance.prune_schedule,
+        crate::model::PruneSchedule::Daily
+    );
+}
 
 #[test]
-fn test_service_merge_environment() {
-    // fleet.kdl と flow.local.kdl を結合したような状態をシミュレート
+fn test_parse_maintenance_rejects_invalid_values() {
+    let kdl = r#"maintenance image_retention=0"#;
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+
+    let kdl = r#"maintenance prune_schedule="hourly""#;
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+
+    // 未指定時は既定（weekly、保持数の制限なし）
+    let flow = parse_kdl_string(r#"maintenance"#, "test".to_string()).unwrap();
+    assert_eq!(
+        flow.maintenance.unwrap(),
+        crate::model::MaintenanceConfig::default()
+    );
+}
+
+#[test]
+fn test_parse_mcp() {
     let kdl = r#"
-        service "api" {
-            image "myapp:latest"
-            env {
-                NODE_ENV "production"
-                LOG_LEVEL "info"
-                DATABASE_URL "postgresql://db:5432/mydb"
-            }
+        mcp {
+            enable_destructive_tools #false
         }
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert!(!flow.mcp.unwrap().enable_destructive_tools);
 
-        service "api" {
-            env {
-                DATABASE_URL "postgresql://localhost:5432/mydb_dev"
+    // 未指定時は有効
+    let flow = parse_kdl_string(r#"mcp"#, "test".to_string()).unwrap();
+    assert!(flow.mcp.unwrap().enable_destructive_tools);
+
+    let kdl = r#"mcp enable_destructive_tools="no""#;
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_stage_without_log_shipping() {
+    let kdl = r#"
+        stage "local" {}
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert!(flow.stages["local"].log_shipping.is_none());
+}
+
+#[test]
+fn test_parse_configs_and_mounts() {
+    let kdl = r#"
+        configs {
+            config "nginx.conf" file="./config/nginx.conf"
+            config "motd" {
+                content "welcome"
             }
         }
+
+        service "web" {
+            image "nginx:latest"
+            config "nginx.conf" target="/etc/nginx/nginx.conf"
+        }
     "#;
 
     let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
+    assert_eq!(flow.configs.len(), 2);
+    assert_eq!(
+        flow.configs["nginx.conf"].file,
+        Some(std::path::PathBuf::from("./config/nginx.conf"))
+    );
+    assert_eq!(flow.configs["motd"].content.as_deref(), Some("welcome"));
 
-    // image は保持される
-    assert_eq!(service.image, Some("myapp:latest".to_string()));
+    let mounts = &flow.services["web"].configs;
+    assert_eq!(mounts.len(), 1);
+    assert_eq!(mounts[0].source, "nginx.conf");
+    assert_eq!(
+        mounts[0].target,
+        std::path::PathBuf::from("/etc/nginx/nginx.conf")
+    );
+}
 
-    // environment はマージされる（後の定義が優先）
-    assert_eq!(service.environment.len(), 3);
-    assert_eq!(service.environment["NODE_ENV"], "production"); // 保持
-    assert_eq!(service.environment["LOG_LEVEL"], "info"); // 保持
+#[test]
+fn test_parse_config_mount_undefined_is_error() {
+    let kdl = r#"
+        service "web" {
+            image "nginx:latest"
+            config "missing.conf" target="/etc/missing.conf"
+        }
+    "#;
+
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_config_requires_file_or_content() {
+    let kdl = r#"
+        configs {
+            config "empty"
+        }
+    "#;
+
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_secrets_and_mounts() {
+    let kdl = r#"
+        secrets {
+            secret "db_password" from="op://Vault/db/password"
+            secret "api_key" env="API_KEY"
+        }
+
+        service "api" {
+            image "myapp:latest"
+            secret "db_password" target="/run/secrets/db_password"
+            secret "api_key"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(flow.secrets.len(), 2);
     assert_eq!(
-        service.environment["DATABASE_URL"],
-        "postgresql://localhost:5432/mydb_dev"
-    ); // オーバーライド
+        flow.secrets["db_password"].from.as_deref(),
+        Some("op://Vault/db/password")
+    );
+    assert_eq!(flow.secrets["api_key"].env.as_deref(), Some("API_KEY"));
+
+    let mounts = &flow.services["api"].secrets;
+    assert_eq!(mounts.len(), 2);
+    assert_eq!(
+        mounts[0].target_path(),
+        std::path::PathBuf::from("/run/secrets/db_password")
+    );
+    assert_eq!(mounts[1].target, None);
+    assert_eq!(
+        mounts[1].target_path(),
+        std::path::PathBuf::from("/run/secrets/api_key")
+    );
 }
 
 #[test]
-fn test_service_merge_ports_preserved() {
+fn test_parse_secret_mount_undefined_is_error() {
     let kdl = r#"
         service "api" {
             image "myapp:latest"
-            ports {
-                port 8080 3000
-                port 8443 3443
-            }
+            secret "missing"
         }
+    "#;
+
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+}
 
+#[test]
+fn test_parse_schema_version() {
+    let current = r#"
+        schema "2"
         service "api" {
-            env {
-                DEBUG "true"
-            }
+            image "api:1"
         }
     "#;
+    assert!(parse_kdl_string(current, "test".to_string()).is_ok());
 
-    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
+    // 旧バージョンは警告のみで読み込める（非推奨の別名も従来どおり解釈）
+    let old = r#"
+        schema "1"
+        service "api" service_type="static" {
+            image "api:1"
+        }
+    "#;
+    let flow = parse_kdl_string(old, "test".to_string()).unwrap();
+    assert!(flow.services["api"].service_type.is_some());
 
-    // image は保持される
-    assert_eq!(service.image, Some("myapp:latest".to_string()));
+    let future = r#"
+        schema "99"
+    "#;
+    let err = parse_kdl_string(future, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("fleet self-update"));
+}
 
-    // ports は空でなければ保持される（後の定義にportsがないので元が残る）
-    assert_eq!(service.ports.len(), 2);
-    assert_eq!(service.ports[0].host, 8080);
-    assert_eq!(service.ports[1].host, 8443);
+#[test]
+fn test_parse_image_template() {
+    let kdl = r#"
+        image_template "{registry}/{project}/{service}:{stage}-{git_short_sha}"
+        stage "prod" {
+            image_template "{registry}/{project}/{service}:{{tag}}"
+        }
+        stage "dev" {}
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(
+        flow.image_template.as_deref(),
+        Some("{registry}/{project}/{service}:{stage}-{git_short_sha}")
+    );
+    assert_eq!(
+        flow.stages["prod"].image_template.as_deref(),
+        Some("{registry}/{project}/{service}:{{tag}}")
+    );
+    assert!(flow.stages["dev"].image_template.is_none());
 
-    // environment はマージされる
-    assert_eq!(service.environment.len(), 1);
-    assert_eq!(service.environment["DEBUG"], "true");
+    let unknown = r#"image_template "{registry}/{team}/{service}""#;
+    let err = parse_kdl_string(unknown, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("unknown variable 'team'"));
+}
+
+#[test]
+fn test_parse_container_name_template() {
+    let kdl = r#"
+        project "myapp"
+        container_name_template "{project}_{service}_{stage}"
+        service "db" {
+            image "postgres:16"
+        }
+        service "api" {
+            image "api:latest"
+            container_name "legacy-api"
+        }
+        stage "prod" {
+            service "db"
+            service "api"
+        }
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(flow.container_name("prod", "db"), "myapp_db_prod");
+    assert_eq!(flow.container_name("prod", "api"), "legacy-api");
+    assert!(flow.validate_stage("prod").is_empty());
+
+    let unknown = r#"container_name_template "{project}-{host}""#;
+    let err = parse_kdl_string(unknown, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("unknown variable 'host'"));
+}
+
+#[test]
+fn test_container_name_must_be_unique_in_stage() {
+    let kdl = r#"
+        container_name_template "{project}-{stage}"
+        service "db" {
+            image "postgres:16"
+        }
+        service "cache" {
+            image "redis:7"
+        }
+        stage "local" {
+            service "db"
+            service "cache"
+        }
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let errors = flow.validate_stage("local");
+    assert!(
+        errors.iter().any(|e| e.contains("コンテナ名 'test-local'")),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn test_conditional_services_and_servers() {
+    let kdl = r#"
+        service "api" {
+            image "api:latest"
+        }
+        service "vector" {
+            image "timberio/vector:0.39.0-alpine"
+            when "{{ stage }} != 'local'"
+        }
+        service "exporter" image="prom/node-exporter" when="{{ stage }} in ['prod']"
+        service "toolbox" {
+            image "busybox"
+            enabled #false
+        }
+        server "web-01" {
+            provider "sakura-cloud"
+        }
+        server "canary" {
+            provider "sakura-cloud"
+            when "{{ stage }} == 'prod'"
+        }
+        stage "local" {
+            service "api"
+            service "vector"
+            service "exporter"
+            service "toolbox"
+        }
+        stage "prod" {
+            service "api"
+            service "vector"
+            service "exporter"
+            service "toolbox"
+            server "web-01"
+            server "canary"
+        }
+        stage "stg" {
+            server "web-01"
+            server "canary"
+        }
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(flow.stages["local"].services, vec!["api"]);
+    assert_eq!(
+        flow.stages["prod"].services,
+        vec!["api", "vector", "exporter"]
+    );
+    assert_eq!(flow.stages["prod"].servers, vec!["web-01", "canary"]);
+    assert_eq!(flow.stages["stg"].servers, vec!["web-01"]);
+    // 定義は残る（ステージから外すだけ）
+    assert!(flow.services.contains_key("toolbox"));
+
+    let invalid = r#"
+        service "api" {
+            image "api:latest"
+            when "{{ stage }} = 'local'"
+        }
+        stage "local" {
+            service "api"
+        }
+    "#;
+    let err = parse_kdl_string(invalid, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("service 'api' (stage 'local')"));
+}
+
+#[test]
+fn test_parse_setup_steps() {
+    let kdl = r#"
+        setup {
+            step "seed-data" run="./scripts/seed.sh" after="init-db" skip_if="test -f .seeded" stage="local,dev"
+            step "init-db" run="./scripts/init-db.sh" retries=3 retry_delay=5 timeout=120
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(flow.setup.len(), 2);
+    let seed = &flow.setup[0];
+    assert_eq!(seed.name, "seed-data");
+    assert_eq!(seed.run, "./scripts/seed.sh");
+    assert_eq!(seed.after, vec!["init-db"]);
+    assert_eq!(seed.skip_if.as_deref(), Some("test -f .seeded"));
+    assert_eq!(seed.stages, vec!["local", "dev"]);
+    assert_eq!(seed.retries, 0);
+
+    let init = &flow.setup[1];
+    assert_eq!(init.retries, 3);
+    assert_eq!(init.retry_delay, Some(5));
+    assert_eq!(init.timeout, Some(120));
+    assert!(init.after.is_empty());
+}
+
+#[test]
+fn test_parse_setup_step_errors() {
+    let missing_run = r#"
+        setup {
+            step "seed-data"
+        }
+    "#;
+    assert!(parse_kdl_string(missing_run, "test".to_string()).is_err());
+
+    let undefined_after = r#"
+        setup {
+            step "seed-data" run="./seed.sh" after="init-db"
+        }
+    "#;
+    assert!(parse_kdl_string(undefined_after, "test".to_string()).is_err());
+
+    let duplicate = r#"
+        setup {
+            step "seed-data" run="./seed.sh"
+            step "seed-data" run="./seed2.sh"
+        }
+    "#;
+    assert!(parse_kdl_string(duplicate, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_secret_requires_single_source() {
+    let none = r#"
+        secrets {
+            secret "empty"
+        }
+    "#;
+    assert!(parse_kdl_string(none, "test".to_string()).is_err());
+
+    let both = r#"
+        secrets {
+            secret "both" from="literal" env="BOTH"
+        }
+    "#;
+    assert!(parse_kdl_string(both, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_secret_mount_relative_target_is_error() {
+    let kdl = r#"
+        secrets {
+            secret "token" env="TOKEN"
+        }
+
+        service "api" {
+            image "myapp:latest"
+            secret "token" target="run/token"
+        }
+    "#;
+
+    assert!(parse_kdl_string(kdl, "test".to_string()).is_err());
+}
+
+#[test]
+fn test_parse_full_cloud_config() {
+    let kdl = r#"
+        project "creo-memories"
+
+        provider "sakura-cloud" {
+            zone "tk1a"
+        }
+
+        server "creo-vps" {
+            provider "sakura-cloud"
+            plan "2core-4gb"
+            disk_size 100
+        }
+
+        service "surrealdb" {
+            image "surrealdb/surrealdb"
+            version "latest"
+        }
+
+        stage "live" {
+            server "creo-vps"
+            service "surrealdb"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "default".to_string()).unwrap();
+
+    assert_eq!(flow.name, "creo-memories");
+    assert_eq!(flow.providers.len(), 1);
+    assert_eq!(flow.servers.len(), 1);
+    assert_eq!(flow.services.len(), 1);
+    assert_eq!(flow.stages.len(), 1);
+
+    let stage = &flow.stages["live"];
+    assert_eq!(stage.servers.len(), 1);
+    assert_eq!(stage.services.len(), 1);
+}
+
+// Issue: サービスマージロジックのテスト
+// 複数のファイルで同じサービスを定義した場合、マージされることを確認
+
+#[test]
+fn test_service_merge_environment() {
+    // fleet.kdl と flow.local.kdl を結合したような状態をシミュレート
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            env {
+                NODE_ENV "production"
+                LOG_LEVEL "info"
+                DATABASE_URL "postgresql://db:5432/mydb"
+            }
+        }
+
+        service "api" {
+            env {
+                DATABASE_URL "postgresql://localhost:5432/mydb_dev"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // image は保持される
+    assert_eq!(service.image, Some("myapp:latest".to_string()));
+
+    // environment はマージされる（後の定義が優先）
+    assert_eq!(service.environment.len(), 3);
+    assert_eq!(service.environment["NODE_ENV"], "production"); // 保持
+    assert_eq!(service.environment["LOG_LEVEL"], "info"); // 保持
+    assert_eq!(
+        service.environment["DATABASE_URL"],
+        "postgresql://localhost:5432/mydb_dev"
+    ); // オーバーライド
+}
+
+#[test]
+fn test_service_merge_ports_preserved() {
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            ports {
+                port 8080 3000
+                port 8443 3443
+            }
+        }
+
+        service "api" {
+            env {
+                DEBUG "true"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // image は保持される
+    assert_eq!(service.image, Some("myapp:latest".to_string()));
+
+    // ports は空でなければ保持される（後の定義にportsがないので元が残る）
+    assert_eq!(service.ports.len(), 2);
+    assert_eq!(service.ports[0].host, 8080);
+    assert_eq!(service.ports[1].host, 8443);
+
+    // environment はマージされる
+    assert_eq!(service.environment.len(), 1);
+    assert_eq!(service.environment["DEBUG"], "true");
+}
+
+#[test]
+fn test_service_merge_ports_overwritten() {
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            ports {
+                port 8080 3000
+                port 8443 3443
+            }
+        }
+
+        service "api" {
+            ports {
+                port 9000 3000
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // image は保持される
+    assert_eq!(service.image, Some("myapp:latest".to_string()));
+
+    // ports は後の定義で上書き（空でないので）
+    assert_eq!(service.ports.len(), 1);
+    assert_eq!(service.ports[0].host, 9000);
+}
+
+#[test]
+fn test_service_merge_version_override() {
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            version "1.0.0"
+            ports {
+                port 8080 3000
+            }
+            env {
+                NODE_ENV "production"
+            }
+        }
+
+        service "api" {
+            version "local"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // version は上書き
+    assert_eq!(service.version, Some("local".to_string()));
+
+    // image は保持（version が変わっても image はそのまま）
+    assert_eq!(service.image, Some("myapp:latest".to_string()));
+
+    // ports, env も保持
+    assert_eq!(service.ports.len(), 1);
+    assert_eq!(service.environment.len(), 1);
+}
+
+#[test]
+fn test_service_merge_volumes_preserved() {
+    let kdl = r#"
+        service "db" {
+            image "postgres:16"
+            volumes {
+                volume "./data" "/var/lib/postgresql/data"
+            }
+        }
+
+        service "db" {
+            env {
+                POSTGRES_PASSWORD "secret"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["db"];
+
+    // volumes は保持される
+    assert_eq!(service.volumes.len(), 1);
+    assert_eq!(service.volumes[0].host.to_str().unwrap(), "./data");
+
+    // environment はマージされる
+    assert_eq!(service.environment.len(), 1);
+    assert_eq!(service.environment["POSTGRES_PASSWORD"], "secret");
+}
+
+#[test]
+fn test_service_merge_depends_on_preserved() {
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            depends_on "db" "redis"
+        }
+
+        service "api" {
+            env {
+                API_KEY "test"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // depends_on は保持される
+    assert_eq!(service.depends_on.len(), 2);
+    assert!(service.depends_on.contains(&"db".to_string()));
+    assert!(service.depends_on.contains(&"redis".to_string()));
+
+    // environment はマージされる
+    assert_eq!(service.environment["API_KEY"], "test");
+}
+
+#[test]
+fn test_service_merge_multiple_overrides() {
+    // 3回のオーバーライドをシミュレート
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            env {
+                NODE_ENV "production"
+                LOG_LEVEL "info"
+            }
+        }
+
+        service "api" {
+            env {
+                LOG_LEVEL "debug"
+                DEBUG "true"
+            }
+        }
+
+        service "api" {
+            env {
+                API_KEY "secret"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let service = &flow.services["api"];
+
+    // 全ての環境変数がマージされる
+    assert_eq!(service.environment.len(), 4);
+    assert_eq!(service.environment["NODE_ENV"], "production"); // 1回目から
+    assert_eq!(service.environment["LOG_LEVEL"], "debug"); // 2回目で上書き
+    assert_eq!(service.environment["DEBUG"], "true"); // 2回目で追加
+    assert_eq!(service.environment["API_KEY"], "secret"); // 3回目で追加
+}
+
+#[test]
+fn test_service_extends_inherits_base_settings() {
+    let kdl = r#"
+        service "base-worker" {
+            image "myapp/worker:latest"
+            restart "unless-stopped"
+            env {
+                QUEUE "default"
+                LOG_LEVEL "info"
+            }
+        }
+
+        service "worker-a" extends="base-worker" {
+            env {
+                QUEUE "high"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let worker = &flow.services["worker-a"];
+
+    assert_eq!(worker.extends.as_deref(), Some("base-worker"));
+    assert_eq!(worker.image.as_deref(), Some("myapp/worker:latest"));
+    assert_eq!(worker.restart, Some(RestartPolicy::UnlessStopped));
+    assert_eq!(worker.environment["QUEUE"], "high"); // 子が優先
+    assert_eq!(worker.environment["LOG_LEVEL"], "info"); // 継承
+
+    // 継承元自体は変わらない
+    assert_eq!(flow.services["base-worker"].environment["QUEUE"], "default");
+}
+
+#[test]
+fn test_service_extends_merges_keyed_lists() {
+    let kdl = r#"
+        service "base" {
+            image "myapp:latest"
+            port 8080 3000
+            port 9090 9090
+            volumes {
+                volume "./data" "/data"
+                volume "./logs" "/var/log/app"
+            }
+        }
+
+        service "child" extends="base" {
+            port 18080 3000
+            volumes {
+                volume "./child-data" "/data"
+                volume "./cache" "/cache"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let child = &flow.services["child"];
+
+    // 同じコンテナポートは子の定義で置き換え、それ以外は結合
+    assert_eq!(child.ports.len(), 2);
+    assert_eq!(child.ports[0].container, 9090);
+    assert_eq!(child.ports[1].host, 18080);
+    assert_eq!(child.ports[1].container, 3000);
+
+    // 同じマウント先は子の定義で置き換え、それ以外は結合
+    let volumes: Vec<_> = child
+        .volumes
+        .iter()
+        .map(|v| (v.host.to_str().unwrap(), v.container.to_str().unwrap()))
+        .collect();
+    assert_eq!(
+        volumes,
+        vec![
+            ("./logs", "/var/log/app"),
+            ("./child-data", "/data"),
+            ("./cache", "/cache"),
+        ]
+    );
 }
 
 #[test]
-fn test_service_merge_ports_overwritten() {
+fn test_service_extends_unions_value_lists() {
     let kdl = r#"
-        service "api" {
+        service "base" {
             image "myapp:latest"
-            ports {
-                port 8080 3000
-                port 8443 3443
-            }
+            depends_on "db" "redis"
+            cap_drop "ALL"
         }
 
-        service "api" {
-            ports {
-                port 9000 3000
-            }
+        service "child" extends="base" {
+            depends_on "redis" "queue"
+            cap_add "NET_BIND_SERVICE"
         }
     "#;
 
     let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
+    let child = &flow.services["child"];
 
-    // image は保持される
-    assert_eq!(service.image, Some("myapp:latest".to_string()));
-
-    // ports は後の定義で上書き（空でないので）
-    assert_eq!(service.ports.len(), 1);
-    assert_eq!(service.ports[0].host, 9000);
+    assert_eq!(child.depends_on, vec!["db", "redis", "queue"]);
+    assert_eq!(child.cap_drop, vec!["ALL"]);
+    assert_eq!(child.cap_add, vec!["NET_BIND_SERVICE"]);
 }
 
 #[test]
-fn test_service_merge_version_override() {
+fn test_service_merge_still_replaces_lists() {
+    // 同名サービスの再定義（オーバーライド）は従来通りリストを置き換える
     let kdl = r#"
         service "api" {
             image "myapp:latest"
-            version "1.0.0"
-            ports {
-                port 8080 3000
-            }
-            env {
-                NODE_ENV "production"
-            }
+            depends_on "db" "redis"
         }
 
         service "api" {
-            version "local"
+            depends_on "queue"
         }
     "#;
 
     let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
-
-    // version は上書き
-    assert_eq!(service.version, Some("local".to_string()));
-
-    // image は保持（version が変わっても image はそのまま）
-    assert_eq!(service.image, Some("myapp:latest".to_string()));
-
-    // ports, env も保持
-    assert_eq!(service.ports.len(), 1);
-    assert_eq!(service.environment.len(), 1);
+    assert_eq!(flow.services["api"].depends_on, vec!["queue"]);
 }
 
 #[test]
-fn test_service_merge_volumes_preserved() {
+fn test_service_extends_multi_level() {
     let kdl = r#"
-        service "db" {
-            image "postgres:16"
-            volumes {
-                volume "./data" "/var/lib/postgresql/data"
+        service "base" {
+            image "myapp:latest"
+            env {
+                A "base"
+                B "base"
+                C "base"
             }
         }
 
-        service "db" {
+        service "mid" extends="base" {
             env {
-                POSTGRES_PASSWORD "secret"
+                B "mid"
+                C "mid"
+            }
+        }
+
+        service "leaf" extends="mid" {
+            env {
+                C "leaf"
             }
         }
     "#;
 
     let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["db"];
+    let env = &flow.services["leaf"].environment;
+    assert_eq!(env["A"], "base");
+    assert_eq!(env["B"], "mid");
+    assert_eq!(env["C"], "leaf");
+    assert_eq!(flow.services["leaf"].image.as_deref(), Some("myapp:latest"));
+}
 
-    // volumes は保持される
-    assert_eq!(service.volumes.len(), 1);
-    assert_eq!(service.volumes[0].host.to_str().unwrap(), "./data");
+#[test]
+fn test_service_extends_undefined_is_error() {
+    let kdl = r#"
+        service "worker" extends="missing" {
+            image "myapp:latest"
+        }
+    "#;
 
-    // environment はマージされる
-    assert_eq!(service.environment.len(), 1);
-    assert_eq!(service.environment["POSTGRES_PASSWORD"], "secret");
+    let err = parse_kdl_string(kdl, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("missing"));
 }
 
 #[test]
-fn test_service_merge_depends_on_preserved() {
+fn test_service_extends_cycle_is_error() {
     let kdl = r#"
-        service "api" {
+        service "a" extends="b" {
             image "myapp:latest"
-            depends_on "db" "redis"
         }
 
-        service "api" {
-            env {
-                API_KEY "test"
-            }
+        service "b" extends="a" {
+            image "myapp:latest"
         }
     "#;
 
-    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
-
-    // depends_on は保持される
-    assert_eq!(service.depends_on.len(), 2);
-    assert!(service.depends_on.contains(&"db".to_string()));
-    assert!(service.depends_on.contains(&"redis".to_string()));
-
-    // environment はマージされる
-    assert_eq!(service.environment["API_KEY"], "test");
+    let err = parse_kdl_string(kdl, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("circular"));
 }
 
 #[test]
-fn test_service_merge_multiple_overrides() {
-    // 3回のオーバーライドをシミュレート
+fn test_service_extends_applies_stage_override_of_base() {
     let kdl = r#"
-        service "api" {
+        service "base" {
             image "myapp:latest"
-            env {
-                NODE_ENV "production"
-                LOG_LEVEL "info"
-            }
         }
 
-        service "api" {
-            env {
-                LOG_LEVEL "debug"
-                DEBUG "true"
-            }
-        }
+        service "worker" extends="base" {}
 
-        service "api" {
-            env {
-                API_KEY "secret"
+        stage "prod" {
+            service "worker"
+            service "base" {
+                image "myapp:1.2.3"
             }
         }
     "#;
 
-    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
-    let service = &flow.services["api"];
-
-    // 全ての環境変数がマージされる
-    assert_eq!(service.environment.len(), 4);
-    assert_eq!(service.environment["NODE_ENV"], "production"); // 1回目から
-    assert_eq!(service.environment["LOG_LEVEL"], "debug"); // 2回目で上書き
-    assert_eq!(service.environment["DEBUG"], "true"); // 2回目で追加
-    assert_eq!(service.environment["API_KEY"], "secret"); // 3回目で追加
+    let flow = parse_kdl_string_with_stage(kdl, "test".to_string(), Some("prod")).unwrap();
+    assert_eq!(
+        flow.services["worker"].image.as_deref(),
+        Some("myapp:1.2.3")
+    );
 }
 
 // ============================================================================
@@ -832,6 +1674,8 @@ fn test_port_unison_kdl_serialize() {
         container: 3000,
         protocol: Protocol::Tcp,
         host_ip: None,
+        name: None,
+        app_protocol: None,
     };
 
     let node = port.to_kdl_node().unwrap();
@@ -852,6 +1696,8 @@ fn test_port_unison_kdl_roundtrip() {
         container: 80,
         protocol: Protocol::Udp,
         host_ip: Some("127.0.0.1".to_string()),
+        name: None,
+        app_protocol: None,
     };
 
     // Serialize -> Deserialize
@@ -1105,3 +1951,307 @@ fn test_include_nested() {
     assert!(flow.services.contains_key("postgres"));
     assert!(flow.services.contains_key("api"));
 }
+
+#[test]
+fn test_strict_mode_rejects_unknown_keys() {
+    let kdl = r#"
+        strict #true
+
+        service "api" {
+            image "myapp:latest"
+            prots {
+                port 8080 3000
+            }
+        }
+    "#;
+
+    let err = parse_kdl_string(kdl, "test".to_string()).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("prots"), "{message}");
+    assert!(message.contains("did you mean 'ports'"), "{message}");
+}
+
+#[test]
+fn test_unknown_keys_ignored_without_strict() {
+    let kdl = r#"
+        service "api" {
+            image "myapp:latest"
+            prots {
+                port 8080 3000
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert!(flow.services["api"].ports.is_empty());
+}
+
+#[test]
+fn test_parse_stop_settings() {
+    let kdl = r#"
+        service "db" {
+            image "postgres:16"
+            stop_signal "sigint"
+            stop_grace_period "2m"
+        }
+
+        service "api" image="myapp:latest" stop_grace_period=15
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let db = &flow.services["db"];
+    assert_eq!(db.stop_signal.as_deref(), Some("SIGINT"));
+    assert_eq!(db.stop_grace_period, Some(120));
+    assert_eq!(flow.services["api"].stop_grace_period, Some(15));
+}
+
+#[test]
+fn test_parse_invalid_stop_grace_period() {
+    let kdl = r#"
+        service "db" {
+            image "postgres:16"
+            stop_grace_period "soon"
+        }
+    "#;
+
+    let err = parse_kdl_string(kdl, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("stop_grace_period"));
+}
+
+#[test]
+fn test_parse_build_remote() {
+    let kdl = r#"
+        service "api" {
+            image "myapp"
+            build {
+                dockerfile "Dockerfile"
+                remote #true
+            }
+        }
+
+        service "worker" {
+            image "worker"
+            build {
+                remote
+            }
+        }
+
+        service "web" {
+            image "web"
+            build {
+                dockerfile "Dockerfile"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert!(flow.services["api"].build.as_ref().unwrap().remote);
+    assert!(flow.services["worker"].build.as_ref().unwrap().remote);
+    assert!(!flow.services["web"].build.as_ref().unwrap().remote);
+}
+
+#[test]
+fn test_parse_build_watch_paths() {
+    let kdl = r#"
+        service "api" {
+            image "api"
+            build {
+                context "services/api"
+                watch_paths "services/api/**" "libs/shared/**"
+                watch_paths "Cargo.lock"
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(
+        flow.services["api"].build.as_ref().unwrap().watch_paths,
+        vec!["services/api/**", "libs/shared/**", "Cargo.lock"]
+    );
+}
+
+#[test]
+fn test_parse_service_test_block() {
+    let kdl = r#"
+        service "api" {
+            image "myapp-api"
+            depends_on "db"
+            test {
+                command "cargo test --workspace"
+                image "rust:1.79"
+                env {
+                    DATABASE_URL "postgres://db:5432/test"
+                }
+            }
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let test = flow.services["api"].test.as_ref().unwrap();
+    assert_eq!(test.command, "cargo test --workspace");
+    assert_eq!(test.image.as_deref(), Some("rust:1.79"));
+    assert_eq!(test.workdir(), "/workspace");
+    assert_eq!(test.env["DATABASE_URL"], "postgres://db:5432/test");
+
+    let missing = r#"
+        service "api" {
+            image "myapp-api"
+            test {
+                image "rust:1.79"
+            }
+        }
+    "#;
+    let err = parse_kdl_string(missing, "test".to_string()).unwrap_err();
+    assert!(err.to_string().contains("test block requires a command"));
+}
+
+#[test]
+fn test_parse_stage_smoke_tests() {
+    let kdl = r#"
+        stage "prod" {
+            service "api"
+            smoke_test {
+                http "https://api.example.com/health" expect=200 timeout="30s"
+                http "https://example.com/" timeout="2m"
+                http "https://example.com/missing" expect=404 timeout=5
+                http service="api" port="http" path="/health"
+            }
+        }
+        service "api" {
+            image "api"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    let tests = &flow.stages["prod"].smoke_tests;
+    assert_eq!(tests.len(), 4);
+    assert_eq!(
+        tests[0].target,
+        SmokeTarget::Url("https://api.example.com/health".to_string())
+    );
+    assert_eq!((tests[0].expect, tests[0].timeout), (200, 30));
+    assert_eq!((tests[1].expect, tests[1].timeout), (200, 120));
+    assert_eq!((tests[2].expect, tests[2].timeout), (404, 5));
+    assert_eq!(
+        tests[3].target,
+        SmokeTarget::Port {
+            service: "api".to_string(),
+            port: "http".to_string(),
+            path: "/health".to_string(),
+        }
+    );
+
+    for (invalid, message) in [
+        (r#"http "api.example.com/health""#, "must start with http"),
+        (
+            r#"http "https://example.com" expect=42"#,
+            "invalid smoke_test expect",
+        ),
+        (
+            r#"http "https://example.com" timeout="soon""#,
+            "invalid smoke_test timeout",
+        ),
+        (r#"tcp "db:5432""#, "unknown smoke_test check"),
+        (r#"http expect=200"#, "requires a URL or service=/port="),
+        (
+            r#"http service="api" port="http" path="health""#,
+            "must start with /",
+        ),
+    ] {
+        let kdl = format!("stage \"prod\" {{\n smoke_test {{\n {invalid}\n }}\n}}");
+        let err = parse_kdl_string(&kdl, "test".to_string()).unwrap_err();
+        assert!(err.to_string().contains(message), "{invalid}: {err}");
+    }
+}
+
+#[test]
+fn test_parse_stage_dns_domain() {
+    let kdl = r#"
+        provider "cloudflare" {
+            zone_id "0123abcd"
+            domain "example.com"
+            api_token env="CF_API_TOKEN"
+        }
+        stage "stg" {
+            dns_domain "stg.example.com"
+        }
+        stage "prod"
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(
+        flow.stages["stg"].dns_domain.as_deref(),
+        Some("stg.example.com")
+    );
+    assert!(flow.stages["prod"].dns_domain.is_none());
+    assert_eq!(
+        flow.providers["cloudflare"].api_token,
+        Some(CredentialRef::Env("CF_API_TOKEN".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_stage_registry_auth() {
+    let kdl = r#"
+        stage "stg" {
+            registry "ghcr.io/acme"
+        }
+        stage "prod" {
+            registry {
+                url "registry.example.com/acme"
+                username_env "PROD_REGISTRY_USER"
+                password_env "PROD_REGISTRY_TOKEN"
+            }
+        }
+    "#;
+    let flow = parse_kdl_string(kdl, "test".to_string()).unwrap();
+    assert_eq!(flow.stages["stg"].registry.as_deref(), Some("ghcr.io/acme"));
+    assert!(flow.stages["stg"].registry_auth.is_none());
+
+    let prod = &flow.stages["prod"];
+    assert_eq!(prod.registry.as_deref(), Some("registry.example.com/acme"));
+    let auth = prod.registry_auth.as_ref().unwrap();
+    assert_eq!(auth.username_env, "PROD_REGISTRY_USER");
+    assert_eq!(auth.password_env, "PROD_REGISTRY_TOKEN");
+
+    let partial = r#"stage "prod" { registry url="ghcr.io/acme" username_env="USER" }"#;
+    let err = parse_kdl_string(partial, "test".to_string()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("both username_env and password_env")
+    );
+}
+
+#[test]
+fn test_parse_site() {
+    let kdl = r#"
+        site "docs" {
+            build "npm run build"
+            dir "web"
+            output "web/dist"
+            domain "docs.{stage}.example.com"
+        }
+    "#;
+
+    let flow = parse_kdl_string(kdl, "myapp".to_string()).unwrap();
+    let site = &flow.sites["docs"];
+    assert_eq!(site.build.as_deref(), Some("npm run build"));
+    assert_eq!(site.output, "web/dist");
+    assert_eq!(
+        site.domain_name(Some("stg")).as_deref(),
+        Some("docs.stg.example.com")
+    );
+    assert_eq!(
+        site.bucket_name(&flow.name, "docs", Some("stg")),
+        "myapp-docs-stg"
+    );
+
+    // output は必須、未知のノードはエラー
+    assert!(parse_kdl_string(r#"site "docs" { build "make" }"#, "test".to_string()).is_err());
+    assert!(
+        parse_kdl_string(
+            r#"site "docs" { output "dist"; cdn "x" }"#,
+            "test".to_string()
+        )
+        .is_err()
+    );
+}
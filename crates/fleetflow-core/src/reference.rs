@@ -0,0 +1,250 @@
+//! サービス参照の解決 — `port()` / `service_host()` / `project` / `stage`
+//!
+//! KDL の文字列の中で他のサービスのポートやホスト名を参照できる。
+//! テンプレート展開の時点ではサービス定義がそろっていないため、
+//! 参照はパース後（ステージの上書き・extends の適用後）に解決する。
+//!
+//! ```kdl
+//! service "api" {
+//!     env {
+//!         DATABASE_URL "postgres://{{ service_host('db') }}:{{ port('db', 'pg') }}/app"
+//!         OTEL_SERVICE_NAME "{{ project }}-{{ stage }}-api"
+//!     }
+//! }
+//! ```
+//!
+//! - `port('db', 'pg')`: サービス `db` の `name="pg"` のポートのコンテナ側番号
+//!   （名前を省略すると最初のポート）。同じネットワークの他サービスから接続する番号
+//! - `service_host('db')`: サービス `db` に接続するホスト名（ネットワークエイリアス =
+//!   サービス名、`network_mode "host"` なら `localhost`）
+//! - `project` / `stage`: プロジェクト名・ステージ名（ステージ未指定のロードでは残す）
+
+use crate::error::{FlowError, Result};
+use crate::model::{NetworkMode, Service};
+use crate::template::DEFERRED_FUNCTION_PATTERN;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+/// `{{ project }}` / `{{ stage }}`
+pub(crate) const VARIABLE_PATTERN: &str = r"\{\{\s*(project|stage)\s*\}\}";
+
+/// 参照を解決するためのコンテキスト
+pub struct ReferenceResolver<'a> {
+    project: &'a str,
+    stage: Option<&'a str>,
+    services: &'a HashMap<String, Service>,
+}
+
+impl<'a> ReferenceResolver<'a> {
+    pub fn new(
+        project: &'a str,
+        stage: Option<&'a str>,
+        services: &'a HashMap<String, Service>,
+    ) -> Self {
+        Self {
+            project,
+            stage,
+            services,
+        }
+    }
+
+    /// 文字列中の参照をすべて解決する
+    pub fn resolve(&self, text: &str) -> Result<String> {
+        if !text.contains("{{") {
+            return Ok(text.to_string());
+        }
+
+        let functions = Regex::new(DEFERRED_FUNCTION_PATTERN).expect("valid regex");
+        let mut error = None;
+        let resolved = functions.replace_all(text, |caps: &Captures| {
+            match self.call(&caps[1], &caps[2], caps.get(3).map(|m| m.as_str())) {
+                Ok(value) => value,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    String::new()
+                }
+            }
+        });
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let variables = Regex::new(VARIABLE_PATTERN).expect("valid regex");
+        let resolved = variables.replace_all(&resolved, |caps: &Captures| match &caps[1] {
+            "project" => self.project.to_string(),
+            // ステージが決まっていなければそのまま残す
+            _ => self
+                .stage
+                .map_or_else(|| caps[0].to_string(), str::to_string),
+        });
+        Ok(resolved.into_owned())
+    }
+
+    fn call(&self, function: &str, service_name: &str, port_name: Option<&str>) -> Result<String> {
+        let call = match port_name {
+            Some(port_name) => format!("{}('{}', '{}')", function, service_name, port_name),
+            None => format!("{}('{}')", function, service_name),
+        };
+        let service = self.services.get(service_name).ok_or_else(|| {
+            FlowError::InvalidConfig(format!(
+                "{}: service '{}' is not defined",
+                call, service_name
+            ))
+        })?;
+
+        if function == "service_host" {
+            return Ok(service_host(service_name, service));
+        }
+
+        let port = match port_name {
+            Some(port_name) => service
+                .ports
+                .iter()
+                .find(|p| p.name.as_deref() == Some(port_name)),
+            None => service.ports.first(),
+        };
+        let port = port.ok_or_else(|| {
+            let names: Vec<&str> = service
+                .ports
+                .iter()
+                .filter_map(|p| p.name.as_deref())
+                .collect();
+            let available = if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            };
+            FlowError::InvalidConfig(format!(
+                "{}: service '{}' has no matching port (named ports: {})",
+                call, service_name, available
+            ))
+        })?;
+        Ok(port.container.to_string())
+    }
+}
+
+/// 他のサービスからの接続先ホスト名（ネットワークエイリアス = サービス名）
+pub(crate) fn service_host(name: &str, service: &Service) -> String {
+    match service.network_mode {
+        Some(NetworkMode::Host) => "localhost".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// サービス定義の文字列フィールド中の参照を解決する
+pub(crate) fn resolve_service_references(
+    services: &mut HashMap<String, Service>,
+    project: &str,
+    stage: Option<&str>,
+) -> Result<()> {
+    let snapshot = services.clone();
+    let resolver = ReferenceResolver::new(project, stage, &snapshot);
+
+    for service in services.values_mut() {
+        if let Some(image) = &mut service.image {
+            *image = resolver.resolve(image)?;
+        }
+        if let Some(command) = &mut service.command {
+            *command = resolver.resolve(command)?;
+        }
+        for arg in service.entrypoint.iter_mut().flatten() {
+            *arg = resolver.resolve(arg)?;
+        }
+        for value in service.environment.values_mut() {
+            *value = resolver.resolve(value)?;
+        }
+        for value in service.labels.values_mut() {
+            *value = resolver.resolve(value)?;
+        }
+        if let Some(build) = &mut service.build {
+            for value in build.args.values_mut() {
+                *value = resolver.resolve(value)?;
+            }
+        }
+        if let Some(healthcheck) = &mut service.healthcheck {
+            for arg in &mut healthcheck.test {
+                *arg = resolver.resolve(arg)?;
+            }
+        }
+        if let Some(test) = &mut service.test {
+            test.command = resolver.resolve(&test.command)?;
+            for value in test.env.values_mut() {
+                *value = resolver.resolve(value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_kdl_string_with_stage;
+
+    const CONFIG: &str = r#"
+        project "shop"
+
+        stage "dev" {
+            service "api"
+            service "db"
+        }
+
+        service "db" {
+            image "postgres:16"
+            ports {
+                port host=15432 container=5432 name="pg"
+            }
+        }
+
+        service "api" {
+            image "shop/api:{{ stage }}"
+            env {
+                DATABASE_URL "postgres://{{ service_host('db') }}:{{ port('db', 'pg') }}/app"
+                DB_PORT "{{ port(\"db\") }}"
+                SERVICE_NAME "{{ project }}-{{ stage }}-api"
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_resolve_after_parse() {
+        let flow = parse_kdl_string_with_stage(CONFIG, "default".to_string(), Some("dev")).unwrap();
+        let api = &flow.services["api"];
+        assert_eq!(api.image.as_deref(), Some("shop/api:dev"));
+        assert_eq!(api.environment["DATABASE_URL"], "postgres://db:5432/app");
+        assert_eq!(api.environment["DB_PORT"], "5432");
+        assert_eq!(api.environment["SERVICE_NAME"], "shop-dev-api");
+    }
+
+    #[test]
+    fn test_stage_left_when_unknown() {
+        let services = HashMap::new();
+        let resolver = ReferenceResolver::new("shop", None, &services);
+        assert_eq!(
+            resolver.resolve("{{ project }}/{{ stage }}").unwrap(),
+            "shop/{{ stage }}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_references() {
+        let mut services = HashMap::new();
+        services.insert(
+            "db".to_string(),
+            Service {
+                network_mode: Some(NetworkMode::Host),
+                ..Default::default()
+            },
+        );
+        let resolver = ReferenceResolver::new("shop", Some("dev"), &services);
+        assert_eq!(
+            resolver.resolve("{{ service_host('db') }}").unwrap(),
+            "localhost"
+        );
+
+        let err = resolver.resolve("{{ port('db', 'http') }}").unwrap_err();
+        assert!(err.to_string().contains("no matching port"));
+        let err = resolver.resolve("{{ service_host('cache') }}").unwrap_err();
+        assert!(err.to_string().contains("'cache' is not defined"));
+    }
+}
@@ -15,10 +15,19 @@ const ESTIMATED_BYTES_PER_FILE: usize = 500;
 /// 変数コンテキスト
 pub type Variables = HashMap<String, serde_json::Value>;
 
+/// 設定の解析後に解決するテンプレート関数の呼び出し（`port('api', 'http')` / `service_host('db')`）
+///
+/// Tera の関数は名前付き引数しか受け付けないため、展開時は `{% raw %}` で囲んでそのまま残し、
+/// パース後に `crate::reference` が解決する。
+pub(crate) const DEFERRED_FUNCTION_PATTERN: &str =
+    r#"\{\{\s*(port|service_host)\(\s*['"]([^'"]*)['"]\s*(?:,\s*['"]([^'"]*)['"]\s*)?\)\s*\}\}"#;
+
 /// テンプレートプロセッサ
 pub struct TemplateProcessor {
     tera: Tera,
     context: Context,
+    /// `port()` / `service_host()` をパース後まで残すか
+    defer_references: bool,
 }
 
 impl TemplateProcessor {
@@ -27,6 +36,7 @@ impl TemplateProcessor {
         Self {
             tera: Tera::default(),
             context: Context::new(),
+            defer_references: false,
         }
     }
 
@@ -35,6 +45,18 @@ impl TemplateProcessor {
         self.context.insert(key.into(), &value);
     }
 
+    /// サービスへの参照をパース後まで残す
+    ///
+    /// `{{ port('api', 'http') }}` / `{{ service_host('db') }}` はそのまま出力し、
+    /// `{{ project }}` と（未指定なら）`{{ stage }}` も自身に展開して、設定の解析後に
+    /// `crate::reference` で解決する。ユーザー定義の同名変数が優先されるよう、
+    /// 変数の追加より前に呼ぶこと。
+    pub fn defer_references(&mut self, stage: Option<&str>) {
+        self.defer_references = true;
+        self.context.insert("project", "{{ project }}");
+        self.context.insert("stage", stage.unwrap_or("{{ stage }}"));
+    }
+
     /// 複数の変数を追加（op://参照は1Passwordから解決）
     pub fn add_variables(&mut self, variables: Variables) {
         for (key, value) in variables {
@@ -166,7 +188,14 @@ impl TemplateProcessor {
 
     /// 文字列をテンプレートとして展開
     pub fn render_str(&mut self, template: &str) -> Result<String> {
-        self.tera.render_str(template, &self.context).map_err(|e| {
+        let template = if self.defer_references {
+            use regex::Regex;
+            let re = Regex::new(DEFERRED_FUNCTION_PATTERN).expect("valid regex");
+            re.replace_all(template, "{% raw %}${0}{% endraw %}")
+        } else {
+            template.into()
+        };
+        self.tera.render_str(&template, &self.context).map_err(|e| {
             // Teraのエラーから詳細情報を抽出
             let error_detail = extract_tera_error_detail(&e);
             FlowError::TemplateRenderError(error_detail)
@@ -180,11 +209,16 @@ impl TemplateProcessor {
             message: e.to_string(),
         })?;
 
-        self.render_str(&content).map_err(|e| {
+        self.render_source(&content, path)
+    }
+
+    /// 読み込み済みの内容をテンプレート展開する（エラーには `file` を付ける）
+    pub fn render_source(&mut self, content: &str, file: &Path) -> Result<String> {
+        self.render_str(content).map_err(|e| {
             // TemplateRenderErrorをより詳細なTemplateErrorに変換
             if let FlowError::TemplateRenderError(msg) = e {
                 FlowError::TemplateError {
-                    file: path.to_path_buf(),
+                    file: file.to_path_buf(),
                     line: None,
                     message: msg,
                 }
@@ -593,6 +627,25 @@ variables {
         assert_eq!(vars.get("name").unwrap(), "second");
     }
 
+    #[test]
+    fn test_deferred_references() {
+        let mut processor = TemplateProcessor::new();
+        processor.defer_references(Some("prod"));
+        processor.add_variable("host", serde_json::json!("db.internal"));
+
+        let result = processor
+            .render_str("{{ host }}:{{ port('db', 'pg') }} {{ service_host(\"db\") }} {{ project }}-{{ stage }}")
+            .unwrap();
+        assert_eq!(
+            result,
+            "db.internal:{{ port('db', 'pg') }} {{ service_host(\"db\") }} {{ project }}-prod"
+        );
+
+        // ユーザー定義の変数が優先される
+        processor.add_variable("project", serde_json::json!("custom"));
+        assert_eq!(processor.render_str("{{ project }}").unwrap(), "custom");
+    }
+
     #[test]
     fn test_undefined_variable_error() {
         let mut processor = TemplateProcessor::new();
@@ -0,0 +1,305 @@
+//! 設定スキーマのアップグレード
+//!
+//! 旧スキーマの設定（ルートの `flow.kdl`、非推奨のノード名・プロパティ名）を検出し、
+//! 現行スキーマへ書き換える。書き換えは kdl のフォーマット情報を保ったまま行うため、
+//! コメントやインデントはそのまま残る。
+//!
+//! スキーマバージョン:
+//! - v1: メインファイルが `flow.kdl`（`.fleetflow/fleet.kdl` が無い）
+//! - v2: 現行（`.fleetflow/fleet.kdl`）
+//!
+//! fleet.kdl はトップレベルの `schema "2"` で明示的に宣言できる（未宣言は現行扱い）。
+
+use crate::error::{FlowError, Result};
+use kdl::{KdlDocument, KdlEntry, KdlIdentifier, KdlNode};
+use std::path::{Path, PathBuf};
+
+/// 現行のスキーマバージョン
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// v1 のメインファイル（優先順）
+const LEGACY_ROOT_FILES: &[&str] = &["flow.kdl", ".fleetflow/flow.kdl"];
+
+/// 現行のメインファイル
+pub const ROOT_FILE: &str = ".fleetflow/fleet.kdl";
+
+/// 非推奨のノード名（親ノード名, 旧名, 新名）
+///
+/// いずれもパーサーが別名として受け付けているもので、書き換えても意味は変わらない。
+const NODE_RENAMES: &[(&str, &str, &str)] = &[
+    ("server", "disk-size", "disk_size"),
+    ("server", "startup-script", "startup_script"),
+    ("server", "init_script", "startup_script"),
+    ("server", "init-script", "startup_script"),
+    ("server", "init-script-vars", "init_script_vars"),
+    ("server", "ssh-keys", "ssh_keys"),
+    ("server", "ssh_key", "ssh_keys"),
+    ("server", "ssh-key", "ssh_keys"),
+    ("server", "dns_alias", "dns_aliases"),
+    ("server", "dns-alias", "dns_aliases"),
+    ("server", "dns-aliases", "dns_aliases"),
+    ("server", "deploy-path", "deploy_path"),
+    ("server", "ssh-host", "ssh_host"),
+    ("server", "ssh-user", "ssh_user"),
+    ("server", "host-service", "host_service"),
+    ("host_service", "exec-start", "exec_start"),
+    ("host_service", "working-directory", "working_directory"),
+];
+
+/// 非推奨のプロパティ名（ノード名, 旧名, 新名）
+const PROP_RENAMES: &[(&str, &str, &str)] = &[("service", "service_type", "type")];
+
+/// 1 件の書き換え
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaRewrite {
+    /// 書き換え箇所（例: `service "api" > service_type`）
+    pub location: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// トップレベルの `schema` 宣言を読む（未宣言なら `None`、最後の宣言が有効）
+///
+/// 値は `schema "2"` / `schema 2` のどちらでもよい。
+pub fn declared_schema_version(doc: &KdlDocument) -> Result<Option<u32>> {
+    let Some(node) = doc.nodes().iter().rfind(|n| n.name().value() == "schema") else {
+        return Ok(None);
+    };
+    let value = node.entries().first().map(|e| e.value());
+    let version = value
+        .and_then(|v| {
+            v.as_string()
+                .and_then(|s| s.parse::<u32>().ok())
+                .or_else(|| v.as_integer().and_then(|i| u32::try_from(i).ok()))
+        })
+        .filter(|v| *v > 0)
+        .ok_or_else(|| {
+            FlowError::InvalidConfig(format!(
+                "schema requires a positive version number (e.g. schema \"{CURRENT_SCHEMA_VERSION}\")"
+            ))
+        })?;
+    Ok(Some(version))
+}
+
+/// プロジェクトのスキーマバージョンを判定する
+///
+/// `.fleetflow/fleet.kdl` があれば `schema` 宣言（未宣言なら現行）、
+/// 無く `flow.kdl` だけがあれば v1。
+pub fn detect_schema_version(project_root: &Path) -> u32 {
+    let root_file = project_root.join(ROOT_FILE);
+    if root_file.exists() {
+        return std::fs::read_to_string(&root_file)
+            .ok()
+            .and_then(|content| content.parse::<KdlDocument>().ok())
+            .and_then(|doc| declared_schema_version(&doc).ok().flatten())
+            .unwrap_or(CURRENT_SCHEMA_VERSION);
+    }
+    if find_legacy_root(project_root).is_some() {
+        1
+    } else {
+        CURRENT_SCHEMA_VERSION
+    }
+}
+
+/// v1 のメインファイルを探す（`.fleetflow/fleet.kdl` の有無は問わない）
+pub fn find_legacy_root(project_root: &Path) -> Option<PathBuf> {
+    LEGACY_ROOT_FILES
+        .iter()
+        .map(|name| project_root.join(name))
+        .find(|path| path.is_file())
+}
+
+/// カレントディレクトリから上に向かって、現行または v1 のプロジェクトルートを探す
+pub fn find_upgradable_project_root() -> Result<PathBuf> {
+    let start_dir = std::env::current_dir()?;
+    let mut current = start_dir.clone();
+    loop {
+        if current.join(ROOT_FILE).exists() || find_legacy_root(&current).is_some() {
+            return Ok(current);
+        }
+        if !current.pop() {
+            return Err(crate::error::FlowError::ProjectRootNotFound(start_dir));
+        }
+    }
+}
+
+/// ノードの表示名（`service "api"` 形式）
+fn node_label(node: &KdlNode) -> String {
+    match node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+    {
+        Some(arg) => format!("{} \"{}\"", node.name().value(), arg),
+        None => node.name().value().to_string(),
+    }
+}
+
+/// `parent` 直下のノードを再帰的に書き換える（`trail` は書き換え箇所の表示用の経路）
+fn upgrade_nodes(
+    doc: &mut KdlDocument,
+    parent: Option<&str>,
+    trail: &str,
+    rewrites: &mut Vec<SchemaRewrite>,
+) {
+    for node in doc.nodes_mut() {
+        if let Some(parent) = parent
+            && let Some((_, from, to)) = NODE_RENAMES
+                .iter()
+                .find(|(p, from, _)| *p == parent && *from == node.name().value())
+        {
+            rewrites.push(SchemaRewrite {
+                location: format!("{trail}{from}"),
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+            node.set_name(*to);
+        }
+
+        let name = node.name().value().to_string();
+        let label = node_label(node);
+        for entry in node.entries_mut() {
+            if let Some(prop) = entry.name_mut()
+                && let Some((_, from, to)) = PROP_RENAMES
+                    .iter()
+                    .find(|(n, from, _)| *n == name && *from == prop.value())
+            {
+                rewrites.push(SchemaRewrite {
+                    location: format!("{trail}{label} {from}="),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+                *prop = KdlIdentifier::from(*to);
+            }
+        }
+
+        let child_trail = format!("{trail}{label} > ");
+        if let Some(children) = node.children_mut().as_mut() {
+            upgrade_nodes(children, Some(&name), &child_trail, rewrites);
+        }
+    }
+}
+
+/// KDL 文字列の非推奨ノード名・プロパティ名を現行スキーマへ書き換える
+///
+/// 戻り値は書き換え後の文字列と書き換え内容の一覧（空なら変更なし）。
+pub fn upgrade_kdl_string(content: &str) -> Result<(String, Vec<SchemaRewrite>)> {
+    let mut doc: KdlDocument = content.parse()?;
+    let mut rewrites = Vec::new();
+
+    // 古い schema 宣言は現行バージョンに更新（未宣言のファイルには追加しない）
+    if let Some(version) = declared_schema_version(&doc)?
+        && version < CURRENT_SCHEMA_VERSION
+    {
+        for node in doc.nodes_mut() {
+            if node.name().value() == "schema" {
+                node.entries_mut().clear();
+                node.push(KdlEntry::new(CURRENT_SCHEMA_VERSION.to_string()));
+            }
+        }
+        rewrites.push(SchemaRewrite {
+            location: "schema".to_string(),
+            from: version.to_string(),
+            to: CURRENT_SCHEMA_VERSION.to_string(),
+        });
+    }
+
+    upgrade_nodes(&mut doc, None, "", &mut rewrites);
+    if rewrites.is_empty() {
+        return Ok((content.to_string(), rewrites));
+    }
+    Ok((doc.to_string(), rewrites))
+}
+
+/// 非推奨のノード名・プロパティ名の使用箇所を返す（ドキュメントは変更しない）
+///
+/// パーサーは別名として受け付けるため、読み込み時の警告に使う。
+pub fn deprecated_names(doc: &KdlDocument) -> Vec<SchemaRewrite> {
+    let mut doc = doc.clone();
+    let mut rewrites = Vec::new();
+    upgrade_nodes(&mut doc, None, "", &mut rewrites);
+    rewrites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_renames_deprecated_names() {
+        let content = r#"// production servers
+server "vps-1" {
+    provider "sakura-cloud"
+    ssh-key "deploy"
+    init_script "./init.sh"
+    host-service "cloudflared" {
+        exec-start "/usr/bin/cloudflared tunnel run"
+    }
+}
+
+service "api" service_type="static" {
+    image "api:1"
+}
+"#;
+        let (upgraded, rewrites) = upgrade_kdl_string(content).unwrap();
+        assert!(upgraded.starts_with("// production servers\n"));
+        assert!(upgraded.contains("    ssh_keys \"deploy\"\n"));
+        assert!(upgraded.contains("    startup_script \"./init.sh\"\n"));
+        assert!(upgraded.contains("    host_service \"cloudflared\" {\n"));
+        assert!(upgraded.contains("        exec_start \"/usr/bin/cloudflared tunnel run\"\n"));
+        assert!(upgraded.contains("service \"api\" type=\"static\" {"));
+        assert_eq!(rewrites.len(), 5);
+        assert_eq!(rewrites[0].location, "server \"vps-1\" > ssh-key");
+        assert_eq!(
+            rewrites[3].location,
+            "server \"vps-1\" > host_service \"cloudflared\" > exec-start"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_current_schema_is_noop() {
+        let content = "service \"api\" type=\"static\" {\n    env {\n        A \"1\"\n    }\n}\n";
+        let (upgraded, rewrites) = upgrade_kdl_string(content).unwrap();
+        assert!(rewrites.is_empty());
+        assert_eq!(upgraded, content);
+    }
+
+    #[test]
+    fn test_upgrade_bumps_old_schema_declaration() {
+        let (upgraded, rewrites) = upgrade_kdl_string("schema \"1\"\nproject \"app\"\n").unwrap();
+        assert!(upgraded.starts_with("schema \"2\"\n"));
+        assert_eq!(rewrites[0].location, "schema");
+
+        let (_, rewrites) = upgrade_kdl_string("schema \"2\"\n").unwrap();
+        assert!(rewrites.is_empty());
+    }
+
+    #[test]
+    fn test_declared_schema_version() {
+        let doc: KdlDocument = "schema \"2\"".parse().unwrap();
+        assert_eq!(declared_schema_version(&doc).unwrap(), Some(2));
+        let doc: KdlDocument = "schema 3".parse().unwrap();
+        assert_eq!(declared_schema_version(&doc).unwrap(), Some(3));
+        let doc: KdlDocument = "project \"app\"".parse().unwrap();
+        assert_eq!(declared_schema_version(&doc).unwrap(), None);
+        let doc: KdlDocument = "schema \"two\"".parse().unwrap();
+        assert!(declared_schema_version(&doc).is_err());
+    }
+
+    #[test]
+    fn test_detect_schema_version() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect_schema_version(dir.path()), CURRENT_SCHEMA_VERSION);
+
+        std::fs::write(dir.path().join("flow.kdl"), "").unwrap();
+        assert_eq!(detect_schema_version(dir.path()), 1);
+
+        std::fs::create_dir_all(dir.path().join(".fleetflow")).unwrap();
+        std::fs::write(dir.path().join(ROOT_FILE), "").unwrap();
+        assert_eq!(detect_schema_version(dir.path()), CURRENT_SCHEMA_VERSION);
+
+        std::fs::write(dir.path().join(ROOT_FILE), "schema \"1\"\n").unwrap();
+        assert_eq!(detect_schema_version(dir.path()), 1);
+    }
+}
@@ -0,0 +1,208 @@
+//! ワークスペース — 複数のプロジェクトをまとめて扱う（workspace.kdl）
+//!
+//! フロントエンドとバックエンドが別リポジトリにある場合などに、関連するプロジェクトを
+//! 1 つのワークスペースとして `fleet ws up` でまとめて起動する。
+//!
+//! ```kdl
+//! workspace "shop"
+//!
+//! project "../shop-frontend" name="frontend"
+//! project "../shop-backend"
+//! ```
+//!
+//! - `project` のパスは workspace.kdl からの相対パス（絶対パスも可）
+//! - `name` を省略するとディレクトリ名をプロジェクト名として使う
+//! - ワークスペースのコンテナは共有ネットワーク（[`Workspace::network_name`]）にも接続され、
+//!   他のプロジェクトのサービスに `<service>.<name>`（name はワークスペース上のプロジェクト名）で到達できる
+
+use crate::error::{FlowError, Result};
+use kdl::KdlDocument;
+use std::path::{Path, PathBuf};
+
+/// ワークスペース定義ファイル名
+pub const WORKSPACE_FILE: &str = "workspace.kdl";
+
+/// ワークスペース
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    /// ワークスペース名（共有ネットワーク名に使う）
+    pub name: String,
+    /// workspace.kdl のあるディレクトリ
+    pub root: PathBuf,
+    /// 記述順のプロジェクト
+    pub projects: Vec<WorkspaceProject>,
+}
+
+/// ワークスペースに属するプロジェクト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceProject {
+    /// 表示・選択に使う名前
+    pub name: String,
+    /// プロジェクトルート（.fleetflow/fleet.kdl のあるディレクトリ）
+    pub path: PathBuf,
+}
+
+impl Workspace {
+    /// ステージごとの共有ネットワーク名
+    pub fn network_name(&self, stage: &str) -> String {
+        format!("{}-{}-workspace", self.name, stage)
+    }
+
+    /// 名前でプロジェクトを選ぶ（空なら全プロジェクト）
+    pub fn select(&self, names: &[String]) -> Result<Vec<&WorkspaceProject>> {
+        if names.is_empty() {
+            return Ok(self.projects.iter().collect());
+        }
+        names
+            .iter()
+            .map(|name| {
+                self.projects
+                    .iter()
+                    .find(|p| &p.name == name)
+                    .ok_or_else(|| {
+                        FlowError::InvalidConfig(format!(
+                            "project '{}' is not part of workspace '{}' (available: {})",
+                            name,
+                            self.name,
+                            self.projects
+                                .iter()
+                                .map(|p| p.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// カレントディレクトリから上に向かって workspace.kdl を探す
+pub fn find_workspace_root() -> Result<PathBuf> {
+    let start_dir = std::env::current_dir()?;
+    start_dir
+        .ancestors()
+        .find(|dir| dir.join(WORKSPACE_FILE).is_file())
+        .map(Path::to_path_buf)
+        .ok_or(FlowError::WorkspaceNotFound(start_dir))
+}
+
+/// ワークスペースルートの workspace.kdl を読み込む
+pub fn load_workspace(root: &Path) -> Result<Workspace> {
+    let path = root.join(WORKSPACE_FILE);
+    let content = std::fs::read_to_string(&path).map_err(|e| FlowError::IoError {
+        path: path.clone(),
+        message: e.to_string(),
+    })?;
+    parse_workspace(&content, root)
+}
+
+/// workspace.kdl の内容を解釈する
+pub fn parse_workspace(content: &str, root: &Path) -> Result<Workspace> {
+    let doc: KdlDocument = content.parse()?;
+    let mut name = None;
+    let mut projects: Vec<WorkspaceProject> = Vec::new();
+
+    for node in doc.nodes() {
+        let argument = node
+            .entries()
+            .iter()
+            .find(|e| e.name().is_none())
+            .and_then(|e| e.value().as_string());
+        match node.name().value() {
+            "workspace" => {
+                name = Some(
+                    argument
+                        .ok_or_else(|| {
+                            FlowError::InvalidConfig(
+                                "workspace requires a name: workspace \"<name>\"".to_string(),
+                            )
+                        })?
+                        .to_string(),
+                );
+            }
+            "project" => {
+                let relative = argument.ok_or_else(|| {
+                    FlowError::InvalidConfig(
+                        "project requires a path: project \"<path>\"".to_string(),
+                    )
+                })?;
+                let path = root.join(relative);
+                let project_name = match node.get("name").and_then(|v| v.as_string()) {
+                    Some(n) => n.to_string(),
+                    None => Path::new(relative)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .ok_or_else(|| {
+                            FlowError::InvalidConfig(format!(
+                                "project \"{}\": cannot derive a name, set name=\"...\"",
+                                relative
+                            ))
+                        })?,
+                };
+                if projects.iter().any(|p| p.name == project_name) {
+                    return Err(FlowError::InvalidConfig(format!(
+                        "project '{}' is listed more than once",
+                        project_name
+                    )));
+                }
+                projects.push(WorkspaceProject {
+                    name: project_name,
+                    path,
+                });
+            }
+            other => {
+                return Err(FlowError::InvalidConfig(format!(
+                    "unknown node in {}: {} (expected workspace / project)",
+                    WORKSPACE_FILE, other
+                )));
+            }
+        }
+    }
+
+    let name = name.or_else(|| root.file_name().map(|n| n.to_string_lossy().into_owned()));
+    Ok(Workspace {
+        name: name.unwrap_or_else(|| "workspace".to_string()),
+        root: root.to_path_buf(),
+        projects,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workspace() {
+        let workspace = parse_workspace(
+            r#"
+            workspace "shop"
+            project "../shop-frontend" name="frontend"
+            project "../shop-backend"
+            "#,
+            Path::new("/work/shop"),
+        )
+        .unwrap();
+
+        assert_eq!(workspace.name, "shop");
+        assert_eq!(workspace.network_name("local"), "shop-local-workspace");
+        assert_eq!(workspace.projects.len(), 2);
+        assert_eq!(workspace.projects[0].name, "frontend");
+        assert_eq!(
+            workspace.projects[0].path,
+            Path::new("/work/shop/../shop-frontend")
+        );
+        assert_eq!(workspace.projects[1].name, "shop-backend");
+
+        let selected = workspace.select(&["shop-backend".to_string()]).unwrap();
+        assert_eq!(selected[0].name, "shop-backend");
+        assert!(workspace.select(&["admin".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_workspace_rejects_invalid() {
+        let root = Path::new("/work/shop");
+        assert!(parse_workspace("project", root).is_err());
+        assert!(parse_workspace("projects \"a\"", root).is_err());
+        assert!(parse_workspace("project \"a\"\nproject \"b/a\"", root).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+//! fleet.kdl の解析（診断・補完・ホバー・定義ジャンプ）
+//!
+//! 検証は fleetflow-core のパーサーと strict モードのスキーマをそのまま使う。
+//! 補完・定義ジャンプは編集途中の（構文が壊れた）テキストでも動くよう、
+//! カーソルまでのテキストを字句単位で走査して文脈を求める。
+
+use fleetflow_core::{FlowError, UnknownKeyKind};
+use kdl::KdlDocument;
+
+/// 診断の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 診断（バイトオフセットの範囲）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub start: usize,
+    pub end: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// 補完候補の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// ノード名
+    Node,
+    /// プロパティ名（`name=` を挿入）
+    Property,
+    /// サービス・ステージ・サーバー名
+    Reference,
+}
+
+/// 補完候補
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<&'static str>,
+}
+
+/// トップレベルで定義された名前（service / stage / server）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// ノード名（`service` など）
+    pub kind: String,
+    pub name: String,
+    /// 名前の文字列リテラルの位置（バイトオフセット, 長さ）
+    pub span: (usize, usize),
+}
+
+/// 参照先の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// 参照先のノード名（`service` など）
+    pub kind: &'static str,
+    pub name: String,
+}
+
+/// テキストの診断
+///
+/// KDL の構文エラーは位置付きで、未知のノード名・プロパティは警告（`strict #true` ならエラー）、
+/// パーサーの検証エラーはファイル先頭に報告する。
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let doc: KdlDocument = match text.parse() {
+        Ok(doc) => doc,
+        Err(err) => {
+            return err
+                .diagnostics
+                .iter()
+                .map(|d| Diagnostic {
+                    start: d.span.offset(),
+                    end: d.span.offset() + d.span.len(),
+                    severity: Severity::Error,
+                    message: d
+                        .message
+                        .clone()
+                        .or_else(|| d.label.clone())
+                        .unwrap_or_else(|| "KDL parse error".to_string()),
+                })
+                .collect();
+        }
+    };
+    let strict = doc
+        .nodes()
+        .iter()
+        .filter(|n| n.name().value() == "strict")
+        .filter_map(|n| n.entries().first().and_then(|e| e.value().as_bool()))
+        .next_back()
+        .unwrap_or(false);
+
+    let mut diagnostics: Vec<Diagnostic> = fleetflow_core::check_unknown_keys(text)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|issue| Diagnostic {
+            start: issue.span.0,
+            end: issue.span.0 + issue.span.1,
+            severity: if strict {
+                Severity::Error
+            } else {
+                Severity::Warning
+            },
+            message: format!(
+                "unknown {} '{}'{}",
+                match issue.kind {
+                    UnknownKeyKind::Node => "node",
+                    UnknownKeyKind::Property => "property",
+                },
+                issue.name,
+                issue
+                    .suggestion
+                    .map(|s| format!(" (did you mean '{}'?)", s))
+                    .unwrap_or_default()
+            ),
+        })
+        .collect();
+
+    match fleetflow_core::parse_kdl_string(text, "fleet".to_string()) {
+        // strict モードの未知のキーは上で位置付きで報告済み
+        Err(FlowError::InvalidConfig(message)) if strict && message.starts_with("strict mode") => {}
+        Err(e) => diagnostics.push(Diagnostic {
+            start: 0,
+            end: 0,
+            severity: Severity::Error,
+            message: e.to_string(),
+        }),
+        Ok(_) => {}
+    }
+    diagnostics
+}
+
+/// トップレベルの service / stage / server 定義
+pub fn symbols(text: &str) -> Vec<Symbol> {
+    let Ok(doc) = text.parse::<KdlDocument>() else {
+        return Vec::new();
+    };
+    doc.nodes()
+        .iter()
+        .filter(|n| matches!(n.name().value(), "service" | "stage" | "server"))
+        .filter_map(|node| {
+            let entry = node.entries().iter().find(|e| e.name().is_none())?;
+            // エントリの範囲は前置の空白を含むことがあるので文字列リテラルに絞る
+            let (offset, len) = (entry.span().offset(), entry.span().len());
+            let raw = text.get(offset..offset + len)?;
+            let leading = raw.len() - raw.trim_start().len();
+            Some(Symbol {
+                kind: node.name().value().to_string(),
+                name: entry.value().as_string()?.to_string(),
+                span: (offset + leading, raw.trim().len()),
+            })
+        })
+        .collect()
+}
+
+/// カーソル位置の文脈
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Context {
+    /// 囲んでいるノード名（外側から）
+    parents: Vec<String>,
+    /// カーソルのある文のノード名（文頭でノード名を入力中なら None）
+    node: Option<String>,
+    /// 入力中の語（文字列リテラル内なら引用符の後ろから）
+    prefix: String,
+    /// 文字列リテラルの中か
+    in_string: bool,
+    /// 文字列がプロパティ値ならそのプロパティ名（`extends="..."`）
+    property: Option<String>,
+}
+
+/// テキスト先頭からカーソルまでを走査して文脈を求める
+fn context_at(text: &str, offset: usize) -> Context {
+    let text = &text[..offset.min(text.len())];
+    let mut ctx = Context::default();
+    let mut chars = text.chars().peekable();
+    let mut line_comment = false;
+    let mut block_comment = 0usize;
+
+    while let Some(c) = chars.next() {
+        if line_comment {
+            if c == '\n' {
+                line_comment = false;
+                ctx.node = None;
+                ctx.prefix.clear();
+            }
+            continue;
+        }
+        if block_comment > 0 {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                block_comment -= 1;
+            }
+            continue;
+        }
+        if ctx.in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => {
+                    ctx.in_string = false;
+                    ctx.property = None;
+                    ctx.prefix.clear();
+                }
+                _ => ctx.prefix.push(c),
+            }
+            continue;
+        }
+        match c {
+            '/' if chars.peek() == Some(&'/') => line_comment = true,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                block_comment += 1;
+            }
+            '"' => {
+                ctx.in_string = true;
+                ctx.property = ctx.prefix.strip_suffix('=').map(str::to_string);
+                if ctx.node.is_none() && ctx.property.is_none() && !ctx.prefix.is_empty() {
+                    ctx.node = Some(std::mem::take(&mut ctx.prefix));
+                }
+                ctx.prefix.clear();
+            }
+            '{' => {
+                let name = ctx.node.take().unwrap_or_else(|| ctx.prefix.clone());
+                ctx.parents.push(name);
+                ctx.prefix.clear();
+            }
+            '}' => {
+                ctx.parents.pop();
+                ctx.node = None;
+                ctx.prefix.clear();
+            }
+            '\n' | ';' => {
+                ctx.node = None;
+                ctx.prefix.clear();
+            }
+            c if c.is_whitespace() => {
+                if ctx.node.is_none() && !ctx.prefix.is_empty() {
+                    ctx.node = Some(std::mem::take(&mut ctx.prefix));
+                }
+                ctx.prefix.clear();
+            }
+            c => ctx.prefix.push(c),
+        }
+    }
+    ctx
+}
+
+/// 文字列リテラル内の参照が指す定義の種類
+fn reference_kind(ctx: &Context) -> Option<&'static str> {
+    let parent = ctx.parents.last().map(String::as_str);
+    match (ctx.property.as_deref(), ctx.node.as_deref(), parent) {
+        (Some("extends"), _, _) => Some("service"),
+        (Some(_), _, _) => None,
+        (None, Some("service"), Some("stage")) => Some("service"),
+        (None, Some("depends_on"), _) => Some("service"),
+        (None, Some("server"), Some("stage")) => Some("server"),
+        (None, Some("stage"), None) => Some("stage"),
+        _ => None,
+    }
+}
+
+/// カーソル位置の補完候補
+///
+/// `symbols` はワークスペース全体（開いているファイルとプロジェクトの設定ファイル）の定義。
+pub fn completions(text: &str, offset: usize, symbols: &[Symbol]) -> Vec<Completion> {
+    let ctx = context_at(text, offset);
+    let mut items: Vec<Completion> = Vec::new();
+
+    if ctx.in_string {
+        let Some(kind) = reference_kind(&ctx) else {
+            return items;
+        };
+        for symbol in symbols.iter().filter(|s| s.kind == kind) {
+            if symbol.name.starts_with(&ctx.prefix) && !items.iter().any(|i| i.label == symbol.name)
+            {
+                items.push(Completion {
+                    label: symbol.name.clone(),
+                    kind: CompletionKind::Reference,
+                    detail: Some(kind),
+                });
+            }
+        }
+        return items;
+    }
+
+    let parents: Vec<&str> = ctx.parents.iter().map(String::as_str).collect();
+    match &ctx.node {
+        None => {
+            for name in fleetflow_core::schema_child_nodes(&parents).unwrap_or_default() {
+                if name.starts_with(&ctx.prefix) {
+                    items.push(Completion {
+                        label: name.to_string(),
+                        kind: CompletionKind::Node,
+                        detail: fleetflow_core::node_doc(name),
+                    });
+                }
+            }
+        }
+        Some(node) => {
+            let mut path = parents;
+            path.push(node);
+            for name in fleetflow_core::schema_properties(&path).unwrap_or_default() {
+                if name.starts_with(&ctx.prefix) {
+                    items.push(Completion {
+                        label: format!("{}=", name),
+                        kind: CompletionKind::Property,
+                        detail: fleetflow_core::node_doc(name),
+                    });
+                }
+            }
+        }
+    }
+    items
+}
+
+/// カーソル位置の語（ノード名・プロパティ名）
+fn word_at(text: &str, offset: usize) -> Option<&str> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let offset = offset.min(text.len());
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = text[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_word(*c))
+        .map_or(text.len(), |(i, _)| offset + i);
+    (start < end).then(|| &text[start..end])
+}
+
+/// カーソル位置のノード名・プロパティ名の説明（Markdown）
+pub fn hover(text: &str, offset: usize) -> Option<String> {
+    if context_at(text, offset).in_string {
+        return None;
+    }
+    let word = word_at(text, offset)?;
+    let doc = fleetflow_core::node_doc(word)?;
+    Some(format!("**{}**\n\n{}", word, doc))
+}
+
+/// カーソル位置の文字列が参照する定義（stage のサービス一覧・depends_on・extends など）
+pub fn reference_at(text: &str, offset: usize) -> Option<Reference> {
+    let ctx = context_at(text, offset);
+    if !ctx.in_string {
+        return None;
+    }
+    let kind = reference_kind(&ctx)?;
+    let rest: String = text[offset.min(text.len())..]
+        .chars()
+        .take_while(|c| *c != '"' && *c != '\n')
+        .collect();
+    Some(Reference {
+        kind,
+        name: format!("{}{}", ctx.prefix, rest),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"project "app"
+
+service "web" {
+    image "nginx"
+}
+
+service "db" {
+    image "postgres"
+}
+
+stage "local" {
+    service "web"
+    service "d
+}
+"#;
+
+    fn offset_after(text: &str, needle: &str) -> usize {
+        text.find(needle).unwrap() + needle.len()
+    }
+
+    #[test]
+    fn test_context_at_nested_nodes() {
+        let text = "stage \"local\" {\n    service \"web\" {\n        im";
+        let ctx = context_at(text, text.len());
+        assert_eq!(ctx.parents, vec!["stage", "service"]);
+        assert_eq!(ctx.node, None);
+        assert_eq!(ctx.prefix, "im");
+
+        let text = "service \"api\" extends=\"ba";
+        let ctx = context_at(text, text.len());
+        assert!(ctx.in_string);
+        assert_eq!(ctx.node.as_deref(), Some("service"));
+        assert_eq!(ctx.property.as_deref(), Some("extends"));
+        assert_eq!(ctx.prefix, "ba");
+    }
+
+    #[test]
+    fn test_complete_node_names() {
+        let text = "service \"api\" {\n    heal";
+        let items = completions(text, text.len(), &[]);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "healthcheck");
+        assert_eq!(items[0].kind, CompletionKind::Node);
+
+        let text = "service \"api\" ima";
+        let items = completions(text, text.len(), &[]);
+        assert!(items.iter().any(|i| i.label == "image="));
+    }
+
+    #[test]
+    fn test_complete_stage_services() {
+        let complete = DOC.replace("service \"d\n}", "service \"d\"\n}");
+        let symbols = symbols(&complete);
+        let offset = offset_after(DOC, "service \"d");
+        let items = completions(DOC, offset, &symbols);
+        let labels: Vec<&str> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["db"]);
+    }
+
+    #[test]
+    fn test_reference_and_symbols() {
+        let text = DOC.replace("service \"d\n}", "service \"db\"\n}");
+        let offset = text.rfind("\"db\"").unwrap() + 2;
+        assert_eq!(
+            reference_at(&text, offset),
+            Some(Reference {
+                kind: "service",
+                name: "db".to_string()
+            })
+        );
+
+        let symbols = symbols(&text);
+        let db = symbols.iter().find(|s| s.name == "db").unwrap();
+        assert_eq!(db.kind, "service");
+        assert_eq!(&text[db.span.0..db.span.0 + db.span.1], "\"db\"");
+        // トップレベルの定義自体は参照ではない
+        let offset = text.find("\"db\"").unwrap() + 2;
+        assert_eq!(reference_at(&text, offset), None);
+    }
+
+    #[test]
+    fn test_hover_and_diagnostics() {
+        let text = "service \"api\" {\n    healthcheck {\n    }\n}\n";
+        let offset = text.find("healthcheck").unwrap() + 3;
+        assert!(hover(text, offset).unwrap().starts_with("**healthcheck**"));
+
+        let text = "service \"api\" {\n    imgae \"x\"\n}\n";
+        let diagnostics = diagnostics(text);
+        let unknown = diagnostics
+            .iter()
+            .find(|d| d.message.contains("imgae"))
+            .unwrap();
+        assert_eq!(unknown.severity, Severity::Warning);
+        assert_eq!(&text[unknown.start..unknown.end], "imgae");
+
+        let diagnostics = super::diagnostics("service \"api\" {\n");
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    }
+}
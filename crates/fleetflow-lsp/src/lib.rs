@@ -0,0 +1,347 @@
+//! FleetFlow Language Server
+//!
+//! fleet.kdl 編集用の LSP サーバー。stdio で動作し、以下を提供する。
+//!
+//! - 診断: KDL の構文エラー、未知のノード名・プロパティ、パーサーの検証エラー
+//! - 補完: ノード名・プロパティ名、stage のサービス一覧・depends_on・extends のサービス名、
+//!   ステージ名・サーバー名
+//! - ホバー: スキーマのノード名・プロパティ名の説明
+//! - 定義ジャンプ: stage のサービス一覧などからサービス定義へ
+//!
+//! 名前の解決には開いているファイルに加え、プロジェクトの設定ファイル
+//! （`fleetflow_core::discover_files`）も使う。
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+mod analysis;
+mod text;
+mod transport;
+
+use analysis::{CompletionKind, Severity, Symbol};
+
+/// JSON-RPC: メソッドが存在しない
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// テキスト同期の種類（Full: 変更のたびに全文を受け取る）
+const TEXT_DOCUMENT_SYNC_FULL: u32 = 1;
+
+/// LSP サーバーの状態
+#[derive(Default)]
+struct Server {
+    /// プロジェクトルート（initialize の rootUri、なければカレントディレクトリから探索）
+    root: Option<PathBuf>,
+    /// 開いているドキュメント（URI → 本文）
+    documents: HashMap<String, String>,
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    uri.strip_prefix("file://")
+        .map(|path| PathBuf::from(path.replace("%20", " ")))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    format!("file://{}", path.display().to_string().replace(' ', "%20"))
+}
+
+fn range(text: &str, start: usize, end: usize) -> Value {
+    let (start_line, start_character) = text::offset_to_position(text, start);
+    let (end_line, end_character) = text::offset_to_position(text, end);
+    json!({
+        "start": {"line": start_line, "character": start_character},
+        "end": {"line": end_line, "character": end_character},
+    })
+}
+
+impl Server {
+    /// 名前解決の対象（URI, 本文）: 開いているドキュメントとプロジェクトの設定ファイル
+    fn workspace(&self) -> Vec<(String, String)> {
+        let mut files: Vec<(String, String)> = self
+            .documents
+            .iter()
+            .map(|(uri, text)| (uri.clone(), text.clone()))
+            .collect();
+
+        let root = self
+            .root
+            .clone()
+            .or_else(|| fleetflow_core::find_project_root().ok());
+        let Some(discovered) = root.and_then(|r| fleetflow_core::discover_files(&r).ok()) else {
+            return files;
+        };
+        let paths = discovered
+            .root
+            .into_iter()
+            .chain(discovered.cloud)
+            .chain(discovered.services)
+            .chain(discovered.stages)
+            .chain(discovered.local_override);
+        for path in paths {
+            let uri = path_to_uri(&path);
+            if self.documents.contains_key(&uri) {
+                continue;
+            }
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                files.push((uri, text));
+            }
+        }
+        files
+    }
+
+    fn workspace_symbols(&self) -> Vec<(String, String, Symbol)> {
+        self.workspace()
+            .into_iter()
+            .flat_map(|(uri, text)| {
+                analysis::symbols(&text)
+                    .into_iter()
+                    .map(move |symbol| (uri.clone(), text.clone(), symbol))
+            })
+            .collect()
+    }
+
+    /// リクエストのドキュメントとカーソル位置（バイトオフセット）
+    fn document_position(&self, params: &Value) -> Option<(&str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as u32;
+        let character = params["position"]["character"].as_u64()? as u32;
+        Some((text, text::position_to_offset(text, line, character)))
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let diagnostics: Vec<Value> = self
+            .documents
+            .get(uri)
+            .map(|text| {
+                analysis::diagnostics(text)
+                    .into_iter()
+                    .map(|d| {
+                        json!({
+                            "range": range(text, d.start, d.end),
+                            "severity": match d.severity {
+                                Severity::Error => 1,
+                                Severity::Warning => 2,
+                            },
+                            "source": "fleetflow",
+                            "message": d.message,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics},
+        })
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => {
+                self.root = params["rootUri"]
+                    .as_str()
+                    .or_else(|| params["workspaceFolders"][0]["uri"].as_str())
+                    .and_then(uri_to_path);
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                        "completionProvider": {"triggerCharacters": ["\"", " "]},
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": "fleetflow-lsp",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }))
+            }
+            "shutdown" => Ok(Value::Null),
+            "textDocument/completion" => {
+                let Some((text, offset)) = self.document_position(params) else {
+                    return Ok(Value::Null);
+                };
+                let symbols: Vec<Symbol> = self
+                    .workspace_symbols()
+                    .into_iter()
+                    .map(|(_, _, symbol)| symbol)
+                    .collect();
+                let items: Vec<Value> = analysis::completions(text, offset, &symbols)
+                    .into_iter()
+                    .map(|item| {
+                        json!({
+                            "label": item.label,
+                            // CompletionItemKind: Property=10, Keyword=14, Reference=18
+                            "kind": match item.kind {
+                                CompletionKind::Node => 14,
+                                CompletionKind::Property => 10,
+                                CompletionKind::Reference => 18,
+                            },
+                            "detail": item.detail,
+                        })
+                    })
+                    .collect();
+                Ok(json!(items))
+            }
+            "textDocument/hover" => {
+                let hover = self
+                    .document_position(params)
+                    .and_then(|(text, offset)| analysis::hover(text, offset));
+                Ok(hover.map_or(
+                    Value::Null,
+                    |markdown| json!({"contents": {"kind": "markdown", "value": markdown}}),
+                ))
+            }
+            "textDocument/definition" => {
+                let Some(reference) = self
+                    .document_position(params)
+                    .and_then(|(text, offset)| analysis::reference_at(text, offset))
+                else {
+                    return Ok(Value::Null);
+                };
+                let location = self
+                    .workspace_symbols()
+                    .into_iter()
+                    .find(|(_, _, s)| s.kind == reference.kind && s.name == reference.name)
+                    .map(|(uri, text, symbol)| {
+                        json!({
+                            "uri": uri,
+                            "range": range(&text, symbol.span.0, symbol.span.0 + symbol.span.1),
+                        })
+                    });
+                Ok(location.unwrap_or(Value::Null))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("未対応のメソッド: {}", method))),
+        }
+    }
+
+    /// 通知を処理し、送り返す通知（診断）を返す
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+                vec![self.publish_diagnostics(uri)]
+            }
+            "textDocument/didChange" => {
+                // Full 同期なので最後の変更が全文
+                let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return Vec::new();
+                };
+                self.documents.insert(uri.to_string(), text.to_string());
+                vec![self.publish_diagnostics(uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![self.publish_diagnostics(uri)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// LSP サーバーを起動（stdio）
+///
+/// クライアントが `exit` を送るか入力を閉じるまで処理を続ける。
+pub fn run_server() -> anyhow::Result<()> {
+    let mut reader = BufReader::new(std::io::stdin().lock());
+    let mut writer = std::io::stdout().lock();
+    let mut server = Server::default();
+
+    while let Some(message) = transport::read_message(&mut reader)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        if method == "exit" {
+            break;
+        }
+
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.handle_request(method, params) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err((code, error)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": code, "message": error},
+                    }),
+                };
+                transport::write_message(&mut writer, &response)?;
+            }
+            None => {
+                for notification in server.handle_notification(method, params) {
+                    transport::write_message(&mut writer, &notification)?;
+                }
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(server: &mut Server, uri: &str, text: &str) -> Vec<Value> {
+        server.handle_notification(
+            "textDocument/didOpen",
+            &json!({"textDocument": {"uri": uri, "text": text}}),
+        )
+    }
+
+    #[test]
+    fn test_definition_from_stage_service_list() {
+        let mut server = Server {
+            root: Some(PathBuf::from("/nonexistent")),
+            ..Default::default()
+        };
+        open(
+            &mut server,
+            "file:///p/services/db.kdl",
+            "service \"db\" {\n    image \"postgres\"\n}\n",
+        );
+        open(
+            &mut server,
+            "file:///p/fleet.kdl",
+            "stage \"local\" {\n    service \"db\"\n}\n",
+        );
+
+        let result = server
+            .handle_request(
+                "textDocument/definition",
+                &json!({
+                    "textDocument": {"uri": "file:///p/fleet.kdl"},
+                    "position": {"line": 1, "character": 14},
+                }),
+            )
+            .unwrap();
+        assert_eq!(result["uri"], "file:///p/services/db.kdl");
+        assert_eq!(result["range"]["start"], json!({"line": 0, "character": 8}));
+    }
+
+    #[test]
+    fn test_did_open_publishes_diagnostics() {
+        let mut server = Server::default();
+        let notifications = open(&mut server, "file:///p/fleet.kdl", "service \"api\" {\n");
+        assert_eq!(
+            notifications[0]["method"],
+            "textDocument/publishDiagnostics"
+        );
+        assert!(
+            !notifications[0]["params"]["diagnostics"]
+                .as_array()
+                .unwrap()
+                .is_empty()
+        );
+
+        let result = server.handle_request("unknown/method", &Value::Null);
+        assert_eq!(result.unwrap_err().0, METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,59 @@
+//! バイトオフセットと LSP の位置（行, UTF-16 の列）の変換
+
+/// バイトオフセットを (行, UTF-16 の列) に変換
+pub fn offset_to_position(text: &str, offset: usize) -> (u32, u32) {
+    let offset = offset.min(text.len());
+    let before = &text[..floor_char_boundary(text, offset)];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    (line as u32, character as u32)
+}
+
+/// (行, UTF-16 の列) をバイトオフセットに変換（範囲外は行末・末尾に丸める）
+pub fn position_to_offset(text: &str, line: u32, character: u32) -> usize {
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if c == '\n' || units >= character as usize {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_round_trip() {
+        let text = "project \"app\"\n// 日本語 🚀\nservice \"web\"\n";
+        let offset = text.find("service").unwrap();
+        assert_eq!(offset_to_position(text, offset), (2, 0));
+        assert_eq!(position_to_offset(text, 2, 0), offset);
+
+        // 🚀 は UTF-16 で 2 単位
+        let offset = text.find('🚀').unwrap() + '🚀'.len_utf8();
+        assert_eq!(offset_to_position(text, offset), (1, 9));
+        assert_eq!(position_to_offset(text, 1, 9), offset);
+
+        // 範囲外
+        assert_eq!(position_to_offset(text, 0, 100), text.find('\n').unwrap());
+        assert_eq!(position_to_offset(text, 10, 0), text.len());
+    }
+}
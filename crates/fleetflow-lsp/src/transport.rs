@@ -0,0 +1,63 @@
+//! LSP のメッセージ入出力（`Content-Length` ヘッダ + JSON-RPC 本文）
+
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// メッセージを 1 件読み込む（入力が閉じられたら None）
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Content-Length ヘッダがありません",
+        )
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// メッセージを 1 件書き込む
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let message = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &json!({"jsonrpc": "2.0", "method": "exit"})).unwrap();
+
+        let mut reader = io::Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(
+            read_message(&mut reader).unwrap().unwrap()["method"],
+            "exit"
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+}
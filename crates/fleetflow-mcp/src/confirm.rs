@@ -0,0 +1,131 @@
+//! 影響の大きい操作の 2 段階確認
+//!
+//! 1 回目の呼び出しでは実行計画と確認トークンを返し、同じパラメータで
+//! トークンを付けて再度呼び出した場合にのみ実行する。トークンは 1 回限りで、
+//! 一定時間で失効する。計画作成後に設定が再読み込みされた場合も無効になる。
+
+use fleetflow_core::Flow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 確認トークンの有効期間
+pub const TOKEN_TTL: Duration = Duration::from_secs(300);
+
+struct Pending {
+    /// 操作内容（ツール名とパラメータ）
+    action: String,
+    /// 計画作成時の設定（再読み込みされていないことの確認用）
+    flow: Arc<Flow>,
+    issued_at: Instant,
+}
+
+/// 発行済みの確認トークン
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl Confirmations {
+    /// 操作に対する確認トークンを発行
+    pub fn issue(&self, action: &str, flow: &Arc<Flow>) -> String {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|_, p| p.issued_at.elapsed() < TOKEN_TTL);
+
+        let token = new_token();
+        pending.insert(
+            token.clone(),
+            Pending {
+                action: action.to_string(),
+                flow: Arc::clone(flow),
+                issued_at: Instant::now(),
+            },
+        );
+        token
+    }
+
+    /// トークンを検証して消費する
+    pub fn consume(&self, token: &str, action: &str, flow: &Arc<Flow>) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = pending.remove(token).ok_or_else(|| {
+            "確認トークンが無効です（使用済み・期限切れ・不明）。confirm_token を付けずに呼び出して計画を再取得してください".to_string()
+        })?;
+
+        if entry.issued_at.elapsed() >= TOKEN_TTL {
+            return Err("確認トークンの有効期限が切れました。計画を再取得してください".to_string());
+        }
+        if entry.action != action {
+            return Err(
+                "確認トークンが別の操作のものです。計画を取得したときと同じパラメータで呼び出してください"
+                    .to_string(),
+            );
+        }
+        if !Arc::ptr_eq(&entry.flow, flow) {
+            return Err(
+                "計画の作成後に設定ファイルが変更されました。計画を再取得してください".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// 推測されにくいトークン（RandomState はプロセスごとにランダムな鍵を持つ）
+fn new_token() -> String {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow() -> Arc<Flow> {
+        Arc::new(Flow {
+            name: "test".to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn token_is_single_use() {
+        let confirmations = Confirmations::default();
+        let flow = flow();
+        let token = confirmations.issue("deploy prod", &flow);
+
+        assert!(confirmations.consume(&token, "deploy prod", &flow).is_ok());
+        assert!(confirmations.consume(&token, "deploy prod", &flow).is_err());
+    }
+
+    #[test]
+    fn token_is_bound_to_action_and_config() {
+        let confirmations = Confirmations::default();
+        let flow = flow();
+
+        let token = confirmations.issue("deploy prod", &flow);
+        assert!(confirmations.consume(&token, "deploy dev", &flow).is_err());
+
+        let token = confirmations.issue("deploy prod", &flow);
+        let reloaded = Arc::new((*flow).clone());
+        assert!(
+            confirmations
+                .consume(&token, "deploy prod", &reloaded)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn tokens_are_unique() {
+        let confirmations = Confirmations::default();
+        let flow = flow();
+        let a = confirmations.issue("setup prod", &flow);
+        let b = confirmations.issue("setup prod", &flow);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,338 @@
+//! 定義（KDL）と稼働中コンテナの比較
+//!
+//! `fleetflow_inspect_service` / `fleetflow_diff` が返す構造化 JSON を組み立てる。
+//! 期待値は `fleet up` と同じ変換（`service_to_container_config`）から作るため、
+//! エージェントは実際に作成されるコンテナ設定との差分をそのまま扱える。
+
+use bollard::models::{ContainerCreateBody, ContainerInspectResponse, HostConfig, PortBinding};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+
+/// マスク済みの値
+const REDACTED: &str = "***";
+
+/// 環境変数キーがセンシティブ（マスク対象）かどうか
+fn is_sensitive_key(key: &str) -> bool {
+    let lower = key.to_lowercase();
+    lower.contains("pass")
+        || lower.contains("secret")
+        || lower.contains("key")
+        || lower.contains("token")
+}
+
+/// 比較対象のコンテナ設定
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ContainerSpec {
+    pub image: Option<String>,
+    pub command: Option<Vec<String>>,
+    pub env: BTreeMap<String, String>,
+    /// `host_ip:host_port->container_port/proto`（ソート済み）
+    pub ports: Vec<String>,
+    /// `host:container:mode`（ソート済み）
+    pub volumes: Vec<String>,
+    pub restart: String,
+    pub user: Option<String>,
+    pub network_mode: Option<String>,
+}
+
+impl ContainerSpec {
+    /// `fleet up` が作成するコンテナ設定から
+    pub fn from_create_body(body: &ContainerCreateBody) -> Self {
+        let host_config = body.host_config.as_ref();
+        Self {
+            image: body.image.clone(),
+            command: body.cmd.clone(),
+            env: parse_env(body.env.as_deref().unwrap_or_default()),
+            ports: host_config.map(format_ports).unwrap_or_default(),
+            volumes: host_config.map(sorted_binds).unwrap_or_default(),
+            restart: host_config.map(restart_name).unwrap_or_else(|| "no".into()),
+            user: body.user.clone().filter(|u| !u.is_empty()),
+            network_mode: host_config.and_then(|h| h.network_mode.clone()),
+        }
+    }
+
+    /// 稼働中コンテナの inspect 結果から
+    pub fn from_inspect(info: &ContainerInspectResponse) -> Self {
+        let config = info.config.as_ref();
+        let host_config = info.host_config.as_ref();
+        Self {
+            image: config.and_then(|c| c.image.clone()),
+            command: config.and_then(|c| c.cmd.clone()),
+            env: parse_env(config.and_then(|c| c.env.as_deref()).unwrap_or_default()),
+            ports: host_config.map(format_ports).unwrap_or_default(),
+            volumes: host_config.map(sorted_binds).unwrap_or_default(),
+            restart: host_config.map(restart_name).unwrap_or_else(|| "no".into()),
+            user: config
+                .and_then(|c| c.user.clone())
+                .filter(|u| !u.is_empty()),
+            network_mode: host_config.and_then(|h| h.network_mode.clone()),
+        }
+    }
+
+    /// センシティブな環境変数の値をマスクした JSON
+    pub fn to_masked_json(&self) -> Value {
+        let mut spec = self.clone();
+        for (key, value) in spec.env.iter_mut() {
+            if is_sensitive_key(key) {
+                *value = REDACTED.to_string();
+            }
+        }
+        serde_json::to_value(spec).unwrap_or(Value::Null)
+    }
+}
+
+/// コンテナの実行状態
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuntimeState {
+    pub status: String,
+    pub running: bool,
+    pub exit_code: Option<i64>,
+    pub health: Option<String>,
+    pub restart_count: i64,
+    pub started_at: Option<String>,
+    pub image_id: Option<String>,
+}
+
+impl RuntimeState {
+    pub fn from_inspect(info: &ContainerInspectResponse) -> Self {
+        let state = info.state.as_ref();
+        Self {
+            status: state
+                .and_then(|s| s.status.as_ref())
+                .map_or_else(|| "unknown".to_string(), |s| s.to_string()),
+            running: state.and_then(|s| s.running).unwrap_or(false),
+            exit_code: state.and_then(|s| s.exit_code),
+            health: state
+                .and_then(|s| s.health.as_ref())
+                .and_then(|h| h.status.as_ref())
+                .map(|s| s.to_string())
+                .filter(|s| !s.is_empty() && s != "none"),
+            restart_count: info.restart_count.unwrap_or(0),
+            started_at: state.and_then(|s| s.started_at.clone()),
+            image_id: info.image.clone(),
+        }
+    }
+}
+
+/// 1 項目の差分
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Drift {
+    /// 項目名（`image`, `env.DATABASE_URL`, `ports` など）
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+impl Drift {
+    fn new(field: impl Into<String>, expected: impl Serialize, actual: impl Serialize) -> Self {
+        Self {
+            field: field.into(),
+            expected: serde_json::to_value(expected).unwrap_or(Value::Null),
+            actual: serde_json::to_value(actual).unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// 定義と稼働中コンテナの差分
+///
+/// `command` / `user` / `network_mode` は定義で指定した場合のみ比較する（未指定なら
+/// イメージ・デーモンの既定値が使われるため）。環境変数とボリュームは定義にあるものが
+/// コンテナに揃っているかを比較する（イメージ由来の `PATH` や config / secret マウントは
+/// 差分にしない）。センシティブな環境変数の値はマスクする。
+pub fn diff(expected: &ContainerSpec, actual: &ContainerSpec) -> Vec<Drift> {
+    let mut drifts = Vec::new();
+
+    if expected.image != actual.image {
+        drifts.push(Drift::new("image", &expected.image, &actual.image));
+    }
+    if expected.command.is_some() && expected.command != actual.command {
+        drifts.push(Drift::new("command", &expected.command, &actual.command));
+    }
+    for (key, value) in &expected.env {
+        let current = actual.env.get(key);
+        if current == Some(value) {
+            continue;
+        }
+        let drift = if is_sensitive_key(key) {
+            Drift::new(format!("env.{}", key), REDACTED, current.map(|_| REDACTED))
+        } else {
+            Drift::new(format!("env.{}", key), value, current)
+        };
+        drifts.push(drift);
+    }
+    if expected.ports != actual.ports {
+        drifts.push(Drift::new("ports", &expected.ports, &actual.ports));
+    }
+    let missing: Vec<&String> = expected
+        .volumes
+        .iter()
+        .filter(|v| !actual.volumes.contains(v))
+        .collect();
+    if !missing.is_empty() {
+        drifts.push(Drift::new("volumes", &missing, &actual.volumes));
+    }
+    if expected.restart != actual.restart {
+        drifts.push(Drift::new("restart", &expected.restart, &actual.restart));
+    }
+    if expected.user.is_some() && expected.user != actual.user {
+        drifts.push(Drift::new("user", &expected.user, &actual.user));
+    }
+    if expected.network_mode.is_some() && expected.network_mode != actual.network_mode {
+        drifts.push(Drift::new(
+            "network_mode",
+            &expected.network_mode,
+            &actual.network_mode,
+        ));
+    }
+    drifts
+}
+
+/// サービス 1 件分の比較結果（JSON）
+///
+/// コンテナが無い場合は `actual: null` とし、`state` の差分として扱う。
+pub fn service_report(
+    service: &str,
+    container: &str,
+    expected: &ContainerSpec,
+    inspect: Option<&ContainerInspectResponse>,
+) -> Value {
+    let Some(info) = inspect else {
+        return json!({
+            "service": service,
+            "container": container,
+            "spec": expected.to_masked_json(),
+            "state": Value::Null,
+            "actual": Value::Null,
+            "in_sync": false,
+            "drift": [Drift::new("state", "running", Value::Null)],
+        });
+    };
+
+    let actual = ContainerSpec::from_inspect(info);
+    let state = RuntimeState::from_inspect(info);
+    let mut drifts = diff(expected, &actual);
+    if !state.running {
+        drifts.insert(0, Drift::new("state", "running", &state.status));
+    }
+    json!({
+        "service": service,
+        "container": container,
+        "spec": expected.to_masked_json(),
+        "state": state,
+        "actual": actual.to_masked_json(),
+        "in_sync": drifts.is_empty(),
+        "drift": drifts,
+    })
+}
+
+fn parse_env(entries: &[String]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .filter_map(|e| e.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn format_ports(host_config: &HostConfig) -> Vec<String> {
+    let bindings: &HashMap<String, Option<Vec<PortBinding>>> = match &host_config.port_bindings {
+        Some(bindings) => bindings,
+        None => return Vec::new(),
+    };
+    let mut ports: Vec<String> = bindings
+        .iter()
+        .flat_map(|(container_port, bindings)| {
+            bindings.iter().flatten().map(move |b| {
+                format!(
+                    "{}:{}->{}",
+                    b.host_ip
+                        .as_deref()
+                        .filter(|ip| !ip.is_empty())
+                        .unwrap_or("0.0.0.0"),
+                    b.host_port.as_deref().unwrap_or_default(),
+                    container_port
+                )
+            })
+        })
+        .collect();
+    ports.sort();
+    ports
+}
+
+fn sorted_binds(host_config: &HostConfig) -> Vec<String> {
+    let mut binds = host_config.binds.clone().unwrap_or_default();
+    binds.sort();
+    binds
+}
+
+fn restart_name(host_config: &HostConfig) -> String {
+    host_config
+        .restart_policy
+        .as_ref()
+        .and_then(|p| p.name.as_ref())
+        .map(|n| n.to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "no".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ContainerSpec {
+        ContainerSpec {
+            image: Some("postgres:16".into()),
+            command: None,
+            env: BTreeMap::from([
+                ("POSTGRES_DB".into(), "app".into()),
+                ("POSTGRES_PASSWORD".into(), "hunter2".into()),
+            ]),
+            ports: vec!["0.0.0.0:5432->5432/tcp".into()],
+            volumes: vec!["/data/pg:/var/lib/postgresql/data:rw".into()],
+            restart: "unless-stopped".into(),
+            user: None,
+            network_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_in_sync_ignores_image_defaults() {
+        let expected = spec();
+        let mut actual = spec();
+        actual.command = Some(vec!["postgres".into()]);
+        actual.env.insert("PATH".into(), "/usr/bin".into());
+        actual.volumes.push("/run/secrets:/run/secrets:ro".into());
+        assert!(diff(&expected, &actual).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_fields_and_masks_secrets() {
+        let expected = spec();
+        let mut actual = spec();
+        actual.image = Some("postgres:15".into());
+        actual.env.insert("POSTGRES_DB".into(), "legacy".into());
+        actual
+            .env
+            .insert("POSTGRES_PASSWORD".into(), "changed".into());
+        actual.ports.clear();
+
+        let drifts = diff(&expected, &actual);
+        let fields: Vec<&str> = drifts.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["image", "env.POSTGRES_DB", "env.POSTGRES_PASSWORD", "ports"]
+        );
+        assert_eq!(drifts[0].actual, json!("postgres:15"));
+        assert_eq!(drifts[1].expected, json!("app"));
+        assert_eq!(drifts[2].expected, json!("***"));
+        assert_eq!(drifts[2].actual, json!("***"));
+    }
+
+    #[test]
+    fn test_service_report_without_container() {
+        let report = service_report("db", "app-local-db", &spec(), None);
+        assert_eq!(report["in_sync"], json!(false));
+        assert_eq!(report["drift"][0]["field"], json!("state"));
+        assert_eq!(report["spec"]["env"]["POSTGRES_PASSWORD"], json!("***"));
+    }
+}
@@ -0,0 +1,196 @@
+//! HTTP トランスポートのアクセス制御
+//!
+//! MCP のツールはコンテナの起動・停止・exec まで行えるため、HTTP で待ち受けるときは
+//! すべてのリクエストに Bearer トークンを要求する。加えて Host / Origin ヘッダーが
+//! 待ち受けアドレスと一致しないリクエストを拒否し、ブラウザ経由の DNS リバインディングを防ぐ。
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use rand::Rng;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// トークンを渡す環境変数（未設定なら起動時に生成する）
+pub const TOKEN_ENV: &str = "FLEET_MCP_TOKEN";
+
+/// 起動時に生成するトークンの長さ
+const TOKEN_LEN: usize = 40;
+
+/// 推測できないアクセストークンを生成
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// リクエストの検証条件
+pub struct HttpGuard {
+    token: String,
+    /// 受け付ける Host（None なら任意のホスト名でポートのみ照合）
+    allowed_hosts: Option<Vec<String>>,
+    port: u16,
+}
+
+impl HttpGuard {
+    pub fn new(addr: SocketAddr, token: String) -> Self {
+        let port = addr.port();
+        let allowed_hosts = if addr.ip().is_unspecified() {
+            // 0.0.0.0 / :: ではクライアントが使うホスト名を事前に決められない
+            None
+        } else {
+            let mut hosts = vec![addr.to_string()];
+            if addr.ip().is_loopback() {
+                hosts.extend([
+                    format!("localhost:{port}"),
+                    format!("127.0.0.1:{port}"),
+                    format!("[::1]:{port}"),
+                ]);
+            }
+            Some(hosts)
+        };
+        Self {
+            token,
+            allowed_hosts,
+            port,
+        }
+    }
+
+    fn host_allowed(&self, host: &str) -> bool {
+        match &self.allowed_hosts {
+            Some(hosts) => hosts.iter().any(|h| h.eq_ignore_ascii_case(host)),
+            None => host
+                .rsplit_once(':')
+                .is_some_and(|(_, port)| port == self.port.to_string()),
+        }
+    }
+
+    /// Host・Origin・Authorization を検証する
+    pub fn check(&self, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(StatusCode::FORBIDDEN)?;
+        if !self.host_allowed(host) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        // ブラウザからのリクエストは Origin が Host と同じでなければ拒否
+        if let Some(origin) = headers.get(header::ORIGIN) {
+            let origin = origin.to_str().map_err(|_| StatusCode::FORBIDDEN)?;
+            let origin_host = origin
+                .strip_prefix("http://")
+                .or_else(|| origin.strip_prefix("https://"))
+                .ok_or(StatusCode::FORBIDDEN)?;
+            if !origin_host.eq_ignore_ascii_case(host) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+        }
+
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if !constant_time_eq(presented.trim().as_bytes(), self.token.as_bytes()) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Ok(())
+    }
+}
+
+/// 比較時間から一致した長さを推測されないよう全バイトを比較する
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// axum のミドルウェア
+pub async fn guard_request(
+    State(guard): State<Arc<HttpGuard>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match guard.check(request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(status) => status.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn guard() -> HttpGuard {
+        HttpGuard::new("127.0.0.1:8787".parse().unwrap(), "secret".to_string())
+    }
+
+    #[test]
+    fn test_check_accepts_token_and_local_host() {
+        let h = headers(&[
+            (header::HOST, "localhost:8787"),
+            (header::AUTHORIZATION, "Bearer secret"),
+        ]);
+        assert_eq!(guard().check(&h), Ok(()));
+    }
+
+    #[test]
+    fn test_check_requires_token() {
+        let missing = headers(&[(header::HOST, "127.0.0.1:8787")]);
+        assert_eq!(guard().check(&missing), Err(StatusCode::UNAUTHORIZED));
+
+        let wrong = headers(&[
+            (header::HOST, "127.0.0.1:8787"),
+            (header::AUTHORIZATION, "Bearer other"),
+        ]);
+        assert_eq!(guard().check(&wrong), Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_check_rejects_rebound_host_and_foreign_origin() {
+        let rebound = headers(&[
+            (header::HOST, "attacker.example:8787"),
+            (header::AUTHORIZATION, "Bearer secret"),
+        ]);
+        assert_eq!(guard().check(&rebound), Err(StatusCode::FORBIDDEN));
+
+        let foreign_origin = headers(&[
+            (header::HOST, "127.0.0.1:8787"),
+            (header::ORIGIN, "http://attacker.example"),
+            (header::AUTHORIZATION, "Bearer secret"),
+        ]);
+        assert_eq!(guard().check(&foreign_origin), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_unspecified_bind_checks_port_only() {
+        let guard = HttpGuard::new("0.0.0.0:8787".parse().unwrap(), "secret".to_string());
+        let ok = headers(&[
+            (header::HOST, "build-host.lan:8787"),
+            (header::AUTHORIZATION, "Bearer secret"),
+        ]);
+        assert_eq!(guard.check(&ok), Ok(()));
+        let other_port = headers(&[
+            (header::HOST, "build-host.lan:9000"),
+            (header::AUTHORIZATION, "Bearer secret"),
+        ]);
+        assert_eq!(guard.check(&other_port), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_generate_token() {
+        let a = generate_token();
+        assert_eq!(a.len(), TOKEN_LEN);
+        assert_ne!(a, generate_token());
+    }
+}
@@ -14,9 +14,18 @@ use rmcp::{
 use schemars::JsonSchema;
 use serde::Deserialize;
 use std::collections::HashMap;
-use tracing::error;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{error, info};
 
+mod confirm;
 mod cp;
+mod drift;
+pub mod http_auth;
+mod prompts;
+mod state;
+
+use state::ServerState;
 
 // ============================================================================
 // パラメータ定義
@@ -59,6 +68,24 @@ pub struct RestartParam {
     pub service: String,
 }
 
+/// サービス詳細パラメータ
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct InspectServiceParam {
+    /// ステージ名
+    pub stage: String,
+    /// サービス名
+    pub service: String,
+}
+
+/// 差分取得パラメータ
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiffParam {
+    /// ステージ名
+    pub stage: String,
+    /// 対象のサービス名（省略時はステージの全サービス）
+    pub service: Option<String>,
+}
+
 /// ビルドパラメータ
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct BuildParam {
@@ -71,6 +98,33 @@ pub struct BuildParam {
     pub no_cache: bool,
 }
 
+/// デプロイパラメータ
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DeployParam {
+    /// ステージ名
+    pub stage: String,
+    /// デプロイ対象のサービス（省略時はステージの全サービス）
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// イメージの pull をスキップする場合は true
+    #[serde(default)]
+    pub no_pull: bool,
+    /// デプロイ後の不要イメージ削除をスキップする場合は true
+    #[serde(default)]
+    pub no_prune: bool,
+    /// 1 回目の呼び出しで返された確認トークン（省略時は計画のみ返す）
+    pub confirm_token: Option<String>,
+}
+
+/// セットアップパラメータ
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SetupParam {
+    /// ステージ名
+    pub stage: String,
+    /// 1 回目の呼び出しで返された確認トークン（省略時は計画のみ返す）
+    pub confirm_token: Option<String>,
+}
+
 // ============================================================================
 // CP パラメータ定義（v2）
 // ============================================================================
@@ -145,6 +199,7 @@ pub struct ContainerLogParam {
 #[derive(Clone)]
 pub struct FleetFlowServer {
     tool_router: ToolRouter<Self>,
+    state: Arc<ServerState>,
 }
 
 impl Default for FleetFlowServer {
@@ -158,18 +213,17 @@ impl FleetFlowServer {
     pub fn new() -> Self {
         Self {
             tool_router: Self::tool_router(),
+            state: Arc::new(ServerState::default()),
         }
     }
 
     /// プロジェクト情報を取得
     #[tool(
-        description = "カレントディレクトリにある FleetFlow プロジェクト（fleet.kdl 等）を解析し、定義されているサービス名、イメージ名、ステージ名、環境変数などの情報を取得します。"
+        description = "カレントディレクトリにある FleetFlow プロジェクト（fleet.kdl 等）を解析し、定義されているサービス名、イメージ名、ステージ名、環境変数などの情報を取得します。",
+        annotations(read_only_hint = true, open_world_hint = false)
     )]
     async fn fleetflow_inspect_project(&self) -> Result<String, String> {
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
+        let (_, config) = self.state.project()?;
 
         let mut info = format!("Project: {}\n\n", config.name);
 
@@ -193,15 +247,12 @@ impl FleetFlowServer {
 
     /// コンテナ一覧を表示
     #[tool(
-        description = "コンテナの一覧を表示します。プロジェクトに関連するコンテナの稼働状況を確認できます。"
+        description = "コンテナの一覧を表示します。プロジェクトに関連するコンテナの稼働状況を確認できます。",
+        annotations(read_only_hint = true, open_world_hint = false)
     )]
     async fn fleetflow_ps(&self) -> Result<String, String> {
-        let docker = bollard::Docker::connect_with_local_defaults()
-            .map_err(|e| format!("Docker接続エラー: {}", e))?;
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
+        let docker = self.state.docker()?;
+        let (_, config) = self.state.project()?;
 
         let mut filter = HashMap::new();
         filter.insert(
@@ -245,15 +296,18 @@ impl FleetFlowServer {
 
     /// ステージを起動
     #[tool(
-        description = "指定されたステージのコンテナを起動します。ネットワークの作成や、既に存在するコンテナの再起動も行います。"
+        description = "指定されたステージのコンテナを起動します。ネットワークの作成や、既に存在するコンテナの再起動も行います。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
     )]
     async fn fleetflow_up(&self, params: Parameters<StageParam>) -> Result<String, String> {
         let stage = &params.0.stage;
 
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
+        let (project_root, config) = self.state.project()?;
 
         let runtime = fleetflow_container::Runtime::new(project_root)
             .map_err(|e| format!("Runtimeの初期化に失敗: {}", e))?;
@@ -269,16 +323,22 @@ impl FleetFlowServer {
 
     /// ステージを停止
     #[tool(
-        description = "指定されたステージのコンテナを停止します。remove=true でコンテナとネットワークを完全に削除します。"
+        description = "指定されたステージのコンテナを停止します。remove=true でコンテナとネットワークを完全に削除します（mcp { enable_destructive_tools #false } の場合は remove=true を拒否します）。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
     )]
     async fn fleetflow_down(&self, params: Parameters<DownParam>) -> Result<String, String> {
         let stage = &params.0.stage;
         let remove = params.0.remove;
 
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
+        let (project_root, config) = self.state.project()?;
+        if remove && !destructive_tools_enabled(&config) {
+            return Err(DESTRUCTIVE_TOOLS_DISABLED.to_string());
+        }
 
         let runtime = fleetflow_container::Runtime::new(project_root)
             .map_err(|e| format!("Runtimeの初期化に失敗: {}", e))?;
@@ -302,7 +362,8 @@ impl FleetFlowServer {
 
     /// ログを取得
     #[tool(
-        description = "指定されたステージのコンテナログを取得します。特定のサービスを指定することも可能です。"
+        description = "指定されたステージのコンテナログを取得します。特定のサービスを指定することも可能です。",
+        annotations(read_only_hint = true, open_world_hint = false)
     )]
     async fn fleetflow_logs(&self, params: Parameters<LogsParam>) -> Result<String, String> {
         use futures_util::StreamExt;
@@ -311,15 +372,11 @@ impl FleetFlowServer {
         let service = params.0.service.as_deref();
         let tail = params.0.tail.unwrap_or(50) as usize;
 
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
-        let docker = bollard::Docker::connect_with_local_defaults()
-            .map_err(|e| format!("Docker接続エラー: {}", e))?;
+        let (_, config) = self.state.project()?;
+        let docker = self.state.docker()?;
 
         let container_name = if let Some(svc) = service {
-            format!("{}-{}-{}", config.name, stage, svc)
+            config.container_name(stage, svc)
         } else {
             let stage_config = config
                 .stages
@@ -329,7 +386,7 @@ impl FleetFlowServer {
                 .services
                 .first()
                 .ok_or_else(|| format!("No services in stage '{}'", stage))?;
-            format!("{}-{}-{}", config.name, stage, first_service)
+            config.container_name(stage, first_service)
         };
 
         let options = bollard::query_parameters::LogsOptions {
@@ -355,19 +412,29 @@ impl FleetFlowServer {
     }
 
     /// サービスを再起動
-    #[tool(description = "指定されたサービスのコンテナを再起動します。")]
+    #[tool(
+        description = "指定されたサービスのコンテナを再起動します。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
     async fn fleetflow_restart(&self, params: Parameters<RestartParam>) -> Result<String, String> {
         let stage = &params.0.stage;
         let service = &params.0.service;
 
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
-        let docker = bollard::Docker::connect_with_local_defaults()
-            .map_err(|e| format!("Docker接続エラー: {}", e))?;
+        let (_, config) = self.state.project()?;
+        let docker = self.state.docker()?;
+
+        let container_name = config.container_name(stage, service);
 
-        let container_name = format!("{}-{}-{}", config.name, stage, service);
+        // tmpfs 上のシークレットはホスト再起動で消えるため、bind mount 元を書き戻す
+        if let Some(definition) = config.services.get(service) {
+            fleetflow_container::materialize_secrets(&config, stage, service, definition)
+                .map_err(|e| format!("シークレットの書き出しに失敗: {}", e))?;
+        }
 
         docker
             .restart_container(
@@ -383,8 +450,80 @@ impl FleetFlowServer {
         ))
     }
 
+    /// サービスの定義と稼働状態を取得（JSON）
+    #[tool(
+        description = "指定サービスの解決済み定義（fleet up が作成するコンテナ設定: image, command, env, ports, volumes, restart 等）と稼働中コンテナの設定・状態、その差分を構造化 JSON で返します。センシティブな環境変数の値はマスクされます。",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn fleetflow_inspect_service(
+        &self,
+        params: Parameters<InspectServiceParam>,
+    ) -> Result<String, String> {
+        let InspectServiceParam { stage, service } = params.0;
+        let (_, config) = self.state.project()?;
+        let (_, services) = local_stage_services(&config, &stage)?;
+        if !services.contains(&service) {
+            return Err(format!(
+                "サービス '{}' はステージ '{}' のコンテナサービスに含まれていません",
+                service, stage
+            ));
+        }
+        let docker = self.state.docker()?;
+        let report = drift_report(&docker, &config, &stage, &service).await;
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "project": config.name,
+            "stage": stage,
+            "service": report,
+        }))
+        .unwrap_or_default())
+    }
+
+    /// 定義と稼働中コンテナの差分を取得（JSON）
+    #[tool(
+        description = "ステージ（または指定サービス）について、解決済み定義と稼働中コンテナの差分を構造化 JSON で返します。各サービスの in_sync と、field / expected / actual からなる drift の配列を含みます。修正を提案する前のドリフト確認に使ってください。",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn fleetflow_diff(&self, params: Parameters<DiffParam>) -> Result<String, String> {
+        let DiffParam { stage, service } = params.0;
+        let (_, config) = self.state.project()?;
+        let (_, services) = local_stage_services(&config, &stage)?;
+        let targets = match service {
+            Some(service) if services.contains(&service) => vec![service],
+            Some(service) => {
+                return Err(format!(
+                    "サービス '{}' はステージ '{}' のコンテナサービスに含まれていません",
+                    service, stage
+                ));
+            }
+            None => services,
+        };
+
+        let docker = self.state.docker()?;
+        let mut reports = Vec::new();
+        for service in &targets {
+            let mut report = drift_report(&docker, &config, &stage, service).await;
+            // diff では比較結果のみ返す（定義全体は fleetflow_inspect_service で取得）
+            if let Some(report) = report.as_object_mut() {
+                report.remove("spec");
+                report.remove("actual");
+            }
+            reports.push(report);
+        }
+        let in_sync = reports.iter().all(|r| r["in_sync"] == true);
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "project": config.name,
+            "stage": stage,
+            "in_sync": in_sync,
+            "services": reports,
+        }))
+        .unwrap_or_default())
+    }
+
     /// 設定を検証
-    #[tool(description = "FleetFlow設定ファイル（fleet.kdl等）の構文と整合性を検証します。")]
+    #[tool(
+        description = "FleetFlow設定ファイル（fleet.kdl等）の構文と整合性を検証します。",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
     async fn fleetflow_validate(&self) -> Result<String, String> {
         let project_root = fleetflow_core::find_project_root()
             .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
@@ -414,16 +553,21 @@ impl FleetFlowServer {
     }
 
     /// イメージをビルド
-    #[tool(description = "指定されたサービスのDockerイメージをビルドします。")]
+    #[tool(
+        description = "指定されたサービスのDockerイメージをビルドします。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
     async fn fleetflow_build(&self, params: Parameters<BuildParam>) -> Result<String, String> {
         let stage = &params.0.stage;
         let service_filter = params.0.service.as_deref();
         let no_cache = params.0.no_cache;
 
-        let project_root = fleetflow_core::find_project_root()
-            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
-        let config = fleetflow_core::load_project_from_root(&project_root)
-            .map_err(|e| format!("設定の読み込みに失敗: {}", e))?;
+        let (project_root, config) = self.state.project()?;
 
         let stage_config = config.stages.get(stage).ok_or_else(|| {
             format!(
@@ -433,8 +577,7 @@ impl FleetFlowServer {
             )
         })?;
 
-        let docker = bollard::Docker::connect_with_local_defaults()
-            .map_err(|e| format!("Docker接続エラー: {}", e))?;
+        let docker = self.state.docker()?;
         let resolver = fleetflow_build::BuildResolver::new(project_root.clone());
         let builder = fleetflow_build::ImageBuilder::new(docker);
 
@@ -496,12 +639,40 @@ impl FleetFlowServer {
                     }
                 };
 
-            match builder
-                .build_image(context_data, &image_tag, build_args, None, no_cache)
-                .await
-            {
+            // MCP は stdio でプロトコルを話すため、進捗は表示せずイベントから要約する
+            let mut steps = 0;
+            let mut cached = 0;
+            let mut digest = None;
+            let result = builder
+                .build_image_with_events(
+                    context_data,
+                    &image_tag,
+                    build_args,
+                    None,
+                    no_cache,
+                    |event| match event {
+                        fleetflow_build::BuildEvent::StepStarted { ref name, .. }
+                            if name.starts_with('[') && !name.starts_with("[internal]") =>
+                        {
+                            steps += 1;
+                        }
+                        fleetflow_build::BuildEvent::LayerCached { .. } => cached += 1,
+                        fleetflow_build::BuildEvent::Digest { digest: d } => digest = Some(d),
+                        _ => {}
+                    },
+                )
+                .await;
+
+            match result {
                 Ok(_) => {
-                    built_services.push(format!("{} → {}", service_name, image_tag));
+                    let mut summary = format!(
+                        "{} → {} (ステップ {} / キャッシュ {})",
+                        service_name, image_tag, steps, cached
+                    );
+                    if let Some(digest) = digest {
+                        summary.push_str(&format!(" {}", digest));
+                    }
+                    built_services.push(summary);
                 }
                 Err(e) => {
                     errors.push(format!("{}: ビルド失敗 - {}", service_name, e));
@@ -549,13 +720,212 @@ impl FleetFlowServer {
         }
     }
 
+    /// ステージをデプロイ（2 段階確認）
+    #[tool(
+        description = "指定されたステージをデプロイします（既存コンテナを停止・削除し、最新イメージで再作成）。影響が大きいため 2 段階で実行します: confirm_token なしで呼び出すと実行計画と確認トークンを返すので、内容をユーザーに確認したうえで同じパラメータに confirm_token を付けて再度呼び出してください。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn fleetflow_deploy(&self, params: Parameters<DeployParam>) -> Result<String, String> {
+        let params = params.0;
+        let (project_root, config) = self.state.project()?;
+        let (stage_config, services) = local_stage_services(&config, &params.stage)?;
+        if let Some(unknown) = params.services.iter().find(|s| !services.contains(s)) {
+            return Err(format!(
+                "サービス '{}' はステージ '{}' のコンテナサービスに含まれていません",
+                unknown, params.stage
+            ));
+        }
+        let targets = if params.services.is_empty() {
+            services
+        } else {
+            params.services.clone()
+        };
+        let action = format!(
+            "deploy stage={} services={} no_pull={} no_prune={}",
+            params.stage,
+            targets.join(","),
+            params.no_pull,
+            params.no_prune
+        );
+
+        let Some(token) = params.confirm_token else {
+            let mut plan = format!(
+                "デプロイ計画: プロジェクト '{}' / ステージ '{}'\n\n",
+                config.name, params.stage
+            );
+            plan.push_str("以下のコンテナを停止・削除し、再作成します:\n");
+            for service in &targets {
+                let image = config
+                    .services
+                    .get(service)
+                    .and_then(|s| s.image.as_deref())
+                    .unwrap_or("(image 未指定)");
+                plan.push_str(&format!(
+                    "  - {} ({})\n",
+                    config.container_name(&params.stage, service),
+                    image
+                ));
+            }
+            plan.push_str(&format!(
+                "\nイメージ pull: {}\n不要イメージ削除: {}\n",
+                if params.no_pull {
+                    "しない"
+                } else {
+                    "する"
+                },
+                if params.no_prune {
+                    "しない"
+                } else {
+                    "する"
+                }
+            ));
+            if stage_config.log_shipping.is_some() {
+                plan.push_str("ログ集約エージェントの設定: 再生成する\n");
+            }
+            let token = self.state.confirmations.issue(&action, &config);
+            plan.push_str(&format!(
+                "\n実行するには {} 秒以内に confirm_token=\"{}\" を付けて同じパラメータで呼び出してください。",
+                confirm::TOKEN_TTL.as_secs(),
+                token
+            ));
+            return Ok(plan);
+        };
+        self.state.confirmations.consume(&token, &action, &config)?;
+
+        // CLI の `fleet deploy` を子プロセスで実行し、権限チェック・プロジェクトロック・
+        // ログ集約エージェントの追加・設定ファイルとシークレットの書き出しを CLI と共通にする
+        let output = tokio::process::Command::new(
+            std::env::current_exe()
+                .map_err(|e| format!("fleet の実行ファイルが見つかりません: {}", e))?,
+        )
+        .arg("-C")
+        .arg(&project_root)
+        .args(deploy_command_args(&params, &targets))
+        .env_remove("FLEET_STAGE")
+        .env("NO_COLOR", "1")
+        // stdio トランスポートでは stdin / stdout が MCP の通信路なので子プロセスに渡さない
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("fleet deploy を起動できません: {}", e))?;
+
+        let log = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if !output.status.success() {
+            return Err(format!(
+                "ステージ '{}' のデプロイに失敗しました（exit {}）\n\n{}",
+                params.stage,
+                output.status.code().unwrap_or(-1),
+                log.trim_end()
+            ));
+        }
+
+        Ok(format!(
+            "✓ ステージ '{}' のデプロイが完了しました: {}\n\n{}",
+            params.stage,
+            targets.join(", "),
+            log.trim_end()
+        ))
+    }
+
+    /// ステージの実行環境を準備（2 段階確認）
+    #[tool(
+        description = "指定されたステージの実行環境を準備します（イメージの pull とネットワーク作成）。既存コンテナには触れず、何度実行しても安全です。confirm_token なしで呼び出すと計画と確認トークンを返すので、同じパラメータに confirm_token を付けて再度呼び出すと実行します。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn fleetflow_setup(&self, params: Parameters<SetupParam>) -> Result<String, String> {
+        let params = params.0;
+        let (project_root, config) = self.state.project()?;
+        let (_, services) = local_stage_services(&config, &params.stage)?;
+        let action = format!("setup stage={}", params.stage);
+
+        let Some(token) = params.confirm_token else {
+            let mut plan = format!(
+                "セットアップ計画: プロジェクト '{}' / ステージ '{}'\n\n",
+                config.name, params.stage
+            );
+            plan.push_str(&format!(
+                "ネットワーク作成: {}\nイメージ pull:\n",
+                fleetflow_container::get_network_name(&config.name, &params.stage)
+            ));
+            for service in &services {
+                if let Some(image) = config
+                    .services
+                    .get(service)
+                    .and_then(|s| s.image.as_deref())
+                {
+                    plan.push_str(&format!("  - {} ({})\n", service, image));
+                }
+            }
+            let token = self.state.confirmations.issue(&action, &config);
+            plan.push_str(&format!(
+                "\n実行するには {} 秒以内に confirm_token=\"{}\" を付けて呼び出してください。",
+                confirm::TOKEN_TTL.as_secs(),
+                token
+            ));
+            return Ok(plan);
+        };
+        self.state.confirmations.consume(&token, &action, &config)?;
+
+        // CLI の `fleet deploy --prepare-only` を子プロセスで実行し、権限チェックと
+        // プロジェクトロックを deploy と共通にする
+        let output = tokio::process::Command::new(
+            std::env::current_exe()
+                .map_err(|e| format!("fleet の実行ファイルが見つかりません: {}", e))?,
+        )
+        .arg("-C")
+        .arg(&project_root)
+        .args(setup_command_args(&params))
+        .env_remove("FLEET_STAGE")
+        .env("NO_COLOR", "1")
+        // stdio トランスポートでは stdin / stdout が MCP の通信路なので子プロセスに渡さない
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("fleet deploy --prepare-only を起動できません: {}", e))?;
+
+        let log = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if !output.status.success() {
+            return Err(format!(
+                "ステージ '{}' の準備に失敗しました（exit {}）\n\n{}",
+                params.stage,
+                output.status.code().unwrap_or(-1),
+                log.trim_end()
+            ));
+        }
+
+        Ok(format!(
+            "✓ ステージ '{}' の準備が完了しました\n\n{}",
+            params.stage,
+            log.trim_end()
+        ))
+    }
+
     // ========================================================================
     // CP 経由ツール（v2）
     // ========================================================================
 
     /// CP 接続状態を確認
     #[tool(
-        description = "Control Plane への接続状態を確認します。ログイン済みか、トークンの有効期限、テナント情報を表示します。"
+        description = "Control Plane への接続状態を確認します。ログイン済みか、トークンの有効期限、テナント情報を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_status(&self) -> Result<String, String> {
         let creds_path = dirs::config_dir()
@@ -596,7 +966,8 @@ impl FleetFlowServer {
 
     /// CP 経由でプロジェクト一覧を取得
     #[tool(
-        description = "Control Plane に登録されている全プロジェクトの一覧を取得します。CP にログイン済みである必要があります。"
+        description = "Control Plane に登録されている全プロジェクトの一覧を取得します。CP にログイン済みである必要があります。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_projects(&self) -> Result<String, String> {
         let (client, creds) = cp::connect().await.map_err(|e| e.to_string())?;
@@ -638,7 +1009,8 @@ impl FleetFlowServer {
 
     /// CP 経由でサーバー一覧を取得
     #[tool(
-        description = "Control Plane に登録されている全サーバーの一覧を取得します。各サーバーのプロバイダ、IP、稼働状態を表示します。"
+        description = "Control Plane に登録されている全サーバーの一覧を取得します。各サーバーのプロバイダ、IP、稼働状態を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_servers(&self) -> Result<String, String> {
         let (client, creds) = cp::connect().await.map_err(|e| e.to_string())?;
@@ -684,7 +1056,8 @@ impl FleetFlowServer {
 
     /// CP 経由で全プロジェクト横断のステージ状態を取得
     #[tool(
-        description = "全プロジェクトのステージ横断状態を取得します。各プロジェクト × ステージのサービス稼働数を表示します。"
+        description = "全プロジェクトのステージ横断状態を取得します。各プロジェクト × ステージのサービス稼働数を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_overview(&self) -> Result<String, String> {
         let (client, creds) = cp::connect().await.map_err(|e| e.to_string())?;
@@ -751,7 +1124,8 @@ impl FleetFlowServer {
 
     /// ステージ概要（1st ビュー）— アラート・デプロイ・サーバー状態付き
     #[tool(
-        description = "テナントの全ステージ概要を取得します。アラート数、直近デプロイ結果、サーバー状態を含む優先度ソート済みの一覧です。"
+        description = "テナントの全ステージ概要を取得します。アラート数、直近デプロイ結果、サーバー状態を含む優先度ソート済みの一覧です。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_stages(&self) -> Result<String, String> {
         let resp = cp::http_get("/api/stages")
@@ -792,7 +1166,8 @@ impl FleetFlowServer {
 
     /// ステージのサービス一覧
     #[tool(
-        description = "指定ステージのサービス一覧を取得します。サービス名、Docker イメージ、稼働状態を表示します。"
+        description = "指定ステージのサービス一覧を取得します。サービス名、Docker イメージ、稼働状態を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_stage_services(
         &self,
@@ -818,7 +1193,8 @@ impl FleetFlowServer {
 
     /// ステージのデプロイ履歴
     #[tool(
-        description = "指定ステージの直近デプロイ履歴を取得します。コマンド、ステータス、実行時刻を表示します。"
+        description = "指定ステージの直近デプロイ履歴を取得します。コマンド、ステータス、実行時刻を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_stage_deployments(
         &self,
@@ -845,7 +1221,13 @@ impl FleetFlowServer {
 
     /// Agent 経由で再デプロイ
     #[tool(
-        description = "指定ステージを Fleet Agent 経由で再デプロイします。CP → Agent → docker compose up の流れで実行されます。"
+        description = "指定ステージを Fleet Agent 経由で再デプロイします。CP → Agent → docker compose up の流れで実行されます。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = false,
+            open_world_hint = true
+        )
     )]
     async fn fleetflow_cp_redeploy(
         &self,
@@ -859,7 +1241,13 @@ impl FleetFlowServer {
 
     /// Agent 経由でサービス再起動
     #[tool(
-        description = "指定サービスを Fleet Agent 経由で再起動します。CP → Agent → docker restart の流れで実行されます。"
+        description = "指定サービスを Fleet Agent 経由で再起動します。CP → Agent → docker restart の流れで実行されます。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = true
+        )
     )]
     async fn fleetflow_cp_service_restart(
         &self,
@@ -876,7 +1264,8 @@ impl FleetFlowServer {
 
     /// コンテナログ取得（LogRouter 経由）
     #[tool(
-        description = "指定コンテナの直近ログを取得します。LogRouter のキャッシュから info 以上のログを返します。"
+        description = "指定コンテナの直近ログを取得します。LogRouter のキャッシュから info 以上のログを返します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_container_logs_v2(
         &self,
@@ -906,7 +1295,8 @@ impl FleetFlowServer {
 
     /// ステージのアクティブアラート一覧
     #[tool(
-        description = "指定ステージのアクティブなアラート一覧を取得します。コンテナ名、タイプ、重要度、メッセージを表示します。"
+        description = "指定ステージのアクティブなアラート一覧を取得します。コンテナ名、タイプ、重要度、メッセージを表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_alerts(
         &self,
@@ -937,7 +1327,8 @@ impl FleetFlowServer {
 
     /// 接続中の Fleet Agent 一覧
     #[tool(
-        description = "Control Plane に接続中の Fleet Agent 一覧を取得します。各サーバーの Agent バージョンを表示します。"
+        description = "Control Plane に接続中の Fleet Agent 一覧を取得します。各サーバーの Agent バージョンを表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_agents(&self) -> Result<String, String> {
         let resp = cp::http_get("/api/agents")
@@ -963,7 +1354,8 @@ impl FleetFlowServer {
 
     /// テナントユーザー一覧
     #[tool(
-        description = "現在のテナントに所属するユーザー一覧を取得します。owner/admin のみアクセス可能です。"
+        description = "現在のテナントに所属するユーザー一覧を取得します。owner/admin のみアクセス可能です。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_tenant_users(&self) -> Result<String, String> {
         let resp = cp::http_get("/api/tenant/users")
@@ -989,7 +1381,8 @@ impl FleetFlowServer {
 
     /// ステージの実 runtime status を取得 (FSC-17)
     #[tool(
-        description = "ステージに含まれる各サービスの実 runtime status（running/stopped/restarting）と uptime_seconds を取得します。Agent → docker ps の結果を整形して返します。"
+        description = "ステージに含まれる各サービスの実 runtime status（running/stopped/restarting）と uptime_seconds を取得します。Agent → docker ps の結果を整形して返します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleet_status(&self, params: Parameters<StagePathParam>) -> Result<String, String> {
         let p = &params.0;
@@ -1000,7 +1393,13 @@ impl FleetFlowServer {
 
     /// ステージのサービスを再起動 (FSC-18)
     #[tool(
-        description = "指定ステージの指定サービスを Agent 経由で再起動します（docker restart）。owner/admin 権限が必要です。"
+        description = "指定ステージの指定サービスを Agent 経由で再起動します（docker restart）。owner/admin 権限が必要です。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = true
+        )
     )]
     async fn fleet_restart(&self, params: Parameters<FleetRestartParam>) -> Result<String, String> {
         let p = &params.0;
@@ -1014,7 +1413,8 @@ impl FleetFlowServer {
 
     /// CP 経由でプロジェクトの詳細を取得
     #[tool(
-        description = "指定プロジェクトの詳細情報を取得します。プロジェクト名、説明、作成日時を表示します。"
+        description = "指定プロジェクトの詳細情報を取得します。プロジェクト名、説明、作成日時を表示します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_project_detail(
         &self,
@@ -1060,7 +1460,13 @@ impl FleetFlowServer {
 
     /// CP 経由でコンテナを起動
     #[tool(
-        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを起動します。"
+        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを起動します。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = true
+        )
     )]
     async fn fleetflow_cp_container_start(
         &self,
@@ -1069,7 +1475,7 @@ impl FleetFlowServer {
         let p = &params.0;
         let (client, _creds) = cp::connect().await.map_err(|e| e.to_string())?;
 
-        let container_name = format!("{}-{}-{}", p.project, p.stage, p.service);
+        let container_name = self.cp_container_name(&p.project, &p.stage, &p.service);
         let resp = cp::request(
             &client,
             "container",
@@ -1090,7 +1496,13 @@ impl FleetFlowServer {
 
     /// CP 経由でコンテナを停止
     #[tool(
-        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを停止します。"
+        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを停止します。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = true
+        )
     )]
     async fn fleetflow_cp_container_stop(
         &self,
@@ -1099,7 +1511,7 @@ impl FleetFlowServer {
         let p = &params.0;
         let (client, _creds) = cp::connect().await.map_err(|e| e.to_string())?;
 
-        let container_name = format!("{}-{}-{}", p.project, p.stage, p.service);
+        let container_name = self.cp_container_name(&p.project, &p.stage, &p.service);
         let resp = cp::request(
             &client,
             "container",
@@ -1120,7 +1532,13 @@ impl FleetFlowServer {
 
     /// CP 経由でコンテナを再起動
     #[tool(
-        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを再起動します。"
+        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナを再起動します。",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = false,
+            open_world_hint = true
+        )
     )]
     async fn fleetflow_cp_container_restart(
         &self,
@@ -1129,7 +1547,7 @@ impl FleetFlowServer {
         let p = &params.0;
         let (client, _creds) = cp::connect().await.map_err(|e| e.to_string())?;
 
-        let container_name = format!("{}-{}-{}", p.project, p.stage, p.service);
+        let container_name = self.cp_container_name(&p.project, &p.stage, &p.service);
         let resp = cp::request(
             &client,
             "container",
@@ -1150,7 +1568,8 @@ impl FleetFlowServer {
 
     /// CP 経由でコンテナログを取得
     #[tool(
-        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナログを取得します。"
+        description = "Control Plane 経由で指定プロジェクト/ステージ/サービスのコンテナログを取得します。",
+        annotations(read_only_hint = true, open_world_hint = true)
     )]
     async fn fleetflow_cp_container_logs(
         &self,
@@ -1160,7 +1579,7 @@ impl FleetFlowServer {
         let tail = p.tail.unwrap_or(50);
         let (client, _creds) = cp::connect().await.map_err(|e| e.to_string())?;
 
-        let container_name = format!("{}-{}-{}", p.project, p.stage, p.service);
+        let container_name = self.cp_container_name(&p.project, &p.stage, &p.service);
         let resp = cp::request(
             &client,
             "container",
@@ -1183,11 +1602,148 @@ impl FleetFlowServer {
     }
 }
 
+/// `mcp { enable_destructive_tools #false }` で無効化するツール
+///
+/// `fleetflow_down` は `remove=true` の呼び出しのみ拒否する（ツール自体は残す）。
+const DESTRUCTIVE_TOOLS: &[&str] = &["fleetflow_deploy"];
+
+const DESTRUCTIVE_TOOLS_DISABLED: &str = "破壊的なツールは無効化されています（fleet.kdl の mcp { enable_destructive_tools #false }）。CLI から実行してください";
+
+/// 破壊的なツールを許可するか（`mcp` ブロック未指定なら許可）
+fn destructive_tools_enabled(config: &fleetflow_core::Flow) -> bool {
+    config
+        .mcp
+        .as_ref()
+        .is_none_or(|mcp| mcp.enable_destructive_tools)
+}
+
+impl FleetFlowServer {
+    /// 現在のプロジェクト設定で破壊的なツールを許可するか
+    ///
+    /// プロジェクトが見つからない場合は許可する（各ツールがプロジェクトの読み込みで失敗する）。
+    fn destructive_tools_enabled(&self) -> bool {
+        self.state
+            .project()
+            .map_or(true, |(_, config)| destructive_tools_enabled(&config))
+    }
+
+    /// CP 経由で操作するコンテナの名前
+    ///
+    /// 対象が現在のプロジェクトならその `container_name` テンプレートに従い、
+    /// それ以外は既定の `{project}-{stage}-{service}`。
+    fn cp_container_name(&self, project: &str, stage: &str, service: &str) -> String {
+        match self.state.project() {
+            Ok((_, config)) if config.name == project => config.container_name(stage, service),
+            _ => fleetflow_core::container_name(None, project, stage, service),
+        }
+    }
+}
+
+/// サービス 1 件の定義と稼働中コンテナを比較する
+async fn drift_report(
+    docker: &bollard::Docker,
+    config: &fleetflow_core::Flow,
+    stage: &str,
+    service_name: &str,
+) -> serde_json::Value {
+    let container_name = config.container_name(stage, service_name);
+    let service = config
+        .services
+        .get(service_name)
+        .cloned()
+        .unwrap_or_default();
+    let (body, _) =
+        fleetflow_container::service_to_container_config(service_name, &service, stage, config);
+    let expected = drift::ContainerSpec::from_create_body(&body);
+    let inspect = docker
+        .inspect_container(
+            &container_name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+        .ok();
+    drift::service_report(service_name, &container_name, &expected, inspect.as_ref())
+}
+
+/// MCP の deploy ツールに対応する `fleet deploy` の引数
+///
+/// 2 段階確認を済ませているので `--yes` を付ける。保護ステージは `--role admin` を
+/// 渡さないため、CLI 側の権限チェックで拒否される。
+fn deploy_command_args(params: &DeployParam, targets: &[String]) -> Vec<String> {
+    let mut args = vec![
+        "deploy".to_string(),
+        params.stage.clone(),
+        "--yes".to_string(),
+    ];
+    for service in targets {
+        args.push("--service".to_string());
+        args.push(service.clone());
+    }
+    if params.no_pull {
+        args.push("--no-pull".to_string());
+    }
+    if params.no_prune {
+        args.push("--no-prune".to_string());
+    }
+    args
+}
+
+/// MCP の setup ツールに対応する `fleet deploy --prepare-only` の引数
+///
+/// deploy と同じく、保護ステージは CLI 側の権限チェックで拒否される。
+fn setup_command_args(params: &SetupParam) -> Vec<String> {
+    vec![
+        "deploy".to_string(),
+        params.stage.clone(),
+        "--prepare-only".to_string(),
+    ]
+}
+
+/// ローカルで操作できるステージのコンテナサービス一覧
+///
+/// リモートサーバーを使うステージ・静的サイトは CLI（`fleet deploy`）の担当なので対象外。
+fn local_stage_services<'a>(
+    config: &'a fleetflow_core::Flow,
+    stage: &str,
+) -> Result<(&'a fleetflow_core::Stage, Vec<String>), String> {
+    let stage_config = config.stages.get(stage).ok_or_else(|| {
+        format!(
+            "ステージ '{}' が見つかりません。利用可能: {}",
+            stage,
+            config.stages.keys().cloned().collect::<Vec<_>>().join(", ")
+        )
+    })?;
+    if !stage_config.servers.is_empty() {
+        return Err(format!(
+            "ステージ '{}' はリモートサーバー（{}）を使用しています。`fleet deploy {}` または fleetflow_cp_redeploy を使用してください",
+            stage,
+            stage_config.servers.join(", "),
+            stage
+        ));
+    }
+    let services = stage_config
+        .services
+        .iter()
+        .filter(|name| {
+            config
+                .services
+                .get(name.as_str())
+                .is_some_and(|s| !s.is_static())
+        })
+        .cloned()
+        .collect();
+    Ok((stage_config, services))
+}
+
 impl ServerHandler for FleetFlowServer {
     fn get_info(&self) -> ServerInfo {
         // rmcp 1.1 で ServerInfo が #[non_exhaustive] 化 → struct literal 不可。
         // Default を base に、mutable で instructions のみ上書き
         let mut info = ServerInfo::default();
+        info.capabilities = ServerCapabilities::builder()
+            .enable_tools()
+            .enable_prompts()
+            .build();
         info.instructions = Some(
             "FleetFlow MCP サーバー。KDLベースのコンテナオーケストレーションツールです。"
                 .to_string(),
@@ -1200,20 +1756,58 @@ impl ServerHandler for FleetFlowServer {
         _request: Option<PaginatedRequestParams>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        if !self.destructive_tools_enabled() {
+            tools.retain(|t| !DESTRUCTIVE_TOOLS.contains(&t.name.as_ref()));
+        }
         Ok(ListToolsResult {
-            tools: self.tool_router.list_all(),
+            tools,
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult {
+            prompts: prompts::list(),
             next_cursor: None,
             meta: None,
         })
     }
 
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        prompts::get(&self.state, &request.name, request.arguments.as_ref())
+    }
+
     async fn call_tool(
         &self,
         request: CallToolRequestParams,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
+        let tool = request.name.clone();
+        if DESTRUCTIVE_TOOLS.contains(&tool.as_ref()) && !self.destructive_tools_enabled() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                DESTRUCTIVE_TOOLS_DISABLED,
+            )]));
+        }
+        let started = Instant::now();
         let tool_context = ToolCallContext::new(self, request, context);
-        self.tool_router.call(tool_context).await
+        let result = self.tool_router.call(tool_context).await;
+        info!(
+            tool = %tool,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            ok = result.as_ref().is_ok_and(|r| r.is_error != Some(true)),
+            "Tool call finished"
+        );
+        result
     }
 }
 
@@ -1236,6 +1830,51 @@ pub async fn run_server() -> Result<()> {
     Ok(())
 }
 
+/// Streamable HTTP トランスポートのエンドポイントパス
+pub const HTTP_ENDPOINT_PATH: &str = "/mcp";
+
+/// MCP サーバーを起動（Streamable HTTP トランスポート）
+///
+/// IDE やリモートのエージェントがバイナリを起動せずに接続できるようにする。
+/// 全セッションで Docker クライアントと設定キャッシュを共有する。Ctrl+C で終了。
+/// リクエストには `Authorization: Bearer <token>` と、待ち受けアドレスに一致する Host を要求する。
+pub async fn run_http_server(addr: std::net::SocketAddr, token: String) -> Result<()> {
+    use rmcp::transport::streamable_http_server::{
+        StreamableHttpService, session::local::LocalSessionManager,
+    };
+
+    let server = FleetFlowServer::new();
+    let service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        LocalSessionManager::default().into(),
+        Default::default(),
+    );
+    let guard = Arc::new(http_auth::HttpGuard::new(addr, token));
+    let router = axum::Router::new()
+        .nest_service(HTTP_ENDPOINT_PATH, service)
+        .layer(axum::middleware::from_fn_with_state(
+            guard,
+            http_auth::guard_request,
+        ));
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("{} で待ち受けできません: {}", addr, e))?;
+    info!(%addr, "MCP HTTP server listening");
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await
+        .map_err(|e| {
+            error!("MCP HTTP server error: {}", e);
+            anyhow::anyhow!("MCP HTTP server error: {}", e)
+        })?;
+
+    Ok(())
+}
+
 // ============================================================================
 // テスト
 // ============================================================================
@@ -1249,6 +1888,44 @@ mod tests {
     // パラメータのデシリアライズ
     // ------------------------------------------------------------------------
 
+    #[test]
+    fn deploy_param_defaults() {
+        let v = json!({"stage": "local"});
+        let p: DeployParam = serde_json::from_value(v).unwrap();
+        assert!(p.services.is_empty());
+        assert!(!p.no_pull);
+        assert!(!p.no_prune);
+        assert!(p.confirm_token.is_none());
+    }
+
+    #[test]
+    fn setup_runs_cli_deploy_prepare_only() {
+        let p: SetupParam = serde_json::from_value(json!({"stage": "dev"})).unwrap();
+        assert_eq!(
+            setup_command_args(&p),
+            vec!["deploy", "dev", "--prepare-only"]
+        );
+    }
+
+    #[test]
+    fn deploy_runs_cli_deploy_with_same_options() {
+        let p: DeployParam =
+            serde_json::from_value(json!({"stage": "dev", "no_pull": true})).unwrap();
+        assert_eq!(
+            deploy_command_args(&p, &["api".to_string(), "db".to_string()]),
+            vec![
+                "deploy",
+                "dev",
+                "--yes",
+                "--service",
+                "api",
+                "--service",
+                "db",
+                "--no-pull"
+            ]
+        );
+    }
+
     #[test]
     fn stage_param_deserialize() {
         let v = json!({"stage": "local"});
@@ -1370,8 +2047,12 @@ mod tests {
         "fleetflow_down",
         "fleetflow_logs",
         "fleetflow_restart",
+        "fleetflow_inspect_service",
+        "fleetflow_diff",
         "fleetflow_validate",
         "fleetflow_build",
+        "fleetflow_deploy",
+        "fleetflow_setup",
         // v2: CP 経由管理操作
         "fleetflow_cp_status",
         "fleetflow_cp_projects",
@@ -1464,6 +2145,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn all_tools_have_annotations() {
+        let server = FleetFlowServer::new();
+        let tools = server.tool_router.list_all();
+
+        for tool in &tools {
+            let annotations = tool
+                .annotations
+                .as_ref()
+                .unwrap_or_else(|| panic!("tool '{}' should have annotations", tool.name));
+            assert!(
+                annotations.read_only_hint.is_some(),
+                "tool '{}' should declare readOnlyHint",
+                tool.name,
+            );
+            // 書き込みを行うツールは destructive / idempotent も明示する
+            if annotations.read_only_hint == Some(false) {
+                assert!(
+                    annotations.destructive_hint.is_some() && annotations.idempotent_hint.is_some(),
+                    "tool '{}' should declare destructiveHint and idempotentHint",
+                    tool.name,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn destructive_tools_are_annotated() {
+        let server = FleetFlowServer::new();
+        let tools = server.tool_router.list_all();
+        let hints = |name: &str| {
+            let tool = tools.iter().find(|t| t.name == name).unwrap();
+            let a = tool.annotations.as_ref().unwrap();
+            (a.read_only_hint, a.destructive_hint)
+        };
+
+        for name in DESTRUCTIVE_TOOLS.iter().chain(&["fleetflow_down"]) {
+            assert_eq!(hints(name), (Some(false), Some(true)), "tool '{}'", name);
+        }
+        for name in [
+            "fleetflow_ps",
+            "fleetflow_diff",
+            "fleetflow_inspect_service",
+        ] {
+            assert_eq!(hints(name).0, Some(true), "tool '{}'", name);
+        }
+    }
+
     #[test]
     fn parameterless_tools_have_no_required_fields() {
         let server = FleetFlowServer::new();
@@ -1607,10 +2336,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deploy_and_setup_tools_make_confirm_token_optional() {
+        let server = FleetFlowServer::new();
+        let tools = server.tool_router.list_all();
+
+        for name in ["fleetflow_deploy", "fleetflow_setup"] {
+            let tool = tools.iter().find(|t| t.name == name).unwrap();
+            let required: Vec<&str> = tool
+                .input_schema
+                .get("required")
+                .and_then(|v| v.as_array())
+                .expect("should have required fields")
+                .iter()
+                .filter_map(|v| v.as_str())
+                .collect();
+            assert_eq!(required, vec!["stage"], "tool '{}'", name);
+            assert!(
+                tool.input_schema["properties"]
+                    .get("confirm_token")
+                    .is_some(),
+                "tool '{}' should accept confirm_token",
+                name,
+            );
+        }
+    }
+
     // ------------------------------------------------------------------------
     // ServerHandler::get_info
     // ------------------------------------------------------------------------
 
+    #[test]
+    fn server_info_enables_tools_and_prompts() {
+        let server = FleetFlowServer::new();
+        let info = server.get_info();
+        assert!(info.capabilities.tools.is_some());
+        assert!(info.capabilities.prompts.is_some());
+    }
+
     #[test]
     fn server_info_has_instructions() {
         let server = FleetFlowServer::new();
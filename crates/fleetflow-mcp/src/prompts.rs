@@ -0,0 +1,386 @@
+//! MCP プロンプト（`prompts/list` / `prompts/get`）
+//!
+//! 定型の運用タスク向けに、fleetflow-core で読み込んだプロジェクト情報を
+//! 埋め込んだプロンプトを提供する。AI アシスタントが推測ではなく実際の設定に基づいて回答できるようにする。
+
+use crate::state::ServerState;
+use fleetflow_core::Flow;
+use rmcp::{
+    ErrorData as McpError,
+    model::{GetPromptResult, JsonObject, Prompt},
+};
+use serde_json::json;
+use std::fmt::Write;
+use std::path::Path;
+
+/// プロンプト定義
+struct PromptSpec {
+    name: &'static str,
+    description: &'static str,
+    /// (引数名, 説明, 必須)
+    arguments: &'static [(&'static str, &'static str, bool)],
+}
+
+const PROMPTS: &[PromptSpec] = &[
+    PromptSpec {
+        name: "diagnose-failing-service",
+        description: "起動に失敗する・再起動を繰り返すサービスを、定義と依存関係を踏まえて診断します。",
+        arguments: &[
+            ("stage", "ステージ名（例: local, dev, prod）", true),
+            ("service", "診断するサービス名", true),
+        ],
+    },
+    PromptSpec {
+        name: "production-deploy-checklist",
+        description: "本番デプロイ前のチェックリストを、ステージの構成に合わせて作成します。",
+        arguments: &[("stage", "デプロイ先のステージ名（省略時: prod）", false)],
+    },
+    PromptSpec {
+        name: "explain-fleet-kdl",
+        description: "このプロジェクトの fleet.kdl と関連 KDL ファイルの構成を解説します。",
+        arguments: &[],
+    },
+];
+
+/// プロンプト一覧
+pub fn list() -> Vec<Prompt> {
+    PROMPTS
+        .iter()
+        .map(|spec| {
+            let arguments: Vec<_> = spec
+                .arguments
+                .iter()
+                .map(|(name, description, required)| {
+                    json!({ "name": name, "description": description, "required": required })
+                })
+                .collect();
+            from_schema(json!({
+                "name": spec.name,
+                "description": spec.description,
+                "arguments": arguments,
+            }))
+        })
+        .collect()
+}
+
+/// カレントプロジェクトを読み込んでプロンプトを生成
+pub fn get(
+    state: &ServerState,
+    name: &str,
+    arguments: Option<&JsonObject>,
+) -> Result<GetPromptResult, McpError> {
+    let spec = PROMPTS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| McpError::invalid_params(format!("不明なプロンプト: {}", name), None))?;
+
+    let arg = |key: &str| {
+        arguments
+            .and_then(|args| args.get(key))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    for (key, _, required) in spec.arguments {
+        if *required && arg(key).is_none() {
+            return Err(McpError::invalid_params(
+                format!("引数 '{}' は必須です", key),
+                None,
+            ));
+        }
+    }
+
+    let (project_root, flow) = state
+        .project()
+        .map_err(|e| McpError::internal_error(e, None))?;
+
+    let text = match spec.name {
+        "diagnose-failing-service" => diagnose_service(
+            &flow,
+            &arg("stage").unwrap_or_default(),
+            &arg("service").unwrap_or_default(),
+        ),
+        "production-deploy-checklist" => {
+            deploy_checklist(&flow, &arg("stage").unwrap_or_else(|| "prod".to_string()))
+        }
+        _ => explain_kdl(&flow, &read_kdl_sources(&project_root)),
+    }
+    .map_err(|e| McpError::invalid_params(e, None))?;
+
+    Ok(from_schema(json!({
+        "description": spec.description,
+        "messages": [{ "role": "user", "content": { "type": "text", "text": text } }],
+    })))
+}
+
+/// rmcp 1.1 のモデル型は #[non_exhaustive] で struct literal 不可のため、MCP スキーマの JSON から組み立てる
+fn from_schema<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> T {
+    serde_json::from_value(value).expect("value should match the MCP schema")
+}
+
+/// サービス定義の要約（プロンプト埋め込み用）
+fn describe_service(flow: &Flow, name: &str) -> String {
+    let Some(service) = flow.services.get(name) else {
+        return format!("- {}: (定義なし)\n", name);
+    };
+
+    let mut out = format!("- {}\n", name);
+    let image = service
+        .image
+        .clone()
+        .or_else(|| {
+            service
+                .build
+                .as_ref()
+                .map(|_| "(build 設定からビルド)".to_string())
+        })
+        .unwrap_or_else(|| "(未指定)".to_string());
+    let _ = writeln!(out, "  image: {}", image);
+    if let Some(command) = &service.command {
+        let _ = writeln!(out, "  command: {}", command);
+    }
+    if !service.ports.is_empty() {
+        let ports: Vec<String> = service
+            .ports
+            .iter()
+            .map(|p| format!("{}:{}", p.host, p.container))
+            .collect();
+        let _ = writeln!(out, "  ports: {}", ports.join(", "));
+    }
+    if !service.depends_on.is_empty() {
+        let _ = writeln!(out, "  depends_on: {}", service.depends_on.join(", "));
+    }
+    if let Some(restart) = &service.restart {
+        let _ = writeln!(out, "  restart: {:?}", restart);
+    }
+    if let Some(hc) = &service.healthcheck {
+        let _ = writeln!(out, "  healthcheck: {}", hc.test.join(" "));
+    }
+    if service.wait_for.is_some() {
+        let _ = writeln!(out, "  wait_for: 有効");
+    }
+    if let Some(readiness) = &service.readiness {
+        let _ = writeln!(out, "  readiness: {}:{}", readiness.path, readiness.port);
+    }
+    if !service.environment.is_empty() {
+        // 値は秘密情報を含み得るためキー名のみ
+        let mut keys: Vec<&String> = service.environment.keys().collect();
+        keys.sort();
+        let keys: Vec<&str> = keys.into_iter().map(String::as_str).collect();
+        let _ = writeln!(out, "  environment keys: {}", keys.join(", "));
+    }
+    out
+}
+
+fn diagnose_service(flow: &Flow, stage: &str, service: &str) -> Result<String, String> {
+    let stage_config = flow
+        .stages
+        .get(stage)
+        .ok_or_else(|| format!("ステージ '{}' が見つかりません", stage))?;
+    if !stage_config.services.iter().any(|s| s == service) {
+        return Err(format!(
+            "サービス '{}' はステージ '{}' に含まれていません（{}）",
+            service,
+            stage,
+            stage_config.services.join(", ")
+        ));
+    }
+
+    let mut text = format!(
+        "FleetFlow プロジェクト '{}' のステージ '{}' で、サービス '{}' が正常に動作していません。原因を診断してください。\n\n",
+        flow.name, stage, service
+    );
+    text.push_str("## 対象サービスの定義\n");
+    text.push_str(&describe_service(flow, service));
+
+    if let Some(def) = flow.services.get(service)
+        && !def.depends_on.is_empty()
+    {
+        text.push_str("\n## 依存サービス\n");
+        for dep in &def.depends_on {
+            text.push_str(&describe_service(flow, dep));
+        }
+    }
+
+    let _ = write!(
+        text,
+        "\n## 進め方\n\
+         1. fleetflow_ps でコンテナの状態（終了コード・再起動回数）を確認する\n\
+         2. fleetflow_logs で stage=\"{stage}\" service=\"{service}\" のログを確認する\n\
+         3. 依存サービスが起動済みか、ポート・環境変数のキーが一致しているかを確認する\n\
+         4. 原因の候補を可能性の高い順に挙げ、fleet.kdl の具体的な修正案を示す\n"
+    );
+    Ok(text)
+}
+
+fn deploy_checklist(flow: &Flow, stage: &str) -> Result<String, String> {
+    let stage_config = flow.stages.get(stage).ok_or_else(|| {
+        let mut names: Vec<&str> = flow.stages.keys().map(String::as_str).collect();
+        names.sort();
+        format!(
+            "ステージ '{}' が見つかりません（{}）",
+            stage,
+            names.join(", ")
+        )
+    })?;
+
+    let mut text = format!(
+        "FleetFlow プロジェクト '{}' をステージ '{}' にデプロイする前のチェックリストを作成してください。\n\n",
+        flow.name, stage
+    );
+    text.push_str("## ステージ構成\n");
+    if !stage_config.servers.is_empty() {
+        let _ = writeln!(text, "servers: {}", stage_config.servers.join(", "));
+    }
+    if let Some(registry) = stage_config.registry.as_ref().or(flow.registry.as_ref()) {
+        let _ = writeln!(text, "registry: {}", registry);
+    }
+    text.push_str("\n## サービス\n");
+    for service in &stage_config.services {
+        text.push_str(&describe_service(flow, service));
+    }
+
+    text.push_str(
+        "\n## 観点\n\
+         - イメージのタグが固定されているか（latest を使っていないか）\n\
+         - healthcheck / restart ポリシーが本番向けに設定されているか\n\
+         - 必要な環境変数・シークレットのキーが揃っているか\n\
+         - 公開ポートとファイアウォール、DNS の設定\n\
+         - データを持つサービスのボリュームとバックアップ\n\
+         - ロールバック手順\n\
+         上記の構成から問題になりそうな点を具体的に指摘し、確認項目をチェックボックス形式で出力してください。\n",
+    );
+    Ok(text)
+}
+
+fn explain_kdl(flow: &Flow, sources: &[(String, String)]) -> Result<String, String> {
+    if sources.is_empty() {
+        return Err("KDL ファイルが見つかりません".to_string());
+    }
+
+    let mut stages: Vec<&str> = flow.stages.keys().map(String::as_str).collect();
+    stages.sort();
+    let mut text = format!(
+        "FleetFlow プロジェクト '{}' の設定ファイルを解説してください。\n\
+         ステージ: {}\n\
+         サービス数: {}\n\n",
+        flow.name,
+        stages.join(", "),
+        flow.services.len()
+    );
+    for (path, content) in sources {
+        let _ = write!(text, "## {}\n```kdl\n{}\n```\n\n", path, content.trim_end());
+    }
+    text.push_str(
+        "各ステージで起動するサービスとその依存関係、ステージ間の違い、\
+         気になる設定（未使用のサービス、固定されていないイメージタグなど）を順に説明してください。\n",
+    );
+    Ok(text)
+}
+
+/// プロジェクトの KDL ファイルを (相対パス, 内容) で読み込む
+fn read_kdl_sources(project_root: &Path) -> Vec<(String, String)> {
+    let Ok(files) = fleetflow_core::discover_files(project_root) else {
+        return Vec::new();
+    };
+    files
+        .root
+        .iter()
+        .chain(&files.services)
+        .chain(&files.stages)
+        .chain(&files.variables)
+        .chain(files.local_override.iter())
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(path).ok()?;
+            let display = path
+                .strip_prefix(project_root)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            Some((display, content))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Service, Stage};
+    use std::collections::HashMap;
+
+    fn flow() -> Flow {
+        let api = Service {
+            image: Some("myapp/api:1.2.0".to_string()),
+            depends_on: vec!["db".to_string()],
+            environment: HashMap::from([("DATABASE_URL".to_string(), "secret".to_string())]),
+            ..Default::default()
+        };
+        let db = Service {
+            image: Some("postgres:16".to_string()),
+            ..Default::default()
+        };
+        let stage = Stage {
+            services: vec!["api".to_string(), "db".to_string()],
+            ..Default::default()
+        };
+        Flow {
+            name: "myapp".to_string(),
+            services: HashMap::from([("api".to_string(), api), ("db".to_string(), db)]),
+            stages: HashMap::from([("prod".to_string(), stage)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn list_contains_all_prompts() {
+        let prompts = list();
+        let names: Vec<&str> = prompts.iter().map(|p| p.name.as_ref()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "diagnose-failing-service",
+                "production-deploy-checklist",
+                "explain-fleet-kdl"
+            ]
+        );
+        assert!(prompts.iter().all(|p| p.description.is_some()));
+    }
+
+    #[test]
+    fn get_unknown_prompt_fails() {
+        assert!(get(&ServerState::default(), "unknown", None).is_err());
+    }
+
+    #[test]
+    fn get_requires_arguments() {
+        assert!(get(&ServerState::default(), "diagnose-failing-service", None).is_err());
+    }
+
+    #[test]
+    fn diagnose_includes_service_and_dependencies() {
+        let text = diagnose_service(&flow(), "prod", "api").unwrap();
+        assert!(text.contains("myapp/api:1.2.0"));
+        assert!(text.contains("postgres:16"));
+        assert!(text.contains("DATABASE_URL"));
+        assert!(!text.contains("secret"), "環境変数の値は埋め込まない");
+        assert!(diagnose_service(&flow(), "prod", "web").is_err());
+    }
+
+    #[test]
+    fn deploy_checklist_requires_known_stage() {
+        let text = deploy_checklist(&flow(), "prod").unwrap();
+        assert!(text.contains("ステージ 'prod'"));
+        let err = deploy_checklist(&flow(), "staging").unwrap_err();
+        assert!(err.contains("prod"));
+    }
+
+    #[test]
+    fn explain_embeds_sources() {
+        let sources = vec![(
+            ".fleetflow/fleet.kdl".to_string(),
+            "project \"myapp\"".to_string(),
+        )];
+        let text = explain_kdl(&flow(), &sources).unwrap();
+        assert!(text.contains("```kdl\nproject \"myapp\"\n```"));
+        assert!(explain_kdl(&flow(), &[]).is_err());
+    }
+}
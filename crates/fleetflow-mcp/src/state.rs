@@ -0,0 +1,128 @@
+//! MCP サーバーの共有状態
+//!
+//! ツール呼び出しのたびに Docker へ再接続し、プロジェクトを再パースしていたのを避けるため、
+//! Docker クライアントは初回利用時に 1 度だけ作成し、パース済みの設定は
+//! 設定ファイルの更新時刻が変わるまで使い回す。
+
+use crate::confirm::Confirmations;
+use fleetflow_core::Flow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+use tracing::debug;
+
+/// 設定ファイルごとの更新時刻（キャッシュの有効性判定用）
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>)>;
+
+struct CachedProject {
+    root: PathBuf,
+    fingerprint: Fingerprint,
+    flow: Arc<Flow>,
+}
+
+/// MCP サーバーの共有状態
+#[derive(Default)]
+pub struct ServerState {
+    docker: OnceLock<bollard::Docker>,
+    project: Mutex<Option<CachedProject>>,
+    /// deploy / setup の確認トークン
+    pub confirmations: Confirmations,
+}
+
+impl ServerState {
+    /// Docker クライアント（初回呼び出し時に接続）
+    pub fn docker(&self) -> Result<bollard::Docker, String> {
+        if let Some(docker) = self.docker.get() {
+            return Ok(docker.clone());
+        }
+        let docker = bollard::Docker::connect_with_local_defaults()
+            .map_err(|e| format!("Docker接続エラー: {}", e))?;
+        // 同時に初期化された場合は先に設定された方を使う
+        Ok(self.docker.get_or_init(|| docker).clone())
+    }
+
+    /// プロジェクトルートとパース済みの設定
+    ///
+    /// 設定ファイルの追加・削除・更新を検出した場合のみ再パースする。
+    pub fn project(&self) -> Result<(PathBuf, Arc<Flow>), String> {
+        let root = fleetflow_core::find_project_root()
+            .map_err(|e| format!("プロジェクトルートが見つかりません: {}", e))?;
+        let fingerprint = fingerprint(&root)?;
+
+        let mut cache = self.project.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.as_ref()
+            && cached.root == root
+            && cached.fingerprint == fingerprint
+        {
+            debug!(project_root = %root.display(), "Using cached project config");
+            return Ok((root, Arc::clone(&cached.flow)));
+        }
+
+        let flow = Arc::new(
+            fleetflow_core::load_project_from_root(&root)
+                .map_err(|e| format!("設定の読み込みに失敗: {}", e))?,
+        );
+        debug!(project_root = %root.display(), "Loaded project config");
+        *cache = Some(CachedProject {
+            root: root.clone(),
+            fingerprint,
+            flow: Arc::clone(&flow),
+        });
+        Ok((root, flow))
+    }
+}
+
+/// プロジェクトの設定ファイル一覧と更新時刻
+fn fingerprint(root: &Path) -> Result<Fingerprint, String> {
+    let files = fleetflow_core::discover_files(root)
+        .map_err(|e| format!("設定ファイルの探索に失敗: {}", e))?;
+
+    let paths = files
+        .root
+        .into_iter()
+        .chain(files.cloud)
+        .chain(files.services)
+        .chain(files.stages)
+        .chain(files.variables)
+        .chain(files.local_override)
+        .chain(files.env_file)
+        .chain(files.external_env_file);
+
+    Ok(paths
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_changes_when_file_updated() {
+        let dir = std::env::temp_dir().join(format!("fleetflow-mcp-state-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".fleetflow")).unwrap();
+        let root_file = dir.join(".fleetflow/fleet.kdl");
+        std::fs::write(&root_file, "project \"a\"\n").unwrap();
+
+        let before = fingerprint(&dir).unwrap();
+        assert_eq!(before.len(), 1);
+        assert_eq!(before, fingerprint(&dir).unwrap());
+
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&root_file)
+            .unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert_ne!(before, fingerprint(&dir).unwrap());
+
+        std::fs::create_dir_all(dir.join("services")).unwrap();
+        std::fs::write(dir.join("services/api.kdl"), "service \"api\"\n").unwrap();
+        assert_eq!(fingerprint(&dir).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,193 @@
+//! ローカル操作履歴（監査ログ）
+//!
+//! `fleet up` / `down` / `restart` / `deploy` の結果とセットアップステップの所要時間を
+//! `.fleetflow/audit.jsonl` に 1 行 1 イベントで追記する。外部には送信せず、
+//! `fleet report` の集計にのみ使う。記録に失敗しても操作自体は失敗させない。
+
+use fleetflow_core::Flow;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// これを超えたら `audit.jsonl.1` へローテートする
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 記録するイベント
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// 変更系コマンドの実行
+    Command {
+        command: String,
+        stage: String,
+        /// 対象サービス
+        services: Vec<String>,
+        duration_ms: u64,
+        success: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// セットアップステップの実行
+    SetupStep {
+        stage: String,
+        step: String,
+        /// done / skipped / failed
+        status: String,
+        attempts: u32,
+        duration_ms: u64,
+    },
+}
+
+/// 監査ログの 1 行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// 記録日時（RFC 3339）
+    pub at: String,
+    pub project: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+pub fn log_path(project_root: &Path) -> PathBuf {
+    project_root.join(".fleetflow").join("audit.jsonl")
+}
+
+fn try_append(project_root: &Path, record: &AuditRecord) -> anyhow::Result<()> {
+    let path = log_path(project_root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        std::fs::rename(&path, path.with_extension("jsonl.1"))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// イベントを追記する（失敗はデバッグログのみ）
+pub fn append(project_root: &Path, project: &str, event: AuditEvent) {
+    let record = AuditRecord {
+        at: chrono::Local::now().to_rfc3339(),
+        project: project.to_string(),
+        event,
+    };
+    if let Err(e) = try_append(project_root, &record) {
+        tracing::debug!(error = %e, "Failed to write audit log");
+    }
+}
+
+/// 監査ログを読み込む（ローテート済みの古い方から順に、壊れた行は読み飛ばす）
+pub fn load(project_root: &Path) -> Vec<AuditRecord> {
+    let path = log_path(project_root);
+    [path.with_extension("jsonl.1"), path]
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<AuditRecord>>()
+        })
+        .collect()
+}
+
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// 変更系コマンドを実行し、所要時間と結果を記録する
+///
+/// `services` が空ならステージの全サービスを対象として記録する。
+/// ステージが解決できない場合（実行自体もエラーになる）は記録しない。
+pub async fn track<F>(
+    project_root: &Path,
+    config: &Flow,
+    command: &str,
+    stage: Option<&str>,
+    services: &[String],
+    operation: F,
+) -> anyhow::Result<()>
+where
+    F: std::future::Future<Output = anyhow::Result<()>>,
+{
+    let started = Instant::now();
+    let result = operation.await;
+
+    if let Ok(stage_name) = crate::utils::determine_stage_name(stage.map(str::to_string), config) {
+        let services = if services.is_empty() {
+            config
+                .stages
+                .get(&stage_name)
+                .map(|s| s.services.clone())
+                .unwrap_or_default()
+        } else {
+            services.to_vec()
+        };
+        append(
+            project_root,
+            &config.name,
+            AuditEvent::Command {
+                command: command.to_string(),
+                stage: stage_name,
+                services,
+                duration_ms: millis(started.elapsed()),
+                success: result.is_ok(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            },
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        append(
+            dir.path(),
+            "myapp",
+            AuditEvent::Command {
+                command: "deploy".to_string(),
+                stage: "prod".to_string(),
+                services: vec!["api".to_string()],
+                duration_ms: 1200,
+                success: false,
+                error: Some("pull failed".to_string()),
+            },
+        );
+        append(
+            dir.path(),
+            "myapp",
+            AuditEvent::SetupStep {
+                stage: "prod".to_string(),
+                step: "migrate".to_string(),
+                status: "done".to_string(),
+                attempts: 1,
+                duration_ms: 300,
+            },
+        );
+        // 壊れた行は読み飛ばす
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log_path(dir.path()))
+            .unwrap();
+        writeln!(file, "not json").unwrap();
+
+        let records = load(dir.path());
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            &records[0].event,
+            AuditEvent::Command { success: false, .. }
+        ));
+        let line = std::fs::read_to_string(log_path(dir.path())).unwrap();
+        assert!(line.contains("\"kind\":\"command\""));
+    }
+}
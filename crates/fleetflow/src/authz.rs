@@ -0,0 +1,295 @@
+//! 変更系コマンドの権限チェック
+//!
+//! グローバル設定の `permissions` ブロックに基づき、共有サーバー上で
+//! どのロールがどのステージを変更できるかを判定する。保護されたステージへの
+//! 変更は `--role admin` の明示と確認（ステージ名の入力または `--yes`）を必須にする。
+//! `permissions` が未設定なら何も制限しない。
+
+use colored::Colorize;
+use fleetflow_config::PermissionsConfig;
+use fleetflow_core::Flow;
+use std::io::{IsTerminal, Write};
+
+/// 全ステージを変更できるロール
+pub const ADMIN_ROLE: &str = "admin";
+
+/// 変更の対象となるステージの範囲
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// 指定したステージ（None ならコマンドのステージ指定・既定ステージ）
+    Stage(Option<String>),
+    /// サーバー上のステージ（None なら servers を持つ全ステージ）
+    Server(Option<String>),
+    /// このホストで動くステージ（servers を持たないステージ）
+    LocalHost,
+}
+
+/// コマンドの実行に必要な権限
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permission {
+    pub command: &'static str,
+    pub scope: Scope,
+    /// `--yes` 指定時は保護ステージの確認を省略
+    pub assume_yes: bool,
+}
+
+impl Permission {
+    pub fn new(command: &'static str, scope: Scope) -> Self {
+        Self {
+            command,
+            scope,
+            assume_yes: false,
+        }
+    }
+
+    /// コマンドのステージ指定（または既定ステージ）を変更する権限
+    pub fn stage(command: &'static str) -> Self {
+        Self::new(command, Scope::Stage(None))
+    }
+
+    pub fn assume_yes(mut self, yes: bool) -> Self {
+        self.assume_yes = yes;
+        self
+    }
+}
+
+/// 権限チェックの結果
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Allow,
+    /// 実行前に確認が必要
+    Confirm,
+    Deny(String),
+}
+
+fn check(permissions: &PermissionsConfig, stage: &str, role: Option<&str>) -> Decision {
+    let effective = role.or(permissions.role.as_deref());
+
+    if let Some(role) = effective
+        && role != ADMIN_ROLE
+    {
+        let Some(stages) = permissions.profiles.get(role) else {
+            return Decision::Deny(format!(
+                "ロール '{}' は定義されていません（permissions の profile を確認してください）",
+                role
+            ));
+        };
+        if !stages.iter().any(|s| s == stage) {
+            return Decision::Deny(format!(
+                "ロール '{}' はステージ '{}' を変更できません（変更可能: {}）",
+                role,
+                stage,
+                if stages.is_empty() {
+                    "なし".to_string()
+                } else {
+                    stages.join(", ")
+                }
+            ));
+        }
+    }
+
+    if permissions.protected.iter().any(|s| s == stage) {
+        // 既定ロールが admin でも、保護ステージでは毎回 --role admin の明示を求める
+        if role != Some(ADMIN_ROLE) {
+            return Decision::Deny(format!(
+                "ステージ '{}' は保護されています。変更するには --role {} を指定してください",
+                stage, ADMIN_ROLE
+            ));
+        }
+        return Decision::Confirm;
+    }
+
+    Decision::Allow
+}
+
+/// `command` でステージ `stage` を変更してよいかを確認する
+///
+/// `assume_yes` が true（`--yes` 指定）なら保護ステージの確認プロンプトを省略する。
+pub fn authorize(
+    command: &str,
+    stage: &str,
+    role: Option<&str>,
+    assume_yes: bool,
+) -> anyhow::Result<()> {
+    let permissions = &crate::utils::global_config().permissions;
+    match check(permissions, stage, role) {
+        Decision::Allow => Ok(()),
+        Decision::Deny(reason) => Err(anyhow::anyhow!("fleet {}: {}", command, reason)),
+        Decision::Confirm if assume_yes => Ok(()),
+        Decision::Confirm => confirm(command, stage),
+    }
+}
+
+/// 権限チェックの対象ステージを列挙する
+///
+/// 範囲に該当するステージが無ければ `default_stage` を対象にする。
+fn target_stages(scope: &Scope, config: &Flow, default_stage: Option<&str>) -> Vec<String> {
+    let mut stages: Vec<String> = match scope {
+        Scope::Stage(Some(stage)) => return vec![stage.clone()],
+        Scope::Stage(None) => Vec::new(),
+        Scope::Server(server) => config
+            .stages
+            .iter()
+            .filter(|(_, stage)| match server {
+                Some(name) => stage.servers.iter().any(|s| s == name),
+                None => !stage.servers.is_empty(),
+            })
+            .map(|(name, _)| name.clone())
+            .collect(),
+        Scope::LocalHost => config
+            .stages
+            .iter()
+            .filter(|(_, stage)| stage.servers.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect(),
+    };
+    if stages.is_empty() {
+        stages.extend(default_stage.map(str::to_string));
+    }
+    stages.sort();
+    stages
+}
+
+/// `permission` の範囲に含まれる全ステージを変更してよいかを確認する
+pub fn authorize_command(
+    permission: &Permission,
+    config: &Flow,
+    default_stage: Option<&str>,
+    role: Option<&str>,
+) -> anyhow::Result<()> {
+    for stage in target_stages(&permission.scope, config, default_stage) {
+        authorize(permission.command, &stage, role, permission.assume_yes)?;
+    }
+    Ok(())
+}
+
+/// 保護ステージの変更をステージ名の入力で確認
+fn confirm(command: &str, stage: &str) -> anyhow::Result<()> {
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "ステージ '{}' は保護されています。非対話環境では --yes を指定してください",
+            stage
+        ));
+    }
+
+    println!(
+        "{}",
+        format!(
+            "⚠ 保護されたステージ '{}' に対して fleet {} を実行します",
+            stage, command
+        )
+        .yellow()
+        .bold()
+    );
+    print!("続行するにはステージ名を入力してください: ");
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim() == stage {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("確認が一致しないため中止しました"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn permissions() -> PermissionsConfig {
+        PermissionsConfig {
+            role: Some("operator".to_string()),
+            protected: vec!["prod".to_string()],
+            profiles: BTreeMap::from([(
+                "operator".to_string(),
+                vec!["local".to_string(), "dev".to_string()],
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_target_stages_by_scope() {
+        let mut config = Flow::default();
+        for (name, servers) in [
+            ("local", vec![]),
+            ("dev", vec!["vps-1"]),
+            ("prod", vec!["vps-2"]),
+        ] {
+            config.stages.insert(
+                name.to_string(),
+                fleetflow_core::Stage {
+                    servers: servers.into_iter().map(str::to_string).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(
+            target_stages(&Scope::Stage(None), &config, Some("dev")),
+            vec!["dev"]
+        );
+        assert_eq!(
+            target_stages(&Scope::Stage(Some("prod".into())), &config, Some("dev")),
+            vec!["prod"]
+        );
+        assert_eq!(
+            target_stages(&Scope::Server(Some("vps-2".into())), &config, None),
+            vec!["prod"]
+        );
+        assert_eq!(
+            target_stages(&Scope::Server(None), &config, None),
+            vec!["dev", "prod"]
+        );
+        assert_eq!(
+            target_stages(&Scope::LocalHost, &config, Some("dev")),
+            vec!["local"]
+        );
+        // 該当ステージが無いサーバーは既定ステージで判定
+        assert_eq!(
+            target_stages(&Scope::Server(Some("vps-9".into())), &config, Some("dev")),
+            vec!["dev"]
+        );
+    }
+
+    #[test]
+    fn test_unrestricted_without_permissions() {
+        let permissions = PermissionsConfig::default();
+        assert_eq!(check(&permissions, "prod", None), Decision::Allow);
+    }
+
+    #[test]
+    fn test_profile_limits_stages() {
+        let permissions = permissions();
+        assert_eq!(check(&permissions, "dev", None), Decision::Allow);
+        assert!(matches!(
+            check(&permissions, "stg", None),
+            Decision::Deny(reason) if reason.contains("local, dev")
+        ));
+        assert!(matches!(
+            check(&permissions, "dev", Some("intern")),
+            Decision::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_protected_stage_requires_explicit_admin() {
+        let mut permissions = permissions();
+        assert!(matches!(
+            check(&permissions, "prod", None),
+            Decision::Deny(_)
+        ));
+        assert_eq!(
+            check(&permissions, "prod", Some(ADMIN_ROLE)),
+            Decision::Confirm
+        );
+
+        permissions.role = Some(ADMIN_ROLE.to_string());
+        assert!(matches!(
+            check(&permissions, "prod", None),
+            Decision::Deny(_)
+        ));
+        assert_eq!(check(&permissions, "stg", None), Decision::Allow);
+    }
+}
@@ -1,7 +1,9 @@
+use crate::build_history;
 use crate::docker;
 use crate::utils;
 use colored::Colorize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 /// docker buildx を使用したクロスプラットフォームビルド
 #[allow(clippy::too_many_arguments)]
@@ -13,6 +15,7 @@ async fn build_with_buildx(
     build_args: &HashMap<String, String>,
     target: Option<&str>,
     no_cache: bool,
+    inline_cache: Option<&str>,
     push: bool,
 ) -> anyhow::Result<()> {
     use std::process::Command;
@@ -42,6 +45,14 @@ async fn build_with_buildx(
     // キャッシュなし
     if no_cache {
         cmd.arg("--no-cache");
+    } else if let Some(cache_ref) = inline_cache {
+        // 共有インラインキャッシュ（プッシュ時はキャッシュタグも一緒にプッシュされる）
+        cmd.arg("-t")
+            .arg(cache_ref)
+            .arg("--cache-from")
+            .arg(cache_ref)
+            .arg("--cache-to")
+            .arg("type=inline");
     }
 
     // プッシュフラグ
@@ -68,20 +79,319 @@ async fn build_with_buildx(
     Ok(())
 }
 
+/// リモートビルド先のサーバーを解決し、(サーバー名, DOCKER_HOST) を返す
+///
+/// `--on <server>` が最優先。なければ `build { remote #true }` のサービスに限り
+/// ステージの最初のサーバーを使う。どちらでもなければローカルビルド（None）。
+fn resolve_remote_build(
+    config: &fleetflow_core::Flow,
+    stage_config: &fleetflow_core::Stage,
+    service: &fleetflow_core::Service,
+    on_server: Option<&str>,
+) -> anyhow::Result<Option<(String, String)>> {
+    let wants_remote = service.build.as_ref().is_some_and(|b| b.remote);
+    let server_name = match on_server {
+        Some(name) => name,
+        None if wants_remote => stage_config.servers.first().ok_or_else(|| {
+            anyhow::anyhow!(
+                "build {{ remote }} にはステージの servers 指定、または --on <server> が必要です"
+            )
+        })?,
+        None => return Ok(None),
+    };
+
+    let server = config
+        .servers
+        .get(server_name)
+        .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", server_name))?;
+    let docker_host = server.docker_host().ok_or_else(|| {
+        anyhow::anyhow!(
+            "サーバー '{}' に ssh_host が設定されていません",
+            server_name
+        )
+    })?;
+
+    Ok(Some((server_name.to_string(), docker_host)))
+}
+
+/// プロジェクトで共有するインラインキャッシュのイメージ参照
+///
+/// `{registry}/{project}-buildcache:{stage}`（registry 未設定時は `{project}-buildcache:{stage}`）。
+/// registry の優先順位は CLI > Stage > Flow。
+fn inline_cache_ref(
+    config: &fleetflow_core::Flow,
+    stage_config: &fleetflow_core::Stage,
+    stage_name: &str,
+    registry: Option<&str>,
+) -> String {
+    let image = format!("{}-buildcache:{}", config.name, stage_name);
+    match registry
+        .or(stage_config.registry.as_deref())
+        .or(config.registry.as_deref())
+    {
+        Some(reg) => format!("{}/{}", reg, image),
+        None => image,
+    }
+}
+
+/// ビルド前にベースイメージ（Dockerfile の FROM）を並列に pull する
+///
+/// 複数サービスで共通のベースイメージは 1 回だけ pull する。失敗しても
+/// ビルド自体で pull し直せるため警告に留める。
+async fn warm_base_images(
+    docker_conn: &bollard::Docker,
+    resolver: &fleetflow_build::BuildResolver,
+    services: &[(&String, &fleetflow_core::Service)],
+    auth: &fleetflow_build::RegistryAuth,
+) {
+    let variables: HashMap<String, String> = std::env::vars().collect();
+    let mut images: Vec<String> = Vec::new();
+    for (service_name, service) in services {
+        let Ok(Some(dockerfile_path)) = resolver.resolve_dockerfile(service_name, service) else {
+            continue;
+        };
+        let build_args = resolver.resolve_build_args(service, &variables);
+        match resolver.resolve_base_images(&dockerfile_path, &build_args) {
+            Ok(found) => {
+                for image in found {
+                    if !images.contains(&image) {
+                        images.push(image);
+                    }
+                }
+            }
+            Err(e) => eprintln!(
+                "  {} {} の読み込みに失敗: {}",
+                "⚠".yellow(),
+                dockerfile_path.display(),
+                e
+            ),
+        }
+    }
+
+    if images.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("🔥 ベースイメージを事前取得中 ({} 個)...", images.len())
+            .blue()
+            .bold()
+    );
+    let results = futures_util::future::join_all(
+        images
+            .iter()
+            .map(|image| docker::pull_image_always(docker_conn, image, auth)),
+    )
+    .await;
+    for (image, result) in images.iter().zip(results) {
+        if let Err(e) = result {
+            eprintln!("  {} {}: {}", "⚠".yellow(), image, e);
+        }
+    }
+}
+
+/// 1 サービス分のビルド計画
+#[derive(Debug, Clone)]
+struct BuildJob {
+    service_name: String,
+    dockerfile_path: PathBuf,
+    context_path: PathBuf,
+    full_image: String,
+    /// 他サービスの FROM と照合する名前（ビルド後のイメージ名と `image` 宣言）
+    provides: Vec<String>,
+    base_images: Vec<String>,
+    build_args: HashMap<String, String>,
+    target: Option<String>,
+    /// サーバー上でビルドする場合の (サーバー名, DOCKER_HOST)
+    remote: Option<(String, String)>,
+}
+
+/// タグ省略時は `latest` として比較できるよう正規化
+fn normalize_image(image: &str) -> String {
+    let (name, tag) = fleetflow_build::split_image_tag(image);
+    format!("{}:{}", name, tag)
+}
+
+/// ビルドの依存グラフを段（レベル）に分ける
+///
+/// あるサービスの FROM が別サービスのイメージを指す場合、そのサービスを先にビルドする。
+/// 同じ段のサービスは互いに依存しないため並列にビルドできる。
+fn plan_build_levels(jobs: &[BuildJob]) -> anyhow::Result<Vec<Vec<usize>>> {
+    let deps: Vec<Vec<usize>> = jobs
+        .iter()
+        .enumerate()
+        .map(|(i, job)| {
+            let bases: Vec<String> = job.base_images.iter().map(|b| normalize_image(b)).collect();
+            jobs.iter()
+                .enumerate()
+                .filter(|(j, other)| {
+                    *j != i
+                        && other
+                            .provides
+                            .iter()
+                            .any(|p| bases.contains(&normalize_image(p)))
+                })
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect();
+
+    let mut done = vec![false; jobs.len()];
+    let mut levels = Vec::new();
+    while done.iter().any(|d| !d) {
+        let level: Vec<usize> = (0..jobs.len())
+            .filter(|&i| !done[i] && deps[i].iter().all(|&d| done[d]))
+            .collect();
+        if level.is_empty() {
+            let cycle: Vec<&str> = (0..jobs.len())
+                .filter(|&i| !done[i])
+                .map(|i| jobs[i].service_name.as_str())
+                .collect();
+            return Err(anyhow::anyhow!(
+                "ビルド依存（FROM）が循環しています: {}",
+                cycle.join(", ")
+            ));
+        }
+        for &i in &level {
+            done[i] = true;
+        }
+        levels.push(level);
+    }
+    Ok(levels)
+}
+
+/// 全ビルドで共通の設定
+#[derive(Clone)]
+struct BuildSettings {
+    docker_conn: bollard::Docker,
+    progress: std::sync::Arc<dyn fleetflow_build::ProgressReporter>,
+    use_buildx: bool,
+    target_platform: String,
+    no_cache: bool,
+    push: bool,
+    inline_cache: Option<String>,
+}
+
+/// 1 サービスをビルドする
+async fn run_build_job(job: BuildJob, settings: BuildSettings) -> anyhow::Result<()> {
+    use fleetflow_build::ImageBuilder;
+
+    let mut builder =
+        ImageBuilder::new(settings.docker_conn.clone()).with_progress(settings.progress.clone());
+    if let Some((_, docker_host)) = &job.remote {
+        // サーバーの Docker デーモンでビルド（コンテキストは SSH 経由で転送）
+        builder = builder.with_docker_host(docker_host.clone());
+    }
+    if let Some(cache_ref) = &settings.inline_cache {
+        builder = builder.with_inline_cache(cache_ref.clone());
+    }
+
+    if job.remote.is_none() && settings.use_buildx && !settings.target_platform.is_empty() {
+        // docker buildx build でクロスプラットフォームビルド
+        build_with_buildx(
+            &job.dockerfile_path,
+            &job.context_path,
+            &job.full_image,
+            &settings.target_platform,
+            &job.build_args,
+            job.target.as_deref(),
+            settings.no_cache,
+            settings.inline_cache.as_deref(),
+            settings.push,
+        )
+        .await
+    } else {
+        // docker buildxでビルド（BuildKit有効）
+        builder
+            .build_image_from_path(
+                &job.context_path,
+                &job.dockerfile_path,
+                &job.full_image,
+                job.build_args.clone(),
+                job.target.as_deref(),
+                settings.no_cache,
+                None,
+            )
+            .await
+            .map_err(anyhow::Error::from)
+    }
+}
+
+/// イメージごとのプッシュ結果
+enum PushStatus {
+    Pushed,
+    Skipped(&'static str),
+    Failed(String),
+}
+
+/// イメージを最大 `jobs` 並列でプッシュする（結果は `targets` の順）
+///
+/// `continue_on_error` でなければ、失敗後はまだ開始していないプッシュを行わない。
+async fn push_images(
+    pusher: &fleetflow_build::ImagePusher,
+    targets: &[(String, String)],
+    jobs: usize,
+    continue_on_error: bool,
+) -> Vec<(String, String, PushStatus)> {
+    use futures_util::stream::{self, StreamExt};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let aborted = AtomicBool::new(false);
+    stream::iter(targets)
+        .map(|(service_name, full_image)| {
+            let aborted = &aborted;
+            async move {
+                if aborted.load(Ordering::SeqCst) {
+                    return (
+                        service_name.clone(),
+                        full_image.clone(),
+                        PushStatus::Skipped("先行のプッシュが失敗したため中止"),
+                    );
+                }
+                // イメージとタグを分離
+                let (image, tag) = fleetflow_build::split_image_tag(full_image);
+                let status = match pusher.push(&image, &tag).await {
+                    Ok(_) => PushStatus::Pushed,
+                    Err(e) => {
+                        if !continue_on_error {
+                            aborted.store(true, Ordering::SeqCst);
+                        }
+                        PushStatus::Failed(e.to_string())
+                    }
+                };
+                (service_name.clone(), full_image.clone(), status)
+            }
+        })
+        .buffered(jobs.max(1))
+        .collect()
+        .await
+}
+
 /// ビルドコマンドを処理
+///
+/// サービス間の依存（FROM が別サービスのイメージ）から段を組み、
+/// 段ごとに依存のないサービスを並列にビルドする。
 #[allow(clippy::too_many_arguments)]
 pub async fn handle_build_command(
     project_root: &std::path::Path,
     config: &fleetflow_core::Flow,
     stage_name: &str,
     service_filters: &[String],
+    all: bool,
     push: bool,
     cli_tag: Option<&str>,
     registry: Option<&str>,
     platform: Option<&str>,
     no_cache: bool,
+    on_server: Option<&str>,
+    warm_cache: bool,
+    compare_last: bool,
+    continue_on_error: bool,
+    push_jobs: usize,
+    changed_since: Option<&str>,
 ) -> anyhow::Result<()> {
-    use fleetflow_build::{BuildResolver, ImageBuilder, ImagePusher, resolve_tag};
+    use fleetflow_build::{BuildResolver, ImagePusher, resolve_tag};
 
     // ステージの取得
     let stage_config = config
@@ -109,8 +419,19 @@ pub async fn handle_build_command(
     }
 
     // ビルド対象のサービスを決定
-    let target_services_owned =
+    // --all はステージに含まれないサービス（ベースイメージ用など）も対象にする
+    let mut target_services_owned =
         utils::filter_services(&stage_config.services, service_filters, stage_name)?;
+    if all {
+        let mut extra: Vec<String> = config
+            .services
+            .keys()
+            .filter(|name| !target_services_owned.contains(name))
+            .cloned()
+            .collect();
+        extra.sort();
+        target_services_owned.extend(extra);
+    }
     let target_services: Vec<&String> = target_services_owned.iter().collect();
 
     // ビルド可能なサービスをフィルタ（build設定があるもののみ）
@@ -136,6 +457,30 @@ pub async fn handle_build_command(
         return Ok(());
     }
 
+    let resolver = BuildResolver::new(project_root.to_path_buf());
+
+    // --changed-since: 変更のあったサービスだけに絞り込む
+    let buildable_services = match changed_since {
+        Some(since) => {
+            let changed = resolver.changed_files(since)?;
+            println!("{} 以降の変更ファイル: {} 個", since.cyan(), changed.len());
+            let mut affected = Vec::new();
+            for (name, service) in buildable_services {
+                if resolver.is_affected(service, &changed)? {
+                    affected.push((name, service));
+                } else {
+                    println!("  {} {} (変更なし)", "⊘".dimmed(), name.dimmed());
+                }
+            }
+            if affected.is_empty() {
+                println!("{}", "変更のあったサービスはありません".yellow());
+                return Ok(());
+            }
+            affected
+        }
+        None => buildable_services,
+    };
+
     println!();
     println!(
         "{}",
@@ -149,36 +494,75 @@ pub async fn handle_build_command(
     println!();
     println!("{}", "Dockerに接続中...".blue());
     let docker_conn = docker::init_docker_with_error_handling().await?;
+    let registry_auth = docker::registry_auth(config, stage_name)?;
 
-    // BuildResolver と ImageBuilder を作成
-    let resolver = BuildResolver::new(project_root.to_path_buf());
-    let builder = ImageBuilder::new(docker_conn.clone());
+    // registry が localhost のレジストリなら、push / キャッシュ取得の前に起動しておく
+    if push || warm_cache {
+        let effective_registry = registry
+            .or(stage_config.registry.as_deref())
+            .or(config.registry.as_deref());
+        crate::commands::local_registry::ensure_for_registry(&docker_conn, effective_registry)
+            .await?;
+    }
+
+    // --warm-cache: ベースイメージの事前取得と共有インラインキャッシュ
+    let inline_cache = (warm_cache && !no_cache)
+        .then(|| inline_cache_ref(config, stage_config, stage_name, registry));
+    if warm_cache {
+        // リモートビルドのサービスはサーバー側のデーモンで pull されるため対象外
+        let local_services: Vec<(&String, &fleetflow_core::Service)> = buildable_services
+            .iter()
+            .filter(|(_, service)| {
+                matches!(
+                    resolve_remote_build(config, stage_config, service, on_server),
+                    Ok(None)
+                )
+            })
+            .copied()
+            .collect();
+        warm_base_images(&docker_conn, &resolver, &local_services, &registry_auth).await;
+        if let Some(cache_ref) = &inline_cache {
+            println!("  → Inline cache: {}", cache_ref.cyan());
+        }
+    }
 
     // プッシュが必要な場合は ImagePusher も作成
     let pusher = if push {
-        Some(ImagePusher::new(docker_conn.clone()))
+        Some(ImagePusher::with_auth(docker_conn.clone(), registry_auth))
     } else {
         None
     };
 
-    // ビルド結果を格納
-    let mut build_results: Vec<(String, String)> = Vec::new();
-
-    // 各サービスをビルド
-    for (service_name, service) in &buildable_services {
-        println!();
-        println!(
-            "{}",
-            format!("🔨 {} をビルド中...", service_name).green().bold()
+    // ビルド計画を作成
+    println!();
+    println!("{}", "📋 ビルド計画".bold());
+    let variables: HashMap<String, String> = std::env::vars().collect();
+    let image_template = stage_config
+        .image_template
+        .as_deref()
+        .or(config.image_template.as_deref());
+    // サービス共通の image_template 変数（git 情報は template がある時だけ取得）
+    let mut template_vars: HashMap<String, String> = HashMap::new();
+    if let Some(template) = image_template {
+        println!("  イメージ名: {}", template.cyan());
+        template_vars = resolver.git_vars();
+        template_vars.insert("project".to_string(), config.name.clone());
+        template_vars.insert("stage".to_string(), stage_name.to_string());
+        template_vars.insert(
+            "timestamp".to_string(),
+            chrono::Utc::now().format("%Y%m%d%H%M%S").to_string(),
         );
-
+    }
+    let mut jobs: Vec<BuildJob> = Vec::new();
+    for (service_name, service) in &buildable_services {
         // Dockerfileを解決
         let dockerfile_path = match resolver.resolve_dockerfile(service_name, service) {
             Ok(Some(path)) => path,
             Ok(None) => {
                 println!(
-                    "  {} Dockerfileが見つかりません。スキップします。",
-                    "⚠".yellow()
+                    "  {} {}: Dockerfileが見つかりません。スキップします。",
+                    "⚠".yellow(),
+                    service_name
                 );
                 continue;
             }
@@ -208,7 +592,22 @@ pub async fn handle_build_command(
             cli_tag,
             service.image.as_deref().unwrap_or(service_name.as_str()),
         );
-        let full_image = if let Some(reg) = effective_registry {
+        let full_image = if let Some(template) = image_template {
+            // image_template（ステージ > トップレベル）
+            let mut vars = template_vars.clone();
+            vars.insert(
+                "registry".to_string(),
+                effective_registry.unwrap_or_default().to_string(),
+            );
+            vars.insert("service".to_string(), service_name.to_string());
+            vars.insert("tag".to_string(), tag.clone());
+            fleetflow_build::render_image_template(template, &vars).map_err(|e| {
+                anyhow::Error::from(e).context(format!(
+                    "サービス '{}' の image_template を展開できません",
+                    service_name
+                ))
+            })?
+        } else if let Some(reg) = effective_registry {
             // registry/{project}-{stage}:{tag} 形式
             format!("{}/{}-{}:{}", reg, config.name, stage_name, tag)
         } else {
@@ -216,75 +615,170 @@ pub async fn handle_build_command(
         };
 
         // ビルド引数を解決
-        let variables: HashMap<String, String> = std::env::vars().collect();
         let build_args = resolver.resolve_build_args(service, &variables);
+        let base_images = resolver
+            .resolve_base_images(&dockerfile_path, &build_args)
+            .unwrap_or_default();
+
+        let mut provides = vec![full_image.clone()];
+        provides.extend(service.image.clone());
+
+        jobs.push(BuildJob {
+            service_name: service_name.to_string(),
+            dockerfile_path,
+            context_path,
+            full_image,
+            provides,
+            base_images,
+            build_args,
+            // ターゲットステージ
+            target: service.build.as_ref().and_then(|b| b.target.clone()),
+            remote: resolve_remote_build(config, stage_config, service, on_server)?,
+        });
+    }
 
-        // ターゲットステージ
-        let target = service.build.as_ref().and_then(|b| b.target.clone());
-
+    let levels = plan_build_levels(&jobs)?;
+    for (n, level) in levels.iter().enumerate() {
+        println!("  {}", format!("段 {}:", n + 1).bold());
+        for &i in level {
+            let job = &jobs[i];
+            println!("    • {} → {}", job.service_name.cyan(), job.full_image);
+            println!("      Dockerfile: {}", job.dockerfile_path.display());
+            println!("      Context: {}", job.context_path.display());
+            if let Some((server_name, docker_host)) = &job.remote {
+                println!(
+                    "      Build on: {} ({})",
+                    server_name.cyan(),
+                    docker_host.dimmed()
+                );
+            }
+        }
+    }
+    // 同じコンテキストを共有するサービス（BuildKit のキャッシュが効く）
+    let mut contexts: BTreeMap<&PathBuf, Vec<&str>> = BTreeMap::new();
+    for job in &jobs {
+        contexts
+            .entry(&job.context_path)
+            .or_default()
+            .push(&job.service_name);
+    }
+    for (context, services) in contexts.iter().filter(|(_, s)| s.len() > 1) {
         println!(
-            "  → Dockerfile: {}",
-            dockerfile_path.display().to_string().cyan()
+            "  {} 共有コンテキスト {}: {}",
+            "ℹ".blue(),
+            context.display(),
+            services.join(", ")
         );
-        println!("  → Context: {}", context_path.display().to_string().cyan());
-        println!("  → Image: {}", full_image.cyan());
-
-        // ビルド実行
-        if use_buildx && !target_platform.is_empty() {
-            // docker buildx build でクロスプラットフォームビルド
-            let result = build_with_buildx(
-                &dockerfile_path,
-                &context_path,
-                &full_image,
-                target_platform,
-                &build_args,
-                target.as_deref(),
-                no_cache,
-                push,
-            )
-            .await;
+    }
+
+    let settings = BuildSettings {
+        docker_conn: docker_conn.clone(),
+        progress: fleetflow_build::reporter(),
+        use_buildx,
+        target_platform: target_platform.to_string(),
+        no_cache,
+        push,
+        inline_cache,
+    };
 
+    // 段ごとに並列ビルド（docker CLI の呼び出しはブロッキングのため blocking スレッドで実行）
+    let runtime = tokio::runtime::Handle::current();
+    let mut durations = vec![0u64; jobs.len()];
+    for level in &levels {
+        println!();
+        for &i in level {
+            println!(
+                "{}",
+                format!("🔨 {} をビルド中...", jobs[i].service_name)
+                    .green()
+                    .bold()
+            );
+        }
+        let handles: Vec<_> = level
+            .iter()
+            .map(|&i| {
+                let job = jobs[i].clone();
+                let settings = settings.clone();
+                let runtime = runtime.clone();
+                tokio::task::spawn_blocking(move || {
+                    let started = std::time::Instant::now();
+                    let result = runtime.block_on(run_build_job(job, settings));
+                    (result, started.elapsed())
+                })
+            })
+            .collect();
+        let results = futures_util::future::join_all(handles).await;
+
+        let mut failed = Vec::new();
+        for (&i, result) in level.iter().zip(results) {
+            let job = &jobs[i];
+            let result = result
+                .map_err(anyhow::Error::from)
+                .and_then(|(r, elapsed)| {
+                    durations[i] = elapsed.as_millis() as u64;
+                    r
+                });
             match result {
-                Ok(_) => {
-                    println!("  {} ビルド完了", "✓".green());
-                    build_results.push((service_name.to_string(), full_image));
-                }
+                Ok(()) => match &job.remote {
+                    Some(_) => println!(
+                        "  {} {}: サーバー上でビルド完了",
+                        "✓".green(),
+                        job.service_name
+                    ),
+                    None => println!("  {} {}: ビルド完了", "✓".green(), job.service_name),
+                },
                 Err(e) => {
-                    eprintln!("  {} ビルドエラー: {}", "✗".red().bold(), e);
-                    return Err(e.context(format!(
-                        "サービス '{}' のビルドに失敗しました",
-                        service_name
-                    )));
-                }
-            }
-        } else {
-            // docker buildxでローカルビルド（BuildKit有効）
-            match builder
-                .build_image_from_path(
-                    &context_path,
-                    &dockerfile_path,
-                    &full_image,
-                    build_args.clone(),
-                    target.as_deref(),
-                    no_cache,
-                    None,
-                )
-                .await
-            {
-                Ok(_) => {
-                    println!("  {} ビルド完了", "✓".green());
-                    build_results.push((service_name.to_string(), full_image));
-                }
-                Err(e) => {
-                    eprintln!("  {} ビルドエラー: {}", "✗".red().bold(), e);
-                    return Err(anyhow::Error::from(e).context(format!(
-                        "サービス '{}' のビルドに失敗しました",
-                        service_name
-                    )));
+                    eprintln!(
+                        "  {} {}: ビルドエラー: {}",
+                        "✗".red().bold(),
+                        job.service_name,
+                        e
+                    );
+                    failed.push(job.service_name.as_str());
                 }
             }
         }
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!(
+                "サービス '{}' のビルドに失敗しました",
+                failed.join("', '")
+            ));
+        }
+    }
+
+    // サイズとレイヤー数（ローカルにロードされたイメージのみ取得できる）
+    let mut service_builds = Vec::new();
+    for (job, duration_ms) in jobs.iter().zip(&durations) {
+        let info = match &job.remote {
+            Some(_) => None,
+            None => docker_conn.inspect_image(&job.full_image).await.ok(),
+        };
+        service_builds.push(build_history::ServiceBuild {
+            service: job.service_name.clone(),
+            image: job.full_image.clone(),
+            duration_ms: *duration_ms,
+            size_bytes: info.as_ref().and_then(|i| i.size).map(|s| s as u64),
+            layers: info
+                .as_ref()
+                .and_then(|i| i.root_fs.as_ref())
+                .and_then(|r| r.layers.as_ref())
+                .map(Vec::len),
+        });
     }
+    let previous = build_history::last_run(project_root, stage_name);
+    build_history::append(project_root, stage_name, service_builds.clone());
+
+    // ビルド結果を格納
+    let build_results: Vec<(String, String)> = jobs
+        .iter()
+        .map(|job| (job.service_name.clone(), job.full_image.clone()))
+        .collect();
+    // サーバー上でビルドしたサービス（イメージはサーバーにあるためプッシュ不要）
+    let remote_built: Vec<String> = jobs
+        .iter()
+        .filter(|job| job.remote.is_some())
+        .map(|job| job.service_name.clone())
+        .collect();
 
     // プッシュ処理（buildxで--push済みの場合はスキップ）
     let already_pushed = use_buildx && push;
@@ -294,24 +788,62 @@ pub async fn handle_build_command(
             println!("{}", "📤 buildxで既にプッシュ済み".blue().bold());
         } else {
             println!();
-            println!("{}", "📤 イメージをプッシュ中...".blue().bold());
-
-            for (service_name, full_image) in &build_results {
-                println!();
-                println!("{}", format!("Pushing {}...", service_name).blue());
-
-                // イメージとタグを分離
-                let (image, tag) = fleetflow_build::split_image_tag(full_image);
-
-                match pusher.push(&image, &tag).await {
-                    Ok(pushed_image) => {
-                        println!("  {} {}", "✓".green(), pushed_image.cyan());
+            println!(
+                "{}",
+                format!(
+                    "📤 イメージをプッシュ中（最大 {} 並列）...",
+                    push_jobs.max(1)
+                )
+                .blue()
+                .bold()
+            );
+
+            let targets: Vec<(String, String)> = build_results
+                .iter()
+                .filter(|(service_name, _)| !remote_built.contains(service_name))
+                .cloned()
+                .collect();
+            let mut pushed = push_images(&pusher, &targets, push_jobs, continue_on_error)
+                .await
+                .into_iter();
+            // サーバー上でビルドしたイメージはサーバーにあるためプッシュ不要
+            let statuses: Vec<(String, String, PushStatus)> = build_results
+                .iter()
+                .filter_map(|(service_name, full_image)| {
+                    if remote_built.contains(service_name) {
+                        Some((
+                            service_name.clone(),
+                            full_image.clone(),
+                            PushStatus::Skipped("サーバー上でビルド済み"),
+                        ))
+                    } else {
+                        pushed.next()
                     }
-                    Err(e) => {
-                        eprintln!("  {} プッシュエラー: {}", "✗".red().bold(), e);
-                        return Err(anyhow::anyhow!("プッシュに失敗しました"));
+                })
+                .collect();
+
+            println!();
+            println!("{}", "プッシュ結果:".bold());
+            for (service_name, full_image, status) in &statuses {
+                let status = match status {
+                    PushStatus::Pushed => format!("{} pushed", "✓".green()),
+                    PushStatus::Skipped(reason) => {
+                        format!("{} skipped（{}）", "-".dimmed(), reason)
                     }
-                }
+                    PushStatus::Failed(e) => format!("{} failed: {}", "✗".red(), e),
+                };
+                println!("  {:<16} {}  {}", service_name, full_image.cyan(), status);
+            }
+
+            let failed = statuses
+                .iter()
+                .filter(|(_, _, s)| matches!(s, PushStatus::Failed(_)))
+                .count();
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} 個のイメージのプッシュに失敗しました",
+                    failed
+                ));
             }
         }
     }
@@ -335,9 +867,148 @@ pub async fn handle_build_command(
     // 結果サマリー
     println!();
     println!("{}", "結果サマリー:".bold());
-    for (service_name, full_image) in &build_results {
-        println!("  {} {}: {}", "✓".green(), service_name, full_image.cyan());
+    print_build_summary(
+        &service_builds,
+        if compare_last {
+            previous.as_ref()
+        } else {
+            None
+        },
+    );
+    if compare_last && previous.is_none() {
+        println!(
+            "{}",
+            format!(
+                "  ℹ ステージ '{}' の前回のビルド記録がありません",
+                stage_name
+            )
+            .dimmed()
+        );
     }
 
     Ok(())
 }
+
+/// サービスごとの所要時間・サイズ・レイヤー数の表（`previous` があれば差分も表示）
+fn print_build_summary(
+    builds: &[build_history::ServiceBuild],
+    previous: Option<&build_history::BuildRun>,
+) {
+    let unknown = || "-".to_string();
+    match previous {
+        Some(_) => println!(
+            "  {:<16} {:>8} {:>8} {:>10} {:>11} {:>7}  {}",
+            "SERVICE", "TIME", "ΔTIME", "SIZE", "ΔSIZE", "LAYERS", "IMAGE"
+        ),
+        None => println!(
+            "  {:<16} {:>8} {:>10} {:>7}  {}",
+            "SERVICE", "TIME", "SIZE", "LAYERS", "IMAGE"
+        ),
+    }
+    for build in builds {
+        let time = format!("{:.1}s", build.duration_ms as f64 / 1000.0);
+        let size = build
+            .size_bytes
+            .map(build_history::format_size)
+            .unwrap_or_else(unknown);
+        let layers = build.layers.map(|l| l.to_string()).unwrap_or_else(unknown);
+        let Some(run) = previous else {
+            println!(
+                "  {:<16} {:>8} {:>10} {:>7}  {}",
+                build.service,
+                time,
+                size,
+                layers,
+                build.image.cyan()
+            );
+            continue;
+        };
+
+        let before = run.service(&build.service);
+        let time_delta = before
+            .map(|b| build_history::duration_delta(build, b))
+            .unwrap_or_else(unknown);
+        let size_delta = before.and_then(|b| build_history::size_delta(build, b));
+        // イメージの肥大化を目立たせる（色付け前に桁揃え）
+        let size_delta = match size_delta {
+            Some(d) if d.starts_with('+') && d != "+0.0 MB" => format!("{:>11}", d).red(),
+            Some(d) => format!("{:>11}", d).normal(),
+            None => format!("{:>11}", unknown()).normal(),
+        };
+        println!(
+            "  {:<16} {:>8} {:>8} {:>10} {} {:>7}  {}",
+            build.service,
+            time,
+            time_delta,
+            size,
+            size_delta,
+            layers,
+            build.image.cyan()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Flow, Stage};
+
+    fn flow(registry: Option<&str>) -> Flow {
+        Flow {
+            name: "myapp".to_string(),
+            registry: registry.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_inline_cache_ref_prefers_cli_registry() {
+        let config = flow(Some("ghcr.io/flow"));
+        let stage = Stage::default();
+        assert_eq!(
+            inline_cache_ref(&config, &stage, "prod", None),
+            "ghcr.io/flow/myapp-buildcache:prod"
+        );
+        assert_eq!(
+            inline_cache_ref(&config, &stage, "prod", Some("ghcr.io/cli")),
+            "ghcr.io/cli/myapp-buildcache:prod"
+        );
+        assert_eq!(
+            inline_cache_ref(&flow(None), &stage, "local", None),
+            "myapp-buildcache:local"
+        );
+    }
+
+    fn job(name: &str, image: &str, bases: &[&str]) -> BuildJob {
+        BuildJob {
+            service_name: name.to_string(),
+            dockerfile_path: PathBuf::from("Dockerfile"),
+            context_path: PathBuf::from("."),
+            full_image: format!("{}:latest", image),
+            provides: vec![format!("{}:latest", image), image.to_string()],
+            base_images: bases.iter().map(|b| b.to_string()).collect(),
+            build_args: HashMap::new(),
+            target: None,
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_build_levels_orders_dependent_images() {
+        let jobs = vec![
+            job("api", "myapp-api", &["myapp-base"]),
+            job("worker", "myapp-worker", &["myapp-base:latest"]),
+            job("web", "myapp-web", &["node:20"]),
+            job("base", "myapp-base", &["debian:bookworm-slim"]),
+        ];
+        let levels = plan_build_levels(&jobs).unwrap();
+        assert_eq!(levels, vec![vec![2, 3], vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_plan_build_levels_detects_cycle() {
+        let jobs = vec![job("a", "img-a", &["img-b"]), job("b", "img-b", &["img-a"])];
+        let err = plan_build_levels(&jobs).unwrap_err().to_string();
+        assert!(err.contains("a, b"));
+    }
+}
@@ -0,0 +1,141 @@
+//! ビルド履歴（サービスごとの所要時間・イメージサイズ・レイヤー数）
+//!
+//! `fleet build` 1 回につき `.fleetflow/build-history.jsonl` に 1 行追記する。
+//! `fleet build --compare-last` で同じステージの前回ビルドと比較し、
+//! イメージの肥大化やビルド時間の悪化を見つけるのに使う。記録に失敗してもビルドは失敗させない。
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// これを超えたら `build-history.jsonl.1` へローテートする
+const MAX_HISTORY_BYTES: u64 = 5 * 1024 * 1024;
+
+/// 1 サービスのビルド結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceBuild {
+    pub service: String,
+    pub image: String,
+    pub duration_ms: u64,
+    /// イメージサイズ（サーバー上でビルド・直接プッシュした場合は取得できず None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<usize>,
+}
+
+/// `fleet build` 1 回分の記録
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildRun {
+    /// 記録日時（RFC 3339）
+    pub at: String,
+    pub stage: String,
+    pub services: Vec<ServiceBuild>,
+}
+
+impl BuildRun {
+    pub fn service(&self, name: &str) -> Option<&ServiceBuild> {
+        self.services.iter().find(|s| s.service == name)
+    }
+}
+
+pub fn history_path(project_root: &Path) -> PathBuf {
+    project_root.join(".fleetflow").join("build-history.jsonl")
+}
+
+fn try_append(project_root: &Path, run: &BuildRun) -> anyhow::Result<()> {
+    let path = history_path(project_root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_HISTORY_BYTES) {
+        std::fs::rename(&path, path.with_extension("jsonl.1"))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(run)?)?;
+    Ok(())
+}
+
+/// ビルド結果を追記する（失敗はデバッグログのみ）
+pub fn append(project_root: &Path, stage: &str, services: Vec<ServiceBuild>) {
+    let run = BuildRun {
+        at: chrono::Local::now().to_rfc3339(),
+        stage: stage.to_string(),
+        services,
+    };
+    if let Err(e) = try_append(project_root, &run) {
+        tracing::debug!(error = %e, "Failed to write build history");
+    }
+}
+
+/// ステージの直近のビルド記録（壊れた行は読み飛ばす）
+pub fn last_run(project_root: &Path, stage: &str) -> Option<BuildRun> {
+    let path = history_path(project_root);
+    [path.with_extension("jsonl.1"), path]
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<BuildRun>(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .filter(|run| run.stage == stage)
+        .last()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+}
+
+/// 前回との差分（`+300.0 MB` / `-1.2s` 形式）。比較できなければ None
+pub fn size_delta(current: &ServiceBuild, previous: &ServiceBuild) -> Option<String> {
+    let (now, before) = (current.size_bytes?, previous.size_bytes?);
+    let sign = if now >= before { "+" } else { "-" };
+    Some(format!("{}{}", sign, format_size(now.abs_diff(before))))
+}
+
+pub fn duration_delta(current: &ServiceBuild, previous: &ServiceBuild) -> String {
+    let diff = current.duration_ms as f64 - previous.duration_ms as f64;
+    format!("{:+.1}s", diff / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(service: &str, duration_ms: u64, size_bytes: Option<u64>) -> ServiceBuild {
+        ServiceBuild {
+            service: service.to_string(),
+            image: format!("myapp-{service}:latest"),
+            duration_ms,
+            size_bytes,
+            layers: Some(8),
+        }
+    }
+
+    #[test]
+    fn test_last_run_returns_latest_for_stage() {
+        let dir = tempfile::tempdir().unwrap();
+        append(dir.path(), "prod", vec![build("api", 1_000, Some(100))]);
+        append(dir.path(), "dev", vec![build("api", 2_000, Some(200))]);
+        append(dir.path(), "prod", vec![build("api", 3_000, Some(300))]);
+
+        let run = last_run(dir.path(), "prod").unwrap();
+        assert_eq!(run.service("api").unwrap().duration_ms, 3_000);
+        assert!(last_run(dir.path(), "stg").is_none());
+    }
+
+    #[test]
+    fn test_deltas() {
+        let before = build("api", 40_000, Some(200 * 1024 * 1024));
+        let after = build("api", 38_500, Some(500 * 1024 * 1024));
+        assert_eq!(size_delta(&after, &before).as_deref(), Some("+300.0 MB"));
+        assert_eq!(size_delta(&before, &after).as_deref(), Some("-300.0 MB"));
+        assert_eq!(duration_delta(&after, &before), "-1.5s");
+        assert_eq!(size_delta(&build("api", 0, None), &before), None);
+    }
+}
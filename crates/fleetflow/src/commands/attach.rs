@@ -0,0 +1,282 @@
+//! `fleet attach` — 実行中コンテナのメインプロセスに端末を接続する
+//!
+//! REPL 型のサービス（`tty #true` / `stdin_open #true` で起動したもの）向け。
+//! デタッチキー（既定: Ctrl+P, Ctrl+Q）は Docker デーモン側で解釈され、
+//! コンテナを止めずに接続だけを切る。
+
+use crate::docker;
+use crate::utils;
+use bollard::Docker;
+use colored::Colorize;
+use futures_util::stream::StreamExt;
+use std::io::{IsTerminal, Read, Write};
+use tokio::io::AsyncWriteExt;
+
+/// Docker CLI と同じ既定のデタッチキー
+pub const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+
+/// 表示用のデタッチキー（`ctrl-p,ctrl-q` → `Ctrl+P, Ctrl+Q`）
+fn describe_detach_keys(keys: &str) -> String {
+    keys.split(',')
+        .map(|key| match key.trim().strip_prefix("ctrl-") {
+            Some(k) => format!("Ctrl+{}", k.to_uppercase()),
+            None => key.trim().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub async fn handle(
+    config: &fleetflow_core::Flow,
+    stage: Option<String>,
+    service: String,
+    detach_keys: Option<String>,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+
+    if !config.services.contains_key(&service) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' が見つかりません\n利用可能なサービス: {}",
+            service,
+            config
+                .services
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let container_name = config.container_name(&stage_name, &service);
+    let detach_keys = detach_keys.unwrap_or_else(|| DEFAULT_DETACH_KEYS.to_string());
+
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+
+    let inspect = match docker_conn
+        .inspect_container(
+            &container_name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+    {
+        Ok(inspect) => inspect,
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            return Err(anyhow::anyhow!(
+                "コンテナ '{}' が存在しません。先に fleet up で起動してください",
+                container_name
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !inspect
+        .state
+        .as_ref()
+        .and_then(|s| s.running)
+        .unwrap_or(false)
+    {
+        return Err(anyhow::anyhow!(
+            "コンテナ '{}' は実行されていません",
+            container_name
+        ));
+    }
+    let container_tty = inspect.config.as_ref().and_then(|c| c.tty).unwrap_or(false);
+    let open_stdin = inspect
+        .config
+        .as_ref()
+        .and_then(|c| c.open_stdin)
+        .unwrap_or(false);
+
+    println!(
+        "{}",
+        format!("コンテナ '{}' に接続中...", container_name).green()
+    );
+    if !open_stdin {
+        println!(
+            "{}",
+            "⚠ コンテナは stdin_open で起動されていないため、入力は送信されません".yellow()
+        );
+    }
+    println!(
+        "{}",
+        format!("デタッチ: {}", describe_detach_keys(&detach_keys)).dimmed()
+    );
+    println!();
+
+    let attached = docker_conn
+        .attach_container(
+            &container_name,
+            Some(bollard::query_parameters::AttachContainerOptions {
+                stdin: open_stdin,
+                stdout: true,
+                stderr: true,
+                stream: true,
+                logs: false,
+                detach_keys: Some(detach_keys),
+            }),
+        )
+        .await?;
+
+    let raw_mode = container_tty && std::io::stdin().is_terminal();
+    if raw_mode {
+        resize_tty(&docker_conn, &container_name).await;
+        crossterm::terminal::enable_raw_mode()?;
+    }
+
+    let result = pump(
+        &docker_conn,
+        &container_name,
+        attached,
+        open_stdin,
+        raw_mode,
+    )
+    .await;
+
+    if raw_mode {
+        crossterm::terminal::disable_raw_mode()?;
+    }
+    result?;
+
+    // ストリームが閉じた理由を判定（デタッチかプロセス終了か）
+    let still_running = docker_conn
+        .inspect_container(
+            &container_name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+        .ok()
+        .and_then(|i| i.state)
+        .and_then(|s| s.running)
+        .unwrap_or(false);
+
+    println!();
+    if still_running {
+        println!("{}", "✓ デタッチしました（コンテナは実行中です）".green());
+    } else {
+        println!("{}", "コンテナのプロセスが終了しました".yellow());
+    }
+
+    Ok(())
+}
+
+/// 標準入出力とコンテナのストリームを中継する
+async fn pump(
+    docker_conn: &Docker,
+    container_name: &str,
+    attached: bollard::container::AttachContainerResults,
+    forward_stdin: bool,
+    watch_resize: bool,
+) -> anyhow::Result<()> {
+    let bollard::container::AttachContainerResults {
+        mut output,
+        mut input,
+    } = attached;
+
+    // stdin → container
+    //
+    // tokio::io::stdin() はブロッキングプールで read するため、デタッチ後もタスクを
+    // abort できずランタイムの終了が次の入力まで止まる。専用スレッドで読んで
+    // チャネル経由で渡し、スレッドは切り離しておく（プロセスの終了を妨げない）。
+    let stdin_handle = forward_stdin.then(|| {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+        std::thread::spawn(move || {
+            let mut stdin = std::io::stdin();
+            let mut buf = [0u8; 1024];
+            loop {
+                match stdin.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(bytes) = rx.recv().await {
+                if input.write_all(&bytes).await.is_err() || input.flush().await.is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    // 端末サイズの変更をコンテナの TTY に反映
+    #[cfg(unix)]
+    let resize_handle = watch_resize.then(|| {
+        let docker_conn = docker_conn.clone();
+        let container_name = container_name.to_string();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{SignalKind, signal};
+            let Ok(mut winch) = signal(SignalKind::window_change()) else {
+                return;
+            };
+            while winch.recv().await.is_some() {
+                resize_tty(&docker_conn, &container_name).await;
+            }
+        })
+    });
+    #[cfg(not(unix))]
+    let _ = (docker_conn, container_name, watch_resize);
+
+    // container → stdout / stderr
+    let mut result = Ok(());
+    while let Some(chunk) = output.next().await {
+        match chunk {
+            Ok(bollard::container::LogOutput::StdErr { message }) => {
+                let mut stderr = std::io::stderr();
+                stderr.write_all(&message)?;
+                stderr.flush()?;
+            }
+            Ok(chunk) => {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(&chunk.into_bytes())?;
+                stdout.flush()?;
+            }
+            Err(e) => {
+                result = Err(e.into());
+                break;
+            }
+        }
+    }
+
+    if let Some(handle) = stdin_handle {
+        handle.abort();
+    }
+    #[cfg(unix)]
+    if let Some(handle) = resize_handle {
+        handle.abort();
+    }
+
+    result
+}
+
+/// ローカル端末のサイズをコンテナの TTY に合わせる
+async fn resize_tty(docker_conn: &Docker, container_name: &str) {
+    let Ok((width, height)) = crossterm::terminal::size() else {
+        return;
+    };
+    let options = bollard::query_parameters::ResizeContainerTTYOptions {
+        w: width.into(),
+        h: height.into(),
+    };
+    if let Err(e) = docker_conn
+        .resize_container_tty(container_name, options)
+        .await
+    {
+        tracing::debug!("TTY のリサイズに失敗しました: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_detach_keys() {
+        assert_eq!(describe_detach_keys(DEFAULT_DETACH_KEYS), "Ctrl+P, Ctrl+Q");
+        assert_eq!(describe_detach_keys("ctrl-x, q"), "Ctrl+X, q");
+    }
+}
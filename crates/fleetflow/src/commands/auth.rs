@@ -259,6 +259,15 @@ pub async fn handle_logout() -> Result<()> {
     Ok(())
 }
 
+/// 保存済みのアクセストークン（未ログイン・期限切れなら None）
+pub fn stored_access_token() -> Option<String> {
+    let content = std::fs::read_to_string(credentials_path().ok()?).ok()?;
+    let creds: Credentials = serde_json::from_str(&content).ok()?;
+    let expired = chrono::DateTime::parse_from_rfc3339(&creds.expires_at)
+        .is_ok_and(|expires| expires < chrono::Utc::now());
+    (!expired).then_some(creds.access_token)
+}
+
 /// `fleet auth status` — 認証状態確認
 pub async fn handle_auth_status() -> Result<()> {
     let path = credentials_path()?;
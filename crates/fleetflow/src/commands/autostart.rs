@@ -0,0 +1,396 @@
+//! `fleet autostart` — ログイン時に `fleet up --stage <stage>` を実行するよう登録
+//!
+//! macOS は launchd（~/Library/LaunchAgents）、Linux は systemd ユーザーユニット
+//! （~/.config/systemd/user）、Windows はタスク スケジューラ（ログオン時トリガー）を使う。
+//! 開発用の依存サービス（DB・キャッシュ等）を PC 起動後に自動で立ち上げるためのもの。
+
+use colored::Colorize;
+use fleetflow_core::Flow;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 登録先の仕組み
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Platform {
+    Launchd,
+    Systemd,
+    TaskScheduler,
+}
+
+impl Platform {
+    fn current() -> anyhow::Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(target_os = "windows") {
+            Ok(Self::TaskScheduler)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else {
+            Err(anyhow::anyhow!(
+                "この OS では autostart に対応していません（macOS / Linux / Windows のみ）"
+            ))
+        }
+    }
+}
+
+/// 登録名（`fleetflow-{project}-{stage}`）
+fn unit_name(project: &str, stage: &str) -> String {
+    format!("fleetflow-{}-{}", project, stage)
+}
+
+/// launchd のラベル
+fn launchd_label(project: &str, stage: &str) -> String {
+    format!("club.chronista.{}", unit_name(project, stage))
+}
+
+/// タスク スケジューラのタスク名
+fn task_name(project: &str, stage: &str) -> String {
+    format!("FleetFlow\\{}", unit_name(project, stage))
+}
+
+/// 出力の記録先（`fleet up` の結果を後から確認できるように）
+fn log_path(project_root: &Path) -> PathBuf {
+    project_root.join(".fleetflow").join("autostart.log")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn launchd_plist(label: &str, exe: &Path, project_root: &Path, stage: &str) -> String {
+    let args = [
+        exe.display().to_string(),
+        "up".to_string(),
+        "--stage".to_string(),
+        stage.to_string(),
+    ]
+    .iter()
+    .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+    .collect::<String>();
+    let root = xml_escape(&project_root.display().to_string());
+    let log = xml_escape(&log_path(project_root).display().to_string());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}    </array>
+    <key>WorkingDirectory</key>
+    <string>{root}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#
+    )
+}
+
+/// systemd のユーザーユニット
+///
+/// Docker デーモンの起動を待てないため、失敗時は 10 秒おきに再試行する。
+fn systemd_unit(project: &str, exe: &Path, project_root: &Path, stage: &str) -> String {
+    format!(
+        r#"[Unit]
+Description=FleetFlow {project} ({stage}) autostart
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+WorkingDirectory={root}
+ExecStart="{exe}" up --stage {stage}
+Restart=on-failure
+RestartSec=10
+
+[Install]
+WantedBy=default.target
+"#,
+        root = project_root.display(),
+        exe = exe.display(),
+    )
+}
+
+/// タスク スケジューラに登録するコマンドライン（作業ディレクトリを移動してから実行）
+fn task_command(exe: &Path, project_root: &Path, stage: &str) -> String {
+    format!(
+        r#"cmd /c cd /d "{}" && "{}" up --stage {} >> "{}" 2>&1"#,
+        project_root.display(),
+        exe.display(),
+        stage,
+        log_path(project_root).display()
+    )
+}
+
+fn launch_agent_path(label: &str) -> anyhow::Result<PathBuf> {
+    let home =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("ホームディレクトリが見つかりません"))?;
+    Ok(home
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{label}.plist")))
+}
+
+fn systemd_unit_path(name: &str) -> anyhow::Result<PathBuf> {
+    let config =
+        dirs::config_dir().ok_or_else(|| anyhow::anyhow!("設定ディレクトリが見つかりません"))?;
+    Ok(config
+        .join("systemd")
+        .join("user")
+        .join(format!("{name}.service")))
+}
+
+/// コマンドを実行し、失敗したら stderr を含めてエラーにする
+fn run(program: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("{} の実行に失敗しました: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} {} が失敗しました: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 成功したかどうかだけを見る（状態確認用）
+fn succeeds(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+fn validate_stage(config: &Flow, stage: Option<String>) -> anyhow::Result<String> {
+    let stage_name = crate::utils::determine_stage_name(stage, config)?;
+    if !config.stages.contains_key(&stage_name) {
+        return Err(anyhow::anyhow!(
+            "ステージ '{}' が見つかりません",
+            stage_name
+        ));
+    }
+    Ok(stage_name)
+}
+
+/// ログイン時の自動起動を登録する
+pub fn enable(config: &Flow, project_root: &Path, stage: Option<String>) -> anyhow::Result<()> {
+    let stage_name = validate_stage(config, stage)?;
+    let exe = std::env::current_exe()?;
+    let name = unit_name(&config.name, &stage_name);
+    std::fs::create_dir_all(project_root.join(".fleetflow"))?;
+
+    match Platform::current()? {
+        Platform::Launchd => {
+            let label = launchd_label(&config.name, &stage_name);
+            let path = launch_agent_path(&label)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let path_str = path.display().to_string();
+            // 再登録時は古い定義を外してから読み込む
+            let _ = succeeds("launchctl", &["unload", &path_str]);
+            std::fs::write(
+                &path,
+                launchd_plist(&label, &exe, project_root, &stage_name),
+            )?;
+            run("launchctl", &["load", "-w", &path_str])?;
+            println!("  ✓ {}", path_str.cyan());
+        }
+        Platform::Systemd => {
+            let path = systemd_unit_path(&name)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(
+                &path,
+                systemd_unit(&config.name, &exe, project_root, &stage_name),
+            )?;
+            run("systemctl", &["--user", "daemon-reload"])?;
+            run(
+                "systemctl",
+                &["--user", "enable", &format!("{name}.service")],
+            )?;
+            println!("  ✓ {}", path.display().to_string().cyan());
+        }
+        Platform::TaskScheduler => {
+            let task = task_name(&config.name, &stage_name);
+            let command = task_command(&exe, project_root, &stage_name);
+            run(
+                "schtasks",
+                &[
+                    "/Create", "/TN", &task, "/TR", &command, "/SC", "ONLOGON", "/F",
+                ],
+            )?;
+            println!("  ✓ タスク: {}", task.cyan());
+        }
+    }
+
+    println!(
+        "{}",
+        format!(
+            "✓ ログイン時に fleet up --stage {} を実行するよう登録しました",
+            stage_name
+        )
+        .green()
+        .bold()
+    );
+    println!("  ログ: {}", log_path(project_root).display());
+    Ok(())
+}
+
+/// 自動起動の登録を解除する
+pub fn disable(config: &Flow, stage: Option<String>) -> anyhow::Result<()> {
+    let stage_name = validate_stage(config, stage)?;
+    let name = unit_name(&config.name, &stage_name);
+
+    let removed = match Platform::current()? {
+        Platform::Launchd => {
+            let path = launch_agent_path(&launchd_label(&config.name, &stage_name))?;
+            if path.exists() {
+                let _ = succeeds("launchctl", &["unload", "-w", &path.display().to_string()]);
+                std::fs::remove_file(&path)?;
+                true
+            } else {
+                false
+            }
+        }
+        Platform::Systemd => {
+            let path = systemd_unit_path(&name)?;
+            if path.exists() {
+                let _ = succeeds(
+                    "systemctl",
+                    &["--user", "disable", &format!("{name}.service")],
+                );
+                std::fs::remove_file(&path)?;
+                let _ = succeeds("systemctl", &["--user", "daemon-reload"]);
+                true
+            } else {
+                false
+            }
+        }
+        Platform::TaskScheduler => {
+            let task = task_name(&config.name, &stage_name);
+            succeeds("schtasks", &["/Delete", "/TN", &task, "/F"])
+        }
+    };
+
+    if removed {
+        println!(
+            "{}",
+            format!("✓ ステージ '{}' の自動起動を解除しました", stage_name).green()
+        );
+    } else {
+        println!(
+            "{}",
+            format!("ステージ '{}' の自動起動は登録されていません", stage_name).yellow()
+        );
+    }
+    Ok(())
+}
+
+/// 各ステージの登録状態を表示する
+pub fn status(config: &Flow) -> anyhow::Result<()> {
+    let platform = Platform::current()?;
+    let mut stages: Vec<&String> = config.stages.keys().collect();
+    stages.sort();
+
+    println!("{:<16} {:<10} {}", "STAGE", "STATUS", "UNIT");
+    for stage_name in stages {
+        let name = unit_name(&config.name, stage_name);
+        let (enabled, unit) = match platform {
+            Platform::Launchd => {
+                let label = launchd_label(&config.name, stage_name);
+                let path = launch_agent_path(&label)?;
+                (path.exists(), path.display().to_string())
+            }
+            Platform::Systemd => {
+                let unit = format!("{name}.service");
+                (
+                    succeeds("systemctl", &["--user", "is-enabled", "--quiet", &unit]),
+                    unit,
+                )
+            }
+            Platform::TaskScheduler => {
+                let task = task_name(&config.name, stage_name);
+                (succeeds("schtasks", &["/Query", "/TN", &task]), task)
+            }
+        };
+        let state = if enabled {
+            "enabled".green()
+        } else {
+            "disabled".dimmed()
+        };
+        println!("{:<16} {:<10} {}", stage_name, state, unit);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = launchd_plist(
+            "club.chronista.fleetflow-myapp-local",
+            Path::new("/usr/local/bin/fleet"),
+            Path::new("/Users/dev/R&D/myapp"),
+            "local",
+        );
+        assert!(plist.contains("<string>club.chronista.fleetflow-myapp-local</string>"));
+        assert!(plist.contains(
+            "        <string>/usr/local/bin/fleet</string>\n        <string>up</string>\n        <string>--stage</string>\n        <string>local</string>\n"
+        ));
+        assert!(plist.contains("<string>/Users/dev/R&amp;D/myapp</string>"));
+        assert!(
+            plist.contains("<string>/Users/dev/R&amp;D/myapp/.fleetflow/autostart.log</string>")
+        );
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let unit = systemd_unit(
+            "myapp",
+            Path::new("/home/dev/.cargo/bin/fleet"),
+            Path::new("/home/dev/my app"),
+            "local",
+        );
+        assert!(unit.contains("WorkingDirectory=/home/dev/my app\n"));
+        assert!(unit.contains("ExecStart=\"/home/dev/.cargo/bin/fleet\" up --stage local\n"));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_task_command() {
+        assert_eq!(
+            task_name("myapp", "local"),
+            "FleetFlow\\fleetflow-myapp-local"
+        );
+        assert_eq!(
+            task_command(
+                Path::new("C:\\tools\\fleet.exe"),
+                Path::new("C:\\src\\myapp"),
+                "local"
+            ),
+            format!(
+                r#"cmd /c cd /d "C:\src\myapp" && "C:\tools\fleet.exe" up --stage local >> "{}" 2>&1"#,
+                log_path(Path::new("C:\\src\\myapp")).display()
+            )
+        );
+    }
+}
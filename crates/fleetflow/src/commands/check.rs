@@ -0,0 +1,280 @@
+//! `fleet up --check` — 読み取り専用の監査モード
+//!
+//! `fleet up` が行う冪等チェック（サーバー定義、Docker 接続、ネットワーク、
+//! イメージ、ボリュームのホストパス、コンテナの稼働とイメージ）を変更なしで実行し、
+//! 差分をレポートする。差分があれば非ゼロ終了するため cron でのドリフト検知に使える。
+
+use crate::docker;
+use colored::Colorize;
+use fleetflow_core::{Flow, Stage};
+use serde::Serialize;
+use std::path::Path;
+
+/// 1 項目のチェック結果
+#[derive(Serialize)]
+pub(crate) struct Finding {
+    pub(crate) step: &'static str,
+    pub(crate) target: String,
+    /// 差分の内容（None なら問題なし）
+    pub(crate) gap: Option<String>,
+}
+
+impl Finding {
+    pub(crate) fn print(&self) {
+        match &self.gap {
+            None => println!("  {} [{}] {}", "✓".green(), self.step, self.target),
+            Some(gap) => println!(
+                "  {} [{}] {}: {}",
+                "✗".red(),
+                self.step,
+                self.target,
+                gap.red()
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Report {
+    pub(crate) findings: Vec<Finding>,
+}
+
+impl Report {
+    pub(crate) fn record(
+        &mut self,
+        step: &'static str,
+        target: impl Into<String>,
+        gap: Option<String>,
+    ) {
+        self.findings.push(Finding {
+            step,
+            target: target.into(),
+            gap,
+        });
+    }
+
+    pub(crate) fn gaps(&self) -> usize {
+        self.findings.iter().filter(|f| f.gap.is_some()).count()
+    }
+}
+
+/// ステージのサーバー定義（存在と接続先）をチェック
+fn check_servers(config: &Flow, stage: &Stage, report: &mut Report) {
+    for server_name in &stage.servers {
+        let gap = match config.servers.get(server_name) {
+            None => Some("サーバー定義がありません".to_string()),
+            Some(server) if server.ssh_host.is_none() => Some("ssh_host が未設定です".to_string()),
+            Some(_) => None,
+        };
+        report.record("server", server_name, gap);
+    }
+}
+
+/// bind mount するホストパスの存在をチェック
+fn check_volumes(project_root: &Path, config: &Flow, services: &[&String], report: &mut Report) {
+    for service_name in services {
+        let Some(service) = config.services.get(*service_name) else {
+            continue;
+        };
+        for volume in &service.volumes {
+            let host = if volume.host.is_absolute() {
+                volume.host.clone()
+            } else {
+                project_root.join(&volume.host)
+            };
+            let gap = (!host.exists()).then(|| "ホストパスが存在しません".to_string());
+            report.record("volume", host.display().to_string(), gap);
+        }
+    }
+}
+
+/// ステージの現状と fleet.kdl の差分を集める（出力・変更はしない）
+pub(crate) async fn audit(
+    config: &Flow,
+    project_root: &Path,
+    stage_name: &str,
+) -> anyhow::Result<Report> {
+    let stage_config = config
+        .stages
+        .get(stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    if stage_config.backend != fleetflow_core::Backend::Docker {
+        return Err(anyhow::anyhow!(
+            "--check は docker backend のステージのみ対応しています（'{}' は {}）",
+            stage_name,
+            stage_config.backend.as_str()
+        ));
+    }
+
+    let mut report = Report::default();
+    for error in config.validate_stage(stage_name) {
+        report.record("config", "fleet.kdl", Some(error));
+    }
+    check_servers(config, stage_config, &mut report);
+
+    let services: Vec<&String> = stage_config
+        .services
+        .iter()
+        .filter(|name| config.services.get(*name).is_some_and(|s| !s.is_static()))
+        .collect();
+    check_volumes(project_root, config, &services, &mut report);
+
+    match docker::init_docker_with_error_handling().await {
+        Err(e) => report.record("docker", "daemon", Some(e.to_string())),
+        Ok(docker_conn) => {
+            report.record("docker", "daemon", None);
+
+            let network_name = fleetflow_container::get_network_name(&config.name, stage_name);
+            let gap = docker_conn
+                .inspect_network(
+                    &network_name,
+                    None::<bollard::query_parameters::InspectNetworkOptions>,
+                )
+                .await
+                .err()
+                .map(|_| "ネットワークがありません".to_string());
+            report.record("network", network_name, gap);
+
+            for service_name in services {
+                let Some(image) = config
+                    .services
+                    .get(service_name)
+                    .and_then(|s| s.image.as_deref())
+                else {
+                    continue;
+                };
+
+                let gap = docker_conn
+                    .inspect_image(image)
+                    .await
+                    .err()
+                    .map(|_| "イメージがローカルにありません".to_string());
+                report.record("image", image, gap);
+
+                // コンテナはラベルで探す（命名規則が変わっても追跡できるように）
+                let found = fleetflow_container::find_containers(
+                    &docker_conn,
+                    &config.name,
+                    Some(stage_name),
+                    Some(service_name),
+                )
+                .await
+                .ok()
+                .and_then(|containers| containers.into_iter().next());
+                let container_name = found
+                    .as_ref()
+                    .and_then(fleetflow_container::summary_name)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| config.container_name(stage_name, service_name));
+                let info = match found.and_then(|c| c.id) {
+                    Some(id) => docker_conn
+                        .inspect_container(
+                            &id,
+                            None::<bollard::query_parameters::InspectContainerOptions>,
+                        )
+                        .await
+                        .ok(),
+                    None => None,
+                };
+                let gap = match info {
+                    None => Some("コンテナがありません".to_string()),
+                    Some(info) => {
+                        let running = info.state.as_ref().and_then(|s| s.running).unwrap_or(false);
+                        let current_image = info.config.as_ref().and_then(|c| c.image.as_deref());
+                        if !running {
+                            Some("停止しています".to_string())
+                        } else if current_image != Some(image) {
+                            Some(format!(
+                                "イメージが異なります（実行中: {}）",
+                                current_image.unwrap_or("不明")
+                            ))
+                        } else {
+                            None
+                        }
+                    }
+                };
+                report.record("container", container_name, gap);
+            }
+        }
+    }
+    Ok(report)
+}
+
+pub async fn handle(
+    config: &Flow,
+    project_root: &Path,
+    stage: Option<String>,
+) -> anyhow::Result<()> {
+    let stage_name = crate::utils::determine_stage_name(stage, config)?;
+    println!(
+        "{}",
+        format!("ステージ '{}' を監査中（変更は行いません）...", stage_name)
+            .blue()
+            .bold()
+    );
+    println!();
+
+    let report = audit(config, project_root, &stage_name).await?;
+    for finding in &report.findings {
+        finding.print();
+    }
+
+    println!();
+    let gaps = report.gaps();
+    let total = report.findings.len();
+    if gaps == 0 {
+        println!(
+            "{}",
+            format!("✓ {} 項目すべて一致しています", total)
+                .green()
+                .bold()
+        );
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} 項目中 {} 件の差分があります（fleet up で解消できます）",
+            total,
+            gaps
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::ServerResource;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_check_servers_reports_missing_and_unreachable() {
+        let config = Flow {
+            name: "myapp".to_string(),
+            servers: HashMap::from([
+                (
+                    "vps-1".to_string(),
+                    ServerResource {
+                        ssh_host: Some("10.0.0.1".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                ("vps-2".to_string(), ServerResource::default()),
+            ]),
+            ..Default::default()
+        };
+        let stage = Stage {
+            servers: vec![
+                "vps-1".to_string(),
+                "vps-2".to_string(),
+                "vps-3".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let mut report = Report::default();
+        check_servers(&config, &stage, &mut report);
+        assert_eq!(report.findings.len(), 3);
+        assert_eq!(report.gaps(), 2);
+        assert!(report.findings[0].gap.is_none());
+    }
+}
@@ -0,0 +1,900 @@
+//! `fleet cloud` — クラウドリソース（サーバー・DNS・静的サイト）の操作
+//!
+//! `up` は fleet.kdl の `site` をビルドして R2 バケットにアップロードし、
+//! `domain` をバケットのカスタムドメインとして接続する（DNS レコードと証明書は Cloudflare が作成）。
+//!
+//! `status` はプロバイダーに問い合わせた実際の電源状態・IP・ディスク・DNS レコードを表示し、
+//! 定義だけあって存在しないサーバーと、プロジェクトのタグが付いているのに未宣言のサーバーを示す。
+//!
+//! `drift` はプロバイダー上の実状態（プラン・ディスクサイズ・電源・タグ・DNS レコード）を
+//! fleet.kdl の `server` 定義と比較し、コンソールなどで手動変更された差分を報告する。
+//! 定期実行向けに `--exit-code`（差分があれば非ゼロ終了）と `--webhook`（差分を POST）を持つ。
+
+use crate::commands::check::Report;
+use colored::Colorize;
+use fleetflow_cloud::server_provider::ServerProvider;
+use fleetflow_cloud::{DnsProvider, DnsRecordSpec};
+use fleetflow_cloud::{ServerSpec, ServerStatus};
+use fleetflow_cloud_cloudflare::{CloudflareApi, DnsRecordInfo, DnsZones, RecordType};
+use fleetflow_cloud_sakura::{CreateServerConfig, SakuraCloudProvider};
+use fleetflow_core::{DnsRecordOptions, Flow, ServerResource, SiteResource};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// ゾーン未指定時のさくらのクラウドのゾーン
+const DEFAULT_SAKURA_ZONE: &str = "tk1a";
+
+/// サイトのアップロードの並列数
+const SITE_UPLOAD_JOBS: usize = 8;
+
+/// プロバイダーが付けるタグ（fleetflow:<project>:<server> など）は比較しない
+fn is_managed_tag(tag: &str) -> bool {
+    tag.starts_with("fleetflow:")
+}
+
+fn display_opt(value: Option<i32>) -> String {
+    value.map_or_else(|| "?".to_string(), |v| v.to_string())
+}
+
+/// サーバー定義のさくらのクラウドのゾーン
+fn sakura_zone(config: &Flow, server: &ServerResource) -> String {
+    config
+        .providers
+        .get(&server.provider)
+        .and_then(|p| p.zone.clone())
+        .unwrap_or_else(|| DEFAULT_SAKURA_ZONE.to_string())
+}
+
+/// ゾーンのサーバー一覧（取得済みのゾーンは再取得しない）
+async fn zone_servers<'a>(
+    cache: &'a mut HashMap<String, Vec<ServerSpec>>,
+    zone: &str,
+) -> anyhow::Result<&'a [ServerSpec]> {
+    if !cache.contains_key(zone) {
+        let servers = SakuraCloudProvider::new(zone)
+            .list_servers()
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("ゾーン {} のサーバー一覧を取得できません: {}", zone, e)
+            })?;
+        cache.insert(zone.to_string(), servers);
+    }
+    Ok(&cache[zone])
+}
+
+/// 作成時に付けたタグで探し、見つからなければ名前で探す
+fn find_server<'a>(servers: &'a [ServerSpec], project: &str, name: &str) -> Option<&'a ServerSpec> {
+    let tag = CreateServerConfig::fleetflow_tags(project, name)
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    servers
+        .iter()
+        .find(|s| s.tags.contains(&tag))
+        .or_else(|| servers.iter().find(|s| s.name == name))
+}
+
+/// プロジェクトのタグ（`fleetflow:<project>:<server>`）が付いたサーバーの定義名
+fn tagged_server_name<'a>(spec: &'a ServerSpec, project: &str) -> Option<&'a str> {
+    let prefix = format!("fleetflow:{}:", project);
+    spec.tags.iter().find_map(|t| t.strip_prefix(&prefix))
+}
+
+/// サーバーの実状態と定義を比較する（項目名, 差分）
+fn compare_server(
+    desired: &ServerResource,
+    actual: &ServerSpec,
+) -> Vec<(&'static str, Option<String>)> {
+    let mut results = Vec::new();
+
+    if let Some(plan) = &desired.plan {
+        let (cpu, memory_gb) = fleetflow_cloud_sakura::parse_plan(&desired.plan);
+        let gap = (actual.cpu != Some(cpu) || actual.memory_gb != Some(memory_gb)).then(|| {
+            format!(
+                "プランが異なります（定義: {}、実際: {}core-{}gb）",
+                plan,
+                display_opt(actual.cpu),
+                display_opt(actual.memory_gb)
+            )
+        });
+        results.push(("plan", gap));
+    }
+
+    // ディスク情報を返さないプロバイダーでは比較しない
+    if let (Some(disk_size), Some(actual_disk)) = (desired.disk_size, actual.disk_gb) {
+        let gap = (actual_disk != disk_size as i32).then(|| {
+            format!(
+                "ディスクサイズが異なります（定義: {}GB、実際: {}GB）",
+                disk_size, actual_disk
+            )
+        });
+        results.push(("disk", gap));
+    }
+
+    let gap =
+        (actual.status != ServerStatus::Running).then(|| format!("電源が {} です", actual.status));
+    results.push(("power", gap));
+
+    let actual_tags: Vec<&String> = actual.tags.iter().filter(|t| !is_managed_tag(t)).collect();
+    let missing: Vec<&str> = desired
+        .tags
+        .iter()
+        .filter(|t| !actual_tags.contains(t))
+        .map(String::as_str)
+        .collect();
+    let extra: Vec<&str> = actual_tags
+        .iter()
+        .filter(|t| !desired.tags.contains(**t))
+        .map(|t| t.as_str())
+        .collect();
+    let gap = (!missing.is_empty() || !extra.is_empty()).then(|| {
+        let mut parts = Vec::new();
+        if !missing.is_empty() {
+            parts.push(format!("不足: {}", missing.join(", ")));
+        }
+        if !extra.is_empty() {
+            parts.push(format!("定義にない: {}", extra.join(", ")));
+        }
+        format!("タグが異なります（{}）", parts.join(" / "))
+    });
+    results.push(("tags", gap));
+
+    results
+}
+
+/// 宣言された TTL / プロキシとレコードの実際の設定を比較する（未宣言の項目は比較しない）
+fn options_gap(declared: &DnsRecordOptions, record: &DnsRecordInfo) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(proxied) = declared.proxied
+        && record.proxied != proxied
+    {
+        parts.push(format!(
+            "プロキシ: {}（定義: {}）",
+            if record.proxied { "on" } else { "off" },
+            if proxied { "on" } else { "off" }
+        ));
+    }
+    // プロキシ経由のレコードの TTL は常に自動
+    if let Some(ttl) = declared.ttl
+        && !record.proxied
+        && record.ttl != Some(ttl)
+    {
+        parts.push(format!(
+            "TTL: {}（定義: {}）",
+            record
+                .ttl
+                .map_or_else(|| "?".to_string(), |t| t.to_string()),
+            ttl
+        ));
+    }
+    (!parts.is_empty()).then(|| parts.join("、"))
+}
+
+/// サーバーの A レコード・dns_aliases の CNAME・追加レコードを確認する
+///
+/// レコードはサーバーのドメイン（`domain`）を含むゾーンで探す。
+async fn check_dns(
+    zones: &DnsZones,
+    domain: &str,
+    name: &str,
+    server: &ServerResource,
+    ip: Option<&str>,
+    report: &mut Report,
+) -> anyhow::Result<()> {
+    let host = format!("{}.{}", name, domain);
+    let Some(dns) = zones.zone_for(&host) else {
+        report.record(
+            "dns",
+            host,
+            Some(format!(
+                "{} を含むゾーンが cloudflare provider に宣言されていません",
+                domain
+            )),
+        );
+        return Ok(());
+    };
+    let relative = |fqdn: &str| dns.relative_name(fqdn).unwrap_or_default();
+
+    let gap = match (dns.find_record(&relative(&host)).await?, ip) {
+        (None, _) => Some("A レコードがありません".to_string()),
+        (Some(record), Some(ip)) if record.content != ip => Some(format!(
+            "{} を指しています（サーバーの IP: {}）",
+            record.content, ip
+        )),
+        (Some(record), _) => options_gap(&server.dns_options, &record),
+    };
+    report.record("dns", host.clone(), gap);
+
+    for alias in &server.dns_aliases {
+        let alias_host = format!("{}.{}", alias, domain);
+        let gap = match dns.find_cname_record(&relative(&alias_host)).await? {
+            None => Some("CNAME レコードがありません".to_string()),
+            Some(record) if record.content != host => Some(format!(
+                "{} を指しています（定義: {}）",
+                record.content, host
+            )),
+            Some(record) => options_gap(&server.alias_options(alias), &record),
+        };
+        report.record("dns", alias_host, gap);
+    }
+
+    for record_def in &server.dns_records {
+        let record_host = format!("{}.{}", record_def.name.as_deref().unwrap_or(name), domain);
+        let record_type: RecordType = record_def.record_type.parse()?;
+        let records = dns
+            .find_records(record_type, &relative(&record_host))
+            .await?;
+        let gap = match records.iter().find(|r| r.content == record_def.content) {
+            Some(record) => options_gap(&record_def.options, record),
+            None if records.is_empty() => Some(format!("{} レコードがありません", record_type)),
+            None => Some(format!(
+                "{} レコードの値が異なります（定義: {}、実際: {}）",
+                record_type,
+                record_def.content,
+                records
+                    .iter()
+                    .map(|r| r.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        };
+        report.record("dns", format!("{} {}", record_host, record_type), gap);
+    }
+    Ok(())
+}
+
+/// 差分を Webhook に POST する
+async fn notify(url: &str, payload: &serde_json::Value) -> anyhow::Result<()> {
+    reqwest::Client::new()
+        .post(url)
+        .json(payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// `fleet cloud status` の 1 行
+#[derive(Debug, Serialize)]
+struct ServerRow {
+    name: String,
+    /// declared（定義どおり存在）/ missing（定義のみ）/ undeclared（未宣言）/ unsupported
+    state: &'static str,
+    status: Option<String>,
+    ip: Option<String>,
+    plan: Option<String>,
+    disk_gb: Option<i32>,
+    zone: Option<String>,
+    dns: Vec<String>,
+}
+
+impl ServerRow {
+    fn new(name: &str, state: &'static str, actual: Option<&ServerSpec>) -> Self {
+        Self {
+            name: name.to_string(),
+            state,
+            status: actual.map(|s| s.status.to_string()),
+            ip: actual.and_then(|s| s.ip_address.clone()),
+            plan: actual.and_then(|s| Some(format!("{}core-{}gb", s.cpu?, s.memory_gb?))),
+            disk_gb: actual.and_then(|s| s.disk_gb),
+            zone: actual.and_then(|s| s.zone.clone()),
+            dns: Vec::new(),
+        }
+    }
+}
+
+/// サーバーを指している DNS レコード（ホスト名・エイリアス・管理タグ・IP で判定）
+fn server_records(
+    records: &[DnsRecordSpec],
+    name: &str,
+    domain: &str,
+    server: Option<&ServerResource>,
+    ip: Option<&str>,
+) -> Vec<String> {
+    let host = format!("{}.{}", name, domain);
+    let aliases: Vec<String> = server
+        .map(|s| {
+            s.dns_aliases
+                .iter()
+                .map(|a| format!("{}.{}", a, domain))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut found: Vec<String> = records
+        .iter()
+        .filter(|r| {
+            r.name == host
+                || aliases.contains(&r.name)
+                || r.managed_by().and_then(|(_, s)| s) == Some(name)
+                || (ip.is_some() && Some(r.content.as_str()) == ip)
+                || (r.record_type == "CNAME" && r.content == host)
+        })
+        .map(|r| format!("{} {}", r.record_type, r.name))
+        .collect();
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// `fleet cloud status`
+pub async fn handle_status(config: &Flow, stage: Option<&str>, json: bool) -> anyhow::Result<()> {
+    let mut server_names: Vec<&String> = match stage {
+        Some(stage_name) => config
+            .stages
+            .get(stage_name)
+            .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?
+            .servers
+            .iter()
+            .collect(),
+        None => config.servers.keys().collect(),
+    };
+    server_names.sort();
+
+    // DNS は Cloudflare の設定が揃っているときだけ取得する
+    let dns_zones = crate::dns::zones(config, stage).await.unwrap_or_default();
+    let mut dns_records: HashMap<String, Vec<DnsRecordSpec>> = HashMap::new();
+    for zone in dns_zones.iter() {
+        match zone.list_dns_records().await {
+            Ok(records) => {
+                dns_records.insert(zone.domain().to_string(), records);
+            }
+            Err(e) if !json => println!("  {} [dns] {}: {}", "⚠".yellow(), zone.domain(), e),
+            Err(_) => {}
+        }
+    }
+    let records_for = |domain: &str| -> &[DnsRecordSpec] {
+        dns_zones
+            .zone_for(domain)
+            .and_then(|z| dns_records.get(z.domain()))
+            .map_or(&[], |r| r.as_slice())
+    };
+
+    let mut cache: HashMap<String, Vec<ServerSpec>> = HashMap::new();
+    let mut rows = Vec::new();
+    let mut matched_ids = BTreeSet::new();
+
+    for name in &server_names {
+        let Some(server) = config.servers.get(*name) else {
+            rows.push(ServerRow::new(name, "missing", None));
+            continue;
+        };
+        if server.provider != "sakura-cloud" {
+            rows.push(ServerRow::new(name, "unsupported", None));
+            continue;
+        }
+        let zone = sakura_zone(config, server);
+        let servers = zone_servers(&mut cache, &zone).await?;
+        let actual = find_server(servers, &config.name, name);
+        let mut row = ServerRow::new(
+            name,
+            if actual.is_some() {
+                "declared"
+            } else {
+                "missing"
+            },
+            actual,
+        );
+        if let Some(actual) = actual {
+            matched_ids.insert(actual.id.clone());
+        }
+        if let Some(domain) = crate::dns::server_domain(config, stage, server) {
+            row.dns = server_records(
+                records_for(&domain),
+                name,
+                &domain,
+                Some(server),
+                row.ip.as_deref(),
+            );
+        }
+        rows.push(row);
+    }
+
+    // 未宣言: プロジェクトのタグが付いているが fleet.kdl（対象ステージ）にないサーバー
+    if let Some(provider) = config.providers.get("sakura-cloud") {
+        let zone = provider
+            .zone
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SAKURA_ZONE.to_string());
+        zone_servers(&mut cache, &zone).await?;
+    }
+    let mut undeclared: Vec<(&str, &ServerSpec)> = cache
+        .values()
+        .flatten()
+        .filter(|spec| !matched_ids.contains(&spec.id))
+        .filter_map(|spec| Some((tagged_server_name(spec, &config.name)?, spec)))
+        .filter(|(name, _)| !config.servers.contains_key(*name))
+        .collect();
+    undeclared.sort_by_key(|(name, _)| *name);
+    for (name, spec) in undeclared {
+        let mut row = ServerRow::new(name, "undeclared", Some(spec));
+        if let Some(domain) = crate::dns::resolve_domain(config, stage) {
+            row.dns = server_records(records_for(&domain), name, &domain, None, row.ip.as_deref());
+        }
+        rows.push(row);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("対象のサーバー定義がありません");
+        return Ok(());
+    }
+
+    println!(
+        "{:<2}{:<16} {:<10} {:<16} {:<12} {:<6} {}",
+        "",
+        "SERVER".bold(),
+        "STATUS".bold(),
+        "IP".bold(),
+        "PLAN".bold(),
+        "DISK".bold(),
+        "DNS".bold()
+    );
+    for row in &rows {
+        let marker = match row.state {
+            "declared" => "✓".green(),
+            "missing" => "✗".red(),
+            "undeclared" => "⚠".yellow(),
+            _ => "-".dimmed(),
+        };
+        let status = match row.state {
+            "missing" => "未作成".red().to_string(),
+            "unsupported" => "未対応".dimmed().to_string(),
+            _ => match row.status.as_deref() {
+                Some("running") => "running".green().to_string(),
+                Some(status) => status.yellow().to_string(),
+                None => "-".to_string(),
+            },
+        };
+        println!(
+            "{:<2}{:<16} {:<10} {:<16} {:<12} {:<6} {}",
+            marker,
+            row.name.cyan(),
+            status,
+            row.ip.as_deref().unwrap_or("-"),
+            row.plan.as_deref().unwrap_or("-"),
+            row.disk_gb
+                .map_or_else(|| "-".to_string(), |d| format!("{}GB", d)),
+            if row.dns.is_empty() {
+                "-".to_string()
+            } else {
+                row.dns.join(", ")
+            }
+        );
+    }
+
+    let missing = rows.iter().filter(|r| r.state == "missing").count();
+    let undeclared = rows.iter().filter(|r| r.state == "undeclared").count();
+    if missing > 0 || undeclared > 0 {
+        println!();
+        if missing > 0 {
+            println!(
+                "  {} 定義のみでプロバイダーに存在しないサーバー: {} 台",
+                "✗".red(),
+                missing
+            );
+        }
+        if undeclared > 0 {
+            println!(
+                "  {} プロジェクトのタグがあるが fleet.kdl に宣言されていないサーバー: {} 台",
+                "⚠".yellow(),
+                undeclared
+            );
+        }
+    }
+    if dns_zones.is_empty() {
+        println!(
+            "  {} [dns] cloudflare provider（zone_id / domain / api_token）または CLOUDFLARE_* が未設定のため表示しません",
+            "-".dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `fleet cloud drift`
+pub async fn handle_drift(
+    config: &Flow,
+    stage: Option<&str>,
+    webhook: Option<&str>,
+    exit_code: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut server_names: Vec<&String> = match stage {
+        Some(stage_name) => config
+            .stages
+            .get(stage_name)
+            .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?
+            .servers
+            .iter()
+            .collect(),
+        None => config.servers.keys().collect(),
+    };
+    server_names.sort();
+    if server_names.is_empty() {
+        println!("対象のサーバー定義がありません");
+        return Ok(());
+    }
+
+    if !json {
+        println!(
+            "{}",
+            "クラウドリソースの差分を確認中（変更は行いません）..."
+                .blue()
+                .bold()
+        );
+        println!();
+    }
+
+    // DNS は Cloudflare の設定（fleet.kdl または環境変数）が揃っているときだけ確認する
+    let dns_zones = match crate::dns::zones(config, stage).await {
+        Ok(zones) => zones,
+        Err(e) => {
+            if !json {
+                println!("  {} [dns] {}", "⚠".yellow(), e);
+            }
+            DnsZones::default()
+        }
+    };
+    let mut report = Report::default();
+    let mut zones: HashMap<String, Vec<ServerSpec>> = HashMap::new();
+
+    for name in server_names {
+        let Some(server) = config.servers.get(name) else {
+            report.record("server", name, Some("サーバー定義がありません".to_string()));
+            continue;
+        };
+        if server.provider != "sakura-cloud" {
+            if !json {
+                println!(
+                    "  {} [server] {}: provider '{}' は差分確認に未対応です",
+                    "-".dimmed(),
+                    name,
+                    server.provider
+                );
+            }
+            continue;
+        }
+
+        let zone = sakura_zone(config, server);
+        let servers = zone_servers(&mut zones, &zone).await?;
+        let Some(actual) = find_server(servers, &config.name, name) else {
+            report.record(
+                "server",
+                name,
+                Some(format!("ゾーン {} にサーバーがありません", zone)),
+            );
+            continue;
+        };
+
+        for (field, gap) in compare_server(server, actual) {
+            report.record("server", format!("{} {}", name, field), gap);
+        }
+        if !dns_zones.is_empty()
+            && let Some(domain) = crate::dns::server_domain(config, stage, server)
+        {
+            check_dns(
+                &dns_zones,
+                &domain,
+                name,
+                server,
+                actual.ip_address.as_deref(),
+                &mut report,
+            )
+            .await?;
+        }
+    }
+
+    let gaps = report.gaps();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report.findings)?);
+    } else {
+        for finding in &report.findings {
+            finding.print();
+        }
+        if dns_zones.is_empty() {
+            println!(
+                "  {} [dns] cloudflare provider（zone_id / domain / api_token）または CLOUDFLARE_* が未設定のため確認しません",
+                "-".dimmed()
+            );
+        }
+        println!();
+        if gaps == 0 {
+            println!(
+                "{}",
+                format!("✓ {} 項目すべて一致しています", report.findings.len())
+                    .green()
+                    .bold()
+            );
+        } else {
+            println!(
+                "{}",
+                format!(
+                    "✗ {} 項目中 {} 件の差分があります",
+                    report.findings.len(),
+                    gaps
+                )
+                .red()
+                .bold()
+            );
+        }
+    }
+
+    if gaps > 0 {
+        if let Some(url) = webhook {
+            let drift: Vec<_> = report.findings.iter().filter(|f| f.gap.is_some()).collect();
+            let payload = serde_json::json!({
+                "project": config.name,
+                "stage": stage,
+                "drift": drift,
+            });
+            match notify(url, &payload).await {
+                Ok(()) if !json => println!("Webhook に通知しました"),
+                Ok(()) => {}
+                Err(e) => eprintln!("{} Webhook に通知できません: {}", "⚠".yellow(), e),
+            }
+        }
+        if exit_code {
+            return Err(anyhow::anyhow!(
+                "クラウドリソースに {} 件の差分があります",
+                gaps
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// サイトのビルドコマンドを実行する
+async fn build_site(project_root: &Path, name: &str, site: &SiteResource) -> anyhow::Result<()> {
+    let Some(build) = &site.build else {
+        return Ok(());
+    };
+    let dir = project_root.join(site.dir.as_deref().unwrap_or("."));
+    println!("  {} {}", "$".dimmed(), build);
+    let status = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(build)
+        .current_dir(&dir)
+        .status()
+        .await
+        .map_err(|e| anyhow::anyhow!("site '{}' のビルドを実行できません: {}", name, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "site '{}' のビルドが失敗しました（{}）",
+            name,
+            status
+        ));
+    }
+    Ok(())
+}
+
+/// `fleet cloud up`
+pub async fn handle_up(
+    config: &Flow,
+    project_root: &Path,
+    stage: Option<&str>,
+    only: Option<&str>,
+    skip_build: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let mut sites: Vec<(&String, &SiteResource)> = config
+        .sites
+        .iter()
+        .filter(|(name, _)| only.is_none_or(|only| only == name.as_str()))
+        .collect();
+    sites.sort_by_key(|(name, _)| *name);
+    if sites.is_empty() {
+        match only {
+            Some(name) => return Err(anyhow::anyhow!("site '{}' が定義されていません", name)),
+            None => {
+                println!("対象の site 定義がありません");
+                return Ok(());
+            }
+        }
+    }
+
+    if dry_run {
+        for (name, site) in &sites {
+            println!("{} site {}", "▶".cyan(), name.bold());
+            if let Some(build) = site.build.as_ref().filter(|_| !skip_build) {
+                println!("  ビルド: {}", build);
+            }
+            println!(
+                "  アップロード: {} → r2://{}",
+                site.output,
+                site.bucket_name(&config.name, name, stage)
+            );
+            if let Some(domain) = site.domain_name(stage) {
+                println!("  ドメイン: https://{}", domain);
+            }
+        }
+        return Ok(());
+    }
+
+    let account_id = crate::dns::account_id(config).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Cloudflare のアカウント ID がありません（provider \"cloudflare\" {{ account_id \"...\" }} または CLOUDFLARE_ACCOUNT_ID）"
+        )
+    })?;
+    let api = CloudflareApi::new(crate::dns::api_token(config)?, account_id);
+    let r2 = crate::commands::storage::client(config)?;
+    let zones = if sites.iter().any(|(_, site)| site.domain.is_some()) {
+        crate::dns::zones(config, stage).await?
+    } else {
+        DnsZones::default()
+    };
+    let buckets = api.list_r2_buckets().await?;
+
+    for (name, site) in sites {
+        println!("{} site {}", "▶".cyan(), name.bold());
+        if !skip_build {
+            build_site(project_root, name, site).await?;
+        }
+
+        let output = project_root.join(&site.output);
+        if !output.is_dir() {
+            return Err(anyhow::anyhow!(
+                "site '{}' の output ディレクトリが見つかりません: {}",
+                name,
+                output.display()
+            ));
+        }
+
+        let bucket = site.bucket_name(&config.name, name, stage);
+        if !buckets.iter().any(|b| b.name == bucket) {
+            api.create_r2_bucket(&bucket).await?;
+            println!("  {} R2 バケット {} を作成しました", "✓".green(), bucket);
+        }
+        crate::commands::storage::upload_dir(
+            &r2,
+            &output,
+            &bucket,
+            "",
+            true,
+            false,
+            SITE_UPLOAD_JOBS,
+        )
+        .await?;
+
+        if let Some(domain) = site.domain_name(stage) {
+            let zone = zones.zone_for(&domain).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} を含むゾーンが cloudflare provider に宣言されていません",
+                    domain
+                )
+            })?;
+            api.ensure_r2_custom_domain(&bucket, &domain, zone.zone_id())
+                .await?;
+            println!("  {} https://{} → r2://{}", "✓".green(), domain, bucket);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(status: ServerStatus, tags: &[&str]) -> ServerSpec {
+        ServerSpec {
+            id: "1".to_string(),
+            name: "web-01".to_string(),
+            cpu: Some(2),
+            memory_gb: Some(4),
+            disk_gb: Some(40),
+            status,
+            ip_address: Some("203.0.113.1".to_string()),
+            provider: "sakura-cloud".to_string(),
+            zone: Some("tk1a".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_options_gap_compares_declared_fields_only() {
+        let record = DnsRecordInfo {
+            id: "1".to_string(),
+            name: "web-01.example.com".to_string(),
+            record_type: "A".to_string(),
+            content: "203.0.113.1".to_string(),
+            ttl: Some(1),
+            proxied: true,
+        };
+        assert_eq!(options_gap(&DnsRecordOptions::default(), &record), None);
+        let declared = DnsRecordOptions {
+            ttl: Some(300),
+            proxied: Some(true),
+        };
+        assert_eq!(options_gap(&declared, &record), None);
+        let declared = DnsRecordOptions {
+            ttl: Some(300),
+            proxied: Some(false),
+        };
+        assert_eq!(
+            options_gap(&declared, &record).as_deref(),
+            Some("プロキシ: on（定義: off）")
+        );
+    }
+
+    #[test]
+    fn test_compare_server_matches() {
+        let desired = ServerResource {
+            plan: Some("2core-4gb".to_string()),
+            disk_size: Some(40),
+            tags: vec!["web".to_string()],
+            ..ServerResource::with_provider("sakura-cloud")
+        };
+        let actual = spec(ServerStatus::Running, &["web", "fleetflow:myapp:web-01"]);
+        let results = compare_server(&desired, &actual);
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|(_, gap)| gap.is_none()));
+    }
+
+    #[test]
+    fn test_compare_server_reports_out_of_band_changes() {
+        let desired = ServerResource {
+            plan: Some("4core-8gb".to_string()),
+            disk_size: Some(100),
+            tags: vec!["web".to_string()],
+            ..ServerResource::with_provider("sakura-cloud")
+        };
+        let actual = spec(ServerStatus::Stopped, &["debug"]);
+        let gaps: Vec<&str> = compare_server(&desired, &actual)
+            .into_iter()
+            .filter(|(_, gap)| gap.is_some())
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(gaps, vec!["plan", "disk", "power", "tags"]);
+    }
+
+    #[test]
+    fn test_find_server_and_undeclared_tag() {
+        let servers = vec![
+            spec(ServerStatus::Running, &["fleetflow:myapp:web-01"]),
+            ServerSpec {
+                id: "2".to_string(),
+                name: "old-worker".to_string(),
+                ..spec(ServerStatus::Stopped, &["fleetflow:myapp:worker-01"])
+            },
+        ];
+        assert_eq!(find_server(&servers, "myapp", "web-01").unwrap().id, "1");
+        assert!(find_server(&servers, "myapp", "db-01").is_none());
+        assert_eq!(tagged_server_name(&servers[1], "myapp"), Some("worker-01"));
+        assert_eq!(tagged_server_name(&servers[1], "other"), None);
+    }
+
+    #[test]
+    fn test_server_records() {
+        let record =
+            |record_type: &str, name: &str, content: &str, comment: Option<&str>| DnsRecordSpec {
+                id: name.to_string(),
+                name: name.to_string(),
+                record_type: record_type.to_string(),
+                content: content.to_string(),
+                ttl: None,
+                proxied: false,
+                comment: comment.map(str::to_string),
+            };
+        let records = vec![
+            record("A", "web-01.example.com", "203.0.113.1", None),
+            record("CNAME", "app.example.com", "web-01.example.com", None),
+            record(
+                "TXT",
+                "_verify.example.com",
+                "x",
+                Some("fleetflow:myapp:web-01"),
+            ),
+            record("A", "db-01.example.com", "203.0.113.2", None),
+        ];
+        assert_eq!(
+            server_records(&records, "web-01", "example.com", None, Some("203.0.113.1")),
+            vec![
+                "A web-01.example.com",
+                "CNAME app.example.com",
+                "TXT _verify.example.com"
+            ]
+        );
+    }
+}
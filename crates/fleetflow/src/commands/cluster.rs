@@ -0,0 +1,125 @@
+//! クラスタ型 backend — `fleet up` / `fleet down` / `fleet ps` の kubernetes / nomad 経路
+//!
+//! stage がクラスタ型の backend を宣言しているとき、`up.rs` / `down.rs` / `ps.rs` から
+//! 本モジュールに分岐する。定義の生成とクラスタへの投入は
+//! `fleetflow-container::ClusterTarget` の実装に集約されており、本モジュールはバックエンドを
+//! 問わない薄い presenter。
+
+use std::path::Path;
+
+use colored::Colorize;
+use fleetflow_container::{ClusterTarget, services_with_host_volumes};
+use fleetflow_core::{Flow, Stage};
+
+fn print_backend(target: &dyn ClusterTarget, stage_name: &str, stage: &Stage) {
+    let name = target.name();
+    match &stage.backend_address {
+        Some(address) => println!(
+            "{}",
+            format!("backend: {name} ({stage_name}, {address})").cyan()
+        ),
+        None => println!("{}", format!("backend: {name} ({stage_name})").cyan()),
+    }
+}
+
+/// `fleet up` のクラスタ経路。
+pub async fn up(
+    target: &dyn ClusterTarget,
+    config: &Flow,
+    project_root: &Path,
+    stage_name: &str,
+    stage: &Stage,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    print_backend(target, stage_name, stage);
+    let backend = format!("backend \"{}\"", target.name());
+    fleetflow_container::reject_configs(config, stage_name, &backend)?;
+    fleetflow_container::reject_secrets(config, &stage.services, &backend)?;
+
+    let unmapped = services_with_host_volumes(config, stage);
+    if !unmapped.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "⚠ ホストパスの volume はクラスタにマッピングされません: {}",
+                unmapped.join(", ")
+            )
+            .yellow()
+        );
+    }
+
+    if dry_run {
+        let rendered = target.render(config, stage_name, stage)?;
+        println!("{}", "[dry-run] 生成される定義:".yellow().bold());
+        for line in rendered.lines() {
+            println!("  {line}");
+        }
+        println!(
+            "{}",
+            "[dry-run] 実際の書き込み・クラスタへの適用は行われません。".yellow()
+        );
+        return Ok(());
+    }
+
+    let file = target.up(project_root, config, stage_name, stage)?;
+    println!(
+        "  {} {} → {}",
+        "✓".green(),
+        file.display().to_string().cyan(),
+        target.name()
+    );
+
+    println!();
+    println!(
+        "{}",
+        format!(
+            "✓ すべてのサービスを適用しました（{}: {}）！",
+            target.name(),
+            target.resource(config, stage_name)
+        )
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+/// `fleet down` のクラスタ経路。
+///
+/// `remove` 指定時はステージの定義ごと削除する。
+pub async fn down(
+    target: &dyn ClusterTarget,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+    remove: bool,
+) -> anyhow::Result<()> {
+    print_backend(target, stage_name, stage);
+
+    target.down(config, stage_name, stage, remove)?;
+    println!(
+        "  {} {} を{}",
+        "✓".green(),
+        target.resource(config, stage_name).cyan(),
+        if remove { "削除" } else { "停止" }
+    );
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ ステージを停止しました（{}）", target.name())
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// `fleet ps` のクラスタ経路（クラスタ側の状態をそのまま表示）。
+pub fn status(
+    target: &dyn ClusterTarget,
+    config: &Flow,
+    stage_name: &str,
+    stage: &Stage,
+) -> anyhow::Result<()> {
+    print_backend(target, stage_name, stage);
+    target.status(config, stage_name, stage)
+}
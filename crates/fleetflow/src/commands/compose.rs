@@ -23,6 +23,13 @@ pub async fn up(
 ) -> anyhow::Result<()> {
     println!("{}", format!("backend: compose ({stage_name})").cyan());
 
+    // tmpfs のシークレットは再起動後の自動起動時に存在しないため扱わない
+    fleetflow_container::reject_secrets(config, &stage.services, "backend \"compose\"")?;
+    // config マウントはレンダリング済みファイルの bind mount として compose.yaml に載せる
+    let (config, files) =
+        fleetflow_container::render_stage_configs(project_root, config, stage_name)?;
+    let config = &config;
+
     if dry_run {
         let yaml = generate_compose_yaml(project_root, config, stage_name, stage)?;
         println!("{}", "[dry-run] 生成される compose.yaml:".yellow().bold());
@@ -36,6 +43,7 @@ pub async fn up(
         return Ok(());
     }
 
+    fleetflow_container::write_rendered_files(&files)?;
     let file = compose_up(project_root, config, stage_name, stage)?;
     println!(
         "  {} {} → podman compose up -d",
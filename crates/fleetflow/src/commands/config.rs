@@ -0,0 +1,62 @@
+//! `fleet config` — グローバル設定（~/.config/fleetflow/config.kdl）の編集
+
+use colored::Colorize;
+
+/// `fleet config set <key> <value>`
+pub fn handle_set(key: &str, value: &str) -> anyhow::Result<()> {
+    let path = fleetflow_config::save_global_value(key, value)?;
+    println!(
+        "{}",
+        format!("✓ {} = {} を設定しました", key, value)
+            .green()
+            .bold()
+    );
+    println!("  {}", path.display().to_string().dimmed());
+    Ok(())
+}
+
+/// `fleet config list` — 設定可能なキーと現在の値を表示
+pub fn handle_list() -> anyhow::Result<()> {
+    let path = fleetflow_config::GlobalConfig::path()?;
+    let (config, unknown) = fleetflow_config::GlobalConfig::load_lenient()?;
+    crate::utils::warn_unknown_global_keys(&unknown);
+
+    println!("{}", "グローバル設定".bold());
+    println!("  {}", path.display().to_string().dimmed());
+    println!();
+
+    let update_channel = config.update.channel.as_deref().unwrap_or("stable");
+    let values = [
+        (
+            "default-stage",
+            config.default_stage.as_deref().map(str::to_string),
+        ),
+        ("color", config.color.map(|c| c.to_string())),
+        ("registry", config.registry.clone()),
+        ("docker-socket", config.docker_socket.clone()),
+        (
+            "docker-retry.attempts",
+            Some(config.docker_retry.attempts.to_string()),
+        ),
+        (
+            "docker-retry.interval",
+            Some(config.docker_retry.interval.to_string()),
+        ),
+        ("update.check", Some(config.update.check.to_string())),
+        ("update.channel", Some(update_channel.to_string())),
+    ];
+
+    for (key, description) in fleetflow_config::GLOBAL_CONFIG_KEYS {
+        let value = values
+            .iter()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, v)| v.clone());
+        match value {
+            Some(value) => println!("  {:<22} {}", key.cyan(), value),
+            None => println!("  {:<22} {}", key.cyan(), "(未設定)".dimmed()),
+        }
+        println!("  {:<22} {}", "", description.dimmed());
+    }
+
+    Ok(())
+}
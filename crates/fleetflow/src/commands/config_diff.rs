@@ -0,0 +1,199 @@
+//! `fleet config diff` — 2 つのステージ（または git リビジョン）の解決済み設定を比較
+//!
+//! `--stage-a stg --stage-b prod` でステージ間、`--git <rev>` でリビジョン間
+//! （指定リビジョンの設定 → 作業ツリーの設定）の差分を表示する。
+//! プロモーションで実際に何が変わるかをレビューするためのもの。
+//! センシティブなキー（`utils::is_sensitive_key`）の値は `--reveal` が無ければ伏せる。
+
+use crate::utils::is_sensitive_key;
+use colored::Colorize;
+use fleetflow_core::{ConfigDiff, FieldChange, Flow};
+use std::path::Path;
+
+/// 指定リビジョンのプロジェクトを一時ディレクトリに展開して読み込む
+fn load_at_revision(project_root: &Path, rev: &str, stage: &str) -> anyhow::Result<Flow> {
+    let git = |args: &[&str]| -> anyhow::Result<Vec<u8>> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(project_root)
+            .args(args)
+            .output()
+            .map_err(|e| anyhow::anyhow!("git を実行できません: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "git {} に失敗: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    };
+
+    // プロジェクトルートがリポジトリのサブディレクトリでも、その木だけを展開する
+    let prefix = String::from_utf8_lossy(&git(&["rev-parse", "--show-prefix"])?)
+        .trim()
+        .trim_end_matches('/')
+        .to_string();
+    let archive = git(&["archive", "--format=tar", &format!("{}:{}", rev, prefix)])?;
+
+    let dir = std::env::temp_dir().join(format!("fleetflow-config-diff-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let loaded = tar::Archive::new(archive.as_slice())
+        .unpack(&dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| {
+            fleetflow_core::load_project_from_root_with_stage(&dir, Some(stage))
+                .map_err(|e| anyhow::anyhow!("{} の設定を読み込めません: {}", rev, e))
+        });
+    let _ = std::fs::remove_dir_all(&dir);
+    loaded
+}
+
+/// センシティブなキーの値を伏せる（変更があったことは残す）
+fn mask(changes: &mut [FieldChange]) {
+    for change in changes {
+        let key = change.field.rsplit('.').next().unwrap_or_default();
+        if !change.field.contains('.') || !is_sensitive_key(key) {
+            continue;
+        }
+        for value in [&mut change.before, &mut change.after] {
+            if value.is_some() {
+                *value = Some("***".to_string());
+            }
+        }
+    }
+}
+
+fn print_change(change: &FieldChange) {
+    match (&change.before, &change.after) {
+        (None, Some(after)) => println!("      {} {}: {}", "+".green(), change.field, after),
+        (Some(before), None) => println!("      {} {}: {}", "-".red(), change.field, before),
+        (Some(before), Some(after)) => println!(
+            "      {} {}: {} → {}",
+            "~".yellow(),
+            change.field,
+            before.dimmed(),
+            after
+        ),
+        (None, None) => {}
+    }
+}
+
+fn print_diff(diff: &ConfigDiff, label_a: &str, label_b: &str) {
+    println!(
+        "{}",
+        format!("設定の差分: {} → {}", label_a, label_b).bold()
+    );
+    if diff.is_empty() {
+        println!();
+        println!("{}", "✓ 差分はありません".green());
+        return;
+    }
+
+    if !diff.stage.is_empty() {
+        println!();
+        println!("  {}", "ステージ設定".bold());
+        diff.stage.iter().for_each(print_change);
+    }
+    if !diff.added.is_empty() || !diff.removed.is_empty() || !diff.changed.is_empty() {
+        println!();
+        println!("  {}", "サービス".bold());
+    }
+    for name in &diff.added {
+        println!("    {} {}（追加）", "+".green(), name.cyan());
+    }
+    for name in &diff.removed {
+        println!("    {} {}（削除）", "-".red(), name.cyan());
+    }
+    for (name, changes) in &diff.changed {
+        println!("    {} {}", "~".yellow(), name.cyan());
+        changes.iter().for_each(print_change);
+    }
+    println!();
+    println!(
+        "{}",
+        format!(
+            "追加 {} / 削除 {} / 変更 {} サービス、ステージ設定の変更 {} 件",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len(),
+            diff.stage.len()
+        )
+        .dimmed()
+    );
+}
+
+/// `fleet config diff`
+pub fn handle_diff(
+    stage_a: &str,
+    stage_b: Option<&str>,
+    git: Option<&str>,
+    reveal: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let project_root = fleetflow_core::find_project_root()?;
+    let stage_b = stage_b.unwrap_or(stage_a);
+    if git.is_none() && stage_a == stage_b {
+        return Err(anyhow::anyhow!(
+            "比較対象がありません（--stage-b で別のステージ、または --git でリビジョンを指定してください）"
+        ));
+    }
+
+    let (before, label_a) = match git {
+        Some(rev) => (
+            load_at_revision(&project_root, rev, stage_a)?,
+            format!("{}@{}", stage_a, rev),
+        ),
+        None => (
+            fleetflow_core::load_project_from_root_with_stage(&project_root, Some(stage_a))?,
+            stage_a.to_string(),
+        ),
+    };
+    let after = fleetflow_core::load_project_from_root_with_stage(&project_root, Some(stage_b))?;
+
+    let mut diff = fleetflow_core::diff_stages(&before, stage_a, &after, stage_b)?;
+    if !reveal {
+        mask(&mut diff.stage);
+        for changes in diff.changed.values_mut() {
+            mask(changes);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print_diff(&diff, &label_a, stage_b);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_sensitive_values() {
+        let mut changes = vec![
+            FieldChange {
+                field: "environment.DB_PASSWORD".to_string(),
+                before: Some("old".to_string()),
+                after: Some("new".to_string()),
+            },
+            FieldChange {
+                field: "environment.RUST_LOG".to_string(),
+                before: None,
+                after: Some("info".to_string()),
+            },
+            FieldChange {
+                field: "image".to_string(),
+                before: Some("app:1".to_string()),
+                after: Some("app:2".to_string()),
+            },
+        ];
+        mask(&mut changes);
+        assert_eq!(changes[0].before.as_deref(), Some("***"));
+        assert_eq!(changes[0].after.as_deref(), Some("***"));
+        assert_eq!(changes[1].after.as_deref(), Some("info"));
+        assert_eq!(changes[2].after.as_deref(), Some("app:2"));
+    }
+}
@@ -398,9 +398,7 @@ pub async fn handle_server(cmd: &ServerCommands) -> Result<()> {
                 anyhow::anyhow!("sakura.server_id が記録されていません (record の populate が必要)")
             })?;
             let zone = server["sakura"]["zone"].as_str().unwrap_or("tk1a");
-            let host = server["dns"]["public_ipv4"]
-                .as_str()
-                .or_else(|| server["ssh_host"].as_str());
+            let host = server_address(server);
 
             println!(
                 "  Sakura ID: {} (zone: {})",
@@ -429,28 +427,85 @@ pub async fn handle_server(cmd: &ServerCommands) -> Result<()> {
             if *wait && let Some(host) = host {
                 println!("  SSH 待機中: {}", host.dimmed());
                 let start = std::time::Instant::now();
-                let timeout = std::time::Duration::from_secs(180);
-                let target = format!("{host}:22");
-                while start.elapsed() < timeout {
-                    // Pure-Rust TCP probe (no external nc dependency)
-                    let connect_result = tokio::time::timeout(
-                        std::time::Duration::from_secs(3),
-                        tokio::net::TcpStream::connect(&target),
-                    )
-                    .await;
-                    if let Ok(Ok(_)) = connect_result {
-                        println!(
-                            "{} SSH 通った ({}秒)",
-                            "✓".green(),
-                            start.elapsed().as_secs()
-                        );
-                        return Ok(());
-                    }
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                if wait_for_ssh(host, std::time::Duration::from_secs(180)).await {
+                    println!(
+                        "{} SSH 通った ({}秒)",
+                        "✓".green(),
+                        start.elapsed().as_secs()
+                    );
+                    return Ok(());
                 }
                 println!("{} SSH 待機 timeout (180s)", "⚠".yellow());
             }
         }
+        ServerCommands::Wait { slug, ssh, timeout } => {
+            let timeout = std::time::Duration::from_secs(*timeout);
+            let start = std::time::Instant::now();
+            eprintln!("{} {}", "サーバー待機中:".bold(), slug.cyan());
+
+            // CP の状態が online になる（--ssh ではアドレスが割り当てられる）まで待つ
+            let host = loop {
+                let resp = cp_client::request(
+                    &client,
+                    "server",
+                    "get",
+                    json!({ "tenant_slug": tenant_slug, "slug": slug }),
+                )
+                .await?;
+                let server = resp
+                    .get("server")
+                    .ok_or_else(|| anyhow::anyhow!("サーバーが見つかりません: {}", slug))?;
+                // --ssh ではポートの応答で判定する（CP の状態はヘルスチェック周期で遅れるため）
+                if *ssh {
+                    if let Some(host) = server_address(server) {
+                        break host.to_string();
+                    }
+                } else if server["status"].as_str() == Some("online") {
+                    eprintln!("{} online ({}秒)", "✓".green(), start.elapsed().as_secs());
+                    return Ok(());
+                }
+                if start.elapsed() >= timeout {
+                    return Err(anyhow::anyhow!(
+                        "サーバー '{}' の待機がタイムアウトしました ({}秒)",
+                        slug,
+                        timeout.as_secs()
+                    ));
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            };
+
+            eprintln!("  SSH 待機中: {}", host.dimmed());
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if !wait_for_ssh(&host, remaining).await {
+                return Err(anyhow::anyhow!(
+                    "サーバー '{}' の SSH 待機がタイムアウトしました ({}秒)",
+                    slug,
+                    timeout.as_secs()
+                ));
+            }
+            eprintln!(
+                "{} SSH 応答あり ({}秒)",
+                "✓".green(),
+                start.elapsed().as_secs()
+            );
+        }
+        ServerCommands::Ip { slug } => {
+            let resp = cp_client::request(
+                &client,
+                "server",
+                "get",
+                json!({ "tenant_slug": tenant_slug, "slug": slug }),
+            )
+            .await?;
+            let server = resp
+                .get("server")
+                .ok_or_else(|| anyhow::anyhow!("サーバーが見つかりません: {}", slug))?;
+            let address = server_address(server).ok_or_else(|| {
+                anyhow::anyhow!("サーバー '{}' のアドレスが記録されていません", slug)
+            })?;
+            // スクリプトで `$(fleet cp server ip web-1)` と使えるよう値のみを出力
+            println!("{}", address);
+        }
         ServerCommands::Shutdown { slug } => {
             println!("{} {}", "Shutdown サーバー:".bold(), slug.cyan());
             println!();
@@ -469,10 +524,8 @@ pub async fn handle_server(cmd: &ServerCommands) -> Result<()> {
                     return Ok(());
                 }
             };
-            let host = server["dns"]["public_ipv4"]
-                .as_str()
-                .or_else(|| server["ssh_host"].as_str())
-                .ok_or_else(|| anyhow::anyhow!("ssh_host が未設定"))?;
+            let host =
+                server_address(server).ok_or_else(|| anyhow::anyhow!("ssh_host が未設定"))?;
             let ssh_user = server["ssh_user"].as_str().unwrap_or("root");
             // root 以外は sudo 必須 (passwordless 必須、要 visudo 設定)
             let sudo_prefix = if ssh_user == "root" { "" } else { "sudo " };
@@ -507,6 +560,32 @@ pub async fn handle_server(cmd: &ServerCommands) -> Result<()> {
     Ok(())
 }
 
+/// サーバーの接続先アドレス（公開 IPv4 を優先し、なければ ssh_host）
+fn server_address(server: &serde_json::Value) -> Option<&str> {
+    server["dns"]["public_ipv4"]
+        .as_str()
+        .or_else(|| server["ssh_host"].as_str())
+}
+
+/// SSH（22 番ポート）が応答するまで待つ。タイムアウトしたら false
+async fn wait_for_ssh(host: &str, timeout: std::time::Duration) -> bool {
+    let start = std::time::Instant::now();
+    let target = format!("{host}:22");
+    while start.elapsed() < timeout {
+        // Pure-Rust TCP probe (no external nc dependency)
+        let connect_result = tokio::time::timeout(
+            std::time::Duration::from_secs(3),
+            tokio::net::TcpStream::connect(&target),
+        )
+        .await;
+        if let Ok(Ok(_)) = connect_result {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+    false
+}
+
 pub async fn handle_cost(cmd: &CostCommands) -> Result<()> {
     let (client, creds) = cp_client::connect().await?;
     let tenant_slug = creds.tenant_slug.as_deref().unwrap_or("default");
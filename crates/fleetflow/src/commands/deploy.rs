@@ -1,7 +1,9 @@
 use crate::docker;
 use crate::utils;
 use colored::Colorize;
+use fleetflow_build::{ProgressEvent, ProgressReporter, ProgressStatus, TaskProgress};
 use fleetflow_container::{DeployEngine, DeployEvent, DeployRequest};
+use std::sync::{Arc, Mutex};
 
 /// 環境変数のキーがセンシティブかどうか判定する
 use crate::utils::is_sensitive_key;
@@ -40,7 +42,7 @@ fn print_dry_run_plan(
             .get(service_name)
             .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
 
-        let container_name = format!("{}-{}-{}", config.name, stage_name, service_name);
+        let container_name = config.container_name(stage_name, service_name);
         let image = service.image.as_deref().unwrap_or("(未設定)");
 
         println!();
@@ -88,6 +90,19 @@ fn print_dry_run_plan(
         }
     }
 
+    if let Some(stage) = config.stages.get(stage_name)
+        && !stage.smoke_tests.is_empty()
+    {
+        println!();
+        println!("  スモークテスト:");
+        for test in &stage.smoke_tests {
+            println!(
+                "    {} → {} (timeout {}s)",
+                test.target, test.expect, test.timeout
+            );
+        }
+    }
+
     println!();
     println!(
         "{}",
@@ -99,42 +114,89 @@ fn print_dry_run_plan(
     Ok(())
 }
 
-/// DeployEvent を CLI 表示に変換する
-fn print_deploy_event(event: DeployEvent) {
-    match event {
-        DeployEvent::StepStarted {
-            step,
-            total,
-            description,
-        } => {
-            println!();
-            let msg = format!("【Step {}/{}】{}", step, total, description);
-            match step {
-                1 | 5 => println!("{}", msg.yellow()),
-                2 | 3 => println!("{}", msg.blue()),
-                4 => println!("{}", msg.green()),
-                _ => println!("{}", msg),
-            }
+/// DeployEvent を進捗表示に変換する
+///
+/// ステップごとにスピナー（非 TTY では行出力）を表示し、
+/// サービス単位の進捗は表示を崩さないようレポーター経由で出力する。
+struct DeployProgress {
+    reporter: Arc<dyn ProgressReporter>,
+    step: Mutex<Option<(Box<dyn TaskProgress>, String)>>,
+}
+
+impl DeployProgress {
+    fn new() -> Self {
+        Self {
+            reporter: fleetflow_build::reporter(),
+            step: Mutex::new(None),
         }
-        DeployEvent::ServiceProgress { service, action } => match action.as_str() {
-            "stopped" => println!("  ✓ {} を停止しました", service.cyan()),
-            "removed" => println!("  ✓ {} を削除しました", service.cyan()),
-            "creating" => {
-                println!();
-                println!("{}", format!("■ {} を起動中...", service).green().bold());
+    }
+
+    fn handle(&self, event: DeployEvent) {
+        let mut step_task = self.step.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            DeployEvent::StepStarted {
+                step,
+                total,
+                description,
+            } => {
+                if let Some((task, description)) = step_task.take() {
+                    task.finish(&description);
+                }
+                let task = self
+                    .reporter
+                    .step_task("deploy", &format!("Step {}/{}", step, total));
+                task.set_message(&description);
+                *step_task = Some((task, description));
+            }
+            DeployEvent::ServiceProgress { service, action } => {
+                let line = match action.as_str() {
+                    "stopped" => format!("  ✓ {} を停止しました", service.cyan()),
+                    "removed" => format!("  ✓ {} を削除しました", service.cyan()),
+                    "creating" => format!("■ {} を起動中...", service)
+                        .green()
+                        .bold()
+                        .to_string(),
+                    "started" => format!("  ✓ {} 起動完了", service.cyan()),
+                    action if action.starts_with("pulling") => {
+                        format!("  ↓ {} ({})", service.cyan(), &action[8..])
+                    }
+                    _ => format!("  {} {}", service, action),
+                };
+                self.reporter.println(&line);
+                let status = match action.as_str() {
+                    "creating" => ProgressStatus::Started,
+                    "stopped" | "removed" | "started" => ProgressStatus::Done,
+                    _ => ProgressStatus::Running,
+                };
+                self.reporter
+                    .event(&ProgressEvent::new("deploy", status, action).service(&service));
+            }
+            DeployEvent::StepCompleted { .. } => {
+                if let Some((task, description)) = step_task.take() {
+                    task.finish(&description);
+                }
             }
-            "started" => println!("  ✓ 起動完了"),
-            action if action.starts_with("pulling") => {
-                println!("  ↓ {} ({})", service.cyan(), &action[8..]);
+            DeployEvent::Completed { services_deployed } => {
+                self.reporter.event(
+                    &ProgressEvent::new(
+                        "deploy",
+                        ProgressStatus::Done,
+                        format!("{} サービスをデプロイしました", services_deployed.len()),
+                    )
+                    .percent(100.0),
+                );
             }
-            _ => println!("  {} {}", service, action),
-        },
-        DeployEvent::StepCompleted { .. } => {}
-        DeployEvent::Completed {
-            services_deployed: _,
-        } => {}
-        DeployEvent::Error { message } => {
-            eprintln!("  ✗ {}", message.red());
+            DeployEvent::Error { message } => match step_task.take() {
+                Some((task, _)) => task.fail(&message),
+                None => {
+                    eprintln!("  ✗ {}", message.red());
+                    self.reporter.event(&ProgressEvent::new(
+                        "deploy",
+                        ProgressStatus::Failed,
+                        message,
+                    ));
+                }
+            },
         }
     }
 }
@@ -150,10 +212,22 @@ pub async fn handle(
     yes: bool,
     dry_run: bool,
     tenant_override: Option<String>,
+    offline: bool,
+    skip_smoke_test: bool,
+    prepare_only: bool,
 ) -> anyhow::Result<()> {
     println!("{}", "デプロイを開始します...".blue().bold());
     utils::print_loaded_config_files(project_root);
 
+    // オフライン: レジストリにアクセスせず、読み込み済みイメージを使う
+    let no_pull = no_pull || offline;
+    if offline {
+        println!(
+            "{}",
+            "オフラインモード（イメージの pull を行いません）".yellow()
+        );
+    }
+
     // ステージ名の決定
     let stage_name = utils::determine_stage_name(stage, config)?;
     println!("ステージ: {}", stage_name.cyan());
@@ -205,6 +279,11 @@ pub async fn handle(
         );
     }
 
+    // 準備のみ（既存コンテナには触れないため確認不要）
+    if prepare_only {
+        return prepare_local(config, &stage_name, &target_services, no_pull).await;
+    }
+
     // 確認（--yesが指定されていない場合）
     if !yes {
         println!();
@@ -236,8 +315,33 @@ pub async fn handle(
         let is_remote = !stage_config.servers.is_empty();
 
         if is_remote {
-            deploy_remote(
+            // シークレットはサーバーの tmpfs に書き戻す手段が無いため扱わない
+            fleetflow_container::reject_secrets(
                 config,
+                &container_names,
+                "リモートステージの deploy",
+            )?;
+            // log-shipper の設定と config マウントのファイルは、サーバー上のパスを
+            // mount するので先に配置する
+            let mut files: Vec<fleetflow_container::RenderedFile> =
+                agent_config_file(config, &stage_name).into_iter().collect();
+            let mut rendered = config.clone();
+            for service_name in &container_names {
+                if let Some(service) = config.services.get(service_name) {
+                    let (service, service_files) = fleetflow_container::remote_configs(
+                        project_root,
+                        config,
+                        &stage_name,
+                        service_name,
+                        service,
+                    )?;
+                    files.extend(service_files);
+                    rendered.services.insert(service_name.clone(), service);
+                }
+            }
+            upload_remote_files(&remote_uploads(config, &stage_name, &files)?).await?;
+            deploy_remote(
+                &rendered,
                 &stage_name,
                 &container_names,
                 no_pull,
@@ -246,7 +350,62 @@ pub async fn handle(
             )
             .await?;
         } else {
-            deploy_local(config, &stage_name, &container_names, no_pull, no_prune).await?;
+            // ログ集約エージェントの設定を生成（log-shipper サービスが bind mount する）
+            if let Some(log_shipping) = &stage_config.log_shipping {
+                fleetflow_container::write_agent_config(
+                    project_root,
+                    config,
+                    &stage_name,
+                    log_shipping,
+                )?;
+            }
+            // 設定ファイル（configs）とシークレットを書き出して bind mount を追加
+            // （deploy は常にコンテナを再作成するため hash ラベルは不要）
+            let mut rendered = config.clone();
+            for service_name in &container_names {
+                if let Some(service) = config.services.get(service_name) {
+                    let (service, _) = fleetflow_container::materialize_configs(
+                        project_root,
+                        config,
+                        &stage_name,
+                        service_name,
+                        service,
+                    )?;
+                    let service = fleetflow_container::materialize_secrets(
+                        config,
+                        &stage_name,
+                        service_name,
+                        &service,
+                    )?;
+                    rendered.services.insert(service_name.clone(), service);
+                }
+            }
+            if offline {
+                ensure_images_loaded(config, &stage_name, &container_names).await?;
+            }
+            deploy_local(
+                &rendered,
+                project_root,
+                &stage_name,
+                &container_names,
+                no_pull,
+                no_prune,
+            )
+            .await?;
+        }
+    }
+
+    // スモークテスト（失敗したらデプロイを失敗にする）
+    if !stage_config.smoke_tests.is_empty() {
+        if skip_smoke_test {
+            println!();
+            println!(
+                "{}",
+                "スモークテストをスキップしました（--skip-smoke-test）".yellow()
+            );
+        } else {
+            let checks = resolve_smoke_tests(config, &stage_name, stage_config)?;
+            run_smoke_tests(&checks).await?;
         }
     }
 
@@ -261,6 +420,120 @@ pub async fn handle(
     Ok(())
 }
 
+/// スモークテストの対象 URL を解決する
+///
+/// 名前付きポート参照（`service="web" port="http"`）は `fleet open` と同じ規則で URL にする
+/// （ローカルは公開ポート、リモートはステージの DNS ドメイン配下）。
+fn resolve_smoke_tests<'a>(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    stage: &'a fleetflow_core::Stage,
+) -> anyhow::Result<Vec<(String, &'a fleetflow_core::SmokeTest)>> {
+    let domain = crate::dns::service_domain(config, Some(stage_name));
+    stage
+        .smoke_tests
+        .iter()
+        .map(|test| {
+            let url = match &test.target {
+                fleetflow_core::SmokeTarget::Url(url) => url.clone(),
+                fleetflow_core::SmokeTarget::Port {
+                    service,
+                    port,
+                    path,
+                } => {
+                    let service_config = config.services.get(service).ok_or_else(|| {
+                        anyhow::anyhow!("smoke_test のサービス '{}' が見つかりません", service)
+                    })?;
+                    let base = super::open::resolve_url(
+                        stage_name,
+                        stage,
+                        service,
+                        service_config,
+                        Some(port),
+                        domain.as_ref(),
+                    )?;
+                    format!("{}{}", base, path)
+                }
+            };
+            Ok((url, test))
+        })
+        .collect()
+}
+
+/// スモークテストを 1 件実行する
+///
+/// `timeout` 秒以内に期待ステータスが返るまで再試行し、返らなければ最後の観測結果を返す。
+/// リダイレクトは追わない（`expect=301` 等を確認できるように）。
+async fn run_smoke_test(
+    client: &reqwest::Client,
+    url: &str,
+    test: &fleetflow_core::SmokeTest,
+) -> Result<(), String> {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(test.timeout);
+    loop {
+        let observed = match client.get(url).send().await {
+            Ok(resp) if resp.status().as_u16() == test.expect => return Ok(()),
+            Ok(resp) => format!("HTTP {}", resp.status().as_u16()),
+            Err(e) => e.to_string(),
+        };
+        if std::time::Instant::now() >= deadline {
+            return Err(observed);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(
+            fleetflow_core::SmokeTest::RETRY_INTERVAL,
+        ))
+        .await;
+    }
+}
+
+/// ステージのスモークテストを並列に実行し、1 件でも失敗したらエラー
+async fn run_smoke_tests(tests: &[(String, &fleetflow_core::SmokeTest)]) -> anyhow::Result<()> {
+    println!();
+    println!(
+        "{}",
+        format!("スモークテスト ({} 件)...", tests.len())
+            .blue()
+            .bold()
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let results = futures_util::future::join_all(
+        tests
+            .iter()
+            .map(|(url, test)| run_smoke_test(&client, url, test)),
+    )
+    .await;
+
+    let mut failed = 0;
+    for ((url, test), result) in tests.iter().zip(&results) {
+        match result {
+            Ok(()) => println!("  {} {} → {}", "✓".green(), url, test.expect),
+            Err(observed) => {
+                failed += 1;
+                println!(
+                    "  {} {} → 期待 {}、結果: {}",
+                    "✗".red(),
+                    url,
+                    test.expect,
+                    observed
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "スモークテストに失敗しました（{}/{} 件）",
+            failed,
+            tests.len()
+        ));
+    }
+    Ok(())
+}
+
 /// 静的サイトデプロイ — ビルド → プロバイダにデプロイ
 async fn deploy_static(
     config: &fleetflow_core::Flow,
@@ -349,9 +622,34 @@ async fn deploy_static(
     Ok(())
 }
 
+/// オフラインデプロイ前に必要なイメージがすべてローカルにあることを確認
+async fn ensure_images_loaded(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    services: &[String],
+) -> anyhow::Result<()> {
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let images = super::image::stage_images(config, stage_name, services)?;
+    let missing = super::image::missing_images(&docker_conn, &images).await?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow::anyhow!(
+        "オフラインデプロイに必要なイメージがありません:\n{}\n\
+         ヒント: `fleet image save` で書き出したファイルを `fleet image load <file>` で読み込んでください",
+        missing
+            .iter()
+            .map(|image| format!("  • {}", image))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
 /// ローカルデプロイ — DeployEngine を直接実行
 async fn deploy_local(
     config: &fleetflow_core::Flow,
+    project_root: &std::path::Path,
     stage_name: &str,
     target_services: &[String],
     no_pull: bool,
@@ -361,7 +659,7 @@ async fn deploy_local(
     println!("{}", "Dockerに接続中...".blue());
     let docker_conn = docker::init_docker_with_error_handling().await?;
 
-    let engine = DeployEngine::new(docker_conn);
+    let engine = DeployEngine::new(docker_conn.clone());
     let request = DeployRequest {
         flow: config.clone(),
         stage_name: stage_name.to_string(),
@@ -370,11 +668,82 @@ async fn deploy_local(
         no_prune,
     };
 
-    engine.execute(&request, print_deploy_event).await?;
+    let progress = DeployProgress::new();
+    let result = engine
+        .execute(&request, |event| progress.handle(event))
+        .await;
+    if let Err(e) = &result {
+        progress.handle(DeployEvent::Error {
+            message: e.to_string(),
+        });
+    }
+    // 失敗後にコンテナが消えても原因を追えるよう、結果にかかわらず起動ログを残す
+    super::logs::capture_startup_logs(
+        &docker_conn,
+        config,
+        project_root,
+        stage_name,
+        target_services,
+    )
+    .await;
+    result?;
 
     Ok(())
 }
 
+/// `--prepare-only` — イメージの pull とネットワーク作成のみ（ローカルステージ）
+async fn prepare_local(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    target_services: &[String],
+    no_pull: bool,
+) -> anyhow::Result<()> {
+    if config
+        .stages
+        .get(stage_name)
+        .is_some_and(|stage| !stage.servers.is_empty())
+    {
+        anyhow::bail!(
+            "--prepare-only はローカルステージのみ対応しています（ステージ '{}' はリモート）",
+            stage_name
+        );
+    }
+    let container_services: Vec<String> = target_services
+        .iter()
+        .filter(|name| {
+            config
+                .services
+                .get(name.as_str())
+                .is_some_and(|s| !s.is_static())
+        })
+        .cloned()
+        .collect();
+
+    println!();
+    println!("{}", "Dockerに接続中...".blue());
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let engine = DeployEngine::new(docker_conn);
+    let pull_targets: &[String] = if no_pull { &[] } else { &container_services };
+    let progress = DeployProgress::new();
+    let log = engine
+        .prepare(config, stage_name, pull_targets, |event| {
+            progress.handle(event)
+        })
+        .await?;
+    for line in &log {
+        println!("  {}", line);
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ 準備完了: ステージ '{}'", stage_name)
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
 /// リモートデプロイ — CP 経由で DeployEngine を実行
 ///
 /// `tenant_override` は CLI flag `--tenant <slug>` 由来 (省略時 None)。
@@ -440,6 +809,96 @@ async fn deploy_remote(
     Ok(())
 }
 
+/// SSH で対象サーバーに配置するファイル
+#[derive(Debug)]
+struct RemoteFile {
+    server: String,
+    host: String,
+    user: String,
+    path: String,
+    content: String,
+}
+
+/// リモートステージのログ集約エージェント設定（サーバー上のパスと内容）
+fn agent_config_file(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+) -> Option<fleetflow_container::RenderedFile> {
+    let log_shipping = config.stages.get(stage_name)?.log_shipping.as_ref()?;
+    Some((
+        fleetflow_container::remote_agent_config_path(&config.name, stage_name, log_shipping.agent),
+        fleetflow_container::render_agent_config(config, stage_name, log_shipping),
+    ))
+}
+
+/// ファイルをステージの全サーバーに配置する計画
+fn remote_uploads(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    files: &[fleetflow_container::RenderedFile],
+) -> anyhow::Result<Vec<RemoteFile>> {
+    let Some(stage) = config.stages.get(stage_name) else {
+        return Ok(Vec::new());
+    };
+    let mut uploads = Vec::new();
+    for name in &stage.servers {
+        let resource = config
+            .servers
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", name))?;
+        let (host, user) = super::host_service::ssh_target(name, resource);
+        for (path, content) in files {
+            uploads.push(RemoteFile {
+                server: name.clone(),
+                host: host.to_string(),
+                user: user.to_string(),
+                path: path.display().to_string(),
+                content: content.clone(),
+            });
+        }
+    }
+    Ok(uploads)
+}
+
+/// ファイルを tailscale ssh 経由で配置する（非 root では一時ファイルから sudo install）
+async fn upload_remote_files(files: &[RemoteFile]) -> anyhow::Result<()> {
+    use fleetflow_cloud::ssh;
+
+    for file in files {
+        println!(
+            "  {} {} に {} を配置",
+            "→".blue(),
+            file.server.cyan(),
+            file.path
+        );
+        let staged = format!("/tmp/fleetflow-{}", file.path.replace('/', "_"));
+        ssh::write_file(&file.host, &file.user, &staged, file.content.as_bytes()).await?;
+        let dir = std::path::Path::new(&file.path)
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "/".to_string());
+        let script = format!(
+            "mkdir -p {dir} && install -m 644 {staged} {path} && rm -f {staged}",
+            path = file.path
+        );
+        let result = ssh::exec(
+            &file.host,
+            &file.user,
+            &super::host_service::privileged(&file.user, &script),
+        )
+        .await?;
+        if !result.success {
+            return Err(anyhow::anyhow!(
+                "{} への {} の配置に失敗しました: {}",
+                file.server,
+                file.path,
+                result.stderr.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// tenant_slug を解決する。
 ///
 /// 優先度 (高 → 低):
@@ -489,18 +948,114 @@ mod tests {
     use super::*;
     use fleetflow_core::TenantSpec;
     use fleetflow_core::model::Flow;
-    use std::collections::HashMap;
+
+    /// 固定のステータスを返す HTTP サーバーを立てて URL を返す
+    async fn serve_status(status: u16) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{addr}/health")
+    }
+
+    #[tokio::test]
+    async fn test_run_smoke_test() {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let smoke = |expect: u16| fleetflow_core::SmokeTest {
+            target: fleetflow_core::SmokeTarget::Url(String::new()),
+            expect,
+            timeout: 0,
+        };
+        let (expect_200, expect_204, expect_503) = (smoke(200), smoke(204), smoke(503));
+
+        let ok = serve_status(200).await;
+        assert!(run_smoke_test(&client, &ok, &expect_200).await.is_ok());
+        assert_eq!(
+            run_smoke_test(&client, &ok, &expect_204).await.unwrap_err(),
+            "HTTP 200"
+        );
+
+        let unavailable = serve_status(503).await;
+        assert!(
+            run_smoke_test(&client, &unavailable, &expect_503)
+                .await
+                .is_ok()
+        );
+        assert!(
+            run_smoke_tests(&[(ok, &expect_200), (unavailable, &expect_200)])
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("1/2")
+        );
+    }
+
+    #[test]
+    fn test_resolve_smoke_tests() {
+        let mut flow = flow_with_tenant(None);
+        flow.services.insert(
+            "web".to_string(),
+            fleetflow_core::Service {
+                ports: vec![fleetflow_core::Port {
+                    host: 18080,
+                    container: 8080,
+                    protocol: fleetflow_core::Protocol::Tcp,
+                    host_ip: None,
+                    name: Some("http".to_string()),
+                    app_protocol: Some(fleetflow_core::AppProtocol::Http),
+                }],
+                ..Default::default()
+            },
+        );
+        let stage = fleetflow_core::Stage {
+            services: vec!["web".to_string()],
+            smoke_tests: vec![
+                fleetflow_core::SmokeTest {
+                    target: fleetflow_core::SmokeTarget::Url("https://example.com/".to_string()),
+                    expect: 200,
+                    timeout: 30,
+                },
+                fleetflow_core::SmokeTest {
+                    target: fleetflow_core::SmokeTarget::Port {
+                        service: "web".to_string(),
+                        port: "http".to_string(),
+                        path: "/health".to_string(),
+                    },
+                    expect: 200,
+                    timeout: 30,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let urls: Vec<String> = resolve_smoke_tests(&flow, "local", &stage)
+            .unwrap()
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec!["https://example.com/", "http://localhost:18080/health"]
+        );
+    }
 
     fn flow_with_tenant(tenant: Option<TenantSpec>) -> Flow {
         Flow {
             name: "test".to_string(),
-            services: HashMap::new(),
-            stages: HashMap::new(),
-            providers: HashMap::new(),
-            servers: HashMap::new(),
-            registry: None,
-            variables: HashMap::new(),
             tenant,
+            ..Default::default()
         }
     }
 
@@ -573,4 +1128,86 @@ mod tests {
         assert!(formatted.contains("default"));
         assert!(formatted.contains("fallback"));
     }
+
+    #[test]
+    fn test_agent_config_file_for_remote_stage() {
+        let mut flow = flow_with_tenant(None);
+        flow.name = "myapp".to_string();
+        flow.servers.insert(
+            "vps-1".to_string(),
+            fleetflow_core::ServerResource {
+                ssh_host: Some("10.0.0.5".to_string()),
+                ssh_user: Some("deploy".to_string()),
+                ..Default::default()
+            },
+        );
+        flow.stages.insert(
+            "prod".to_string(),
+            fleetflow_core::Stage {
+                servers: vec!["vps-1".to_string()],
+                log_shipping: Some(fleetflow_core::LogShippingConfig {
+                    agent: fleetflow_core::LogShippingAgent::FluentBit,
+                    endpoint: Some("http://loki:3100".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+
+        // サーバーへ配置したパスを log-shipper が mount する
+        let mut injected = flow.clone();
+        fleetflow_container::inject_log_shipper(
+            &mut injected,
+            std::path::Path::new("/home/dev/myapp"),
+            "prod",
+        );
+        let mounted = &injected.services[fleetflow_container::LOG_SHIPPER_SERVICE].volumes[1];
+
+        let files: Vec<_> = agent_config_file(&flow, "prod").into_iter().collect();
+        let uploads = remote_uploads(&flow, "prod", &files).unwrap();
+        assert_eq!(uploads.len(), 1);
+        let upload = &uploads[0];
+        assert_eq!(
+            (upload.host.as_str(), upload.user.as_str()),
+            ("10.0.0.5", "deploy")
+        );
+        assert_eq!(std::path::Path::new(&upload.path), mounted.host);
+        assert!(
+            upload
+                .content
+                .contains("Regex  $attrs['fleetflow.project'] ^myapp$")
+        );
+
+        flow.stages.get_mut("prod").unwrap().log_shipping = None;
+        assert!(agent_config_file(&flow, "prod").is_none());
+    }
+
+    #[test]
+    fn test_remote_uploads_to_every_stage_server() {
+        let mut flow = flow_with_tenant(None);
+        for name in ["vps-1", "vps-2"] {
+            flow.servers.insert(name.to_string(), Default::default());
+        }
+        flow.stages.insert(
+            "prod".to_string(),
+            fleetflow_core::Stage {
+                servers: vec!["vps-1".to_string(), "vps-2".to_string()],
+                ..Default::default()
+            },
+        );
+        let files = vec![(
+            std::path::PathBuf::from("/var/lib/fleetflow/rendered/test/prod/web/app.conf"),
+            "listen 8080".to_string(),
+        )];
+
+        let uploads = remote_uploads(&flow, "prod", &files).unwrap();
+        let targets: Vec<(&str, &str)> = uploads
+            .iter()
+            .map(|u| (u.host.as_str(), u.user.as_str()))
+            .collect();
+        assert_eq!(targets, vec![("vps-1", "root"), ("vps-2", "root")]);
+
+        flow.stages.get_mut("prod").unwrap().servers = vec!["missing".to_string()];
+        assert!(remote_uploads(&flow, "prod", &files).is_err());
+    }
 }
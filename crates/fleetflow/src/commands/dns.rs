@@ -0,0 +1,362 @@
+//! `fleet dns` — プロジェクトが管理する DNS レコードの一覧・追加・削除
+//!
+//! `add` で作成したレコードにはコメントに管理タグ（`fleetflow:<project>[:<server>]`）を付け、
+//! `list` / `rm` はそのタグでプロジェクトのレコードを見分ける。
+//! 対象ゾーンは fleet.kdl の cloudflare provider（`dns { zone ... }` を含む）から解決する。
+
+use colored::Colorize;
+use fleetflow_cloud::{DnsProvider, DnsRecordRequest, DnsRecordSpec, managed_comment};
+use fleetflow_cloud_cloudflare::DnsZones;
+use fleetflow_core::Flow;
+
+async fn load_zones(config: &Flow, stage: Option<&str>) -> anyhow::Result<DnsZones> {
+    let zones = crate::dns::zones(config, stage).await?;
+    if zones.is_empty() {
+        return Err(anyhow::anyhow!(
+            "DNS ゾーンが設定されていません（cloudflare provider の zone_id / domain / dns {{ zone }}、または CLOUDFLARE_*）"
+        ));
+    }
+    Ok(zones)
+}
+
+/// レコード名を FQDN にする（管理ゾーン内の FQDN はそのまま、それ以外は `<name>.<domain>`）
+fn record_fqdn(zones: &DnsZones, name: &str, domain: Option<&str>) -> anyhow::Result<String> {
+    let name = name.trim_end_matches('.');
+    if zones.zone_for(name).is_some() {
+        return Ok(name.to_string());
+    }
+    let domain = domain.ok_or_else(|| {
+        anyhow::anyhow!(
+            "'{}' はどの管理ゾーンにも含まれません（FQDN で指定するか、ステージの dns_domain を設定してください）",
+            name
+        )
+    })?;
+    Ok(format!("{}.{}", name, domain))
+}
+
+/// レコードが指しているサーバー（管理タグ → A/AAAA の ssh_host → CNAME のサーバーホスト名）
+fn points_at(config: &Flow, stage: Option<&str>, record: &DnsRecordSpec) -> Option<String> {
+    if let Some((_, Some(server))) = record.managed_by() {
+        return Some(server.to_string());
+    }
+    let mut names: Vec<&String> = config.servers.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .find(|name| {
+            let server = &config.servers[*name];
+            match record.record_type.as_str() {
+                "A" | "AAAA" => server.ssh_host.as_deref() == Some(record.content.as_str()),
+                "CNAME" => crate::dns::server_domain(config, stage, server)
+                    .is_some_and(|domain| record.content == format!("{}.{}", name, domain)),
+                _ => false,
+            }
+        })
+        .cloned()
+}
+
+fn is_managed(config: &Flow, record: &DnsRecordSpec) -> bool {
+    record
+        .managed_by()
+        .is_some_and(|(project, _)| project == config.name)
+}
+
+/// `fleet dns list`
+pub async fn handle_list(
+    config: &Flow,
+    stage: Option<&str>,
+    all: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let zones = load_zones(config, stage).await?;
+
+    let mut rows = Vec::new();
+    for zone in zones.iter() {
+        let records = zone.list_dns_records().await.map_err(|e| {
+            anyhow::anyhow!("ゾーン {} のレコードを取得できません: {}", zone.domain(), e)
+        })?;
+        for record in records {
+            if all || is_managed(config, &record) {
+                let server = points_at(config, stage, &record);
+                rows.push((record, server));
+            }
+        }
+    }
+    rows.sort_by(|(a, _), (b, _)| (&a.name, &a.record_type).cmp(&(&b.name, &b.record_type)));
+
+    if json {
+        let value: Vec<_> = rows
+            .iter()
+            .map(|(record, server)| {
+                serde_json::json!({
+                    "record": record,
+                    "managed": is_managed(config, record),
+                    "server": server,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!(
+            "プロジェクト {} が管理する DNS レコードはありません（--all でゾーンの全レコード）",
+            config.name
+        );
+        return Ok(());
+    }
+    println!(
+        "{:<2}{:<6} {:<40} {:<40} {:<16} {}",
+        "",
+        "TYPE".bold(),
+        "NAME".bold(),
+        "CONTENT".bold(),
+        "SERVER".bold(),
+        "TTL/PROXY".bold()
+    );
+    for (record, server) in &rows {
+        let marker = if is_managed(config, record) {
+            "✓"
+        } else {
+            " "
+        };
+        let ttl = if record.proxied {
+            "proxied".to_string()
+        } else {
+            match record.ttl {
+                Some(1) | None => "auto".to_string(),
+                Some(ttl) => ttl.to_string(),
+            }
+        };
+        println!(
+            "{:<2}{:<6} {:<40} {:<40} {:<16} {}",
+            marker.green(),
+            record.record_type,
+            record.name.cyan(),
+            record.content,
+            server.as_deref().unwrap_or("-"),
+            ttl.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `fleet dns add`
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_add(
+    config: &Flow,
+    stage: Option<&str>,
+    name: &str,
+    record_type: &str,
+    content: Option<&str>,
+    server_name: Option<&str>,
+    ttl: Option<u32>,
+    proxied: bool,
+) -> anyhow::Result<()> {
+    let zones = load_zones(config, stage).await?;
+    let server = server_name
+        .map(|s| {
+            config
+                .servers
+                .get(s)
+                .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", s))
+        })
+        .transpose()?;
+
+    let record_type = record_type.to_ascii_uppercase();
+    let content = match (content, server) {
+        (Some(content), _) => content.to_string(),
+        (None, Some(server)) if record_type == "A" => server.ssh_host.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "サーバー '{}' に ssh_host がないため値を決められません（値を指定してください）",
+                server_name.unwrap_or_default()
+            )
+        })?,
+        (None, _) => {
+            return Err(anyhow::anyhow!(
+                "レコードの値を指定してください（A レコードは --server でサーバーの ssh_host を使えます）"
+            ));
+        }
+    };
+
+    let domain = server
+        .and_then(|s| crate::dns::server_domain(config, stage, s))
+        .or_else(|| crate::dns::resolve_domain(config, stage));
+    let fqdn = record_fqdn(&zones, name, domain.as_deref())?;
+    let zone = zones.zone_for(&fqdn).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} を含むゾーンが cloudflare provider に宣言されていません",
+            fqdn
+        )
+    })?;
+    let relative = zone
+        .relative_name(&fqdn)
+        .filter(|r| !r.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("ゾーンの頂点（{}）には追加できません", fqdn))?;
+
+    let request = DnsRecordRequest {
+        name: relative,
+        record_type,
+        content,
+        ttl,
+        proxied,
+        comment: Some(managed_comment(&config.name, server_name)),
+    };
+    let record = zone.upsert_dns_record(&request).await?;
+    println!(
+        "{} {} {} → {}",
+        "✓".green(),
+        record.record_type,
+        record.name.cyan(),
+        record.content
+    );
+    Ok(())
+}
+
+/// `fleet dns rm`
+pub async fn handle_rm(
+    config: &Flow,
+    stage: Option<&str>,
+    name: &str,
+    record_type: Option<&str>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let zones = load_zones(config, stage).await?;
+    let fqdn = record_fqdn(
+        &zones,
+        name,
+        crate::dns::resolve_domain(config, stage).as_deref(),
+    )?;
+    let zone = zones.zone_for(&fqdn).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} を含むゾーンが cloudflare provider に宣言されていません",
+            fqdn
+        )
+    })?;
+
+    let records: Vec<DnsRecordSpec> = zone
+        .list_dns_records()
+        .await?
+        .into_iter()
+        .filter(|r| r.name == fqdn)
+        .filter(|r| record_type.is_none_or(|t| r.record_type.eq_ignore_ascii_case(t)))
+        .collect();
+    if records.is_empty() {
+        return Err(anyhow::anyhow!("レコード {} が見つかりません", fqdn));
+    }
+
+    let mut skipped = 0;
+    for record in &records {
+        if !force && !is_managed(config, record) {
+            println!(
+                "  {} {} {} はプロジェクト {} の管理外です（--force で削除）",
+                "⚠".yellow(),
+                record.record_type,
+                record.name,
+                config.name
+            );
+            skipped += 1;
+            continue;
+        }
+        zone.delete_dns_record(&record.id).await?;
+        println!(
+            "{} {} {} を削除しました",
+            "✓".green(),
+            record.record_type,
+            record.name
+        );
+    }
+    if skipped == records.len() {
+        return Err(anyhow::anyhow!("削除したレコードはありません"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_cloud_cloudflare::{CloudflareDns, DnsConfig};
+
+    fn zones() -> DnsZones {
+        DnsZones::new(vec![CloudflareDns::new(DnsConfig {
+            api_token: "token".to_string(),
+            zone_id: "zone".to_string(),
+            domain: "example.com".to_string(),
+            subdomain_template: None,
+        })])
+    }
+
+    fn record(record_type: &str, content: &str, comment: Option<&str>) -> DnsRecordSpec {
+        DnsRecordSpec {
+            id: "1".to_string(),
+            name: "app.example.com".to_string(),
+            record_type: record_type.to_string(),
+            content: content.to_string(),
+            ttl: Some(1),
+            proxied: false,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_record_fqdn() {
+        let zones = zones();
+        assert_eq!(
+            record_fqdn(&zones, "api.example.com", None).unwrap(),
+            "api.example.com"
+        );
+        assert_eq!(
+            record_fqdn(&zones, "api", Some("stg.example.com")).unwrap(),
+            "api.stg.example.com"
+        );
+        assert!(record_fqdn(&zones, "api", None).is_err());
+    }
+
+    #[test]
+    fn test_points_at() {
+        let config = fleetflow_core::parse_kdl_string(
+            r#"
+            provider "cloudflare" {
+                domain "example.com"
+            }
+            server "web-1" {
+                ssh_host "203.0.113.10"
+            }
+            stage "prod"
+            "#,
+            "myapp".to_string(),
+        )
+        .unwrap();
+        let stage = Some("prod");
+        assert_eq!(
+            points_at(&config, stage, &record("A", "203.0.113.10", None)).as_deref(),
+            Some("web-1")
+        );
+        assert_eq!(
+            points_at(&config, stage, &record("CNAME", "web-1.example.com", None)).as_deref(),
+            Some("web-1")
+        );
+        assert_eq!(
+            points_at(
+                &config,
+                stage,
+                &record("TXT", "verify", Some("fleetflow:myapp:db-1"))
+            )
+            .as_deref(),
+            Some("db-1")
+        );
+        assert_eq!(
+            points_at(&config, stage, &record("A", "198.51.100.1", None)),
+            None
+        );
+        assert!(is_managed(
+            &config,
+            &record("A", "x", Some("fleetflow:myapp"))
+        ));
+        assert!(!is_managed(
+            &config,
+            &record("A", "x", Some("fleetflow:other"))
+        ));
+    }
+}
@@ -0,0 +1,454 @@
+//! `fleet doctor` — 実行環境の診断
+//!
+//! Docker のインストールとバージョン、ディスク空き容量、メモリ、ステージが公開する
+//! ポートの使用状況、プロジェクトのコンテナを確認し、pass / warn / fail で報告する。
+//! `--remote <server>` ではサーバー定義の接続先に SSH（tailscale ssh）で同じ確認を行い、
+//! 時刻のずれも確認する。fail が 1 つでもあれば非ゼロ終了する。
+
+use colored::Colorize;
+use fleetflow_cloud::ssh;
+use fleetflow_core::{Flow, Protocol};
+
+/// ディスク使用率の警告・失敗のしきい値（%）
+const DISK_WARN_PERCENT: u8 = 80;
+const DISK_FAIL_PERCENT: u8 = 90;
+/// 空きメモリ率の警告・失敗のしきい値（%）
+const MEMORY_WARN_PERCENT: u64 = 15;
+const MEMORY_FAIL_PERCENT: u64 = 5;
+/// 時刻のずれの警告・失敗のしきい値（秒）
+const SKEW_WARN_SECS: i64 = 5;
+const SKEW_FAIL_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Default)]
+struct Report {
+    levels: Vec<Level>,
+}
+
+impl Report {
+    fn record(&mut self, check: &str, level: Level, message: impl AsRef<str>) {
+        let mark = match level {
+            Level::Pass => "✓".green(),
+            Level::Warn => "⚠".yellow(),
+            Level::Fail => "✗".red(),
+        };
+        println!("  {} [{}] {}", mark, check, message.as_ref());
+        self.levels.push(level);
+    }
+
+    fn count(&self, level: Level) -> usize {
+        self.levels.iter().filter(|l| **l == level).count()
+    }
+}
+
+/// 診断対象のホスト
+enum Target<'a> {
+    Local,
+    Remote { host: &'a str, user: &'a str },
+}
+
+impl Target<'_> {
+    /// シェルスクリプトを実行し、成功すれば標準出力を返す
+    async fn run(&self, script: &str) -> Option<String> {
+        match self {
+            Target::Local => {
+                let output = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(script)
+                    .output()
+                    .await
+                    .ok()?;
+                output
+                    .status
+                    .success()
+                    .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Target::Remote { host, user } => {
+                let result = ssh::exec(host, user, script).await.ok()?;
+                result.success.then_some(result.stdout)
+            }
+        }
+    }
+}
+
+/// `df -Pk` の出力から使用率（%）を取り出す
+fn parse_df_use_percent(output: &str) -> Option<u8> {
+    output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(4)?
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
+
+/// `/proc/meminfo` から (MemTotal, MemAvailable) を MB で取り出す
+fn parse_meminfo(output: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| -> Option<u64> {
+        let line = output.lines().find(|l| l.starts_with(name))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb / 1024)
+    };
+    Some((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+/// `ss -ltnH` の出力から待ち受け中の TCP ポートを取り出す
+fn parse_listening_ports(output: &str) -> Vec<u16> {
+    let mut ports: Vec<u16> = output
+        .lines()
+        .filter_map(|line| {
+            let local = line.split_whitespace().nth(3)?;
+            local.rsplit_once(':')?.1.parse().ok()
+        })
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
+
+fn disk_level(percent: u8) -> Level {
+    if percent >= DISK_FAIL_PERCENT {
+        Level::Fail
+    } else if percent >= DISK_WARN_PERCENT {
+        Level::Warn
+    } else {
+        Level::Pass
+    }
+}
+
+fn memory_level(total_mb: u64, available_mb: u64) -> Level {
+    let percent = available_mb * 100 / total_mb.max(1);
+    if percent < MEMORY_FAIL_PERCENT {
+        Level::Fail
+    } else if percent < MEMORY_WARN_PERCENT {
+        Level::Warn
+    } else {
+        Level::Pass
+    }
+}
+
+fn skew_level(skew_secs: i64) -> Level {
+    match skew_secs.abs() {
+        s if s > SKEW_FAIL_SECS => Level::Fail,
+        s if s > SKEW_WARN_SECS => Level::Warn,
+        _ => Level::Pass,
+    }
+}
+
+/// 対象ホストで公開される TCP ホストポート
+fn published_ports(config: &Flow, stages: &[&str]) -> Vec<u16> {
+    let mut ports: Vec<u16> = stages
+        .iter()
+        .filter_map(|stage| config.stages.get(*stage))
+        .flat_map(|stage| stage.services.iter())
+        .filter_map(|name| config.services.get(name))
+        .flat_map(|service| service.ports.iter())
+        .filter(|port| port.protocol == Protocol::Tcp && port.host != 0)
+        .map(|port| port.host)
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
+
+pub async fn handle(
+    config: &Flow,
+    stage: Option<String>,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut report = Report::default();
+
+    // 対象ホストと、そのホストで動くステージ
+    let (target, stages): (Target, Vec<&str>) = match remote {
+        Some(server_name) => {
+            let server = config.servers.get(server_name).ok_or_else(|| {
+                anyhow::anyhow!("サーバー '{}' が定義されていません", server_name)
+            })?;
+            let mut stages: Vec<&str> = config
+                .stages
+                .iter()
+                .filter(|(_, s)| s.servers.iter().any(|s| s == server_name))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            stages.sort();
+            let target = Target::Remote {
+                host: server.ssh_host.as_deref().unwrap_or(server_name),
+                user: server.ssh_user.as_deref().unwrap_or("root"),
+            };
+            (target, stages)
+        }
+        None => {
+            // ステージが決まらなくても診断はできる（ポートの確認だけ省く）
+            let stage = crate::utils::determine_stage_name(stage, config)
+                .ok()
+                .and_then(|name| config.stages.get_key_value(&name))
+                .map(|(name, _)| name.as_str());
+            (Target::Local, stage.into_iter().collect())
+        }
+    };
+
+    match &target {
+        Target::Local => println!("{}", "ローカル環境を診断中...".green()),
+        Target::Remote { host, user } => {
+            println!(
+                "{}",
+                format!(
+                    "{} ({}@{}) を診断中...",
+                    remote.unwrap_or_default(),
+                    user,
+                    host
+                )
+                .green()
+            );
+        }
+    }
+    println!();
+
+    // SSH 接続（リモートのみ。接続できなければ以降の確認は行わない）
+    if let Target::Remote { host, user } = &target {
+        let sent_at = chrono::Utc::now().timestamp();
+        let Some(remote_now) = target.run("date +%s").await else {
+            report.record(
+                "ssh",
+                Level::Fail,
+                format!("{}@{} に接続できません", user, host),
+            );
+            return finish(&report);
+        };
+        report.record(
+            "ssh",
+            Level::Pass,
+            format!("{}@{} に接続できます", user, host),
+        );
+
+        // 往復時間の中間を基準に時刻のずれを測る
+        let local_now = (sent_at + chrono::Utc::now().timestamp()) / 2;
+        match remote_now.trim().parse::<i64>() {
+            Ok(remote_now) => {
+                let skew = remote_now - local_now;
+                report.record(
+                    "clock",
+                    skew_level(skew),
+                    format!("ローカルとの時刻のずれ {:+} 秒", skew),
+                );
+            }
+            Err(_) => report.record("clock", Level::Warn, "時刻を取得できません"),
+        }
+    }
+
+    // Docker
+    match target
+        .run("docker version --format '{{.Server.Version}}'")
+        .await
+    {
+        Some(version) => report.record(
+            "docker",
+            Level::Pass,
+            format!("Docker Engine {}", version.trim()),
+        ),
+        None if target.run("command -v docker").await.is_some() => report.record(
+            "docker",
+            Level::Fail,
+            "docker はインストール済みですが、デーモンに接続できません",
+        ),
+        None => report.record("docker", Level::Fail, "docker がインストールされていません"),
+    }
+
+    // Docker の接続先（fleet が実際に使うソケット）
+    if matches!(target, Target::Local) {
+        let socket = crate::utils::global_config().docker_socket.clone();
+        match fleetflow_container::connect_docker(socket.as_deref()).await {
+            Ok((_, endpoint)) => {
+                report.record("docker-endpoint", Level::Pass, endpoint.to_string())
+            }
+            Err(fleetflow_container::ContainerError::DockerConnectionFailed(tried)) => {
+                report.record(
+                    "docker-endpoint",
+                    Level::Fail,
+                    "接続できる Docker ソケットがありません（fleet config set docker-socket <path> で指定できます）",
+                );
+                for line in tried.lines() {
+                    println!("      {}", line.trim().dimmed());
+                }
+            }
+            Err(e) => report.record("docker-endpoint", Level::Fail, e.to_string()),
+        }
+    }
+
+    // ディスク（Docker のデータ領域、なければルート）
+    let df = target
+        .run("df -Pk /var/lib/docker 2>/dev/null || df -Pk /")
+        .await;
+    match df.as_deref().and_then(parse_df_use_percent) {
+        Some(percent) => report.record(
+            "disk",
+            disk_level(percent),
+            format!("ディスク使用率 {}%", percent),
+        ),
+        None => report.record("disk", Level::Warn, "ディスク使用率を取得できません"),
+    }
+
+    // メモリ（Linux のみ）
+    let meminfo = target.run("cat /proc/meminfo").await;
+    match meminfo.as_deref().and_then(parse_meminfo) {
+        Some((total, available)) => report.record(
+            "memory",
+            memory_level(total, available),
+            format!("空きメモリ {} MB / {} MB", available, total),
+        ),
+        None => report.record(
+            "memory",
+            Level::Warn,
+            "メモリ情報を取得できません（/proc/meminfo がありません）",
+        ),
+    }
+
+    // プロジェクトのコンテナ
+    let ps = target
+        .run(&format!(
+            "docker ps -a --filter label=fleetflow.project={} --format '{{{{.Names}}}}\t{{{{.State}}}}'",
+            config.name
+        ))
+        .await
+        .unwrap_or_default();
+    let containers: Vec<(&str, &str)> = ps
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+    let running = containers
+        .iter()
+        .filter(|(_, state)| *state == "running")
+        .count();
+    if containers.is_empty() {
+        report.record(
+            "containers",
+            Level::Warn,
+            format!("プロジェクト '{}' のコンテナがありません", config.name),
+        );
+    } else if running < containers.len() {
+        let stopped: Vec<&str> = containers
+            .iter()
+            .filter(|(_, state)| *state != "running")
+            .map(|(name, _)| *name)
+            .collect();
+        report.record(
+            "containers",
+            Level::Warn,
+            format!(
+                "{}/{} 個が稼働中（停止: {}）",
+                running,
+                containers.len(),
+                stopped.join(", ")
+            ),
+        );
+    } else {
+        report.record(
+            "containers",
+            Level::Pass,
+            format!("{} 個すべて稼働中", containers.len()),
+        );
+    }
+
+    // 公開ポート（プロジェクトのコンテナが動いていないのに使用中なら衝突）
+    let required = published_ports(config, &stages);
+    if !required.is_empty() {
+        match target.run("ss -ltnH").await {
+            Some(output) => {
+                let listening = parse_listening_ports(&output);
+                let busy: Vec<String> = required
+                    .iter()
+                    .filter(|p| listening.contains(p))
+                    .map(|p| p.to_string())
+                    .collect();
+                if busy.is_empty() || running > 0 {
+                    report.record(
+                        "ports",
+                        Level::Pass,
+                        format!(
+                            "公開ポート {} 個（待ち受け中 {} 個）",
+                            required.len(),
+                            busy.len()
+                        ),
+                    );
+                } else {
+                    report.record(
+                        "ports",
+                        Level::Warn,
+                        format!("別のプロセスが使用中: {}", busy.join(", ")),
+                    );
+                }
+            }
+            None => report.record(
+                "ports",
+                Level::Warn,
+                "待ち受けポートを取得できません（ss がありません）",
+            ),
+        }
+    }
+
+    finish(&report)
+}
+
+fn finish(report: &Report) -> anyhow::Result<()> {
+    println!();
+    println!(
+        "{} pass, {} warn, {} fail",
+        report.count(Level::Pass).to_string().green(),
+        report.count(Level::Warn).to_string().yellow(),
+        report.count(Level::Fail).to_string().red()
+    );
+    if report.count(Level::Fail) > 0 {
+        return Err(anyhow::anyhow!(
+            "{} 個の項目が失敗しました",
+            report.count(Level::Fail)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df_use_percent() {
+        let output = "Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+                      /dev/sda1        102400000 87040000  15360000      85% /\n";
+        assert_eq!(parse_df_use_percent(output), Some(85));
+        assert_eq!(disk_level(85), Level::Warn);
+        assert_eq!(disk_level(95), Level::Fail);
+        assert_eq!(disk_level(40), Level::Pass);
+        assert_eq!(parse_df_use_percent(""), None);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let output = "MemTotal:        4039100 kB\nMemFree:          201232 kB\nMemAvailable:     409600 kB\n";
+        assert_eq!(parse_meminfo(output), Some((3944, 400)));
+        assert_eq!(memory_level(3944, 400), Level::Warn);
+        assert_eq!(memory_level(4000, 100), Level::Fail);
+        assert_eq!(memory_level(4000, 2000), Level::Pass);
+    }
+
+    #[test]
+    fn test_parse_listening_ports() {
+        let output = "LISTEN 0      4096         0.0.0.0:80        0.0.0.0:*\n\
+                      LISTEN 0      128          0.0.0.0:22        0.0.0.0:*\n\
+                      LISTEN 0      4096            [::]:80           [::]:*\n";
+        assert_eq!(parse_listening_ports(output), vec![22, 80]);
+    }
+
+    #[test]
+    fn test_skew_level() {
+        assert_eq!(skew_level(2), Level::Pass);
+        assert_eq!(skew_level(-10), Level::Warn);
+        assert_eq!(skew_level(120), Level::Fail);
+    }
+}
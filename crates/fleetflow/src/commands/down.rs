@@ -21,7 +21,7 @@ pub async fn handle(
         .get(&stage_name)
         .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
 
-    // WS2: backend が Quadlet/Compose なら専用経路へ分岐
+    // WS2: backend が Quadlet/Compose/Kubernetes/Nomad なら専用経路へ分岐
     match stage_config.backend {
         fleetflow_core::Backend::Docker => {}
         fleetflow_core::Backend::Quadlet => {
@@ -37,6 +37,26 @@ pub async fn handle(
             )
             .await;
         }
+        fleetflow_core::Backend::Kubernetes => {
+            return crate::commands::cluster::down(
+                &fleetflow_container::KubernetesTarget,
+                config,
+                &stage_name,
+                stage_config,
+                remove,
+            )
+            .await;
+        }
+        fleetflow_core::Backend::Nomad => {
+            return crate::commands::cluster::down(
+                &fleetflow_container::NomadTarget,
+                config,
+                &stage_name,
+                stage_config,
+                remove,
+            )
+            .await;
+        }
     }
 
     println!();
@@ -53,72 +73,73 @@ pub async fn handle(
     println!("{}", "Dockerに接続中...".blue());
     let docker_conn = docker::init_docker_with_error_handling().await?;
 
-    // 各サービスを停止
-    for service_name in &stage_config.services {
+    // ステージのコンテナを fleetflow.* ラベルで探す（命名規則が変わっても停止できるように）
+    let mut containers: std::collections::BTreeMap<String, Vec<String>> =
+        std::collections::BTreeMap::new();
+    for container in
+        fleetflow_container::find_containers(&docker_conn, &config.name, Some(&stage_name), None)
+            .await?
+    {
+        if let (Some(service), Some(name)) = (
+            fleetflow_container::label(&container, fleetflow_container::SERVICE_LABEL),
+            fleetflow_container::summary_name(&container),
+        ) {
+            containers
+                .entry(service.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+    }
+
+    // 各サービスを停止（依存される側を後にする）
+    for service_name in &fleetflow_container::shutdown_order(&stage_config.services, config) {
         println!();
         println!(
             "{}",
             format!("■ {} を停止中...", service_name).yellow().bold()
         );
 
-        // OrbStack連携の命名規則を使用: {project}-{stage}-{service}
-        let container_name = format!("{}-{}-{}", config.name, stage_name, service_name);
+        let Some(names) = containers.remove(service_name) else {
+            println!("  ℹ コンテナが見つかりません");
+            continue;
+        };
+        // コンテナを停止（stop_signal / stop_grace_period を適用）
+        let stop_options = config
+            .services
+            .get(service_name)
+            .and_then(fleetflow_container::stop_container_options);
+        for container_name in &names {
+            stop_container(&docker_conn, container_name, stop_options.clone(), remove).await;
+        }
+    }
 
-        // コンテナを停止
-        match docker_conn
-            .stop_container(
-                &container_name,
-                None::<bollard::query_parameters::StopContainerOptions>,
+    // ステージから外れたサービスのコンテナも停止する
+    for (service_name, names) in &containers {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "■ {} を停止中...（ステージに定義がありません）",
+                service_name
             )
-            .await
-        {
-            Ok(_) => {
-                println!("  ✓ 停止完了");
-
-                // --remove フラグが指定されている場合は削除
-                if remove {
-                    match docker_conn
-                        .remove_container(
-                            &container_name,
-                            None::<bollard::query_parameters::RemoveContainerOptions>,
-                        )
-                        .await
-                    {
-                        Ok(_) => println!("  ✓ 削除完了"),
-                        Err(e) => println!("  ⚠ 削除エラー: {}", e),
-                    }
-                }
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 304, ..
-            }) => {
-                println!("  ℹ コンテナは既に停止しています");
-
-                // --remove フラグが指定されている場合は削除
-                if remove {
-                    match docker_conn
-                        .remove_container(
-                            &container_name,
-                            None::<bollard::query_parameters::RemoveContainerOptions>,
-                        )
-                        .await
-                    {
-                        Ok(_) => println!("  ✓ 削除完了"),
-                        Err(e) => println!("  ⚠ 削除エラー: {}", e),
-                    }
-                }
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 404, ..
-            }) => {
-                println!("  ℹ コンテナが見つかりません");
-            }
-            Err(e) => {
-                println!("  ⚠ 停止エラー: {}", e);
-            }
+            .yellow()
+            .bold()
+        );
+        for container_name in names {
+            stop_container(&docker_conn, container_name, None, remove).await;
         }
     }
 
+    // シークレットファイルを削除（tmpfs 上に残さない）
+    match fleetflow_container::remove_stage_secrets(&config.name, &stage_name) {
+        Ok(true) => {
+            println!();
+            println!("{}", "🔑 シークレットファイルを削除しました".dimmed());
+        }
+        Ok(false) => {}
+        Err(e) => println!("  ⚠ シークレットファイル削除エラー: {}", e),
+    }
+
     // ネットワーク削除 (#14)
     if remove {
         let network_name = fleetflow_container::get_network_name(&config.name, &stage_name);
@@ -160,3 +181,45 @@ pub async fn handle(
 
     Ok(())
 }
+
+/// コンテナを停止し、`remove` なら削除する
+async fn stop_container(
+    docker_conn: &bollard::Docker,
+    container_name: &str,
+    stop_options: Option<bollard::query_parameters::StopContainerOptions>,
+    remove: bool,
+) {
+    match docker_conn
+        .stop_container(container_name, stop_options)
+        .await
+    {
+        Ok(_) => println!("  ✓ 停止完了"),
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 304, ..
+        }) => println!("  ℹ コンテナは既に停止しています"),
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            println!("  ℹ コンテナが見つかりません");
+            return;
+        }
+        Err(e) => {
+            println!("  ⚠ 停止エラー: {}", e);
+            return;
+        }
+    }
+
+    // --remove フラグが指定されている場合は削除
+    if remove {
+        match docker_conn
+            .remove_container(
+                container_name,
+                None::<bollard::query_parameters::RemoveContainerOptions>,
+            )
+            .await
+        {
+            Ok(_) => println!("  ✓ 削除完了"),
+            Err(e) => println!("  ⚠ 削除エラー: {}", e),
+        }
+    }
+}
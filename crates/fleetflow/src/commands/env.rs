@@ -0,0 +1,419 @@
+//! `fleet env` — サービスの環境変数の確認と編集
+//!
+//! - `fleet env <service>`: 解決済みの環境変数を表示（センシティブな値はマスク）
+//! - `fleet env set <service> KEY=VALUE...`: flow.local.kdl（または flow.{stage}.kdl）へ書き込み
+//!
+//! 変更はオーバーライドファイルに残るため、何を変えたかを後から追跡できる。
+
+use crate::docker;
+use crate::utils;
+use crate::utils::is_sensitive_key;
+use colored::Colorize;
+use kdl::{KdlDocument, KdlEntry, KdlNode};
+use std::path::{Path, PathBuf};
+
+/// 解決済みの環境変数を表示する
+pub fn handle_show(
+    config: &fleetflow_core::Flow,
+    service_name: &str,
+    stage: Option<String>,
+    reveal: bool,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let service = config
+        .services
+        .get(service_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", service_name))?;
+
+    println!(
+        "{}",
+        format!(
+            "サービス '{}' の環境変数 (ステージ: {})",
+            service_name, stage_name
+        )
+        .bold()
+    );
+
+    if service.environment.is_empty() && service.secrets.is_empty() {
+        println!("  {}", "(環境変数はありません)".dimmed());
+        return Ok(());
+    }
+
+    let mut keys: Vec<_> = service.environment.keys().collect();
+    keys.sort();
+    for key in keys {
+        let value = &service.environment[key];
+        let shown = if !reveal && is_sensitive_key(key) {
+            "***".dimmed().to_string()
+        } else {
+            value.clone()
+        };
+        println!("  {}={}", key.cyan(), shown);
+    }
+
+    // シークレットは環境変数ではなくファイルとして渡される
+    for mount in &service.secrets {
+        println!(
+            "  {} {} → {}",
+            "(secret)".dimmed(),
+            mount.source.cyan(),
+            mount.target_path().display()
+        );
+    }
+
+    Ok(())
+}
+
+/// 環境変数をオーバーライドファイルへ書き込む
+pub async fn handle_set(
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    service_name: &str,
+    assignments: &[String],
+    stage: Option<String>,
+    stage_file: bool,
+    recreate: bool,
+) -> anyhow::Result<()> {
+    if !config.services.contains_key(service_name) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' が見つかりません",
+            service_name
+        ));
+    }
+
+    let vars = assignments
+        .iter()
+        .map(|s| parse_assignment(s))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let stage_name = if stage_file || recreate {
+        Some(utils::determine_stage_name(stage, config)?)
+    } else {
+        stage
+    };
+    let target = if stage_file {
+        override_file_path(project_root, stage_name.as_deref())
+    } else {
+        override_file_path(project_root, None)
+    };
+
+    let content = if target.exists() {
+        std::fs::read_to_string(&target)?
+    } else {
+        String::new()
+    };
+    let (updated, previous) = set_env_in_kdl(&content, service_name, &vars)?;
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&target, updated)?;
+
+    println!(
+        "{}",
+        format!("✓ {} を更新しました", target.display()).green()
+    );
+    for ((key, value), old) in vars.iter().zip(previous) {
+        let mask = |v: &str| {
+            if is_sensitive_key(key) {
+                "***".to_string()
+            } else {
+                v.to_string()
+            }
+        };
+        match old {
+            Some(old) => println!("  {}: {} → {}", key.cyan(), mask(&old), mask(value)),
+            None => println!("  {}: {} (追加)", key.cyan(), mask(value)),
+        }
+    }
+
+    if recreate {
+        let stage_name = stage_name.expect("recreate 時はステージ名を決定済み");
+        recreate_service(project_root, &stage_name, service_name).await?;
+    } else {
+        println!(
+            "{}",
+            "  反映するには --recreate を付けるか fleet up を実行してください".dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// `KEY=VALUE` をパースする
+pub(crate) fn parse_assignment(s: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("KEY=VALUE 形式で指定してください: {}", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow::anyhow!("環境変数名が空です: {}", s));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// 書き込み先のオーバーライドファイル
+///
+/// 既存ファイル（プロジェクト直下 → `.fleetflow/`）があればそれを使い、
+/// 無ければプロジェクト直下に作成する。
+pub(crate) fn override_file_path(project_root: &Path, stage: Option<&str>) -> PathBuf {
+    let file_name = match stage {
+        Some(stage) => format!("flow.{}.kdl", stage),
+        None => "flow.local.kdl".to_string(),
+    };
+    let root_file = project_root.join(&file_name);
+    let fleetflow_file = project_root.join(".fleetflow").join(&file_name);
+    if !root_file.exists() && fleetflow_file.exists() {
+        fleetflow_file
+    } else {
+        root_file
+    }
+}
+
+/// KDL ドキュメント内の `service "<name>" { env { ... } }` に値を設定する
+///
+/// サービスや env ブロックが無ければ追加する。戻り値の 2 要素目は
+/// 各キーの変更前の値（新規追加の場合は `None`）。
+pub(crate) fn set_env_in_kdl(
+    content: &str,
+    service_name: &str,
+    vars: &[(String, String)],
+) -> anyhow::Result<(String, Vec<Option<String>>)> {
+    let mut doc: KdlDocument = content
+        .parse()
+        .map_err(|e| anyhow::anyhow!("KDL のパースに失敗しました: {}", e))?;
+
+    let service_idx = doc.nodes().iter().rposition(|n| {
+        n.name().value() == "service"
+            && n.entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string())
+                == Some(service_name)
+    });
+    let service_idx = match service_idx {
+        Some(idx) => idx,
+        None => {
+            let mut node = KdlNode::new("service");
+            node.push(KdlEntry::new(service_name));
+            doc.nodes_mut().push(node);
+            doc.nodes().len() - 1
+        }
+    };
+
+    let mut previous = Vec::with_capacity(vars.len());
+    let service = &mut doc.nodes_mut()[service_idx];
+    {
+        let children = service.ensure_children();
+        let env_idx = children.nodes().iter().rposition(|n| {
+            matches!(n.name().value(), "env" | "environment") && n.children().is_some()
+        });
+        let env_idx = match env_idx {
+            Some(idx) => idx,
+            None => {
+                children.nodes_mut().push(KdlNode::new("env"));
+                children.nodes().len() - 1
+            }
+        };
+        let env = children.nodes_mut()[env_idx].ensure_children();
+
+        for (key, value) in vars {
+            let mut node = KdlNode::new(key.as_str());
+            node.push(KdlEntry::new(value.as_str()));
+
+            match env
+                .nodes()
+                .iter()
+                .position(|n| n.name().value() == key.as_str())
+            {
+                Some(idx) => {
+                    previous.push(
+                        env.nodes()[idx]
+                            .entries()
+                            .first()
+                            .and_then(|e| e.value().as_string())
+                            .map(str::to_string),
+                    );
+                    env.nodes_mut()[idx] = node;
+                }
+                None => {
+                    previous.push(None);
+                    env.nodes_mut().push(node);
+                }
+            }
+        }
+    }
+    service.autoformat();
+
+    Ok((doc.to_string(), previous))
+}
+
+/// 設定を読み直してサービスのコンテナを作り直す
+pub(crate) async fn recreate_service(
+    project_root: &Path,
+    stage_name: &str,
+    service_name: &str,
+) -> anyhow::Result<()> {
+    println!();
+    println!(
+        "{}",
+        format!("▶ {} を再作成中...", service_name).green().bold()
+    );
+
+    let config = fleetflow_core::load_project_from_root_with_stage(project_root, Some(stage_name))?;
+    let service = config
+        .services
+        .get(service_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", service_name))?;
+    let (service, _) = fleetflow_container::materialize_configs(
+        project_root,
+        &config,
+        stage_name,
+        service_name,
+        service,
+    )?;
+    let service =
+        fleetflow_container::materialize_secrets(&config, stage_name, service_name, &service)?;
+
+    let (container_config, create_options) = fleetflow_container::service_to_container_config(
+        service_name,
+        &service,
+        stage_name,
+        &config,
+    );
+    let container_name = create_options.name.clone().unwrap_or_default();
+
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let network_name = fleetflow_container::get_network_name(&config.name, stage_name);
+    docker::ensure_network(&docker_conn, &network_name).await?;
+
+    match docker_conn
+        .remove_container(
+            &container_name,
+            Some(bollard::query_parameters::RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+    {
+        Ok(_) => println!("  ✓ 既存コンテナを削除しました"),
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {}
+        Err(e) => return Err(anyhow::anyhow!("コンテナ削除に失敗: {}", e)),
+    }
+
+    docker::ensure_container_running(
+        &docker_conn,
+        &container_name,
+        container_config,
+        create_options,
+        &docker::registry_auth(&config, stage_name)?,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn env_of(content: &str, service: &str) -> std::collections::HashMap<String, String> {
+        let flow = fleetflow_core::parse_kdl_string(content, "test".to_string()).unwrap();
+        flow.services[service].environment.clone()
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("LOG_LEVEL=debug").unwrap(),
+            ("LOG_LEVEL".to_string(), "debug".to_string())
+        );
+        assert_eq!(
+            parse_assignment("URL=postgres://u@h/db?a=b").unwrap(),
+            ("URL".to_string(), "postgres://u@h/db?a=b".to_string())
+        );
+        assert_eq!(
+            parse_assignment("EMPTY=").unwrap(),
+            ("EMPTY".to_string(), String::new())
+        );
+        assert!(parse_assignment("NOVALUE").is_err());
+        assert!(parse_assignment("=value").is_err());
+    }
+
+    #[test]
+    fn test_set_env_creates_service_and_env_block() {
+        let (out, previous) = set_env_in_kdl("", "api", &vars(&[("LOG_LEVEL", "debug")])).unwrap();
+
+        assert_eq!(previous, vec![None]);
+        assert_eq!(env_of(&out, "api")["LOG_LEVEL"], "debug");
+    }
+
+    #[test]
+    fn test_set_env_replaces_existing_value() {
+        let content = r#"
+service "api" {
+    image "api:latest"
+    env {
+        LOG_LEVEL "info"
+        PORT "3000"
+    }
+}
+"#;
+        let (out, previous) = set_env_in_kdl(
+            content,
+            "api",
+            &vars(&[("LOG_LEVEL", "debug"), ("NEW_KEY", "x")]),
+        )
+        .unwrap();
+
+        assert_eq!(previous, vec![Some("info".to_string()), None]);
+        let env = env_of(&out, "api");
+        assert_eq!(env["LOG_LEVEL"], "debug");
+        assert_eq!(env["PORT"], "3000");
+        assert_eq!(env["NEW_KEY"], "x");
+        assert!(out.contains("image \"api:latest\""));
+    }
+
+    #[test]
+    fn test_set_env_leaves_other_services_untouched() {
+        let content = r#"
+service "db" {
+    env {
+        POSTGRES_DB "app"
+    }
+}
+"#;
+        let (out, _) = set_env_in_kdl(content, "api", &vars(&[("A", "1")])).unwrap();
+
+        assert_eq!(env_of(&out, "db")["POSTGRES_DB"], "app");
+        assert_eq!(env_of(&out, "api")["A"], "1");
+    }
+
+    #[test]
+    fn test_override_file_path() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            override_file_path(dir.path(), None),
+            dir.path().join("flow.local.kdl")
+        );
+        assert_eq!(
+            override_file_path(dir.path(), Some("prod")),
+            dir.path().join("flow.prod.kdl")
+        );
+
+        std::fs::create_dir_all(dir.path().join(".fleetflow")).unwrap();
+        std::fs::write(dir.path().join(".fleetflow/flow.local.kdl"), "").unwrap();
+        assert_eq!(
+            override_file_path(dir.path(), None),
+            dir.path().join(".fleetflow/flow.local.kdl")
+        );
+    }
+}
@@ -27,7 +27,7 @@ pub async fn handle(
     }
 
     // コンテナ名
-    let container_name = format!("{}-{}-{}", config.name, stage_name, service);
+    let container_name = config.container_name(&stage_name, &service);
 
     // コマンドが省略された場合は /bin/sh
     let cmd: Vec<String> = if command.is_empty() {
@@ -0,0 +1,173 @@
+//! `fleet host-service` — server 定義の `host_service` を systemd ユニットとして導入
+//!
+//! ユニット生成は `fleetflow-container::host_service`、リモート実行は
+//! `fleetflow-cloud::ssh`（tailscale ssh）に集約されており、本モジュールはその presenter。
+//! 接続先は `ssh_host`（未設定時はサーバー名を Tailscale ノード名として使う）。
+
+use colored::Colorize;
+use fleetflow_cloud::ssh;
+use fleetflow_container::host_service::{
+    generate_host_service_unit, host_service_unit_name, host_service_unit_path,
+};
+use fleetflow_core::{Flow, ServerResource};
+
+/// 対象サーバー（名前順、`server` 指定時はその 1 台）
+fn target_servers<'a>(
+    config: &'a Flow,
+    server: Option<&str>,
+) -> anyhow::Result<Vec<(&'a str, &'a ServerResource)>> {
+    if let Some(name) = server {
+        let (name, resource) = config
+            .servers
+            .get_key_value(name)
+            .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が見つかりません", name))?;
+        return Ok(vec![(name.as_str(), resource)]);
+    }
+    let mut servers: Vec<(&str, &ServerResource)> = config
+        .servers
+        .iter()
+        .filter(|(_, s)| !s.host_services.is_empty())
+        .map(|(name, s)| (name.as_str(), s))
+        .collect();
+    servers.sort_by_key(|(name, _)| *name);
+    Ok(servers)
+}
+
+/// 接続先（ssh_host 未設定時はサーバー名）とユーザー
+pub(crate) fn ssh_target<'a>(name: &'a str, server: &'a ServerResource) -> (&'a str, &'a str) {
+    (
+        server.ssh_host.as_deref().unwrap_or(name),
+        server.ssh_user.as_deref().unwrap_or("root"),
+    )
+}
+
+/// root 以外で接続する場合は sudo を付ける
+pub(crate) fn privileged(user: &str, command: &str) -> String {
+    if user == "root" {
+        command.to_string()
+    } else {
+        format!("sudo sh -c '{}'", command.replace('\'', "'\\''"))
+    }
+}
+
+/// ホストサービスのユニットを配置して enable / (re)start する
+pub async fn apply(config: &Flow, server: Option<&str>, dry_run: bool) -> anyhow::Result<()> {
+    let servers = target_servers(config, server)?;
+    if servers.iter().all(|(_, s)| s.host_services.is_empty()) {
+        println!(
+            "{}",
+            "host_service が定義されたサーバーはありません".yellow()
+        );
+        return Ok(());
+    }
+
+    let mut applied = 0;
+    for (name, resource) in servers {
+        let (host, user) = ssh_target(name, resource);
+        println!("{} {} ({}@{})", "▶".blue(), name.cyan().bold(), user, host);
+
+        let mut services: Vec<_> = resource.host_services.iter().collect();
+        services.sort_by_key(|(service_name, _)| *service_name);
+        for (service_name, host_service) in services {
+            let unit = generate_host_service_unit(&config.name, name, service_name, host_service);
+            let unit_name = host_service_unit_name(service_name);
+
+            if dry_run {
+                println!(
+                    "{}",
+                    format!("[dry-run] {}:", host_service_unit_path(service_name)).yellow()
+                );
+                for line in unit.lines() {
+                    println!("  {line}");
+                }
+                continue;
+            }
+
+            // 非 root では /etc に直接書けないため一時ファイル経由で配置
+            let path = host_service_unit_path(service_name);
+            let staged = if user == "root" {
+                path.clone()
+            } else {
+                format!("/tmp/{unit_name}")
+            };
+            ssh::write_file(host, user, &staged, unit.as_bytes()).await?;
+
+            let mut script = String::new();
+            if staged != path {
+                script.push_str(&format!(
+                    "install -m 644 {staged} {path} && rm -f {staged} && "
+                ));
+            }
+            script.push_str(&format!(
+                "systemctl daemon-reload && systemctl enable {unit_name} && systemctl restart {unit_name}"
+            ));
+            let result = ssh::exec(host, user, &privileged(user, &script)).await?;
+            if !result.success {
+                return Err(anyhow::anyhow!(
+                    "{} の {} の起動に失敗しました: {}",
+                    name,
+                    unit_name,
+                    result.stderr.trim()
+                ));
+            }
+            println!("  {} {}", "✓".green(), unit_name);
+            applied += 1;
+        }
+    }
+
+    if !dry_run {
+        println!();
+        println!(
+            "{}",
+            format!("✓ {} 個のホストサービスを反映しました", applied)
+                .green()
+                .bold()
+        );
+    }
+    Ok(())
+}
+
+/// ホストサービスの稼働状態を表示する
+pub async fn status(config: &Flow, server: Option<&str>) -> anyhow::Result<()> {
+    let servers = target_servers(config, server)?;
+
+    println!("{:<20} {:<24} {}", "SERVER", "SERVICE", "STATUS");
+    for (name, resource) in servers {
+        let (host, user) = ssh_target(name, resource);
+        let mut services: Vec<&String> = resource.host_services.keys().collect();
+        services.sort();
+        for service_name in services {
+            let unit_name = host_service_unit_name(service_name);
+            // is-active は非稼働時に非ゼロ終了するため stdout で判定
+            let state =
+                match ssh::exec(host, user, &format!("systemctl is-active {unit_name}")).await {
+                    Ok(result) => result.stdout.trim().to_string(),
+                    Err(e) => format!("unreachable ({e})"),
+                };
+            let colored_state = match state.as_str() {
+                "active" => state.green(),
+                "activating" | "reloading" => state.yellow(),
+                _ => state.red(),
+            };
+            println!("{:<20} {:<24} {}", name, service_name, colored_state);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_privileged_wraps_non_root() {
+        assert_eq!(
+            privileged("root", "systemctl daemon-reload"),
+            "systemctl daemon-reload"
+        );
+        assert_eq!(
+            privileged("ubuntu", "echo 'a'"),
+            "sudo sh -c 'echo '\\''a'\\'''"
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! `fleet image` — イメージの一覧・削除・書き出し・読み込み
+//!
+//! `ls` / `rm` はプロジェクトの命名規則（`{registry}/{project}-{stage}:tag`、
+//! サービスの `image`、ビルドキャッシュ）に合うローカルイメージを扱う。
+//!
+//! レジストリにアクセスできない環境（エアギャップ）向けに、ステージが使う
+//! イメージを `docker save` で 1 つの tar にまとめ、対象ホストで `docker load` する。
+//! 読み込み先はローカル、または `--server` 指定で SSH 経由のサーバー。
+
+use crate::docker;
+use crate::utils;
+use colored::Colorize;
+use std::path::Path;
+use std::process::Command;
+
+/// ステージ（の指定サービス）が使うイメージ一覧（重複なし・ソート済み）
+///
+/// 静的サイトサービスはコンテナイメージを持たないため除外する。
+pub fn stage_images(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    services: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let stage_config = config
+        .stages
+        .get(stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+    let target_services = utils::filter_services(&stage_config.services, services, stage_name)?;
+
+    let mut images: Vec<String> = target_services
+        .iter()
+        .filter_map(|name| {
+            let service = config.services.get(name)?;
+            (!service.is_static()).then(|| fleetflow_container::resolve_image(name, service))
+        })
+        .collect();
+    images.sort();
+    images.dedup();
+    Ok(images)
+}
+
+/// ローカルに存在しないイメージを返す
+pub async fn missing_images(
+    docker_conn: &bollard::Docker,
+    images: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let mut missing = Vec::new();
+    for image in images {
+        match docker_conn.inspect_image(image).await {
+            Ok(_) => {}
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => missing.push(image.clone()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(missing)
+}
+
+/// プロジェクトのイメージとみなすリポジトリ名（レジストリなし）
+///
+/// `stage` 指定時はそのステージのみ、省略時は全ステージ分。
+fn project_repositories(
+    config: &fleetflow_core::Flow,
+    stage: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let stage_names: Vec<&String> = match stage {
+        Some(name) => vec![
+            config
+                .stages
+                .get_key_value(name)
+                .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", name))?
+                .0,
+        ],
+        None => config.stages.keys().collect(),
+    };
+
+    let mut repos = vec![format!("{}-buildcache", config.name)];
+    for stage_name in stage_names {
+        repos.push(format!("{}-{}", config.name, stage_name));
+        for image in stage_images(config, stage_name, &[])? {
+            repos.push(fleetflow_build::split_image_tag(&image).0);
+        }
+    }
+    repos.sort();
+    repos.dedup();
+    Ok(repos)
+}
+
+/// リポジトリ名がプロジェクトのものか（レジストリ接頭辞は問わない）
+fn is_project_repository(repo: &str, repositories: &[String]) -> bool {
+    repositories
+        .iter()
+        .any(|r| repo == r || repo.ends_with(&format!("/{}", r)))
+}
+
+/// 稼働中のコンテナが使っているイメージ（名前と ID）
+async fn images_in_use(docker_conn: &bollard::Docker) -> anyhow::Result<Vec<String>> {
+    let containers = docker_conn
+        .list_containers(Some(bollard::query_parameters::ListContainersOptions {
+            all: false,
+            ..Default::default()
+        }))
+        .await?;
+    Ok(containers
+        .into_iter()
+        .flat_map(|c| [c.image, c.image_id])
+        .flatten()
+        .collect())
+}
+
+fn short_id(id: &str) -> &str {
+    let id = id.split_once(':').map_or(id, |(_, hex)| hex);
+    &id[..id.len().min(12)]
+}
+
+/// `fleet image ls` — プロジェクトのイメージを一覧表示
+pub async fn handle_ls(config: &fleetflow_core::Flow, stage: Option<&str>) -> anyhow::Result<()> {
+    let repositories = project_repositories(config, stage)?;
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let in_use = images_in_use(&docker_conn).await?;
+
+    let mut images = docker_conn
+        .list_images(Some(bollard::query_parameters::ListImagesOptions {
+            all: false,
+            ..Default::default()
+        }))
+        .await?;
+    images.sort_by(|a, b| b.created.cmp(&a.created));
+
+    let mut rows = 0;
+    println!(
+        "{:<48} {:<20} {:<14} {:>10} {:<17} {}",
+        "REPOSITORY", "TAG", "DIGEST", "SIZE", "CREATED", "IN USE"
+    );
+    for image in &images {
+        let used = in_use.iter().any(|u| u == &image.id);
+        for repo_tag in &image.repo_tags {
+            let (repo, tag) = fleetflow_build::split_image_tag(repo_tag);
+            if !is_project_repository(&repo, &repositories) {
+                continue;
+            }
+            let digest = image
+                .repo_digests
+                .iter()
+                .find(|d| d.starts_with(&format!("{}@", repo)))
+                .and_then(|d| d.split_once('@'))
+                .map_or_else(|| short_id(&image.id), |(_, digest)| short_id(digest));
+            let created = chrono::DateTime::from_timestamp(image.created, 0)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let used = used || in_use.iter().any(|u| u == repo_tag);
+            println!(
+                "{:<48} {:<20} {:<14} {:>10} {:<17} {}",
+                repo,
+                tag,
+                digest,
+                format!("{:.1} MB", image.size as f64 / 1024.0 / 1024.0),
+                created,
+                if used { "yes".green() } else { "-".dimmed() }
+            );
+            rows += 1;
+        }
+    }
+    if rows == 0 {
+        println!("{}", "プロジェクトのイメージはありません".yellow());
+    }
+    Ok(())
+}
+
+/// `fleet image rm` — プロジェクトのイメージを削除
+///
+/// 稼働中のコンテナが使うイメージと、プロジェクトの命名規則に合わないイメージは削除しない。
+pub async fn handle_rm(
+    config: &fleetflow_core::Flow,
+    images: &[String],
+    force: bool,
+) -> anyhow::Result<()> {
+    let repositories = project_repositories(config, None)?;
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let in_use = images_in_use(&docker_conn).await?;
+
+    let mut refused = Vec::new();
+    for image in images {
+        let (repo, _) = fleetflow_build::split_image_tag(image);
+        if !is_project_repository(&repo, &repositories) {
+            eprintln!(
+                "  {} {}: プロジェクト '{}' のイメージではありません",
+                "✗".red(),
+                image,
+                config.name
+            );
+            refused.push(image.as_str());
+            continue;
+        }
+        let id = match docker_conn.inspect_image(image).await {
+            Ok(info) => info.id.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("  {} {}: {}", "✗".red(), image, e);
+                refused.push(image.as_str());
+                continue;
+            }
+        };
+        if in_use.iter().any(|u| u == image || u == &id) {
+            eprintln!(
+                "  {} {}: 稼働中のコンテナが使用しています",
+                "✗".red(),
+                image
+            );
+            refused.push(image.as_str());
+            continue;
+        }
+
+        let options = bollard::query_parameters::RemoveImageOptions {
+            force,
+            ..Default::default()
+        };
+        match docker_conn.remove_image(image, Some(options), None).await {
+            Ok(_) => println!("  {} {}", "✓".green(), image),
+            Err(e) => {
+                eprintln!("  {} {}: {}", "✗".red(), image, e);
+                refused.push(image.as_str());
+            }
+        }
+    }
+
+    if refused.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} 個のイメージを削除できませんでした: {}",
+            refused.len(),
+            refused.join(", ")
+        ))
+    }
+}
+
+/// `fleet image save` — ステージのイメージを tar に書き出す
+pub async fn handle_save(
+    config: &fleetflow_core::Flow,
+    stage: Option<String>,
+    services: &[String],
+    output: &Path,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    println!("{}", "イメージを書き出し中...".green());
+    println!("ステージ: {}", stage_name.cyan());
+
+    let images = stage_images(config, &stage_name, services)?;
+    if images.is_empty() {
+        println!("{}", "書き出すイメージがありません".yellow());
+        return Ok(());
+    }
+
+    println!();
+    println!("{}", format!("イメージ ({} 個):", images.len()).bold());
+    for image in &images {
+        println!("  • {}", image.cyan());
+    }
+
+    // ローカルにないイメージは先に pull しておく
+    println!();
+    println!("{}", "Dockerに接続中...".blue());
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let registry_auth = docker::registry_auth(config, &stage_name)?;
+    for image in missing_images(&docker_conn, &images).await? {
+        docker::pull_image(&docker_conn, &image, &registry_auth).await?;
+    }
+
+    println!();
+    println!("  ↓ docker save → {}", output.display().to_string().cyan());
+    let status = Command::new("docker")
+        .arg("save")
+        .arg("-o")
+        .arg(output)
+        .args(&images)
+        .status()
+        .map_err(|e| anyhow::anyhow!("docker save の実行に失敗しました: {}", e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "docker save が失敗しました (exit {:?})",
+            status.code()
+        ));
+    }
+
+    let size = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+    println!();
+    println!(
+        "{}",
+        format!(
+            "✓ {} 個のイメージを書き出しました ({:.1} MB)",
+            images.len(),
+            size as f64 / 1024.0 / 1024.0
+        )
+        .green()
+        .bold()
+    );
+    println!(
+        "  対象ホストで: {} の後 {}",
+        format!("fleet image load {}", output.display()).cyan(),
+        "fleet deploy --offline".cyan()
+    );
+
+    Ok(())
+}
+
+/// `fleet image load` — tar からイメージを読み込む
+///
+/// `server` 指定時は設定ファイルのサーバー定義（ssh_host / ssh_user）を使い、
+/// `DOCKER_HOST=ssh://...` としてサーバーの Docker デーモンに読み込む。
+pub async fn handle_load(input: &Path, server: Option<&str>) -> anyhow::Result<()> {
+    if !input.exists() {
+        return Err(anyhow::anyhow!(
+            "ファイルが見つかりません: {}",
+            input.display()
+        ));
+    }
+
+    let mut cmd = Command::new("docker");
+    cmd.arg("load").arg("-i").arg(input);
+
+    match server {
+        Some(server_name) => {
+            let project_root = fleetflow_core::find_project_root()?;
+            let config = fleetflow_core::load_project_from_root(&project_root)?;
+            let docker_host = config
+                .servers
+                .get(server_name)
+                .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", server_name))?
+                .docker_host()
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "サーバー '{}' に ssh_host が設定されていません",
+                        server_name
+                    )
+                })?;
+            println!(
+                "{}",
+                format!(
+                    "イメージを {} ({}) に読み込み中...",
+                    server_name, docker_host
+                )
+                .green()
+            );
+            cmd.env("DOCKER_HOST", docker_host);
+        }
+        None => println!("{}", "イメージを読み込み中...".green()),
+    }
+
+    let status = cmd
+        .status()
+        .map_err(|e| anyhow::anyhow!("docker load の実行に失敗しました: {}", e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "docker load が失敗しました (exit {:?})",
+            status.code()
+        ));
+    }
+
+    println!();
+    println!("{}", "✓ イメージを読み込みました".green().bold());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Flow, Service, ServiceType, Stage};
+    use std::collections::HashMap;
+
+    fn flow() -> Flow {
+        let mut services = HashMap::new();
+        services.insert(
+            "api".to_string(),
+            Service {
+                image: Some("ghcr.io/acme/api".to_string()),
+                version: Some("1.2.0".to_string()),
+                ..Default::default()
+            },
+        );
+        services.insert(
+            "db".to_string(),
+            Service {
+                image: Some("postgres:16".to_string()),
+                ..Default::default()
+            },
+        );
+        services.insert(
+            "worker".to_string(),
+            Service {
+                image: Some("postgres:16".to_string()),
+                ..Default::default()
+            },
+        );
+        services.insert(
+            "site".to_string(),
+            Service {
+                service_type: Some(ServiceType::Static),
+                ..Default::default()
+            },
+        );
+
+        let mut stages = HashMap::new();
+        stages.insert(
+            "prod".to_string(),
+            Stage {
+                services: vec![
+                    "api".to_string(),
+                    "db".to_string(),
+                    "worker".to_string(),
+                    "site".to_string(),
+                ],
+                ..Default::default()
+            },
+        );
+
+        Flow {
+            name: "acme".to_string(),
+            services,
+            stages,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stage_images_dedups_and_skips_static() {
+        let images = stage_images(&flow(), "prod", &[]).unwrap();
+        assert_eq!(images, vec!["ghcr.io/acme/api:1.2.0", "postgres:16"]);
+    }
+
+    #[test]
+    fn test_project_repositories_match_naming_scheme() {
+        let repos = project_repositories(&flow(), Some("prod")).unwrap();
+        assert_eq!(
+            repos,
+            vec![
+                "acme-buildcache",
+                "acme-prod",
+                "ghcr.io/acme/api",
+                "postgres"
+            ]
+        );
+        assert!(is_project_repository("ghcr.io/acme/acme-prod", &repos));
+        assert!(is_project_repository("acme-buildcache", &repos));
+        assert!(!is_project_repository("acme-dev", &repos));
+        assert!(!is_project_repository("redis", &repos));
+        assert!(project_repositories(&flow(), Some("dev")).is_err());
+    }
+
+    #[test]
+    fn test_short_id() {
+        assert_eq!(short_id("sha256:0123456789abcdef0123"), "0123456789ab");
+        assert_eq!(short_id("abc"), "abc");
+    }
+
+    #[test]
+    fn test_stage_images_with_service_filter() {
+        let images = stage_images(&flow(), "prod", &["api".to_string()]).unwrap();
+        assert_eq!(images, vec!["ghcr.io/acme/api:1.2.0"]);
+
+        assert!(stage_images(&flow(), "prod", &["missing".to_string()]).is_err());
+        assert!(stage_images(&flow(), "dev", &[]).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! `fleet inventory` — 管理対象サーバーを Ansible インベントリ / SSH config として出力
+//!
+//! fleet.kdl（cloud.kdl）の `server` 定義から、既存の自動化ツールが
+//! FleetFlow で構築したホストを扱えるようにする。`ssh_host` の無いサーバーは出力しない。
+
+use colored::Colorize;
+use fleetflow_core::{Flow, ServerResource};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InventoryFormat {
+    /// Ansible の INI 形式インベントリ
+    Ansible,
+    /// ~/.ssh/config に Include できる形式
+    #[value(alias = "ssh_config")]
+    SshConfig,
+}
+
+/// ssh_host を持つサーバーを名前順に返す
+fn reachable_servers(flow: &Flow) -> BTreeMap<&str, (&ServerResource, &str)> {
+    flow.servers
+        .iter()
+        .filter_map(|(name, server)| {
+            let host = server.ssh_host.as_deref()?;
+            Some((name.as_str(), (server, host)))
+        })
+        .collect()
+}
+
+/// Ansible のグループ名に使えない文字を `_` に置き換える
+fn group_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn render_ansible(flow: &Flow) -> String {
+    let servers = reachable_servers(flow);
+    let mut out = format!("# fleet inventory: {}\n", flow.name);
+
+    out.push_str("\n[all]\n");
+    for (name, (server, host)) in &servers {
+        let _ = write!(
+            out,
+            "{} ansible_host={} ansible_user={} fleetflow_provider={}",
+            name,
+            host,
+            server.ssh_user.as_deref().unwrap_or("root"),
+            server.provider
+        );
+        if !server.ssh_keys.is_empty() {
+            let _ = write!(out, " fleetflow_ssh_keys={}", server.ssh_keys.join(","));
+        }
+        if let Some(path) = &server.deploy_path {
+            let _ = write!(out, " fleetflow_deploy_path={}", path);
+        }
+        out.push('\n');
+    }
+
+    // ステージとタグをグループとして出力
+    let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (stage_name, stage) in &flow.stages {
+        for server in &stage.servers {
+            if servers.contains_key(server.as_str()) {
+                groups
+                    .entry(group_name(stage_name))
+                    .or_default()
+                    .push(server);
+            }
+        }
+    }
+    for (name, (server, _)) in &servers {
+        for tag in &server.tags {
+            groups
+                .entry(format!("tag_{}", group_name(tag)))
+                .or_default()
+                .push(name);
+        }
+    }
+    for (group, mut members) in groups {
+        members.sort_unstable();
+        members.dedup();
+        let _ = write!(out, "\n[{}]\n", group);
+        for member in members {
+            let _ = writeln!(out, "{}", member);
+        }
+    }
+
+    out
+}
+
+fn render_ssh_config(flow: &Flow) -> String {
+    let mut out = format!("# fleet inventory: {}\n", flow.name);
+    for (name, (server, host)) in reachable_servers(flow) {
+        let _ = write!(
+            out,
+            "\nHost {}\n    HostName {}\n    User {}\n",
+            name,
+            host,
+            server.ssh_user.as_deref().unwrap_or("root")
+        );
+        if !server.tags.is_empty() {
+            let _ = writeln!(out, "    # tags: {}", server.tags.join(", "));
+        }
+    }
+    out
+}
+
+pub fn handle(flow: &Flow, format: InventoryFormat, output: Option<&Path>) -> anyhow::Result<()> {
+    let skipped: Vec<&str> = {
+        let mut names: Vec<&str> = flow
+            .servers
+            .iter()
+            .filter(|(_, s)| s.ssh_host.is_none())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    };
+    if !skipped.is_empty() {
+        eprintln!(
+            "{}",
+            format!(
+                "⚠ ssh_host が未設定のため出力しません: {}",
+                skipped.join(", ")
+            )
+            .yellow()
+        );
+    }
+
+    let rendered = match format {
+        InventoryFormat::Ansible => render_ansible(flow),
+        InventoryFormat::SshConfig => render_ssh_config(flow),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .map_err(|e| anyhow::anyhow!("{} への書き込みに失敗: {}", path.display(), e))?;
+            eprintln!(
+                "{}",
+                format!("✓ {} に書き出しました", path.display()).green()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::Stage;
+    use std::collections::HashMap;
+
+    fn flow() -> Flow {
+        let web = ServerResource {
+            provider: "sakura-cloud".to_string(),
+            ssh_host: Some("153.0.0.10".to_string()),
+            ssh_keys: vec!["deploy".to_string()],
+            tags: vec!["web-tier".to_string()],
+            ..Default::default()
+        };
+        let db = ServerResource {
+            provider: "sakura-cloud".to_string(),
+            ssh_host: Some("153.0.0.11".to_string()),
+            ssh_user: Some("ubuntu".to_string()),
+            ..Default::default()
+        };
+        let pending = ServerResource::with_provider("sakura-cloud");
+        let prod = Stage {
+            servers: vec!["web-1".to_string(), "db-1".to_string(), "new-1".to_string()],
+            ..Default::default()
+        };
+        Flow {
+            name: "myapp".to_string(),
+            stages: HashMap::from([("prod".to_string(), prod)]),
+            servers: HashMap::from([
+                ("web-1".to_string(), web),
+                ("db-1".to_string(), db),
+                ("new-1".to_string(), pending),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_ansible() {
+        let out = render_ansible(&flow());
+        assert!(out.contains(
+            "db-1 ansible_host=153.0.0.11 ansible_user=ubuntu fleetflow_provider=sakura-cloud\n"
+        ));
+        assert!(out.contains("web-1 ansible_host=153.0.0.10 ansible_user=root"));
+        assert!(out.contains("fleetflow_ssh_keys=deploy"));
+        assert!(out.contains("\n[prod]\ndb-1\nweb-1\n"));
+        assert!(out.contains("\n[tag_web_tier]\nweb-1\n"));
+        assert!(!out.contains("new-1"));
+    }
+
+    #[test]
+    fn test_render_ssh_config() {
+        let out = render_ssh_config(&flow());
+        assert!(out.contains("\nHost db-1\n    HostName 153.0.0.11\n    User ubuntu\n"));
+        assert!(out.contains("\nHost web-1\n    HostName 153.0.0.10\n    User root\n"));
+        assert!(!out.contains("new-1"));
+    }
+}
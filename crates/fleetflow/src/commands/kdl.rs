@@ -0,0 +1,82 @@
+//! `fleet kdl` — KDL 設定ファイルの操作
+//!
+//! `fleet kdl fmt` は設定ファイルを `fleetflow_core::format_kdl_string` で正規化する。
+//! `--check` では書き換えず、整形が必要なファイルがあれば非ゼロ終了する（CI 向け）。
+
+use colored::Colorize;
+use fleetflow_core::FormatOptions;
+use std::path::{Path, PathBuf};
+
+/// フォーマット対象の KDL ファイル（ファイル指定が無い場合）
+fn project_files(project_root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let discovered = fleetflow_core::discover_files(project_root)?;
+    let mut files: Vec<PathBuf> = Vec::new();
+    files.extend(discovered.root);
+    files.extend(discovered.cloud);
+    files.extend(discovered.services);
+    files.extend(discovered.stages);
+    files.extend(discovered.variables);
+    files.extend(discovered.local_override);
+    files.dedup();
+    Ok(files)
+}
+
+/// `fleet kdl fmt`
+pub fn handle_fmt(
+    project_root: &Path,
+    files: &[PathBuf],
+    check: bool,
+    sort_env: bool,
+) -> anyhow::Result<()> {
+    let files = if files.is_empty() {
+        project_files(project_root)?
+    } else {
+        files.to_vec()
+    };
+    let options = FormatOptions { sort_env };
+
+    let mut unformatted = Vec::new();
+    for file in &files {
+        let original = std::fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("{} の読み込みに失敗: {}", file.display(), e))?;
+        let formatted = fleetflow_core::format_kdl_string(&original, options)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+        if formatted == original {
+            continue;
+        }
+
+        let display = file.strip_prefix(project_root).unwrap_or(file).display();
+        if check {
+            println!("  {} {}", "~".yellow(), display);
+        } else {
+            std::fs::write(file, &formatted)
+                .map_err(|e| anyhow::anyhow!("{} の書き込みに失敗: {}", file.display(), e))?;
+            println!("  {} {}", "✓".green(), display);
+        }
+        unformatted.push(file);
+    }
+
+    if unformatted.is_empty() {
+        println!(
+            "{}",
+            format!("✓ {} ファイルは整形済みです", files.len())
+                .green()
+                .bold()
+        );
+        return Ok(());
+    }
+    if check {
+        return Err(anyhow::anyhow!(
+            "{} ファイルの整形が必要です（fleet kdl fmt で整形できます）",
+            unformatted.len()
+        ));
+    }
+    println!();
+    println!(
+        "{}",
+        format!("✓ {} ファイルを整形しました", unformatted.len())
+            .green()
+            .bold()
+    );
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! `fleet kill` — コンテナにシグナルを送る
+//!
+//! 設定の再読み込み（nginx の SIGHUP 等）や強制停止に使う。
+//! シグナル省略時は Docker と同じ SIGKILL。
+
+use crate::docker;
+use crate::utils;
+use colored::Colorize;
+
+/// シグナル名を正規化（`hup` → `SIGHUP`、数値はそのまま）
+fn normalize_signal(signal: &str) -> anyhow::Result<String> {
+    let signal = signal.trim().to_uppercase();
+    if signal.is_empty()
+        || !signal
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-')
+    {
+        return Err(anyhow::anyhow!(
+            "シグナルが不正です: '{}'（例: SIGHUP, USR1, 9）",
+            signal
+        ));
+    }
+    if signal.chars().all(|c| c.is_ascii_digit()) || signal.starts_with("SIG") {
+        Ok(signal)
+    } else {
+        Ok(format!("SIG{}", signal))
+    }
+}
+
+pub async fn handle(
+    config: &fleetflow_core::Flow,
+    stage: Option<String>,
+    service: Option<String>,
+    signal: &str,
+) -> anyhow::Result<()> {
+    let signal = normalize_signal(signal)?;
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    // service 未指定（--all）の場合はステージの全コンテナサービス
+    let targets: Vec<&String> = match &service {
+        Some(svc) => {
+            if !stage_config.services.contains(svc) {
+                return Err(anyhow::anyhow!(
+                    "サービス '{}' はステージ '{}' に含まれていません。\n利用可能なサービス: {}",
+                    svc,
+                    stage_name,
+                    stage_config.services.join(", ")
+                ));
+            }
+            vec![svc]
+        }
+        None => stage_config
+            .services
+            .iter()
+            .filter(|name| {
+                config
+                    .services
+                    .get(*name)
+                    .is_none_or(|service| !service.is_static())
+            })
+            .collect(),
+    };
+
+    println!("ステージ: {}", stage_name.cyan());
+    println!(
+        "{}",
+        format!("{} を {} 個のサービスに送信中...", signal, targets.len()).green()
+    );
+    println!();
+
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let mut sent = 0;
+
+    for svc_name in targets {
+        let container_name = config.container_name(&stage_name, svc_name);
+        match docker_conn
+            .kill_container(
+                &container_name,
+                Some(bollard::query_parameters::KillContainerOptions {
+                    signal: signal.clone(),
+                }),
+            )
+            .await
+        {
+            Ok(_) => {
+                println!("  {} {} ← {}", "✓".green(), svc_name.cyan(), signal);
+                sent += 1;
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {
+                println!("  ℹ {}: コンテナが存在しません", svc_name);
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 409, ..
+            }) => {
+                println!("  ℹ {}: コンテナは実行されていません", svc_name);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ {} 個のコンテナに {} を送信しました", sent, signal)
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_signal() {
+        assert_eq!(normalize_signal("SIGHUP").unwrap(), "SIGHUP");
+        assert_eq!(normalize_signal("hup").unwrap(), "SIGHUP");
+        assert_eq!(normalize_signal("usr1").unwrap(), "SIGUSR1");
+        assert_eq!(normalize_signal("9").unwrap(), "9");
+        assert_eq!(normalize_signal("SIGRTMIN+3").unwrap(), "SIGRTMIN+3");
+        assert!(normalize_signal("").is_err());
+        assert!(normalize_signal("SIG HUP").is_err());
+    }
+}
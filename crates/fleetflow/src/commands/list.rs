@@ -0,0 +1,84 @@
+//! `fleet list` — ステージ・サービス名の一覧
+//!
+//! シェル補完やスクリプト、エディタ連携から有効な対象を列挙するためのコマンド。
+//! プレーン出力は 1 行 1 名前、`--json` は構造化された一覧を出力する。
+
+use serde_json::json;
+
+/// `fleet list stages`
+pub fn handle_stages(config: &fleetflow_core::Flow, json: bool) -> anyhow::Result<()> {
+    let names = config.stage_names();
+
+    if json {
+        let stages: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let stage = &config.stages[*name];
+                json!({
+                    "name": name,
+                    "services": stage.services,
+                    "servers": stage.servers,
+                    "backend": stage.backend.as_str(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&stages)?);
+    } else {
+        for name in names {
+            println!("{}", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// `fleet list services [--stage s]`
+pub fn handle_services(
+    config: &fleetflow_core::Flow,
+    stage: Option<&str>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let names = config.service_names(stage).ok_or_else(|| {
+        anyhow::anyhow!(
+            "ステージ '{}' が見つかりません（定義済み: {}）",
+            stage.unwrap_or_default(),
+            config.stage_names().join(", ")
+        )
+    })?;
+
+    if json {
+        let services: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let service = config.services.get(*name);
+                let ports: Vec<_> = service
+                    .map(|s| s.ports.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|p| {
+                        json!({
+                            "name": p.name,
+                            "host": p.host,
+                            "container": p.container,
+                            "protocol": p.app_protocol.map_or(p.protocol.as_str(), |a| a.as_str()),
+                            "url": p.app_protocol.and_then(|_| p.url()),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": name,
+                    "image": service.and_then(|s| s.image.as_deref()),
+                    "stages": config.stages_of_service(name),
+                    "ports": ports,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&services)?);
+    } else {
+        for name in names {
+            println!("{}", name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,372 @@
+//! `fleet registry` — ローカルの Docker レジストリ（registry:2）を起動・管理する
+//!
+//! エアギャップ環境や CI に近い手順をローカルで繰り返すときに、ビルドしたイメージの
+//! push / pull を手元で完結させる。コンテナは `fleetflow.local-registry` ラベルで管理し、
+//! データは名前付きボリュームに保存するため作り直しても消えない。
+//! `--proxy` を付けると上流レジストリの pull-through キャッシュとして動く（push はできない）。
+//!
+//! ステージ（またはプロジェクト）の `registry "localhost:5000"` のように手元のレジストリを
+//! 指定している場合、`fleet build --push` と `fleet up` は必要に応じて自動で起動する。
+
+use crate::docker;
+use bollard::models::{
+    ContainerCreateBody, HostConfig, PortBinding, RestartPolicy, RestartPolicyNameEnum,
+};
+use bollard::query_parameters::{CreateContainerOptions, ListContainersOptions};
+use colored::Colorize;
+use fleetflow_container::{ContainerError, VERSION_LABEL, poll_until};
+use fleetflow_core::WaitConfig;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const REGISTRY_IMAGE: &str = "registry:2";
+/// ローカルレジストリのコンテナに付けるラベル（値は公開ポート）
+const LOCAL_REGISTRY_LABEL: &str = "fleetflow.local-registry";
+/// pull-through キャッシュの上流 URL
+const PROXY_LABEL: &str = "fleetflow.local-registry.proxy";
+/// 起動後に応答を待つ時間
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// コンテナ名（データのボリューム名も同じ）
+fn container_name(port: u16) -> String {
+    format!("fleetflow-registry-{}", port)
+}
+
+/// `localhost:5000` / `127.0.0.1:5000/acme` のような手元のレジストリならポートを返す
+pub(crate) fn local_registry_port(registry: &str) -> Option<u16> {
+    let host = fleetflow_core::registry_host(registry);
+    let (name, port) = host.rsplit_once(':')?;
+    if !matches!(name, "localhost" | "127.0.0.1") {
+        return None;
+    }
+    port.parse().ok()
+}
+
+/// レジストリコンテナの設定
+fn registry_container_config(
+    port: u16,
+    proxy: Option<&str>,
+) -> (ContainerCreateBody, CreateContainerOptions) {
+    let name = container_name(port);
+    let mut labels = HashMap::from([
+        (LOCAL_REGISTRY_LABEL.to_string(), port.to_string()),
+        (
+            VERSION_LABEL.to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ),
+    ]);
+    let mut env = Vec::new();
+    if let Some(proxy) = proxy {
+        labels.insert(PROXY_LABEL.to_string(), proxy.to_string());
+        env.push(format!("REGISTRY_PROXY_REMOTEURL={}", proxy));
+    }
+
+    let config = ContainerCreateBody {
+        image: Some(REGISTRY_IMAGE.to_string()),
+        env: Some(env),
+        labels: Some(labels),
+        exposed_ports: Some(vec!["5000/tcp".to_string()]),
+        host_config: Some(HostConfig {
+            port_bindings: Some(HashMap::from([(
+                "5000/tcp".to_string(),
+                Some(vec![PortBinding {
+                    host_ip: Some("127.0.0.1".to_string()),
+                    host_port: Some(port.to_string()),
+                }]),
+            )])),
+            binds: Some(vec![format!("{}:/var/lib/registry", name)]),
+            restart_policy: Some(RestartPolicy {
+                name: Some(RestartPolicyNameEnum::UNLESS_STOPPED),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let options = CreateContainerOptions {
+        name: Some(name),
+        ..Default::default()
+    };
+    (config, options)
+}
+
+/// レジストリの API が応答するか
+async fn is_serving(client: &reqwest::Client, port: u16) -> bool {
+    client
+        .get(format!("http://127.0.0.1:{}/v2/", port))
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success())
+}
+
+fn http_client() -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .build()?)
+}
+
+/// レジストリコンテナを起動する（既にあれば再利用、`proxy` が変わっていれば作り直す）
+async fn ensure_running(
+    docker_conn: &bollard::Docker,
+    port: u16,
+    proxy: Option<&str>,
+) -> anyhow::Result<()> {
+    let name = container_name(port);
+    match docker_conn
+        .inspect_container(
+            &name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+    {
+        Ok(info) => {
+            let current_proxy = info
+                .config
+                .as_ref()
+                .and_then(|c| c.labels.as_ref())
+                .and_then(|labels| labels.get(PROXY_LABEL))
+                .map(String::as_str);
+            if current_proxy != proxy {
+                println!("  ↻ pull-through キャッシュの設定が変わったため作り直します");
+                docker_conn
+                    .remove_container(
+                        &name,
+                        Some(bollard::query_parameters::RemoveContainerOptions {
+                            force: true,
+                            ..Default::default()
+                        }),
+                    )
+                    .await?;
+                create_and_start(docker_conn, port, proxy).await?;
+            } else if info.state.and_then(|s| s.running) == Some(true) {
+                println!("  ✓ {} は起動済みです", name.cyan());
+                return Ok(());
+            } else {
+                docker_conn
+                    .start_container(
+                        &name,
+                        None::<bollard::query_parameters::StartContainerOptions>,
+                    )
+                    .await?;
+                println!("  ✓ {} を起動しました", name.cyan());
+            }
+        }
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => create_and_start(docker_conn, port, proxy).await?,
+        Err(e) => return Err(e.into()),
+    }
+
+    let client = http_client()?;
+    let client = &client;
+    poll_until(READY_TIMEOUT, &WaitConfig::default(), || async move {
+        is_serving(client, port).await.then_some(())
+    })
+    .await
+    .ok_or_else(|| ContainerError::WaitTimeout {
+        target: name.clone(),
+        condition: "registry API".to_string(),
+        timeout_secs: READY_TIMEOUT.as_secs(),
+    })?;
+    Ok(())
+}
+
+async fn create_and_start(
+    docker_conn: &bollard::Docker,
+    port: u16,
+    proxy: Option<&str>,
+) -> anyhow::Result<()> {
+    let (config, options) = registry_container_config(port, proxy);
+    docker::ensure_container_running(
+        docker_conn,
+        &container_name(port),
+        config,
+        options,
+        &fleetflow_build::RegistryAuth::new(),
+    )
+    .await
+}
+
+/// `registry` が手元のレジストリを指していれば、応答しない場合に起動する
+///
+/// 自分で立てたレジストリが既に応答していれば何もしない。
+pub(crate) async fn ensure_for_registry(
+    docker_conn: &bollard::Docker,
+    registry: Option<&str>,
+) -> anyhow::Result<()> {
+    let Some(port) = registry.and_then(local_registry_port) else {
+        return Ok(());
+    };
+    if is_serving(&http_client()?, port).await {
+        return Ok(());
+    }
+    println!();
+    println!(
+        "{}",
+        format!("ローカルレジストリ (localhost:{}) を起動中...", port).blue()
+    );
+    ensure_running(docker_conn, port, None).await
+}
+
+/// `fleet registry serve`
+pub async fn handle_serve(port: u16, proxy: Option<&str>) -> anyhow::Result<()> {
+    println!(
+        "{}",
+        format!("ローカルレジストリ (localhost:{}) を起動中...", port)
+            .green()
+            .bold()
+    );
+    if let Some(proxy) = proxy {
+        println!("  pull-through キャッシュ: {}", proxy.cyan());
+    }
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    ensure_running(&docker_conn, port, proxy).await?;
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ localhost:{} でレジストリが動いています", port)
+            .green()
+            .bold()
+    );
+    if proxy.is_some() {
+        println!(
+            "  {} localhost:{}/library/nginx:alpine のように pull すると上流の結果をキャッシュします（push は不可）",
+            "ℹ".blue(),
+            port
+        );
+    } else {
+        println!(
+            "  {} ステージに {} を書くと build --push / up がこのレジストリを使います",
+            "ℹ".blue(),
+            format!("registry \"localhost:{}\"", port).cyan()
+        );
+    }
+    Ok(())
+}
+
+/// `fleet registry stop`
+pub async fn handle_stop(port: u16, purge: bool) -> anyhow::Result<()> {
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let name = container_name(port);
+    match docker_conn
+        .remove_container(
+            &name,
+            Some(bollard::query_parameters::RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+    {
+        Ok(_) => println!("{} {} を停止・削除しました", "✓".green(), name.cyan()),
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => println!("{} {} は存在しません", "ℹ".blue(), name.cyan()),
+        Err(e) => return Err(e.into()),
+    }
+
+    if purge {
+        match docker_conn
+            .remove_volume(
+                &name,
+                None::<bollard::query_parameters::RemoveVolumeOptions>,
+            )
+            .await
+        {
+            Ok(_) => println!("{} ボリューム {} を削除しました", "✓".green(), name.cyan()),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// `fleet registry status`
+pub async fn handle_status() -> anyhow::Result<()> {
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let containers = docker_conn
+        .list_containers(Some(ListContainersOptions {
+            all: true,
+            filters: Some(HashMap::from([(
+                "label".to_string(),
+                vec![LOCAL_REGISTRY_LABEL.to_string()],
+            )])),
+            ..Default::default()
+        }))
+        .await?;
+    if containers.is_empty() {
+        println!(
+            "{} ローカルレジストリはありません（{} で起動）",
+            "ℹ".blue(),
+            "fleet registry serve".cyan()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "{:<28} {:<16} {:<10} {}",
+            "NAME", "ADDRESS", "STATE", "PROXY"
+        )
+        .bold()
+    );
+    for container in &containers {
+        let port = fleetflow_container::label(container, LOCAL_REGISTRY_LABEL).unwrap_or("?");
+        let state = container
+            .state
+            .as_ref()
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let state = if state == "running" {
+            state.green()
+        } else {
+            state.dimmed()
+        };
+        println!(
+            "{:<28} {:<16} {:<10} {}",
+            fleetflow_container::summary_name(container).unwrap_or_default(),
+            format!("localhost:{}", port),
+            state,
+            fleetflow_container::label(container, PROXY_LABEL).unwrap_or("-")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_registry_port() {
+        assert_eq!(local_registry_port("localhost:5000"), Some(5000));
+        assert_eq!(local_registry_port("127.0.0.1:5001/acme"), Some(5001));
+        assert_eq!(local_registry_port("http://localhost:5000"), Some(5000));
+        assert_eq!(local_registry_port("ghcr.io/acme"), None);
+        assert_eq!(local_registry_port("registry.example.com:5000"), None);
+        assert_eq!(local_registry_port("localhost"), None);
+    }
+
+    #[test]
+    fn test_registry_container_config() {
+        let (config, options) =
+            registry_container_config(5000, Some("https://registry-1.docker.io"));
+        assert_eq!(options.name.as_deref(), Some("fleetflow-registry-5000"));
+        let labels = config.labels.unwrap();
+        assert_eq!(labels[LOCAL_REGISTRY_LABEL], "5000");
+        assert_eq!(labels[PROXY_LABEL], "https://registry-1.docker.io");
+        assert_eq!(
+            config.env.unwrap(),
+            vec!["REGISTRY_PROXY_REMOTEURL=https://registry-1.docker.io"]
+        );
+        let host_config = config.host_config.unwrap();
+        assert_eq!(
+            host_config.binds.unwrap(),
+            vec!["fleetflow-registry-5000:/var/lib/registry"]
+        );
+    }
+}
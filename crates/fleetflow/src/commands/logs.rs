@@ -1,7 +1,209 @@
 use crate::docker;
 use crate::utils;
+use bollard::container::LogOutput;
 use colored::Colorize;
+use futures_util::stream::StreamExt;
+use regex::Regex;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+/// up / deploy で起動したサービスのログを保存するディレクトリ
+const CAPTURE_DIR: &str = ".fleetflow/logs";
+/// 残すキャプチャの数（古いものから削除）
+const CAPTURE_KEEP: usize = 20;
+/// 1 サービスあたりに保存する行数
+const CAPTURE_LINES: usize = 1000;
+
+/// `--level` で指定するログレベル（指定レベル以上を表示）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// ログ行からレベルを推定する（ヒューリスティック）
+    ///
+    /// 以下の一般的な形式を認識する:
+    /// - JSON: `"level":"error"`
+    /// - logfmt: `level=warn` / `lvl=warn` / `severity=ERROR`
+    /// - テキスト: `[ERROR]` / ` WARN ` / `ERROR:` 等の大文字トークン
+    pub fn detect(line: &str) -> Option<Self> {
+        static KEYED: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+        static TOKEN: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+
+        let keyed = KEYED.get_or_init(|| {
+            Regex::new(r#"(?i)"?\b(?:level|lvl|severity)"?\s*[=:]\s*"?([a-z]+)"#).unwrap()
+        });
+        if let Some(caps) = keyed.captures(line)
+            && let Some(level) = Self::from_name(&caps[1])
+        {
+            return Some(level);
+        }
+
+        let token = TOKEN.get_or_init(|| {
+            Regex::new(r"\b(TRACE|DEBUG|INFO|WARN|WARNING|ERROR|ERR|FATAL|CRITICAL|PANIC)\b")
+                .unwrap()
+        });
+        token
+            .captures(line)
+            .and_then(|caps| Self::from_name(&caps[1]))
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "trace" | "debug" => Some(Self::Debug),
+            "info" | "notice" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" | "err" | "fatal" | "critical" | "crit" | "panic" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// ログ行のフィルタ（`--grep` / `--invert` / `--level`）
+///
+/// `fleet logs -f | grep` ではサービスごとの色付きプレフィックスが失われるため、
+/// 表示前のストリームに対して適用する。
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    grep: Option<Regex>,
+    invert: bool,
+    level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    pub fn new(grep: Option<&str>, invert: bool, level: Option<LogLevel>) -> anyhow::Result<Self> {
+        let grep = grep
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("--grep の正規表現が不正です: {}", e))?;
+        Ok(Self {
+            grep,
+            invert,
+            level,
+        })
+    }
+
+    /// 行を表示するかどうか
+    ///
+    /// `--invert` は `--grep` の一致判定のみを反転する。`--level` 指定時、
+    /// レベルを推定できない行は表示しない。
+    pub fn matches(&self, line: &str) -> bool {
+        if let Some(min) = self.level
+            && !LogLevel::detect(line).is_some_and(|level| level >= min)
+        {
+            return false;
+        }
+        match &self.grep {
+            Some(re) => re.is_match(line) != self.invert,
+            None => true,
+        }
+    }
+}
+
+/// ログの出力先（端末、または `--output` のファイル）
+pub(crate) enum LogSink {
+    Terminal,
+    File {
+        path: PathBuf,
+        writer: std::io::BufWriter<std::fs::File>,
+        lines: usize,
+    },
+}
+
+impl LogSink {
+    pub(crate) fn open(output: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(path) = output else {
+            return Ok(Self::Terminal);
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("{} を作成できません: {}", path.display(), e))?;
+        Ok(Self::File {
+            path: path.to_path_buf(),
+            writer: std::io::BufWriter::new(file),
+            lines: 0,
+        })
+    }
+
+    /// 1 行を書き出す（ファイルには色を付けない）
+    pub(crate) fn line(
+        &mut self,
+        service: &str,
+        color: colored::Color,
+        stderr: bool,
+        line: &str,
+    ) -> std::io::Result<()> {
+        match self {
+            Self::Terminal => {
+                let prefix = format!("[{}]", service).color(color);
+                if stderr {
+                    println!("{} {} {}", prefix, "stderr:".red(), line);
+                } else {
+                    println!("{} {}", prefix, line);
+                }
+                Ok(())
+            }
+            Self::File { writer, lines, .. } => {
+                *lines += 1;
+                if stderr {
+                    writeln!(writer, "[{}] stderr: {}", service, line)
+                } else {
+                    writeln!(writer, "[{}] {}", service, line)
+                }
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Terminal => Ok(()),
+            Self::File { writer, .. } => writer.flush(),
+        }
+    }
+
+    /// 書き出しを終える。ファイルに書き出した場合は行数を表示して `true` を返す
+    pub(crate) fn finish(mut self) -> anyhow::Result<bool> {
+        self.flush()?;
+        let Self::File { path, lines, .. } = &self else {
+            return Ok(false);
+        };
+        println!(
+            "{} {} 行を {} に書き出しました",
+            "✓".green(),
+            lines,
+            path.display()
+        );
+        Ok(true)
+    }
+}
+
+/// ログのチャンクを (stderr か, 本文) に分ける
+fn split_output(output: LogOutput) -> Option<(bool, String)> {
+    match output {
+        LogOutput::StdOut { message } | LogOutput::Console { message } => {
+            Some((false, String::from_utf8_lossy(&message).into_owned()))
+        }
+        LogOutput::StdErr { message } => {
+            Some((true, String::from_utf8_lossy(&message).into_owned()))
+        }
+        LogOutput::StdIn { .. } => None,
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn handle(
     config: &fleetflow_core::Flow,
     project_root: &std::path::Path,
@@ -10,12 +212,15 @@ pub async fn handle(
     lines: usize,
     follow: bool,
     since: Option<String>,
+    filter: &LogFilter,
+    output: Option<&Path>,
 ) -> anyhow::Result<()> {
+    let mut sink = LogSink::open(output)?;
     println!("{}", "ログを取得中...".blue());
     utils::print_loaded_config_files(project_root);
 
     // Docker接続
-    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let mut docker_conn = docker::init_docker_with_error_handling().await?;
 
     // ステージ名の決定（他コマンドと同じロジックを使用）
     let stage_name = utils::determine_stage_name(stage, config)?;
@@ -33,11 +238,7 @@ pub async fn handle(
     // --since の計算（現在時刻 - duration → Unix timestamp）
     let since_ts = if let Some(ref since_str) = since {
         let duration_secs = utils::parse_duration(since_str)?;
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let ts = i32::try_from(now.saturating_sub(duration_secs)).unwrap_or(i32::MAX);
+        let ts = i32::try_from(unix_now().saturating_sub(duration_secs)).unwrap_or(i32::MAX);
         println!("  ℹ {}前からのログを表示", since_str);
         ts
     } else {
@@ -55,12 +256,13 @@ pub async fn handle(
         colored::Color::Blue,
     ];
 
+    let mut last_seen = unix_now();
     for (idx, service_name) in target_services.iter().enumerate() {
-        // OrbStack連携の命名規則を使用: {project}-{stage}-{service}
-        let container_name = format!("{}-{}-{}", config.name, stage_name, service_name);
+        // コンテナ名（container_name / container_name_template、既定は {project}-{stage}-{service}）
+        let container_name = config.container_name(&stage_name, service_name);
         let service_color = colors[idx % colors.len()];
 
-        if !follow {
+        if !follow && matches!(sink, LogSink::Terminal) {
             println!(
                 "{}",
                 format!("=== {} のログ ===", service_name)
@@ -69,7 +271,7 @@ pub async fn handle(
             );
         }
 
-        let options = bollard::query_parameters::LogsOptions {
+        let mut options = bollard::query_parameters::LogsOptions {
             follow,
             stdout: true,
             stderr: true,
@@ -79,43 +281,31 @@ pub async fn handle(
             ..Default::default()
         };
 
-        use bollard::container::LogOutput;
-        use futures_util::stream::StreamExt;
-
-        let mut log_stream = docker_conn.logs(&container_name, Some(options));
+        let mut log_stream = docker_conn.logs(&container_name, Some(options.clone()));
 
         while let Some(log) = log_stream.next().await {
             match log {
                 Ok(output) => {
-                    let prefix = format!("[{}]", service_name).color(service_color);
-
-                    match output {
-                        LogOutput::StdOut { message } => {
-                            let msg = String::from_utf8_lossy(&message);
-                            for line in msg.lines() {
-                                if !line.is_empty() {
-                                    println!("{} {}", prefix, line);
-                                }
-                            }
-                        }
-                        LogOutput::StdErr { message } => {
-                            let msg = String::from_utf8_lossy(&message);
-                            for line in msg.lines() {
-                                if !line.is_empty() {
-                                    println!("{} {} {}", prefix, "stderr:".red(), line);
-                                }
-                            }
+                    last_seen = unix_now();
+                    let Some((stderr, message)) = split_output(output) else {
+                        continue;
+                    };
+                    for line in message.lines() {
+                        if !line.is_empty() && filter.matches(line) {
+                            sink.line(service_name, service_color, stderr, line)?;
                         }
-                        LogOutput::Console { message } => {
-                            let msg = String::from_utf8_lossy(&message);
-                            for line in msg.lines() {
-                                if !line.is_empty() {
-                                    println!("{} {}", prefix, line);
-                                }
-                            }
-                        }
-                        LogOutput::StdIn { .. } => {}
                     }
+                    if follow {
+                        sink.flush()?;
+                    }
+                }
+                Err(e) if follow => {
+                    // デーモンの再起動などで切れた場合は再接続し、最後に受け取った時刻から追跡を再開する
+                    eprintln!("  ⚠ ログ取得エラー ({}): {}", service_name, e);
+                    docker_conn = docker::reconnect_docker().await?;
+                    options.tail = "all".to_string();
+                    options.since = i32::try_from(last_seen).unwrap_or(i32::MAX);
+                    log_stream = docker_conn.logs(&container_name, Some(options.clone()));
                 }
                 Err(e) => {
                     eprintln!("  ⚠ ログ取得エラー ({}): {}", service_name, e);
@@ -124,11 +314,15 @@ pub async fn handle(
             }
         }
 
-        if !follow {
+        if !follow && matches!(sink, LogSink::Terminal) {
             println!();
         }
     }
 
+    if sink.finish()? {
+        return Ok(());
+    }
+
     if follow {
         println!();
         println!("{}", "Ctrl+C でログ追跡を終了".dimmed());
@@ -136,3 +330,194 @@ pub async fn handle(
 
     Ok(())
 }
+
+/// 起動したサービスのログを `.fleetflow/logs/<timestamp>/<service>.log` に保存する
+///
+/// デプロイに失敗してコンテナが消えた後でも原因を追えるよう、up / deploy の最後に呼ぶ。
+/// 保存の失敗は警告にとどめ、up / deploy 自体の結果は変えない。
+pub(crate) async fn capture_startup_logs<S: AsRef<str>>(
+    docker_conn: &bollard::Docker,
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    stage_name: &str,
+    services: &[S],
+) {
+    match write_capture(docker_conn, config, project_root, stage_name, services).await {
+        Ok(Some(dir)) => println!(
+            "  {} 起動ログを保存しました: {}",
+            "ℹ".blue(),
+            dir.display().to_string().dimmed()
+        ),
+        Ok(None) => {}
+        Err(e) => eprintln!("  {} 起動ログを保存できませんでした: {}", "⚠".yellow(), e),
+    }
+}
+
+async fn write_capture<S: AsRef<str>>(
+    docker_conn: &bollard::Docker,
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    stage_name: &str,
+    services: &[S],
+) -> anyhow::Result<Option<PathBuf>> {
+    let root = project_root.join(CAPTURE_DIR);
+    let dir = root.join(chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    let mut written = 0;
+    for service_name in services.iter().map(AsRef::as_ref) {
+        let container_name = config.container_name(stage_name, service_name);
+        let Some(log) = tail_logs(docker_conn, &container_name, CAPTURE_LINES).await? else {
+            continue;
+        };
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(format!("{}.log", service_name)), log)?;
+        written += 1;
+    }
+    prune_captures(&root, CAPTURE_KEEP)?;
+    Ok((written > 0).then_some(dir))
+}
+
+/// コンテナのログの末尾 `lines` 行（stderr の行には `stderr: ` を付ける）。コンテナが無ければ `None`
+pub(crate) async fn tail_logs(
+    docker_conn: &bollard::Docker,
+    container_name: &str,
+    lines: usize,
+) -> anyhow::Result<Option<String>> {
+    let options = bollard::query_parameters::LogsOptions {
+        stdout: true,
+        stderr: true,
+        timestamps: true,
+        tail: lines.to_string(),
+        ..Default::default()
+    };
+    let mut stream = docker_conn.logs(container_name, Some(options));
+    let mut log = String::new();
+    while let Some(output) = stream.next().await {
+        let output = match output {
+            Ok(output) => output,
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let Some((stderr, message)) = split_output(output) else {
+            continue;
+        };
+        for line in message.lines() {
+            if stderr {
+                log.push_str("stderr: ");
+            }
+            log.push_str(line);
+            log.push('\n');
+        }
+    }
+    Ok(Some(log))
+}
+
+/// 古いキャプチャを削除し、新しい `keep` 個だけ残す（ディレクトリ名は時刻順）
+fn prune_captures(root: &Path, keep: usize) -> std::io::Result<()> {
+    if !root.exists() {
+        return Ok(());
+    }
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(root)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    let excess = dirs.len().saturating_sub(keep);
+    for dir in &dirs[..excess] {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_level_json_and_logfmt() {
+        assert_eq!(
+            LogLevel::detect(r#"{"level":"error","msg":"boom"}"#),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(
+            LogLevel::detect("ts=2026-01-01 level=warn msg=slow"),
+            Some(LogLevel::Warn)
+        );
+        assert_eq!(
+            LogLevel::detect("severity=INFO request done"),
+            Some(LogLevel::Info)
+        );
+    }
+
+    #[test]
+    fn detect_level_text_tokens() {
+        assert_eq!(
+            LogLevel::detect("2026-01-01T00:00:00Z [ERROR] connection refused"),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(
+            LogLevel::detect("2026-01-01T00:00:00Z  WARN hyper: retrying"),
+            Some(LogLevel::Warn)
+        );
+        assert_eq!(LogLevel::detect("listening on :8080"), None);
+    }
+
+    #[test]
+    fn filter_by_grep_and_invert() {
+        let filter = LogFilter::new(Some("GET /health"), false, None).unwrap();
+        assert!(filter.matches("GET /health 200"));
+        assert!(!filter.matches("POST /api 201"));
+
+        let inverted = LogFilter::new(Some("GET /health"), true, None).unwrap();
+        assert!(!inverted.matches("GET /health 200"));
+        assert!(inverted.matches("POST /api 201"));
+    }
+
+    #[test]
+    fn filter_by_minimum_level() {
+        let filter = LogFilter::new(None, false, Some(LogLevel::Warn)).unwrap();
+        assert!(filter.matches("level=error msg=boom"));
+        assert!(filter.matches("level=warn msg=slow"));
+        assert!(!filter.matches("level=info msg=ok"));
+        assert!(!filter.matches("no level here"));
+    }
+
+    #[test]
+    fn filter_rejects_invalid_regex() {
+        assert!(LogFilter::new(Some("("), false, None).is_err());
+    }
+
+    #[test]
+    fn file_sink_writes_plain_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out/logs.txt");
+        let mut sink = LogSink::open(Some(&path)).unwrap();
+        sink.line("api", colored::Color::Cyan, false, "listening")
+            .unwrap();
+        sink.line("api", colored::Color::Cyan, true, "boom")
+            .unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[api] listening\n[api] stderr: boom\n"
+        );
+    }
+
+    #[test]
+    fn prune_keeps_newest_captures() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["20261001-000000", "20261002-000000", "20261003-000000"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        prune_captures(dir.path(), 2).unwrap();
+
+        let mut left: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["20261002-000000", "20261003-000000"]);
+    }
+}
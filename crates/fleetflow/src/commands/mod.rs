@@ -1,14 +1,49 @@
+pub mod attach;
 pub mod auth;
+pub mod autostart;
+pub mod check;
+pub mod cloud;
+pub mod cluster;
 pub mod compose;
+pub mod config;
+pub mod config_diff;
 pub mod cp;
 pub mod cp_client;
 pub mod daemon;
 pub mod deploy;
+pub mod dns;
+pub mod doctor;
 pub mod down;
+pub mod env;
 pub mod exec;
+pub mod host_service;
+pub mod image;
+pub mod inventory;
+pub mod kdl;
+pub mod kill;
+pub mod list;
+pub mod local_registry;
 pub mod logs;
+pub mod open;
+pub mod outdated;
+pub mod overrides;
+pub mod port_forward;
 pub mod ps;
 pub mod quadlet;
+pub mod reconcile;
 pub mod registry;
+pub mod remote;
+pub mod remote_daemon;
+pub mod report;
 pub mod restart;
+pub mod schema;
+pub mod setup;
+pub mod storage;
+pub mod supervise;
+pub mod support_bundle;
+pub mod test;
 pub mod up;
+pub mod upgrade_config;
+pub mod validate;
+pub mod wait;
+pub mod workspace;
@@ -0,0 +1,166 @@
+//! `fleet open` — サービスの URL をブラウザで開く
+//!
+//! ローカルステージでは公開ポート（`port host=...`）から `http://localhost:<port>` を、
+//! `--port <name>` 指定時は名前付きポート（`port ... name="admin"`）を使う。
+//! リモートステージ（`servers` を持つステージ）では Cloudflare DNS の命名規則
+//! （既定は `<service>-<stage>.<ドメイン>`、provider の `dns { subdomain ... }` で変更可）から
+//! URL を組み立てる。
+
+use crate::utils;
+use colored::Colorize;
+
+/// サービスの URL を解決する
+///
+/// `domain` はリモートステージで使う管理ドメインと命名規則（[`crate::dns::service_domain`]）。
+pub(crate) fn resolve_url(
+    stage_name: &str,
+    stage: &fleetflow_core::Stage,
+    service_name: &str,
+    service: &fleetflow_core::Service,
+    port_name: Option<&str>,
+    domain: Option<&crate::dns::ServiceDomain>,
+) -> anyhow::Result<String> {
+    if let Some(name) = port_name
+        && service.port_by_name(name).is_none()
+    {
+        let names: Vec<&str> = service
+            .ports
+            .iter()
+            .filter_map(|p| p.name.as_deref())
+            .collect();
+        return Err(anyhow::anyhow!(
+            "サービス '{}' に名前付きポート '{}' がありません（定義済み: {}）",
+            service_name,
+            name,
+            if names.is_empty() {
+                "なし".to_string()
+            } else {
+                names.join(", ")
+            }
+        ));
+    }
+
+    if !stage.servers.is_empty() {
+        let domain = domain.ok_or_else(|| {
+            anyhow::anyhow!(
+                "ステージ '{}' はリモートステージですが、DNS ドメインが設定されていません（stage の dns_domain、cloudflare provider の domain、CLOUDFLARE_DOMAIN のいずれか）",
+                stage_name
+            )
+        })?;
+        return Ok(domain.url(service_name, stage_name));
+    }
+
+    fleetflow_container::published_url(service, port_name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "サービス '{}' は TCP ポートを公開していません（port host=... container=... を追加してください）",
+            service_name
+        )
+    })
+}
+
+pub fn handle(
+    config: &fleetflow_core::Flow,
+    service_name: &str,
+    stage: Option<String>,
+    port_name: Option<&str>,
+    print: bool,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    if !stage_config.services.iter().any(|s| s == service_name) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' はステージ '{}' に含まれていません。\n利用可能なサービス: {}",
+            service_name,
+            stage_name,
+            stage_config.services.join(", ")
+        ));
+    }
+    let service = config
+        .services
+        .get(service_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", service_name))?;
+
+    let domain = crate::dns::service_domain(config, Some(&stage_name));
+    let url = resolve_url(
+        &stage_name,
+        stage_config,
+        service_name,
+        service,
+        port_name,
+        domain.as_ref(),
+    )?;
+
+    if print {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    println!("{} {}", "🌐".bold(), url.cyan().underline());
+    if open::that(&url).is_err() {
+        println!(
+            "{}",
+            "ブラウザを自動的に開けませんでした。手動で上記URLを開いてください。".yellow()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Port, Protocol, Service, Stage};
+
+    #[test]
+    fn test_resolve_url_local() {
+        let stage = Stage::default();
+        let service = Service {
+            ports: vec![Port {
+                host: 3000,
+                container: 80,
+                protocol: Protocol::Tcp,
+                host_ip: None,
+                name: None,
+                app_protocol: None,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            resolve_url("local", &stage, "web", &service, None, None).unwrap(),
+            "http://localhost:3000"
+        );
+        let err = resolve_url("local", &stage, "web", &service, Some("admin"), None).unwrap_err();
+        assert!(err.to_string().contains("名前付きポート 'admin'"));
+        let err =
+            resolve_url("local", &stage, "worker", &Service::default(), None, None).unwrap_err();
+        assert!(err.to_string().contains("TCP ポートを公開していません"));
+    }
+
+    #[test]
+    fn test_resolve_url_remote() {
+        let stage = Stage {
+            servers: vec!["app-1".to_string()],
+            ..Default::default()
+        };
+        let service = Service::default();
+        let mut domain = crate::dns::ServiceDomain {
+            domain: "example.com".to_string(),
+            subdomain_template: None,
+        };
+
+        assert_eq!(
+            resolve_url("prod", &stage, "web", &service, None, Some(&domain)).unwrap(),
+            "https://web-prod.example.com"
+        );
+        domain.subdomain_template = Some("{service}".to_string());
+        assert_eq!(
+            resolve_url("prod", &stage, "web", &service, None, Some(&domain)).unwrap(),
+            "https://web.example.com"
+        );
+        assert!(resolve_url("prod", &stage, "web", &service, None, None).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! `fleet outdated` — 稼働中のコンテナがイメージタグの最新より古いかを調べる
+//!
+//! サービスの `image` のタグがレジストリで指すマニフェストのダイジェストと、
+//! 稼働中コンテナのイメージのダイジェスト（RepoDigests）を比べる。
+//! レジストリへの問い合わせは Docker デーモン経由（distribution API）で、
+//! 認証情報はステージの `registry` ブロック、`~/.docker/config.json` の順に探す。
+//! `--update` は古いサービスのイメージを pull し、起動順に 1 つずつ作り直す。
+
+use crate::docker;
+use crate::utils;
+use colored::Colorize;
+use fleetflow_core::Flow;
+use serde::Serialize;
+
+/// 1 サービスの調査結果
+#[derive(Debug, Serialize)]
+struct OutdatedRow {
+    service: String,
+    image: String,
+    container: Option<String>,
+    /// 稼働中コンテナのイメージのダイジェスト
+    current: Option<String>,
+    /// レジストリでタグが指すダイジェスト
+    latest: Option<String>,
+    /// up-to-date / outdated / not-running / pinned / local-build / unknown
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// コンテナのイメージがレジストリの最新と一致するか判定する
+///
+/// `repo_digests` は `repo@sha256:...` 形式（`docker image inspect` の RepoDigests）。
+fn classify(
+    has_container: bool,
+    latest: Option<&str>,
+    repo_digests: &[String],
+) -> (&'static str, Option<String>) {
+    if !has_container {
+        return ("not-running", None);
+    }
+    let current = repo_digests
+        .iter()
+        .filter_map(|d| d.split_once('@').map(|(_, digest)| digest.to_string()))
+        .collect::<Vec<_>>();
+    let Some(latest) = latest else {
+        return ("unknown", current.into_iter().next());
+    };
+    if current.iter().any(|d| d == latest) {
+        ("up-to-date", Some(latest.to_string()))
+    } else if current.is_empty() {
+        // ローカルでビルド・load したイメージはダイジェストを持たない
+        ("unknown", None)
+    } else {
+        ("outdated", current.into_iter().next())
+    }
+}
+
+/// `sha256:0123456789ab...` → `0123456789ab`
+fn short_digest(digest: &str) -> String {
+    let hex = digest.split_once(':').map_or(digest, |(_, hex)| hex);
+    hex.chars().take(12).collect()
+}
+
+/// 稼働中コンテナのイメージ（RepoDigests）
+async fn container_digests(
+    docker_conn: &bollard::Docker,
+    config: &Flow,
+    stage_name: &str,
+    service_name: &str,
+) -> anyhow::Result<Option<(String, Vec<String>)>> {
+    let containers = fleetflow_container::find_containers(
+        docker_conn,
+        &config.name,
+        Some(stage_name),
+        Some(service_name),
+    )
+    .await?;
+    let Some(container) = containers.first() else {
+        return Ok(None);
+    };
+    let name = fleetflow_container::summary_name(container)
+        .map(str::to_string)
+        .unwrap_or_else(|| config.container_name(stage_name, service_name));
+    let Some(image_id) = container.image_id.as_deref() else {
+        return Ok(Some((name, Vec::new())));
+    };
+    let digests = docker_conn
+        .inspect_image(image_id)
+        .await
+        .map(|image| image.repo_digests.unwrap_or_default())
+        .unwrap_or_default();
+    Ok(Some((name, digests)))
+}
+
+/// レジストリでタグが指すマニフェストのダイジェスト
+async fn registry_digest(
+    docker_conn: &bollard::Docker,
+    image: &str,
+    auth: &fleetflow_build::RegistryAuth,
+) -> anyhow::Result<String> {
+    let (repo, tag) = fleetflow_build::split_image_tag(image);
+    let reference = format!("{}:{}", repo, tag);
+    let credentials = auth.get_credentials(&reference).ok().flatten();
+    docker_conn
+        .inspect_registry_image(&reference, credentials)
+        .await?
+        .descriptor
+        .digest
+        .ok_or_else(|| anyhow::anyhow!("レジストリがダイジェストを返しませんでした"))
+}
+
+/// `fleet outdated`
+pub async fn handle(
+    config: &Flow,
+    stage: Option<String>,
+    json: bool,
+    update: bool,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+    if !json {
+        println!(
+            "{}",
+            format!("ステージ '{}' のイメージを確認中...", stage_name).blue()
+        );
+    }
+
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let registry_auth = docker::registry_auth(config, &stage_name)?;
+    let mut rows = Vec::new();
+    for service_name in &stage_config.services {
+        let Some(service) = config.services.get(service_name) else {
+            continue;
+        };
+        if service.is_static() {
+            continue;
+        }
+        let Some(image) = service.image.clone() else {
+            continue;
+        };
+        let container = container_digests(&docker_conn, config, &stage_name, service_name).await?;
+
+        let mut row = OutdatedRow {
+            service: service_name.clone(),
+            image: image.clone(),
+            container: container.as_ref().map(|(name, _)| name.clone()),
+            current: None,
+            latest: None,
+            state: "unknown",
+            error: None,
+        };
+        // ダイジェスト固定のイメージと、ローカルでビルドするイメージはタグを追わない
+        if image.contains('@') {
+            row.state = "pinned";
+            rows.push(row);
+            continue;
+        }
+        if service.build.is_some() {
+            row.state = "local-build";
+            rows.push(row);
+            continue;
+        }
+
+        match registry_digest(&docker_conn, &image, &registry_auth).await {
+            Ok(digest) => row.latest = Some(digest),
+            Err(e) => row.error = Some(e.to_string()),
+        }
+        let digests = container.map(|(_, digests)| digests);
+        let (state, current) = classify(
+            digests.is_some(),
+            row.latest.as_deref(),
+            digests.as_deref().unwrap_or_default(),
+        );
+        row.state = state;
+        row.current = current;
+        rows.push(row);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!(
+            "{:<20} {:<44} {:<14} {:<14} {}",
+            "SERVICE", "IMAGE", "CURRENT", "LATEST", "STATE"
+        )
+        .bold()
+    );
+    for row in &rows {
+        let state = match row.state {
+            "up-to-date" => row.state.green(),
+            "outdated" => row.state.yellow().bold(),
+            _ => row.state.dimmed(),
+        };
+        println!(
+            "{:<20} {:<44} {:<14} {:<14} {}",
+            row.service,
+            row.image,
+            row.current.as_deref().map(short_digest).unwrap_or_default(),
+            row.latest.as_deref().map(short_digest).unwrap_or_default(),
+            state
+        );
+        if let Some(error) = &row.error {
+            println!("  {} {}", "⚠".yellow(), error.dimmed());
+        }
+    }
+
+    let outdated: Vec<String> = rows
+        .iter()
+        .filter(|row| row.state == "outdated")
+        .map(|row| row.service.clone())
+        .collect();
+    println!();
+    if outdated.is_empty() {
+        println!(
+            "{}",
+            "✓ 古いイメージで動いているサービスはありません".green()
+        );
+        return Ok(());
+    }
+    if !update {
+        println!(
+            "{} 個のサービスが最新ではありません（{} で pull して作り直します）",
+            outdated.len().to_string().yellow(),
+            "fleet outdated --update".cyan()
+        );
+        return Ok(());
+    }
+
+    // 依存される側から 1 つずつ pull → 作り直し → 準備完了待ち
+    for service_name in fleetflow_container::order_by_dependencies(&outdated, config) {
+        println!();
+        println!(
+            "{}",
+            format!("▶ {} を更新中...", service_name).green().bold()
+        );
+        if let Some(image) = config
+            .services
+            .get(&service_name)
+            .and_then(|s| s.image.as_deref())
+        {
+            docker::pull_image_always(&docker_conn, image, &registry_auth).await?;
+        }
+        crate::commands::restart::recreate_service(
+            &docker_conn,
+            config,
+            &stage_name,
+            &service_name,
+        )
+        .await?;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("✓ {} 個のサービスを更新しました", outdated.len())
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let digests = vec!["postgres@sha256:aaa".to_string()];
+        assert_eq!(
+            classify(true, Some("sha256:aaa"), &digests),
+            ("up-to-date", Some("sha256:aaa".to_string()))
+        );
+        assert_eq!(
+            classify(true, Some("sha256:bbb"), &digests),
+            ("outdated", Some("sha256:aaa".to_string()))
+        );
+        assert_eq!(classify(false, Some("sha256:bbb"), &[]).0, "not-running");
+        assert_eq!(classify(true, Some("sha256:bbb"), &[]).0, "unknown");
+        assert_eq!(classify(true, None, &digests).0, "unknown");
+    }
+
+    #[test]
+    fn test_short_digest() {
+        assert_eq!(
+            short_digest("sha256:0123456789abcdef0123456789abcdef"),
+            "0123456789ab"
+        );
+    }
+}
@@ -0,0 +1,569 @@
+//! `fleet override` — 開発者ごとのローカル上書き（flow.local.kdl）の編集と確認
+//!
+//! - `fleet override set <service> <field> <values...>`: image / ports / volumes / command / restart / env を書き込む
+//! - `fleet override unset <service> [field]`: 上書きを取り消す（field 省略時はサービスごと）
+//! - `fleet override show`: 読み込まれるオーバーライドファイルと、その中の上書きを表示
+//!
+//! ファイルは手で編集してもよい。コマンドは対象のノードだけを置き換えるため、
+//! 既存のコメントや他のサービスの記述はそのまま残る。
+
+use crate::commands::env;
+use crate::utils;
+use colored::Colorize;
+use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+use std::path::Path;
+
+/// 上書きできるフィールド
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OverrideField {
+    /// イメージ（例: postgres:17）
+    Image,
+    /// ポート（[HOST_IP:]HOST:CONTAINER[/udp]、複数指定可。元の ports を置き換える）
+    Ports,
+    /// ボリューム（HOST:CONTAINER[:ro]、複数指定可。元の volumes を置き換える）
+    Volumes,
+    /// コマンド
+    Command,
+    /// 再起動ポリシー（no / always / on-failure / unless-stopped）
+    Restart,
+    /// 環境変数（KEY=VALUE、複数指定可。元の env に追加・上書き）
+    Env,
+}
+
+impl OverrideField {
+    /// KDL 上のノード名（同じ意味の別名を含む）
+    fn node_names(self) -> &'static [&'static str] {
+        match self {
+            Self::Image => &["image"],
+            Self::Ports => &["ports", "port"],
+            Self::Volumes => &["volumes"],
+            Self::Command => &["command"],
+            Self::Restart => &["restart"],
+            Self::Env => &["env", "environment"],
+        }
+    }
+}
+
+/// `fleet override set`
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_set(
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    service_name: &str,
+    field: OverrideField,
+    values: &[String],
+    stage: Option<String>,
+    stage_file: bool,
+    recreate: bool,
+) -> anyhow::Result<()> {
+    ensure_service(config, service_name)?;
+
+    let stage_name = if stage_file || recreate {
+        Some(utils::determine_stage_name(stage, config)?)
+    } else {
+        stage
+    };
+    let target =
+        env::override_file_path(project_root, stage_name.as_deref().filter(|_| stage_file));
+    let created = !target.exists();
+    let content = if created {
+        String::new()
+    } else {
+        std::fs::read_to_string(&target)?
+    };
+
+    let updated = if field == OverrideField::Env {
+        let vars = values
+            .iter()
+            .map(|s| env::parse_assignment(s))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        env::set_env_in_kdl(&content, service_name, &vars)?.0
+    } else {
+        set_field_in_kdl(&content, service_name, field, field_nodes(field, values)?)?
+    };
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&target, updated)?;
+
+    println!(
+        "{}",
+        format!("✓ {} を更新しました", target.display()).green()
+    );
+    println!(
+        "  {} {}: {}",
+        service_name.cyan(),
+        field_label(field),
+        values.join(" ")
+    );
+    if created && !stage_file && !is_gitignored(project_root) {
+        println!(
+            "  {} flow.local.kdl は開発者ごとの設定です。.gitignore への追加をおすすめします",
+            "ℹ".blue()
+        );
+    }
+
+    if recreate {
+        let stage_name = stage_name.expect("recreate 時はステージ名を決定済み");
+        env::recreate_service(project_root, &stage_name, service_name).await?;
+    } else {
+        println!(
+            "{}",
+            "  反映するには --recreate を付けるか fleet up を実行してください".dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `fleet override unset`
+pub fn handle_unset(
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    service_name: &str,
+    field: Option<OverrideField>,
+    stage: Option<String>,
+    stage_file: bool,
+) -> anyhow::Result<()> {
+    ensure_service(config, service_name)?;
+
+    let stage_name = if stage_file {
+        Some(utils::determine_stage_name(stage, config)?)
+    } else {
+        None
+    };
+    let target = env::override_file_path(project_root, stage_name.as_deref());
+    if !target.exists() {
+        println!("{} {} はありません", "ℹ".blue(), target.display());
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(&target)?;
+    let (updated, removed) = unset_in_kdl(&content, service_name, field)?;
+    if !removed {
+        println!(
+            "{} {} に {} の上書きはありません",
+            "ℹ".blue(),
+            target.display(),
+            service_name.cyan()
+        );
+        return Ok(());
+    }
+    std::fs::write(&target, updated)?;
+
+    let what = field.map_or_else(|| "すべての上書き".to_string(), field_label);
+    println!(
+        "{}",
+        format!(
+            "✓ {} から {} の {} を削除しました",
+            target.display(),
+            service_name,
+            what
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// `fleet override show`
+pub fn handle_show(
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    stage: Option<&str>,
+) -> anyhow::Result<()> {
+    // 読み込み順（後のファイルほど優先）
+    let mut files = Vec::new();
+    if let Some(stage) = stage {
+        files.push(env::override_file_path(project_root, Some(stage)));
+    }
+    files.push(env::override_file_path(project_root, None));
+    let files: Vec<_> = files.into_iter().filter(|f| f.exists()).collect();
+
+    if files.is_empty() {
+        println!(
+            "{} ローカルの上書きはありません（{} で作成）",
+            "ℹ".blue(),
+            "fleet override set <service> <field> <value>".cyan()
+        );
+        return Ok(());
+    }
+
+    for file in files {
+        println!("{}", file.display().to_string().bold());
+        let doc: KdlDocument = std::fs::read_to_string(&file)?.parse().map_err(|e| {
+            anyhow::anyhow!("{}: KDL のパースに失敗しました: {}", file.display(), e)
+        })?;
+        if doc.nodes().is_empty() {
+            println!("  {}", "(空)".dimmed());
+        }
+        for node in doc.nodes() {
+            let name = first_string(node);
+            match (node.name().value(), name) {
+                ("service", Some(service)) => {
+                    let unknown = if config.services.contains_key(service) {
+                        String::new()
+                    } else {
+                        format!(" {}", "(fleet.kdl に未定義)".yellow())
+                    };
+                    println!("  service {}{}", service.cyan(), unknown);
+                    for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+                        print_node(child, 4);
+                    }
+                }
+                _ => print_node(node, 2),
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn ensure_service(config: &fleetflow_core::Flow, service_name: &str) -> anyhow::Result<()> {
+    if config.services.contains_key(service_name) {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "サービス '{}' が見つかりません\n利用可能なサービス: {}",
+        service_name,
+        config
+            .services
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+fn field_label(field: OverrideField) -> String {
+    field.node_names()[0].to_string()
+}
+
+/// ノードを整形し、インデントを揃えて表示する
+fn print_node(node: &KdlNode, indent: usize) {
+    let mut node = node.clone();
+    node.autoformat();
+    for line in node.to_string().trim().lines() {
+        println!("{:indent$}{}", "", line, indent = indent);
+    }
+}
+
+fn first_string(node: &KdlNode) -> Option<&str> {
+    node.entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+}
+
+/// プロジェクトの .gitignore に flow.local.kdl が含まれているか
+fn is_gitignored(project_root: &Path) -> bool {
+    std::fs::read_to_string(project_root.join(".gitignore")).is_ok_and(|content| {
+        content.lines().any(|line| {
+            line.trim()
+                .trim_start_matches('/')
+                .starts_with("flow.local")
+        })
+    })
+}
+
+/// フィールドの値を KDL ノードにする
+fn field_nodes(field: OverrideField, values: &[String]) -> anyhow::Result<Vec<KdlNode>> {
+    if values.is_empty() {
+        return Err(anyhow::anyhow!("値を指定してください"));
+    }
+    let single = |name: &str| -> anyhow::Result<KdlNode> {
+        let [value] = values else {
+            return Err(anyhow::anyhow!("{} には値を 1 つ指定してください", name));
+        };
+        let mut node = KdlNode::new(name);
+        node.push(KdlEntry::new(value.as_str()));
+        Ok(node)
+    };
+
+    let node = match field {
+        OverrideField::Image => single("image")?,
+        OverrideField::Command => {
+            // 引用符なしで複数語を渡した場合も 1 つのコマンドとして扱う
+            let mut node = KdlNode::new("command");
+            node.push(KdlEntry::new(values.join(" ")));
+            node
+        }
+        OverrideField::Restart => {
+            let node = single("restart")?;
+            if fleetflow_core::RestartPolicy::parse(&values[0]).is_none() {
+                return Err(anyhow::anyhow!(
+                    "再起動ポリシーは no / always / on-failure / unless-stopped のいずれかです: {}",
+                    values[0]
+                ));
+            }
+            node
+        }
+        OverrideField::Ports => {
+            let mut node = KdlNode::new("ports");
+            let children = node.ensure_children();
+            for spec in values {
+                children.nodes_mut().push(port_node(spec)?);
+            }
+            node
+        }
+        OverrideField::Volumes => {
+            let mut node = KdlNode::new("volumes");
+            let children = node.ensure_children();
+            for spec in values {
+                children.nodes_mut().push(volume_node(spec)?);
+            }
+            node
+        }
+        OverrideField::Env => unreachable!("env は set_env_in_kdl で書き込む"),
+    };
+    Ok(vec![node])
+}
+
+/// `[HOST_IP:]HOST:CONTAINER[/udp]` → `port host=.. container=..`
+fn port_node(spec: &str) -> anyhow::Result<KdlNode> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "ポートは [HOST_IP:]HOST:CONTAINER[/udp] 形式で指定してください: {}",
+            spec
+        )
+    };
+    let (mapping, protocol) = match spec.split_once('/') {
+        Some((mapping, protocol)) => (mapping, Some(protocol)),
+        None => (spec, None),
+    };
+    let mut parts = mapping.rsplitn(3, ':');
+    let container: u16 = parts
+        .next()
+        .and_then(|p| p.parse().ok())
+        .ok_or_else(invalid)?;
+    let host: u16 = match parts.next() {
+        Some(p) => p.parse().map_err(|_| invalid())?,
+        None => container,
+    };
+
+    let mut node = KdlNode::new("port");
+    node.push(KdlEntry::new_prop("host", KdlValue::Integer(host.into())));
+    node.push(KdlEntry::new_prop(
+        "container",
+        KdlValue::Integer(container.into()),
+    ));
+    if let Some(host_ip) = parts.next() {
+        node.push(KdlEntry::new_prop("host_ip", host_ip));
+    }
+    match protocol {
+        None | Some("tcp") => {}
+        Some("udp") => node.push(KdlEntry::new_prop("protocol", "udp")),
+        Some(_) => return Err(invalid()),
+    }
+    Ok(node)
+}
+
+/// `HOST:CONTAINER[:ro]` → `volume "HOST" "CONTAINER"`
+fn volume_node(spec: &str) -> anyhow::Result<KdlNode> {
+    let (mapping, read_only) = match spec.strip_suffix(":ro") {
+        Some(mapping) => (mapping, true),
+        None => (spec.strip_suffix(":rw").unwrap_or(spec), false),
+    };
+    let (host, container) = mapping
+        .split_once(':')
+        .filter(|(host, container)| !host.is_empty() && container.starts_with('/'))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "ボリュームは HOST:CONTAINER[:ro] 形式で指定してください: {}",
+                spec
+            )
+        })?;
+
+    let mut node = KdlNode::new("volume");
+    node.push(KdlEntry::new(host));
+    node.push(KdlEntry::new(container));
+    if read_only {
+        node.push(KdlEntry::new_prop("read_only", true));
+    }
+    Ok(node)
+}
+
+/// `service "<name>"` ノードの位置（複数あれば最後のもの）
+fn service_index(doc: &KdlDocument, service_name: &str) -> Option<usize> {
+    doc.nodes()
+        .iter()
+        .rposition(|n| n.name().value() == "service" && first_string(n) == Some(service_name))
+}
+
+/// `service "<name>" { ... }` 内のフィールドを置き換える
+///
+/// 同じ意味のノード（`ports` と `port` など）はすべて取り除き、最初の位置に新しいノードを置く。
+fn set_field_in_kdl(
+    content: &str,
+    service_name: &str,
+    field: OverrideField,
+    nodes: Vec<KdlNode>,
+) -> anyhow::Result<String> {
+    let mut doc: KdlDocument = content
+        .parse()
+        .map_err(|e| anyhow::anyhow!("KDL のパースに失敗しました: {}", e))?;
+
+    let service_idx = match service_index(&doc, service_name) {
+        Some(idx) => idx,
+        None => {
+            let mut node = KdlNode::new("service");
+            node.push(KdlEntry::new(service_name));
+            doc.nodes_mut().push(node);
+            doc.nodes().len() - 1
+        }
+    };
+
+    let service = &mut doc.nodes_mut()[service_idx];
+    {
+        let children = service.ensure_children();
+        let names = field.node_names();
+        let position = children
+            .nodes()
+            .iter()
+            .position(|n| names.contains(&n.name().value()))
+            .unwrap_or(children.nodes().len());
+        children
+            .nodes_mut()
+            .retain(|n| !names.contains(&n.name().value()));
+        let position = position.min(children.nodes().len());
+        children.nodes_mut().splice(position..position, nodes);
+    }
+    service.autoformat();
+
+    Ok(doc.to_string())
+}
+
+/// 上書きを取り除く（`field` が `None` ならサービスのブロックごと）
+///
+/// 中身が空になったサービスのブロックも取り除く。戻り値の 2 要素目は何か削除したか。
+fn unset_in_kdl(
+    content: &str,
+    service_name: &str,
+    field: Option<OverrideField>,
+) -> anyhow::Result<(String, bool)> {
+    let mut doc: KdlDocument = content
+        .parse()
+        .map_err(|e| anyhow::anyhow!("KDL のパースに失敗しました: {}", e))?;
+    let is_target =
+        |n: &KdlNode| n.name().value() == "service" && first_string(n) == Some(service_name);
+
+    let before = doc.nodes().len();
+    let Some(field) = field else {
+        doc.nodes_mut().retain(|n| !is_target(n));
+        let removed = doc.nodes().len() != before;
+        return Ok((doc.to_string(), removed));
+    };
+
+    let names = field.node_names();
+    let mut removed = false;
+    for node in doc.nodes_mut().iter_mut().filter(|n| is_target(n)) {
+        if let Some(children) = node.children_mut() {
+            let count = children.nodes().len();
+            children
+                .nodes_mut()
+                .retain(|n| !names.contains(&n.name().value()));
+            removed |= children.nodes().len() != count;
+        }
+    }
+    doc.nodes_mut().retain(|n| {
+        !is_target(n)
+            || n.children().is_some_and(|c| !c.nodes().is_empty())
+            || n.entries().len() > 1
+    });
+    Ok((doc.to_string(), removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn service_of(content: &str, service: &str) -> fleetflow_core::Service {
+        let flow = fleetflow_core::parse_kdl_string(content, "test".to_string()).unwrap();
+        flow.services[service].clone()
+    }
+
+    #[test]
+    fn test_set_ports_replaces_existing_and_keeps_comments() {
+        let content = r#"// 自分の環境ではポートが衝突する
+service "api" {
+    // 開発用イメージ
+    image "api:dev"
+    port 3000
+}
+
+service "db" {
+    image "postgres:16"
+}
+"#;
+        let nodes = field_nodes(
+            OverrideField::Ports,
+            &values(&["3001:3000", "127.0.0.1:9229:9229/udp"]),
+        )
+        .unwrap();
+        let out = set_field_in_kdl(content, "api", OverrideField::Ports, nodes).unwrap();
+
+        assert!(out.contains("// 自分の環境ではポートが衝突する"));
+        assert!(out.contains("// 開発用イメージ"));
+        let api = service_of(&out, "api");
+        assert_eq!(api.image.as_deref(), Some("api:dev"));
+        assert_eq!(api.ports.len(), 2);
+        assert_eq!((api.ports[0].host, api.ports[0].container), (3001, 3000));
+        assert_eq!(api.ports[1].host_ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(service_of(&out, "db").image.as_deref(), Some("postgres:16"));
+    }
+
+    #[test]
+    fn test_set_image_creates_service() {
+        let nodes = field_nodes(OverrideField::Image, &values(&["postgres:17"])).unwrap();
+        let out = set_field_in_kdl("", "db", OverrideField::Image, nodes).unwrap();
+        assert_eq!(service_of(&out, "db").image.as_deref(), Some("postgres:17"));
+    }
+
+    #[test]
+    fn test_field_nodes_rejects_invalid_values() {
+        assert!(field_nodes(OverrideField::Image, &values(&["a", "b"])).is_err());
+        assert!(field_nodes(OverrideField::Ports, &values(&["http"])).is_err());
+        assert!(field_nodes(OverrideField::Ports, &values(&["80:80/sctp"])).is_err());
+        assert!(field_nodes(OverrideField::Volumes, &values(&["data"])).is_err());
+        assert!(field_nodes(OverrideField::Restart, &values(&["sometimes"])).is_err());
+        assert!(field_nodes(OverrideField::Ports, &[]).is_err());
+    }
+
+    #[test]
+    fn test_set_volumes() {
+        let nodes = field_nodes(
+            OverrideField::Volumes,
+            &values(&["./seed:/seed:ro", "./data:/data"]),
+        )
+        .unwrap();
+        let out = set_field_in_kdl("", "db", OverrideField::Volumes, nodes).unwrap();
+        let db = service_of(&out, "db");
+        assert_eq!(db.volumes.len(), 2);
+        assert!(db.volumes[0].read_only);
+        assert_eq!(db.volumes[1].container, std::path::PathBuf::from("/data"));
+    }
+
+    #[test]
+    fn test_unset() {
+        let content = r#"
+service "api" {
+    image "api:dev"
+    restart "no"
+}
+"#;
+        let (out, removed) = unset_in_kdl(content, "api", Some(OverrideField::Image)).unwrap();
+        assert!(removed);
+        assert!(!out.contains("api:dev"));
+        assert!(out.contains("restart"));
+
+        let (out, removed) = unset_in_kdl(&out, "api", Some(OverrideField::Restart)).unwrap();
+        assert!(removed);
+        assert!(!out.contains("service"));
+
+        let (_, removed) = unset_in_kdl(content, "db", None).unwrap();
+        assert!(!removed);
+    }
+}
@@ -0,0 +1,276 @@
+//! `fleet port-forward` — リモートステージのサービスへ SSH トンネルを張る
+//!
+//! サービスの公開ポート（`port host=... container=...` の host 側）へ、
+//! ステージのサーバーに SSH でトンネルを張ってローカルから接続できるようにする。
+//! 接続が切れた場合はバックオフしながら再接続し、Ctrl+C で終了する。
+
+use crate::utils;
+use colored::Colorize;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// 再接続待ちの上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// この時間以上つながっていれば安定していたとみなし、バックオフをリセットする
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+
+/// `<local>:<remote>` または `<port>`（同じ番号）を解析
+fn parse_forward_spec(spec: &str) -> anyhow::Result<(u16, u16)> {
+    let parse = |s: &str| -> anyhow::Result<u16> {
+        s.parse::<u16>()
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| anyhow::anyhow!("ポート番号が不正です: '{}'", s))
+    };
+
+    match spec.split_once(':') {
+        Some((local, remote)) => Ok((parse(local)?, parse(remote)?)),
+        None => {
+            let port = parse(spec)?;
+            Ok((port, port))
+        }
+    }
+}
+
+/// コンテナポートに対応するサーバー上の公開アドレス (host_ip, host_port) を解決
+fn resolve_published_port(
+    service_name: &str,
+    service: &fleetflow_core::Service,
+    container_port: u16,
+) -> anyhow::Result<(String, u16)> {
+    let port = service
+        .ports
+        .iter()
+        .find(|p| p.container == container_port && p.protocol == fleetflow_core::Protocol::Tcp)
+        .ok_or_else(|| {
+            let published: Vec<String> = service
+                .ports
+                .iter()
+                .filter(|p| p.protocol == fleetflow_core::Protocol::Tcp)
+                .map(|p| p.container.to_string())
+                .collect();
+            anyhow::anyhow!(
+                "サービス '{}' はコンテナポート {} を公開していません（公開中: {}）",
+                service_name,
+                container_port,
+                if published.is_empty() {
+                    "なし".to_string()
+                } else {
+                    published.join(", ")
+                }
+            )
+        })?;
+
+    // 全インターフェースで公開されている場合はサーバー自身のループバック経由で接続する
+    let host_ip = match port.host_ip.as_deref() {
+        None | Some("") | Some("0.0.0.0") | Some("::") => "127.0.0.1".to_string(),
+        Some(ip) => ip.to_string(),
+    };
+    Ok((host_ip, port.host))
+}
+
+fn ssh_args(local_port: u16, remote_ip: &str, remote_port: u16, ssh_target: &str) -> Vec<String> {
+    vec![
+        "-N".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-o".to_string(),
+        "ServerAliveInterval=15".to_string(),
+        "-o".to_string(),
+        "ServerAliveCountMax=3".to_string(),
+        "-L".to_string(),
+        format!("127.0.0.1:{}:{}:{}", local_port, remote_ip, remote_port),
+        ssh_target.to_string(),
+    ]
+}
+
+/// 次の再接続待ち時間（倍々で増やし、上限で頭打ち）
+fn next_backoff(current: Duration) -> Duration {
+    (current * 2).min(MAX_BACKOFF)
+}
+
+pub async fn handle(
+    config: &fleetflow_core::Flow,
+    service_name: &str,
+    spec: &str,
+    stage: Option<String>,
+    server: Option<&str>,
+) -> anyhow::Result<()> {
+    let (local_port, container_port) = parse_forward_spec(spec)?;
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    if !stage_config.services.iter().any(|s| s == service_name) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' はステージ '{}' に含まれていません。\n利用可能なサービス: {}",
+            service_name,
+            stage_name,
+            stage_config.services.join(", ")
+        ));
+    }
+    let service = config
+        .services
+        .get(service_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", service_name))?;
+
+    let server_name = match server {
+        Some(name) if stage_config.servers.iter().any(|s| s == name) => name,
+        Some(name) => {
+            return Err(anyhow::anyhow!(
+                "サーバー '{}' はステージ '{}' に含まれていません（{}）",
+                name,
+                stage_name,
+                stage_config.servers.join(", ")
+            ));
+        }
+        None => match stage_config.servers.as_slice() {
+            [] => {
+                return Err(anyhow::anyhow!(
+                    "ステージ '{}' はリモートサーバーを使用していません。ローカルのポートに直接接続してください",
+                    stage_name
+                ));
+            }
+            [only] => only.as_str(),
+            servers => {
+                return Err(anyhow::anyhow!(
+                    "ステージ '{}' に複数のサーバーがあります。--server で指定してください（{}）",
+                    stage_name,
+                    servers.join(", ")
+                ));
+            }
+        },
+    };
+    let ssh_target = config
+        .servers
+        .get(server_name)
+        .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", server_name))?
+        .ssh_target()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "サーバー '{}' に ssh_host が設定されていません",
+                server_name
+            )
+        })?;
+
+    let (remote_ip, remote_port) = resolve_published_port(service_name, service, container_port)?;
+
+    println!("ステージ: {}", stage_name.cyan());
+    println!(
+        "{} localhost:{} → {} ({}:{}) → {}:{}",
+        "ポートフォワード:".green().bold(),
+        local_port.to_string().cyan(),
+        server_name.cyan(),
+        remote_ip,
+        remote_port,
+        service_name.cyan(),
+        container_port
+    );
+    println!("{}", "Ctrl+C で終了します".dimmed());
+    println!();
+
+    let args = ssh_args(local_port, &remote_ip, remote_port, &ssh_target);
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        let started = Instant::now();
+        let mut child = Command::new("ssh")
+            .args(&args)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("ssh の実行に失敗しました: {}", e))?;
+        println!("  {} {} に接続しました", "✓".green(), ssh_target);
+
+        let status = tokio::select! {
+            status = child.wait() => status?,
+            _ = tokio::signal::ctrl_c() => {
+                child.kill().await.ok();
+                println!();
+                println!("{}", "ポートフォワードを終了しました".green());
+                return Ok(());
+            }
+        };
+
+        if started.elapsed() >= STABLE_CONNECTION {
+            backoff = Duration::from_secs(1);
+        }
+        println!(
+            "  {} トンネルが切断されました（exit {:?}）。{} 秒後に再接続します...",
+            "⚠".yellow(),
+            status.code(),
+            backoff.as_secs()
+        );
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!();
+                println!("{}", "ポートフォワードを終了しました".green());
+                return Ok(());
+            }
+        }
+        backoff = next_backoff(backoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Port, Protocol, Service};
+
+    fn port(host: u16, container: u16, host_ip: Option<&str>) -> Port {
+        Port {
+            host,
+            container,
+            protocol: Protocol::Tcp,
+            host_ip: host_ip.map(str::to_string),
+            name: None,
+            app_protocol: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_forward_spec() {
+        assert_eq!(parse_forward_spec("15432:5432").unwrap(), (15432, 5432));
+        assert_eq!(parse_forward_spec("8080").unwrap(), (8080, 8080));
+        assert!(parse_forward_spec("0:5432").is_err());
+        assert!(parse_forward_spec("abc:5432").is_err());
+        assert!(parse_forward_spec("70000").is_err());
+    }
+
+    #[test]
+    fn test_resolve_published_port() {
+        let service = Service {
+            ports: vec![port(15432, 5432, None), port(8443, 443, Some("10.0.0.5"))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            resolve_published_port("db", &service, 5432).unwrap(),
+            ("127.0.0.1".to_string(), 15432)
+        );
+        assert_eq!(
+            resolve_published_port("db", &service, 443).unwrap(),
+            ("10.0.0.5".to_string(), 8443)
+        );
+        let err = resolve_published_port("db", &service, 6379).unwrap_err();
+        assert!(err.to_string().contains("5432, 443"));
+    }
+
+    #[test]
+    fn test_ssh_args_binds_loopback() {
+        let args = ssh_args(15432, "127.0.0.1", 5432, "root@10.0.0.5");
+        assert!(args.contains(&"ExitOnForwardFailure=yes".to_string()));
+        assert!(args.contains(&"127.0.0.1:15432:127.0.0.1:5432".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("root@10.0.0.5"));
+    }
+
+    #[test]
+    fn test_next_backoff_caps() {
+        assert_eq!(next_backoff(Duration::from_secs(1)), Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::from_secs(20)), MAX_BACKOFF);
+    }
+}
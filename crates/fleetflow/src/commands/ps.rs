@@ -1,124 +1,283 @@
 use crate::docker;
 use crate::utils;
+use bollard::models::ContainerInspectResponse;
 use colored::Colorize;
 
+/// `--status` で絞り込むコンテナの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatusFilter {
+    Running,
+    Exited,
+}
+
+impl StatusFilter {
+    fn as_docker_status(self) -> &'static str {
+        match self {
+            StatusFilter::Running => "running",
+            StatusFilter::Exited => "exited",
+        }
+    }
+}
+
+/// `fleet ps` の絞り込み条件
+#[derive(Debug, Default)]
+pub struct PsFilter {
+    pub service: Option<String>,
+    pub status: Option<StatusFilter>,
+    /// コンテナ名のみを出力
+    pub quiet: bool,
+}
+
+/// 起動からの経過時間を `3d 4h` / `5h 12m` / `42s` 形式で表す
+fn format_uptime(started_at: &str, now: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    let started = chrono::DateTime::parse_from_rfc3339(started_at).ok()?;
+    let secs = (now - started.with_timezone(&chrono::Utc)).num_seconds();
+    if secs < 0 {
+        return None;
+    }
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    Some(if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m {}s", mins, secs % 60)
+    } else {
+        format!("{}s", secs)
+    })
+}
+
+/// 公開ポートの表示（名前付きポートは `http=18080:8080`）と、http / https ポートなら URL
+fn describe_port(
+    service: Option<&fleetflow_core::Service>,
+    public_port: u16,
+    private_port: u16,
+) -> (String, Option<String>) {
+    let port = service.and_then(|s| s.ports.iter().find(|p| p.container == private_port));
+    let label = match port.and_then(|p| p.name.as_deref()) {
+        Some(name) => format!("{}={}:{}", name, public_port, private_port),
+        None => format!("{}:{}", public_port, private_port),
+    };
+    let url = port
+        .and_then(|p| p.app_protocol)
+        .map(|app| format!("{}://localhost:{}", app.as_str(), public_port));
+    (label, url)
+}
+
 pub async fn handle(
     config: &fleetflow_core::Flow,
     project_root: &std::path::Path,
     stage: Option<String>,
     all: bool,
+    filter: PsFilter,
 ) -> anyhow::Result<()> {
-    println!("{}", "コンテナ一覧を取得中...".blue());
-    utils::print_loaded_config_files(project_root);
+    if !filter.quiet {
+        println!("{}", "コンテナ一覧を取得中...".blue());
+        utils::print_loaded_config_files(project_root);
+    }
+
+    if let Some(service) = &filter.service
+        && !config.services.contains_key(service)
+    {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' が見つかりません\n利用可能なサービス: {}",
+            service,
+            config
+                .services
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    // クラスタ型ステージはクラスタ側の状態をそのまま表示
+    if let Some(stage_name) = &stage
+        && let Some(stage_config) = config.stages.get(stage_name)
+        && let Some(target) = fleetflow_container::cluster_target(stage_config.backend)
+    {
+        return crate::commands::cluster::status(target, config, stage_name, stage_config);
+    }
 
     // Docker接続
     let docker_conn = docker::init_docker_with_error_handling().await?;
 
-    // コンテナ一覧を取得
-    let filters = if let Some(stage_name) = stage {
-        println!("ステージ: {}", stage_name.cyan());
-
-        // ステージに属するサービスのみフィルタ
-        let stage_config = config
-            .stages
-            .get(&stage_name)
-            .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
-
-        let mut filter_map = std::collections::HashMap::new();
-        // OrbStack連携の命名規則: {project}-{stage}-{service}
-        let names: Vec<String> = stage_config
-            .services
-            .iter()
-            .map(|s| format!("{}-{}-{}", config.name, stage_name, s))
-            .collect();
-        filter_map.insert("name".to_string(), names);
-        Some(filter_map)
-    } else {
-        // fleetflow.project ラベルでフィルタ
-        let mut filter_map = std::collections::HashMap::new();
+    // コンテナ一覧を取得（命名規則に依存しないよう fleetflow.* ラベルで絞り込む）
+    let stage_name = match stage {
+        Some(stage_name) => {
+            if !filter.quiet {
+                println!("ステージ: {}", stage_name.cyan());
+            }
+
+            let stage_config = config
+                .stages
+                .get(&stage_name)
+                .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+            if let Some(service) = &filter.service
+                && !stage_config.services.contains(service)
+            {
+                return Err(anyhow::anyhow!(
+                    "サービス '{}' はステージ '{}' に含まれていません",
+                    service,
+                    stage_name
+                ));
+            }
+            Some(stage_name)
+        }
+        None => None,
+    };
+    let mut filter_map = fleetflow_container::label_filters(
+        &config.name,
+        stage_name.as_deref(),
+        filter.service.as_deref(),
+    );
+    if let Some(status) = filter.status {
         filter_map.insert(
-            "label".to_string(),
-            vec![format!("fleetflow.project={}", config.name)],
+            "status".to_string(),
+            vec![status.as_docker_status().to_string()],
         );
-        Some(filter_map)
-    };
+    }
 
     let options = bollard::query_parameters::ListContainersOptions {
-        all,
-        filters: Some(filters.unwrap_or_default()),
+        // 停止中の状態で絞り込む場合は -a を暗黙に有効化
+        all: all || filter.status == Some(StatusFilter::Exited),
+        filters: Some(filter_map),
         ..Default::default()
     };
 
     let containers = docker_conn.list_containers(Some(options)).await?;
 
+    let names: Vec<String> = containers
+        .iter()
+        .map(|c| {
+            c.names
+                .as_ref()
+                .and_then(|n| n.first())
+                .map(|n| n.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| "N/A".to_string())
+        })
+        .collect();
+
+    if filter.quiet {
+        for name in &names {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    // ヘルス・再起動回数・起動時刻は list の結果に無いため並列で inspect する
+    let inspects: Vec<Option<ContainerInspectResponse>> =
+        futures_util::future::join_all(containers.iter().map(|c| {
+            let docker_conn = &docker_conn;
+            async move {
+                let id = c.id.as_deref()?;
+                docker_conn
+                    .inspect_container(
+                        id,
+                        None::<bollard::query_parameters::InspectContainerOptions>,
+                    )
+                    .await
+                    .ok()
+            }
+        }))
+        .await;
+    let now = chrono::Utc::now();
+    let mut urls: Vec<(String, String)> = Vec::new();
+
     println!();
     if containers.is_empty() {
-        println!("{}", "実行中のコンテナはありません".dimmed());
+        println!("{}", "該当するコンテナはありません".dimmed());
     } else {
         println!(
             "{}",
             format!(
-                "{:<20} {:<15} {:<12} {:<20} {:<50}",
-                "NAME", "STATUS", "HEALTH", "IMAGE", "PORTS"
+                "{:<30} {:<10} {:<10} {:<8} {:<10} {:<30} {:<30}",
+                "NAME", "STATUS", "HEALTH", "RESTARTS", "UPTIME", "IMAGE", "PORTS"
             )
             .bold()
         );
-        println!("{}", "─".repeat(117).dimmed());
+        println!("{}", "─".repeat(134).dimmed());
 
-        for container in &containers {
-            let name = container
-                .names
-                .as_ref()
-                .and_then(|n| n.first())
-                .map(|n| n.trim_start_matches('/'))
-                .unwrap_or("N/A");
+        for ((container, name), inspect) in containers.iter().zip(&names).zip(&inspects) {
+            let state = inspect.as_ref().and_then(|i| i.state.as_ref());
 
-            let status = container.status.as_deref().unwrap_or("N/A");
-            let status_colored = if status.contains("Up") {
-                status.green()
-            } else {
-                status.red()
+            let status = container.state.as_ref().map(|s| s.to_string());
+            let status = status.as_deref().unwrap_or("N/A");
+            let status_colored = match status {
+                "running" => status.green(),
+                "restarting" | "paused" | "created" => status.yellow(),
+                _ => status.red(),
             };
 
-            // Docker status 文字列からヘルス情報を抽出
-            let health = if status.contains("(healthy)") {
-                "healthy".green()
-            } else if status.contains("(unhealthy)") {
-                "unhealthy".red()
-            } else if status.contains("(health: starting)") {
-                "starting".yellow()
+            let health = state
+                .and_then(|s| s.health.as_ref())
+                .and_then(|h| h.status.as_ref())
+                .map(|s| s.to_string())
+                .filter(|s| !s.is_empty() && s != "none");
+            let health = match health.as_deref() {
+                Some("healthy") => "healthy".green(),
+                Some("unhealthy") => "unhealthy".red(),
+                Some("starting") => "starting".yellow(),
+                _ => "-".dimmed(),
+            };
+
+            let restarts = inspect.as_ref().and_then(|i| i.restart_count).unwrap_or(0);
+            let restarts = if restarts > 0 {
+                restarts.to_string().yellow()
             } else {
-                "-".dimmed()
+                restarts.to_string().normal()
             };
 
+            let uptime = state
+                .filter(|s| s.running == Some(true))
+                .and_then(|s| s.started_at.as_deref())
+                .and_then(|started| format_uptime(started, now))
+                .unwrap_or_else(|| "-".to_string());
+
             let image = container.image.as_deref().unwrap_or("N/A");
 
-            let ports = container
-                .ports
+            let service = container
+                .labels
                 .as_ref()
-                .map(|ports| {
-                    ports
-                        .iter()
-                        .filter_map(|p| {
-                            p.public_port
-                                .map(|pub_port| format!("{}:{}", pub_port, p.private_port))
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                })
-                .unwrap_or_default();
+                .and_then(|l| l.get("fleetflow.service"))
+                .and_then(|s| config.services.get(s));
+            let mut labels = Vec::new();
+            for port in container.ports.iter().flatten() {
+                let Some(public_port) = port.public_port else {
+                    continue;
+                };
+                let (label, url) = describe_port(service, public_port, port.private_port);
+                labels.push(label);
+                if let Some(url) = url
+                    && status == "running"
+                    && !urls.iter().any(|(_, u)| *u == url)
+                {
+                    urls.push((name.clone(), url));
+                }
+            }
+            let ports = labels.join(", ");
 
             println!(
-                "{:<20} {:<15} {:<12} {:<20} {:<50}",
+                "{:<30} {:<10} {:<10} {:<8} {:<10} {:<30} {:<30}",
                 name.cyan(),
                 status_colored,
                 health,
+                restarts,
+                uptime,
                 image,
                 ports.dimmed()
             );
         }
     }
 
+    if !urls.is_empty() {
+        println!();
+        println!("{}", "URL:".bold());
+        for (name, url) in &urls {
+            println!("  {:<30} {}", name.cyan(), url.underline());
+        }
+    }
+
     Ok(())
 }
 
@@ -213,3 +372,72 @@ pub async fn handle_cp_query(project: Option<&str>, stage: Option<&str>) -> anyh
     client.disconnect().await.ok();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{AppProtocol, Port, Protocol, Service};
+
+    #[test]
+    fn test_describe_port() {
+        let service = Service {
+            ports: vec![
+                Port {
+                    host: 18080,
+                    container: 8080,
+                    protocol: Protocol::Tcp,
+                    host_ip: None,
+                    name: Some("http".to_string()),
+                    app_protocol: Some(AppProtocol::Http),
+                },
+                Port {
+                    host: 15432,
+                    container: 5432,
+                    protocol: Protocol::Tcp,
+                    host_ip: None,
+                    name: None,
+                    app_protocol: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            describe_port(Some(&service), 18080, 8080),
+            (
+                "http=18080:8080".to_string(),
+                Some("http://localhost:18080".to_string())
+            )
+        );
+        assert_eq!(
+            describe_port(Some(&service), 15432, 5432),
+            ("15432:5432".to_string(), None)
+        );
+        assert_eq!(describe_port(None, 80, 80), ("80:80".to_string(), None));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-01-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+
+        assert_eq!(
+            format_uptime("2026-01-10T11:59:18.123456789Z", now).as_deref(),
+            Some("41s")
+        );
+        assert_eq!(
+            format_uptime("2026-01-10T11:47:30Z", now).as_deref(),
+            Some("12m 30s")
+        );
+        assert_eq!(
+            format_uptime("2026-01-10T06:48:00Z", now).as_deref(),
+            Some("5h 12m")
+        );
+        assert_eq!(
+            format_uptime("2026-01-07T08:00:00Z", now).as_deref(),
+            Some("3d 4h")
+        );
+        assert_eq!(format_uptime("not a date", now), None);
+    }
+}
@@ -16,16 +16,25 @@ use fleetflow_container::quadlet::{
     systemctl_user_daemon_reload, systemctl_user_stop,
 };
 use fleetflow_core::{Flow, Stage};
+use std::path::Path;
 
 /// `fleet up` の Quadlet 経路。
 pub async fn up(
     config: &Flow,
+    project_root: &Path,
     stage_name: &str,
     stage: &Stage,
     dry_run: bool,
 ) -> anyhow::Result<()> {
     println!("{}", format!("backend: quadlet ({stage_name})").cyan());
 
+    // tmpfs のシークレットは再起動後の自動起動時に存在しないため扱わない
+    fleetflow_container::reject_secrets(config, &stage.services, "backend \"quadlet\"")?;
+    // config マウントはレンダリング済みファイルの Volume= としてユニットに載せる
+    let (config, files) =
+        fleetflow_container::render_stage_configs(project_root, config, stage_name)?;
+    let config = &config;
+
     if dry_run {
         let units = build_stage_units(config, stage_name, stage)?;
         println!(
@@ -44,6 +53,7 @@ pub async fn up(
         return Ok(());
     }
 
+    fleetflow_container::write_rendered_files(&files)?;
     let outcome = apply_stage(config, stage_name, stage)?;
     println!(
         "  {} {} 個のユニットを反映 + daemon-reload",
@@ -0,0 +1,468 @@
+//! `fleet reconcile` — Git リポジトリの fleet.kdl にステージを追従させる（最小の GitOps コントローラ）
+//!
+//! 一定間隔でリポジトリを clone / pull し、`fleet up --check` と同じ監査で
+//! 実際の状態（サーバー定義・ネットワーク・イメージ・コンテナ）との差分を調べる。
+//! 新しいリビジョンが来たとき、または差分が見つかったときに
+//! `fleet -C <checkout> deploy -s <stage> --yes` を実行して適用する。
+//!
+//! 状態は `<data_dir>/fleetflow/reconcile/<name>/` に置く:
+//! - `checkout/` — リポジトリの作業ツリー
+//! - `status.json` — 最後の同期結果（`fleet reconcile status` で表示）
+//! - `paused` — `fleet reconcile pause` で作られる一時停止マーカー
+//!
+//! コミットメッセージに `fleet-reconcile: pause` を含めると、そのリビジョンが
+//! HEAD の間は適用しない（注釈による一時停止）。
+
+use crate::commands::check;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// コミットメッセージによる一時停止の注釈
+const PAUSE_ANNOTATION: &str = "fleet-reconcile: pause";
+
+/// 同期ループの設定
+pub struct ReconcileOptions {
+    pub repo: String,
+    pub branch: String,
+    /// リポジトリ内のプロジェクトルート（省略時はリポジトリ直下）
+    pub path: Option<String>,
+    pub stage: String,
+    /// 状態ディレクトリ名（省略時は `<リポジトリ名>-<stage>`）
+    pub name: Option<String>,
+    pub interval: String,
+    pub once: bool,
+}
+
+/// 1 回の同期の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SyncResult {
+    /// 差分なし
+    Synced,
+    /// 適用した
+    Applied,
+    /// 一時停止中のため適用しなかった
+    Paused,
+    /// 失敗した
+    Failed,
+}
+
+impl SyncResult {
+    fn label(self) -> colored::ColoredString {
+        match self {
+            SyncResult::Synced => "synced".green(),
+            SyncResult::Applied => "applied".cyan(),
+            SyncResult::Paused => "paused".yellow(),
+            SyncResult::Failed => "failed".red(),
+        }
+    }
+}
+
+/// status.json の内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Status {
+    repo: String,
+    branch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    stage: String,
+    /// 最後に取得したリビジョン
+    revision: String,
+    /// 最後に適用に成功したリビジョン
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    applied_revision: Option<String>,
+    /// 監査で見つかった差分の数
+    drift: usize,
+    result: SyncResult,
+    message: String,
+    checked_at: String,
+}
+
+fn reconcile_root() -> anyhow::Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("データディレクトリが見つかりません"))?
+        .join("fleetflow")
+        .join("reconcile"))
+}
+
+/// 状態ディレクトリ名の既定値: `<リポジトリ名>-<stage>`
+fn default_name(repo: &str, stage: &str) -> String {
+    let base = repo
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(repo)
+        .trim_end_matches(".git");
+    format!("{}-{}", base, stage)
+}
+
+/// コミットメッセージに一時停止の注釈があるか
+fn is_pause_annotated(message: &str) -> bool {
+    message
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case(PAUSE_ANNOTATION))
+}
+
+fn git(args: &[&str], dir: Option<&Path>) -> anyhow::Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command.args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git {} が失敗しました: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// リポジトリを clone / 最新化し、(リビジョン, コミットメッセージ) を返す
+fn sync_checkout(checkout: &Path, repo: &str, branch: &str) -> anyhow::Result<(String, String)> {
+    if checkout.join(".git").exists() {
+        git(&["fetch", "--quiet", "origin", branch], Some(checkout))?;
+        git(
+            &["reset", "--quiet", "--hard", "FETCH_HEAD"],
+            Some(checkout),
+        )?;
+    } else {
+        let checkout_str = checkout.to_string_lossy();
+        git(
+            &[
+                "clone",
+                "--quiet",
+                "--single-branch",
+                "--branch",
+                branch,
+                repo,
+                &checkout_str,
+            ],
+            None,
+        )?;
+    }
+    let revision = git(&["rev-parse", "HEAD"], Some(checkout))?;
+    let message = git(&["log", "-1", "--format=%B"], Some(checkout))?;
+    Ok((revision, message))
+}
+
+fn read_status(dir: &Path) -> Option<Status> {
+    let content = std::fs::read_to_string(dir.join("status.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_status(dir: &Path, status: &Status) -> anyhow::Result<()> {
+    std::fs::write(
+        dir.join("status.json"),
+        serde_json::to_string_pretty(status)?,
+    )?;
+    Ok(())
+}
+
+/// 1 回分の同期: 取得 → 一時停止の判定 → 監査 → 必要なら適用
+async fn reconcile_once(
+    options: &ReconcileOptions,
+    dir: &Path,
+    previous: Option<&Status>,
+    role: Option<&str>,
+) -> Status {
+    let mut status = Status {
+        repo: options.repo.clone(),
+        branch: options.branch.clone(),
+        path: options.path.clone(),
+        stage: options.stage.clone(),
+        revision: previous.map(|s| s.revision.clone()).unwrap_or_default(),
+        applied_revision: previous.and_then(|s| s.applied_revision.clone()),
+        drift: 0,
+        result: SyncResult::Failed,
+        message: String::new(),
+        checked_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    let checkout = dir.join("checkout");
+    let (revision, commit_message) = match sync_checkout(&checkout, &options.repo, &options.branch)
+    {
+        Ok(v) => v,
+        Err(e) => {
+            status.message = e.to_string();
+            return status;
+        }
+    };
+    status.revision = revision.clone();
+
+    let project_root = match &options.path {
+        Some(path) => checkout.join(path),
+        None => checkout.clone(),
+    };
+    let config = match fleetflow_core::load_project_from_root_with_stage(
+        &project_root,
+        Some(&options.stage),
+    ) {
+        Ok(config) => config,
+        Err(e) => {
+            status.message = format!("fleet.kdl を読み込めません: {}", e);
+            return status;
+        }
+    };
+    match check::audit(&config, &project_root, &options.stage).await {
+        Ok(report) => {
+            for finding in report.findings.iter().filter(|f| f.gap.is_some()) {
+                finding.print();
+            }
+            status.drift = report.gaps();
+        }
+        Err(e) => {
+            status.message = format!("監査に失敗しました: {}", e);
+            return status;
+        }
+    }
+
+    let new_revision = status.applied_revision.as_deref() != Some(revision.as_str());
+    if !new_revision && status.drift == 0 {
+        status.result = SyncResult::Synced;
+        status.message = "差分はありません".to_string();
+        return status;
+    }
+
+    if dir.join("paused").exists() {
+        status.result = SyncResult::Paused;
+        status.message = "fleet reconcile pause により一時停止中です".to_string();
+        return status;
+    }
+    if is_pause_annotated(&commit_message) {
+        status.result = SyncResult::Paused;
+        status.message = format!("コミットに '{}' の注釈があります", PAUSE_ANNOTATION);
+        return status;
+    }
+
+    let reason = if new_revision {
+        format!("新しいリビジョン {}", short(&revision))
+    } else {
+        format!("{} 件の差分", status.drift)
+    };
+    println!("  {} 適用します（{}）", "→".blue(), reason);
+    match apply(&project_root, &options.stage, role) {
+        Ok(()) => {
+            status.result = SyncResult::Applied;
+            status.applied_revision = Some(revision);
+            status.message = format!("{}を適用しました", reason);
+        }
+        Err(e) => status.message = e.to_string(),
+    }
+    status
+}
+
+/// `fleet -C <project_root> deploy -s <stage> --yes` で適用する
+fn apply(project_root: &Path, stage: &str, role: Option<&str>) -> anyhow::Result<()> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg("-C")
+        .arg(project_root)
+        .args(["deploy", "-s", stage, "--yes", "--wait"]);
+    if let Some(role) = role {
+        command.args(["--role", role]);
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "fleet deploy が失敗しました（exit {}）",
+            status.code().unwrap_or(-1)
+        ));
+    }
+    Ok(())
+}
+
+fn short(revision: &str) -> &str {
+    &revision[..revision.len().min(8)]
+}
+
+/// `fleet reconcile --repo <url>`
+pub async fn handle_run(options: ReconcileOptions, role: Option<&str>) -> anyhow::Result<()> {
+    let interval = crate::utils::parse_duration(&options.interval)?;
+    let name = options
+        .name
+        .clone()
+        .unwrap_or_else(|| default_name(&options.repo, &options.stage));
+    let dir = reconcile_root()?.join(&name);
+    std::fs::create_dir_all(&dir)?;
+
+    println!(
+        "{}",
+        format!(
+            "{} ({}) をステージ '{}' に同期します [{}]",
+            options.repo, options.branch, options.stage, name
+        )
+        .blue()
+        .bold()
+    );
+
+    loop {
+        let previous = read_status(&dir);
+        let status = reconcile_once(&options, &dir, previous.as_ref(), role).await;
+        write_status(&dir, &status)?;
+        println!(
+            "[{}] {} {} {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            short(&status.revision),
+            status.result.label(),
+            status.message
+        );
+
+        if options.once {
+            return match status.result {
+                SyncResult::Failed => {
+                    Err(anyhow::anyhow!("同期に失敗しました: {}", status.message))
+                }
+                _ => Ok(()),
+            };
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+/// 対象の状態ディレクトリ（名前省略時は 1 つだけ存在する場合に限る）
+fn resolve_dir(name: Option<&str>) -> anyhow::Result<PathBuf> {
+    let root = reconcile_root()?;
+    if let Some(name) = name {
+        let dir = root.join(name);
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!("同期設定 '{}' が見つかりません", name));
+        }
+        return Ok(dir);
+    }
+    let dirs = list_dirs(&root);
+    match dirs.as_slice() {
+        [dir] => Ok(dir.clone()),
+        [] => Err(anyhow::anyhow!(
+            "同期設定がありません（fleet reconcile --repo <url> で開始します）"
+        )),
+        _ => Err(anyhow::anyhow!(
+            "同期設定が複数あります。名前を指定してください: {}",
+            dirs.iter()
+                .filter_map(|d| d.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn list_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// `fleet reconcile status`
+pub fn handle_status(json: bool) -> anyhow::Result<()> {
+    let entries: Vec<(String, Option<Status>, bool)> = list_dirs(&reconcile_root()?)
+        .into_iter()
+        .map(|dir| {
+            let name = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            (name, read_status(&dir), dir.join("paused").exists())
+        })
+        .collect();
+
+    if json {
+        let value: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(name, status, paused)| {
+                serde_json::json!({ "name": name, "paused": paused, "status": status })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("同期設定はありません（fleet reconcile --repo <url> で開始します）");
+        return Ok(());
+    }
+    for (name, status, paused) in &entries {
+        let paused = if *paused {
+            " （一時停止中）".yellow().to_string()
+        } else {
+            String::new()
+        };
+        println!("{}{}", name.cyan().bold(), paused);
+        match status {
+            None => println!("  まだ同期していません"),
+            Some(status) => {
+                println!("  リポジトリ: {} ({})", status.repo, status.branch);
+                println!("  ステージ:   {}", status.stage);
+                println!(
+                    "  リビジョン: {}（適用済み: {}）",
+                    short(&status.revision),
+                    status.applied_revision.as_deref().map(short).unwrap_or("-")
+                );
+                println!(
+                    "  結果:       {} {}",
+                    status.result.label(),
+                    status.message.dimmed()
+                );
+                println!("  差分:       {}", status.drift);
+                println!("  確認日時:   {}", status.checked_at);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `fleet reconcile pause` / `fleet reconcile resume`
+pub fn handle_pause(name: Option<&str>, paused: bool) -> anyhow::Result<()> {
+    let dir = resolve_dir(name)?;
+    let marker = dir.join("paused");
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if paused {
+        std::fs::write(&marker, chrono::Local::now().to_rfc3339())?;
+        println!("{} {} の自動適用を一時停止しました", "✓".green(), name);
+    } else {
+        if marker.exists() {
+            std::fs::remove_file(&marker)?;
+        }
+        println!("{} {} の自動適用を再開しました", "✓".green(), name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_name() {
+        assert_eq!(
+            default_name("https://github.com/acme/infra.git", "prod"),
+            "infra-prod"
+        );
+        assert_eq!(
+            default_name("git@github.com:acme/infra", "stg"),
+            "infra-stg"
+        );
+        assert_eq!(default_name("/srv/git/infra/", "dev"), "infra-dev");
+    }
+
+    #[test]
+    fn test_pause_annotation() {
+        assert!(is_pause_annotated(
+            "Bump api image\n\nfleet-reconcile: pause\n"
+        ));
+        assert!(!is_pause_annotated("Bump api image"));
+        assert!(!is_pause_annotated("Mention fleet-reconcile: pause inline"));
+    }
+}
@@ -0,0 +1,223 @@
+//! `fleet remote` — 管理サーバー上の保守操作
+//!
+//! サーバー定義（ssh_host / ssh_user）から `DOCKER_HOST=ssh://...` を組み立て、
+//! サーバーの Docker デーモンに対して docker CLI を実行する。
+
+use colored::Colorize;
+use fleetflow_container::ImageEntry;
+use std::process::Command;
+
+/// サーバーの Docker デーモンで docker CLI を実行し、標準出力を返す
+fn docker_output(docker_host: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("docker")
+        .env("DOCKER_HOST", docker_host)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("docker の実行に失敗しました: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "docker {} が失敗しました: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `docker image inspect --format '{{.Id}}\t{{.Created}}\t{{join .RepoTags " "}}'` の出力を解析
+fn parse_image_inspect(output: &str) -> Vec<ImageEntry> {
+    let mut images = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(id), Some(created)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Ok(created) = chrono::DateTime::parse_from_rfc3339(created.trim()) else {
+            continue;
+        };
+        for repo_tag in fields.next().unwrap_or_default().split_whitespace() {
+            images.push(ImageEntry {
+                repo_tag: repo_tag.to_string(),
+                id: id.trim().to_string(),
+                created: created.timestamp(),
+            });
+        }
+    }
+    images
+}
+
+/// サーバーのイメージ一覧
+fn list_images(docker_host: &str) -> anyhow::Result<Vec<ImageEntry>> {
+    let ids = docker_output(docker_host, &["image", "ls", "-q", "--no-trunc"])?;
+    let mut ids: Vec<&str> = ids
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut args = vec![
+        "image",
+        "inspect",
+        "--format",
+        "{{.Id}}\t{{.Created}}\t{{join .RepoTags \" \"}}",
+    ];
+    args.extend(ids);
+    Ok(parse_image_inspect(&docker_output(docker_host, &args)?))
+}
+
+/// コンテナ（停止中を含む）が参照するイメージ名と ID
+fn images_in_use(docker_host: &str) -> anyhow::Result<Vec<String>> {
+    let names = docker_output(docker_host, &["ps", "-a", "--format", "{{.Image}}"])?;
+    let mut in_use: Vec<String> = names.lines().map(|l| l.trim().to_string()).collect();
+
+    let container_ids = docker_output(docker_host, &["ps", "-aq"])?;
+    let container_ids: Vec<&str> = container_ids.lines().map(str::trim).collect();
+    if !container_ids.is_empty() {
+        let mut args = vec!["inspect", "--format", "{{.Image}}"];
+        args.extend(container_ids);
+        in_use.extend(
+            docker_output(docker_host, &args)?
+                .lines()
+                .map(|l| l.trim().to_string()),
+        );
+    }
+    in_use.retain(|i| !i.is_empty());
+    Ok(in_use)
+}
+
+/// `fleet remote prune` — サーバーのイメージ・ビルドキャッシュを maintenance ポリシーに従って削除
+///
+/// サーバーを使うステージのビルドイメージを `image_retention` 個だけ残し、
+/// `prune_schedule` の期間より古い dangling イメージ・ビルドキャッシュを削除する
+/// （`never` の場合は期間を問わない）。
+pub fn handle_prune(
+    config: &fleetflow_core::Flow,
+    server_name: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let docker_host = config
+        .servers
+        .get(server_name)
+        .ok_or_else(|| anyhow::anyhow!("サーバー '{}' が定義されていません", server_name))?
+        .docker_host()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "サーバー '{}' に ssh_host が設定されていません",
+                server_name
+            )
+        })?;
+    let maintenance = config.maintenance.clone().unwrap_or_default();
+
+    println!(
+        "{}",
+        format!("{} ({}) を整理中...", server_name, docker_host).green()
+    );
+    println!(
+        "  保持数: {}  削除間隔: {}",
+        maintenance
+            .image_retention
+            .map_or_else(|| "制限なし".to_string(), |n| n.to_string())
+            .cyan(),
+        maintenance.prune_schedule.as_str().cyan()
+    );
+
+    // 古いビルドイメージ（サーバーを使う全ステージ分）
+    if let Some(keep) = maintenance.image_retention {
+        let mut stages: Vec<&String> = config
+            .stages
+            .iter()
+            .filter(|(_, stage)| stage.servers.iter().any(|s| s == server_name))
+            .map(|(name, _)| name)
+            .collect();
+        stages.sort();
+        let mut repositories: Vec<String> = stages
+            .iter()
+            .flat_map(|stage| fleetflow_container::project_build_repositories(config, stage))
+            .collect();
+        repositories.sort();
+        repositories.dedup();
+
+        let images = list_images(&docker_host)?;
+        let in_use = images_in_use(&docker_host)?;
+        let candidates = fleetflow_container::retention_candidates(
+            &images,
+            &repositories,
+            keep as usize,
+            &in_use,
+        );
+
+        println!();
+        if candidates.is_empty() {
+            println!("  ℹ 保持数を超えたイメージはありません");
+        }
+        for repo_tag in &candidates {
+            if dry_run {
+                println!("  - {} {}", repo_tag, "(dry-run)".dimmed());
+                continue;
+            }
+            match docker_output(&docker_host, &["image", "rm", repo_tag]) {
+                Ok(_) => println!("  {} {}", "✓".green(), repo_tag),
+                Err(e) => eprintln!("  {} {}: {}", "✗".red(), repo_tag, e),
+            }
+        }
+    }
+
+    if dry_run {
+        println!();
+        println!("{}", "dry-run のため削除していません".yellow());
+        return Ok(());
+    }
+
+    // dangling イメージとビルドキャッシュ
+    let until = maintenance
+        .prune_schedule
+        .until_filter()
+        .map(|until| format!("until={}", until));
+    let mut image_args = vec!["image", "prune", "-f"];
+    let mut builder_args = vec!["builder", "prune", "-f"];
+    if let Some(until) = &until {
+        image_args.extend(["--filter", until.as_str()]);
+        builder_args.extend(["--filter", until.as_str()]);
+    }
+    println!();
+    for args in [&image_args, &builder_args] {
+        let output = docker_output(&docker_host, args)?;
+        if let Some(line) = output.lines().rev().find(|l| !l.trim().is_empty()) {
+            println!("  {} {}", "✓".green(), line.trim());
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        docker_output(&docker_host, &["system", "df"])?.trim_end()
+    );
+    println!();
+    println!(
+        "{}",
+        format!("✓ {} を整理しました", server_name).green().bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_image_inspect() {
+        let output = "sha256:aaa\t2026-01-02T03:04:05.123456789Z\tmyapp-prod:v1 ghcr.io/acme/myapp-prod:v1\n\
+                      sha256:bbb\t2026-01-01T00:00:00Z\t\n\
+                      broken line\n";
+        let images = parse_image_inspect(output);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].repo_tag, "myapp-prod:v1");
+        assert_eq!(images[1].repo_tag, "ghcr.io/acme/myapp-prod:v1");
+        assert_eq!(images[0].id, "sha256:aaa");
+        assert_eq!(images[0].created, 1767323045);
+    }
+}
@@ -0,0 +1,490 @@
+//! `fleet --host ssh://user@server` — リモートの fleetflowd 経由で ps / logs / restart
+//!
+//! SSH（tailscale ssh）しか届かないホストで、ローカルに fleet.kdl が無くても操作できるよう、
+//! サーバー上の fleetflowd の HTTP API（ループバックの Web ポート）を SSH 越しに呼ぶ。
+//! プロジェクトは `FLEET_PROJECT`、ローカルの設定、デーモンに登録されたステージの順に決め、
+//! サービス名はデーモンに登録されたものから補完・検証する。
+//! `fleet cp login` 済みならそのトークンを標準入力経由で渡す（コマンドラインには載せない）。
+
+use super::logs::{LogFilter, LogSink};
+use crate::utils::shell_escape;
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// fleetflowd の Web ポートの既定値（fleetflowd の `web { listen }` の既定と同じ）
+const DEFAULT_WEB_PORT: u16 = 32080;
+/// `logs -f` のポーリング間隔
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `--host` で指定したリモートの fleetflowd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonHost {
+    pub user: String,
+    pub host: String,
+    /// サーバー上の fleetflowd の Web ポート（ループバックで待ち受けている前提）
+    pub web_port: u16,
+}
+
+impl DaemonHost {
+    /// `ssh://[user@]host[:port]` を解釈する
+    ///
+    /// tailscale ssh は接続ポートを取らないため、`port` はサーバー上の fleetflowd の
+    /// Web ポートとして扱う（省略時 32080）。ユーザー省略時は root。
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let rest = url
+            .strip_prefix("ssh://")
+            .map(|rest| rest.trim_end_matches('/'))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "--host は ssh://user@server の形式で指定してください: {}",
+                    url
+                )
+            })?;
+        let (user, host_port) = match rest.split_once('@') {
+            Some((user, host_port)) => (user, host_port),
+            None => ("root", rest),
+        };
+        let (host, web_port) = match host_port.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|_| anyhow::anyhow!("--host のポートが不正です: {}", port))?,
+            ),
+            None => (host_port, DEFAULT_WEB_PORT),
+        };
+        if user.is_empty() || host.is_empty() {
+            return Err(anyhow::anyhow!(
+                "--host は ssh://user@server の形式で指定してください: {}",
+                url
+            ));
+        }
+        Ok(Self {
+            user: user.to_string(),
+            host: host.to_string(),
+            web_port,
+        })
+    }
+
+    /// fleetflowd の API を呼び、JSON を返す
+    async fn request(&self, method: &str, path: &str) -> anyhow::Result<Value> {
+        let token = super::auth::stored_access_token();
+        let url = format!("http://127.0.0.1:{}{}", self.web_port, path);
+        // トークンはコマンドラインに載せず、標準入力からヘッダーとして渡す
+        let command = format!(
+            "curl -sS -X {} -w '\\n%{{http_code}}'{} {}",
+            method,
+            if token.is_some() { " -H @-" } else { "" },
+            shell_escape(&url)
+        );
+        let input = token
+            .map(|token| format!("Authorization: Bearer {}\n", token))
+            .unwrap_or_default();
+
+        let result = fleetflow_cloud::ssh::exec_with_input(
+            &self.host,
+            &self.user,
+            &command,
+            input.as_bytes(),
+        )
+        .await?;
+        if !result.success {
+            return Err(anyhow::anyhow!(
+                "{}@{} の fleetflowd に接続できません: {}",
+                self.user,
+                self.host,
+                result.stderr.trim()
+            ));
+        }
+
+        let output = result.stdout.trim_end();
+        let (body, status) = output.rsplit_once('\n').unwrap_or(("", output));
+        let value: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        if !status.starts_with('2') {
+            let message = value["error"].as_str().unwrap_or(body);
+            return Err(anyhow::anyhow!(
+                "fleetflowd がエラーを返しました (HTTP {}): {}",
+                status,
+                message
+            ));
+        }
+        Ok(value)
+    }
+
+    /// 操作対象のプロジェクト・ステージを決める
+    async fn resolve_target(&self, stage: Option<&str>) -> anyhow::Result<(String, String)> {
+        let project = std::env::var("FLEET_PROJECT")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .or_else(local_project_name);
+        let stages = self.request("GET", "/api/stages").await?;
+        let entries: Vec<(String, String)> = stages["stages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|s| {
+                Some((
+                    s["project_slug"].as_str()?.to_string(),
+                    s["stage"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        pick_target(&entries, project.as_deref(), stage)
+    }
+
+    async fn status(&self, project: &str, stage: &str) -> anyhow::Result<Value> {
+        self.request(
+            "GET",
+            &format!("/api/v1/stages/{}/{}/status", project, stage),
+        )
+        .await
+    }
+}
+
+/// ローカルのプロジェクト設定（見つからなければ `None`）
+fn local_project() -> Option<fleetflow_core::Flow> {
+    let root = fleetflow_core::find_project_root().ok()?;
+    fleetflow_core::load_project_from_root(&root).ok()
+}
+
+/// ローカルにプロジェクトがあればその名前
+fn local_project_name() -> Option<String> {
+    local_project().map(|flow| flow.name)
+}
+
+/// サービスのコンテナ名
+///
+/// ローカルに同じプロジェクトがあればその `container_name` テンプレートに従い、
+/// なければ既定の `{project}-{stage}-{service}`。
+fn container_name(
+    local: Option<&fleetflow_core::Flow>,
+    project: &str,
+    stage: &str,
+    service: &str,
+) -> String {
+    match local {
+        Some(flow) if flow.name == project => flow.container_name(stage, service),
+        _ => fleetflow_core::container_name(None, project, stage, service),
+    }
+}
+
+/// 登録済みの (プロジェクト, ステージ) から 1 つに絞り込む
+fn pick_target(
+    entries: &[(String, String)],
+    project: Option<&str>,
+    stage: Option<&str>,
+) -> anyhow::Result<(String, String)> {
+    let candidates: Vec<&(String, String)> = entries
+        .iter()
+        .filter(|(p, _)| project.is_none_or(|project| p == project))
+        .filter(|(_, s)| stage.is_none_or(|stage| s == stage))
+        .collect();
+    let list = |items: &[&(String, String)]| {
+        items
+            .iter()
+            .map(|(p, s)| format!("{}/{}", p, s))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match candidates.as_slice() {
+        [target] => Ok((*target).clone()),
+        [] => Err(anyhow::anyhow!(
+            "該当するステージがデーモンに登録されていません（登録済み: {}）",
+            list(&entries.iter().collect::<Vec<_>>())
+        )),
+        _ => Err(anyhow::anyhow!(
+            "対象のステージが 1 つに決まりません。-s または FLEET_PROJECT で指定してください（候補: {}）",
+            list(&candidates)
+        )),
+    }
+}
+
+/// ステージ状態のサービス名
+fn service_names(status: &Value) -> Vec<String> {
+    status["services"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// 指定されたサービス名を登録済みのものに解決する（一意な前方一致で補完。空なら全サービス）
+fn resolve_services(available: &[String], requested: &[String]) -> anyhow::Result<Vec<String>> {
+    if requested.is_empty() {
+        return Ok(available.to_vec());
+    }
+    requested
+        .iter()
+        .map(|name| {
+            if available.contains(name) {
+                return Ok(name.clone());
+            }
+            let matches: Vec<&String> = available
+                .iter()
+                .filter(|s| s.starts_with(name.as_str()))
+                .collect();
+            match matches.as_slice() {
+                [service] => Ok((*service).clone()),
+                _ => Err(anyhow::anyhow!(
+                    "サービス '{}' がデーモンに登録されていません（登録済み: {}）",
+                    name,
+                    available.join(", ")
+                )),
+            }
+        })
+        .collect()
+}
+
+fn print_target(daemon: &DaemonHost, project: &str, stage: &str) {
+    println!(
+        "{} {}@{} / {} / {}",
+        "fleetflowd:".bold(),
+        daemon.user,
+        daemon.host,
+        project.cyan(),
+        stage.cyan()
+    );
+    println!();
+}
+
+/// `fleet --host ... ps`
+pub async fn handle_ps(
+    host: &str,
+    stage: Option<&str>,
+    service: Option<&str>,
+) -> anyhow::Result<()> {
+    let daemon = DaemonHost::parse(host)?;
+    let (project, stage) = daemon.resolve_target(stage).await?;
+    print_target(&daemon, &project, &stage);
+
+    let status = daemon.status(&project, &stage).await?;
+    let requested: Vec<String> = service.map(str::to_string).into_iter().collect();
+    let targets = resolve_services(&service_names(&status), &requested)?;
+
+    println!(
+        "{}",
+        format!(
+            "{:<20} {:<10} {:<40} {}",
+            "SERVICE", "STATE", "IMAGE", "UPTIME"
+        )
+        .bold()
+    );
+    for svc in status["services"].as_array().into_iter().flatten() {
+        let name = svc["name"].as_str().unwrap_or_default();
+        if !targets.iter().any(|t| t == name) {
+            continue;
+        }
+        let state = svc["state"].as_str().unwrap_or("unknown");
+        let state_colored = match state {
+            "running" => state.green(),
+            "stopped" => state.red(),
+            _ => state.yellow(),
+        };
+        let uptime = svc["uptime_seconds"]
+            .as_u64()
+            .map(|secs| format!("{}s", secs))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<20} {:<10} {:<40} {}",
+            name.cyan(),
+            state_colored,
+            svc["image"].as_str().unwrap_or("-"),
+            uptime
+        );
+    }
+    Ok(())
+}
+
+/// `fleet --host ... restart`
+pub async fn handle_restart(
+    host: &str,
+    stage: Option<&str>,
+    service: Option<&str>,
+    role: Option<&str>,
+) -> anyhow::Result<()> {
+    let daemon = DaemonHost::parse(host)?;
+    let (project, stage) = daemon.resolve_target(stage).await?;
+    crate::authz::authorize("restart", &stage, role, false)?;
+    print_target(&daemon, &project, &stage);
+
+    let status = daemon.status(&project, &stage).await?;
+    let requested: Vec<String> = service.map(str::to_string).into_iter().collect();
+    for name in resolve_services(&service_names(&status), &requested)? {
+        daemon
+            .request(
+                "POST",
+                &format!(
+                    "/api/v1/stages/{}/{}/services/{}/restart",
+                    project, stage, name
+                ),
+            )
+            .await?;
+        println!("  {} {} を再起動しました", "✓".green(), name.cyan());
+    }
+    Ok(())
+}
+
+/// `fleet --host ... logs`
+///
+/// デーモンが収集している直近のログを表示する。`-f` では新しい行をポーリングで追う。
+pub async fn handle_logs(
+    host: &str,
+    stage: Option<&str>,
+    services: &[String],
+    lines: usize,
+    follow: bool,
+    filter: &LogFilter,
+    output: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
+    let mut sink = LogSink::open(output)?;
+    let daemon = DaemonHost::parse(host)?;
+    let (project, stage) = daemon.resolve_target(stage).await?;
+    print_target(&daemon, &project, &stage);
+
+    let status = daemon.status(&project, &stage).await?;
+    let targets = resolve_services(&service_names(&status), services)?;
+
+    let colors = [
+        colored::Color::Cyan,
+        colored::Color::Green,
+        colored::Color::Yellow,
+        colored::Color::Magenta,
+        colored::Color::Blue,
+    ];
+    // サービスごとに表示済みの最後のタイムスタンプ（RFC 3339 は文字列比較で順序が保てる）
+    let mut last_seen: HashMap<String, String> = HashMap::new();
+    let mut first = true;
+    let local = local_project();
+
+    loop {
+        for (idx, name) in targets.iter().enumerate() {
+            let container = container_name(local.as_ref(), &project, &stage, name);
+            let response = daemon
+                .request(
+                    "GET",
+                    &format!("/api/stages/{}/{}/logs/{}", project, stage, container),
+                )
+                .await?;
+            let entries: Vec<&Value> = response["logs"].as_array().into_iter().flatten().collect();
+            let since = last_seen.get(name).cloned().unwrap_or_default();
+            let mut fresh: Vec<&Value> = entries
+                .into_iter()
+                .filter(|e| e["timestamp"].as_str().unwrap_or_default() > since.as_str())
+                .collect();
+            fresh.sort_by(|a, b| {
+                a["timestamp"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .cmp(b["timestamp"].as_str().unwrap_or_default())
+            });
+            if first {
+                fresh = fresh.split_off(fresh.len().saturating_sub(lines));
+            }
+
+            for entry in &fresh {
+                let message = entry["message"].as_str().unwrap_or_default();
+                if message.is_empty() || !filter.matches(message) {
+                    continue;
+                }
+                let stderr = entry["stream"].as_str() == Some("stderr");
+                sink.line(name, colors[idx % colors.len()], stderr, message)?;
+            }
+            if let Some(latest) = fresh.last().and_then(|e| e["timestamp"].as_str()) {
+                last_seen.insert(name.clone(), latest.to_string());
+            }
+        }
+
+        if !follow {
+            sink.finish()?;
+            return Ok(());
+        }
+        sink.flush()?;
+        if first {
+            println!("{}", "Ctrl+C でログ追跡を終了".dimmed());
+            first = false;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(FOLLOW_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c() => {
+                sink.finish()?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host() {
+        assert_eq!(
+            DaemonHost::parse("ssh://deploy@edge-1").unwrap(),
+            DaemonHost {
+                user: "deploy".to_string(),
+                host: "edge-1".to_string(),
+                web_port: DEFAULT_WEB_PORT,
+            }
+        );
+        let daemon = DaemonHost::parse("ssh://edge-1:8080/").unwrap();
+        assert_eq!(daemon.user, "root");
+        assert_eq!(daemon.web_port, 8080);
+
+        assert!(DaemonHost::parse("edge-1").is_err());
+        assert!(DaemonHost::parse("ssh://deploy@").is_err());
+        assert!(DaemonHost::parse("ssh://edge-1:http").is_err());
+    }
+
+    #[test]
+    fn test_container_name_follows_local_template() {
+        let local = fleetflow_core::Flow {
+            name: "shop".to_string(),
+            container_name_template: Some("{service}.{project}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            container_name(Some(&local), "shop", "prod", "api"),
+            "api.shop"
+        );
+        assert_eq!(
+            container_name(Some(&local), "blog", "prod", "api"),
+            "blog-prod-api"
+        );
+        assert_eq!(container_name(None, "shop", "prod", "api"), "shop-prod-api");
+    }
+
+    #[test]
+    fn test_pick_target() {
+        let entries = vec![
+            ("shop".to_string(), "stg".to_string()),
+            ("shop".to_string(), "prod".to_string()),
+            ("blog".to_string(), "prod".to_string()),
+        ];
+        assert_eq!(
+            pick_target(&entries, Some("shop"), Some("prod")).unwrap(),
+            ("shop".to_string(), "prod".to_string())
+        );
+        assert_eq!(
+            pick_target(&entries, None, Some("stg")).unwrap(),
+            ("shop".to_string(), "stg".to_string())
+        );
+        let err = pick_target(&entries, None, Some("prod")).unwrap_err();
+        assert!(err.to_string().contains("shop/prod, blog/prod"));
+        assert!(pick_target(&entries, Some("shop"), Some("dev")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_services() {
+        let available = vec!["api".to_string(), "db".to_string(), "worker".to_string()];
+        assert_eq!(resolve_services(&available, &[]).unwrap(), available);
+        assert_eq!(
+            resolve_services(&available, &["work".to_string()]).unwrap(),
+            vec!["worker"]
+        );
+        let err = resolve_services(&available, &["cache".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("api, db, worker"));
+    }
+}
@@ -0,0 +1,390 @@
+//! `fleet report` — ローカル操作履歴の集計レポート
+//!
+//! `.fleetflow/audit.jsonl`（`crate::audit`）から、コマンドごとの実行頻度と平均所要時間、
+//! サービスごとの失敗率、セットアップステップの所要時間の推移を集計する。
+//! データはマシンの外へ出さない。振り返り用にターミナル表示または JSON で出力する。
+
+use crate::audit::{self, AuditEvent, AuditRecord};
+use chrono::{DateTime, Duration, FixedOffset};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub avg_duration_ms: u64,
+    /// 集計期間における 1 週間あたりの実行回数
+    pub per_week: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceStats {
+    pub service: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepStats {
+    pub step: String,
+    pub runs: usize,
+    pub avg_duration_ms: u64,
+    pub max_duration_ms: u64,
+    pub last_duration_ms: u64,
+    /// 前半に対する後半の平均所要時間の変化率（%）。実行が 2 回未満なら None
+    pub trend_pct: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub project: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub commands: Vec<CommandStats>,
+    pub services: Vec<ServiceStats>,
+    pub setup_steps: Vec<StepStats>,
+}
+
+fn rate(failures: usize, runs: usize) -> f64 {
+    if runs == 0 {
+        0.0
+    } else {
+        failures as f64 / runs as f64
+    }
+}
+
+fn average(values: &[u64]) -> u64 {
+    if values.is_empty() {
+        0
+    } else {
+        values.iter().sum::<u64>() / values.len() as u64
+    }
+}
+
+fn timestamp(record: &AuditRecord) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&record.at).ok()
+}
+
+/// 監査ログを集計する（`records` は記録順）
+pub fn build_report(project: &str, records: &[AuditRecord], stage: Option<&str>) -> Report {
+    let records: Vec<&AuditRecord> = records
+        .iter()
+        .filter(|r| r.project == project)
+        .filter(|r| match (&r.event, stage) {
+            (_, None) => true,
+            (AuditEvent::Command { stage: s, .. }, Some(stage))
+            | (AuditEvent::SetupStep { stage: s, .. }, Some(stage)) => s == stage,
+        })
+        .collect();
+
+    let times: Vec<DateTime<FixedOffset>> = records.iter().filter_map(|r| timestamp(r)).collect();
+    let from = times.iter().min().copied();
+    let to = times.iter().max().copied();
+    // 期間が 1 日未満でも頻度が極端にならないよう最低 1 日として扱う
+    let weeks = match (from, to) {
+        (Some(from), Some(to)) => {
+            (to - from).max(Duration::days(1)).num_seconds() as f64 / 604_800.0
+        }
+        _ => 1.0,
+    };
+
+    let mut commands: BTreeMap<&str, (usize, usize, Vec<u64>)> = BTreeMap::new();
+    let mut services: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    let mut steps: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for record in &records {
+        match &record.event {
+            AuditEvent::Command {
+                command,
+                services: targets,
+                duration_ms,
+                success,
+                ..
+            } => {
+                let entry = commands.entry(command).or_default();
+                entry.0 += 1;
+                entry.1 += usize::from(!success);
+                entry.2.push(*duration_ms);
+                for service in targets {
+                    let entry = services.entry(service).or_default();
+                    entry.0 += 1;
+                    entry.1 += usize::from(!success);
+                }
+            }
+            AuditEvent::SetupStep {
+                step,
+                status,
+                duration_ms,
+                ..
+            } => {
+                // スキップは所要時間の推移に含めない
+                if status != "skipped" {
+                    steps.entry(step).or_default().push(*duration_ms);
+                }
+            }
+        }
+    }
+
+    let mut service_stats: Vec<ServiceStats> = services
+        .into_iter()
+        .map(|(service, (runs, failures))| ServiceStats {
+            service: service.to_string(),
+            runs,
+            failures,
+            failure_rate: rate(failures, runs),
+        })
+        .collect();
+    service_stats.sort_by(|a, b| b.failure_rate.total_cmp(&a.failure_rate));
+
+    let mut step_stats: Vec<StepStats> = steps
+        .into_iter()
+        .map(|(step, durations)| {
+            let (older, newer) = durations.split_at(durations.len() / 2);
+            let trend_pct = (!older.is_empty() && average(older) > 0).then(|| {
+                (average(newer) as f64 - average(older) as f64) / average(older) as f64 * 100.0
+            });
+            StepStats {
+                step: step.to_string(),
+                runs: durations.len(),
+                avg_duration_ms: average(&durations),
+                max_duration_ms: durations.iter().copied().max().unwrap_or(0),
+                last_duration_ms: durations.last().copied().unwrap_or(0),
+                trend_pct,
+            }
+        })
+        .collect();
+    step_stats.sort_by(|a, b| b.avg_duration_ms.cmp(&a.avg_duration_ms));
+
+    Report {
+        project: project.to_string(),
+        from: from.map(|t| t.to_rfc3339()),
+        to: to.map(|t| t.to_rfc3339()),
+        commands: commands
+            .into_iter()
+            .map(|(command, (runs, failures, durations))| CommandStats {
+                command: command.to_string(),
+                runs,
+                failures,
+                failure_rate: rate(failures, runs),
+                avg_duration_ms: average(&durations),
+                per_week: runs as f64 / weeks,
+            })
+            .collect(),
+        services: service_stats,
+        setup_steps: step_stats,
+    }
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+fn render(report: &Report) {
+    println!(
+        "{}",
+        format!("操作レポート: {}", report.project).blue().bold()
+    );
+    if let (Some(from), Some(to)) = (&report.from, &report.to) {
+        println!("期間: {} 〜 {}", from, to);
+    }
+
+    println!();
+    println!("{}", "コマンド".bold());
+    println!(
+        "  {:<10} {:>6} {:>8} {:>8} {:>10}",
+        "COMMAND", "RUNS", "FAILED", "AVG", "PER WEEK"
+    );
+    for stats in &report.commands {
+        println!(
+            "  {:<10} {:>6} {:>7.0}% {:>8} {:>10.1}",
+            stats.command,
+            stats.runs,
+            stats.failure_rate * 100.0,
+            seconds(stats.avg_duration_ms),
+            stats.per_week
+        );
+    }
+
+    println!();
+    println!("{}", "サービス別の失敗率".bold());
+    println!("  {:<20} {:>6} {:>8}", "SERVICE", "RUNS", "FAILED");
+    for stats in &report.services {
+        let rate = format!("{:.0}%", stats.failure_rate * 100.0);
+        let rate = if stats.failures > 0 {
+            rate.red()
+        } else {
+            rate.green()
+        };
+        println!("  {:<20} {:>6} {:>8}", stats.service, stats.runs, rate);
+    }
+
+    if !report.setup_steps.is_empty() {
+        println!();
+        println!("{}", "セットアップステップ（平均所要時間の長い順）".bold());
+        println!(
+            "  {:<20} {:>6} {:>8} {:>8} {:>8} {:>8}",
+            "STEP", "RUNS", "AVG", "MAX", "LAST", "TREND"
+        );
+        for stats in &report.setup_steps {
+            let trend = match stats.trend_pct {
+                Some(pct) if pct > 10.0 => format!("{:+.0}%", pct).red(),
+                Some(pct) => format!("{:+.0}%", pct).normal(),
+                None => "-".dimmed(),
+            };
+            println!(
+                "  {:<20} {:>6} {:>8} {:>8} {:>8} {:>8}",
+                stats.step,
+                stats.runs,
+                seconds(stats.avg_duration_ms),
+                seconds(stats.max_duration_ms),
+                seconds(stats.last_duration_ms),
+                trend
+            );
+        }
+    }
+}
+
+pub fn handle(
+    config: &fleetflow_core::Flow,
+    project_root: &Path,
+    stage: Option<&str>,
+    since_days: Option<u32>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut records = audit::load(project_root);
+    if let Some(days) = since_days {
+        let cutoff = chrono::Local::now() - Duration::days(i64::from(days));
+        records.retain(|r| timestamp(r).is_some_and(|t| t >= cutoff));
+    }
+
+    let report = build_report(&config.name, &records, stage);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    if report.commands.is_empty() && report.setup_steps.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "操作履歴がありません（{} に fleet up / deploy 等の結果が記録されます）",
+                audit::log_path(project_root).display()
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+    render(&report);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(at: &str, command: &str, services: &[&str], ms: u64, success: bool) -> AuditRecord {
+        AuditRecord {
+            at: at.to_string(),
+            project: "myapp".to_string(),
+            event: AuditEvent::Command {
+                command: command.to_string(),
+                stage: "prod".to_string(),
+                services: services.iter().map(|s| s.to_string()).collect(),
+                duration_ms: ms,
+                success,
+                error: None,
+            },
+        }
+    }
+
+    fn step(at: &str, name: &str, status: &str, ms: u64) -> AuditRecord {
+        AuditRecord {
+            at: at.to_string(),
+            project: "myapp".to_string(),
+            event: AuditEvent::SetupStep {
+                stage: "prod".to_string(),
+                step: name.to_string(),
+                status: status.to_string(),
+                attempts: 1,
+                duration_ms: ms,
+            },
+        }
+    }
+
+    #[test]
+    fn test_build_report_aggregates_history() {
+        let records = vec![
+            command(
+                "2026-10-01T10:00:00+09:00",
+                "deploy",
+                &["api", "web"],
+                10_000,
+                true,
+            ),
+            command(
+                "2026-10-08T10:00:00+09:00",
+                "deploy",
+                &["api"],
+                20_000,
+                false,
+            ),
+            command(
+                "2026-10-15T10:00:00+09:00",
+                "deploy",
+                &["api", "web"],
+                30_000,
+                true,
+            ),
+            step("2026-10-01T10:01:00+09:00", "migrate", "done", 1_000),
+            step("2026-10-08T10:01:00+09:00", "migrate", "done", 2_000),
+            step("2026-10-08T10:02:00+09:00", "seed", "skipped", 0),
+            AuditRecord {
+                project: "other".to_string(),
+                ..command("2026-10-15T10:00:00+09:00", "up", &["db"], 1, false)
+            },
+        ];
+        let report = build_report("myapp", &records, None);
+
+        assert_eq!(report.commands.len(), 1);
+        let deploy = &report.commands[0];
+        assert_eq!(deploy.runs, 3);
+        assert_eq!(deploy.failures, 1);
+        assert_eq!(deploy.avg_duration_ms, 20_000);
+        assert!((deploy.per_week - 1.5).abs() < 1e-9);
+
+        assert_eq!(report.services[0].service, "api");
+        assert_eq!(report.services[0].failures, 1);
+        assert_eq!(report.services[1].service, "web");
+        assert_eq!(report.services[1].failures, 0);
+
+        assert_eq!(report.setup_steps.len(), 1);
+        let migrate = &report.setup_steps[0];
+        assert_eq!(migrate.runs, 2);
+        assert_eq!(migrate.max_duration_ms, 2_000);
+        assert_eq!(migrate.trend_pct, Some(100.0));
+    }
+
+    #[test]
+    fn test_build_report_filters_stage() {
+        let records = vec![command(
+            "2026-10-01T10:00:00+09:00",
+            "up",
+            &["api"],
+            1_000,
+            true,
+        )];
+        assert_eq!(
+            build_report("myapp", &records, Some("prod")).commands.len(),
+            1
+        );
+        assert!(
+            build_report("myapp", &records, Some("dev"))
+                .commands
+                .is_empty()
+        );
+    }
+}
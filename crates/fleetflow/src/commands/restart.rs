@@ -1,11 +1,14 @@
 use crate::docker;
 use crate::utils;
+use bollard::Docker;
 use colored::Colorize;
 
 pub async fn handle(
     config: &fleetflow_core::Flow,
     service: Option<String>,
     stage: Option<String>,
+    rolling: bool,
+    cascade: bool,
 ) -> anyhow::Result<()> {
     // ステージ名の決定
     let stage_name = utils::determine_stage_name(stage, config)?;
@@ -17,136 +20,347 @@ pub async fn handle(
         .get(&stage_name)
         .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
 
-    let target_services = if let Some(ref svc) = service {
-        if !stage_config.services.contains(svc) {
-            return Err(anyhow::anyhow!(
-                "サービス '{}' はステージ '{}' に含まれていません。\n利用可能なサービス: {}",
-                svc,
-                stage_name,
-                stage_config.services.join(", ")
-            ));
-        }
-        vec![svc.clone()]
-    } else {
-        stage_config.services.clone()
-    };
-
-    let is_stage_restart = service.is_none();
-    if is_stage_restart {
-        println!(
-            "{}",
-            format!(
-                "ステージ '{}' の全サービス ({} 個) を再起動中...",
-                stage_name,
-                target_services.len()
-            )
-            .green()
-        );
-    } else {
-        println!(
-            "{}",
-            format!("サービス '{}' を再起動中...", target_services[0]).green()
-        );
+    if let Some(ref svc) = service
+        && !stage_config.services.contains(svc)
+    {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' はステージ '{}' に含まれていません。\n利用可能なサービス: {}",
+            svc,
+            stage_name,
+            stage_config.services.join(", ")
+        ));
     }
 
+    // tmpfs 上のシークレットはホスト再起動で消えるため、bind mount 元を書き戻す
+    fleetflow_container::rematerialize_stage_secrets(config, &stage_name)?;
+
     // Docker接続
     let docker_conn = docker::init_docker_with_error_handling().await?;
 
-    for svc_name in &target_services {
-        let service_def = config
-            .services
-            .get(svc_name)
-            .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", svc_name))?;
+    let Some(svc_name) = service else {
+        return restart_stage(&docker_conn, config, &stage_name, stage_config, rolling).await;
+    };
 
-        let container_name = format!("{}-{}-{}", config.name, stage_name, svc_name);
+    println!(
+        "{}",
+        format!("サービス '{}' を再起動中...", svc_name).green()
+    );
+    stop_service(&docker_conn, config, &stage_name, &svc_name).await?;
+    start_service(&docker_conn, config, &stage_name, &svc_name).await?;
 
-        if is_stage_restart {
-            println!();
-            println!("{}", format!("▶ {} を再起動中...", svc_name).green().bold());
+    if cascade {
+        let dependents = reverse_dependents(config, &stage_config.services, &svc_name);
+        if dependents.is_empty() {
+            println!("  ℹ '{}' に依存するサービスはありません", svc_name);
+        } else {
+            // 古い接続を掴んだままの依存サービスは、対象の準備完了後に起動順で再起動する
+            wait_ready(&docker_conn, config, &stage_name, &svc_name).await?;
+            for (i, dependent) in dependents.iter().enumerate() {
+                println!();
+                println!(
+                    "{}",
+                    format!(
+                        "▶ [{}/{}] 依存サービス {} を再起動中...",
+                        i + 1,
+                        dependents.len(),
+                        dependent
+                    )
+                    .green()
+                    .bold()
+                );
+                stop_service(&docker_conn, config, &stage_name, dependent).await?;
+                start_service(&docker_conn, config, &stage_name, dependent).await?;
+                wait_ready(&docker_conn, config, &stage_name, dependent).await?;
+            }
         }
+    }
 
-        // コンテナの停止
-        println!("  ↓ コンテナを停止中...");
-        match docker_conn
-            .stop_container(
-                &container_name,
-                None::<bollard::query_parameters::StopContainerOptions>,
-            )
-            .await
-        {
-            Ok(_) => println!("  ✓ コンテナを停止しました"),
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 404, ..
-            }) => {
-                println!("  ℹ コンテナが存在しません");
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 304, ..
-            }) => {
-                println!("  ℹ コンテナは既に停止しています");
+    println!();
+    println!(
+        "{}",
+        format!("✓ '{}' を再起動しました", svc_name).green().bold()
+    );
+
+    Ok(())
+}
+
+/// `target` に（推移的に）依存するステージ内のサービスを起動順に返す
+fn reverse_dependents(
+    config: &fleetflow_core::Flow,
+    stage_services: &[String],
+    target: &str,
+) -> Vec<String> {
+    let mut dependents: Vec<String> = Vec::new();
+    let mut queue = vec![target.to_string()];
+    while let Some(current) = queue.pop() {
+        for name in stage_services {
+            let depends = config
+                .services
+                .get(name)
+                .is_some_and(|s| s.depends_on.contains(&current));
+            if depends && name != target && !dependents.contains(name) {
+                dependents.push(name.clone());
+                queue.push(name.clone());
             }
-            Err(e) => return Err(e.into()),
         }
+    }
+
+    fleetflow_container::shutdown_order(&dependents, config)
+        .into_iter()
+        .rev()
+        .collect()
+}
+
+/// ステージ全体を依存順に再起動
+///
+/// 通常は依存する側から全サービスを停止し、依存される側から起動する。
+/// `rolling` の場合は起動順に 1 サービスずつ再起動し、準備完了を待ってから次へ進む。
+async fn restart_stage(
+    docker_conn: &Docker,
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    stage_config: &fleetflow_core::Stage,
+    rolling: bool,
+) -> anyhow::Result<()> {
+    let shutdown = fleetflow_container::shutdown_order(&stage_config.services, config);
+    let startup: Vec<String> = shutdown.iter().rev().cloned().collect();
 
-        // コンテナの起動
-        println!("  ↑ コンテナを起動中...");
-        match docker_conn
-            .start_container(
+    println!(
+        "{}",
+        format!(
+            "ステージ '{}' の全サービス ({} 個) を{}再起動中...",
+            stage_name,
+            startup.len(),
+            if rolling { "ローリング" } else { "" }
+        )
+        .green()
+    );
+
+    if rolling {
+        for (i, svc_name) in startup.iter().enumerate() {
+            println!();
+            println!(
+                "{}",
+                format!("▶ [{}/{}] {} を再起動中...", i + 1, startup.len(), svc_name)
+                    .green()
+                    .bold()
+            );
+            stop_service(docker_conn, config, stage_name, svc_name).await?;
+            start_service(docker_conn, config, stage_name, svc_name).await?;
+            wait_ready(docker_conn, config, stage_name, svc_name).await?;
+        }
+    } else {
+        for svc_name in &shutdown {
+            println!();
+            println!("{}", format!("■ {} を停止中...", svc_name).yellow().bold());
+            stop_service(docker_conn, config, stage_name, svc_name).await?;
+        }
+        for svc_name in &startup {
+            println!();
+            println!("{}", format!("▶ {} を起動中...", svc_name).green().bold());
+            start_service(docker_conn, config, stage_name, svc_name).await?;
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ ステージ '{}' の全サービスを再起動しました", stage_name)
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
+fn service_def<'a>(
+    config: &'a fleetflow_core::Flow,
+    svc_name: &str,
+) -> anyhow::Result<&'a fleetflow_core::Service> {
+    config
+        .services
+        .get(svc_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' が見つかりません", svc_name))
+}
+
+async fn stop_service(
+    docker_conn: &Docker,
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    svc_name: &str,
+) -> anyhow::Result<()> {
+    let service_def = service_def(config, svc_name)?;
+    let container_name = config.container_name(stage_name, svc_name);
+
+    println!("  ↓ コンテナを停止中...");
+    match docker_conn
+        .stop_container(
+            &container_name,
+            fleetflow_container::stop_container_options(service_def),
+        )
+        .await
+    {
+        Ok(_) => println!("  ✓ コンテナを停止しました"),
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            println!("  ℹ コンテナが存在しません");
+        }
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 304, ..
+        }) => {
+            println!("  ℹ コンテナは既に停止しています");
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
+
+async fn start_service(
+    docker_conn: &Docker,
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    svc_name: &str,
+) -> anyhow::Result<()> {
+    let service_def = service_def(config, svc_name)?;
+    let container_name = config.container_name(stage_name, svc_name);
+
+    println!("  ↑ コンテナを起動中...");
+    match docker_conn
+        .start_container(
+            &container_name,
+            None::<bollard::query_parameters::StartContainerOptions>,
+        )
+        .await
+    {
+        Ok(_) => {
+            println!("  ✓ コンテナを起動しました");
+        }
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            // コンテナが存在しない場合は作成して起動
+            println!("  ℹ コンテナが存在しないため、新規作成します");
+
+            let (container_config, create_options) =
+                fleetflow_container::service_to_container_config(
+                    svc_name,
+                    service_def,
+                    stage_name,
+                    config,
+                );
+
+            docker::ensure_container_running(
+                docker_conn,
                 &container_name,
-                None::<bollard::query_parameters::StartContainerOptions>,
+                container_config,
+                create_options,
+                &docker::registry_auth(config, stage_name)?,
             )
-            .await
-        {
-            Ok(_) => {
-                println!("  ✓ コンテナを起動しました");
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 404, ..
-            }) => {
-                // コンテナが存在しない場合は作成して起動
-                println!("  ℹ コンテナが存在しないため、新規作成します");
-
-                let (container_config, create_options) =
-                    fleetflow_container::service_to_container_config(
-                        svc_name,
-                        service_def,
-                        &stage_name,
-                        &config.name,
-                    );
-
-                docker::ensure_container_running(
-                    &docker_conn,
-                    &container_name,
-                    container_config,
-                    create_options,
-                )
-                .await?;
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 304, ..
-            }) => {
-                println!("  ℹ コンテナは既に起動しています");
-            }
-            Err(e) => return Err(e.into()),
+            .await?;
+        }
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 304, ..
+        }) => {
+            println!("  ℹ コンテナは既に起動しています");
         }
+        Err(e) => return Err(e.into()),
     }
 
-    println!();
-    if is_stage_restart {
-        println!(
-            "{}",
-            format!("✓ ステージ '{}' の全サービスを再起動しました", stage_name)
-                .green()
-                .bold()
-        );
-    } else {
-        println!(
-            "{}",
-            format!("✓ '{}' を再起動しました", target_services[0])
-                .green()
-                .bold()
-        );
+    Ok(())
+}
+
+/// コンテナを作り直す（pull した新しいイメージで起動し、準備完了を待つ）
+pub(crate) async fn recreate_service(
+    docker_conn: &Docker,
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    svc_name: &str,
+) -> anyhow::Result<()> {
+    stop_service(docker_conn, config, stage_name, svc_name).await?;
+    let container_name = config.container_name(stage_name, svc_name);
+    match docker_conn
+        .remove_container(
+            &container_name,
+            None::<bollard::query_parameters::RemoveContainerOptions>,
+        )
+        .await
+    {
+        Ok(_)
+        | Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {}
+        Err(e) => return Err(e.into()),
     }
+    start_service(docker_conn, config, stage_name, svc_name).await?;
+    wait_ready(docker_conn, config, stage_name, svc_name).await
+}
+
+/// ローリング / カスケード再起動で次のサービスへ進む前に準備完了（healthy / running）を待つ
+async fn wait_ready(
+    docker_conn: &Docker,
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+    svc_name: &str,
+) -> anyhow::Result<()> {
+    let service_def = service_def(config, svc_name)?;
+    let container_name = config.container_name(stage_name, svc_name);
+    let wait_config = service_def.wait_for.clone().unwrap_or_default();
+
+    println!("  ⏳ 準備完了を待機中...");
+    fleetflow_container::wait_for_service(docker_conn, &container_name, &wait_config)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "'{}' が準備完了にならなかったため、再起動を中断しました: {}",
+                svc_name,
+                e
+            )
+        })?;
+    println!("  ✓ 準備完了");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fleetflow_core::{Flow, Service};
+    use std::collections::HashMap;
+
+    fn service(depends_on: &[&str]) -> Service {
+        Service {
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_reverse_dependents() {
+        let flow = Flow {
+            name: "test".to_string(),
+            services: HashMap::from([
+                ("db".to_string(), service(&[])),
+                ("cache".to_string(), service(&[])),
+                ("api".to_string(), service(&["db", "cache"])),
+                ("worker".to_string(), service(&["api"])),
+                ("web".to_string(), service(&["api", "worker"])),
+                ("batch".to_string(), service(&["db"])),
+            ]),
+            ..Default::default()
+        };
+        let stage: Vec<String> = ["db", "cache", "api", "worker", "web"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        // batch はステージ外なので含まない。起動順（依存される側が先）で返す
+        assert_eq!(
+            reverse_dependents(&flow, &stage, "db"),
+            vec!["api", "worker", "web"]
+        );
+        assert_eq!(reverse_dependents(&flow, &stage, "worker"), vec!["web"]);
+        assert!(reverse_dependents(&flow, &stage, "web").is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! `fleet schema export` — 設定ファイルのスキーマを JSON Schema / Markdown で出力
+//!
+//! strict モードの検証に使うスキーマ（`fleetflow_core::parser::schema`）から生成するため、
+//! エディタ補完やドキュメントがパーサーの受け付けるキーとずれない。
+
+use colored::Colorize;
+use std::path::Path;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    /// JSON Schema（draft 2020-12）
+    #[value(alias = "json_schema")]
+    JsonSchema,
+    /// Markdown のリファレンス
+    Markdown,
+}
+
+/// `fleet schema export`
+pub fn handle_export(format: SchemaFormat, output: Option<&Path>) -> anyhow::Result<()> {
+    let rendered = match format {
+        SchemaFormat::JsonSchema => {
+            let mut json = serde_json::to_string_pretty(&fleetflow_core::export_json_schema())?;
+            json.push('\n');
+            json
+        }
+        SchemaFormat::Markdown => fleetflow_core::export_markdown(),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .map_err(|e| anyhow::anyhow!("{} への書き込みに失敗: {}", path.display(), e))?;
+            eprintln!(
+                "{}",
+                format!("✓ {} に書き出しました", path.display()).green()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
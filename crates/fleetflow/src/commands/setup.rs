@@ -0,0 +1,503 @@
+//! セットアップステップの実行 — fleet.kdl の `setup { step ... }`
+//!
+//! `fleet up` でサービス起動後に、DB 初期化やシードデータ投入などの
+//! ユーザー定義ステップを `after` の依存順に実行する。各ステップは
+//! プロジェクトルートで `sh -c` として実行され、所要時間と結果を表示する。
+//!
+//! 各ステップの結果は `.fleetflow/setup-state.json` にステージごとに記録され、
+//! `fleet up --resume` では前回完了済み（かつ `run` が変わっていない）ステップを飛ばして再開する。
+
+use crate::audit::{self, AuditEvent};
+use colored::Colorize;
+use fleetflow_build::{ProgressEvent, ProgressStatus};
+use fleetflow_core::{Flow, SetupStep, order_setup_steps};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// `retry_delay` 未指定時の再試行までの待機秒数
+const DEFAULT_RETRY_DELAY_SECS: u64 = 2;
+
+/// 1 ステップの実行結果
+enum Outcome {
+    /// 実行して成功（試行回数）
+    Done(u32),
+    Skipped(&'static str),
+}
+
+/// ステップの記録状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StepStatus {
+    Done,
+    Skipped,
+    Failed,
+}
+
+/// 1 ステップの実行記録
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StepRecord {
+    status: StepStatus,
+    /// 実行したコマンド（変更されていたら再開時も再実行する）
+    run: String,
+    attempts: u32,
+    duration_ms: u64,
+    finished_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl StepStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Done => "done",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// `.fleetflow/setup-state.json` の内容（ステージ名 → ステップ名 → 記録）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SetupState {
+    #[serde(default)]
+    stages: BTreeMap<String, BTreeMap<String, StepRecord>>,
+}
+
+impl SetupState {
+    fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".fleetflow").join("setup-state.json")
+    }
+
+    /// 読み込む（存在しない・壊れている場合は空として扱う）
+    fn load(project_root: &Path) -> Self {
+        std::fs::read_to_string(Self::path(project_root))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, project_root: &Path) -> anyhow::Result<()> {
+        let path = Self::path(project_root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow::anyhow!("{} への書き込みに失敗: {}", path.display(), e))
+    }
+
+    /// 前回の実行で完了しており、コマンドも変わっていないか
+    fn completed(&self, stage_name: &str, step: &SetupStep) -> bool {
+        self.stages
+            .get(stage_name)
+            .and_then(|steps| steps.get(&step.name))
+            .is_some_and(|r| r.status == StepStatus::Done && r.run == step.run)
+    }
+
+    fn record(
+        &mut self,
+        stage_name: &str,
+        step: &SetupStep,
+        status: StepStatus,
+        attempts: u32,
+        elapsed: Duration,
+        error: Option<String>,
+    ) {
+        self.stages
+            .entry(stage_name.to_string())
+            .or_default()
+            .insert(
+                step.name.clone(),
+                StepRecord {
+                    status,
+                    run: step.run.clone(),
+                    attempts,
+                    duration_ms: elapsed.as_millis() as u64,
+                    finished_at: chrono::Local::now().to_rfc3339(),
+                    error,
+                },
+            );
+    }
+}
+
+/// ステージで実行対象となるステップを依存順に返す
+fn planned_steps<'a>(config: &'a Flow, stage_name: &str) -> anyhow::Result<Vec<&'a SetupStep>> {
+    let ordered = order_setup_steps(&config.setup).map_err(|e| anyhow::anyhow!(e))?;
+    Ok(ordered
+        .into_iter()
+        .filter(|step| step.applies_to(stage_name))
+        .collect())
+}
+
+fn shell(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    script: &str,
+) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c")
+        .arg(script)
+        .current_dir(project_root)
+        .env("FLEET_PROJECT", &config.name)
+        .env("FLEET_STAGE", stage_name)
+        .kill_on_drop(true);
+    cmd
+}
+
+/// 1 回分の実行（タイムアウト付き）
+async fn run_once(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    step: &SetupStep,
+) -> anyhow::Result<()> {
+    let status = shell(project_root, config, stage_name, &step.run).status();
+    let status = match step.timeout {
+        Some(secs) => tokio::time::timeout(Duration::from_secs(secs), status)
+            .await
+            .map_err(|_| anyhow::anyhow!("{} 秒でタイムアウトしました", secs))?,
+        None => status.await,
+    }?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("終了コード {:?}", status.code()))
+    }
+}
+
+async fn run_step(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    step: &SetupStep,
+    skip: &[String],
+) -> anyhow::Result<Outcome> {
+    if skip.iter().any(|s| s == &step.name) {
+        return Ok(Outcome::Skipped("--skip-step"));
+    }
+    if let Some(check) = &step.skip_if
+        && shell(project_root, config, stage_name, check)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await
+            .is_ok_and(|s| s.success())
+    {
+        return Ok(Outcome::Skipped("skip_if"));
+    }
+
+    let attempts = step.retries + 1;
+    let delay = step.retry_delay.unwrap_or(DEFAULT_RETRY_DELAY_SECS);
+    let mut attempt = 1;
+    loop {
+        match run_once(project_root, config, stage_name, step).await {
+            Ok(()) => return Ok(Outcome::Done(attempt)),
+            Err(e) if attempt < attempts => {
+                println!(
+                    "    {} {}（{}/{} 回目）、{} 秒後に再試行します",
+                    "↻".yellow(),
+                    e,
+                    attempt,
+                    attempts,
+                    delay
+                );
+                tokio::time::sleep(Duration::from_secs(delay)).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "ステップ '{}' が失敗しました: {}（{} 回試行）",
+                    step.name,
+                    e,
+                    attempts
+                ));
+            }
+        }
+    }
+}
+
+/// 直近の実行記録を監査ログにも残す（`fleet report` の集計用）
+fn audit_step(
+    project_root: &Path,
+    config: &Flow,
+    stage_name: &str,
+    step: &SetupStep,
+    state: &SetupState,
+) {
+    if let Some(record) = state.stages.get(stage_name).and_then(|s| s.get(&step.name)) {
+        audit::append(
+            project_root,
+            &config.name,
+            AuditEvent::SetupStep {
+                stage: stage_name.to_string(),
+                step: step.name.clone(),
+                status: record.status.as_str().to_string(),
+                attempts: record.attempts,
+                duration_ms: record.duration_ms,
+            },
+        );
+    }
+}
+
+/// ステージのセットアップステップを実行する
+///
+/// `dry_run` の場合は実行順のみ表示する。`skip` に指定したステップは実行しない。
+/// `resume` の場合は `.fleetflow/setup-state.json` で完了済みのステップを飛ばす。
+pub async fn run(
+    config: &Flow,
+    project_root: &Path,
+    stage_name: &str,
+    skip: &[String],
+    resume: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let steps = planned_steps(config, stage_name)?;
+    if steps.is_empty() {
+        return Ok(());
+    }
+    for name in skip {
+        if !config.setup.iter().any(|s| &s.name == name) {
+            return Err(anyhow::anyhow!(
+                "セットアップステップ '{}' が見つかりません",
+                name
+            ));
+        }
+    }
+
+    let mut state = SetupState::load(project_root);
+    if !resume {
+        state.stages.remove(stage_name);
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("セットアップステップ ({})", steps.len())
+            .blue()
+            .bold()
+    );
+
+    if dry_run {
+        for (i, step) in steps.iter().enumerate() {
+            let note = if resume && state.completed(stage_name, step) {
+                "（前回完了済みのためスキップ）".dimmed().to_string()
+            } else {
+                String::new()
+            };
+            println!(
+                "  [{}/{}] {}: {}{}",
+                i + 1,
+                steps.len(),
+                step.name.cyan(),
+                step.run,
+                note
+            );
+        }
+        return Ok(());
+    }
+
+    let total = Instant::now();
+    let mut skipped = 0;
+    let progress = fleetflow_build::reporter();
+    let event = |step: &SetupStep, status: ProgressStatus, message: String, done: usize| {
+        progress.event(
+            &ProgressEvent::new("setup", status, message)
+                .target(&step.name)
+                .percent((done * 100 / steps.len()) as f64),
+        );
+    };
+    for (i, step) in steps.iter().enumerate() {
+        if resume && state.completed(stage_name, step) {
+            skipped += 1;
+            event(
+                step,
+                ProgressStatus::Skipped,
+                "前回完了済み".to_string(),
+                i + 1,
+            );
+            println!(
+                "  [{}/{}] {} {} をスキップ（前回完了済み: setup-state）",
+                i + 1,
+                steps.len(),
+                "⊘".yellow(),
+                step.name
+            );
+            continue;
+        }
+
+        println!("  [{}/{}] {}", i + 1, steps.len(), step.name.cyan().bold());
+        event(step, ProgressStatus::Started, step.run.clone(), i);
+        let started = Instant::now();
+        let result = run_step(project_root, config, stage_name, step, skip).await;
+        let elapsed = started.elapsed();
+        match result {
+            Ok(Outcome::Done(attempts)) => {
+                state.record(stage_name, step, StepStatus::Done, attempts, elapsed, None);
+                event(
+                    step,
+                    ProgressStatus::Done,
+                    format!("{:.1}s", elapsed.as_secs_f64()),
+                    i + 1,
+                );
+                println!(
+                    "  {} {} ({:.1}s)",
+                    "✓".green(),
+                    step.name,
+                    elapsed.as_secs_f64()
+                );
+            }
+            Ok(Outcome::Skipped(reason)) => {
+                skipped += 1;
+                state.record(stage_name, step, StepStatus::Skipped, 0, elapsed, None);
+                event(step, ProgressStatus::Skipped, reason.to_string(), i + 1);
+                println!("  {} {} をスキップ（{}）", "⊘".yellow(), step.name, reason);
+            }
+            Err(e) => {
+                event(step, ProgressStatus::Failed, e.to_string(), i);
+                state.record(
+                    stage_name,
+                    step,
+                    StepStatus::Failed,
+                    step.retries + 1,
+                    elapsed,
+                    Some(e.to_string()),
+                );
+                audit_step(project_root, config, stage_name, step, &state);
+                state.save(project_root)?;
+                println!(
+                    "{}",
+                    "fleet up --resume で完了済みのステップを飛ばして再開できます".yellow()
+                );
+                return Err(e);
+            }
+        }
+        audit_step(project_root, config, stage_name, step, &state);
+        state.save(project_root)?;
+    }
+
+    println!(
+        "{}",
+        format!(
+            "✓ セットアップ完了: {} 実行 / {} スキップ ({:.1}s)",
+            steps.len() - skipped,
+            skipped,
+            total.elapsed().as_secs_f64()
+        )
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(steps: Vec<SetupStep>) -> Flow {
+        Flow {
+            name: "myapp".to_string(),
+            setup: steps,
+            ..Default::default()
+        }
+    }
+
+    fn step(name: &str, run: &str) -> SetupStep {
+        SetupStep {
+            name: name.to_string(),
+            run: run.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_planned_steps_filters_stage() {
+        let mut seed = step("seed", "true");
+        seed.stages = vec!["local".to_string()];
+        let mut migrate = step("migrate", "true");
+        migrate.after = vec!["seed".to_string()];
+        let config = flow(vec![migrate, seed]);
+
+        let local: Vec<&str> = planned_steps(&config, "local")
+            .unwrap()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(local, vec!["seed", "migrate"]);
+
+        let prod: Vec<&str> = planned_steps(&config, "prod")
+            .unwrap()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(prod, vec!["migrate"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_step_retries_and_skips() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = flow(vec![]);
+
+        let mut flaky = step("flaky", "test -f marker || { touch marker; exit 1; }");
+        flaky.retries = 1;
+        flaky.retry_delay = Some(0);
+        let outcome = run_step(dir.path(), &config, "local", &flaky, &[])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, Outcome::Done(2)));
+
+        let mut guarded = step("guarded", "exit 1");
+        guarded.skip_if = Some("test -f marker".to_string());
+        let outcome = run_step(dir.path(), &config, "local", &guarded, &[])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, Outcome::Skipped("skip_if")));
+
+        let failing = step("failing", "exit 3");
+        assert!(
+            run_step(dir.path(), &config, "local", &failing, &[])
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = step("first", "echo x >> first.log");
+        let mut second = step("second", "test -f ok");
+        second.after = vec!["first".to_string()];
+        let config = flow(vec![first, second]);
+
+        // 2 番目が失敗 → 状態に first=done, second=failed が残る
+        assert!(
+            run(&config, dir.path(), "local", &[], false, false)
+                .await
+                .is_err()
+        );
+        let state = SetupState::load(dir.path());
+        let records = &state.stages["local"];
+        assert_eq!(records["first"].status, StepStatus::Done);
+        assert_eq!(records["second"].status, StepStatus::Failed);
+
+        // 再開時は first を実行しない
+        std::fs::write(dir.path().join("ok"), "").unwrap();
+        run(&config, dir.path(), "local", &[], true, false)
+            .await
+            .unwrap();
+        let log = std::fs::read_to_string(dir.path().join("first.log")).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        let state = SetupState::load(dir.path());
+        assert_eq!(state.stages["local"]["second"].status, StepStatus::Done);
+
+        // コマンドが変わったステップは再開時も再実行する
+        let mut changed = config.clone();
+        changed.setup[0].run = "echo y >> first.log".to_string();
+        run(&changed, dir.path(), "local", &[], true, false)
+            .await
+            .unwrap();
+        let log = std::fs::read_to_string(dir.path().join("first.log")).unwrap();
+        assert_eq!(log.lines().count(), 2);
+    }
+}
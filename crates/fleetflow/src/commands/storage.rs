@@ -0,0 +1,353 @@
+//! `fleet storage sync` — ローカルディレクトリとオブジェクトストレージ（R2）の同期
+//!
+//! `fleet storage sync ./dist r2://assets/site` でアップロード、引数を逆にするとダウンロード。
+//! 内容の同一性は ETag（MD5。マルチパートは `<md5>-<parts>`）で判定し、変わったファイルだけ転送する。
+//! 認証は `R2_ACCESS_KEY_ID` / `R2_SECRET_ACCESS_KEY`、アカウントは cloudflare provider の
+//! `account_id` または `CLOUDFLARE_ACCOUNT_ID`。
+
+use colored::Colorize;
+use fleetflow_cloud_cloudflare::R2Client;
+use fleetflow_cloud_cloudflare::r2::{content_type_for, local_etag};
+use fleetflow_core::Flow;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const R2_SCHEME: &str = "r2://";
+
+/// 同期の端点
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Location {
+    Local(PathBuf),
+    R2 { bucket: String, prefix: String },
+}
+
+impl Location {
+    fn parse(value: &str) -> anyhow::Result<Self> {
+        let Some(rest) = value.strip_prefix(R2_SCHEME) else {
+            return Ok(Location::Local(PathBuf::from(value)));
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(anyhow::anyhow!(
+                "バケット名がありません: {}（r2://bucket/prefix の形式で指定してください）",
+                value
+            ));
+        }
+        let prefix = prefix.trim_matches('/');
+        Ok(Location::R2 {
+            bucket: bucket.to_string(),
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", prefix)
+            },
+        })
+    }
+}
+
+/// 転送・削除の計画（パスは同期ルートからの相対パス）
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SyncPlan {
+    transfer: Vec<String>,
+    delete: Vec<String>,
+    unchanged: usize,
+}
+
+/// 同期元と同期先の ETag を比べて計画を立てる
+fn plan(
+    source: &BTreeMap<String, String>,
+    destination: &BTreeMap<String, String>,
+    delete: bool,
+) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    for (path, etag) in source {
+        if destination.get(path) == Some(etag) {
+            plan.unchanged += 1;
+        } else {
+            plan.transfer.push(path.clone());
+        }
+    }
+    if delete {
+        plan.delete = destination
+            .keys()
+            .filter(|path| !source.contains_key(*path))
+            .cloned()
+            .collect();
+    }
+    plan
+}
+
+/// ディレクトリ配下のファイルを再帰的に集める（`/` 区切りの相対パス → ETag）
+fn local_files(root: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    if !root.exists() {
+        return Ok(files);
+    }
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative, local_etag(&std::fs::read(&path)?));
+        }
+    }
+    Ok(files)
+}
+
+/// プレフィックス配下のオブジェクト（プレフィックスを除いたキー → ETag）
+async fn remote_files(
+    client: &R2Client,
+    bucket: &str,
+    prefix: &str,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    Ok(client
+        .list_objects(bucket, prefix)
+        .await?
+        .into_iter()
+        .filter_map(|object| {
+            let relative = object.key.strip_prefix(prefix)?.to_string();
+            (!relative.is_empty() && !relative.ends_with('/')).then_some((relative, object.etag))
+        })
+        .collect())
+}
+
+/// R2 の認証情報（アカウント ID は provider の宣言を優先）
+pub(crate) fn client(config: &Flow) -> anyhow::Result<R2Client> {
+    R2Client::from_env(crate::dns::account_id(config)).map_err(|e| {
+        anyhow::anyhow!(
+            "R2 の認証情報がありません: {}（R2_ACCESS_KEY_ID / R2_SECRET_ACCESS_KEY を設定してください）",
+            e
+        )
+    })
+}
+
+/// ディレクトリを R2 にアップロードする（ETag が同じファイルは飛ばす）
+pub(crate) async fn upload_dir(
+    client: &R2Client,
+    dir: &Path,
+    bucket: &str,
+    prefix: &str,
+    delete: bool,
+    dry_run: bool,
+    jobs: usize,
+) -> anyhow::Result<()> {
+    let local = local_files(dir)?;
+    let remote = remote_files(client, bucket, prefix).await?;
+    let plan = plan(&local, &remote, delete);
+    print_plan(&plan, "↑", &format!("r2://{}/{}", bucket, prefix));
+    if dry_run {
+        return Ok(());
+    }
+
+    stream::iter(plan.transfer.iter())
+        .map(|path| {
+            let key = format!("{}{}", prefix, path);
+            let expected = &local[path];
+            async move {
+                let data = tokio::fs::read(dir.join(path)).await?;
+                let etag = client
+                    .upload_object(bucket, &key, data, content_type_for(path))
+                    .await?;
+                if !etag.is_empty() && etag != *expected {
+                    return Err(anyhow::anyhow!(
+                        "チェックサムが一致しません: {}（local {} / remote {}）",
+                        key,
+                        expected,
+                        etag
+                    ));
+                }
+                println!("  {} {}", "↑".green(), path);
+                Ok::<(), anyhow::Error>(())
+            }
+        })
+        .buffer_unordered(jobs)
+        .try_collect::<Vec<()>>()
+        .await?;
+
+    for path in &plan.delete {
+        client
+            .delete_object(bucket, &format!("{}{}", prefix, path))
+            .await?;
+        println!("  {} {}", "✗".red(), path);
+    }
+    print_summary(&plan);
+    Ok(())
+}
+
+/// `fleet storage sync`
+pub async fn handle_sync(
+    config: &Flow,
+    source: &str,
+    destination: &str,
+    delete: bool,
+    dry_run: bool,
+    jobs: usize,
+) -> anyhow::Result<()> {
+    let source = Location::parse(source)?;
+    let destination = Location::parse(destination)?;
+    let jobs = jobs.max(1);
+
+    match (source, destination) {
+        (Location::Local(dir), Location::R2 { bucket, prefix }) => {
+            if !dir.is_dir() {
+                return Err(anyhow::anyhow!(
+                    "ディレクトリが見つかりません: {}",
+                    dir.display()
+                ));
+            }
+            let client = client(config)?;
+            upload_dir(&client, &dir, &bucket, &prefix, delete, dry_run, jobs).await?;
+        }
+        (Location::R2 { bucket, prefix }, Location::Local(dir)) => {
+            let client = client(config)?;
+            let remote = remote_files(&client, &bucket, &prefix).await?;
+            let local = local_files(&dir)?;
+            let plan = plan(&remote, &local, delete);
+            print_plan(&plan, "↓", &dir.display().to_string());
+            if dry_run {
+                return Ok(());
+            }
+
+            stream::iter(plan.transfer.iter())
+                .map(|path| {
+                    let (client, bucket, dir) = (&client, &bucket, &dir);
+                    let key = format!("{}{}", prefix, path);
+                    let expected = &remote[path];
+                    async move {
+                        let data = client.get_object(bucket, &key).await?;
+                        // マルチパートの ETag はパートサイズに依存するため単一パートのみ検証
+                        if !expected.contains('-') && local_etag(&data) != *expected {
+                            return Err(anyhow::anyhow!(
+                                "チェックサムが一致しません: {}（remote {} / local {}）",
+                                key,
+                                expected,
+                                local_etag(&data)
+                            ));
+                        }
+                        let target = dir.join(path);
+                        if let Some(parent) = target.parent() {
+                            tokio::fs::create_dir_all(parent).await?;
+                        }
+                        tokio::fs::write(&target, data).await?;
+                        println!("  {} {}", "↓".green(), path);
+                        Ok::<(), anyhow::Error>(())
+                    }
+                })
+                .buffer_unordered(jobs)
+                .try_collect::<Vec<()>>()
+                .await?;
+
+            for path in &plan.delete {
+                std::fs::remove_file(dir.join(path))?;
+                println!("  {} {}", "✗".red(), path);
+            }
+            print_summary(&plan);
+        }
+        (Location::Local(_), Location::Local(_)) => {
+            return Err(anyhow::anyhow!(
+                "同期元か同期先のどちらかに r2://bucket/prefix を指定してください"
+            ));
+        }
+        (Location::R2 { .. }, Location::R2 { .. }) => {
+            return Err(anyhow::anyhow!(
+                "R2 同士の同期には対応していません（一方をローカルディレクトリにしてください）"
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn print_plan(plan: &SyncPlan, arrow: &str, target: &str) {
+    println!(
+        "{} {} へ転送 {} 件、削除 {} 件、変更なし {} 件",
+        arrow.cyan(),
+        target.cyan(),
+        plan.transfer.len(),
+        plan.delete.len(),
+        plan.unchanged
+    );
+}
+
+fn print_summary(plan: &SyncPlan) {
+    println!(
+        "{} 同期しました（転送 {} / 削除 {} / 変更なし {}）",
+        "✓".green(),
+        plan.transfer.len(),
+        plan.delete.len(),
+        plan.unchanged
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            Location::parse("r2://assets/site/v1/").unwrap(),
+            Location::R2 {
+                bucket: "assets".to_string(),
+                prefix: "site/v1/".to_string()
+            }
+        );
+        assert_eq!(
+            Location::parse("r2://assets").unwrap(),
+            Location::R2 {
+                bucket: "assets".to_string(),
+                prefix: String::new()
+            }
+        );
+        assert_eq!(
+            Location::parse("./dist").unwrap(),
+            Location::Local(PathBuf::from("./dist"))
+        );
+        assert!(Location::parse("r2:///site").is_err());
+    }
+
+    #[test]
+    fn test_plan() {
+        let source = files(&[("index.html", "a"), ("app.js", "b"), ("new.css", "c")]);
+        let destination = files(&[("index.html", "a"), ("app.js", "old"), ("stale.js", "d")]);
+
+        let result = plan(&source, &destination, false);
+        assert_eq!(result.transfer, vec!["app.js", "new.css"]);
+        assert!(result.delete.is_empty());
+        assert_eq!(result.unchanged, 1);
+
+        let result = plan(&source, &destination, true);
+        assert_eq!(result.delete, vec!["stale.js"]);
+    }
+
+    #[test]
+    fn test_local_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("css")).unwrap();
+        std::fs::write(dir.path().join("index.html"), "").unwrap();
+        std::fs::write(dir.path().join("css/site.css"), "").unwrap();
+
+        let files = local_files(dir.path()).unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec!["css/site.css", "index.html"]
+        );
+        assert_eq!(files["index.html"], "d41d8cd98f00b204e9800998ecf8427e");
+    }
+}
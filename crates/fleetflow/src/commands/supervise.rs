@@ -0,0 +1,518 @@
+//! `fleet supervise` — コンテナの自動復旧（auto-heal）
+//!
+//! Docker イベントを購読し、ステージのコンテナが非ゼロで終了した、または unhealthy に
+//! なったときに指数バックオフで再起動する。直前に kill イベントがある終了
+//! （`fleet down` / `fleet kill` / `docker stop`）は意図的な停止として扱い、再起動しない。
+//! 再起動回数が上限に達したサービスは監視対象から外し、`--notify` のコマンドで知らせる。
+
+use crate::docker;
+use crate::utils;
+use bollard::Docker;
+use colored::Colorize;
+use futures_util::stream::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// バックオフの上限
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// 再起動後この時間以上安定して動いていれば、再起動回数をリセットする
+const STABLE_RUN: Duration = Duration::from_secs(600);
+
+/// 再起動のきっかけ
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Failure {
+    /// 非ゼロの終了コードで終了した
+    Exited(i64),
+    /// ヘルスチェックが unhealthy になった
+    Unhealthy,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Exited(code) => write!(f, "exit {}", code),
+            Failure::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
+/// Docker イベントを監視上の意味に分類したもの
+#[derive(Debug, PartialEq, Eq)]
+enum Observed {
+    /// シグナル送信（直後の終了は意図的な停止）
+    Kill,
+    /// コンテナが起動した
+    Start,
+    /// 再起動が必要な異常
+    Failed(Failure),
+}
+
+/// コンテナイベントのアクションと属性を分類する（関係ないイベントは None）
+fn classify(action: &str, attributes: &HashMap<String, String>) -> Option<Observed> {
+    match action {
+        "kill" => Some(Observed::Kill),
+        "start" => Some(Observed::Start),
+        "die" => {
+            let code = attributes
+                .get("exitCode")
+                .and_then(|c| c.parse::<i64>().ok())
+                .unwrap_or(0);
+            (code != 0).then_some(Observed::Failed(Failure::Exited(code)))
+        }
+        "health_status: unhealthy" => Some(Observed::Failed(Failure::Unhealthy)),
+        _ => None,
+    }
+}
+
+/// `initial` から倍々に伸ばした attempt 回目の待ち時間（上限 MAX_BACKOFF）
+fn backoff_delay(initial: Duration, attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    initial.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// 異常を受けてどうするか
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    /// delay 待ってから attempt 回目の再起動を行う
+    Restart { attempt: u32, delay: Duration },
+    /// 上限に達したので監視対象から外す
+    GiveUp,
+    /// 再起動待ち・監視対象外のため何もしない
+    Ignore,
+}
+
+#[derive(Debug, Default)]
+struct ServiceState {
+    restarts: u32,
+    last_restart: Option<Instant>,
+    pending: bool,
+    given_up: bool,
+}
+
+/// サービスごとの再起動回数・バックオフを管理する
+struct Supervisor {
+    max_restarts: u32,
+    initial_backoff: Duration,
+    services: HashMap<String, ServiceState>,
+    /// kill イベントを受けて停止待ちのサービス
+    stopping: HashSet<String>,
+}
+
+impl Supervisor {
+    fn new(max_restarts: u32, initial_backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            initial_backoff,
+            services: HashMap::new(),
+            stopping: HashSet::new(),
+        }
+    }
+
+    fn on_failure(&mut self, service: &str, now: Instant) -> Decision {
+        let state = self.services.entry(service.to_string()).or_default();
+        if state.given_up || state.pending {
+            return Decision::Ignore;
+        }
+        if state
+            .last_restart
+            .is_some_and(|t| now.duration_since(t) >= STABLE_RUN)
+        {
+            state.restarts = 0;
+        }
+        if state.restarts >= self.max_restarts {
+            state.given_up = true;
+            return Decision::GiveUp;
+        }
+        state.restarts += 1;
+        state.pending = true;
+        state.last_restart = Some(now);
+        Decision::Restart {
+            attempt: state.restarts,
+            delay: backoff_delay(self.initial_backoff, state.restarts),
+        }
+    }
+
+    fn on_restart_finished(&mut self, service: &str) {
+        if let Some(state) = self.services.get_mut(service) {
+            state.pending = false;
+        }
+    }
+}
+
+/// 再起動タスクの完了通知
+struct Finished {
+    service: String,
+    reason: String,
+    attempt: u32,
+    outcome: Outcome,
+}
+
+/// 再起動タスクの結果
+enum Outcome {
+    Restarted,
+    /// 待っている間に回復していた（restart ポリシー等）
+    Recovered,
+    Failed(String),
+}
+
+/// `--notify` のコマンドを FLEET_SUPERVISE_* 環境変数付きで実行する（完了は待たない）
+fn notify(
+    command: Option<&str>,
+    project: &str,
+    stage: &str,
+    service: &str,
+    event: &str,
+    reason: &str,
+    attempt: u32,
+) {
+    let Some(command) = command else {
+        return;
+    };
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .env("FLEET_PROJECT", project)
+        .env("FLEET_STAGE", stage)
+        .env("FLEET_SERVICE", service)
+        .env("FLEET_SUPERVISE_EVENT", event)
+        .env("FLEET_SUPERVISE_REASON", reason)
+        .env("FLEET_SUPERVISE_ATTEMPT", attempt.to_string());
+    tokio::spawn(async move {
+        match cmd.status().await {
+            Ok(status) if !status.success() => {
+                eprintln!(
+                    "  {} 通知コマンドが失敗しました（exit {:?}）",
+                    "⚠".yellow(),
+                    status.code()
+                );
+            }
+            Err(e) => eprintln!("  {} 通知コマンドを実行できません: {}", "⚠".yellow(), e),
+            Ok(_) => {}
+        }
+    });
+}
+
+/// delay 待ってから、まだ異常が続いていればコンテナを再起動する
+async fn restart_after(
+    docker_conn: Docker,
+    container_name: String,
+    failure: Failure,
+    delay: Duration,
+) -> Outcome {
+    tokio::time::sleep(delay).await;
+
+    let state = match docker_conn
+        .inspect_container(
+            &container_name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+    {
+        Ok(info) => info.state,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    let running = state.as_ref().and_then(|s| s.running).unwrap_or(false);
+    let unhealthy = state
+        .as_ref()
+        .and_then(|s| s.health.as_ref())
+        .and_then(|h| h.status)
+        == Some(bollard::models::HealthStatusEnum::UNHEALTHY);
+    let recovered = match failure {
+        Failure::Exited(_) => running,
+        Failure::Unhealthy => running && !unhealthy,
+    };
+    if recovered {
+        return Outcome::Recovered;
+    }
+
+    match docker_conn
+        .restart_container(
+            &container_name,
+            None::<bollard::query_parameters::RestartContainerOptions>,
+        )
+        .await
+    {
+        Ok(_) => Outcome::Restarted,
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+pub async fn handle(
+    config: &fleetflow_core::Flow,
+    stage: Option<String>,
+    max_restarts: u32,
+    backoff: &str,
+    notify_command: Option<&str>,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    if !config.stages.contains_key(&stage_name) {
+        return Err(anyhow::anyhow!(
+            "ステージ '{}' が見つかりません",
+            stage_name
+        ));
+    }
+    let initial_backoff = Duration::from_secs(utils::parse_duration(backoff)?.max(1));
+
+    let mut docker_conn = docker::init_docker_with_error_handling().await?;
+
+    println!("ステージ: {}", stage_name.cyan());
+    println!(
+        "{}",
+        format!(
+            "コンテナを監視中（再起動上限 {} 回、初回待機 {} 秒）。Ctrl+C で終了します",
+            max_restarts,
+            initial_backoff.as_secs()
+        )
+        .green()
+    );
+
+    let filters = HashMap::from([
+        ("type".to_string(), vec!["container".to_string()]),
+        (
+            "label".to_string(),
+            vec![
+                format!("fleetflow.project={}", config.name),
+                format!("fleetflow.stage={}", stage_name),
+            ],
+        ),
+    ]);
+    let subscribe = |docker: &Docker, since: Option<i64>| {
+        docker.events(Some(bollard::query_parameters::EventsOptions {
+            since: since.map(|t| t.to_string()),
+            filters: Some(filters.clone()),
+            ..Default::default()
+        }))
+    };
+    let mut events = subscribe(&docker_conn, None);
+    let mut last_event: Option<i64> = None;
+
+    let mut supervisor = Supervisor::new(max_restarts, initial_backoff);
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel::<Finished>();
+
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                // デーモンの再起動などで購読が切れたら再接続し、最後のイベント以降から購読し直す
+                let event = match event {
+                    Some(Ok(event)) => event,
+                    Some(Err(e)) => {
+                        eprintln!("  ⚠ Docker イベントの購読エラー: {}", e);
+                        docker_conn = docker::reconnect_docker().await?;
+                        events = subscribe(&docker_conn, last_event);
+                        continue;
+                    }
+                    None => {
+                        docker_conn = docker::reconnect_docker().await?;
+                        events = subscribe(&docker_conn, last_event);
+                        continue;
+                    }
+                };
+                if let Some(time) = event.time {
+                    last_event = Some(time);
+                }
+                let Some(actor) = event.actor else { continue };
+                let attributes = actor.attributes.unwrap_or_default();
+                let Some(service) = attributes.get("fleetflow.service").cloned() else {
+                    continue;
+                };
+                let Some(observed) = classify(event.action.as_deref().unwrap_or(""), &attributes)
+                else {
+                    continue;
+                };
+
+                let failure = match observed {
+                    Observed::Kill => {
+                        supervisor.stopping.insert(service);
+                        continue;
+                    }
+                    Observed::Start => {
+                        supervisor.stopping.remove(&service);
+                        continue;
+                    }
+                    Observed::Failed(Failure::Exited(_))
+                        if supervisor.stopping.remove(&service) =>
+                    {
+                        continue;
+                    }
+                    Observed::Failed(failure) => failure,
+                };
+
+                match supervisor.on_failure(&service, Instant::now()) {
+                    Decision::Ignore => {}
+                    Decision::GiveUp => {
+                        println!(
+                            "  {} {} が {} 回再起動しても回復しないため、監視対象から外します（{}）",
+                            "✗".red(),
+                            service.cyan(),
+                            max_restarts,
+                            failure
+                        );
+                        notify(
+                            notify_command,
+                            &config.name,
+                            &stage_name,
+                            &service,
+                            "gave_up",
+                            &failure.to_string(),
+                            max_restarts,
+                        );
+                    }
+                    Decision::Restart { attempt, delay } => {
+                        println!(
+                            "  {} {} が異常です（{}）。{} 秒後に再起動します [{}/{}]",
+                            "⚠".yellow(),
+                            service.cyan(),
+                            failure,
+                            delay.as_secs(),
+                            attempt,
+                            max_restarts
+                        );
+                        let container_name =
+                            config.container_name(&stage_name, &service);
+                        let docker_conn = docker_conn.clone();
+                        let done_tx = done_tx.clone();
+                        let reason = failure.to_string();
+                        tokio::spawn(async move {
+                            let outcome =
+                                restart_after(docker_conn, container_name, failure, delay).await;
+                            let _ = done_tx.send(Finished {
+                                service,
+                                reason,
+                                attempt,
+                                outcome,
+                            });
+                        });
+                    }
+                }
+            }
+            Some(finished) = done_rx.recv() => {
+                let Finished {
+                    service,
+                    reason,
+                    attempt,
+                    outcome,
+                } = finished;
+                supervisor.on_restart_finished(&service);
+                match outcome {
+                    Outcome::Restarted => {
+                        println!("  {} {} を再起動しました", "✓".green(), service.cyan());
+                        notify(
+                            notify_command,
+                            &config.name,
+                            &stage_name,
+                            &service,
+                            "restarted",
+                            &reason,
+                            attempt,
+                        );
+                    }
+                    Outcome::Recovered => {
+                        println!("  ℹ {} は既に回復しています", service.cyan());
+                    }
+                    Outcome::Failed(e) => {
+                        println!(
+                            "  {} {} の再起動に失敗しました: {}",
+                            "✗".red(),
+                            service.cyan(),
+                            e
+                        );
+                        notify(
+                            notify_command,
+                            &config.name,
+                            &stage_name,
+                            &service,
+                            "restart_failed",
+                            &e,
+                            attempt,
+                        );
+                    }
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                println!();
+                println!("{}", "監視を終了しました".green());
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let exit = |code: &str| HashMap::from([("exitCode".to_string(), code.to_string())]);
+        assert_eq!(
+            classify("die", &exit("1")),
+            Some(Observed::Failed(Failure::Exited(1)))
+        );
+        assert_eq!(classify("die", &exit("0")), None);
+        assert_eq!(
+            classify("health_status: unhealthy", &HashMap::new()),
+            Some(Observed::Failed(Failure::Unhealthy))
+        );
+        assert_eq!(classify("health_status: healthy", &HashMap::new()), None);
+        assert_eq!(classify("kill", &HashMap::new()), Some(Observed::Kill));
+        assert_eq!(classify("start", &HashMap::new()), Some(Observed::Start));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let initial = Duration::from_secs(2);
+        assert_eq!(backoff_delay(initial, 1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(initial, 2), Duration::from_secs(4));
+        assert_eq!(backoff_delay(initial, 4), Duration::from_secs(16));
+        assert_eq!(backoff_delay(initial, 40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_supervisor_caps_restarts() {
+        let mut supervisor = Supervisor::new(2, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert_eq!(
+            supervisor.on_failure("api", now),
+            Decision::Restart {
+                attempt: 1,
+                delay: Duration::from_secs(1)
+            }
+        );
+        // 再起動待ちの間の異常は無視
+        assert_eq!(supervisor.on_failure("api", now), Decision::Ignore);
+        supervisor.on_restart_finished("api");
+        assert_eq!(
+            supervisor.on_failure("api", now),
+            Decision::Restart {
+                attempt: 2,
+                delay: Duration::from_secs(2)
+            }
+        );
+        supervisor.on_restart_finished("api");
+        assert_eq!(supervisor.on_failure("api", now), Decision::GiveUp);
+        assert_eq!(supervisor.on_failure("api", now), Decision::Ignore);
+        // 他のサービスには影響しない
+        assert!(matches!(
+            supervisor.on_failure("db", now),
+            Decision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_supervisor_resets_after_stable_run() {
+        let mut supervisor = Supervisor::new(1, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert!(matches!(
+            supervisor.on_failure("api", now),
+            Decision::Restart { attempt: 1, .. }
+        ));
+        supervisor.on_restart_finished("api");
+        assert!(matches!(
+            supervisor.on_failure("api", now + STABLE_RUN),
+            Decision::Restart { attempt: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,473 @@
+//! `fleet support-bundle` — バグ報告に添付するための診断情報を tar.gz にまとめる
+//!
+//! 解決済みの設定、コンテナの inspect、サービスごとの直近ログ、docker info、
+//! fleet のバージョンを 1 つのアーカイブに収める。センシティブなキー
+//! （`utils::is_sensitive_key`）と `secrets` の配下の値はすべて `***` に置き換え、
+//! ログ中に現れた同じ値（解決済みのシークレットを含む）も伏せる。
+//! 個々の収集に失敗してもバンドル自体は作成し、失敗内容は `errors.txt` に残す。
+
+use crate::docker;
+use crate::utils::{self, is_sensitive_key};
+use colored::Colorize;
+use fleetflow_container::secret_files::{secrets_root, stage_secrets_dir};
+use fleetflow_core::Flow;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// 伏せ字
+const REDACTED: &str = "***";
+/// 1 ファイルあたりの上限（超えた分は先頭から切り詰める）
+const MAX_FILE_BYTES: usize = 2 * 1024 * 1024;
+/// バンドル全体の上限（超えたら以降のログは含めない）
+const MAX_BUNDLE_BYTES: usize = 20 * 1024 * 1024;
+/// アーカイブ内のルートディレクトリ
+const BUNDLE_DIR: &str = "support-bundle";
+
+/// アーカイブに収めるファイル群
+#[derive(Default)]
+struct Bundle {
+    entries: Vec<(String, Vec<u8>)>,
+    total: usize,
+    errors: Vec<String>,
+}
+
+impl Bundle {
+    /// ファイルを追加する（全体の上限を超える場合は追加せず false）
+    fn add(&mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) -> bool {
+        let name = name.into();
+        let data = cap_tail(data.into(), MAX_FILE_BYTES);
+        if self.total + data.len() > MAX_BUNDLE_BYTES {
+            self.errors.push(format!(
+                "{name}: バンドルの上限（{MAX_BUNDLE_BYTES} bytes）を超えるため省略"
+            ));
+            return false;
+        }
+        self.total += data.len();
+        self.entries.push((name, data));
+        true
+    }
+
+    fn add_json(&mut self, name: &str, value: &Value) {
+        let data = serde_json::to_vec_pretty(value).unwrap_or_default();
+        self.add(name, data);
+    }
+
+    fn error(&mut self, what: &str, e: impl std::fmt::Display) {
+        self.errors.push(format!("{what}: {e}"));
+    }
+
+    fn write_tar_gz(mut self, path: &Path) -> anyhow::Result<()> {
+        if !self.errors.is_empty() {
+            let errors = self.errors.join("\n") + "\n";
+            self.entries
+                .push(("errors.txt".to_string(), errors.into_bytes()));
+        }
+
+        let file = std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("{} の作成に失敗: {}", path.display(), e))?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        let mtime = chrono::Utc::now().timestamp().max(0) as u64;
+        for (name, data) in &self.entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            archive.append_data(&mut header, format!("{BUNDLE_DIR}/{name}"), data.as_slice())?;
+        }
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+}
+
+/// 上限を超える内容は末尾（新しい側）を残して切り詰める
+fn cap_tail(data: Vec<u8>, max: usize) -> Vec<u8> {
+    if data.len() <= max {
+        return data;
+    }
+    let marker = format!("[truncated: first {} bytes omitted]\n", data.len() - max);
+    let mut capped = marker.into_bytes();
+    capped.extend_from_slice(&data[data.len() - max..]);
+    capped
+}
+
+/// JSON 内のセンシティブなキーの値を伏せる
+///
+/// センシティブなキー（`secrets` を含む）の配下は、ネストしたオブジェクト・配列の値もすべて伏せる。
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    redact_all(v);
+                } else {
+                    redact_json(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// キーの構造は残し、値をすべて伏せ字にする
+fn redact_all(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(redact_all),
+        Value::Array(items) => items.iter_mut().for_each(redact_all),
+        Value::Null => {}
+        _ => *value = Value::String(REDACTED.to_string()),
+    }
+}
+
+/// `KEY=VALUE` 形式（docker inspect の Config.Env）の値を伏せる
+fn redact_env_list(value: &mut Value) {
+    if let Some(Value::Array(env)) = value.pointer_mut("/Config/Env") {
+        for entry in env {
+            if let Some(s) = entry.as_str()
+                && let Some((key, _)) = s.split_once('=')
+                && is_sensitive_key(key)
+            {
+                *entry = Value::String(format!("{key}={REDACTED}"));
+            }
+        }
+    }
+}
+
+/// 設定中のセンシティブな値（ログ伏せ字用、短すぎる値は誤検出を避けて除外）
+///
+/// `secrets` はリテラル・環境変数の値に加え、書き出し済みのシークレットファイル
+/// （1Password から解決した値）も含める。
+fn sensitive_values(config: &Flow, stage_name: &str) -> Vec<String> {
+    let mut values: Vec<String> = config
+        .services
+        .values()
+        .flat_map(|s| s.environment.iter())
+        .chain(config.variables.iter())
+        .chain(config.stages.values().flat_map(|s| s.variables.iter()))
+        .filter(|(key, _)| is_sensitive_key(key))
+        .map(|(_, value)| value.clone())
+        .collect();
+    for secret in config.secrets.values() {
+        match (&secret.from, &secret.env) {
+            (Some(from), _) if !fleetflow_core::onepassword::is_op_reference(from) => {
+                values.push(from.clone());
+            }
+            (None, Some(env)) => values.extend(std::env::var(env).ok()),
+            _ => {}
+        }
+    }
+    if let Ok(root) = secrets_root() {
+        values.extend(materialized_secret_values(&stage_secrets_dir(
+            &root,
+            &config.name,
+            stage_name,
+        )));
+    }
+
+    values.retain(|value| value.len() >= 4);
+    values.sort();
+    values.dedup();
+    values
+}
+
+/// `<stage_dir>/<service>/<secret>` に書き出し済みのシークレットの値
+fn materialized_secret_values(stage_dir: &Path) -> Vec<String> {
+    let Ok(services) = std::fs::read_dir(stage_dir) else {
+        return Vec::new();
+    };
+    services
+        .flatten()
+        .filter_map(|service| std::fs::read_dir(service.path()).ok())
+        .flat_map(|files| files.flatten())
+        .filter_map(|file| std::fs::read_to_string(file.path()).ok())
+        .map(|value| value.trim_end().to_string())
+        .collect()
+}
+
+fn redact_text(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |acc, secret| {
+        acc.replace(secret.as_str(), REDACTED)
+    })
+}
+
+fn version_info(config: &Flow, stage_name: &str) -> String {
+    format!(
+        "fleet {}\nos: {} ({})\nproject: {}\nstage: {}\ngenerated_at: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        config.name,
+        stage_name,
+        chrono::Local::now().to_rfc3339()
+    )
+}
+
+/// コンテナの直近ログを取得する
+async fn recent_logs(
+    docker_conn: &bollard::Docker,
+    container_name: &str,
+    lines: u64,
+) -> anyhow::Result<String> {
+    use bollard::container::LogOutput;
+    use futures_util::stream::StreamExt;
+
+    let options = bollard::query_parameters::LogsOptions {
+        stdout: true,
+        stderr: true,
+        tail: lines.to_string(),
+        timestamps: true,
+        ..Default::default()
+    };
+    let mut stream = docker_conn.logs(container_name, Some(options));
+    let mut out = String::new();
+    while let Some(log) = stream.next().await {
+        match log? {
+            LogOutput::StdErr { message } => {
+                for line in String::from_utf8_lossy(&message).lines() {
+                    out.push_str("stderr: ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            LogOutput::StdOut { message } | LogOutput::Console { message } => {
+                out.push_str(&String::from_utf8_lossy(&message));
+            }
+            LogOutput::StdIn { .. } => {}
+        }
+    }
+    Ok(out)
+}
+
+pub async fn handle(
+    config: &Flow,
+    stage: Option<String>,
+    output: Option<PathBuf>,
+    lines: u64,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    println!(
+        "{}",
+        format!("サポートバンドルを作成中（ステージ: {}）...", stage_name)
+            .blue()
+            .bold()
+    );
+
+    let mut bundle = Bundle::default();
+    bundle.add("version.txt", version_info(config, &stage_name));
+
+    let mut resolved = serde_json::to_value(config)?;
+    redact_json(&mut resolved);
+    bundle.add_json("config.json", &resolved);
+
+    let secrets = sensitive_values(config, &stage_name);
+    match docker::init_docker_with_error_handling().await {
+        Err(e) => bundle.error("docker", e),
+        Ok(docker_conn) => {
+            match docker_conn.info().await {
+                Ok(info) => bundle.add_json("docker-info.json", &serde_json::to_value(info)?),
+                Err(e) => bundle.error("docker info", e),
+            }
+
+            for service_name in &stage_config.services {
+                let container_name = config.container_name(&stage_name, service_name);
+                match docker_conn
+                    .inspect_container(
+                        &container_name,
+                        None::<bollard::query_parameters::InspectContainerOptions>,
+                    )
+                    .await
+                {
+                    Ok(info) => {
+                        let mut value = serde_json::to_value(info)?;
+                        redact_env_list(&mut value);
+                        bundle.add_json(&format!("containers/{service_name}.json"), &value);
+                    }
+                    Err(e) => {
+                        bundle.error(&format!("inspect {container_name}"), e);
+                        continue;
+                    }
+                }
+
+                match recent_logs(&docker_conn, &container_name, lines).await {
+                    Ok(logs) => {
+                        bundle.add(
+                            format!("logs/{service_name}.log"),
+                            redact_text(&logs, &secrets),
+                        );
+                    }
+                    Err(e) => bundle.error(&format!("logs {container_name}"), e),
+                }
+            }
+        }
+    }
+
+    let path = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "fleetflow-support-{}-{}-{}.tar.gz",
+            config.name,
+            stage_name,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    let (files, errors) = (bundle.entries.len(), bundle.errors.len());
+    bundle.write_tar_gz(&path)?;
+
+    println!(
+        "{}",
+        format!("✓ {} を作成しました（{} ファイル）", path.display(), files)
+            .green()
+            .bold()
+    );
+    if errors > 0 {
+        println!(
+            "{}",
+            format!("⚠ {} 件の収集に失敗しました（errors.txt を参照）", errors).yellow()
+        );
+    }
+    println!(
+        "{}",
+        "センシティブな値は伏せていますが、添付前に内容を確認してください".dimmed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_json_masks_sensitive_keys() {
+        let mut value = json!({
+            "services": {
+                "api": {
+                    "environment": {
+                        "DATABASE_PASSWORD": "hunter22",
+                        "APP_ENV": "production"
+                    }
+                }
+            },
+            "variables": { "API_TOKEN": "abc" }
+        });
+        redact_json(&mut value);
+        assert_eq!(
+            value["services"]["api"]["environment"]["DATABASE_PASSWORD"],
+            "***"
+        );
+        assert_eq!(
+            value["services"]["api"]["environment"]["APP_ENV"],
+            "production"
+        );
+        assert_eq!(value["variables"]["API_TOKEN"], "***");
+    }
+
+    #[test]
+    fn test_redact_env_list_and_text() {
+        let mut inspect = json!({ "Config": { "Env": ["SECRET_KEY=s3cr3t", "PORT=8080"] } });
+        redact_env_list(&mut inspect);
+        assert_eq!(
+            inspect["Config"]["Env"],
+            json!(["SECRET_KEY=***", "PORT=8080"])
+        );
+
+        let text = redact_text("connecting with s3cr3t\n", &["s3cr3t".to_string()]);
+        assert_eq!(text, "connecting with ***\n");
+    }
+
+    #[test]
+    fn test_cap_tail_keeps_newest_bytes() {
+        let capped = cap_tail(b"0123456789".to_vec(), 4);
+        assert_eq!(
+            String::from_utf8(capped).unwrap(),
+            "[truncated: first 6 bytes omitted]\n6789"
+        );
+        assert_eq!(cap_tail(b"abc".to_vec(), 4), b"abc");
+    }
+
+    #[test]
+    fn test_write_tar_gz_includes_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        let mut bundle = Bundle::default();
+        bundle.add("version.txt", "fleet 0.0.0\n");
+        bundle.error("docker", "connection refused");
+        bundle.write_tar_gz(&path).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["support-bundle/version.txt", "support-bundle/errors.txt"]
+        );
+    }
+
+    #[test]
+    fn test_redact_nested_values_under_sensitive_keys() {
+        let mut value = json!({
+            "secrets": { "db_password": { "from": "hunter22", "env": null } },
+            "api_keys": ["k1", { "value": "k2" }]
+        });
+        redact_json(&mut value);
+        assert_eq!(
+            value["secrets"],
+            json!({ "db_password": { "from": "***", "env": null } })
+        );
+        assert_eq!(value["api_keys"], json!(["***", { "value": "***" }]));
+    }
+
+    #[test]
+    fn test_literal_secret_is_not_in_bundle() {
+        let config = fleetflow_core::parse_kdl_string(
+            r#"
+project "shop"
+secrets {
+    secret "db_password" from="literal-hunter22"
+}
+service "db" {
+    image "postgres:16"
+    secret "db_password"
+}
+stage "local" {
+    service "db"
+}
+"#,
+            "shop".to_string(),
+        )
+        .unwrap();
+
+        let mut resolved = serde_json::to_value(&config).unwrap();
+        redact_json(&mut resolved);
+        assert!(!resolved.to_string().contains("literal-hunter22"));
+
+        let secrets = sensitive_values(&config, "local");
+        assert!(secrets.contains(&"literal-hunter22".to_string()));
+        let logs = redact_text("password=literal-hunter22\n", &secrets);
+        assert!(!logs.contains("literal-hunter22"));
+    }
+
+    #[test]
+    fn test_materialized_secret_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let service_dir = dir.path().join("db");
+        std::fs::create_dir_all(&service_dir).unwrap();
+        std::fs::write(service_dir.join("db_password"), "from-1password\n").unwrap();
+
+        assert_eq!(
+            materialized_secret_values(dir.path()),
+            vec!["from-1password".to_string()]
+        );
+        assert!(materialized_secret_values(&dir.path().join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+//! `fleet test`: サービスの test ブロックを使い捨てコンテナで実行
+//!
+//! ステージのネットワーク上で依存サービス（depends_on）を起動し、テストを順に実行して、
+//! 最後に自分が起動した依存サービスだけを片付ける。既に動いていたコンテナには触れない。
+
+use crate::docker;
+use crate::utils;
+use bollard::models::{ContainerCreateBody, EndpointSettings, HostConfig, NetworkingConfig};
+use bollard::query_parameters::CreateContainerOptions;
+use colored::Colorize;
+use fleetflow_core::{Flow, Service, TestConfig};
+use futures_util::stream::StreamExt;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+/// テスト用コンテナに付けるラベル
+const TEST_LABEL: &str = "fleetflow.test";
+
+/// テスト 1 件の結果
+struct TestResult {
+    service: String,
+    exit_code: i64,
+    duration_secs: f64,
+}
+
+/// 依存サービスをテスト後にどう片付けるか
+enum Started {
+    /// 新規に作成した（削除する）
+    Created(String),
+    /// 停止していたものを起動した（停止に戻す）
+    Resumed(String),
+}
+
+/// 対象サービスが依存するサービスを、起動すべき順（依存先が先）に返す
+///
+/// depends_on を推移的にたどる。ステージに含まれないサービスや循環は無視する。
+fn dependency_order(config: &Flow, stage_services: &[String], targets: &[String]) -> Vec<String> {
+    fn visit(
+        config: &Flow,
+        stage_services: &[String],
+        name: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) {
+        if order.iter().any(|n| n == name) || visiting.iter().any(|n| n == name) {
+            return;
+        }
+        let Some(service) = config.services.get(name) else {
+            return;
+        };
+        visiting.push(name.to_string());
+        for dep in &service.depends_on {
+            if stage_services.contains(dep) {
+                visit(config, stage_services, dep, visiting, order);
+                if !order.contains(dep) {
+                    order.push(dep.clone());
+                }
+            }
+        }
+        visiting.pop();
+    }
+
+    let mut order = Vec::new();
+    for target in targets {
+        visit(config, stage_services, target, &mut Vec::new(), &mut order);
+    }
+    order
+}
+
+/// テスト用コンテナの設定
+///
+/// プロジェクトルートを作業ディレクトリにマウントし、サービスの env に test の env を重ねる。
+fn test_container_config(
+    project_root: &Path,
+    project_name: &str,
+    stage_name: &str,
+    service_name: &str,
+    service: &Service,
+    test: &TestConfig,
+    image: &str,
+) -> (ContainerCreateBody, CreateContainerOptions) {
+    let mut env_map: HashMap<&str, &str> = service
+        .environment
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    env_map.extend(test.env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    let mut env: Vec<String> = env_map.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    env.sort();
+
+    let network_name = fleetflow_container::get_network_name(project_name, stage_name);
+    let labels = HashMap::from([
+        ("fleetflow.project".to_string(), project_name.to_string()),
+        ("fleetflow.stage".to_string(), stage_name.to_string()),
+        ("fleetflow.service".to_string(), service_name.to_string()),
+        (TEST_LABEL.to_string(), "true".to_string()),
+    ]);
+
+    let config = ContainerCreateBody {
+        image: Some(image.to_string()),
+        cmd: Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            test.command.clone(),
+        ]),
+        env: Some(env),
+        working_dir: Some(test.workdir().to_string()),
+        labels: Some(labels),
+        host_config: Some(HostConfig {
+            binds: Some(vec![format!(
+                "{}:{}",
+                project_root.display(),
+                test.workdir()
+            )]),
+            network_mode: Some(network_name.clone()),
+            ..Default::default()
+        }),
+        networking_config: Some(NetworkingConfig {
+            endpoints_config: Some(HashMap::from([(network_name, EndpointSettings::default())])),
+        }),
+        ..Default::default()
+    };
+
+    let options = CreateContainerOptions {
+        name: Some(format!(
+            "{}-{}-{}-test",
+            project_name, stage_name, service_name
+        )),
+        ..Default::default()
+    };
+
+    (config, options)
+}
+
+/// 依存サービスを起動して準備完了を待つ
+async fn start_dependency(
+    docker_conn: &bollard::Docker,
+    config: &Flow,
+    project_root: &Path,
+    stage_name: &str,
+    service_name: &str,
+) -> anyhow::Result<Option<Started>> {
+    let service = config
+        .services
+        .get(service_name)
+        .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
+    let container_name = config.container_name(stage_name, service_name);
+
+    let started = match docker_conn
+        .inspect_container(
+            &container_name,
+            None::<bollard::query_parameters::InspectContainerOptions>,
+        )
+        .await
+    {
+        Ok(existing) if existing.state.and_then(|s| s.running).unwrap_or(false) => {
+            println!("  ✓ {} (起動済み)", service_name.cyan());
+            None
+        }
+        Ok(_) => {
+            docker_conn
+                .start_container(
+                    &container_name,
+                    None::<bollard::query_parameters::StartContainerOptions>,
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("コンテナ起動に失敗: {}", e))?;
+            println!("  ✓ {} を起動", service_name.cyan());
+            Some(Started::Resumed(container_name.clone()))
+        }
+        Err(bollard::errors::Error::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {
+            let (rendered, _) = fleetflow_container::materialize_configs(
+                project_root,
+                config,
+                stage_name,
+                service_name,
+                service,
+            )?;
+            let rendered = fleetflow_container::materialize_secrets(
+                config,
+                stage_name,
+                service_name,
+                &rendered,
+            )?;
+            let (container_config, create_options) =
+                fleetflow_container::service_to_container_config(
+                    service_name,
+                    &rendered,
+                    stage_name,
+                    config,
+                );
+            docker::ensure_container_running(
+                docker_conn,
+                &container_name,
+                container_config,
+                create_options,
+                &docker::registry_auth(config, stage_name)?,
+            )
+            .await?;
+            println!("  ✓ {} を作成・起動", service_name.cyan());
+            Some(Started::Created(container_name.clone()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let wait_config = service.wait_for.clone().unwrap_or_default();
+    fleetflow_container::wait_for_service(docker_conn, &container_name, &wait_config).await?;
+
+    Ok(started)
+}
+
+/// テストコンテナを実行し、出力を流して終了コードを返す
+async fn run_test(
+    docker_conn: &bollard::Docker,
+    container_config: ContainerCreateBody,
+    create_options: CreateContainerOptions,
+    auth: &fleetflow_build::RegistryAuth,
+) -> anyhow::Result<i64> {
+    let container_name = create_options.name.clone().unwrap_or_default();
+    let image = container_config.image.clone().unwrap_or_default();
+
+    // 前回の中断で残ったテストコンテナを掃除
+    let remove_options = Some(bollard::query_parameters::RemoveContainerOptions {
+        force: true,
+        ..Default::default()
+    });
+    let _ = docker_conn
+        .remove_container(&container_name, remove_options.clone())
+        .await;
+
+    if let Err(bollard::errors::Error::DockerResponseServerError {
+        status_code: 404, ..
+    }) = docker_conn.inspect_image(&image).await
+    {
+        docker::pull_image(docker_conn, &image, auth).await?;
+    }
+
+    docker_conn
+        .create_container(Some(create_options), container_config)
+        .await
+        .map_err(|e| anyhow::anyhow!("テストコンテナの作成に失敗: {}", e))?;
+
+    let result = async {
+        docker_conn
+            .start_container(
+                &container_name,
+                None::<bollard::query_parameters::StartContainerOptions>,
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("テストコンテナの起動に失敗: {}", e))?;
+
+        use bollard::container::LogOutput;
+        let mut logs = docker_conn.logs(
+            &container_name,
+            Some(bollard::query_parameters::LogsOptions {
+                follow: true,
+                stdout: true,
+                stderr: true,
+                ..Default::default()
+            }),
+        );
+        while let Some(Ok(output)) = logs.next().await {
+            match output {
+                LogOutput::StdErr { message } => {
+                    eprint!("{}", String::from_utf8_lossy(&message));
+                }
+                other => print!("{}", String::from_utf8_lossy(&other.into_bytes())),
+            }
+        }
+
+        // 非 0 終了は DockerContainerWaitError として返る
+        let mut wait = docker_conn.wait_container(
+            &container_name,
+            None::<bollard::query_parameters::WaitContainerOptions>,
+        );
+        match wait.next().await {
+            Some(Ok(response)) => Ok(response.status_code),
+            Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => Ok(code),
+            Some(Err(e)) => Err(anyhow::anyhow!("テストコンテナの終了待ちに失敗: {}", e)),
+            None => Err(anyhow::anyhow!(
+                "テストコンテナの終了コードを取得できません"
+            )),
+        }
+    }
+    .await;
+
+    let _ = docker_conn
+        .remove_container(&container_name, remove_options)
+        .await;
+    result
+}
+
+/// 起動した依存サービスを元の状態に戻す
+async fn teardown(docker_conn: &bollard::Docker, started: &[Started]) {
+    for entry in started.iter().rev() {
+        let result = match entry {
+            Started::Created(name) => docker_conn
+                .remove_container(
+                    name,
+                    Some(bollard::query_parameters::RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .map(|_| format!("{} を削除", name)),
+            Started::Resumed(name) => docker_conn
+                .stop_container(
+                    name,
+                    None::<bollard::query_parameters::StopContainerOptions>,
+                )
+                .await
+                .map(|_| format!("{} を停止", name)),
+        };
+        match result {
+            Ok(message) => println!("  ✓ {}", message),
+            Err(e) => eprintln!("  {} 片付けに失敗: {}", "⚠".yellow(), e),
+        }
+    }
+}
+
+/// `fleet test` を実行
+///
+/// テストが 1 つでも失敗した場合は、最初に失敗したテストの終了コードでプロセスを終了する。
+pub async fn handle(
+    config: &Flow,
+    project_root: &Path,
+    stage: Option<String>,
+    service_filters: &[String],
+    keep_deps: bool,
+) -> anyhow::Result<()> {
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    let stage_config = config
+        .stages
+        .get(&stage_name)
+        .ok_or_else(|| anyhow::anyhow!("ステージ '{}' が見つかりません", stage_name))?;
+
+    let candidates = utils::filter_services(&stage_config.services, service_filters, &stage_name)?;
+    if let Some(missing) = service_filters.iter().find(|name| {
+        config
+            .services
+            .get(name.as_str())
+            .is_none_or(|s| s.test.is_none())
+    }) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' に test ブロックがありません",
+            missing
+        ));
+    }
+    let targets: Vec<String> = candidates
+        .into_iter()
+        .filter(|name| config.services.get(name).is_some_and(|s| s.test.is_some()))
+        .collect();
+
+    if targets.is_empty() {
+        println!(
+            "{}",
+            "テスト対象のサービスがありません（test ブロックが必要です）".yellow()
+        );
+        return Ok(());
+    }
+
+    println!("ステージ: {}", stage_name.cyan());
+    println!(
+        "{}",
+        format!("テスト対象サービス ({} 個):", targets.len()).bold()
+    );
+    for name in &targets {
+        println!("  • {}", name.cyan());
+    }
+
+    println!();
+    println!("{}", "Dockerに接続中...".blue());
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let registry_auth = docker::registry_auth(config, &stage_name)?;
+
+    let network_name = fleetflow_container::get_network_name(&config.name, &stage_name);
+    docker::ensure_network(&docker_conn, &network_name).await?;
+
+    let dependencies: Vec<String> = dependency_order(config, &stage_config.services, &targets)
+        .into_iter()
+        .filter(|name| config.services.get(name).is_some_and(|s| !s.is_static()))
+        .collect();
+
+    let mut started = Vec::new();
+    let outcome = async {
+        if !dependencies.is_empty() {
+            println!();
+            println!("{}", "依存サービスを起動中...".blue());
+            for dep in &dependencies {
+                if let Some(entry) =
+                    start_dependency(&docker_conn, config, project_root, &stage_name, dep).await?
+                {
+                    started.push(entry);
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        for service_name in &targets {
+            let service = &config.services[service_name];
+            let Some(test) = &service.test else {
+                continue;
+            };
+            let image = match &test.image {
+                Some(image) => image.clone(),
+                None => fleetflow_container::service_to_container_config(
+                    service_name,
+                    service,
+                    &stage_name,
+                    config,
+                )
+                .0
+                .image
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "サービス '{}' のテストに使うイメージがありません（test に image を指定してください）",
+                        service_name
+                    )
+                })?,
+            };
+
+            println!();
+            println!(
+                "{}",
+                format!("▶ {} のテストを実行中...", service_name).green().bold()
+            );
+            println!("  イメージ: {}", image.cyan());
+            println!("  コマンド: {}", test.command.cyan());
+
+            let (container_config, create_options) = test_container_config(
+                project_root,
+                &config.name,
+                &stage_name,
+                service_name,
+                service,
+                test,
+                &image,
+            );
+            let started_at = Instant::now();
+            let exit_code = run_test(
+                &docker_conn,
+                container_config,
+                create_options,
+                &registry_auth,
+            )
+            .await?;
+            results.push(TestResult {
+                service: service_name.clone(),
+                exit_code,
+                duration_secs: started_at.elapsed().as_secs_f64(),
+            });
+        }
+        anyhow::Ok(results)
+    }
+    .await;
+
+    if !started.is_empty() {
+        println!();
+        if keep_deps {
+            println!(
+                "{}",
+                "依存サービスは起動したままにします（--keep-deps）".dimmed()
+            );
+        } else {
+            println!("{}", "依存サービスを片付け中...".blue());
+            teardown(&docker_conn, &started).await;
+        }
+    }
+
+    let results = outcome?;
+    println!();
+    println!("{}", "テスト結果:".bold());
+    for result in &results {
+        if result.exit_code == 0 {
+            println!(
+                "  {} {:<20} {:.1}s",
+                "✓".green(),
+                result.service,
+                result.duration_secs
+            );
+        } else {
+            println!(
+                "  {} {:<20} {:.1}s  (exit {})",
+                "✗".red(),
+                result.service,
+                result.duration_secs,
+                result.exit_code
+            );
+        }
+    }
+
+    let failed: Vec<&TestResult> = results.iter().filter(|r| r.exit_code != 0).collect();
+    if let Some(first) = failed.first() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "✗ {}/{} 件のテストが失敗しました",
+                failed.len(),
+                results.len()
+            )
+            .red()
+            .bold()
+        );
+        std::process::exit(i32::try_from(first.exit_code).unwrap_or(1));
+    }
+
+    println!();
+    println!("{}", "✓ すべてのテストが成功しました".green().bold());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(services: &[(&str, &[&str])]) -> Flow {
+        let mut flow = Flow {
+            name: "myapp".to_string(),
+            ..Default::default()
+        };
+        for (name, deps) in services {
+            flow.services.insert(
+                name.to_string(),
+                Service {
+                    depends_on: deps.iter().map(|d| d.to_string()).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        flow
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let config = flow(&[
+            ("api", &["db", "cache"]),
+            ("worker", &["db", "queue"]),
+            ("db", &[]),
+            ("cache", &[]),
+            ("queue", &["db"]),
+        ]);
+        let stage: Vec<String> = ["api", "worker", "db", "cache", "queue"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(
+            dependency_order(&config, &stage, &["api".to_string(), "worker".to_string()]),
+            vec!["db", "cache", "queue"]
+        );
+        // ステージに含まれない依存先は起動しない
+        assert_eq!(
+            dependency_order(&config, &stage[..3], &["worker".to_string()]),
+            vec!["db"]
+        );
+    }
+
+    #[test]
+    fn test_test_container_config() {
+        let service = Service {
+            environment: HashMap::from([
+                ("DATABASE_URL".to_string(), "postgres://db/dev".to_string()),
+                ("RUST_LOG".to_string(), "info".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let test = TestConfig {
+            command: "cargo test".to_string(),
+            env: HashMap::from([("DATABASE_URL".to_string(), "postgres://db/test".to_string())]),
+            ..Default::default()
+        };
+
+        let (config, options) = test_container_config(
+            Path::new("/src/myapp"),
+            "myapp",
+            "local",
+            "api",
+            &service,
+            &test,
+            "rust:1.79",
+        );
+
+        assert_eq!(options.name.as_deref(), Some("myapp-local-api-test"));
+        assert_eq!(config.image.as_deref(), Some("rust:1.79"));
+        assert_eq!(
+            config.cmd,
+            Some(vec!["sh".into(), "-c".into(), "cargo test".into()])
+        );
+        assert_eq!(
+            config.env,
+            Some(vec![
+                "DATABASE_URL=postgres://db/test".to_string(),
+                "RUST_LOG=info".to_string()
+            ])
+        );
+        assert_eq!(config.working_dir.as_deref(), Some("/workspace"));
+        let host_config = config.host_config.unwrap();
+        assert_eq!(
+            host_config.binds,
+            Some(vec!["/src/myapp:/workspace".to_string()])
+        );
+        assert_eq!(host_config.network_mode.as_deref(), Some("myapp-local"));
+    }
+}
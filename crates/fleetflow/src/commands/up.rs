@@ -1,5 +1,6 @@
 use crate::docker;
 use colored::Colorize;
+use fleetflow_build::{ProgressEvent, ProgressStatus};
 use std::collections::HashMap;
 
 /// サービスのローカルビルドを実行する共通関数
@@ -50,6 +51,92 @@ async fn build_service_image(
     Ok(())
 }
 
+/// 起動したコンテナが直後に終了していないか確認する猶予
+const STARTUP_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+/// 直後に終了したコンテナについて表示するログの行数
+const EXIT_LOG_LINES: usize = 50;
+
+/// 起動したコンテナが猶予時間のあとも動いているか確認する
+///
+/// 終了（または再起動を繰り返している）していれば、終了コードと末尾のログを表示して失敗にする。
+/// 終了コード 0 で終わったものはマイグレーションなどの一度きりのジョブとみなす。
+/// 猶予は最後に起動したコンテナから数えるため、待ち時間はサービス数によらない。
+async fn verify_started(
+    docker_conn: &bollard::Docker,
+    launched: &[(&str, String, tokio::time::Instant)],
+) -> anyhow::Result<()> {
+    let Some(last) = launched.iter().map(|(_, _, at)| *at).max() else {
+        return Ok(());
+    };
+    tokio::time::sleep_until(last + STARTUP_GRACE).await;
+
+    let mut exited = Vec::new();
+    for (service_name, container_name, _) in launched {
+        let state = docker_conn
+            .inspect_container(
+                container_name,
+                None::<bollard::query_parameters::InspectContainerOptions>,
+            )
+            .await?
+            .state
+            .unwrap_or_default();
+        let restarting = state.restarting == Some(true);
+        if state.running == Some(true) && !restarting {
+            continue;
+        }
+        let exit_code = state.exit_code.unwrap_or_default();
+        if exit_code == 0 && !restarting {
+            println!(
+                "  {} {} は終了コード 0 で終了しました（一度きりのジョブとして扱います）",
+                "ℹ".blue(),
+                service_name.cyan()
+            );
+            continue;
+        }
+
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!(
+                "✗ {} が起動直後に終了しました（終了コード {}）",
+                service_name, exit_code
+            )
+            .red()
+            .bold()
+        );
+        if restarting {
+            eprintln!("  再起動を繰り返しています");
+        }
+        if state.oom_killed == Some(true) {
+            eprintln!("  メモリ不足で強制終了されました（OOMKilled）");
+        }
+        if let Some(error) = state.error.filter(|e| !e.is_empty()) {
+            eprintln!("  {}", error);
+        }
+        match crate::commands::logs::tail_logs(docker_conn, container_name, EXIT_LOG_LINES).await {
+            Ok(Some(log)) if !log.is_empty() => {
+                eprintln!(
+                    "{}",
+                    format!("  --- 直近 {} 行のログ ---", EXIT_LOG_LINES).dimmed()
+                );
+                for line in log.lines() {
+                    eprintln!("  {}", line);
+                }
+            }
+            _ => eprintln!("  {}", "(ログはありません)".dimmed()),
+        }
+        exited.push(*service_name);
+    }
+
+    if exited.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "起動直後に終了したサービスがあります: {}",
+        exited.join(", ")
+    ))
+}
+
 /// 環境変数のキーがセンシティブかどうか判定する
 use crate::utils::is_sensitive_key;
 
@@ -76,7 +163,7 @@ fn print_dry_run_plan(
             .get(service_name)
             .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
 
-        let container_name = format!("{}-{}-{}", config.name, stage_name, service_name);
+        let container_name = config.container_name(stage_name, service_name);
         let image = service.image.as_deref().unwrap_or("(未設定)");
 
         println!();
@@ -216,11 +303,31 @@ pub async fn handle(
         )
     })?;
 
-    // WS2: backend が Quadlet/Compose なら専用経路へ分岐
+    // ログ集約エージェントの設定を生成（log-shipper サービスが bind mount する）
+    if let Some(log_shipping) = &stage_config.log_shipping
+        && !dry_run
+    {
+        let path = fleetflow_container::write_agent_config(
+            project_root,
+            config,
+            &stage_name,
+            log_shipping,
+        )?;
+        println!("ログ集約設定: {}", path.display().to_string().cyan());
+    }
+
+    // WS2: backend が Quadlet/Compose/Kubernetes/Nomad なら専用経路へ分岐
     match stage_config.backend {
         fleetflow_core::Backend::Docker => {}
         fleetflow_core::Backend::Quadlet => {
-            return crate::commands::quadlet::up(config, &stage_name, stage_config, dry_run).await;
+            return crate::commands::quadlet::up(
+                config,
+                project_root,
+                &stage_name,
+                stage_config,
+                dry_run,
+            )
+            .await;
         }
         fleetflow_core::Backend::Compose => {
             return crate::commands::compose::up(
@@ -232,6 +339,28 @@ pub async fn handle(
             )
             .await;
         }
+        fleetflow_core::Backend::Kubernetes => {
+            return crate::commands::cluster::up(
+                &fleetflow_container::KubernetesTarget,
+                config,
+                project_root,
+                &stage_name,
+                stage_config,
+                dry_run,
+            )
+            .await;
+        }
+        fleetflow_core::Backend::Nomad => {
+            return crate::commands::cluster::up(
+                &fleetflow_container::NomadTarget,
+                config,
+                project_root,
+                &stage_name,
+                stage_config,
+                dry_run,
+            )
+            .await;
+        }
     }
 
     // dry-run モードの場合は実行計画を表示して終了
@@ -272,6 +401,16 @@ pub async fn handle(
     println!();
     println!("{}", "Dockerに接続中...".blue());
     let docker_conn = docker::init_docker_with_error_handling().await?;
+    let registry_auth = docker::registry_auth(config, &stage_name)?;
+    // registry が localhost のレジストリなら、pull の前に起動しておく
+    crate::commands::local_registry::ensure_for_registry(
+        &docker_conn,
+        stage_config
+            .registry
+            .as_deref()
+            .or(config.registry.as_deref()),
+    )
+    .await?;
 
     let network_name = fleetflow_container::get_network_name(&config.name, &stage_name);
     println!();
@@ -279,166 +418,294 @@ pub async fn handle(
     docker::ensure_network(&docker_conn, &network_name).await?;
 
     // 各コンテナサービスを起動
-    for service_name in &container_services {
-        let service = config
-            .services
-            .get(service_name.as_str())
-            .ok_or_else(|| anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name))?;
-
-        if service.image.is_none() {
-            return Err(anyhow::anyhow!(
-                "サービス '{}' に image が指定されていません",
-                service_name
-            ));
-        }
-
-        println!();
-        println!(
-            "{}",
-            format!("▶ {} を起動中...", service_name).green().bold()
-        );
-
-        // サービスをコンテナ設定に変換
-        let (container_config, create_options) = fleetflow_container::service_to_container_config(
-            service_name,
-            service,
-            &stage_name,
-            &config.name,
-        );
+    let progress = fleetflow_build::reporter();
+    let percent = |done: usize| (done * 100 / container_services.len()) as f64;
+    // 起動に失敗した場合も、コンテナが消える前にログを残す
+    let started = async {
+        let mut launched = Vec::with_capacity(container_services.len());
+        for (i, service_name) in container_services.iter().enumerate() {
+            let service = config.services.get(service_name.as_str()).ok_or_else(|| {
+                anyhow::anyhow!("サービス '{}' の定義が見つかりません", service_name)
+            })?;
+
+            if service.image.is_none() {
+                return Err(anyhow::anyhow!(
+                    "サービス '{}' に image が指定されていません",
+                    service_name
+                ));
+            }
 
-        // build設定がある場合は先にビルドを実行（ローカルビルド優先）
-        if service.build.is_some() {
-            let image = container_config
-                .image
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
+            println!();
+            println!(
+                "{}",
+                format!("▶ {} を起動中...", service_name).green().bold()
+            );
+            progress.event(
+                &ProgressEvent::new("up", ProgressStatus::Started, "起動中")
+                    .service(service_name)
+                    .percent(percent(i)),
+            );
 
-            build_service_image(&docker_conn, project_root, service_name, service, image).await?;
-        }
+            // 設定ファイル（configs）をレンダリングして bind mount を追加
+            let (rendered_service, config_hash) = fleetflow_container::materialize_configs(
+                project_root,
+                config,
+                &stage_name,
+                service_name,
+                service,
+            )?;
+            // シークレットを tmpfs 上のファイルとして書き出して bind mount を追加
+            let rendered_service = fleetflow_container::materialize_secrets(
+                config,
+                &stage_name,
+                service_name,
+                &rendered_service,
+            )?;
+            let service = &rendered_service;
+
+            // サービスをコンテナ設定に変換
+            let (mut container_config, create_options) =
+                fleetflow_container::service_to_container_config(
+                    service_name,
+                    service,
+                    &stage_name,
+                    config,
+                );
 
-        // --pull フラグが指定されていて、build設定がない場合は最新イメージをpull
-        if pull && service.build.is_none() {
-            let image = container_config
-                .image
-                .as_ref()
-                .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
-            docker::pull_image_always(&docker_conn, image).await?;
-        }
+            if let Some(hash) = &config_hash {
+                container_config
+                    .labels
+                    .get_or_insert_with(Default::default)
+                    .insert(
+                        fleetflow_container::CONFIG_HASH_LABEL.to_string(),
+                        hash.clone(),
+                    );
 
-        // コンテナ作成
-        match docker_conn
-            .create_container(Some(create_options.clone()), container_config.clone())
-            .await
-        {
-            Ok(response) => {
-                println!("  ✓ コンテナ作成: {}", response.id);
-
-                // コンテナ起動
-                docker_conn
-                    .start_container(
-                        &response.id,
-                        None::<bollard::query_parameters::StartContainerOptions>,
-                    )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("コンテナ起動に失敗: {}", e))?;
-                println!("  ✓ 起動完了");
-            }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 409, ..
-            }) => {
-                // コンテナが既に存在する場合
-                println!("  ℹ コンテナは既に存在します");
+                // 設定ファイルの内容が変わっていれば既存コンテナを作り直す
                 let container_name = create_options.name.as_deref().unwrap_or_default();
-
-                // 既存コンテナを起動
-                match docker_conn
-                    .start_container(
+                if let Ok(existing) = docker_conn
+                    .inspect_container(
                         container_name,
-                        None::<bollard::query_parameters::StartContainerOptions>,
+                        None::<bollard::query_parameters::InspectContainerOptions>,
                     )
                     .await
                 {
-                    Ok(_) => println!("  ✓ 既存コンテナを起動"),
-                    Err(bollard::errors::Error::DockerResponseServerError {
-                        status_code: 304,
-                        ..
-                    }) => {
-                        // 既に起動中のコンテナは再起動
-                        println!("  ℹ コンテナは既に起動中、再起動します...");
+                    let current = existing.config.and_then(|c| c.labels).and_then(|labels| {
+                        labels.get(fleetflow_container::CONFIG_HASH_LABEL).cloned()
+                    });
+                    if current.as_deref() != Some(hash.as_str()) {
+                        println!("  ℹ 設定ファイルが変更されたためコンテナを再作成します");
                         docker_conn
-                            .restart_container(
+                            .remove_container(
                                 container_name,
-                                None::<bollard::query_parameters::RestartContainerOptions>,
+                                Some(bollard::query_parameters::RemoveContainerOptions {
+                                    force: true,
+                                    ..Default::default()
+                                }),
                             )
                             .await
-                            .map_err(|e| anyhow::anyhow!("コンテナ再起動に失敗: {}", e))?;
-                        println!("  ✓ 再起動完了");
-                    }
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("コンテナ起動に失敗: {}", e));
+                            .map_err(|e| anyhow::anyhow!("コンテナ削除に失敗: {}", e))?;
                     }
                 }
             }
-            Err(bollard::errors::Error::DockerResponseServerError {
-                status_code: 404, ..
-            }) => {
-                // イメージが見つからない場合
+
+            // build設定がある場合は先にビルドを実行（ローカルビルド優先）
+            if service.build.is_some() {
                 let image = container_config
                     .image
                     .as_ref()
                     .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
 
-                if service.build.is_some() {
-                    println!("  ℹ イメージが見つかりません: {}", image.cyan());
-                    build_service_image(&docker_conn, project_root, service_name, service, image)
+                build_service_image(&docker_conn, project_root, service_name, service, image)
+                    .await?;
+            }
+
+            // --pull フラグが指定されていて、build設定がない場合は最新イメージをpull
+            if pull && service.build.is_none() {
+                let image = container_config
+                    .image
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
+                docker::pull_image_always(&docker_conn, image, &registry_auth).await?;
+            }
+
+            // inject_wait: 元のエントリーポイントを得るためイメージを先に用意してからラップする
+            if service.inject_wait == Some(true) {
+                let image = container_config
+                    .image
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
+                if let Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404,
+                    ..
+                }) = docker_conn.inspect_image(image).await
+                {
+                    if service.build.is_some() {
+                        build_service_image(
+                            &docker_conn,
+                            project_root,
+                            service_name,
+                            service,
+                            image,
+                        )
                         .await?;
-                } else {
-                    docker::pull_image(&docker_conn, image).await?;
+                    } else {
+                        docker::pull_image(&docker_conn, image, &registry_auth).await?;
+                    }
                 }
+                fleetflow_container::inject_wait(
+                    &docker_conn,
+                    service,
+                    config,
+                    &mut container_config,
+                )
+                .await?;
+            }
 
-                // pull/build成功後、再度コンテナ作成を試行
-                let response = docker_conn
-                    .create_container(Some(create_options.clone()), container_config.clone())
-                    .await
-                    .map_err(|e| anyhow::anyhow!("コンテナ作成に失敗: {}", e))?;
-
-                println!("  ✓ コンテナ作成: {}", response.id);
+            // コンテナ作成
+            match docker_conn
+                .create_container(Some(create_options.clone()), container_config.clone())
+                .await
+            {
+                Ok(response) => {
+                    println!("  ✓ コンテナ作成: {}", response.id);
+
+                    // コンテナ起動
+                    docker_conn
+                        .start_container(
+                            &response.id,
+                            None::<bollard::query_parameters::StartContainerOptions>,
+                        )
+                        .await
+                        .map_err(|e| anyhow::anyhow!("コンテナ起動に失敗: {}", e))?;
+                    println!("  ✓ 起動完了");
+                }
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 409, ..
+                }) => {
+                    // コンテナが既に存在する場合
+                    println!("  ℹ コンテナは既に存在します");
+                    let container_name = create_options.name.as_deref().unwrap_or_default();
+
+                    // 既存コンテナを起動
+                    match docker_conn
+                        .start_container(
+                            container_name,
+                            None::<bollard::query_parameters::StartContainerOptions>,
+                        )
+                        .await
+                    {
+                        Ok(_) => println!("  ✓ 既存コンテナを起動"),
+                        Err(bollard::errors::Error::DockerResponseServerError {
+                            status_code: 304,
+                            ..
+                        }) => {
+                            // 既に起動中のコンテナは再起動
+                            println!("  ℹ コンテナは既に起動中、再起動します...");
+                            docker_conn
+                                .restart_container(
+                                    container_name,
+                                    None::<bollard::query_parameters::RestartContainerOptions>,
+                                )
+                                .await
+                                .map_err(|e| anyhow::anyhow!("コンテナ再起動に失敗: {}", e))?;
+                            println!("  ✓ 再起動完了");
+                        }
+                        Err(e) => {
+                            return Err(anyhow::anyhow!("コンテナ起動に失敗: {}", e));
+                        }
+                    }
+                }
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {
+                    // イメージが見つからない場合
+                    let image = container_config
+                        .image
+                        .as_ref()
+                        .ok_or_else(|| anyhow::anyhow!("イメージ名が指定されていません"))?;
+
+                    if service.build.is_some() {
+                        println!("  ℹ イメージが見つかりません: {}", image.cyan());
+                        build_service_image(
+                            &docker_conn,
+                            project_root,
+                            service_name,
+                            service,
+                            image,
+                        )
+                        .await?;
+                    } else {
+                        docker::pull_image(&docker_conn, image, &registry_auth).await?;
+                    }
 
-                docker_conn
-                    .start_container(
-                        &response.id,
-                        None::<bollard::query_parameters::StartContainerOptions>,
-                    )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("コンテナ起動に失敗: {}", e))?;
-                println!("  ✓ 起動完了");
-            }
-            Err(e) => {
-                let err_str = e.to_string();
-                if err_str.contains("port is already allocated") {
-                    eprintln!();
-                    eprintln!("{}", "✗ ポートが既に使用されています".red().bold());
-                    eprintln!();
-                    eprintln!("{}", "原因:".yellow());
-                    eprintln!("  {}", err_str);
-                    eprintln!();
-                    eprintln!("{}", "解決方法:".yellow());
-                    eprintln!(
-                        "  • 既存のコンテナを停止: fleet down --stage={}",
-                        stage_name
-                    );
-                    eprintln!("  • 別のポート番号を使用してください");
-                    eprintln!("  • docker ps でポートを使用しているコンテナを確認してください");
-                } else {
-                    eprintln!();
-                    eprintln!("{}", "✗ コンテナ作成エラー".red().bold());
-                    eprintln!();
-                    eprintln!("{}", "原因:".yellow());
-                    eprintln!("  {}", err_str);
+                    // pull/build成功後、再度コンテナ作成を試行
+                    let response = docker_conn
+                        .create_container(Some(create_options.clone()), container_config.clone())
+                        .await
+                        .map_err(|e| anyhow::anyhow!("コンテナ作成に失敗: {}", e))?;
+
+                    println!("  ✓ コンテナ作成: {}", response.id);
+
+                    docker_conn
+                        .start_container(
+                            &response.id,
+                            None::<bollard::query_parameters::StartContainerOptions>,
+                        )
+                        .await
+                        .map_err(|e| anyhow::anyhow!("コンテナ起動に失敗: {}", e))?;
+                    println!("  ✓ 起動完了");
+                }
+                Err(e) => {
+                    let err_str = e.to_string();
+                    if err_str.contains("port is already allocated") {
+                        eprintln!();
+                        eprintln!("{}", "✗ ポートが既に使用されています".red().bold());
+                        eprintln!();
+                        eprintln!("{}", "原因:".yellow());
+                        eprintln!("  {}", err_str);
+                        eprintln!();
+                        eprintln!("{}", "解決方法:".yellow());
+                        eprintln!(
+                            "  • 既存のコンテナを停止: fleet down --stage={}",
+                            stage_name
+                        );
+                        eprintln!("  • 別のポート番号を使用してください");
+                        eprintln!("  • docker ps でポートを使用しているコンテナを確認してください");
+                    } else {
+                        eprintln!();
+                        eprintln!("{}", "✗ コンテナ作成エラー".red().bold());
+                        eprintln!();
+                        eprintln!("{}", "原因:".yellow());
+                        eprintln!("  {}", err_str);
+                    }
+                    return Err(anyhow::anyhow!("コンテナ作成に失敗しました"));
                 }
-                return Err(anyhow::anyhow!("コンテナ作成に失敗しました"));
             }
+            launched.push((
+                service_name.as_str(),
+                create_options.name.clone().unwrap_or_default(),
+                tokio::time::Instant::now(),
+            ));
+            progress.event(
+                &ProgressEvent::new("up", ProgressStatus::Done, "起動完了")
+                    .service(service_name)
+                    .percent(percent(i + 1)),
+            );
         }
+        verify_started(&docker_conn, &launched).await
+    }
+    .await;
+    if let Err(e) = started {
+        crate::commands::logs::capture_startup_logs(
+            &docker_conn,
+            config,
+            project_root,
+            &stage_name,
+            &container_services,
+        )
+        .await;
+        return Err(e);
     }
 
     // Readinessチェック: readiness設定があるサービスを確認
@@ -505,6 +772,14 @@ pub async fn handle(
     }
 
     println!();
+    crate::commands::logs::capture_startup_logs(
+        &docker_conn,
+        config,
+        project_root,
+        &stage_name,
+        &container_services,
+    )
+    .await;
     println!("{}", "✓ すべてのサービスが起動しました！".green().bold());
 
     Ok(())
@@ -0,0 +1,212 @@
+//! `fleet upgrade-config` — 旧スキーマの設定ファイルを現行スキーマへ移行
+//!
+//! スキーマバージョンの判定と書き換えは `fleetflow_core::upgrade` に集約されており、
+//! 本モジュールは対象ファイルの収集、バックアップ（`*.bak`）、書き込みを担う。
+//! `--check` では何も書き換えず、移行が必要なら非ゼロ終了する（CI 向け）。
+
+use colored::Colorize;
+use fleetflow_core::{
+    CURRENT_SCHEMA_VERSION, ROOT_FILE, SchemaRewrite, detect_schema_version, find_legacy_root,
+    upgrade_kdl_string,
+};
+use std::path::{Path, PathBuf};
+
+/// 1 ファイル分の移行計画
+struct FilePlan {
+    /// 読み込み元
+    source: PathBuf,
+    /// 書き込み先（レイアウト移行時のみ source と異なる）
+    target: PathBuf,
+    content: String,
+    rewrites: Vec<SchemaRewrite>,
+}
+
+impl FilePlan {
+    fn changed(&self) -> bool {
+        self.source != self.target || !self.rewrites.is_empty()
+    }
+}
+
+/// ステージ別オーバーライド（`flow.{name}.kdl`）をルートと `.fleetflow/` から集める
+fn override_files(project_root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in [project_root.to_path_buf(), project_root.join(".fleetflow")] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("flow.") && name.ends_with(".kdl") && name != "flow.kdl" {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 移行対象の KDL ファイル（メインファイルが先頭）
+fn config_files(project_root: &Path, legacy_root: Option<&Path>) -> anyhow::Result<Vec<PathBuf>> {
+    let discovered = fleetflow_core::discover_files(project_root)?;
+    let mut files: Vec<PathBuf> = Vec::new();
+    files.extend(legacy_root.map(Path::to_path_buf).or(discovered.root));
+    files.extend(discovered.cloud);
+    files.extend(discovered.services);
+    files.extend(discovered.stages);
+    files.extend(discovered.variables);
+    files.extend(override_files(project_root));
+    files.dedup();
+    Ok(files)
+}
+
+fn plan(project_root: &Path) -> anyhow::Result<Vec<FilePlan>> {
+    let legacy_root = if detect_schema_version(project_root) < CURRENT_SCHEMA_VERSION {
+        find_legacy_root(project_root)
+    } else {
+        None
+    };
+
+    let mut plans = Vec::new();
+    for source in config_files(project_root, legacy_root.as_deref())? {
+        let original = std::fs::read_to_string(&source)
+            .map_err(|e| anyhow::anyhow!("{} の読み込みに失敗: {}", source.display(), e))?;
+        let (content, rewrites) = upgrade_kdl_string(&original)
+            .map_err(|e| anyhow::anyhow!("{}: {}", source.display(), e))?;
+        let target = if legacy_root.as_deref() == Some(source.as_path()) {
+            project_root.join(ROOT_FILE)
+        } else {
+            source.clone()
+        };
+        plans.push(FilePlan {
+            source,
+            target,
+            content,
+            rewrites,
+        });
+    }
+    Ok(plans)
+}
+
+fn relative<'a>(project_root: &Path, path: &'a Path) -> std::borrow::Cow<'a, str> {
+    path.strip_prefix(project_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+}
+
+/// `{path}.bak` にバックアップする
+fn backup(path: &Path) -> anyhow::Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)
+        .map_err(|e| anyhow::anyhow!("{} のバックアップに失敗: {}", path.display(), e))?;
+    Ok(backup)
+}
+
+pub fn handle(check: bool) -> anyhow::Result<()> {
+    let project_root = fleetflow_core::find_upgradable_project_root()?;
+    let version = detect_schema_version(&project_root);
+    let plans = plan(&project_root)?;
+    let changes: usize = plans
+        .iter()
+        .map(|p| p.rewrites.len() + usize::from(p.source != p.target))
+        .sum();
+
+    println!("スキーマ: v{} (現行 v{})", version, CURRENT_SCHEMA_VERSION);
+
+    if changes == 0 {
+        println!("{}", "✓ 設定ファイルは現行スキーマです".green().bold());
+        return Ok(());
+    }
+
+    for plan in plans.iter().filter(|p| p.changed()) {
+        let source = relative(&project_root, &plan.source);
+        if plan.source != plan.target {
+            println!(
+                "  {} {} → {}",
+                "~".yellow(),
+                source,
+                relative(&project_root, &plan.target).cyan()
+            );
+        } else {
+            println!("  {} {}", "~".yellow(), source);
+        }
+        for rewrite in &plan.rewrites {
+            println!(
+                "      {}: {} → {}",
+                rewrite.location,
+                rewrite.from.red(),
+                rewrite.to.green()
+            );
+        }
+    }
+
+    if check {
+        return Err(anyhow::anyhow!(
+            "{} 件の移行が必要です（fleet upgrade-config で更新できます）",
+            changes
+        ));
+    }
+
+    println!();
+    for plan in plans.iter().filter(|p| p.changed()) {
+        let backup_path = backup(&plan.source)?;
+        if let Some(parent) = plan.target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&plan.target, &plan.content)
+            .map_err(|e| anyhow::anyhow!("{} への書き込みに失敗: {}", plan.target.display(), e))?;
+        if plan.source != plan.target {
+            std::fs::remove_file(&plan.source)?;
+        }
+        println!(
+            "  {} {}（バックアップ: {}）",
+            "✓".green(),
+            relative(&project_root, &plan.target),
+            relative(&project_root, &backup_path)
+        );
+    }
+
+    // 移行結果が読み込めるか確認（失敗してもバックアップから戻せる）
+    if let Err(e) = fleetflow_core::load_project_from_root(&project_root) {
+        println!(
+            "{}",
+            format!(
+                "⚠ 移行後の設定の読み込みに失敗しました（*.bak から復元できます）: {}",
+                e
+            )
+            .yellow()
+        );
+    }
+
+    println!(
+        "{}",
+        format!("✓ {} 件を移行しました", changes).green().bold()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_migrates_legacy_layout_and_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("flow.kdl"),
+            "service \"api\" service_type=\"static\" {\n    image \"api:1\"\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("flow.local.kdl"), "service \"api\" {}\n").unwrap();
+
+        let plans = plan(root).unwrap();
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].source, root.join("flow.kdl"));
+        assert_eq!(plans[0].target, root.join(ROOT_FILE));
+        assert_eq!(plans[0].rewrites.len(), 1);
+        assert!(plans[0].content.contains("type=\"static\""));
+        assert!(!plans[1].changed());
+    }
+}
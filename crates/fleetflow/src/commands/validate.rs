@@ -0,0 +1,76 @@
+//! `fleet validate` — 設定ファイルの検証
+//!
+//! 設定を読み込んでパースエラーを報告する。ステージ指定時はそのステージを、
+//! 未指定時は全ステージを解決し、参照の整合性（サービス・サーバー・depends_on・
+//! image/build・ポート）を検証する。`--strict` を付けると未知のノード名・
+//! プロパティ（typo）も検出する。
+
+use colored::Colorize;
+use std::path::Path;
+
+pub fn handle(project_root: &Path, stage: Option<String>, strict: bool) -> anyhow::Result<()> {
+    println!("{}", "設定を検証中...".blue());
+    if let Some(stage) = &stage {
+        println!("ステージ: {}", stage.cyan());
+    }
+
+    let flow =
+        match fleetflow_core::load_project_from_root_with_stage(project_root, stage.as_deref()) {
+            Ok(flow) => flow,
+            Err(e) => {
+                eprintln!();
+                eprintln!("{}", "✗ 設定の読み込みに失敗しました".red().bold());
+                eprintln!("  {}", e);
+                return Err(anyhow::anyhow!("設定の検証に失敗しました"));
+            }
+        };
+
+    let mut errors: Vec<String> = Vec::new();
+
+    let mut stages: Vec<String> = match &stage {
+        Some(stage) => vec![stage.clone()],
+        None => flow.stages.keys().cloned().collect(),
+    };
+    stages.sort();
+    for stage_name in &stages {
+        errors.extend(
+            flow.validate_stage(stage_name)
+                .into_iter()
+                .map(|error| format!("stage \"{}\": {}", stage_name, error)),
+        );
+    }
+
+    if strict {
+        let content = fleetflow_core::expand_project_with_stage(project_root, stage.as_deref())?;
+        errors.extend(
+            fleetflow_core::check_unknown_keys(&content)?
+                .iter()
+                .map(|issue| issue.to_string()),
+        );
+    }
+
+    if !errors.is_empty() {
+        eprintln!();
+        eprintln!(
+            "{}",
+            format!("✗ {} 件の問題が見つかりました", errors.len())
+                .red()
+                .bold()
+        );
+        for error in &errors {
+            eprintln!("  • {}", error);
+        }
+        return Err(anyhow::anyhow!("設定の検証に失敗しました"));
+    }
+
+    println!();
+    println!("{}", "✓ 設定は有効です".green().bold());
+    println!(
+        "  プロジェクト: {} / サービス: {} 個 / ステージ: {} 個",
+        flow.name.cyan(),
+        flow.services.len(),
+        flow.stages.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! `fleet wait` — コンテナや HTTP エンドポイントが条件を満たすまで待つ
+//!
+//! シェルスクリプトや CI で `sleep` のループを書かずに手順を順序付けるためのコマンド。
+//! 待機は waiter モジュール（exponential backoff）で行い、`--timeout` を過ぎると失敗する。
+//! `--exited` はコンテナの終了コードをそのまま終了コードにする（マイグレーション用コンテナなど）。
+
+use crate::docker;
+use crate::utils;
+use colored::Colorize;
+use fleetflow_container::{ContainerCondition, ContainerError, poll_until, wait_for_condition};
+use fleetflow_core::WaitConfig;
+use std::time::Duration;
+
+/// 待機する条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Container(ContainerCondition),
+    Http(String),
+}
+
+/// HTTP エンドポイントが 2xx を返すか
+async fn http_ok(client: &reqwest::Client, url: &str) -> bool {
+    client
+        .get(url)
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success())
+}
+
+/// `fleet wait`
+pub async fn handle(
+    config: &fleetflow_core::Flow,
+    stage: Option<String>,
+    service: Option<&str>,
+    condition: Condition,
+    timeout: &str,
+) -> anyhow::Result<()> {
+    let timeout = Duration::from_secs(utils::parse_duration(timeout)?);
+    let wait_config = WaitConfig::default();
+
+    let (target, condition) = match condition {
+        Condition::Http(url) => {
+            println!(
+                "{} {} が応答するまで待機中（最大 {}s）...",
+                "⏳".blue(),
+                url.cyan(),
+                timeout.as_secs()
+            );
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()?;
+            let (client, url_ref) = (&client, url.as_str());
+            poll_until(timeout, &wait_config, || async move {
+                http_ok(client, url_ref).await.then_some(())
+            })
+            .await
+            .ok_or_else(|| ContainerError::WaitTimeout {
+                target: url.clone(),
+                condition: "2xx".to_string(),
+                timeout_secs: timeout.as_secs(),
+            })?;
+            println!("{} {} が応答しました", "✓".green(), url.cyan());
+            return Ok(());
+        }
+        Condition::Container(condition) => {
+            let service = service
+                .ok_or_else(|| anyhow::anyhow!("--service で待機するサービスを指定してください"))?;
+            (service, condition)
+        }
+    };
+
+    let stage_name = utils::determine_stage_name(stage, config)?;
+    if !config.services.contains_key(target) {
+        return Err(anyhow::anyhow!(
+            "サービス '{}' が見つかりません\n利用可能なサービス: {}",
+            target,
+            config
+                .services
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let container_name = config.container_name(&stage_name, target);
+
+    println!(
+        "{} {} が {} になるまで待機中（最大 {}s）...",
+        "⏳".blue(),
+        target.cyan(),
+        condition,
+        timeout.as_secs()
+    );
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    let exit_code = wait_for_condition(
+        &docker_conn,
+        &container_name,
+        condition,
+        timeout,
+        &wait_config,
+    )
+    .await?;
+
+    match exit_code {
+        Some(0) => println!(
+            "{} {} が終了しました（終了コード 0）",
+            "✓".green(),
+            target.cyan()
+        ),
+        Some(code) => {
+            eprintln!(
+                "{} {} が終了コード {} で終了しました",
+                "✗".red(),
+                target.cyan(),
+                code
+            );
+            std::process::exit(i32::try_from(code).unwrap_or(1));
+        }
+        None => println!("{} {} は {} です", "✓".green(), target.cyan(), condition),
+    }
+    Ok(())
+}
@@ -0,0 +1,247 @@
+//! `fleet ws` — ワークスペース（workspace.kdl）の複数プロジェクトをまとめて操作
+//!
+//! 各プロジェクトは `fleet -C <dir> up/down` として順に実行する（プロジェクトごとの
+//! .env・ロック・権限チェックはそのまま効く）。`up` の後はステージの共有ネットワークを作り、
+//! 各プロジェクトのコンテナを `<service>.<project>` のエイリアスで接続して、
+//! プロジェクトをまたいだ名前解決ができるようにする。
+
+use crate::docker;
+use colored::Colorize;
+use fleetflow_core::{Workspace, WorkspaceProject};
+use std::path::Path;
+
+fn load() -> anyhow::Result<Workspace> {
+    let root = fleetflow_core::find_workspace_root()?;
+    Ok(fleetflow_core::load_workspace(&root)?)
+}
+
+/// 対象プロジェクト（`--all-projects` か `--project` のどちらかが必要）
+fn targets<'a>(
+    workspace: &'a Workspace,
+    all_projects: bool,
+    projects: &[String],
+) -> anyhow::Result<Vec<&'a WorkspaceProject>> {
+    if !all_projects && projects.is_empty() {
+        return Err(anyhow::anyhow!(
+            "対象を --all-projects または --project <name> で指定してください"
+        ));
+    }
+    Ok(workspace.select(projects)?)
+}
+
+/// ワークスペースルートからの相対表示
+fn display_path(workspace: &Workspace, path: &Path) -> String {
+    path.strip_prefix(&workspace.root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// `fleet -C <project> <args...>` を実行する
+fn run_in_project(
+    project: &WorkspaceProject,
+    args: &[&str],
+    role: Option<&str>,
+) -> anyhow::Result<()> {
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command.arg("-C").arg(&project.path).args(args);
+    if let Some(role) = role {
+        command.args(["--role", role]);
+    }
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "{} で fleet {} が失敗しました（exit {}）",
+            project.name,
+            args.join(" "),
+            status.code().unwrap_or(-1)
+        ));
+    }
+    Ok(())
+}
+
+/// `fleet ws list`
+pub fn handle_list() -> anyhow::Result<()> {
+    let workspace = load()?;
+    println!("{} {}", "ワークスペース:".bold(), workspace.name.cyan());
+    println!("  {}", workspace.root.display().to_string().dimmed());
+    println!();
+    for project in &workspace.projects {
+        let state = if project.path.join(".fleetflow/fleet.kdl").exists() {
+            "✓".green()
+        } else {
+            "✗ fleet.kdl が見つかりません".red()
+        };
+        println!(
+            "  {:<16} {} {}",
+            project.name.cyan(),
+            display_path(&workspace, &project.path),
+            state
+        );
+    }
+    Ok(())
+}
+
+/// `fleet ws up`
+pub async fn handle_up(
+    all_projects: bool,
+    projects: &[String],
+    stage: &str,
+    pull: bool,
+    role: Option<&str>,
+) -> anyhow::Result<()> {
+    let workspace = load()?;
+    let targets = targets(&workspace, all_projects, projects)?;
+
+    let mut args = vec!["up", "-s", stage];
+    if pull {
+        args.push("--pull");
+    }
+    for project in &targets {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "━━ {} ({}) ━━",
+                project.name,
+                display_path(&workspace, &project.path)
+            )
+            .bold()
+        );
+        run_in_project(project, &args, role)?;
+    }
+
+    bridge(&workspace, &targets, stage).await?;
+
+    println!();
+    println!(
+        "{}",
+        format!(
+            "✓ ワークスペース {} の {} プロジェクトを起動しました",
+            workspace.name,
+            targets.len()
+        )
+        .green()
+        .bold()
+    );
+    Ok(())
+}
+
+/// 共有ネットワークを作り、各プロジェクトのコンテナを `<service>.<project>` で接続する
+async fn bridge(
+    workspace: &Workspace,
+    targets: &[&WorkspaceProject],
+    stage: &str,
+) -> anyhow::Result<()> {
+    let network_name = workspace.network_name(stage);
+    println!();
+    println!("{}", format!("共有ネットワーク: {}", network_name).blue());
+
+    let docker_conn = docker::init_docker_with_error_handling().await?;
+    docker::ensure_network(&docker_conn, &network_name).await?;
+
+    for project in targets {
+        let config = fleetflow_core::load_project_from_root_with_stage(&project.path, Some(stage))?;
+        let Some(stage_config) = config.stages.get(stage) else {
+            continue;
+        };
+        for service_name in &stage_config.services {
+            if config
+                .services
+                .get(service_name)
+                .is_some_and(|s| s.is_static())
+            {
+                continue;
+            }
+            let container_name = config.container_name(stage, service_name);
+            let alias = format!("{}.{}", service_name, project.name);
+            let request = bollard::models::NetworkConnectRequest {
+                container: Some(container_name.clone()),
+                endpoint_config: Some(bollard::models::EndpointSettings {
+                    aliases: Some(vec![alias.clone()]),
+                    ..Default::default()
+                }),
+            };
+            match docker_conn.connect_network(&network_name, request).await {
+                Ok(()) => println!("  ✓ {} → {}", container_name, alias.cyan()),
+                // 既に接続済み
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 403 | 409,
+                    ..
+                }) => println!("  ✓ {} → {}（接続済み）", container_name, alias.cyan()),
+                Err(e) => {
+                    println!(
+                        "  {} {} を接続できません: {}",
+                        "⚠".yellow(),
+                        container_name,
+                        e
+                    )
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `fleet ws down`
+pub async fn handle_down(
+    all_projects: bool,
+    projects: &[String],
+    stage: &str,
+    role: Option<&str>,
+) -> anyhow::Result<()> {
+    let workspace = load()?;
+    let targets = targets(&workspace, all_projects, projects)?;
+
+    // 起動と逆順に停止する
+    for project in targets.iter().rev() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "━━ {} ({}) ━━",
+                project.name,
+                display_path(&workspace, &project.path)
+            )
+            .bold()
+        );
+        run_in_project(project, &["down", "-s", stage], role)?;
+    }
+
+    // 全プロジェクトを止めたときだけ共有ネットワークを片付ける
+    if targets.len() == workspace.projects.len() {
+        let network_name = workspace.network_name(stage);
+        let docker_conn = docker::init_docker_with_error_handling().await?;
+        match docker_conn.remove_network(&network_name).await {
+            Ok(()) => println!("  ✓ 共有ネットワーク {} を削除しました", network_name),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => println!(
+                "  {} 共有ネットワーク {} を削除できません: {}",
+                "⚠".yellow(),
+                network_name,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_require_selection() {
+        let workspace = fleetflow_core::parse_workspace(
+            "workspace \"shop\"\nproject \"../frontend\"\nproject \"../backend\"",
+            Path::new("/work/shop"),
+        )
+        .unwrap();
+        assert!(targets(&workspace, false, &[]).is_err());
+        assert_eq!(targets(&workspace, true, &[]).unwrap().len(), 2);
+        let selected = targets(&workspace, false, &["backend".to_string()]).unwrap();
+        assert_eq!(selected[0].name, "backend");
+    }
+}
@@ -0,0 +1,275 @@
+//! fleet.kdl の cloudflare provider から DNS 設定を解決する
+//!
+//! ```kdl
+//! provider "cloudflare" {
+//!     zone_id "0123abcd..."
+//!     domain "example.com"
+//!     api_token secret="cloudflare_token"   // または env="CF_API_TOKEN"
+//!     dns {
+//!         zone "internal.example.net"       // 追加ゾーン（id 省略時は API で引く）
+//!         subdomain "{service}-{stage}"     // サービスのサブドメイン命名規則
+//!     }
+//! }
+//!
+//! stage "stg" {
+//!     dns_domain "stg.example.com"          // ステージごとの上書き
+//! }
+//!
+//! server "db-1" {
+//!     domain "internal.example.net"         // サーバーごとの上書き
+//! }
+//! ```
+//!
+//! 値ごとに「ステージの `dns_domain`（ドメインのみ）→ provider の宣言 → `CLOUDFLARE_*` 環境変数」
+//! の順で解決する。サーバーのレコードは `domain` を含むゾーンのクライアントで操作する。
+
+use fleetflow_cloud_cloudflare::{CloudflareDns, DnsConfig, DnsZones};
+use fleetflow_core::{CredentialRef, Flow, ServerResource};
+
+/// DNS 設定を持つ provider 名
+const PROVIDER: &str = "cloudflare";
+
+/// fleet.kdl で宣言されたドメイン（ステージの `dns_domain` → provider の `domain`）
+fn declared_domain(config: &Flow, stage: Option<&str>) -> Option<String> {
+    stage
+        .and_then(|name| config.stages.get(name))
+        .and_then(|s| s.dns_domain.clone())
+        .or_else(|| {
+            let provider = config.providers.get(PROVIDER)?;
+            provider
+                .domain
+                .clone()
+                .or_else(|| Some(provider.dns_zones.first()?.domain.clone()))
+        })
+}
+
+/// ステージで使うドメイン（API 認証情報は不要）
+pub fn resolve_domain(config: &Flow, stage: Option<&str>) -> Option<String> {
+    declared_domain(config, stage)
+        .or_else(|| fleetflow_cloud_cloudflare::dns::domain_from_env().ok())
+}
+
+/// サーバーのレコードを置くドメイン（サーバーの `domain` → ステージのドメイン）
+pub fn server_domain(
+    config: &Flow,
+    stage: Option<&str>,
+    server: &ServerResource,
+) -> Option<String> {
+    server
+        .domain
+        .clone()
+        .or_else(|| resolve_domain(config, stage))
+}
+
+/// サービス URL の組み立てに使うドメインと命名規則
+#[derive(Debug, Clone)]
+pub struct ServiceDomain {
+    pub domain: String,
+    pub subdomain_template: Option<String>,
+}
+
+impl ServiceDomain {
+    /// サービスの公開 URL（`https://<subdomain>.<domain>`）
+    pub fn url(&self, service: &str, stage: &str) -> String {
+        format!(
+            "https://{}.{}",
+            fleetflow_cloud_cloudflare::dns::render_subdomain(
+                self.subdomain_template.as_deref(),
+                service,
+                stage
+            ),
+            self.domain
+        )
+    }
+}
+
+/// ステージのサービス URL 用ドメイン（API 認証情報は不要）
+pub fn service_domain(config: &Flow, stage: Option<&str>) -> Option<ServiceDomain> {
+    Some(ServiceDomain {
+        domain: resolve_domain(config, stage)?,
+        subdomain_template: config
+            .providers
+            .get(PROVIDER)
+            .and_then(|p| p.subdomain_template.clone()),
+    })
+}
+
+/// 認証情報の参照を値に解決する
+fn resolve_credential(config: &Flow, credential: &CredentialRef) -> anyhow::Result<String> {
+    match credential {
+        CredentialRef::Env(name) => std::env::var(name)
+            .map_err(|_| anyhow::anyhow!("環境変数 '{}' が設定されていません", name)),
+        CredentialRef::Secret(key) => {
+            let secret = config.secrets.get(key).ok_or_else(|| {
+                anyhow::anyhow!("secret '{}' が secrets ブロックに宣言されていません", key)
+            })?;
+            fleetflow_container::resolve_secret(key, secret)
+        }
+    }
+}
+
+/// provider で宣言された API トークン
+fn declared_api_token(config: &Flow) -> anyhow::Result<Option<String>> {
+    config
+        .providers
+        .get(PROVIDER)
+        .and_then(|p| p.api_token.as_ref())
+        .map(|credential| resolve_credential(config, credential))
+        .transpose()
+}
+
+/// Cloudflare API トークン（provider の `api_token` → `CLOUDFLARE_API_TOKEN`）
+pub fn api_token(config: &Flow) -> anyhow::Result<String> {
+    match declared_api_token(config)? {
+        Some(token) => Ok(token),
+        None => std::env::var("CLOUDFLARE_API_TOKEN").map_err(|_| {
+            anyhow::anyhow!(
+                "cloudflare provider の api_token または CLOUDFLARE_API_TOKEN が設定されていません"
+            )
+        }),
+    }
+}
+
+/// Cloudflare のアカウント ID（provider の `account_id` → `CLOUDFLARE_ACCOUNT_ID`）
+pub fn account_id(config: &Flow) -> Option<String> {
+    config
+        .providers
+        .get(PROVIDER)
+        .and_then(|p| {
+            p.config
+                .get("account_id")
+                .or_else(|| p.config.get("account-id"))
+                .cloned()
+        })
+        .or_else(|| std::env::var("CLOUDFLARE_ACCOUNT_ID").ok())
+}
+
+/// ステージの DNS 設定
+pub fn resolve_config(config: &Flow, stage: Option<&str>) -> anyhow::Result<DnsConfig> {
+    let provider = config.providers.get(PROVIDER);
+    let zone_id = provider.and_then(|p| p.zone_id.clone());
+    let dns_config = DnsConfig::with_overrides(
+        declared_api_token(config)?,
+        zone_id,
+        declared_domain(config, stage),
+    )?;
+    Ok(dns_config.with_subdomain_template(provider.and_then(|p| p.subdomain_template.clone())))
+}
+
+/// ステージの DNS クライアント
+pub fn client(config: &Flow, stage: Option<&str>) -> anyhow::Result<CloudflareDns> {
+    Ok(CloudflareDns::new(resolve_config(config, stage)?))
+}
+
+/// provider で宣言された全ゾーンの DNS クライアント
+///
+/// ステージの主ゾーン（[`resolve_config`]）に `dns { zone ... }` の各ゾーンを加える。
+/// `id` を省略したゾーンは API でドメイン名からゾーン ID を引く。
+pub async fn zones(config: &Flow, stage: Option<&str>) -> anyhow::Result<DnsZones> {
+    let mut clients = Vec::new();
+    if let Ok(primary) = client(config, stage) {
+        clients.push(primary);
+    }
+
+    let Some(provider) = config.providers.get(PROVIDER) else {
+        return Ok(DnsZones::new(clients));
+    };
+    if provider.dns_zones.is_empty() {
+        return Ok(DnsZones::new(clients));
+    }
+
+    let api_token = api_token(config)?;
+    for zone in &provider.dns_zones {
+        if clients.iter().any(|c| c.domain() == zone.domain) {
+            continue;
+        }
+        let zone_id = match &zone.zone_id {
+            Some(id) => id.clone(),
+            None => fleetflow_cloud_cloudflare::dns::lookup_zone_id(&api_token, &zone.domain)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!("ゾーン {} の ID を取得できません: {}", zone.domain, e)
+                })?,
+        };
+        clients.push(CloudflareDns::new(DnsConfig {
+            api_token: api_token.clone(),
+            zone_id,
+            domain: zone.domain.clone(),
+            subdomain_template: provider.subdomain_template.clone(),
+        }));
+    }
+    Ok(DnsZones::new(clients))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_domain_prefers_stage() {
+        let config = fleetflow_core::parse_kdl_string(
+            r#"
+            provider "cloudflare" {
+                domain "example.com"
+            }
+            stage "stg" {
+                dns_domain "stg.example.com"
+            }
+            stage "prod"
+            "#,
+            "myapp".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            declared_domain(&config, Some("stg")).as_deref(),
+            Some("stg.example.com")
+        );
+        assert_eq!(
+            declared_domain(&config, Some("prod")).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            declared_domain(&config, None).as_deref(),
+            Some("example.com")
+        );
+    }
+
+    #[test]
+    fn test_server_domain_and_service_url() {
+        let config = fleetflow_core::parse_kdl_string(
+            r#"
+            provider "cloudflare" {
+                dns {
+                    zone "example.com"
+                    zone "internal.example.net"
+                    subdomain "{service}.{stage}"
+                }
+            }
+            server "web-1" {
+                provider "sakura-cloud"
+            }
+            server "db-1" {
+                provider "sakura-cloud"
+                domain "internal.example.net"
+            }
+            stage "prod"
+            "#,
+            "myapp".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            server_domain(&config, Some("prod"), &config.servers["web-1"]).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            server_domain(&config, Some("prod"), &config.servers["db-1"]).as_deref(),
+            Some("internal.example.net")
+        );
+        assert_eq!(
+            service_domain(&config, Some("prod"))
+                .unwrap()
+                .url("api", "prod"),
+            "https://api.prod.example.com"
+        );
+    }
+}
@@ -16,6 +16,7 @@ pub fn parse_image_tag(image: &str) -> (&str, &str) {
 async fn pull_image_inner(
     docker: &bollard::Docker,
     image: &str,
+    auth: &fleetflow_build::RegistryAuth,
     pre_msg: &str,
     done_msg: &str,
 ) -> anyhow::Result<()> {
@@ -23,7 +24,6 @@ async fn pull_image_inner(
 
     println!("{}", pre_msg);
 
-    let auth = fleetflow_build::RegistryAuth::new();
     let credentials = auth
         .get_credentials(image)
         .map_err(|e| anyhow::anyhow!("認証情報の取得に失敗: {}", e))?;
@@ -35,6 +35,7 @@ async fn pull_image_inner(
     };
 
     let mut stream = docker.create_image(Some(options), None, credentials);
+    let task = fleetflow_build::reporter().step_task("pull", image);
 
     while let Some(info) = stream.next().await {
         match info {
@@ -44,19 +45,16 @@ async fn pull_image_inner(
                 progress_detail,
                 ..
             }) => {
-                let progress = progress_detail.and_then(|pd| match (pd.current, pd.total) {
-                    (Some(c), Some(t)) if t > 0 => Some(format!("{c}/{t}")),
-                    _ => None,
-                });
-                use std::io::Write;
-                match progress {
-                    Some(p) => print!("\r  ↓ {}: {}", status, p),
-                    None => print!("\r  ↓ {}                    ", status),
+                task.set_message(&status);
+                if let Some(pd) = progress_detail
+                    && let (Some(current), Some(total)) = (pd.current, pd.total)
+                    && total > 0
+                {
+                    task.set_position(current as u64, total as u64);
                 }
-                std::io::stdout().flush()?;
             }
             Err(e) => {
-                println!();
+                task.fail("ダウンロード失敗");
                 return Err(anyhow::anyhow!(
                     "イメージのダウンロードに失敗しました: {}",
                     e
@@ -66,33 +64,58 @@ async fn pull_image_inner(
         }
     }
 
-    println!();
-    println!("{}", done_msg);
+    task.finish(done_msg);
 
     Ok(())
 }
 
+/// ステージのレジストリ認証（`registry` ブロックの環境変数 → ~/.docker/config.json の順）
+pub fn registry_auth(
+    config: &fleetflow_core::Flow,
+    stage_name: &str,
+) -> anyhow::Result<fleetflow_build::RegistryAuth> {
+    let auth = fleetflow_build::RegistryAuth::new();
+    let credentials = match config.stages.get(stage_name) {
+        Some(stage) => stage.registry_credentials()?,
+        None => None,
+    };
+    Ok(match credentials {
+        Some(credentials) => auth.with_credentials(credentials),
+        None => auth,
+    })
+}
+
 /// Dockerイメージを自動的にpull
-pub async fn pull_image(docker: &bollard::Docker, image: &str) -> anyhow::Result<()> {
+pub async fn pull_image(
+    docker: &bollard::Docker,
+    image: &str,
+    auth: &fleetflow_build::RegistryAuth,
+) -> anyhow::Result<()> {
     pull_image_inner(
         docker,
         image,
+        auth,
         &format!(
             "  ℹ イメージが見つかりません: {}\n  ↓ イメージをダウンロード中...",
             image.cyan()
         ),
-        "  ✓ イメージのダウンロード完了",
+        "イメージのダウンロード完了",
     )
     .await
 }
 
 /// 最新イメージを強制的にpull（--pull フラグ用）
-pub async fn pull_image_always(docker: &bollard::Docker, image: &str) -> anyhow::Result<()> {
+pub async fn pull_image_always(
+    docker: &bollard::Docker,
+    image: &str,
+    auth: &fleetflow_build::RegistryAuth,
+) -> anyhow::Result<()> {
     pull_image_inner(
         docker,
         image,
+        auth,
         &format!("  ↓ 最新イメージをプル中: {}", image.cyan()),
-        "  ✓ プル完了",
+        "プル完了",
     )
     .await
 }
@@ -128,6 +151,7 @@ pub async fn ensure_container_running(
     container_name: &str,
     container_config: bollard::models::ContainerCreateBody,
     create_options: bollard::query_parameters::CreateContainerOptions,
+    auth: &fleetflow_build::RegistryAuth,
 ) -> anyhow::Result<()> {
     let image = container_config
         .image
@@ -140,7 +164,7 @@ pub async fn ensure_container_running(
         Err(bollard::errors::Error::DockerResponseServerError {
             status_code: 404, ..
         }) => {
-            pull_image(docker, image).await?;
+            pull_image(docker, image, auth).await?;
         }
         Err(e) => return Err(e.into()),
     }
@@ -163,41 +187,75 @@ pub async fn ensure_container_running(
     Ok(())
 }
 
+/// グローバル設定の `docker-retry` に従って接続する
+async fn connect_with_retry()
+-> fleetflow_container::Result<(bollard::Docker, fleetflow_container::DockerEndpoint)> {
+    let config = crate::utils::global_config();
+    let retry = fleetflow_container::ConnectRetry {
+        attempts: config.docker_retry.attempts,
+        interval: std::time::Duration::from_secs(config.docker_retry.interval),
+    };
+    fleetflow_container::connect_docker_with_retry(
+        config.docker_socket.as_deref(),
+        &retry,
+        |attempt, delay| {
+            eprintln!(
+                "  {} Docker に接続できません。{} 秒後に再試行します [{}/{}]",
+                "⚠".yellow(),
+                delay.as_secs(),
+                attempt,
+                retry.attempts
+            );
+        },
+    )
+    .await
+}
+
+/// ストリームが切れたときに Docker へ再接続する（logs -f / supervise 用）
+pub async fn reconnect_docker() -> anyhow::Result<bollard::Docker> {
+    eprintln!(
+        "  {} Docker との接続が切れました。再接続します...",
+        "⚠".yellow()
+    );
+    let (docker, endpoint) = connect_with_retry()
+        .await
+        .map_err(|e| anyhow::anyhow!("Docker に再接続できません: {}", e))?;
+    eprintln!("  {} Docker に再接続しました: {}", "✓".green(), endpoint);
+    Ok(docker)
+}
+
 /// Docker接続を初期化（エラーハンドリング付き）
+///
+/// `DOCKER_HOST`、グローバル設定の `docker-socket`、既知のソケット
+/// （OrbStack / Docker Desktop / Colima / Lima / rootless Docker / Podman）の順に試す。
+///
+/// デーモンが再起動中などで応答しない場合は、グローバル設定の `docker-retry` に従って再試行する。
 pub async fn init_docker_with_error_handling() -> anyhow::Result<bollard::Docker> {
-    match bollard::Docker::connect_with_local_defaults() {
-        Ok(docker) => {
-            // 接続テスト
-            match docker.ping().await {
-                Ok(_) => Ok(docker),
-                Err(e) => {
-                    eprintln!();
-                    eprintln!("{}", "✗ Docker接続エラー".red().bold());
-                    eprintln!();
-                    eprintln!("{}", "原因:".yellow());
-                    eprintln!("  {}", e);
-                    eprintln!();
-                    eprintln!("{}", "解決方法:".yellow());
-                    eprintln!("  • Dockerが起動しているか確認してください");
-                    eprintln!(
-                        "  • OrbStackまたはDocker Desktopがインストールされているか確認してください"
-                    );
-                    eprintln!("  • docker ps コマンドが正常に動作するか確認してください");
-                    Err(anyhow::anyhow!("Docker接続に失敗しました"))
-                }
-            }
+    match connect_with_retry().await {
+        Ok((docker, endpoint)) => {
+            tracing::info!(endpoint = %endpoint, "Docker endpoint selected");
+            Ok(docker)
         }
         Err(e) => {
             eprintln!();
             eprintln!("{}", "✗ Docker接続エラー".red().bold());
             eprintln!();
-            eprintln!("{}", "原因:".yellow());
-            eprintln!("  {}", e);
+            let tried = match &e {
+                fleetflow_container::ContainerError::DockerConnectionFailed(tried) => tried.clone(),
+                other => other.to_string(),
+            };
+            eprintln!("{}", "試した接続先:".yellow());
+            eprintln!("  {}", tried);
             eprintln!();
             eprintln!("{}", "解決方法:".yellow());
             eprintln!("  • Dockerが起動しているか確認してください");
-            eprintln!("  • OrbStackまたはDocker Desktopがインストールされているか確認してください");
+            eprintln!(
+                "  • OrbStack / Docker Desktop / Colima / Podman などがインストールされているか確認してください"
+            );
             eprintln!("  • docker ps コマンドが正常に動作するか確認してください");
+            eprintln!(
+                "  • ソケットの場所が特殊な場合は fleet config set docker-socket <path> で指定できます"
+            );
             Err(anyhow::anyhow!("Docker接続に失敗しました"))
         }
     }
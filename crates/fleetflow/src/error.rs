@@ -0,0 +1,285 @@
+//! CLI のエラー分類と終了コード
+//!
+//! 各クレートのエラー（`FlowError` / `ContainerError` / `BuildError` / `CloudError`）を
+//! config / docker / build / cloud / ssh のカテゴリにまとめ、診断コード・ヒント付きで表示する。
+//! 終了コードはカテゴリごとに固定で、スクリプトや CI から失敗の種類を判別できる。
+//!
+//! | 終了コード | 意味 |
+//! |-----------|------|
+//! | 0 | 成功 |
+//! | 1 | その他のエラー |
+//! | 2 | 引数の誤り・確認が必要（`--yes` なしなど） |
+//! | 3 | 設定（fleet.kdl・ステージ・テンプレート・シークレット） |
+//! | 4 | Docker（接続・コンテナ・イメージ・ポート） |
+//! | 5 | ビルド・プッシュ |
+//! | 6 | クラウドプロバイダー（API・認証・リソース） |
+//! | 7 | SSH（リモートホストへの接続・転送） |
+
+use colored::Colorize;
+use fleetflow_build::{BuildError, ProgressEvent, ProgressStatus};
+use fleetflow_cloud::CloudError;
+use fleetflow_container::ContainerError;
+use fleetflow_core::FlowError;
+
+pub const EXIT_FAILURE: u8 = 1;
+pub const EXIT_CONFIG: u8 = 3;
+pub const EXIT_DOCKER: u8 = 4;
+pub const EXIT_BUILD: u8 = 5;
+pub const EXIT_CLOUD: u8 = 6;
+pub const EXIT_SSH: u8 = 7;
+
+/// カテゴリ分けした CLI のエラー
+#[derive(Debug, thiserror::Error)]
+pub enum FleetError {
+    #[error(transparent)]
+    Config(#[from] FlowError),
+
+    #[error(transparent)]
+    Docker(#[from] ContainerError),
+
+    #[error("{}", .0.user_message())]
+    Build(#[from] BuildError),
+
+    #[error(transparent)]
+    Cloud(CloudError),
+
+    #[error(transparent)]
+    Ssh(CloudError),
+}
+
+impl From<CloudError> for FleetError {
+    fn from(err: CloudError) -> Self {
+        match err {
+            CloudError::Ssh(_) => FleetError::Ssh(err),
+            err => FleetError::Cloud(err),
+        }
+    }
+}
+
+impl FleetError {
+    /// 終了コード
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            FleetError::Config(_) => EXIT_CONFIG,
+            FleetError::Docker(_) | FleetError::Build(BuildError::DockerConnection(_)) => {
+                EXIT_DOCKER
+            }
+            FleetError::Build(_) => EXIT_BUILD,
+            FleetError::Cloud(_) => EXIT_CLOUD,
+            FleetError::Ssh(_) => EXIT_SSH,
+        }
+    }
+
+    /// 診断コード（`fleet::<category>::<kind>`）
+    pub fn code(&self) -> &'static str {
+        match self {
+            FleetError::Config(e) => e.code(),
+            FleetError::Docker(e) => e.code(),
+            FleetError::Build(e) => e.code(),
+            FleetError::Cloud(e) | FleetError::Ssh(e) => e.code(),
+        }
+    }
+
+    /// 解決のヒント
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            FleetError::Config(e) => e.help(),
+            FleetError::Docker(e) => e.help(),
+            // user_message に解決方法が含まれている
+            FleetError::Build(_) => None,
+            FleetError::Cloud(e) | FleetError::Ssh(e) => e.help(),
+        }
+    }
+
+    fn diagnosis(&self) -> Diagnosis {
+        Diagnosis {
+            exit_code: self.exit_code(),
+            code: self.code(),
+            help: self.help(),
+        }
+    }
+
+    /// anyhow のエラーチェーンから各クレートのエラーを探して分類する
+    ///
+    /// エラー本体は消費しないため、`.context()` で付けたメッセージも含めて表示できる。
+    pub fn classify(err: &anyhow::Error) -> Option<Diagnosis> {
+        err.chain().find_map(diagnose)
+    }
+}
+
+/// 分類結果（終了コード・診断コード・ヒント）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub exit_code: u8,
+    pub code: &'static str,
+    pub help: Option<&'static str>,
+}
+
+/// エラーチェーンの 1 要素を分類する
+fn diagnose(cause: &(dyn std::error::Error + 'static)) -> Option<Diagnosis> {
+    if let Some(e) = cause.downcast_ref::<FleetError>() {
+        return Some(e.diagnosis());
+    }
+    if let Some(e) = cause.downcast_ref::<FlowError>() {
+        return Some(Diagnosis {
+            exit_code: EXIT_CONFIG,
+            code: e.code(),
+            help: e.help(),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<ContainerError>() {
+        return Some(container_diagnosis(e));
+    }
+    if let Some(e) = cause.downcast_ref::<BuildError>() {
+        return Some(Diagnosis {
+            exit_code: if matches!(e, BuildError::DockerConnection(_)) {
+                EXIT_DOCKER
+            } else {
+                EXIT_BUILD
+            },
+            code: e.code(),
+            // user_message に解決方法が含まれている
+            help: None,
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<CloudError>() {
+        return Some(Diagnosis {
+            exit_code: if matches!(e, CloudError::Ssh(_)) {
+                EXIT_SSH
+            } else {
+                EXIT_CLOUD
+            },
+            code: e.code(),
+            help: e.help(),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<bollard::errors::Error>() {
+        // ContainerError::from と同じ判定（404 / 409 のメッセージは接続エラーに該当しない）
+        let message = e.to_string();
+        let e = if message.contains("Connection refused")
+            || message.contains("No such file or directory")
+        {
+            ContainerError::DockerConnectionFailed(message)
+        } else {
+            ContainerError::DockerApiError(message)
+        };
+        return Some(container_diagnosis(&e));
+    }
+    None
+}
+
+fn container_diagnosis(e: &ContainerError) -> Diagnosis {
+    Diagnosis {
+        exit_code: EXIT_DOCKER,
+        code: e.code(),
+        help: e.help(),
+    }
+}
+
+/// `.context()` を含むエラーチェーン全体を 1 行にする（`{:#}` と同じ形式）
+///
+/// ビルドエラーは解決方法を含む `user_message` で表示する。
+fn chain_message(err: &anyhow::Error) -> String {
+    err.chain()
+        .map(|cause| match cause.downcast_ref::<BuildError>() {
+            Some(e) => e.user_message(),
+            None => cause.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// エラーを標準エラーに表示し、終了コードを返す
+///
+/// `--progress=json` の場合は失敗イベントも出力する。
+pub fn report(err: anyhow::Error) -> u8 {
+    let progress = fleetflow_build::reporter();
+    match FleetError::classify(&err) {
+        Some(diagnosis) => {
+            let message = chain_message(&err);
+            progress.event(
+                &ProgressEvent::new("error", ProgressStatus::Failed, message.clone())
+                    .target(diagnosis.code),
+            );
+            eprintln!("{} {}", "Error:".red().bold(), message);
+            if let Some(help) = diagnosis.help {
+                eprintln!("  {} {}", "help:".cyan(), help);
+            }
+            eprintln!("  {} {}", "code:".dimmed(), diagnosis.code.dimmed());
+            diagnosis.exit_code
+        }
+        None => {
+            progress.event(&ProgressEvent::new(
+                "error",
+                ProgressStatus::Failed,
+                format!("{:#}", err),
+            ));
+            eprintln!("{} {:?}", "Error:".red().bold(), err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_exit_codes() {
+        let cases: Vec<(anyhow::Error, u8, &str)> = vec![
+            (
+                FlowError::InvalidConfig("x".into()).into(),
+                EXIT_CONFIG,
+                "fleet::config::invalid",
+            ),
+            (
+                ContainerError::DockerConnectionFailed("x".into()).into(),
+                EXIT_DOCKER,
+                "fleet::docker::connection_failed",
+            ),
+            (
+                BuildError::BuildFailed("x".into()).into(),
+                EXIT_BUILD,
+                "fleet::build::failed",
+            ),
+            (
+                CloudError::ApiError("x".into()).into(),
+                EXIT_CLOUD,
+                "fleet::cloud::api_error",
+            ),
+            (
+                CloudError::Ssh("x".into()).into(),
+                EXIT_SSH,
+                "fleet::ssh::failed",
+            ),
+        ];
+        for (err, exit_code, code) in cases {
+            let diagnosis = FleetError::classify(&err).unwrap();
+            assert_eq!(diagnosis.exit_code, exit_code);
+            assert_eq!(diagnosis.code, code);
+        }
+    }
+
+    #[test]
+    fn test_classify_through_context() {
+        let err = anyhow::Error::from(FlowError::ServiceNotFound("api".into()))
+            .context("サービスを解決できません");
+        assert_eq!(FleetError::classify(&err).unwrap().exit_code, EXIT_CONFIG);
+
+        let err = anyhow::anyhow!("plain error");
+        assert!(FleetError::classify(&err).is_none());
+    }
+
+    #[test]
+    fn test_context_message_survives_classification() {
+        let err = anyhow::Error::from(FlowError::ServiceNotFound("api".into()))
+            .context("stage 'prod' の読み込みに失敗")
+            .context("デプロイを中止しました");
+        let diagnosis = FleetError::classify(&err).unwrap();
+        assert_eq!(diagnosis.exit_code, EXIT_CONFIG);
+
+        let message = chain_message(&err);
+        assert!(message.starts_with("デプロイを中止しました: stage 'prod' の読み込みに失敗: "));
+        assert!(message.ends_with(&FlowError::ServiceNotFound("api".into()).to_string()));
+    }
+}
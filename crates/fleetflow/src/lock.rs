@@ -0,0 +1,263 @@
+//! プロジェクトロック
+//!
+//! 同じプロジェクトに対する up/down/deploy の同時実行でコンテナ操作が
+//! 交錯しないよう、`.fleetflow/lock` に flock(2) の排他ロックを取得する。
+//! ロックはファイル記述子に結びつくため、保持プロセスが終了すればカーネルが解放する。
+//! 終了済みプロセスのロックを回収する処理が要らず、回収どうしが競合することもない。
+//! ファイルの内容は表示用の保持者情報で、解放時に空にする。
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// ロック解放待ちのポーリング間隔
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ロックファイルに記録する保持者情報
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub host: String,
+    pub command: String,
+    pub acquired_at: String,
+}
+
+impl LockInfo {
+    fn current(command: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+            command: command.to_string(),
+            acquired_at: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "fleet {} (pid {} @ {}, {})",
+            self.command, self.pid, self.host, self.acquired_at
+        )
+    }
+}
+
+/// 取得済みのプロジェクトロック。drop 時に保持者情報を消してロックを解放する
+///
+/// ロックファイル自体は削除しない。削除すると、削除前にファイルを開いたプロセスと
+/// 新しく作り直したプロセスが別々の inode をロックして、両方が取得できてしまう。
+#[derive(Debug)]
+pub struct ProjectLock {
+    file: File,
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
+
+/// ロックファイルのパス
+pub fn lock_path(project_root: &Path) -> PathBuf {
+    project_root.join(".fleetflow").join("lock")
+}
+
+/// 現在のロック保持者を読み取る（ロックされていなければ `None`）
+///
+/// 保持者情報の書き込み前・破損している場合は保持者不明として `Some(None)` を返す。
+pub fn read_lock(project_root: &Path) -> Option<Option<LockInfo>> {
+    let mut file = File::open(lock_path(project_root)).ok()?;
+    // 取得できたら誰も保持していない（file の drop で解放される）
+    if try_lock(&file).unwrap_or(false) {
+        return None;
+    }
+    Some(read_holder(&mut file))
+}
+
+/// プロジェクトロックを取得する
+///
+/// 他のプロセスが保持している場合、`wait` が true なら解放を待ち、
+/// false なら保持者を示してエラーにする。
+pub async fn acquire(
+    project_root: &Path,
+    command: &str,
+    wait: bool,
+) -> anyhow::Result<ProjectLock> {
+    let path = lock_path(project_root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    let mut waiting = false;
+    loop {
+        if try_lock(&file)? {
+            let info = LockInfo::current(command);
+            file.set_len(0)?;
+            file.rewind()?;
+            file.write_all(serde_json::to_string_pretty(&info)?.as_bytes())?;
+            file.flush()?;
+            return Ok(ProjectLock { file });
+        }
+
+        let holder_desc = read_holder(&mut file)
+            .as_ref()
+            .map(LockInfo::describe)
+            .unwrap_or_else(|| "不明".to_string());
+
+        if !wait {
+            return Err(anyhow::anyhow!(
+                "プロジェクトは別の操作でロックされています: {}\n\
+                 ヒント: 完了を待つには --wait を指定してください",
+                holder_desc
+            ));
+        }
+
+        if !waiting {
+            println!(
+                "{}",
+                format!("⏳ ロックの解放を待機中: {}", holder_desc).yellow()
+            );
+            waiting = true;
+        }
+        tokio::time::sleep(WAIT_INTERVAL).await;
+    }
+}
+
+/// 排他ロックをノンブロッキングで試みる。他のプロセスが保持していれば false
+fn try_lock(file: &File) -> io::Result<bool> {
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+/// ロックファイルに書かれた保持者情報
+fn read_holder(file: &mut File) -> Option<LockInfo> {
+    let mut content = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if ret != 0 {
+        return "unknown".to_string();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+/// `fleet unlock` — ロックを強制的に解除する
+///
+/// ロックは保持プロセスの終了とともに解放されるため、通常は不要。保持プロセスが
+/// 応答しなくなった場合に `--force` でロックファイルを削除し、以降の操作が新しい
+/// ファイルでロックを取れるようにする（保持中のプロセス自体は止まらない）。
+pub fn handle_unlock(project_root: &Path, force: bool) -> anyhow::Result<()> {
+    let Some(holder) = read_lock(project_root) else {
+        println!("{}", "ロックは取得されていません".green());
+        return Ok(());
+    };
+
+    if let Some(holder) = &holder {
+        println!("ロック保持者: {}", holder.describe().cyan());
+    }
+    if !force {
+        return Err(anyhow::anyhow!(
+            "ロック保持プロセスが実行中です。強制的に解除するには --force を指定してください"
+        ));
+    }
+
+    std::fs::remove_file(lock_path(project_root))?;
+    println!("{}", "✓ ロックを解除しました".green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_and_release() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let lock = acquire(dir.path(), "up", false).await.unwrap();
+        let holder = read_lock(dir.path()).unwrap().unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(holder.command, "up");
+
+        drop(lock);
+        assert!(read_lock(dir.path()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_fails_while_held() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let _lock = acquire(dir.path(), "deploy", false).await.unwrap();
+        let err = acquire(dir.path(), "down", false).await.unwrap_err();
+        assert!(err.to_string().contains("fleet deploy"));
+    }
+
+    #[tokio::test]
+    async fn test_leftover_lock_file_is_not_held() {
+        // 異常終了したプロセスが残した保持者情報はロックを意味しない
+        let dir = tempfile::tempdir().unwrap();
+        let leftover = LockInfo {
+            pid: i32::MAX as u32,
+            host: hostname(),
+            command: "up".to_string(),
+            acquired_at: "2024-01-01T00:00:00+00:00".to_string(),
+        };
+        std::fs::create_dir_all(dir.path().join(".fleetflow")).unwrap();
+        std::fs::write(
+            lock_path(dir.path()),
+            serde_json::to_string(&leftover).unwrap(),
+        )
+        .unwrap();
+        assert!(read_lock(dir.path()).is_none());
+
+        let _lock = acquire(dir.path(), "down", false).await.unwrap();
+        let holder = read_lock(dir.path()).unwrap().unwrap();
+        assert_eq!(holder.command, "down");
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = acquire(dir.path(), "deploy", false).await.unwrap();
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(lock);
+        });
+        let _lock = acquire(dir.path(), "up", true).await.unwrap();
+        release.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unlock_requires_force_for_held_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        handle_unlock(dir.path(), false).unwrap();
+
+        let _lock = acquire(dir.path(), "deploy", false).await.unwrap();
+        assert!(handle_unlock(dir.path(), false).is_err());
+        handle_unlock(dir.path(), true).unwrap();
+        assert!(read_lock(dir.path()).is_none());
+    }
+}
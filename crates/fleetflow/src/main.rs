@@ -1,6 +1,12 @@
+mod audit;
+mod authz;
 mod build;
+mod build_history;
 mod commands;
+mod dns;
 mod docker;
+mod error;
+mod lock;
 mod self_update;
 mod tui;
 mod utils;
@@ -23,10 +29,33 @@ struct Cli {
     /// エラー以外の出力を抑制
     #[arg(short, long, global = true)]
     quiet: bool,
+
+    /// 操作ロール（グローバル設定の permissions で保護されたステージの変更には admin が必要）
+    #[arg(long, global = true, value_name = "ROLE")]
+    role: Option<String>,
+
+    /// リモートの fleetflowd に SSH 経由で接続して操作（ps / logs / restart。例: ssh://user@server）
+    #[arg(long, global = true, env = "FLEET_HOST", value_name = "URL")]
+    host: Option<String>,
+
+    /// 指定したディレクトリで実行（git -C と同様）
+    #[arg(short = 'C', global = true, value_name = "DIR")]
+    directory: Option<PathBuf>,
+
+    /// 進捗の出力形式（json: up / build / deploy / setup の進捗を改行区切り JSON で stdout に出し、通常の出力は stderr へ）
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value = "auto",
+        env = "FLEET_PROGRESS",
+        value_name = "FORMAT"
+    )]
+    progress: utils::ProgressFormat,
 }
 
 // ─────────────────────────────────────────────
-// Top-level commands: Daily(6) + Ship(2) + Util(3) + CP(1)
+// Top-level commands: Daily(15) + Ship(6) + Util(21) + CP(1)
 // ─────────────────────────────────────────────
 
 #[derive(Subcommand)]
@@ -51,6 +80,18 @@ enum Commands {
         /// 実行せずに実行計画のみ表示
         #[arg(long)]
         dry_run: bool,
+        /// 変更せずに現状との差分を監査（差分があれば非ゼロ終了）
+        #[arg(long, conflicts_with_all = ["dry_run", "pull"])]
+        check: bool,
+        /// 他の操作がプロジェクトロックを保持している場合、解放まで待機する
+        #[arg(long)]
+        wait: bool,
+        /// 指定したセットアップステップを実行しない（複数指定可）
+        #[arg(long = "skip-step", value_name = "NAME")]
+        skip_step: Vec<String>,
+        /// 前回の setup-state で完了済みのセットアップステップを飛ばして再開する
+        #[arg(long)]
+        resume: bool,
     },
     /// ステージを停止
     Down {
@@ -68,6 +109,9 @@ enum Commands {
         /// コンテナを削除する（デフォルトは停止のみ）
         #[arg(short, long)]
         remove: bool,
+        /// 他の操作がプロジェクトロックを保持している場合、解放まで待機する
+        #[arg(long)]
+        wait: bool,
     },
     /// サービスまたはステージ全体を再起動
     Restart {
@@ -82,9 +126,38 @@ enum Commands {
             hide = true
         )]
         stage_flag: Option<String>,
-        /// サービス名（省略時はステージ全体を再起動）
+        /// サービス名（省略時はステージ全体を依存順に再起動）
         #[arg(short = 'n', long)]
         service: Option<String>,
+        /// 1 サービスずつ再起動し、準備完了を待ってから次へ進む
+        #[arg(long, conflicts_with = "service")]
+        rolling: bool,
+        /// 対象サービスの準備完了後、それに依存するサービスも起動順に再起動する
+        #[arg(long, requires = "service")]
+        cascade: bool,
+    },
+    /// コンテナを監視し、異常終了・unhealthy になったサービスを自動で再起動
+    Supervise {
+        /// ステージ名 (local, dev, stg, prod)
+        stage: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(
+            short = 's',
+            long = "stage",
+            env = "FLEET_STAGE",
+            conflicts_with = "stage",
+            hide = true
+        )]
+        stage_flag: Option<String>,
+        /// サービスごとの再起動回数の上限（超えたら監視対象から外す）
+        #[arg(long, default_value_t = 5)]
+        max_restarts: u32,
+        /// 最初の再起動までの待ち時間（以降は倍々、最大 5 分。例: 1s, 30s）
+        #[arg(long, default_value = "1s")]
+        backoff: String,
+        /// 再起動・断念時に実行するコマンド（sh -c、FLEET_SERVICE / FLEET_SUPERVISE_EVENT 等を設定）
+        #[arg(long, value_name = "COMMAND")]
+        notify: Option<String>,
     },
     /// コンテナの一覧・状態を表示
     Ps {
@@ -102,6 +175,12 @@ enum Commands {
         /// 停止中のコンテナも表示
         #[arg(short, long)]
         all: bool,
+        /// サービス名で絞り込み
+        #[arg(long)]
+        service: Option<String>,
+        /// 状態で絞り込み（exited は停止中のコンテナも対象にする）
+        #[arg(long, value_enum)]
+        status: Option<commands::ps::StatusFilter>,
         /// Control Plane 横断: プロジェクト名で絞り込み
         #[arg(long)]
         project: Option<String>,
@@ -134,6 +213,47 @@ enum Commands {
         /// 指定時間以降のログを表示（例: 5m, 1h, 30s）
         #[arg(long)]
         since: Option<String>,
+        /// 正規表現に一致する行のみ表示
+        #[arg(long, value_name = "REGEX")]
+        grep: Option<String>,
+        /// --grep の一致判定を反転（一致しない行を表示）
+        #[arg(long, requires = "grep")]
+        invert: bool,
+        /// 指定レベル以上の行のみ表示（ログ形式から推定）
+        #[arg(long, value_enum)]
+        level: Option<commands::logs::LogLevel>,
+        /// 端末ではなくファイルに書き出す（色なし、-f 中も随時書き込む）
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// リモートステージのサービスのポートを SSH トンネルでローカルに転送
+    #[command(name = "port-forward")]
+    PortForward {
+        /// サービス名
+        service: String,
+        /// ローカルポート:コンテナポート（例: 15432:5432）
+        #[arg(value_name = "LOCAL:REMOTE")]
+        ports: String,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 接続先サーバー（ステージに複数ある場合）
+        #[arg(long)]
+        server: Option<String>,
+    },
+    /// サービスの URL をブラウザで開く（ローカルは公開ポート、リモートは DNS ドメイン）
+    Open {
+        /// サービス名
+        service: String,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 開くポートの名前（`port ... name="admin"`、省略時は http/https ポート）
+        #[arg(long, value_name = "NAME")]
+        port: Option<String>,
+        /// ブラウザを開かずに URL だけを出力
+        #[arg(long)]
+        print: bool,
     },
     /// サービスコンテナ内でコマンドを実行
     Exec {
@@ -161,6 +281,90 @@ enum Commands {
         #[arg(last = true)]
         command: Vec<String>,
     },
+    /// コンテナにシグナルを送信（例: nginx の設定再読み込み）
+    Kill {
+        /// サービス名
+        #[arg(required_unless_present = "all")]
+        service: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 送信するシグナル（例: SIGHUP, USR1, 9）
+        #[arg(long, default_value = "SIGKILL")]
+        signal: String,
+        /// ステージの全サービスに送信
+        #[arg(long, conflicts_with = "service")]
+        all: bool,
+    },
+    /// 実行中コンテナのメインプロセスに端末を接続
+    Attach {
+        /// サービス名
+        service: String,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// デタッチキー（既定: ctrl-p,ctrl-q）
+        #[arg(long, value_name = "KEYS")]
+        detach_keys: Option<String>,
+    },
+    /// サービスや HTTP エンドポイントが条件を満たすまで待機（スクリプト・CI 向け）
+    Wait {
+        /// 待機するサービス
+        #[arg(short = 'n', long, required_unless_present = "http")]
+        service: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// ヘルスチェックが healthy（なければ running）になるまで待つ（既定）
+        #[arg(long)]
+        healthy: bool,
+        /// コンテナが終了するまで待ち、その終了コードで終了する
+        #[arg(long, conflicts_with = "healthy")]
+        exited: bool,
+        /// URL が 2xx を返すまで待つ
+        #[arg(long, value_name = "URL", conflicts_with_all = ["healthy", "exited"])]
+        http: Option<String>,
+        /// 待機時間の上限（例: 30s, 2m）
+        #[arg(long, default_value = "60s")]
+        timeout: String,
+    },
+    /// サービスの環境変数を表示・編集
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Env {
+        #[command(subcommand)]
+        action: Option<EnvCommands>,
+        /// サービス名
+        service: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// センシティブな値もマスクせずに表示
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// 開発者ごとのローカル上書き（flow.local.kdl）を編集・表示
+    #[command(subcommand)]
+    Override(OverrideCommands),
+    /// サービスの test ブロックを使い捨てコンテナで実行（依存サービスを起動し、終了後に片付ける）
+    Test {
+        /// ステージ名 (local, dev, stg, prod)
+        stage: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(
+            short = 's',
+            long = "stage",
+            env = "FLEET_STAGE",
+            conflicts_with = "stage",
+            hide = true
+        )]
+        stage_flag: Option<String>,
+        /// テストするサービス（複数指定可、省略時は test を持つ全サービス）
+        #[arg(short = 'n', long)]
+        service: Vec<String>,
+        /// テスト後も依存サービスを起動したままにする
+        #[arg(long)]
+        keep_deps: bool,
+    },
 
     // ── Ship ───────────────────────────────────
     /// Dockerイメージをビルド
@@ -179,6 +383,9 @@ enum Commands {
         /// ビルド対象のサービス（複数指定可、省略時は全サービス）
         #[arg(short = 'n', long)]
         service: Vec<String>,
+        /// ステージに含まれないサービス（ベースイメージ等）も含め、build を持つ全サービスをビルド
+        #[arg(long, conflicts_with = "service")]
+        all: bool,
         /// ビルド後にレジストリにプッシュ
         #[arg(long)]
         push: bool,
@@ -190,55 +397,808 @@ enum Commands {
         registry: Option<String>,
         /// ターゲットプラットフォーム（例: linux/amd64）
         #[arg(long)]
-        platform: Option<String>,
-        /// キャッシュを使用しない
+        platform: Option<String>,
+        /// キャッシュを使用しない
+        #[arg(long)]
+        no_cache: bool,
+        /// 指定サーバーの Docker デーモンでビルド（SSH 経由、レジストリ不要）
+        #[arg(long, value_name = "SERVER")]
+        on: Option<String>,
+        /// ベースイメージを並列に事前取得し、プロジェクト共有のインラインキャッシュを使う
+        #[arg(long)]
+        warm_cache: bool,
+        /// 同じステージの前回ビルドと所要時間・イメージサイズを比較
+        #[arg(long)]
+        compare_last: bool,
+        /// プッシュに失敗しても残りのサービスをプッシュし、最後にまとめて報告
+        #[arg(long, requires = "push")]
+        continue_on_error: bool,
+        /// 同時にプッシュするイメージ数
+        #[arg(long, value_name = "N", default_value = "4")]
+        push_jobs: usize,
+        /// 指定した git リビジョン以降に変更があったサービスだけをビルド（例: origin/main）
+        #[arg(long, value_name = "REF")]
+        changed_since: Option<String>,
+    },
+    /// ステージをデプロイ（pull→停止→再起動）
+    Deploy {
+        /// ステージ名 (local, dev, stg, prod)
+        stage: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(
+            short = 's',
+            long = "stage",
+            env = "FLEET_STAGE",
+            conflicts_with = "stage",
+            hide = true
+        )]
+        stage_flag: Option<String>,
+        /// デプロイ対象のサービス（複数指定可、省略時は全サービス）
+        #[arg(short = 'n', long)]
+        service: Vec<String>,
+        /// イメージのpullをスキップ（デフォルトは常にpull）
+        #[arg(long)]
+        no_pull: bool,
+        /// デプロイ後の不要イメージ・ビルドキャッシュ削除をスキップ
+        #[arg(long)]
+        no_prune: bool,
+        /// 確認なしで実行
+        #[arg(short, long)]
+        yes: bool,
+        /// 実行せずに実行計画のみ表示
+        #[arg(long)]
+        dry_run: bool,
+        /// テナント slug を override (省略時は fleet.kdl の `tenant` block → CLI auth context → "default" の順で解決)
+        #[arg(long)]
+        tenant: Option<String>,
+        /// 他の操作がプロジェクトロックを保持している場合、解放まで待機する
+        #[arg(long)]
+        wait: bool,
+        /// レジストリにアクセスしない（イメージは `fleet image load` で事前に読み込む）
+        #[arg(long)]
+        offline: bool,
+        /// ステージの smoke_test を実行しない
+        #[arg(long)]
+        skip_smoke_test: bool,
+        /// イメージの pull とネットワーク作成のみ行う（既存コンテナには触れない）
+        #[arg(long, conflicts_with = "dry_run")]
+        prepare_only: bool,
+    },
+    /// イメージの書き出し・読み込み（エアギャップ環境向け）
+    #[command(subcommand)]
+    Image(ImageCommands),
+    /// 稼働中のコンテナがイメージタグの最新より古いかを調べる（レジストリのダイジェストと比較）
+    Outdated {
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// JSON で出力（自動化向け）
+        #[arg(long)]
+        json: bool,
+        /// 古いサービスのイメージを pull し、1 つずつ作り直す
+        #[arg(long, conflicts_with = "json")]
+        update: bool,
+    },
+    /// Git リポジトリの fleet.kdl にステージを追従させる（GitOps の同期ループ）
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Reconcile {
+        #[command(subcommand)]
+        command: Option<ReconcileCommands>,
+        /// fleet.kdl を含む Git リポジトリの URL
+        #[arg(long, value_name = "URL", required = true)]
+        repo: Option<String>,
+        /// 追従するブランチ
+        #[arg(long, default_value = "main")]
+        branch: String,
+        /// リポジトリ内のプロジェクトルート（省略時はリポジトリ直下）
+        #[arg(long, value_name = "DIR")]
+        path: Option<String>,
+        /// ステージ名
+        #[arg(short = 's', long, env = "FLEET_STAGE", required = true)]
+        stage: Option<String>,
+        /// 同期間隔（例: 30s, 5m, 1h）
+        #[arg(long, default_value = "5m")]
+        interval: String,
+        /// 状態ディレクトリ名（省略時は <リポジトリ名>-<stage>）
+        #[arg(long)]
+        name: Option<String>,
+        /// 1 回だけ同期して終了（cron / CI 向け。失敗時は非ゼロ終了）
+        #[arg(long)]
+        once: bool,
+    },
+    /// ローカルのイメージレジストリ（registry:2）を起動・管理する
+    #[command(subcommand, name = "registry")]
+    LocalRegistry(LocalRegistryCommands),
+
+    // ── Admin ──────────────────────────────────
+    /// Control Plane 管理
+    #[command(subcommand)]
+    Cp(CpCommands),
+
+    // ── Util ───────────────────────────────────
+    /// 設定ファイルを検証
+    Validate {
+        /// ステージ名 (local, dev, stg, prod)
+        stage: Option<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(
+            short = 's',
+            long = "stage",
+            env = "FLEET_STAGE",
+            conflicts_with = "stage",
+            hide = true
+        )]
+        stage_flag: Option<String>,
+        /// 未知のノード名・プロパティ（typo）もエラーにする
+        #[arg(long)]
+        strict: bool,
+    },
+    /// プロジェクトロック（.fleetflow/lock）を強制的に解除（保持プロセスが終了すれば自動で解放される）
+    Unlock {
+        /// 保持プロセスが実行中でも解除する
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// ステージ・サービス名を一覧表示（補完・スクリプト向け）
+    #[command(subcommand)]
+    List(ListCommands),
+    /// グローバル設定（~/.config/fleetflow/config.kdl）の編集、プロジェクト設定の比較
+    #[command(subcommand)]
+    Config(ConfigCommands),
+    /// サーバー定義の host_service を systemd ユニットとして導入・確認
+    #[command(subcommand, name = "host-service")]
+    HostService(HostServiceCommands),
+    /// ログイン時にステージを自動起動（launchd / systemd ユーザーユニット / タスク スケジューラ）
+    #[command(subcommand)]
+    Autostart(AutostartCommands),
+    /// 管理サーバーの保守（maintenance ポリシーに従ったイメージ削除など）
+    #[command(subcommand)]
+    Remote(RemoteHostCommands),
+    /// クラウドリソース（サーバー・DNS）の操作
+    #[command(subcommand)]
+    Cloud(CloudCommands),
+    /// プロジェクトが管理する DNS レコードの一覧・追加・削除
+    #[command(subcommand)]
+    Dns(DnsRecordCommands),
+    /// オブジェクトストレージ（R2）とローカルディレクトリの同期
+    #[command(subcommand)]
+    Storage(StorageCommands),
+    /// 実行環境を診断（Docker・ディスク・メモリ・ポート・コンテナ、--remote でサーバー）
+    Doctor {
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 診断するサーバー（SSH 経由、時刻のずれも確認）
+        #[arg(long, value_name = "SERVER")]
+        remote: Option<String>,
+    },
+    /// サーバー定義から Ansible インベントリ / SSH config を生成
+    Inventory {
+        /// 出力形式
+        #[arg(long, value_enum, default_value = "ansible")]
+        format: commands::inventory::InventoryFormat,
+        /// 出力先ファイル（省略時は標準出力）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// バグ報告用の診断情報（設定・inspect・ログ・docker info）を tar.gz にまとめる
+    #[command(name = "support-bundle")]
+    SupportBundle {
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 出力先（省略時は fleetflow-support-{project}-{stage}-{日時}.tar.gz）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// サービスごとに含めるログの行数
+        #[arg(short = 'n', long, default_value = "1000")]
+        lines: u64,
+    },
+    /// ローカルの操作履歴（.fleetflow/audit.jsonl）を集計してレポート
+    Report {
+        /// 集計対象のステージ（省略時は全ステージ）
+        #[arg(short = 's', long = "stage")]
+        stage: Option<String>,
+        /// 直近 N 日分のみ集計
+        #[arg(long, value_name = "DAYS")]
+        since: Option<u32>,
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+    /// 旧スキーマの設定ファイル（flow.kdl、非推奨のノード名）を現行スキーマへ移行
+    #[command(name = "upgrade-config")]
+    UpgradeConfig {
+        /// 書き換えずに移行の要否だけ確認（必要なら非ゼロ終了、CI 向け）
+        #[arg(long)]
+        check: bool,
+    },
+    /// KDL 設定ファイルの操作（整形など）
+    #[command(subcommand)]
+    Kdl(KdlCommands),
+    /// 設定ファイルのスキーマ（JSON Schema / Markdown）を出力
+    #[command(subcommand)]
+    Schema(SchemaCommands),
+    /// ワークスペース（workspace.kdl）の複数プロジェクトをまとめて操作
+    #[command(subcommand)]
+    Ws(WsCommands),
+    /// fleet.kdl 編集用の言語サーバー（LSP、stdio）を起動
+    Lsp,
+    /// MCP (Model Context Protocol) サーバーを起動
+    Mcp {
+        /// stdio の代わりに HTTP（Streamable HTTP）で待ち受けるアドレス（例: 127.0.0.1:8787）
+        #[arg(long, value_name = "ADDR")]
+        http: Option<std::net::SocketAddr>,
+        /// ループバック以外のアドレスでの待ち受けを許可する
+        #[arg(long, requires = "http")]
+        allow_remote: bool,
+    },
+    /// FleetFlow自体を最新版に更新
+    #[command(name = "self-update")]
+    SelfUpdate {
+        /// 更新チャネル（省略時はグローバル設定 update.channel、未設定なら stable）
+        #[arg(long, value_enum, conflicts_with = "version")]
+        channel: Option<self_update::Channel>,
+        /// 指定バージョンに固定（例: 0.9.2、ダウングレードも可）
+        #[arg(long, value_name = "X.Y.Z")]
+        version: Option<String>,
+    },
+}
+
+impl Commands {
+    /// 起動時の更新確認を行うか
+    ///
+    /// 常駐するコマンドや他のプロセスから起動されるコマンドでは、入力待ちで
+    /// 止まらないよう確認しない。
+    fn checks_for_updates(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Supervise { .. }
+                | Commands::Reconcile { .. }
+                | Commands::Lsp
+                | Commands::Mcp { .. }
+                | Commands::Cp(CpCommands::Daemon(_))
+                | Commands::SelfUpdate { .. }
+        )
+    }
+
+    /// 実行に必要な権限（None は読み取り専用、またはステージを変更しないコマンド）
+    ///
+    /// サブコマンドを追加したら必ずここで権限を決めるよう、ワイルドカードを使わない。
+    fn permission(&self) -> Option<authz::Permission> {
+        use authz::{Permission, Scope};
+        match self {
+            Commands::Up { check, .. } => (!check).then(|| Permission::stage("up")),
+            Commands::Down { .. } => Some(Permission::stage("down")),
+            Commands::Restart { .. } => Some(Permission::stage("restart")),
+            Commands::Supervise { .. } => Some(Permission::stage("supervise")),
+            Commands::Exec { .. } => Some(Permission::stage("exec")),
+            Commands::Kill { .. } => Some(Permission::stage("kill")),
+            Commands::Attach { .. } => Some(Permission::stage("attach")),
+            Commands::Test { .. } => Some(Permission::stage("test")),
+            Commands::Deploy {
+                yes, prepare_only, ..
+            } => Some(
+                Permission::stage(if *prepare_only {
+                    "deploy --prepare-only"
+                } else {
+                    "deploy"
+                })
+                .assume_yes(*yes),
+            ),
+            Commands::Env {
+                action: Some(EnvCommands::Set { recreate, .. }),
+                ..
+            } => recreate.then(|| Permission::stage("env set --recreate")),
+            Commands::Env { action: None, .. } => None,
+            Commands::Override(OverrideCommands::Set { recreate, .. }) => {
+                recreate.then(|| Permission::stage("override set --recreate"))
+            }
+            Commands::Override(OverrideCommands::Unset { .. } | OverrideCommands::Show { .. }) => {
+                None
+            }
+            Commands::Outdated { update, .. } => {
+                update.then(|| Permission::stage("outdated --update"))
+            }
+            Commands::Image(ImageCommands::Rm { .. }) => {
+                Some(Permission::new("image rm", Scope::LocalHost))
+            }
+            Commands::Image(ImageCommands::Load { server, .. }) => Some(Permission::new(
+                "image load",
+                match server {
+                    Some(server) => Scope::Server(Some(server.clone())),
+                    None => Scope::LocalHost,
+                },
+            )),
+            Commands::Image(ImageCommands::Ls { .. } | ImageCommands::Save { .. }) => None,
+            Commands::HostService(HostServiceCommands::Apply { server, dry_run }) => (!dry_run)
+                .then(|| Permission::new("host-service apply", Scope::Server(server.clone()))),
+            Commands::HostService(HostServiceCommands::Status { .. }) => None,
+            Commands::Autostart(AutostartCommands::Enable { .. }) => {
+                Some(Permission::stage("autostart enable"))
+            }
+            Commands::Autostart(AutostartCommands::Disable { .. }) => {
+                Some(Permission::stage("autostart disable"))
+            }
+            Commands::Autostart(AutostartCommands::Status) => None,
+            Commands::Remote(RemoteHostCommands::Prune { server, dry_run }) => (!dry_run)
+                .then(|| Permission::new("remote prune", Scope::Server(Some(server.clone())))),
+            Commands::Cloud(CloudCommands::Up { stage, dry_run, .. }) => {
+                (!dry_run).then(|| Permission::new("cloud up", Scope::Stage(stage.clone())))
+            }
+            Commands::Cloud(CloudCommands::Status { .. } | CloudCommands::Drift { .. }) => None,
+            Commands::Dns(DnsRecordCommands::Add { stage, .. }) => {
+                Some(Permission::new("dns add", Scope::Stage(stage.clone())))
+            }
+            Commands::Dns(DnsRecordCommands::Rm { stage, .. }) => {
+                Some(Permission::new("dns rm", Scope::Stage(stage.clone())))
+            }
+            Commands::Dns(DnsRecordCommands::List { .. }) => None,
+            // ステージに属さないオブジェクトストレージの操作
+            Commands::Storage(StorageCommands::Sync { .. }) => None,
+            // 子プロセスの fleet up / down / deploy に --role を渡し、そちらで判定する
+            Commands::Ws(_) | Commands::Reconcile { .. } => None,
+            Commands::Ps { .. }
+            | Commands::Logs { .. }
+            | Commands::PortForward { .. }
+            | Commands::Open { .. }
+            | Commands::Wait { .. }
+            | Commands::Build { .. }
+            | Commands::LocalRegistry(_)
+            | Commands::Cp(_)
+            | Commands::Validate { .. }
+            | Commands::Unlock { .. }
+            | Commands::List(_)
+            | Commands::Config(_)
+            | Commands::Doctor { .. }
+            | Commands::Inventory { .. }
+            | Commands::SupportBundle { .. }
+            | Commands::Report { .. }
+            | Commands::UpgradeConfig { .. }
+            | Commands::Kdl(_)
+            | Commands::Schema(_)
+            | Commands::Lsp
+            | Commands::Mcp { .. }
+            | Commands::SelfUpdate { .. } => None,
+        }
+    }
+}
+
+/// 環境変数編集のサブコマンド — fleet env <subcommand>
+#[derive(Subcommand)]
+enum EnvCommands {
+    /// 環境変数を設定（flow.local.kdl に書き込み）
+    Set {
+        /// サービス名
+        service: String,
+        /// 設定する値（KEY=VALUE、複数指定可）
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// flow.local.kdl ではなくステージのオーバーライドファイル（flow.{stage}.kdl）に書き込む
+        #[arg(long)]
+        stage_file: bool,
+        /// 書き込み後にコンテナを再作成して反映する
+        #[arg(long)]
+        recreate: bool,
+    },
+}
+
+/// ローカル上書きのサブコマンド — fleet override <subcommand>
+#[derive(Subcommand)]
+enum OverrideCommands {
+    /// フィールドを上書き（例: fleet override set web ports 3001:3000）
+    Set {
+        /// サービス名
+        service: String,
+        /// 上書きするフィールド
+        #[arg(value_enum)]
+        field: commands::overrides::OverrideField,
+        /// 値（ports / volumes / env は複数指定可）
+        #[arg(required = true, allow_hyphen_values = true)]
+        values: Vec<String>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// flow.local.kdl ではなくステージのオーバーライドファイル（flow.{stage}.kdl）に書き込む
+        #[arg(long)]
+        stage_file: bool,
+        /// 書き込み後にコンテナを再作成して反映する
+        #[arg(long)]
+        recreate: bool,
+    },
+    /// 上書きを取り消す（フィールド省略時はサービスの上書きをすべて削除）
+    Unset {
+        /// サービス名
+        service: String,
+        /// 取り消すフィールド
+        #[arg(value_enum)]
+        field: Option<commands::overrides::OverrideField>,
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// flow.{stage}.kdl から取り消す
+        #[arg(long)]
+        stage_file: bool,
+    },
+    /// 読み込まれるオーバーライドファイルと上書きの内容を表示
+    Show {
+        /// ステージ名（指定すると flow.{stage}.kdl も表示）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+    },
+}
+
+/// 一覧表示のサブコマンド — fleet list <subcommand>
+#[derive(Subcommand)]
+enum ListCommands {
+    /// ステージ名を一覧表示
+    Stages {
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+    /// サービス名を一覧表示
+    Services {
+        /// 指定ステージに含まれるサービスのみ（省略時は全サービス）
+        #[arg(short = 's', long)]
+        stage: Option<String>,
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// ホストサービスのサブコマンド — fleet host-service <subcommand>
+#[derive(Subcommand)]
+enum HostServiceCommands {
+    /// ユニットを配置して enable / restart する
+    Apply {
+        /// 対象サーバー（省略時は host_service を持つ全サーバー）
+        server: Option<String>,
+        /// 生成されるユニットを表示するだけで配置しない
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// ユニットの稼働状態（systemctl is-active）を表示
+    Status {
+        /// 対象サーバー（省略時は host_service を持つ全サーバー）
+        server: Option<String>,
+    },
+}
+
+/// KDL 設定ファイル操作のサブコマンド — fleet kdl <subcommand>
+#[derive(Subcommand)]
+enum KdlCommands {
+    /// 設定ファイルを正規のスタイルに整形（service の並び順・インデント、コメントは保持）
+    Fmt {
+        /// 対象ファイル（省略時はプロジェクトの設定ファイル一式）
+        files: Vec<PathBuf>,
+        /// 書き換えずに整形の要否だけ確認（必要なら非ゼロ終了、CI 向け）
+        #[arg(long)]
+        check: bool,
+        /// env ブロックのキーを名前順に並べる
+        #[arg(long)]
+        sort_env: bool,
+    },
+}
+
+/// スキーマ出力のサブコマンド — fleet schema <subcommand>
+#[derive(Subcommand)]
+enum SchemaCommands {
+    /// パーサーのスキーマから JSON Schema / Markdown のリファレンスを生成
+    Export {
+        /// 出力形式
+        #[arg(long, value_enum, default_value = "json-schema")]
+        format: commands::schema::SchemaFormat,
+        /// 出力先ファイル（省略時は標準出力）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// ワークスペース操作のサブコマンド — fleet ws <subcommand>
+#[derive(Subcommand)]
+enum WsCommands {
+    /// ワークスペースのプロジェクト一覧を表示
+    List,
+    /// プロジェクトを順に起動し、共有ネットワークで相互に接続
+    Up {
+        /// すべてのプロジェクトを対象にする
+        #[arg(long, conflicts_with = "projects")]
+        all_projects: bool,
+        /// 対象プロジェクト（複数指定可）
+        #[arg(short = 'p', long = "project", value_name = "NAME")]
+        projects: Vec<String>,
+        /// ステージ名
+        #[arg(short = 's', long, env = "FLEET_STAGE")]
+        stage: String,
+        /// 起動前に最新イメージをpullする
+        #[arg(long)]
+        pull: bool,
+    },
+    /// プロジェクトを起動と逆順に停止
+    Down {
+        /// すべてのプロジェクトを対象にする
+        #[arg(long, conflicts_with = "projects")]
+        all_projects: bool,
+        /// 対象プロジェクト（複数指定可）
+        #[arg(short = 'p', long = "project", value_name = "NAME")]
+        projects: Vec<String>,
+        /// ステージ名
+        #[arg(short = 's', long, env = "FLEET_STAGE")]
+        stage: String,
+    },
+}
+
+/// クラウドリソース操作のサブコマンド — fleet cloud <subcommand>
+#[derive(Subcommand)]
+enum CloudCommands {
+    /// プロバイダーに問い合わせたサーバーの実状態（電源・IP・ディスク・DNS）を表示
+    Status {
+        /// 対象ステージ（省略時は全サーバー定義）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+    /// 静的サイト（site）をビルドして R2 に配置し、ドメインを接続
+    Up {
+        /// 対象ステージ（site の domain / bucket の {stage} に使う）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 対象の site（省略時はすべて）
+        #[arg(long)]
+        site: Option<String>,
+        /// ビルドコマンドを実行せず、既存の output をアップロードする
+        #[arg(long)]
+        skip_build: bool,
+        /// 実行せずに実行計画のみ表示
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// プロバイダー上の実状態（プラン・ディスク・電源・タグ・DNS）と fleet.kdl の差分を報告
+    Drift {
+        /// 対象ステージ（省略時は全サーバー定義）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 差分があれば JSON を POST する URL（定期実行の通知向け）
+        #[arg(long, env = "FLEET_DRIFT_WEBHOOK", value_name = "URL")]
+        webhook: Option<String>,
+        /// 差分があれば非ゼロ終了（cron / CI 向け）
+        #[arg(long)]
+        exit_code: bool,
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// オブジェクトストレージのサブコマンド — fleet storage <subcommand>
+#[derive(Subcommand)]
+enum StorageCommands {
+    /// ローカルディレクトリと R2 を同期（引数の順で転送方向が決まる）
+    Sync {
+        /// 同期元（ローカルディレクトリまたは r2://bucket/prefix）
+        source: String,
+        /// 同期先（ローカルディレクトリまたは r2://bucket/prefix）
+        destination: String,
+        /// 同期元にないファイルを同期先から削除する
+        #[arg(long)]
+        delete: bool,
+        /// 実行せずに転送・削除するファイル数を表示
+        #[arg(long)]
+        dry_run: bool,
+        /// 並列転送数
+        #[arg(short = 'j', long, default_value_t = 8)]
+        jobs: usize,
+    },
+}
+
+/// DNS レコード管理のサブコマンド — fleet dns <subcommand>
+#[derive(Subcommand)]
+enum DnsRecordCommands {
+    /// プロジェクトが管理するレコードと、それぞれが指すサーバーを一覧表示
+    List {
+        /// ステージ名（ドメインの解決に使う）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 管理外のレコードも含めてゾーンの全レコードを表示
+        #[arg(long)]
+        all: bool,
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+    /// レコードを追加（同じ名前・タイプがあれば更新）
+    Add {
+        /// レコード名（ステージのドメインからの相対名、または FQDN）
+        name: String,
+        /// レコードの値（A レコードで --server 指定時は省略可）
+        content: Option<String>,
+        /// レコードタイプ（A, AAAA, CNAME, TXT）
+        #[arg(short = 't', long = "type", default_value = "A")]
+        record_type: String,
+        /// 紐付けるサーバー（管理タグに記録し、A レコードの値に ssh_host を使う）
+        #[arg(long)]
+        server: Option<String>,
+        /// TTL（秒。省略時は自動）
+        #[arg(long)]
+        ttl: Option<u32>,
+        /// Cloudflare プロキシを有効にする
+        #[arg(long)]
+        proxied: bool,
+        /// ステージ名（ドメインの解決に使う）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+    },
+    /// レコードを削除（プロジェクトの管理タグがあるものだけ）
+    Rm {
+        /// レコード名（ステージのドメインからの相対名、または FQDN）
+        name: String,
+        /// 削除するレコードタイプ（省略時はその名前の全タイプ）
+        #[arg(short = 't', long = "type")]
+        record_type: Option<String>,
+        /// 管理タグのないレコードも削除する
+        #[arg(long)]
+        force: bool,
+        /// ステージ名（ドメインの解決に使う）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+    },
+}
+
+/// 管理サーバー保守のサブコマンド — fleet remote <subcommand>
+#[derive(Subcommand)]
+enum RemoteHostCommands {
+    /// 古いビルドイメージ・dangling イメージ・ビルドキャッシュを削除
+    Prune {
+        /// 対象サーバー
+        server: String,
+        /// 削除対象のイメージを表示するだけで削除しない
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// 自動起動のサブコマンド — fleet autostart <subcommand>
+#[derive(Subcommand)]
+enum AutostartCommands {
+    /// ログイン時に fleet up を実行するよう登録
+    Enable {
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+    },
+    /// 登録を解除
+    Disable {
+        /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+    },
+    /// ステージごとの登録状態を表示
+    Status,
+}
+
+/// グローバル設定のサブコマンド — fleet config <subcommand>
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// 設定値を書き込む（例: fleet config set update.check false）
+    Set {
+        /// 設定キー（fleet config list で一覧表示）
+        key: String,
+        /// 設定値
+        value: String,
+    },
+    /// 設定可能なキーと現在の値を表示
+    List,
+    /// 2 つのステージ（または git リビジョン）の解決済み設定を比較
+    Diff {
+        /// 比較元のステージ
+        #[arg(long, value_name = "STAGE")]
+        stage_a: String,
+        /// 比較先のステージ（省略時は --stage-a と同じ、--git と組み合わせる）
+        #[arg(long, value_name = "STAGE")]
+        stage_b: Option<String>,
+        /// 比較元を指定リビジョン（例: origin/main）の設定から読み込む
+        #[arg(long, value_name = "REV")]
+        git: Option<String>,
+        /// センシティブな値（パスワード・トークンなど）も表示
+        #[arg(long)]
+        reveal: bool,
+        /// JSON 形式で出力
         #[arg(long)]
-        no_cache: bool,
+        json: bool,
     },
-    /// ステージをデプロイ（pull→停止→再起動）
-    Deploy {
-        /// ステージ名 (local, dev, stg, prod)
+}
+
+/// GitOps 同期のサブコマンド — fleet reconcile <subcommand>
+#[derive(Subcommand)]
+enum ReconcileCommands {
+    /// 同期の状態（リビジョン・差分・最後の結果）を表示
+    Status {
+        /// JSON で出力
+        #[arg(long)]
+        json: bool,
+    },
+    /// 自動適用を一時停止（差分の検知と状態の記録は続ける）
+    Pause {
+        /// 状態ディレクトリ名（1 つだけなら省略可）
+        name: Option<String>,
+    },
+    /// 自動適用を再開
+    Resume {
+        /// 状態ディレクトリ名（1 つだけなら省略可）
+        name: Option<String>,
+    },
+}
+
+/// イメージ書き出し・読み込みのサブコマンド — fleet image <subcommand>
+#[derive(Subcommand)]
+enum ImageCommands {
+    /// プロジェクトのイメージ（タグ・ダイジェスト・サイズ・作成日時）を一覧表示
+    Ls {
+        /// ステージ名（省略時は全ステージ）
+        #[arg(short = 's', long = "stage")]
         stage: Option<String>,
+    },
+    /// プロジェクトのイメージを削除（稼働中のコンテナが使うイメージは削除しない）
+    Rm {
+        /// 削除するイメージ（name:tag）
+        #[arg(required = true)]
+        images: Vec<String>,
+        /// 停止中のコンテナが参照していても削除
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// ステージが使うイメージを tar に書き出す（docker save）
+    Save {
         /// ステージ名 (-s/--stage フラグ、FLEET_STAGE 環境変数)
-        #[arg(
-            short = 's',
-            long = "stage",
-            env = "FLEET_STAGE",
-            conflicts_with = "stage",
-            hide = true
-        )]
-        stage_flag: Option<String>,
-        /// デプロイ対象のサービス（複数指定可、省略時は全サービス）
+        #[arg(short = 's', long = "stage", env = "FLEET_STAGE")]
+        stage: Option<String>,
+        /// 対象のサービス（複数指定可、省略時は全サービス）
         #[arg(short = 'n', long)]
         service: Vec<String>,
-        /// イメージのpullをスキップ（デフォルトは常にpull）
-        #[arg(long)]
-        no_pull: bool,
-        /// デプロイ後の不要イメージ・ビルドキャッシュ削除をスキップ
-        #[arg(long)]
-        no_prune: bool,
-        /// 確認なしで実行
-        #[arg(short, long)]
-        yes: bool,
-        /// 実行せずに実行計画のみ表示
-        #[arg(long)]
-        dry_run: bool,
-        /// テナント slug を override (省略時は fleet.kdl の `tenant` block → CLI auth context → "default" の順で解決)
+        /// 出力ファイル
+        #[arg(short, long, default_value = "fleet-images.tar")]
+        output: PathBuf,
+    },
+    /// tar からイメージを読み込む（docker load）
+    Load {
+        /// `fleet image save` で書き出したファイル
+        input: PathBuf,
+        /// 読み込み先サーバー（SSH 経由、省略時はローカル）
         #[arg(long)]
-        tenant: Option<String>,
+        server: Option<String>,
     },
+}
 
-    // ── Admin ──────────────────────────────────
-    /// Control Plane 管理
-    #[command(subcommand)]
-    Cp(CpCommands),
-
-    // ── Util ───────────────────────────────────
-    /// MCP (Model Context Protocol) サーバーを起動
-    Mcp,
-    /// FleetFlow自体を最新版に更新
-    #[command(name = "self-update")]
-    SelfUpdate,
+/// ローカルレジストリのサブコマンド — fleet registry <subcommand>
+#[derive(Subcommand)]
+enum LocalRegistryCommands {
+    /// ローカルレジストリを起動（既に起動していれば何もしない）
+    Serve {
+        /// 公開ポート（127.0.0.1 のみで待ち受ける）
+        #[arg(long, default_value_t = 5000)]
+        port: u16,
+        /// 上流レジストリの pull-through キャッシュとして動かす（例: https://registry-1.docker.io）
+        #[arg(long, value_name = "URL")]
+        proxy: Option<String>,
+    },
+    /// ローカルレジストリを停止・削除
+    Stop {
+        /// 公開ポート
+        #[arg(long, default_value_t = 5000)]
+        port: u16,
+        /// 保存したイメージ（ボリューム）も削除
+        #[arg(long)]
+        purge: bool,
+    },
+    /// FleetFlow が管理するローカルレジストリを一覧表示
+    Status,
 }
 
 // ─────────────────────────────────────────────
@@ -396,6 +1356,22 @@ enum ServerCommands {
         /// サーバーのスラッグ
         slug: String,
     },
+    /// サーバーが online になるまで待機（--ssh で SSH ポートの応答まで待つ）
+    Wait {
+        /// サーバーのスラッグ
+        slug: String,
+        /// SSH（22 番ポート）が応答するまで待つ
+        #[arg(long)]
+        ssh: bool,
+        /// タイムアウト秒数
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+    },
+    /// サーバーの IP アドレスを出力（スクリプト用に値のみ）
+    Ip {
+        /// サーバーのスラッグ
+        slug: String,
+    },
 }
 
 /// コスト管理のサブコマンド
@@ -660,11 +1636,28 @@ fn resolve_stage(positional: Option<String>, flag: Option<String>) -> Option<Str
 // ─────────────────────────────────────────────
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> std::process::ExitCode {
     let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => std::process::ExitCode::from(error::report(e)),
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    // -C: 以降の処理（プロジェクトルート・ワークスペースの探索）を指定ディレクトリから行う
+    if let Some(dir) = &cli.directory {
+        std::env::set_current_dir(dir)
+            .map_err(|e| anyhow::anyhow!("-C {} に移動できません: {}", dir.display(), e))?;
+    }
+
+    // ── LSP: stdout を JSON-RPC に使うのでロギング初期化前に処理 ──
+    if let Commands::Lsp = cli.command {
+        return fleetflow_lsp::run_server();
+    }
 
     // ── MCP: stdout を JSON-RPC に使うので先に処理 ──
-    if matches!(cli.command, Commands::Mcp) {
+    if let Commands::Mcp { http, allow_remote } = cli.command {
         use std::fs::OpenOptions;
         let log_file = OpenOptions::new()
             .create(true)
@@ -683,6 +1676,38 @@ async fn main() -> anyhow::Result<()> {
                 .init();
         }
 
+        if let Some(addr) = http {
+            if !addr.ip().is_loopback() {
+                if !allow_remote {
+                    anyhow::bail!(
+                        "{} はループバック以外のアドレスです。ネットワークに公開する場合は --allow-remote を指定してください",
+                        addr
+                    );
+                }
+                eprintln!(
+                    "⚠ {} はループバック以外のアドレスです。トークンを知っていればコンテナを操作できるため、信頼できるネットワークでのみ使用してください",
+                    addr
+                );
+            }
+            let token = match std::env::var(fleetflow_mcp::http_auth::TOKEN_ENV) {
+                Ok(token) if !token.trim().is_empty() => token.trim().to_string(),
+                _ => {
+                    let token = fleetflow_mcp::http_auth::generate_token();
+                    eprintln!("アクセストークン: {}", token);
+                    eprintln!(
+                        "  （{} で固定のトークンを指定できます）",
+                        fleetflow_mcp::http_auth::TOKEN_ENV
+                    );
+                    token
+                }
+            };
+            eprintln!(
+                "MCP サーバーを起動しました: http://{}{}（Authorization: Bearer <token>、Ctrl+C で終了）",
+                addr,
+                fleetflow_mcp::HTTP_ENDPOINT_PATH
+            );
+            return fleetflow_mcp::run_http_server(addr, token).await;
+        }
         return fleetflow_mcp::run_server().await;
     }
 
@@ -703,9 +1728,47 @@ async fn main() -> anyhow::Result<()> {
             .init();
     }
 
+    // ── グローバル設定（色） ──
+    if let Some(color) = utils::global_config().color {
+        colored::control::set_override(color);
+    }
+
+    // ── 進捗の出力形式 ──
+    utils::init_progress(cli.progress);
+
     // ── 設定ファイル不要なコマンド ──
-    if matches!(cli.command, Commands::SelfUpdate) {
-        return self_update::self_update().await;
+    if let Commands::SelfUpdate {
+        channel,
+        ref version,
+    } = cli.command
+    {
+        return self_update::self_update(channel, version.clone()).await;
+    }
+
+    // 起動時の更新確認（対話端末のみ・結果は 1 日キャッシュ、update.check で無効化可）
+    if !cli.quiet && cli.command.checks_for_updates() && self_update::prompt_if_outdated().await {
+        return Ok(());
+    }
+
+    // グローバル設定の編集はプロジェクト不要
+    if let Commands::Config(ref config_cmd) = cli.command {
+        return match config_cmd {
+            ConfigCommands::Set { key, value } => commands::config::handle_set(key, value),
+            ConfigCommands::List => commands::config::handle_list(),
+            ConfigCommands::Diff {
+                stage_a,
+                stage_b,
+                git,
+                reveal,
+                json,
+            } => commands::config_diff::handle_diff(
+                stage_a,
+                stage_b.as_deref(),
+                git.as_deref(),
+                *reveal,
+                *json,
+            ),
+        };
     }
 
     // CP コマンドは設定ファイル不要
@@ -713,6 +1776,149 @@ async fn main() -> anyhow::Result<()> {
         return handle_cp(cp_cmd).await;
     }
 
+    // image load は対象ホストにプロジェクトがなくても実行できる
+    // （プロジェクト内では権限チェックのため通常の経路で処理）
+    if let Commands::Image(ImageCommands::Load { input, server }) = &cli.command
+        && fleetflow_core::find_project_root().is_err()
+    {
+        return commands::image::handle_load(input, server.as_deref()).await;
+    }
+
+    // ローカルレジストリはプロジェクトに属さない
+    if let Commands::LocalRegistry(cmd) = &cli.command {
+        return match cmd {
+            LocalRegistryCommands::Serve { port, proxy } => {
+                commands::local_registry::handle_serve(*port, proxy.as_deref()).await
+            }
+            LocalRegistryCommands::Stop { port, purge } => {
+                commands::local_registry::handle_stop(*port, *purge).await
+            }
+            LocalRegistryCommands::Status => commands::local_registry::handle_status().await,
+        };
+    }
+
+    // スキーマはバイナリに組み込まれているのでプロジェクト不要
+    if let Commands::Schema(SchemaCommands::Export { format, output }) = &cli.command {
+        return commands::schema::handle_export(*format, output.as_deref());
+    }
+
+    // ワークスペースは各プロジェクトを個別に読み込む
+    if let Commands::Ws(ref ws_cmd) = cli.command {
+        let role = cli.role.as_deref();
+        return match ws_cmd {
+            WsCommands::List => commands::workspace::handle_list(),
+            WsCommands::Up {
+                all_projects,
+                projects,
+                stage,
+                pull,
+            } => commands::workspace::handle_up(*all_projects, projects, stage, *pull, role).await,
+            WsCommands::Down {
+                all_projects,
+                projects,
+                stage,
+            } => commands::workspace::handle_down(*all_projects, projects, stage, role).await,
+        };
+    }
+
+    // GitOps 同期はリポジトリを自分で取得するのでローカルのプロジェクト不要
+    if let Commands::Reconcile {
+        command,
+        repo,
+        branch,
+        path,
+        stage,
+        interval,
+        name,
+        once,
+    } = &cli.command
+    {
+        return match command {
+            Some(ReconcileCommands::Status { json }) => commands::reconcile::handle_status(*json),
+            Some(ReconcileCommands::Pause { name }) => {
+                commands::reconcile::handle_pause(name.as_deref(), true)
+            }
+            Some(ReconcileCommands::Resume { name }) => {
+                commands::reconcile::handle_pause(name.as_deref(), false)
+            }
+            None => {
+                let options = commands::reconcile::ReconcileOptions {
+                    repo: repo.clone().unwrap_or_default(),
+                    branch: branch.clone(),
+                    path: path.clone(),
+                    stage: stage.clone().unwrap_or_default(),
+                    name: name.clone(),
+                    interval: interval.clone(),
+                    once: *once,
+                };
+                commands::reconcile::handle_run(options, cli.role.as_deref()).await
+            }
+        };
+    }
+
+    // 旧スキーマの移行は現行のプロジェクトルートが無くても実行できる
+    if let Commands::UpgradeConfig { check } = cli.command {
+        return commands::upgrade_config::handle(check);
+    }
+
+    // --host: ローカルの設定なしでリモートの fleetflowd 経由で操作する
+    if let Some(host) = &cli.host {
+        return match &cli.command {
+            Commands::Ps {
+                stage,
+                stage_flag,
+                service,
+                ..
+            } => {
+                let stage = stage.as_deref().or(stage_flag.as_deref());
+                commands::remote_daemon::handle_ps(host, stage, service.as_deref()).await
+            }
+            Commands::Logs {
+                stage,
+                stage_flag,
+                service,
+                lines,
+                follow,
+                grep,
+                invert,
+                level,
+                output,
+                ..
+            } => {
+                let stage = stage.as_deref().or(stage_flag.as_deref());
+                let filter = commands::logs::LogFilter::new(grep.as_deref(), *invert, *level)?;
+                commands::remote_daemon::handle_logs(
+                    host,
+                    stage,
+                    service,
+                    *lines,
+                    *follow,
+                    &filter,
+                    output.as_deref(),
+                )
+                .await
+            }
+            Commands::Restart {
+                stage,
+                stage_flag,
+                service,
+                ..
+            } => {
+                let stage = stage.as_deref().or(stage_flag.as_deref());
+                commands::remote_daemon::handle_restart(
+                    host,
+                    stage,
+                    service.as_deref(),
+                    cli.role.as_deref(),
+                )
+                .await
+            }
+            _ => Err(anyhow::anyhow!(
+                "--host に対応しているのは ps / logs / restart のみです"
+            )),
+        };
+    }
+
     // CP 横断クエリ（--project / --global）
     match &cli.command {
         Commands::Ps {
@@ -778,6 +1984,29 @@ async fn main() -> anyhow::Result<()> {
         Err(e) => return Err(e.into()),
     };
 
+    // validate は読み込みエラー自体を報告するため設定ロード前に処理
+    if let Commands::Validate {
+        stage,
+        stage_flag,
+        strict,
+    } = &cli.command
+    {
+        let stage = resolve_stage(stage.clone(), stage_flag.clone());
+        return commands::validate::handle(&project_root, stage, *strict);
+    }
+    if let Commands::Unlock { force } = &cli.command {
+        return lock::handle_unlock(&project_root, *force);
+    }
+    // 整形は構文さえ読めればよいので設定ロード前に処理
+    if let Commands::Kdl(KdlCommands::Fmt {
+        files,
+        check,
+        sort_env,
+    }) = &cli.command
+    {
+        return commands::kdl::handle_fmt(&project_root, files, *check, *sort_env);
+    }
+
     // ── stage ヒント抽出 & 設定ロード ──
     let stage_from_env = std::env::var("FLEET_STAGE").ok();
     let stage_name_hint: Option<&str> = match &cli.command {
@@ -790,6 +2019,9 @@ async fn main() -> anyhow::Result<()> {
         | Commands::Restart {
             stage, stage_flag, ..
         }
+        | Commands::Supervise {
+            stage, stage_flag, ..
+        }
         | Commands::Ps {
             stage, stage_flag, ..
         }
@@ -799,12 +2031,36 @@ async fn main() -> anyhow::Result<()> {
         | Commands::Exec {
             stage, stage_flag, ..
         }
+        | Commands::Test {
+            stage, stage_flag, ..
+        }
         | Commands::Build {
             stage, stage_flag, ..
         }
         | Commands::Deploy {
             stage, stage_flag, ..
         } => stage.as_deref().or(stage_flag.as_deref()),
+        Commands::Autostart(AutostartCommands::Enable { stage })
+        | Commands::Autostart(AutostartCommands::Disable { stage }) => stage.as_deref(),
+        Commands::Image(ImageCommands::Save { stage, .. })
+        | Commands::Image(ImageCommands::Ls { stage })
+        | Commands::PortForward { stage, .. }
+        | Commands::Open { stage, .. }
+        | Commands::Attach { stage, .. }
+        | Commands::Kill { stage, .. }
+        | Commands::Wait { stage, .. }
+        | Commands::Outdated { stage, .. }
+        | Commands::SupportBundle { stage, .. }
+        | Commands::Doctor { stage, .. }
+        | Commands::List(ListCommands::Services { stage, .. }) => stage.as_deref(),
+        Commands::Env {
+            action: Some(EnvCommands::Set { stage, .. }),
+            ..
+        }
+        | Commands::Env { stage, .. } => stage.as_deref(),
+        Commands::Override(OverrideCommands::Set { stage, .. })
+        | Commands::Override(OverrideCommands::Unset { stage, .. })
+        | Commands::Override(OverrideCommands::Show { stage }) => stage.as_deref(),
         _ => stage_from_env.as_deref(),
     };
 
@@ -814,7 +2070,7 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    let config = match fleetflow_core::load_project_from_root_with_stage(
+    let mut config = match fleetflow_core::load_project_from_root_with_stage(
         &project_root,
         stage_name_hint,
     ) {
@@ -839,47 +2095,161 @@ async fn main() -> anyhow::Result<()> {
             eprintln!("  fleet <command> <stage>              例: fleet ps prod");
             eprintln!("  fleet <command> -s <stage>           例: fleet ps -s prod");
             eprintln!("  FLEET_STAGE=<stage> fleet <command>  例: FLEET_STAGE=prod fleet ps");
-            std::process::exit(1);
+            std::process::exit(error::EXIT_CONFIG.into());
         }
         Err(e) => return Err(e.into()),
     };
+    utils::warn_config_warnings(&config.warnings);
+
+    // ── グローバル設定をプロジェクト設定の下に重ねる ──
+    if config.registry.is_none() {
+        config.registry = utils::global_config().registry.clone();
+    }
+
+    // ── ステージの log_shipping 宣言から log-shipper サービスを追加 ──
+    if let Ok(stage_name) =
+        utils::determine_stage_name(stage_name_hint.map(str::to_string), &config)
+    {
+        fleetflow_container::inject_log_shipper(&mut config, &project_root, &stage_name);
+    }
+
+    // ── 変更系コマンドの権限チェック ──
+    if let Some(permission) = cli.command.permission() {
+        let default_stage =
+            utils::determine_stage_name(stage_name_hint.map(str::to_string), &config).ok();
+        authz::authorize_command(
+            &permission,
+            &config,
+            default_stage.as_deref(),
+            cli.role.as_deref(),
+        )?;
+    }
 
     // ── コマンドディスパッチ ──
     match cli.command {
         // Daily
+        Commands::Up {
+            stage,
+            stage_flag,
+            check: true,
+            ..
+        } => {
+            let stage = resolve_stage(stage, stage_flag);
+            commands::check::handle(&config, &project_root, stage).await?;
+        }
         Commands::Up {
             stage,
             stage_flag,
             pull,
             dry_run,
+            check: false,
+            wait,
+            skip_step,
+            resume,
         } => {
             let stage = resolve_stage(stage, stage_flag);
-            commands::up::handle(&config, &project_root, stage, pull, dry_run).await?;
+            let _lock = if dry_run {
+                None
+            } else {
+                Some(lock::acquire(&project_root, "up", wait).await?)
+            };
+            let stage_name = utils::determine_stage_name(stage, &config)?;
+            let operation = async {
+                commands::up::handle(
+                    &config,
+                    &project_root,
+                    Some(stage_name.clone()),
+                    pull,
+                    dry_run,
+                )
+                .await?;
+                commands::setup::run(
+                    &config,
+                    &project_root,
+                    &stage_name,
+                    &skip_step,
+                    resume,
+                    dry_run,
+                )
+                .await
+            };
+            if dry_run {
+                operation.await?;
+            } else {
+                audit::track(
+                    &project_root,
+                    &config,
+                    "up",
+                    Some(&stage_name),
+                    &[],
+                    operation,
+                )
+                .await?;
+            }
         }
         Commands::Down {
             stage,
             stage_flag,
             remove,
+            wait,
         } => {
             let stage = resolve_stage(stage, stage_flag);
-            commands::down::handle(&config, &project_root, stage, remove).await?;
+            let _lock = lock::acquire(&project_root, "down", wait).await?;
+            audit::track(
+                &project_root,
+                &config,
+                "down",
+                stage.as_deref(),
+                &[],
+                commands::down::handle(&config, &project_root, stage.clone(), remove),
+            )
+            .await?;
         }
         Commands::Restart {
             stage,
             stage_flag,
             service,
+            rolling,
+            cascade,
+        } => {
+            let stage = resolve_stage(stage, stage_flag);
+            let services: Vec<String> = service.iter().cloned().collect();
+            audit::track(
+                &project_root,
+                &config,
+                "restart",
+                stage.as_deref(),
+                &services,
+                commands::restart::handle(&config, service, stage.clone(), rolling, cascade),
+            )
+            .await?;
+        }
+        Commands::Supervise {
+            stage,
+            stage_flag,
+            max_restarts,
+            backoff,
+            notify,
         } => {
             let stage = resolve_stage(stage, stage_flag);
-            commands::restart::handle(&config, service, stage).await?;
+            commands::supervise::handle(&config, stage, max_restarts, &backoff, notify.as_deref())
+                .await?;
         }
         Commands::Ps {
             stage,
             stage_flag,
             all,
+            service,
+            status,
             ..
         } => {
             let stage = resolve_stage(stage, stage_flag);
-            commands::ps::handle(&config, &project_root, stage, all).await?;
+            let filter = commands::ps::PsFilter {
+                service,
+                status,
+                quiet: cli.quiet,
+            };
+            commands::ps::handle(&config, &project_root, stage, all, filter).await?;
         }
         Commands::Logs {
             stage,
@@ -888,8 +2258,13 @@ async fn main() -> anyhow::Result<()> {
             lines,
             follow,
             since,
+            grep,
+            invert,
+            level,
+            output,
         } => {
             let stage = resolve_stage(stage, stage_flag);
+            let filter = commands::logs::LogFilter::new(grep.as_deref(), invert, level)?;
             commands::logs::handle(
                 &config,
                 &project_root,
@@ -898,6 +2273,8 @@ async fn main() -> anyhow::Result<()> {
                 lines,
                 follow,
                 since,
+                &filter,
+                output.as_deref(),
             )
             .await?;
         }
@@ -912,17 +2289,162 @@ async fn main() -> anyhow::Result<()> {
             let stage = resolve_stage(stage, stage_flag);
             commands::exec::handle(&config, stage, service, command, interactive, tty).await?;
         }
+        Commands::Wait {
+            service,
+            stage,
+            healthy: _,
+            exited,
+            http,
+            timeout,
+        } => {
+            let condition = match http {
+                Some(url) => commands::wait::Condition::Http(url),
+                None if exited => commands::wait::Condition::Container(
+                    fleetflow_container::ContainerCondition::Exited,
+                ),
+                None => commands::wait::Condition::Container(
+                    fleetflow_container::ContainerCondition::Healthy,
+                ),
+            };
+            commands::wait::handle(&config, stage, service.as_deref(), condition, &timeout).await?;
+        }
+        Commands::Outdated {
+            stage,
+            json,
+            update,
+        } => {
+            commands::outdated::handle(&config, stage, json, update).await?;
+        }
+        Commands::Kill {
+            service,
+            stage,
+            signal,
+            ..
+        } => {
+            commands::kill::handle(&config, stage, service, &signal).await?;
+        }
+        Commands::Attach {
+            service,
+            stage,
+            detach_keys,
+        } => {
+            commands::attach::handle(&config, stage, service, detach_keys).await?;
+        }
+        Commands::PortForward {
+            service,
+            ports,
+            stage,
+            server,
+        } => {
+            commands::port_forward::handle(&config, &service, &ports, stage, server.as_deref())
+                .await?;
+        }
+        Commands::Open {
+            service,
+            stage,
+            port,
+            print,
+        } => {
+            commands::open::handle(&config, &service, stage, port.as_deref(), print)?;
+        }
+        Commands::Env {
+            action,
+            service,
+            stage,
+            reveal,
+        } => match action {
+            Some(EnvCommands::Set {
+                service,
+                vars,
+                stage,
+                stage_file,
+                recreate,
+            }) => {
+                commands::env::handle_set(
+                    &config,
+                    &project_root,
+                    &service,
+                    &vars,
+                    stage,
+                    stage_file,
+                    recreate,
+                )
+                .await?;
+            }
+            None => {
+                let service = service.ok_or_else(|| {
+                    anyhow::anyhow!("サービス名を指定してください: fleet env <service>")
+                })?;
+                commands::env::handle_show(&config, &service, stage, reveal)?;
+            }
+        },
+        Commands::Override(cmd) => match cmd {
+            OverrideCommands::Set {
+                service,
+                field,
+                values,
+                stage,
+                stage_file,
+                recreate,
+            } => {
+                commands::overrides::handle_set(
+                    &config,
+                    &project_root,
+                    &service,
+                    field,
+                    &values,
+                    stage,
+                    stage_file,
+                    recreate,
+                )
+                .await?;
+            }
+            OverrideCommands::Unset {
+                service,
+                field,
+                stage,
+                stage_file,
+            } => {
+                commands::overrides::handle_unset(
+                    &config,
+                    &project_root,
+                    &service,
+                    field,
+                    stage,
+                    stage_file,
+                )?;
+            }
+            OverrideCommands::Show { stage } => {
+                commands::overrides::handle_show(&config, &project_root, stage.as_deref())?;
+            }
+        },
+        Commands::Test {
+            stage,
+            stage_flag,
+            service,
+            keep_deps,
+        } => {
+            let stage = resolve_stage(stage, stage_flag);
+            commands::test::handle(&config, &project_root, stage, &service, keep_deps).await?;
+        }
 
         // Ship
         Commands::Build {
             stage,
             stage_flag,
             service,
+            all,
             push,
             tag,
             registry,
             platform,
             no_cache,
+            on,
+            warm_cache,
+            compare_last,
+            continue_on_error,
+            push_jobs,
+            changed_since,
         } => {
             let stage = resolve_stage(stage, stage_flag);
             let stage_name = utils::determine_stage_name(stage, &config)?;
@@ -931,11 +2453,18 @@ async fn main() -> anyhow::Result<()> {
                 &config,
                 &stage_name,
                 &service,
+                all,
                 push,
                 tag.as_deref(),
                 registry.as_deref(),
                 platform.as_deref(),
                 no_cache,
+                on.as_deref(),
+                warm_cache,
+                compare_last,
+                continue_on_error,
+                push_jobs,
+                changed_since.as_deref(),
             )
             .await?;
         }
@@ -948,25 +2477,198 @@ async fn main() -> anyhow::Result<()> {
             yes,
             dry_run,
             tenant,
+            wait,
+            offline,
+            skip_smoke_test,
+            prepare_only,
         } => {
             let stage = resolve_stage(stage, stage_flag);
-            commands::deploy::handle(
+            let _lock = if dry_run {
+                None
+            } else {
+                Some(lock::acquire(&project_root, "deploy", wait).await?)
+            };
+            let operation = commands::deploy::handle(
                 &config,
                 &project_root,
-                stage,
+                stage.clone(),
                 &service,
                 no_pull,
                 no_prune,
                 yes,
                 dry_run,
                 tenant,
-            )
-            .await?;
+                offline,
+                skip_smoke_test,
+                prepare_only,
+            );
+            if dry_run {
+                operation.await?;
+            } else {
+                audit::track(
+                    &project_root,
+                    &config,
+                    "deploy",
+                    stage.as_deref(),
+                    &service,
+                    operation,
+                )
+                .await?;
+            }
+        }
+        Commands::Image(ImageCommands::Save {
+            stage,
+            service,
+            output,
+        }) => {
+            commands::image::handle_save(&config, stage, &service, &output).await?;
+        }
+        Commands::Image(ImageCommands::Load { input, server }) => {
+            commands::image::handle_load(&input, server.as_deref()).await?;
+        }
+        Commands::Image(ImageCommands::Ls { stage }) => {
+            commands::image::handle_ls(&config, stage.as_deref()).await?;
+        }
+        Commands::Image(ImageCommands::Rm { images, force }) => {
+            commands::image::handle_rm(&config, &images, force).await?;
         }
 
         // Util
-        Commands::Mcp => unreachable!("handled before config loading"),
-        Commands::SelfUpdate => unreachable!("handled before config loading"),
+        Commands::Validate { .. } => unreachable!("handled before config loading"),
+        Commands::Unlock { .. } => unreachable!("handled before config loading"),
+        Commands::Mcp { .. } => unreachable!("handled before config loading"),
+        Commands::Lsp => unreachable!("handled before config loading"),
+        Commands::List(ListCommands::Stages { json }) => {
+            commands::list::handle_stages(&config, json)?;
+        }
+        Commands::List(ListCommands::Services { stage, json }) => {
+            commands::list::handle_services(&config, stage.as_deref(), json)?;
+        }
+        Commands::Config(_) => unreachable!("handled before config loading"),
+        Commands::HostService(HostServiceCommands::Apply { server, dry_run }) => {
+            commands::host_service::apply(&config, server.as_deref(), dry_run).await?;
+        }
+        Commands::HostService(HostServiceCommands::Status { server }) => {
+            commands::host_service::status(&config, server.as_deref()).await?;
+        }
+        Commands::Autostart(AutostartCommands::Enable { stage }) => {
+            commands::autostart::enable(&config, &project_root, stage)?;
+        }
+        Commands::Autostart(AutostartCommands::Disable { stage }) => {
+            commands::autostart::disable(&config, stage)?;
+        }
+        Commands::Autostart(AutostartCommands::Status) => {
+            commands::autostart::status(&config)?;
+        }
+        Commands::Remote(RemoteHostCommands::Prune { server, dry_run }) => {
+            commands::remote::handle_prune(&config, &server, dry_run)?;
+        }
+        Commands::Cloud(CloudCommands::Up {
+            stage,
+            site,
+            skip_build,
+            dry_run,
+        }) => {
+            commands::cloud::handle_up(
+                &config,
+                &project_root,
+                stage.as_deref(),
+                site.as_deref(),
+                skip_build,
+                dry_run,
+            )
+            .await?;
+        }
+        Commands::Cloud(CloudCommands::Status { stage, json }) => {
+            commands::cloud::handle_status(&config, stage.as_deref(), json).await?;
+        }
+        Commands::Cloud(CloudCommands::Drift {
+            stage,
+            webhook,
+            exit_code,
+            json,
+        }) => {
+            commands::cloud::handle_drift(
+                &config,
+                stage.as_deref(),
+                webhook.as_deref(),
+                exit_code,
+                json,
+            )
+            .await?;
+        }
+        Commands::Dns(DnsRecordCommands::List { stage, all, json }) => {
+            commands::dns::handle_list(&config, stage.as_deref(), all, json).await?;
+        }
+        Commands::Dns(DnsRecordCommands::Add {
+            name,
+            content,
+            record_type,
+            server,
+            ttl,
+            proxied,
+            stage,
+        }) => {
+            commands::dns::handle_add(
+                &config,
+                stage.as_deref(),
+                &name,
+                &record_type,
+                content.as_deref(),
+                server.as_deref(),
+                ttl,
+                proxied,
+            )
+            .await?;
+        }
+        Commands::Dns(DnsRecordCommands::Rm {
+            name,
+            record_type,
+            force,
+            stage,
+        }) => {
+            commands::dns::handle_rm(
+                &config,
+                stage.as_deref(),
+                &name,
+                record_type.as_deref(),
+                force,
+            )
+            .await?;
+        }
+        Commands::Storage(StorageCommands::Sync {
+            source,
+            destination,
+            delete,
+            dry_run,
+            jobs,
+        }) => {
+            commands::storage::handle_sync(&config, &source, &destination, delete, dry_run, jobs)
+                .await?;
+        }
+        Commands::Doctor { stage, remote } => {
+            commands::doctor::handle(&config, stage, remote.as_deref()).await?;
+        }
+        Commands::Inventory { format, output } => {
+            commands::inventory::handle(&config, format, output.as_deref())?;
+        }
+        Commands::SupportBundle {
+            stage,
+            output,
+            lines,
+        } => {
+            commands::support_bundle::handle(&config, stage, output, lines).await?;
+        }
+        Commands::Report { stage, since, json } => {
+            commands::report::handle(&config, &project_root, stage.as_deref(), since, json)?;
+        }
+        Commands::UpgradeConfig { .. } => unreachable!("handled before config loading"),
+        Commands::Kdl(_) => unreachable!("handled before config loading"),
+        Commands::Schema(_) => unreachable!("handled before config loading"),
+        Commands::Ws(_) => unreachable!("handled before config loading"),
+        Commands::Reconcile { .. } => unreachable!("handled before config loading"),
+        Commands::LocalRegistry(_) => unreachable!("handled before config loading"),
+        Commands::SelfUpdate { .. } => unreachable!("handled before config loading"),
         Commands::Cp(_) => unreachable!("handled before config loading"),
     }
 
@@ -1,7 +1,42 @@
 use colored::Colorize;
+use sha2::{Digest, Sha256};
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RELEASES_API: &str = "https://api.github.com/repos/chronista-club/fleetflow/releases";
+
+/// リリースと一緒に公開されるチェックサムファイル名
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// 起動時の更新確認の間隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 更新チャネル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Channel {
+    /// 正式リリースのみ
+    #[default]
+    Stable,
+    /// プレリリース（beta / rc）を含む
+    Beta,
+}
+
+impl Channel {
+    /// グローバル設定の `update.channel`（未設定なら stable）
+    fn configured() -> Self {
+        match crate::utils::global_config().update.channel.as_deref() {
+            Some("beta") => Channel::Beta,
+            _ => Channel::Stable,
+        }
+    }
+}
 
-/// FleetFlow self-update: GitHub Releasesから最新版をダウンロードして更新
-pub async fn self_update() -> anyhow::Result<()> {
+/// FleetFlow self-update: GitHub Releasesからダウンロードして更新
+///
+/// `version` 指定時はそのバージョンに固定（ダウングレードも可）、
+/// 未指定時は `channel`（省略時はグローバル設定）の最新版に更新する。
+/// ダウンロードしたアーカイブはリリースの SHA256SUMS で検証する。
+pub async fn self_update(channel: Option<Channel>, version: Option<String>) -> anyhow::Result<()> {
     use std::process::Command;
 
     println!("{}", "FleetFlow self-update".blue().bold());
@@ -10,42 +45,45 @@ pub async fn self_update() -> anyhow::Result<()> {
     let current_version = env!("CARGO_PKG_VERSION");
     println!("現在のバージョン: {}", current_version.cyan());
 
-    // GitHub APIから最新リリース情報を取得
-    println!("最新バージョンを確認中...");
-
     let client = reqwest::Client::new();
-    let response = client
-        .get("https://api.github.com/repos/chronista-club/fleetflow/releases/latest")
-        .header("User-Agent", "fleetflow")
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "GitHubからリリース情報を取得できませんでした: {}",
-            response.status()
-        ));
-    }
+    let channel = channel.unwrap_or_else(Channel::configured);
 
-    let release: serde_json::Value = response.json().await?;
-    let latest_version = release["tag_name"]
-        .as_str()
-        .ok_or_else(|| anyhow::anyhow!("tag_nameが見つかりません"))?
-        .trim_start_matches('v');
+    let release = match &version {
+        Some(version) => {
+            println!("バージョン {} を取得中...", version.cyan());
+            fetch_release_by_version(&client, version).await?
+        }
+        None => {
+            println!("最新バージョンを確認中...");
+            fetch_latest_release(&client, channel).await?
+        }
+    };
 
-    println!("最新バージョン: {}", latest_version.green());
+    let target_version = release_version(&release)?;
 
-    // バージョン比較
-    if !is_newer_version(latest_version, current_version) {
-        println!();
-        println!("{}", "✓ 既に最新版です！".green().bold());
-        return Ok(());
+    match &version {
+        Some(_) => {
+            println!("対象バージョン: {}", target_version.green());
+            if target_version == current_version {
+                println!();
+                println!("{}", "✓ 既にこのバージョンです".green().bold());
+                return Ok(());
+            }
+        }
+        None => {
+            println!("最新バージョン: {}", target_version.green());
+            if !is_newer_version(&target_version, current_version) {
+                println!();
+                println!("{}", "✓ 既に最新版です！".green().bold());
+                return Ok(());
+            }
+        }
     }
 
     println!();
     println!(
         "{}",
-        format!("{} → {} に更新します", current_version, latest_version).yellow()
+        format!("{} → {} に更新します", current_version, target_version).yellow()
     );
 
     // ダウンロードURL決定
@@ -66,17 +104,8 @@ pub async fn self_update() -> anyhow::Result<()> {
         }
     };
 
-    // ダウンロードURLを取得
-    let assets = release["assets"].as_array();
-
-    let download_url = assets.and_then(|arr| {
-        arr.iter()
-            .find(|a| a["name"].as_str() == Some(asset_name))
-            .and_then(|a| a["browser_download_url"].as_str())
-    });
-
     // バイナリがない場合は cargo install を使用
-    let download_url = match download_url {
+    let download_url = match asset_url(&release, asset_name) {
         Some(url) => url.to_string(),
         None => {
             println!(
@@ -86,10 +115,28 @@ pub async fn self_update() -> anyhow::Result<()> {
             println!("cargo install でビルドします...");
             println!();
 
-            return cargo_install_update().await;
+            let tag = release["tag_name"].as_str().unwrap_or_default();
+            return cargo_install_update(tag).await;
         }
     };
 
+    // チェックサムが公開されていないリリースは検証できないため更新しない
+    let checksums_url = asset_url(&release, CHECKSUMS_ASSET).ok_or_else(|| {
+        anyhow::anyhow!(
+            "リリース {} に {} が含まれていないため、検証できません",
+            target_version,
+            CHECKSUMS_ASSET
+        )
+    })?;
+    let checksums = fetch(&client, checksums_url).await?.text().await?;
+    let expected = expected_checksum(&checksums, asset_name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} に {} のエントリがありません",
+            CHECKSUMS_ASSET,
+            asset_name
+        )
+    })?;
+
     println!("ダウンロード中: {}", asset_name);
 
     // 一時ディレクトリにダウンロード
@@ -99,8 +146,20 @@ pub async fn self_update() -> anyhow::Result<()> {
     let tar_path = temp_dir.join(asset_name);
 
     // ダウンロード
-    let response = client.get(&download_url).send().await?;
-    let bytes = response.bytes().await?;
+    let bytes = fetch(&client, &download_url).await?.bytes().await?;
+
+    println!("チェックサムを検証中...");
+    let actual = sha256_hex(&bytes);
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(anyhow::anyhow!(
+            "チェックサムが一致しません（{}）\n  期待値: {}\n  実際:   {}",
+            asset_name,
+            expected,
+            actual
+        ));
+    }
+    println!("  ✓ sha256:{}", actual);
+
     std::fs::write(&tar_path, &bytes)?;
 
     println!("展開中...");
@@ -158,7 +217,7 @@ pub async fn self_update() -> anyhow::Result<()> {
             println!();
             println!(
                 "{}",
-                format!("✓ FleetFlow {} に更新しました！", latest_version)
+                format!("✓ FleetFlow {} に更新しました！", target_version)
                     .green()
                     .bold()
             );
@@ -181,13 +240,262 @@ pub async fn self_update() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 起動時の更新確認の結果（キャッシュファイルに保存する）
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CheckCache {
+    /// 最後にリリースを確認した時刻（UNIX 秒）
+    checked_at: u64,
+    /// その時点の最新バージョン（確認に失敗したときは None）
+    latest: Option<String>,
+    /// 更新を断ったバージョン（同じバージョンでは再び尋ねない）
+    declined: Option<String>,
+}
+
+impl CheckCache {
+    /// `checked_at` / `latest` / `declined` を 1 行ずつ並べた形式を読む
+    fn parse(content: &str) -> Self {
+        let mut lines = content.lines().map(str::trim);
+        let checked_at = lines
+            .next()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or_default();
+        let mut next = || lines.next().filter(|l| !l.is_empty()).map(str::to_string);
+        let latest = next();
+        let declined = next();
+        Self {
+            checked_at,
+            latest,
+            declined,
+        }
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n",
+            self.checked_at,
+            self.latest.as_deref().unwrap_or_default(),
+            self.declined.as_deref().unwrap_or_default()
+        )
+    }
+
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked_at) < CHECK_INTERVAL.as_secs()
+    }
+
+    /// 尋ねるべき新しいバージョン（現行より新しく、まだ断られていないもの）
+    fn pending_update(&self, current_version: &str) -> Option<&str> {
+        self.latest
+            .as_deref()
+            .filter(|latest| is_newer_version(latest, current_version))
+            .filter(|latest| self.declined.as_deref() != Some(*latest))
+    }
+}
+
+/// 起動時の更新確認
+///
+/// stdin / stdout / stderr がすべて対話端末のときだけ、新しいバージョンがあれば更新を提案する。
+/// リリースの確認結果は 1 日キャッシュし、断ったバージョンは再び尋ねない。
+/// グローバル設定の `update { check #false }` または CI 環境では何もしない。
+/// 更新した場合は true を返す（呼び出し側はコマンドを実行せずに終了する）。
+pub async fn prompt_if_outdated() -> bool {
+    if !crate::utils::global_config().update.check
+        || std::env::var_os("CI").is_some()
+        || !std::io::stdin().is_terminal()
+        || !std::io::stdout().is_terminal()
+        || !std::io::stderr().is_terminal()
+    {
+        return false;
+    }
+
+    let Some(state_path) = dirs::cache_dir().map(|d| d.join("fleetflow").join("update-check"))
+    else {
+        return false;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let mut cache = std::fs::read_to_string(&state_path)
+        .map(|content| CheckCache::parse(&content))
+        .unwrap_or_default();
+
+    if !cache.is_fresh(now) {
+        cache.checked_at = now;
+        cache.latest = fetch_latest_version().await;
+        write_check_cache(&state_path, &cache);
+    }
+
+    let current_version = env!("CARGO_PKG_VERSION");
+    let Some(latest) = cache.pending_update(current_version).map(str::to_string) else {
+        return false;
+    };
+
+    eprint!(
+        "{} {} → {} 今すぐ更新しますか？ [y/N] ",
+        "新しいバージョンがあります:".yellow(),
+        current_version,
+        latest.green()
+    );
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err()
+        || !matches!(answer.trim(), "y" | "Y" | "yes")
+    {
+        cache.declined = Some(latest);
+        write_check_cache(&state_path, &cache);
+        eprintln!(
+            "  （{} で無効化できます）",
+            "fleet config set update.check false".dimmed()
+        );
+        return false;
+    }
+
+    if let Err(e) = self_update(Some(Channel::configured()), None).await {
+        eprintln!("{} {}", "更新に失敗しました:".red(), e);
+        return false;
+    }
+    println!();
+    println!("コマンドを再実行してください。");
+    true
+}
+
+/// 最新リリースのバージョン（起動を遅らせないよう短いタイムアウトで確認し、失敗は無視する）
+async fn fetch_latest_version() -> Option<String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .ok()?;
+    match fetch_latest_release(&client, Channel::configured()).await {
+        Ok(release) => release_version(&release).ok(),
+        Err(e) => {
+            tracing::debug!("更新確認に失敗しました: {}", e);
+            None
+        }
+    }
+}
+
+fn write_check_cache(path: &std::path::Path, cache: &CheckCache) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).ok();
+    }
+    std::fs::write(path, cache.render()).ok();
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> anyhow::Result<reqwest::Response> {
+    let response = client
+        .get(url)
+        .header("User-Agent", "fleetflow")
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "GitHubからの取得に失敗しました: {} ({})",
+            response.status(),
+            url
+        ));
+    }
+    Ok(response)
+}
+
+/// チャネルの最新リリースを取得
+///
+/// stable は GitHub の latest（プレリリースを除く）、beta はドラフト以外で最も新しいもの。
+async fn fetch_latest_release(
+    client: &reqwest::Client,
+    channel: Channel,
+) -> anyhow::Result<serde_json::Value> {
+    match channel {
+        Channel::Stable => Ok(fetch(client, &format!("{}/latest", RELEASES_API))
+            .await?
+            .json()
+            .await?),
+        Channel::Beta => {
+            let releases: Vec<serde_json::Value> =
+                fetch(client, &format!("{}?per_page=30", RELEASES_API))
+                    .await?
+                    .json()
+                    .await?;
+            releases
+                .into_iter()
+                .filter(|r| r["draft"].as_bool() != Some(true))
+                .filter_map(|r| Some((release_version(&r).ok()?, r)))
+                .reduce(|best, next| {
+                    if is_newer_version(&next.0, &best.0) {
+                        next
+                    } else {
+                        best
+                    }
+                })
+                .map(|(_, r)| r)
+                .ok_or_else(|| anyhow::anyhow!("リリースが見つかりません"))
+        }
+    }
+}
+
+/// 指定バージョンのリリースを取得（タグは `v` 付き・無しの両方を試す）
+async fn fetch_release_by_version(
+    client: &reqwest::Client,
+    version: &str,
+) -> anyhow::Result<serde_json::Value> {
+    let version = version.trim_start_matches('v');
+    for tag in [format!("v{}", version), version.to_string()] {
+        if let Ok(response) = fetch(client, &format!("{}/tags/{}", RELEASES_API, tag)).await {
+            return Ok(response.json().await?);
+        }
+    }
+    Err(anyhow::anyhow!(
+        "バージョン {} のリリースが見つかりません",
+        version
+    ))
+}
+
+fn release_version(release: &serde_json::Value) -> anyhow::Result<String> {
+    Ok(release["tag_name"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("tag_nameが見つかりません"))?
+        .trim_start_matches('v')
+        .to_string())
+}
+
+fn asset_url<'a>(release: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+    release["assets"]
+        .as_array()?
+        .iter()
+        .find(|a| a["name"].as_str() == Some(name))
+        .and_then(|a| a["browser_download_url"].as_str())
+}
+
+/// SHA256SUMS（`<hex>  <file>` 形式、`*<file>` のバイナリ表記も可）から期待値を取り出す
+fn expected_checksum(checksums: &str, asset_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim().trim_start_matches('*');
+        (file == asset_name).then(|| hash.to_lowercase())
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
 /// バージョン比較: new_ver が current_ver より新しければ true
+///
+/// `1.2.0-beta.1` のようなプレリリースは同じ番号の正式版より古いものとして扱う。
 fn is_newer_version(new_ver: &str, current_ver: &str) -> bool {
-    let parse_version =
-        |v: &str| -> Vec<u32> { v.split('.').filter_map(|s| s.parse().ok()).collect() };
+    let split = |v: &str| -> (Vec<u32>, Option<String>) {
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core.to_string(), Some(pre.to_string())),
+            None => (v.to_string(), None),
+        };
+        (
+            core.split('.').filter_map(|s| s.parse().ok()).collect(),
+            pre,
+        )
+    };
 
-    let new_parts = parse_version(new_ver);
-    let current_parts = parse_version(current_ver);
+    let (new_parts, new_pre) = split(new_ver);
+    let (current_parts, current_pre) = split(current_ver);
 
     for (n, c) in new_parts.iter().zip(current_parts.iter()) {
         if n > c {
@@ -197,30 +505,49 @@ fn is_newer_version(new_ver: &str, current_ver: &str) -> bool {
             return false;
         }
     }
+    if new_parts.len() != current_parts.len() {
+        return new_parts.len() > current_parts.len();
+    }
 
-    new_parts.len() > current_parts.len()
+    match (new_pre, current_pre) {
+        (None, Some(_)) => true,
+        (Some(_), None) | (None, None) => false,
+        (Some(n), Some(c)) => is_newer_prerelease(&n, &c),
+    }
+}
+
+/// プレリリース識別子の比較（`beta.10` > `beta.9`、`rc.1` > `beta.3`）
+fn is_newer_prerelease(new_pre: &str, current_pre: &str) -> bool {
+    for (n, c) in new_pre.split('.').zip(current_pre.split('.')) {
+        let ordering = match (n.parse::<u32>(), c.parse::<u32>()) {
+            (Ok(n), Ok(c)) => n.cmp(&c),
+            _ => n.cmp(c),
+        };
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering.is_gt();
+        }
+    }
+    new_pre.split('.').count() > current_pre.split('.').count()
 }
 
-/// cargo install でFleetFlowを更新
-async fn cargo_install_update() -> anyhow::Result<()> {
+/// cargo install でFleetFlowを更新（`tag` のリリースをビルド）
+async fn cargo_install_update(tag: &str) -> anyhow::Result<()> {
     use std::process::Command;
 
-    println!(
-        "{}",
-        "cargo install --git https://github.com/chronista-club/fleetflow --package fleetflow --force".cyan()
-    );
+    let mut args = vec![
+        "install",
+        "--git",
+        "https://github.com/chronista-club/fleetflow",
+    ];
+    if !tag.is_empty() {
+        args.extend(["--tag", tag]);
+    }
+    args.extend(["--package", "fleetflow", "--force"]);
+
+    println!("{}", format!("cargo {}", args.join(" ")).cyan());
     println!();
 
-    let status = Command::new("cargo")
-        .args([
-            "install",
-            "--git",
-            "https://github.com/chronista-club/fleetflow",
-            "--package",
-            "fleetflow",
-            "--force",
-        ])
-        .status()?;
+    let status = Command::new("cargo").args(&args).status()?;
 
     if status.success() {
         println!();
@@ -238,6 +565,41 @@ async fn cargo_install_update() -> anyhow::Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_cache_round_trip() {
+        let cache = CheckCache {
+            checked_at: 100,
+            latest: Some("1.2.0".to_string()),
+            declined: None,
+        };
+        assert_eq!(CheckCache::parse(&cache.render()), cache);
+        // 旧形式（時刻のみ）も読める
+        assert_eq!(
+            CheckCache::parse("100\n"),
+            CheckCache {
+                checked_at: 100,
+                ..Default::default()
+            }
+        );
+        assert!(cache.is_fresh(100 + CHECK_INTERVAL.as_secs() - 1));
+        assert!(!cache.is_fresh(100 + CHECK_INTERVAL.as_secs()));
+    }
+
+    #[test]
+    fn test_check_cache_pending_update() {
+        let mut cache = CheckCache {
+            checked_at: 0,
+            latest: Some("1.2.0".to_string()),
+            declined: None,
+        };
+        assert_eq!(cache.pending_update("1.1.0"), Some("1.2.0"));
+        assert_eq!(cache.pending_update("1.2.0"), None);
+        cache.declined = Some("1.2.0".to_string());
+        assert_eq!(cache.pending_update("1.1.0"), None);
+        cache.latest = Some("1.3.0".to_string());
+        assert_eq!(cache.pending_update("1.1.0"), Some("1.3.0"));
+    }
+
     #[test]
     fn test_is_newer_version_major() {
         assert!(is_newer_version("1.0.0", "0.9.0"));
@@ -266,4 +628,46 @@ mod tests {
         assert!(is_newer_version("0.9.0.1", "0.9.0"));
         assert!(!is_newer_version("0.9.0", "0.9.0.1"));
     }
+
+    #[test]
+    fn test_is_newer_version_prerelease() {
+        assert!(is_newer_version("1.0.0", "1.0.0-beta.2"));
+        assert!(!is_newer_version("1.0.0-beta.2", "1.0.0"));
+        assert!(is_newer_version("1.0.0-beta.10", "1.0.0-beta.9"));
+        assert!(is_newer_version("1.0.0-rc.1", "1.0.0-beta.3"));
+        assert!(is_newer_version("1.0.1-beta.1", "1.0.0"));
+        assert!(!is_newer_version("1.0.0-beta.1", "1.0.0-beta.1"));
+    }
+
+    #[test]
+    fn test_expected_checksum() {
+        let sums = "\
+3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b  fleetflow-linux-amd64.tar.gz
+ABCDEF0123  *fleetflow-darwin-arm64.tar.gz
+";
+        assert_eq!(
+            expected_checksum(sums, "fleetflow-linux-amd64.tar.gz").as_deref(),
+            Some("3a7bd3e2360a3d29eea436fcfb7e44c735d117c42d1c1835420b6b9942dd4f1b")
+        );
+        assert_eq!(
+            expected_checksum(sums, "fleetflow-darwin-arm64.tar.gz").as_deref(),
+            Some("abcdef0123")
+        );
+        assert_eq!(
+            expected_checksum(sums, "fleetflow-linux-arm64.tar.gz"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"fleetflow"),
+            format!("{:x}", Sha256::digest(b"fleetflow"))
+        );
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
 }
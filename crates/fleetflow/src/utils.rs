@@ -1,4 +1,98 @@
 use colored::Colorize;
+use std::sync::OnceLock;
+
+/// グローバル設定（~/.config/fleetflow/config.kdl）
+///
+/// 初回呼び出し時に読み込んでキャッシュする。不明なキーは警告して無視し、
+/// 読み込めない場合はエラーを表示してデフォルトを使う。
+pub fn global_config() -> &'static fleetflow_config::GlobalConfig {
+    static CONFIG: OnceLock<fleetflow_config::GlobalConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| match fleetflow_config::GlobalConfig::load_lenient() {
+        Ok((config, unknown)) => {
+            warn_unknown_global_keys(&unknown);
+            config
+        }
+        Err(e) => {
+            eprintln!(
+                "{} {}（グローバル設定を使わずに続行します）",
+                "⚠".yellow(),
+                e
+            );
+            fleetflow_config::GlobalConfig::default()
+        }
+    })
+}
+
+/// グローバル設定の不明なキーを警告する
+pub fn warn_unknown_global_keys(unknown: &[String]) {
+    if !unknown.is_empty() {
+        eprintln!(
+            "{} グローバル設定の不明なキーを無視しました: {}",
+            "⚠".yellow(),
+            unknown.join(", ")
+        );
+    }
+}
+
+/// プロジェクト設定のロード時の警告（固定していないリモート include 等）を表示する
+pub fn warn_config_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("{} {}", "⚠".yellow(), warning);
+    }
+}
+
+/// 進捗の表示形式（`--progress`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// TTY ならスピナー、CI・パイプでは行単位
+    #[default]
+    Auto,
+    /// 常に行単位のプレーンテキスト
+    Plain,
+    /// 改行区切りの JSON イベント（step / service / percent / message）
+    Json,
+}
+
+/// 共有の進捗レイヤー（fleetflow_build::reporter）の出力形式を固定する
+///
+/// `json` では stdout を JSON イベント専用にするため、元の stdout を複製してイベントの出力先とし、
+/// fd 1 を stderr に付け替える。以降の人向けの出力（println! やログ）はすべて stderr に出る。
+pub fn init_progress(format: ProgressFormat) {
+    use std::sync::Arc;
+
+    match format {
+        ProgressFormat::Auto => {}
+        ProgressFormat::Plain => {
+            fleetflow_build::set_reporter(Arc::new(fleetflow_build::PlainProgress));
+        }
+        ProgressFormat::Json => {
+            fleetflow_build::set_reporter(Arc::new(fleetflow_build::JsonProgress::new(
+                json_event_writer(),
+            )));
+        }
+    }
+}
+
+/// JSON イベントの出力先（元の stdout の複製）を用意し、fd 1 を stderr に付け替える
+#[cfg(unix)]
+fn json_event_writer() -> Box<dyn std::io::Write + Send> {
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+
+    let _ = std::io::stdout().flush();
+    let fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if fd >= 0 && unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } >= 0 {
+        Box::new(unsafe { std::fs::File::from_raw_fd(fd) })
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+/// fd の付け替えができない環境では stdout にそのまま出す（人向けの出力と混在する）
+#[cfg(not(unix))]
+fn json_event_writer() -> Box<dyn std::io::Write + Send> {
+    Box::new(std::io::stdout())
+}
 
 /// ステージ名を決定する（共通ロジック）
 pub fn determine_stage_name(
@@ -7,6 +101,10 @@ pub fn determine_stage_name(
 ) -> anyhow::Result<String> {
     if let Some(s) = stage {
         Ok(s)
+    } else if let Some(default_stage) = &global_config().default_stage
+        && config.stages.contains_key(default_stage)
+    {
+        Ok(default_stage.clone())
     } else if config.stages.contains_key("default") {
         Ok("default".to_string())
     } else if config.stages.len() == 1 {